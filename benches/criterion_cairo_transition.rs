@@ -0,0 +1,48 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use lambdaworks_stark::{
+    cairo::{
+        air::{CairoAIR, CairoRAPChallenges},
+        runner::program::CairoProgram,
+    },
+    starks::{frame::Frame, proof::options::ProofOptions, trace::TraceTable, traits::AIR},
+    FE,
+};
+
+pub mod functions;
+
+/// Throughput of the evaluation of the Cairo transition constraints, measured over
+/// the frames of the trace of a program.
+fn transition_benches(c: &mut Criterion) {
+    let program =
+        CairoProgram::from_file(functions::cairo::cairo0_program_path("fibonacci_500.json"))
+            .unwrap();
+    let (main_trace, pub_inputs) = program.generate_prover_args(&None).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+    let air = CairoAIR::new(main_trace.n_rows(), &pub_inputs, &proof_options);
+
+    let rap_challenges = CairoRAPChallenges {
+        alpha_memory: FE::from(2),
+        z_memory: FE::from(3),
+        z_range_check: FE::from(5),
+    };
+    let aux_trace = air.build_auxiliary_trace(0, &main_trace, &rap_challenges);
+    let trace = TraceTable::concatenate_tables(&[&main_trace, &aux_trace]);
+    let offsets = &air.context().transition_offsets;
+    let frames: Vec<Frame<Stark252PrimeField>> = (0..trace.n_rows() - 1)
+        .map(|row| Frame::read_from_trace(&trace, row, 1, offsets))
+        .collect();
+
+    let mut group = c.benchmark_group("CAIRO_TRANSITION");
+    group.throughput(Throughput::Elements(frames.len() as u64));
+    group.bench_function("fibonacci/500", |bench| {
+        bench.iter(|| {
+            for frame in &frames {
+                black_box(air.compute_transition(frame, &rap_challenges));
+            }
+        });
+    });
+}
+
+criterion_group!(benches, transition_benches);
+criterion_main!(benches);
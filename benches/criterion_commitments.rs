@@ -0,0 +1,115 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use lambdaworks_stark::{
+    starks::{
+        config::{BatchedMerkleTree, FriMerkleTree},
+        proof::options::{CommitmentHash, MerkleTreeStorage},
+    },
+    FE,
+};
+
+const NUM_LEAVES: u64 = 1 << 16;
+// Columns of the main trace of Cairo programs without builtins
+const ROW_LENGTH: u64 = 34;
+const NUM_QUERIES: usize = 64;
+
+/// Time to build the Merkle trees of the commitments with each hash, for the rows
+/// of an LDE trace and the evaluations of a FRI layer.
+fn commitment_benches(c: &mut Criterion) {
+    let rows: Vec<Vec<FE>> = (0..NUM_LEAVES)
+        .map(|row| {
+            (0..ROW_LENGTH)
+                .map(|col| FE::from(row * ROW_LENGTH + col))
+                .collect()
+        })
+        .collect();
+    let evaluations: Vec<FE> = (0..NUM_LEAVES).map(FE::from).collect();
+
+    let hashes = [
+        ("keccak256", CommitmentHash::Keccak256),
+        #[cfg(feature = "blake3")]
+        ("blake3", CommitmentHash::Blake3),
+    ];
+
+    let storages = storages();
+
+    let mut group = c.benchmark_group("COMMITMENTS");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(NUM_LEAVES));
+    for (name, hash) in hashes {
+        for (storage_name, storage) in &storages {
+            group.bench_function(format!("trace_rows/{name}/{storage_name}"), |bench| {
+                bench.iter(|| {
+                    black_box(
+                        BatchedMerkleTree::<Stark252PrimeField>::build(hash, storage, &rows)
+                            .unwrap()
+                            .root(),
+                    )
+                });
+            });
+            group.bench_function(format!("fri_layer/{name}/{storage_name}"), |bench| {
+                bench.iter(|| {
+                    black_box(
+                        FriMerkleTree::<Stark252PrimeField>::build(hash, storage, &evaluations)
+                            .unwrap()
+                            .root(),
+                    )
+                });
+            });
+        }
+    }
+}
+
+/// Time to extract the paths opened by the queries of a proof from the tree of a FRI
+/// layer, one at a time and all at once.
+fn merkle_path_benches(c: &mut Criterion) {
+    let evaluations: Vec<FE> = (0..NUM_LEAVES).map(FE::from).collect();
+    // Each query opens an element and its symmetric one
+    let positions: Vec<usize> = (0..NUM_QUERIES)
+        .flat_map(|i| {
+            let position = (i * 40503) % NUM_LEAVES as usize;
+            [
+                position,
+                (position + NUM_LEAVES as usize / 2) % NUM_LEAVES as usize,
+            ]
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("MERKLE_PATHS");
+    group.throughput(Throughput::Elements(NUM_QUERIES as u64));
+    for (storage_name, storage) in storages() {
+        let tree = FriMerkleTree::<Stark252PrimeField>::build(
+            CommitmentHash::Keccak256,
+            &storage,
+            &evaluations,
+        )
+        .unwrap();
+        group.bench_function(format!("{storage_name}/per_query"), |bench| {
+            bench.iter(|| {
+                for position in &positions {
+                    black_box(tree.get_proof_by_pos(*position).unwrap());
+                }
+            });
+        });
+        group.bench_function(format!("{storage_name}/batched"), |bench| {
+            bench.iter(|| black_box(tree.get_proofs_by_pos(&positions).unwrap()));
+        });
+    }
+}
+
+fn storages() -> Vec<(&'static str, MerkleTreeStorage)> {
+    vec![
+        ("in_memory", MerkleTreeStorage::InMemory),
+        #[cfg(feature = "mmap")]
+        (
+            "on_disk",
+            MerkleTreeStorage::OnDisk {
+                directory: std::env::temp_dir(),
+                cached_levels: 10,
+            },
+        ),
+    ]
+}
+
+criterion_group!(benches, commitment_benches, merkle_path_benches);
+criterion_main!(benches);
@@ -3,10 +3,7 @@ use criterion::{black_box, measurement::WallTime, BenchmarkGroup};
 use criterion::{criterion_group, criterion_main, Criterion};
 #[cfg(feature = "giza")]
 use lambdaworks_stark::{
-    cairo::{
-        air::generate_cairo_proof,
-        runner::run::{generate_prover_args, CairoVersion},
-    },
+    cairo::{air::generate_cairo_proof, runner::program::CairoProgram},
     starks::proof::options::{self, SecurityLevel},
 };
 #[cfg(feature = "giza")]
@@ -82,9 +79,8 @@ fn run_lambdaworks_bench(
     benchname: &str,
     program_path: &str,
 ) {
-    let program_content = std::fs::read(program_path).unwrap();
-    let (main_trace, pub_inputs) =
-        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+    let program = CairoProgram::from_file(program_path).unwrap();
+    let (main_trace, pub_inputs) = program.generate_prover_args(&None).unwrap();
 
     group.bench_function(benchname, |bench| {
         bench.iter(|| {
@@ -105,7 +101,7 @@ fn run_giza_bench(
     let proof_options = giza_prover::ProofOptions::with_proof_options(
         Some(proof_options.fri_number_of_queries),
         Some(proof_options.blowup_factor as usize),
-        Some(proof_options.grinding_factor as u32),
+        Some(proof_options.grinding.bits() as u32),
         None,
         None,
     );
@@ -2,10 +2,7 @@ use criterion::{
     black_box, criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, Criterion,
 };
 use lambdaworks_stark::{
-    cairo::{
-        air::generate_cairo_proof,
-        runner::run::{generate_prover_args, CairoVersion},
-    },
+    cairo::{air::generate_cairo_proof, runner::program::CairoProgram},
     starks::proof::options::{ProofOptions, SecurityLevel},
 };
 
@@ -47,10 +44,9 @@ fn cairo0_program_path(program_name: &str) -> String {
 }
 
 fn run_cairo_bench(group: &mut BenchmarkGroup<'_, WallTime>, benchname: &str, program_path: &str) {
-    let program_content = std::fs::read(program_path).unwrap();
+    let program = CairoProgram::from_file(program_path).unwrap();
     let proof_options = ProofOptions::new_secure(SecurityLevel::Provable80Bits, 3);
-    let (main_trace, pub_inputs) =
-        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+    let (main_trace, pub_inputs) = program.generate_prover_args(&None).unwrap();
     println!("Generated main trace with {} rows", main_trace.n_rows());
 
     group.bench_function(benchname, |bench| {
@@ -0,0 +1,152 @@
+//! Time of each stage of the prover on its own, with the inputs of a proof of a Cairo
+//! program computed beforehand (see `StageFixture`).
+//!
+//! With the `flamegraph` feature, running the benchmarks with `--profile-time <seconds>`
+//! writes a flamegraph of each one to `target/criterion/<benchmark>/profile`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use lambdaworks_crypto::fiat_shamir::default_transcript::DefaultTranscript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use lambdaworks_stark::{
+    cairo::execution_trace::build_main_trace,
+    starks::{
+        config::BatchedVectorCommitment,
+        constraints::evaluator::ConstraintEvaluator,
+        fri::fri_commit_phase,
+        grinding::grind,
+        proof::options::{GrindingConfig, ProofOptions, SecurityLevel},
+        traits::AIR,
+        vector_commitment::IsVectorCommitment,
+    },
+    FE,
+};
+
+use functions::stages::{lde_evaluations, StageFixture};
+
+pub mod functions;
+
+const PROGRAM: &str = "fibonacci_1000.json";
+const GRINDING_BITS: u8 = 16;
+
+fn prover_stage_benches(c: &mut Criterion) {
+    let proof_options = ProofOptions::new_secure(SecurityLevel::Provable80Bits, 3);
+    let fixture = StageFixture::new(PROGRAM, &proof_options);
+
+    let mut group = c.benchmark_group("PROVER_STAGES");
+    group.sample_size(10);
+
+    group.bench_function("trace_build", |bench| {
+        bench.iter_batched(
+            || fixture.pub_inputs.clone(),
+            |mut pub_inputs| {
+                black_box(build_main_trace(
+                    &fixture.register_states,
+                    &fixture.memory,
+                    &mut pub_inputs,
+                ))
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("interpolation", |bench| {
+        bench.iter(|| black_box(fixture.main_trace.compute_trace_polys()));
+    });
+
+    group.bench_function("lde", |bench| {
+        bench.iter(|| {
+            for poly in &fixture.trace_polys {
+                black_box(lde_evaluations(poly, &fixture.air, &proof_options));
+            }
+        });
+    });
+
+    group.bench_function("commitment", |bench| {
+        let rows = fixture.lde_trace.rows();
+        bench.iter(|| {
+            black_box(
+                BatchedVectorCommitment::<Stark252PrimeField>::commit(&proof_options, &rows)
+                    .unwrap()
+                    .root(),
+            )
+        });
+    });
+
+    group.bench_function("constraint_evaluation", |bench| {
+        let num_transition_constraints = fixture.air.num_transition_constraints();
+        let num_boundary_constraints = fixture
+            .air
+            .boundary_constraints(&fixture.rap_challenges)
+            .constraints
+            .len();
+        let transition_coeffs = coefficients(num_transition_constraints);
+        let boundary_coeffs = coefficients(num_boundary_constraints);
+        let evaluator = ConstraintEvaluator::new(&fixture.air, &fixture.rap_challenges);
+        bench.iter(|| {
+            black_box(evaluator.evaluate(
+                &fixture.lde_trace,
+                &fixture.domain,
+                &transition_coeffs,
+                &boundary_coeffs,
+                &fixture.rap_challenges,
+            ))
+        });
+    });
+
+    group.bench_function("fri", |bench| {
+        let trace_length = fixture.air.trace_length();
+        bench.iter_batched(
+            || fixture.trace_polys[0].clone(),
+            |p_0| {
+                black_box(
+                    fri_commit_phase(
+                        proof_options.fri_number_of_layers(trace_length),
+                        proof_options.fri_last_poly_length(trace_length),
+                        p_0,
+                        &mut DefaultTranscript::new(),
+                        &proof_options.coset_offset.to_field_element(),
+                        fixture.lde_domain_size(),
+                        &proof_options,
+                    )
+                    .unwrap(),
+                )
+            },
+            BatchSize::LargeInput,
+        );
+    });
+
+    group.bench_function(format!("grinding/{GRINDING_BITS}_bits"), |bench| {
+        let mut challenge = [0; 32];
+        bench.iter(|| {
+            // A different challenge each time, as the work depends on it
+            challenge[0] = challenge[0].wrapping_add(1);
+            black_box(grind(&challenge, GrindingConfig::Bits(GRINDING_BITS)))
+        });
+    });
+}
+
+/// Arbitrary coefficients of the constraints, as the time doesn't depend on them.
+fn coefficients(num_constraints: usize) -> Vec<(FE, FE)> {
+    (0..num_constraints as u64)
+        .map(|i| (FE::from(2 * i + 1), FE::from(2 * i + 2)))
+        .collect()
+}
+
+#[cfg(feature = "flamegraph")]
+fn config() -> Criterion {
+    use pprof::criterion::{Output, PProfProfiler};
+
+    Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)))
+}
+
+#[cfg(not(feature = "flamegraph"))]
+fn config() -> Criterion {
+    Criterion::default()
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = prover_stage_benches
+}
+criterion_main!(benches);
@@ -6,23 +6,35 @@ use lambdaworks_math::{
 };
 use lambdaworks_stark::{
     cairo::air::{verify_cairo_proof, PublicInputs},
-    starks::proof::{
-        options::{ProofOptions, SecurityLevel},
-        stark::StarkProof,
+    starks::{
+        example::simple_fibonacci::{self, FibonacciAIR, FibonacciPublicInputs},
+        proof::{
+            options::{ProofOptions, SecurityLevel},
+            stark::StarkProof,
+        },
+        prover::prove,
+        verifier::{verify, verify_with_context, VerifierContext},
     },
+    FE,
 };
 
 pub mod functions;
 
 fn load_proof_and_pub_inputs(input_path: &str) -> (StarkProof<Stark252PrimeField>, PublicInputs) {
-    let program_content = std::fs::read(input_path).unwrap();
+    // The proofs are generated by `make bench_proofs`, in the format of this build
+    const REGENERATE: &str = "run `make bench_proofs` to generate the proofs";
+    let program_content = std::fs::read(input_path)
+        .unwrap_or_else(|error| panic!("Error reading {input_path}: {error}, {REGENERATE}"));
     let mut bytes = program_content.as_slice();
     let proof_len = usize::from_be_bytes(bytes[0..8].try_into().unwrap());
     bytes = &bytes[8..];
-    let proof = StarkProof::<Stark252PrimeField>::deserialize(&bytes[0..proof_len]).unwrap();
+    let proof = StarkProof::<Stark252PrimeField>::deserialize(&bytes[0..proof_len])
+        .unwrap_or_else(|error| panic!("Invalid proof {input_path}: {error:?}, {REGENERATE}"));
     bytes = &bytes[proof_len..];
 
-    let public_inputs = PublicInputs::deserialize(bytes).unwrap();
+    let public_inputs = PublicInputs::deserialize(bytes).unwrap_or_else(|error| {
+        panic!("Invalid public inputs {input_path}: {error:?}, {REGENERATE}")
+    });
 
     (proof, public_inputs)
 }
@@ -73,5 +85,46 @@ fn run_verifier_bench(
     });
 }
 
-criterion_group!(benches, verifier_benches);
+/// Verification of many small proofs, as done by an aggregator of proofs, with a
+/// new context for each proof and with a context reused by all of them.
+fn small_proofs_verifier_benches(c: &mut Criterion) {
+    let trace = simple_fibonacci::fibonacci_trace([FE::one(), FE::one()], 256);
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+    let proof_options = ProofOptions::new_secure(SecurityLevel::Conjecturable80Bits, 3);
+    let proof = prove::<Stark252PrimeField, FibonacciAIR<Stark252PrimeField>>(
+        &trace,
+        &pub_inputs,
+        &proof_options,
+    )
+    .unwrap();
+
+    let mut group = c.benchmark_group("VERIFIER_SMALL_PROOFS");
+    group.bench_function("fibonacci/256/new_context", |bench| {
+        bench.iter(|| {
+            black_box(
+                verify::<Stark252PrimeField, FibonacciAIR<Stark252PrimeField>>(
+                    &proof,
+                    &pub_inputs,
+                    &proof_options,
+                ),
+            )
+        });
+    });
+    let mut context = VerifierContext::new();
+    group.bench_function("fibonacci/256/reused_context", |bench| {
+        bench.iter(|| {
+            black_box(verify_with_context::<
+                Stark252PrimeField,
+                FibonacciAIR<Stark252PrimeField>,
+            >(
+                &proof, &pub_inputs, &proof_options, &mut context
+            ))
+        });
+    });
+}
+
+criterion_group!(benches, verifier_benches, small_proofs_verifier_benches);
 criterion_main!(benches);
@@ -16,14 +16,20 @@ use lambdaworks_stark::{
 pub mod functions;
 
 fn load_proof_and_pub_inputs(input_path: &str) -> (StarkProof<Stark252PrimeField>, PublicInputs) {
-    let program_content = std::fs::read(input_path).unwrap();
+    // The proofs are generated by `make bench_proofs`, in the format of this build
+    const REGENERATE: &str = "run `make bench_proofs` to generate the proofs";
+    let program_content = std::fs::read(input_path)
+        .unwrap_or_else(|error| panic!("Error reading {input_path}: {error}, {REGENERATE}"));
     let mut bytes = program_content.as_slice();
     let proof_len = usize::from_be_bytes(bytes[0..8].try_into().unwrap());
     bytes = &bytes[8..];
-    let proof = StarkProof::<Stark252PrimeField>::deserialize(&bytes[0..proof_len]).unwrap();
+    let proof = StarkProof::<Stark252PrimeField>::deserialize(&bytes[0..proof_len])
+        .unwrap_or_else(|error| panic!("Invalid proof {input_path}: {error:?}, {REGENERATE}"));
     bytes = &bytes[proof_len..];
 
-    let public_inputs = PublicInputs::deserialize(bytes).unwrap();
+    let public_inputs = PublicInputs::deserialize(bytes).unwrap_or_else(|error| {
+        panic!("Invalid public inputs {input_path}: {error:?}, {REGENERATE}")
+    });
 
     (proof, public_inputs)
 }
@@ -1,2 +1,3 @@
 pub mod cairo;
+pub mod stages;
 pub mod stark;
@@ -0,0 +1,111 @@
+//! Inputs of each stage of the prover, computed once from a run of a Cairo program,
+//! so the benchmarks of a stage only measure that stage.
+
+use lambdaworks_crypto::fiat_shamir::default_transcript::DefaultTranscript;
+use lambdaworks_math::{
+    field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField, polynomial::Polynomial,
+};
+use lambdaworks_stark::{
+    cairo::{
+        air::{CairoAIR, CairoRAPChallenges, PublicInputs},
+        cairo_layout::CairoLayout,
+        cairo_mem::CairoMemory,
+        execution_trace::build_main_trace,
+        register_states::RegisterStates,
+        runner::{
+            hints::HintConfig,
+            run::{cairo0_program_path, run_program_with_hint_config, CairoVersion},
+        },
+    },
+    starks::{
+        domain::Domain, proof::options::ProofOptions, prover::evaluate_polynomial_on_lde_domain,
+        trace::TraceTable, traits::AIR,
+    },
+    FE,
+};
+
+pub struct StageFixture {
+    pub register_states: RegisterStates,
+    pub memory: CairoMemory,
+    /// Public inputs as read from the run, before the trace is built.
+    pub pub_inputs: PublicInputs,
+    pub main_trace: TraceTable<Stark252PrimeField>,
+    pub air: CairoAIR,
+    pub domain: Domain<Stark252PrimeField>,
+    pub rap_challenges: CairoRAPChallenges,
+    /// Polynomials of the main and auxiliary columns.
+    pub trace_polys: Vec<Polynomial<FE>>,
+    /// Evaluations of `trace_polys` on the LDE domain, by column.
+    pub lde_evaluations: Vec<Vec<FE>>,
+    pub lde_trace: TraceTable<Stark252PrimeField>,
+}
+
+impl StageFixture {
+    /// Runs the Cairo 0 program and computes the inputs of every stage.
+    pub fn new(program_name: &str, proof_options: &ProofOptions) -> Self {
+        let program_content = std::fs::read(cairo0_program_path(program_name)).unwrap();
+        let (register_states, memory, program_size, memory_segments, _) =
+            run_program_with_hint_config(
+                None,
+                CairoLayout::Plain,
+                &program_content,
+                &CairoVersion::V0,
+                &HintConfig::default(),
+            )
+            .unwrap();
+        let pub_inputs = PublicInputs::from_regs_and_mem(
+            &register_states,
+            &memory,
+            program_size,
+            &memory_segments,
+        );
+
+        let mut trace_pub_inputs = pub_inputs.clone();
+        let main_trace = build_main_trace(&register_states, &memory, &mut trace_pub_inputs);
+        let air = CairoAIR::new(main_trace.n_rows(), &trace_pub_inputs, proof_options);
+        let domain = Domain::new(&air);
+
+        let rap_challenges = air.build_rap_challenges(&mut DefaultTranscript::new());
+        let aux_trace = air.build_auxiliary_trace(0, &main_trace, &rap_challenges);
+        let trace_polys =
+            TraceTable::concatenate_tables(&[&main_trace, &aux_trace]).compute_trace_polys();
+
+        let lde_evaluations: Vec<Vec<FE>> = trace_polys
+            .iter()
+            .map(|poly| lde_evaluations(poly, &air, proof_options))
+            .collect();
+        let lde_trace = TraceTable::new_from_cols(&lde_evaluations);
+
+        Self {
+            register_states,
+            memory,
+            pub_inputs,
+            main_trace,
+            air,
+            domain,
+            rap_challenges,
+            trace_polys,
+            lde_evaluations,
+            lde_trace,
+        }
+    }
+
+    pub fn lde_domain_size(&self) -> usize {
+        self.air.trace_length() * self.air.blowup_factor() as usize
+    }
+}
+
+/// Evaluations of the polynomial on the LDE domain of the AIR.
+pub fn lde_evaluations(
+    poly: &Polynomial<FE>,
+    air: &CairoAIR,
+    proof_options: &ProofOptions,
+) -> Vec<FE> {
+    evaluate_polynomial_on_lde_domain(
+        poly,
+        proof_options.blowup_factor as usize,
+        air.trace_length(),
+        &proof_options.coset_offset.to_field_element(),
+    )
+    .unwrap()
+}
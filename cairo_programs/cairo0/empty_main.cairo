@@ -0,0 +1,4 @@
+// `main` is compiled to a single `ret` instruction, so the execution has one step.
+func main() {
+	return ();
+}
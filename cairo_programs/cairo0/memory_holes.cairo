@@ -0,0 +1,8 @@
+// The cells skipped by `ap += 3` are never accessed, so they are memory holes
+// in the execution segment.
+func main() {
+	[ap] = 1, ap++;
+	ap += 3;
+	[ap] = [ap - 4] + 2, ap++;
+	return ();
+}
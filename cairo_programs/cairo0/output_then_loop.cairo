@@ -0,0 +1,19 @@
+%builtins output
+
+from starkware.cairo.common.serialize import serialize_word
+
+// Writes an output, runs for more steps than a chunk of the runner and writes another one.
+func main{output_ptr: felt*}() {
+    serialize_word(1234);
+    count_down(20000);
+    serialize_word(5678);
+
+    return ();
+}
+
+func count_down(n) {
+    if (n == 0) {
+        return ();
+    }
+    return count_down(n - 1);
+}
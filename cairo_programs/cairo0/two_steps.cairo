@@ -0,0 +1,5 @@
+// An execution of two steps: an assert_eq writing an immediate and the `ret` of main.
+func main() {
+	[ap] = 1, ap++;
+	ret;
+}
@@ -0,0 +1,138 @@
+//! Generates the proofs the verifier benchmarks load from `benches/proofs`, proving
+//! the compiled Cairo 0 programs of `cairo_programs/cairo0`.
+//!
+//! Usage: `cargo run --release --bin gen_bench_proofs [--security <level>] [<program>...]`
+//!
+//! Programs are given by their name, without extension, and default to the ones the
+//! benchmarks verify. The level is one of `conjecturable80`, `conjecturable100`,
+//! `conjecturable128`, `provable80`, `provable100` or `provable128`, and defaults to
+//! `provable80`, the one the benchmarks verify with. The proof of a program is written
+//! to `<program>.proof` at the default level and to `<program>_<level>.proof` at any
+//! other one.
+//!
+//! Each file is the length of the serialized proof as a big endian `u64`, followed by
+//! the proof and the serialized public inputs. The proofs must be generated again
+//! whenever their format changes, `make bench_proofs` does it before running the
+//! benchmarks.
+//!
+//! Exits with a non-zero status if any proof couldn't be generated.
+
+use std::path::PathBuf;
+use std::time::Instant;
+
+use lambdaworks_math::traits::Serializable;
+use lambdaworks_stark::cairo::air::{generate_cairo_proof, verify_cairo_proof};
+use lambdaworks_stark::cairo::runner::program::CairoProgram;
+use lambdaworks_stark::cairo::runner::run::cairo0_program_path;
+use lambdaworks_stark::starks::proof::options::{ProofOptions, SecurityLevel};
+
+const DEFAULT_PROGRAMS: [&str; 3] = ["fibonacci_500", "fibonacci_1000", "fibonacci_70000"];
+
+const DEFAULT_SECURITY_LEVEL: &str = "provable80";
+
+/// Coset offset of the options of the verifier benchmarks.
+const COSET_OFFSET: u64 = 3;
+
+fn security_level(name: &str) -> Option<SecurityLevel> {
+    match name {
+        "conjecturable80" => Some(SecurityLevel::Conjecturable80Bits),
+        "conjecturable100" => Some(SecurityLevel::Conjecturable100Bits),
+        "conjecturable128" => Some(SecurityLevel::Conjecturable128Bits),
+        "provable80" => Some(SecurityLevel::Provable80Bits),
+        "provable100" => Some(SecurityLevel::Provable100Bits),
+        "provable128" => Some(SecurityLevel::Provable128Bits),
+        _ => None,
+    }
+}
+
+fn proof_path(program: &str, security_level: &str) -> PathBuf {
+    let file_name = if security_level == DEFAULT_SECURITY_LEVEL {
+        format!("{program}.proof")
+    } else {
+        format!("{program}_{security_level}.proof")
+    };
+    [env!("CARGO_MANIFEST_DIR"), "benches", "proofs", &file_name]
+        .iter()
+        .collect()
+}
+
+/// Proves `program` and writes its proof, checking first that it verifies.
+fn generate_bench_proof(
+    program: &str,
+    security_level: &str,
+    proof_options: &ProofOptions,
+) -> Result<PathBuf, String> {
+    let program_path = cairo0_program_path(&format!("{program}.json"));
+    let cairo_program = CairoProgram::from_file(&program_path)
+        .map_err(|error| format!("Error opening {program_path}: {error}"))?;
+    let (main_trace, pub_inputs) = cairo_program
+        .generate_prover_args(&None)
+        .map_err(|error| format!("Error running {program}: {error}"))?;
+
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, proof_options)
+        .map_err(|error| format!("Error proving {program}: {error:?}"))?;
+    if !verify_cairo_proof(&proof, &pub_inputs, proof_options) {
+        return Err(format!("The proof of {program} does not verify"));
+    }
+
+    let proof_bytes = proof.serialize();
+    let mut bytes = (proof_bytes.len() as u64).to_be_bytes().to_vec();
+    bytes.extend(proof_bytes);
+    bytes.extend(pub_inputs.serialize());
+
+    let output_path = proof_path(program, security_level);
+    if let Some(proofs_dir) = output_path.parent() {
+        std::fs::create_dir_all(proofs_dir)
+            .map_err(|error| format!("Error creating {}: {error}", proofs_dir.display()))?;
+    }
+    std::fs::write(&output_path, bytes)
+        .map_err(|error| format!("Error writing {}: {error}", output_path.display()))?;
+    Ok(output_path)
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1).peekable();
+
+    let security_level_name = if args.peek().map(String::as_str) == Some("--security") {
+        args.next();
+        let Some(name) = args.next() else {
+            eprintln!("Usage: gen_bench_proofs [--security <level>] [<program>...]");
+            std::process::exit(1);
+        };
+        name
+    } else {
+        DEFAULT_SECURITY_LEVEL.to_string()
+    };
+    let Some(level) = security_level(&security_level_name) else {
+        eprintln!("Unknown security level: {security_level_name}");
+        std::process::exit(1);
+    };
+    let proof_options = ProofOptions::new_secure(level, COSET_OFFSET);
+
+    let mut programs: Vec<String> = args.collect();
+    if programs.is_empty() {
+        programs = DEFAULT_PROGRAMS
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+    }
+
+    let mut failed = false;
+    for program in &programs {
+        let timer = Instant::now();
+        match generate_bench_proof(program, &security_level_name, &proof_options) {
+            Ok(output_path) => println!(
+                "{program}: proof written to {} in {:?}",
+                output_path.display(),
+                timer.elapsed()
+            ),
+            Err(error) => {
+                eprintln!("{error}");
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
@@ -1,4 +1,7 @@
-use std::{collections::HashMap, ops::Range};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ops::Range,
+};
 
 use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
 use lambdaworks_math::{
@@ -8,67 +11,49 @@ use lambdaworks_math::{
     },
     traits::{ByteConversion, Deserializable, Serializable},
 };
+use num_integer::div_ceil;
+use tracing::error;
 
 use crate::{
     starks::{
-        constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+        config::{Commitment, COMMITMENT_SIZE},
+        constraints::{
+            boundary::{BoundaryConstraint, BoundaryConstraints},
+            symbolic::Expr,
+        },
         context::AirContext,
         frame::Frame,
-        proof::{options::ProofOptions, stark::StarkProof},
-        prover::{prove, ProvingError},
+        metrics::ProverMetrics,
+        plan::ProvingPlan,
+        proof::{
+            options::{ProofOptions, ProofOptionsPolicy},
+            stark::StarkProof,
+        },
+        prover::{
+            prove_recursive_friendly, prove_with_metrics, CpuProver, IsStarkProver, ProvingError,
+            RecursionFriendlyProof,
+        },
         trace::TraceTable,
         traits::AIR,
         transcript::transcript_to_field,
-        verifier::verify,
+        utils::{check_fully_read, deserialize_felt, deserialize_felt_len},
+        verifier::{try_verify, verify, verify_recursive_friendly, VerificationError},
+        virtual_columns::VirtualColumn,
     },
     FE,
 };
 
-use super::{cairo_mem::CairoMemory, register_states::RegisterStates};
-
-/// Main constraint identifiers
-const INST: usize = 16;
-const DST_ADDR: usize = 17;
-const OP0_ADDR: usize = 18;
-const OP1_ADDR: usize = 19;
-const NEXT_AP: usize = 20;
-const NEXT_FP: usize = 21;
-const NEXT_PC_1: usize = 22;
-const NEXT_PC_2: usize = 23;
-const T0: usize = 24;
-const T1: usize = 25;
-const MUL_1: usize = 26;
-const MUL_2: usize = 27;
-const CALL_1: usize = 28;
-const CALL_2: usize = 29;
-const ASSERT_EQ: usize = 30;
-
-// Auxiliary constraint identifiers
-const MEMORY_INCREASING_0: usize = 31;
-const MEMORY_INCREASING_1: usize = 32;
-const MEMORY_INCREASING_2: usize = 33;
-const MEMORY_INCREASING_3: usize = 34;
-
-const MEMORY_CONSISTENCY_0: usize = 35;
-const MEMORY_CONSISTENCY_1: usize = 36;
-const MEMORY_CONSISTENCY_2: usize = 37;
-const MEMORY_CONSISTENCY_3: usize = 38;
-
-const PERMUTATION_ARGUMENT_0: usize = 39;
-const PERMUTATION_ARGUMENT_1: usize = 40;
-const PERMUTATION_ARGUMENT_2: usize = 41;
-const PERMUTATION_ARGUMENT_3: usize = 42;
-
-const RANGE_CHECK_INCREASING_0: usize = 43;
-const RANGE_CHECK_INCREASING_1: usize = 44;
-const RANGE_CHECK_INCREASING_2: usize = 45;
-
-const RANGE_CHECK_0: usize = 46;
-const RANGE_CHECK_1: usize = 47;
-const RANGE_CHECK_2: usize = 48;
-
-// Range-check builtin value decomposition constraint
-const RANGE_CHECK_BUILTIN: usize = 49;
+use super::{
+    cairo_layout::CairoBuiltin,
+    cairo_mem::CairoMemory,
+    constraints::ConstraintId::{self, *},
+    errors::PublicInputsError,
+    execution_trace::{ADDR_COLUMNS, MEMORY_COLUMNS},
+    felt::fe_to_u64,
+    register_states::RegisterStates,
+    runner::run::{estimate_execution, estimate_trace_length, CairoVersion, Error as RunnerError},
+    trace_layout::{RangeCheckBuiltinColumns, TraceLayout},
+};
 
 // Frame row identifiers
 //  - Flags
@@ -109,57 +94,72 @@ pub const FRAME_T1: usize = 31;
 pub const FRAME_MUL: usize = 32;
 pub const FRAME_SELECTOR: usize = 33;
 
-// Range-check frame identifiers
-pub const RC_0: usize = 34;
-pub const RC_1: usize = 35;
-pub const RC_2: usize = 36;
-pub const RC_3: usize = 37;
-pub const RC_4: usize = 38;
-pub const RC_5: usize = 39;
-pub const RC_6: usize = 40;
-pub const RC_7: usize = 41;
-pub const RC_VALUE: usize = 42;
-
-// Auxiliary range check columns
-pub const RANGE_CHECK_COL_1: usize = 43;
-pub const RANGE_CHECK_COL_2: usize = 44;
-pub const RANGE_CHECK_COL_3: usize = 45;
-
-// Auxiliary memory columns
-pub const MEMORY_ADDR_SORTED_0: usize = 46;
-pub const MEMORY_ADDR_SORTED_1: usize = 47;
-pub const MEMORY_ADDR_SORTED_2: usize = 48;
-pub const MEMORY_ADDR_SORTED_3: usize = 49;
-
-pub const MEMORY_VALUES_SORTED_0: usize = 50;
-pub const MEMORY_VALUES_SORTED_1: usize = 51;
-pub const MEMORY_VALUES_SORTED_2: usize = 52;
-pub const MEMORY_VALUES_SORTED_3: usize = 53;
-
-pub const PERMUTATION_ARGUMENT_COL_0: usize = 54;
-pub const PERMUTATION_ARGUMENT_COL_1: usize = 55;
-pub const PERMUTATION_ARGUMENT_COL_2: usize = 56;
-pub const PERMUTATION_ARGUMENT_COL_3: usize = 57;
-
-pub const PERMUTATION_ARGUMENT_RANGE_CHECK_COL_1: usize = 58;
-pub const PERMUTATION_ARGUMENT_RANGE_CHECK_COL_2: usize = 59;
-pub const PERMUTATION_ARGUMENT_RANGE_CHECK_COL_3: usize = 60;
-
 // Trace layout
 pub const MEM_P_TRACE_OFFSET: usize = 17;
 pub const MEM_A_TRACE_OFFSET: usize = 19;
 
-// If Cairo AIR doesn't implement builtins, the auxiliary columns should have a smaller
-// index.
-const BUILTIN_OFFSET: usize = 9;
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum MemorySegment {
     RangeCheck,
     Output,
+    RangeCheck96,
 }
 
-pub type MemorySegmentMap = HashMap<MemorySegment, Range<u64>>;
+/// Layout of the columns of a range-check builtin. The values of its memory segment
+/// are decomposed in `n_parts` parts of `part_bits` bits, so the constraint of the
+/// builtin checks that they are smaller than `2^(n_parts * part_bits)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeCheckBuiltinLayout {
+    pub segment: MemorySegment,
+    pub constraint: ConstraintId,
+    pub n_parts: usize,
+    pub part_bits: usize,
+}
+
+impl RangeCheckBuiltinLayout {
+    /// Number of columns of the builtin: one for each part and one for the values.
+    pub const fn n_columns(&self) -> usize {
+        self.n_parts + 1
+    }
+
+    /// Layouts of the range-check builtins with a memory segment in `memory_segments`,
+    /// in the order of their columns.
+    pub fn used_in(memory_segments: &MemorySegmentMap) -> Vec<Self> {
+        RANGE_CHECK_BUILTIN_LAYOUTS
+            .into_iter()
+            .filter(|layout| memory_segments.contains_key(&layout.segment))
+            .collect()
+    }
+}
+
+/// Range-check builtin, checking values of 128 bits in eight 16-bit parts.
+pub const RANGE_CHECK_BUILTIN_LAYOUT: RangeCheckBuiltinLayout = RangeCheckBuiltinLayout {
+    segment: MemorySegment::RangeCheck,
+    constraint: ConstraintId::RangeCheckBuiltin,
+    n_parts: 8,
+    part_bits: 16,
+};
+
+/// Range-check96 builtin of the newer layouts, checking values of 96 bits in six
+/// 16-bit parts.
+pub const RANGE_CHECK96_BUILTIN_LAYOUT: RangeCheckBuiltinLayout = RangeCheckBuiltinLayout {
+    segment: MemorySegment::RangeCheck96,
+    constraint: ConstraintId::RangeCheck96Builtin,
+    n_parts: 6,
+    part_bits: 16,
+};
+
+/// Range-check builtins supported by the Cairo AIR, in the order of their columns.
+pub const RANGE_CHECK_BUILTIN_LAYOUTS: [RangeCheckBuiltinLayout; 2] =
+    [RANGE_CHECK_BUILTIN_LAYOUT, RANGE_CHECK96_BUILTIN_LAYOUT];
+
+/// Ranges of addresses of the memory segments of the builtins. Ordered by segment,
+/// so the public inputs serialize the same on every run.
+pub type MemorySegmentMap = BTreeMap<MemorySegment, Range<u64>>;
+
+/// Size in bits of the address space of Cairo programs. Memory addresses and registers
+/// must be smaller than `2^DEFAULT_ADDRESS_SPACE_BITS`.
+pub const DEFAULT_ADDRESS_SPACE_BITS: u32 = 64;
 
 #[derive(Debug, Clone)]
 pub struct PublicInputs {
@@ -176,10 +176,53 @@ pub struct PublicInputs {
     pub range_check_min: Option<u16>,
     // maximum range check value
     pub range_check_max: Option<u16>,
+    // maximum memory address accessed. Every address is bounded by it, since the
+    // sorted memory addresses start at zero and increase at most by one in each step.
+    pub memory_address_max: Option<u64>,
     // Range-check builtin address range
     pub memory_segments: MemorySegmentMap,
     pub public_memory: HashMap<FE, FE>,
     pub num_steps: usize, // number of execution steps
+    pub memory_padding: MemoryPadding,
+    // Number of addresses never accessed by the execution. They are filled with dummy
+    // accesses in rows after the execution, so the sorted memory addresses are continuous.
+    pub num_memory_holes: usize,
+    // Names of the builtins used by the program. Proofs of programs with builtins
+    // the AIR doesn't support are rejected before building it, see `unsupported_builtins`.
+    pub builtins: Vec<String>,
+    // Commitment to the whole memory of the execution, only set by the runner when
+    // asked to, see `generate_prover_args_with_memory_commitment`. It lets other
+    // systems reference the memory without running the program again, checking the
+    // cells with `MemoryOpening::verify`. It's not checked by the verifier, so it can
+    // only be trusted by who checks it against the memory.
+    pub memory_commitment: Option<Commitment>,
+}
+
+/// Rows added to the execution to build the main trace, after the range-check and
+/// memory holes. They are set when the main trace is built, and absorbed in the
+/// transcript before the memory permutation challenges are sampled.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryPadding {
+    /// Number of `(0, 0)` dummy memory accesses. The last ones of the memory columns
+    /// are replaced by the public memory cells, each contributing a factor `z` to the
+    /// final value of the memory permutation argument. See section 9.8 of the Cairo
+    /// whitepaper.
+    pub dummy_accesses: usize,
+    /// Number of copies of the last row appended to make the trace length a power
+    /// of two.
+    pub padding_rows: usize,
+}
+
+impl MemoryPadding {
+    /// Number of rows holding the dummy memory accesses.
+    pub fn dummy_rows(&self) -> usize {
+        self.dummy_accesses / ADDR_COLUMNS.len()
+    }
+
+    /// Rows at the end of the trace whose memory columns are zero.
+    pub fn num_rows(&self) -> usize {
+        self.dummy_rows() + self.padding_rows
+    }
 }
 
 impl PublicInputs {
@@ -205,6 +248,16 @@ impl PublicInputs {
         };
         let last_step = &register_states.rows[register_states.steps() - 1];
 
+        let builtins = CairoBuiltin::PROVABLE
+            .into_iter()
+            .filter(|builtin| {
+                builtin
+                    .memory_segment()
+                    .map_or(false, |segment| memory_segments.contains_key(&segment))
+            })
+            .map(|builtin| builtin.as_str().to_string())
+            .collect();
+
         PublicInputs {
             pc_init: FE::from(register_states.rows[0].pc),
             ap_init: FE::from(register_states.rows[0].ap),
@@ -213,13 +266,205 @@ impl PublicInputs {
             ap_final: FieldElement::from(last_step.ap),
             range_check_min: None,
             range_check_max: None,
+            memory_address_max: None,
             memory_segments: memory_segments.clone(),
             public_memory,
             num_steps: register_states.steps(),
+            memory_padding: MemoryPadding::default(),
+            num_memory_holes: 0,
+            builtins,
+            memory_commitment: None,
+        }
+    }
+
+    /// Names of the builtins of the program that the Cairo AIR can't prove, in the
+    /// order they are declared. Only builtins with a memory segment handled by the
+    /// AIR are supported, see `CairoBuiltin::memory_segment`.
+    pub fn unsupported_builtins(&self) -> Vec<&str> {
+        self.builtins
+            .iter()
+            .map(String::as_str)
+            .filter(|name| !is_supported_builtin(name))
+            .collect()
+    }
+
+    /// Values written by the program in the output builtin segment, in order.
+    /// Cells of the segment missing from the public memory are skipped, as in the
+    /// outputs streamed by `run_program_streaming_outputs`. Empty if the program
+    /// doesn't use the output builtin.
+    ///
+    /// Only the public memory is walked, so segments claimed by untrusted public
+    /// inputs don't bound the work.
+    pub fn outputs(&self) -> Vec<FE> {
+        let mut outputs: Vec<_> = self
+            .public_memory
+            .iter()
+            .filter(|(address, _)| self.is_output_address(address))
+            .map(|(address, value)| (address.representative(), *value))
+            .collect();
+        outputs.sort_by_key(|(address, _)| *address);
+        outputs.into_iter().map(|(_, value)| value).collect()
+    }
+
+    /// Whether `address` is in the output builtin segment.
+    pub fn is_output_address(&self, address: &FE) -> bool {
+        self.memory_segments
+            .get(&MemorySegment::Output)
+            .zip(fe_to_u64(address))
+            .map_or(false, |(output_range, address)| {
+                output_range.contains(&address)
+            })
+    }
+
+    /// Checks that the public inputs describe the execution in the main trace: the
+    /// registers at the first and last steps, the number of steps, and the values of
+    /// the public memory cells accessed during the execution.
+    pub fn check_trace_consistency(
+        &self,
+        main_trace: &TraceTable<Stark252PrimeField>,
+    ) -> Result<(), PublicInputsError> {
+        if self.memory_address_max.is_none() {
+            return Err(PublicInputsError::AddressOutOfBounds);
+        }
+
+        // The offsets of every row, including the range-check holes filled in the padding
+        let offsets = main_trace.get_cols(&[OFF_DST, OFF_OP0, OFF_OP1]).table;
+        let offsets_min = offsets.iter().min_by_key(|offset| offset.representative());
+        let offsets_max = offsets.iter().max_by_key(|offset| offset.representative());
+        match (self.range_check_bounds(), offsets_min, offsets_max) {
+            (Some((rc_min, rc_max)), Some(offsets_min), Some(offsets_max))
+                if offsets_min == &FE::from(rc_min as u64)
+                    && offsets_max == &FE::from(rc_max as u64) => {}
+            _ => return Err(PublicInputsError::RangeCheckBoundsMismatch),
+        }
+
+        let num_steps = self.num_steps;
+        if num_steps == 0 || main_trace.n_steps() < num_steps {
+            return Err(PublicInputsError::NotEnoughSteps(main_trace.n_steps()));
+        }
+
+        // The selector is one in every step but the last one, and the padding copies the last step
+        let last_step = num_steps - 1;
+        if main_trace.get(last_step, FRAME_SELECTOR) != FE::zero()
+            || (last_step > 0 && main_trace.get(last_step - 1, FRAME_SELECTOR) != FE::one())
+        {
+            return Err(PublicInputsError::NumStepsMismatch);
         }
+
+        let registers = [
+            ("pc_init", &self.pc_init, 0, FRAME_PC),
+            ("ap_init", &self.ap_init, 0, FRAME_AP),
+            ("fp_init", &self.fp_init, 0, FRAME_FP),
+            ("pc_final", &self.pc_final, last_step, FRAME_PC),
+            ("ap_final", &self.ap_final, last_step, FRAME_AP),
+        ];
+        for (name, value, step, col) in registers {
+            if &main_trace.get(step, col) != value {
+                return Err(PublicInputsError::RegisterMismatch(name));
+            }
+        }
+
+        let accesses = [
+            (FRAME_PC, FRAME_INST),
+            (FRAME_DST_ADDR, FRAME_DST),
+            (FRAME_OP0_ADDR, FRAME_OP0),
+            (FRAME_OP1_ADDR, FRAME_OP1),
+        ];
+        for step in 0..num_steps {
+            for (addr_col, value_col) in accesses {
+                let addr = main_trace.get(step, addr_col);
+                match self.public_memory.get(&addr) {
+                    Some(value) if value != &main_trace.get(step, value_col) => {
+                        return Err(PublicInputsError::PublicMemoryMismatch(addr));
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        self.check_memory_padding(main_trace.n_rows())?;
+        let padding_rows = main_trace.n_rows() - self.memory_padding.num_rows();
+        for row in padding_rows..main_trace.n_rows() {
+            if MEMORY_COLUMNS
+                .iter()
+                .any(|col| main_trace.get(row, *col) != FE::zero())
+            {
+                return Err(PublicInputsError::MemoryPaddingMismatch);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Number of rows filling the memory holes, with one hole in each address column.
+    pub fn memory_hole_rows(&self) -> usize {
+        div_ceil(self.num_memory_holes, ADDR_COLUMNS.len())
+    }
+
+    /// Checks that the memory holes, the dummy memory accesses and padding rows fit
+    /// in a trace of `trace_length` rows after the execution, and that there is a
+    /// dummy access for every public memory cell. The verifier relies on these counts to know
+    /// how many factors of the memory permutation argument come from the public
+    /// memory, instead of deriving them from the length of the trace.
+    pub fn check_memory_padding(&self, trace_length: usize) -> Result<(), PublicInputsError> {
+        let padding = &self.memory_padding;
+        let fits_in_trace = self
+            .num_steps
+            .checked_add(self.memory_hole_rows())
+            .and_then(|num_rows| num_rows.checked_add(padding.num_rows()))
+            .map_or(false, |num_rows| num_rows <= trace_length);
+        if padding.dummy_accesses < self.public_memory.len()
+            || padding.dummy_accesses % ADDR_COLUMNS.len() != 0
+            || !fits_in_trace
+        {
+            return Err(PublicInputsError::MemoryPaddingMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the range-check bounds, if they are set and the minimum is not
+    /// greater than the maximum.
+    ///
+    /// The bounds are enforced by boundary constraints on the first and last values
+    /// of the sorted offsets column. Since that column is a permutation of the
+    /// offsets and increases at most by one in each step, these values are the
+    /// minimum and maximum offsets of the trace, so the verifier only needs to
+    /// check that the claimed bounds are well formed.
+    pub fn range_check_bounds(&self) -> Option<(u16, u16)> {
+        match (self.range_check_min, self.range_check_max) {
+            (Some(rc_min), Some(rc_max)) if rc_min <= rc_max => Some((rc_min, rc_max)),
+            _ => None,
+        }
+    }
+
+    /// Checks that the registers at the boundaries of the execution and every memory
+    /// address are smaller than `2^address_space_bits`. The builtin segments are
+    /// bounded by the maximum address in `CairoAIR::check_configuration`.
+    pub fn check_address_bounds(&self, address_space_bits: u32) -> bool {
+        let fits = |value: u64| address_space_bits >= 64 || value < (1 << address_space_bits);
+
+        let registers = [
+            &self.pc_init,
+            &self.ap_init,
+            &self.fp_init,
+            &self.pc_final,
+            &self.ap_final,
+        ];
+        let registers_fit = registers
+            .into_iter()
+            .all(|register| fe_to_u64(register).map_or(false, fits));
+
+        registers_fit && self.memory_address_max.map_or(false, fits)
     }
 }
 
+fn is_supported_builtin(name: &str) -> bool {
+    CairoBuiltin::from_name(name)
+        .and_then(|builtin| builtin.memory_segment())
+        .is_some()
+}
+
 impl Serializable for PublicInputs {
     fn serialize(&self) -> Vec<u8> {
         let mut bytes = vec![];
@@ -246,11 +491,27 @@ impl Serializable for PublicInputs {
             bytes.extend(0u8.to_be_bytes());
         }
 
+        if let Some(memory_address_max) = self.memory_address_max {
+            bytes.extend(1u8.to_be_bytes());
+            bytes.extend(memory_address_max.to_be_bytes());
+        } else {
+            bytes.extend(0u8.to_be_bytes());
+        }
+
+        // The builtins go before the memory segments, so the segments of builtins
+        // unknown to the verifier can be skipped and reported as unsupported
+        bytes.extend(self.builtins.len().to_be_bytes());
+        for name in self.builtins.iter() {
+            bytes.extend(name.len().to_be_bytes());
+            bytes.extend(name.as_bytes());
+        }
+
         let mut memory_segment_bytes = vec![];
         for (segment, range) in self.memory_segments.iter() {
             let segment_type = match segment {
                 MemorySegment::RangeCheck => 0u8,
                 MemorySegment::Output => 1u8,
+                MemorySegment::RangeCheck96 => 2u8,
             };
             memory_segment_bytes.extend(segment_type.to_be_bytes());
             memory_segment_bytes.extend(range.start.to_be_bytes());
@@ -260,9 +521,16 @@ impl Serializable for PublicInputs {
         bytes.extend(memory_segment_length.to_be_bytes());
         bytes.extend(memory_segment_bytes);
 
+        // Sorted by address, so equal public inputs have equal bytes
+        let mut public_memory: Vec<_> = self
+            .public_memory
+            .iter()
+            .map(|(address, value)| (address.to_bytes_be(), value))
+            .collect();
+        public_memory.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
         let mut public_memory_bytes = vec![];
-        for (address, value) in self.public_memory.iter() {
-            public_memory_bytes.extend(address.to_bytes_be());
+        for (address, value) in public_memory {
+            public_memory_bytes.extend(address);
             public_memory_bytes.extend(value.to_bytes_be());
         }
         let public_memory_length = self.public_memory.len();
@@ -270,6 +538,16 @@ impl Serializable for PublicInputs {
         bytes.extend(public_memory_bytes);
 
         bytes.extend(self.num_steps.to_be_bytes());
+        bytes.extend(self.memory_padding.dummy_accesses.to_be_bytes());
+        bytes.extend(self.memory_padding.padding_rows.to_be_bytes());
+        bytes.extend(self.num_memory_holes.to_be_bytes());
+
+        if let Some(memory_commitment) = self.memory_commitment {
+            bytes.extend(1u8.to_be_bytes());
+            bytes.extend(memory_commitment);
+        } else {
+            bytes.extend(0u8.to_be_bytes());
+        }
 
         bytes
     }
@@ -280,45 +558,12 @@ impl Deserializable for PublicInputs {
     where
         Self: Sized,
     {
-        let mut bytes = bytes;
-        let felt_len = usize::from_be_bytes(
-            bytes
-                .get(0..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
-        bytes = &bytes[8..];
-        let pc_init = FE::from_bytes_be(
-            bytes
-                .get(..felt_len)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-        )?;
-        bytes = &bytes[felt_len..];
-        let ap_init = FE::from_bytes_be(
-            bytes
-                .get(..felt_len)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-        )?;
-        bytes = &bytes[felt_len..];
-        let fp_init = FE::from_bytes_be(
-            bytes
-                .get(..felt_len)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-        )?;
-        bytes = &bytes[felt_len..];
-        let pc_final = FE::from_bytes_be(
-            bytes
-                .get(..felt_len)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-        )?;
-        bytes = &bytes[felt_len..];
-        let ap_final = FE::from_bytes_be(
-            bytes
-                .get(..felt_len)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-        )?;
-        bytes = &bytes[felt_len..];
+        let (felt_len, bytes) = deserialize_felt_len::<Stark252PrimeField>(bytes)?;
+        let (pc_init, bytes) = deserialize_felt(bytes, felt_len)?;
+        let (ap_init, bytes) = deserialize_felt(bytes, felt_len)?;
+        let (fp_init, bytes) = deserialize_felt(bytes, felt_len)?;
+        let (pc_final, bytes) = deserialize_felt(bytes, felt_len)?;
+        let (ap_final, mut bytes) = deserialize_felt(bytes, felt_len)?;
 
         if bytes.is_empty() {
             return Err(DeserializationError::InvalidAmountOfBytes);
@@ -362,6 +607,57 @@ impl Deserializable for PublicInputs {
             _ => return Err(DeserializationError::FieldFromBytesError),
         };
 
+        if bytes.is_empty() {
+            return Err(DeserializationError::InvalidAmountOfBytes);
+        }
+        let memory_address_max = match bytes[0] {
+            0 => {
+                bytes = &bytes[1..];
+                None
+            }
+            1 => {
+                bytes = &bytes[1..];
+                let memory_address_max = u64::from_be_bytes(
+                    bytes
+                        .get(..8)
+                        .ok_or(DeserializationError::InvalidAmountOfBytes)?
+                        .try_into()
+                        .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
+                );
+                bytes = &bytes[8..];
+                Some(memory_address_max)
+            }
+            _ => return Err(DeserializationError::FieldFromBytesError),
+        };
+
+        let builtins_length = usize::from_be_bytes(
+            bytes
+                .get(0..8)
+                .ok_or(DeserializationError::InvalidAmountOfBytes)?
+                .try_into()
+                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
+        );
+        bytes = &bytes[8..];
+        let mut builtins = Vec::new();
+        for _ in 0..builtins_length {
+            let name_length = usize::from_be_bytes(
+                bytes
+                    .get(0..8)
+                    .ok_or(DeserializationError::InvalidAmountOfBytes)?
+                    .try_into()
+                    .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
+            );
+            bytes = &bytes[8..];
+            let name = bytes
+                .get(..name_length)
+                .ok_or(DeserializationError::InvalidAmountOfBytes)?;
+            let name =
+                String::from_utf8(name.to_vec()).map_err(|_| DeserializationError::InvalidValue)?;
+            bytes = &bytes[name_length..];
+            builtins.push(name);
+        }
+        let has_unsupported_builtins = builtins.iter().any(|name| !is_supported_builtin(name));
+
         let mut memory_segments = MemorySegmentMap::new();
         let memory_segment_length = usize::from_be_bytes(
             bytes
@@ -371,13 +667,21 @@ impl Deserializable for PublicInputs {
                 .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
         );
         bytes = &bytes[8..];
+        let mut previous_segment_type = None;
         for _ in 0..memory_segment_length {
             if bytes.is_empty() {
                 return Err(DeserializationError::InvalidAmountOfBytes);
             }
+            // The segments are written in increasing order of their types, once each
+            if previous_segment_type >= Some(bytes[0]) {
+                return Err(DeserializationError::InvalidValue);
+            }
+            previous_segment_type = Some(bytes[0]);
             let segment_type = match bytes[0] {
-                0 => MemorySegment::RangeCheck,
-                1 => MemorySegment::Output,
+                0 => Some(MemorySegment::RangeCheck),
+                1 => Some(MemorySegment::Output),
+                2 => Some(MemorySegment::RangeCheck96),
+                _ if has_unsupported_builtins => None,
                 _ => return Err(DeserializationError::FieldFromBytesError),
             };
             bytes = &bytes[1..];
@@ -397,7 +701,9 @@ impl Deserializable for PublicInputs {
                     .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
             );
             bytes = &bytes[8..];
-            memory_segments.insert(segment_type, start..end);
+            if let Some(segment_type) = segment_type {
+                memory_segments.insert(segment_type, start..end);
+            }
         }
 
         let mut public_memory = HashMap::new();
@@ -409,29 +715,51 @@ impl Deserializable for PublicInputs {
                 .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
         );
         bytes = &bytes[8..];
+        let mut previous_address = None;
         for _ in 0..public_memory_length {
-            let address = FE::from_bytes_be(
-                bytes
-                    .get(..felt_len)
-                    .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-            )?;
-            bytes = &bytes[felt_len..];
-            let value = FE::from_bytes_be(
-                bytes
-                    .get(..felt_len)
-                    .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-            )?;
-            bytes = &bytes[felt_len..];
+            let (address, value): (FE, FE);
+            (address, bytes) = deserialize_felt(bytes, felt_len)?;
+            (value, bytes) = deserialize_felt(bytes, felt_len)?;
+            // The cells are written in increasing order of their addresses, once each
+            let address_bytes = address.to_bytes_be();
+            if previous_address.as_ref() >= Some(&address_bytes) {
+                return Err(DeserializationError::InvalidValue);
+            }
+            previous_address = Some(address_bytes);
             public_memory.insert(address, value);
         }
 
-        let num_steps = usize::from_be_bytes(
-            bytes
-                .get(0..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
+        let mut counts = [0; 4];
+        for count in counts.iter_mut() {
+            *count = usize::from_be_bytes(
+                bytes
+                    .get(0..8)
+                    .ok_or(DeserializationError::InvalidAmountOfBytes)?
+                    .try_into()
+                    .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
+            );
+            bytes = &bytes[8..];
+        }
+        let [num_steps, dummy_accesses, padding_rows, num_memory_holes] = counts;
+
+        if bytes.is_empty() {
+            return Err(DeserializationError::InvalidAmountOfBytes);
+        }
+        let (memory_commitment, bytes) = match bytes[0] {
+            0 => (None, &bytes[1..]),
+            1 => (
+                Some(
+                    bytes
+                        .get(1..1 + COMMITMENT_SIZE)
+                        .ok_or(DeserializationError::InvalidAmountOfBytes)?
+                        .try_into()
+                        .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
+                ),
+                &bytes[1 + COMMITMENT_SIZE..],
+            ),
+            _ => return Err(DeserializationError::FieldFromBytesError),
+        };
+        check_fully_read(bytes)?;
 
         Ok(Self {
             pc_init,
@@ -441,9 +769,17 @@ impl Deserializable for PublicInputs {
             ap_final,
             range_check_min,
             range_check_max,
+            memory_address_max,
             memory_segments,
             public_memory,
             num_steps,
+            memory_padding: MemoryPadding {
+                dummy_accesses,
+                padding_rows,
+            },
+            num_memory_holes,
+            builtins,
+            memory_commitment,
         })
     }
 }
@@ -453,19 +789,71 @@ pub struct CairoAIR {
     pub context: AirContext,
     pub trace_length: usize,
     pub pub_inputs: PublicInputs,
-    has_rc_builtin: bool,
+    pub layout: TraceLayout,
+    transition_constraints: Vec<ConstraintId>,
 }
 
 impl CairoAIR {
-    fn get_builtin_offset(&self) -> usize {
-        if self.has_rc_builtin {
-            0
-        } else {
-            BUILTIN_OFFSET
-        }
+    /// Estimates the length of the trace of the program before running it fully, to
+    /// size the proof options with `ProofOptions::sized_for`. See
+    /// `runner::run::estimate_trace_length`.
+    pub fn estimate_trace_length(
+        program_content: &[u8],
+        cairo_version: &CairoVersion,
+    ) -> Result<usize, RunnerError> {
+        estimate_trace_length(program_content, cairo_version)
     }
 }
 
+/// What proving a Cairo program takes, see `plan_cairo_proof`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CairoProvingPlan {
+    pub num_steps: usize,
+    /// Plan of a proof of a trace of the estimated length, which is the exact one
+    /// unless the range-check holes take the trace to the next power of two. Then
+    /// the trace is twice as long, and so are the domains of the proof.
+    pub plan: ProvingPlan,
+}
+
+/// Plans the proof of a program with `proof_options` before proving it: the number
+/// of steps and the builtin segments are found by running the program without
+/// recording its trace, and the trace length is the one of `estimate_trace_length`,
+/// so the plan is an estimate too, see `CairoProvingPlan::plan`.
+pub fn plan_cairo_proof(
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+    proof_options: &ProofOptions,
+) -> Result<CairoProvingPlan, RunnerError> {
+    let estimate = estimate_execution(program_content, cairo_version)?;
+
+    // Only the builtin segments shape the AIR, the rest of the public inputs are
+    // placeholders.
+    let pub_inputs = PublicInputs {
+        pc_init: FE::zero(),
+        ap_init: FE::zero(),
+        fp_init: FE::zero(),
+        pc_final: FE::zero(),
+        ap_final: FE::zero(),
+        range_check_min: Some(0),
+        range_check_max: Some(0),
+        memory_address_max: Some(u64::MAX),
+        memory_segments: estimate.memory_segments,
+        public_memory: HashMap::new(),
+        num_steps: estimate.num_steps,
+        memory_padding: MemoryPadding::default(),
+        num_memory_holes: 0,
+        builtins: vec![],
+        memory_commitment: None,
+    };
+    let air = CairoAIR::new(estimate.trace_length, &pub_inputs, proof_options);
+    let plan = ProvingPlan::for_air(&air).ok_or(RunnerError::NoLdeDomain(estimate.trace_length))?;
+
+    Ok(CairoProvingPlan {
+        num_steps: estimate.num_steps,
+        plan,
+    })
+}
+
 pub struct CairoRAPChallenges {
     pub alpha_memory: FieldElement<Stark252PrimeField>,
     pub z_memory: FieldElement<Stark252PrimeField>,
@@ -516,10 +904,18 @@ fn get_pub_memory_addrs(
     }
 }
 
-fn sort_columns_by_memory_address(adresses: Vec<FE>, values: Vec<FE>) -> (Vec<FE>, Vec<FE>) {
-    let mut tuples: Vec<_> = adresses.into_iter().zip(values).collect();
-    tuples.sort_by(|(x, _), (y, _)| x.representative().cmp(&y.representative()));
-    tuples.into_iter().unzip()
+fn sort_columns_by_memory_address(addresses: Vec<FE>, values: Vec<FE>) -> (Vec<FE>, Vec<FE>) {
+    // Getting the representative of an element converts it out of Montgomery form, so
+    // the keys are computed once instead of on each comparison.
+    let keys: Vec<_> = addresses.iter().map(FE::representative).collect();
+    let mut permutation: Vec<usize> = (0..addresses.len()).collect();
+    // The sort is stable, so accesses to the same address keep their order
+    permutation.sort_by_key(|&i| &keys[i]);
+
+    permutation
+        .into_iter()
+        .map(|i| (addresses[i], values[i]))
+        .unzip()
 }
 
 fn generate_memory_permutation_argument_column(
@@ -590,49 +986,31 @@ impl AIR for CairoAIR {
         proof_options: &ProofOptions
     ) -> Self {
         debug_assert!(trace_length.is_power_of_two());
+        debug_assert!(Self::check_configuration(pub_inputs).is_ok());
+
+        // The columns and constraints of the range-check builtins depend on the segments
+        // of the program: each builtin adds the columns of its values decomposition.
+        let layout = TraceLayout::new(&pub_inputs.memory_segments);
+        let trace_columns = layout.num_columns();
+
+        // Degrees and exemptions come from the constraints registry, in index order.
+        let builtin_constraints: Vec<_> = layout
+            .range_check_builtins
+            .iter()
+            .map(|columns| columns.layout.constraint)
+            .collect();
+        let constraints: Vec<ConstraintId> = ConstraintId::enabled(&builtin_constraints).collect();
+        let transition_degrees = constraints.iter().map(|id| id.degree()).collect();
+        let transition_exemptions = constraints.iter().map(|id| id.exemptions()).collect();
+        let num_transition_constraints = constraints.len();
 
-        let mut trace_columns = 34 + 3 + 12 + 3;
-        let mut transition_degrees = vec![
-            2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, // Flags 0-14.
-            1, // Flag 15
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, // Other constraints.
-            2, 2, 2, 2, // Increasing memory auxiliary constraints.
-            2, 2, 2, 2, // Consistent memory auxiliary constraints.
-            2, 2, 2, 2, // Permutation auxiliary constraints.
-            2, 2, 2, // range-check increasing constraints.
-            2, 2, 2, // range-check permutation argument constraints.
-        ];
-        let mut transition_exemptions = vec![
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // flags (16)
-            0, // inst (1)
-            0, 0, 0, // operand consraints (3)
-            1, 1, 1, 1, 0, 0, // register constraints (6)
-            0, 0, 0, 0, 0, // opcode constraints (5)
-            0, 0, 0, 1, // memory continuous (4)
-            0, 0, 0, 1, // memory value consistency (4)
-            0, 0, 0, 1, // memory permutation argument (4)
-            0, 0, 1, // range check continuous (3)
-            0, 0, 0, // range check permutation argument (3)
-        ];
-        let mut num_transition_constraints = 49;
-
-        // This is a hacky solution for the moment and must be changed once we start implementing 
-        // layouts functionality. The `has_rc_builtin` boolean should not exist, we will know the
-        // layout from the Cairo public inputs directly, and the number of constraints and columns
-        // will be enforced through that.
-        let has_rc_builtin = !pub_inputs.memory_segments.is_empty();
-        if has_rc_builtin {
-            trace_columns += 8 + 1; // 8 columns for each rc of the range-check builtin values decomposition, 1 for the values
-            transition_degrees.push(1); // Range check builtin constraint
-            transition_exemptions.push(0); // range-check builtin exemption
-            num_transition_constraints += 1; // range-check builtin value decomposition constraint
-        }
         let num_transition_exemptions =1_usize;
         let context = AirContext {
             proof_options: proof_options.clone(),
             trace_columns,
             transition_degrees,
             transition_exemptions,
+            transition_exemption_zones: Vec::new(),
             transition_offsets: vec![0, 1],
             num_transition_constraints,
             num_transition_exemptions,
@@ -653,12 +1031,60 @@ impl AIR for CairoAIR {
             context,
             pub_inputs: pub_inputs.clone(),
             trace_length,
-            has_rc_builtin,
+            layout,
+            transition_constraints: constraints,
+        }
+    }
+
+    fn check_configuration(pub_inputs: &Self::PublicInputs) -> Result<(), String> {
+        if pub_inputs.num_steps == 0 {
+            return Err("the execution has no steps".to_string());
+        }
+        // The initial and final registers of a single step are in the same row, so only
+        // the initial ones are constrained. See `boundary_constraints`.
+        if pub_inputs.num_steps == 1
+            && (pub_inputs.pc_final != pub_inputs.pc_init
+                || pub_inputs.ap_final != pub_inputs.ap_init)
+        {
+            return Err(
+                "the final registers of a single step execution must be the initial ones"
+                    .to_string(),
+            );
+        }
+
+        // The range-check boundary constraints need both bounds, and the verifier
+        // would otherwise accept offsets out of any range.
+        if pub_inputs.range_check_bounds().is_none() {
+            return Err("the range-check bounds are missing or inverted".to_string());
+        }
+
+        // Every address is bounded by the maximum one, including the ones of the builtin
+        // segments, whose constraints only read addresses in the memory.
+        let Some(memory_address_max) = pub_inputs.memory_address_max else {
+            return Err("the maximum memory address is missing".to_string());
+        };
+        for (segment, range) in &pub_inputs.memory_segments {
+            if range.start > range.end || (!range.is_empty() && range.end - 1 > memory_address_max)
+            {
+                return Err(format!(
+                    "the {segment:?} segment {range:?} is out of the memory addresses"
+                ));
+            }
+        }
+
+        let unsupported_builtins = pub_inputs.unsupported_builtins();
+        if !unsupported_builtins.is_empty() {
+            return Err(format!(
+                "the Cairo AIR doesn't support the builtins {}",
+                unsupported_builtins.join(", ")
+            ));
         }
+        Ok(())
     }
 
     fn build_auxiliary_trace(
         &self,
+        _round: usize,
         main_trace: &TraceTable<Self::Field>,
         rap_challenges: &Self::RAPChallenges,
     ) -> TraceTable<Self::Field> {
@@ -703,32 +1129,24 @@ impl AIR for CairoAIR {
         );
 
         // Convert from long-format to wide-format again
-        let mut aux_table = Vec::new();
-        for i in 0..main_trace.n_rows() {
-            aux_table.push(offsets_sorted[3 * i]);
-            aux_table.push(offsets_sorted[3 * i + 1]);
-            aux_table.push(offsets_sorted[3 * i + 2]);
-            aux_table.push(addresses[4 * i]);
-            aux_table.push(addresses[4 * i + 1]);
-            aux_table.push(addresses[4 * i + 2]);
-            aux_table.push(addresses[4 * i + 3]);
-            aux_table.push(values[4 * i]);
-            aux_table.push(values[4 * i + 1]);
-            aux_table.push(values[4 * i + 2]);
-            aux_table.push(values[4 * i + 3]);
-            aux_table.push(permutation_col[4 * i]);
-            aux_table.push(permutation_col[4 * i + 1]);
-            aux_table.push(permutation_col[4 * i + 2]);
-            aux_table.push(permutation_col[4 * i + 3]);
-            aux_table.push(range_check_permutation_col[3 * i]);
-            aux_table.push(range_check_permutation_col[3 * i + 1]);
-            aux_table.push(range_check_permutation_col[3 * i + 2]);
-        }
-
-        TraceTable::new(aux_table, self.number_auxiliary_rap_columns())
+        let aux_table = TraceTable::concatenate_tables(&[
+            &TraceTable::new(offsets_sorted, 3),
+            &TraceTable::new(addresses, 4),
+            &TraceTable::new(values, 4),
+            &TraceTable::new(permutation_col, 4),
+            &TraceTable::new(range_check_permutation_col, 3),
+        ]);
+        debug_assert_eq!(aux_table.n_cols, self.number_auxiliary_rap_columns());
+
+        aux_table.with_rows_per_step(main_trace.rows_per_step())
     }
 
     fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges {
+        let memory_padding = &self.pub_inputs.memory_padding;
+        transcript.append(&(memory_padding.dummy_accesses as u64).to_be_bytes());
+        transcript.append(&(memory_padding.padding_rows as u64).to_be_bytes());
+        transcript.append(&(self.pub_inputs.num_memory_holes as u64).to_be_bytes());
+
         CairoRAPChallenges {
             alpha_memory: transcript_to_field(transcript),
             z_memory: transcript_to_field(transcript),
@@ -737,7 +1155,7 @@ impl AIR for CairoAIR {
     }
 
     fn number_auxiliary_rap_columns(&self) -> usize {
-        12 + 3 + 3
+        self.layout.num_auxiliary_columns()
     }
 
     fn compute_transition(
@@ -745,8 +1163,6 @@ impl AIR for CairoAIR {
         frame: &Frame<Self::Field>,
         rap_challenges: &Self::RAPChallenges,
     ) -> Vec<FieldElement<Self::Field>> {
-        let builtin_offset = self.get_builtin_offset();
-
         let mut constraints: Vec<FieldElement<Self::Field>> =
             vec![FE::zero(); self.num_transition_constraints()];
 
@@ -755,12 +1171,16 @@ impl AIR for CairoAIR {
         compute_register_constraints(&mut constraints, frame);
         compute_opcode_constraints(&mut constraints, frame);
         enforce_selector(&mut constraints, frame);
-        memory_is_increasing(&mut constraints, frame, builtin_offset);
-        permutation_argument(&mut constraints, frame, rap_challenges, builtin_offset);
-        permutation_argument_range_check(&mut constraints, frame, rap_challenges, builtin_offset);
-
-        if self.has_rc_builtin {
-            range_check_builtin(&mut constraints, frame);
+        memory_is_increasing(&mut constraints, frame, &self.layout);
+        permutation_argument(&mut constraints, frame, rap_challenges, &self.layout);
+        permutation_argument_range_check(&mut constraints, frame, rap_challenges, &self.layout);
+
+        // The constraints of the builtins go after the CPU ones, in the order of their
+        // columns, see `ConstraintId::enabled`.
+        for (index, rc_builtin) in
+            (RangeCheckBuiltin.index()..).zip(&self.layout.range_check_builtins)
+        {
+            range_check_builtin(&mut constraints, frame, index, rc_builtin);
         }
 
         constraints
@@ -781,22 +1201,23 @@ impl AIR for CairoAIR {
         let initial_pc = BoundaryConstraint::new(MEM_A_TRACE_OFFSET, 0, self.pub_inputs.pc_init);
         let initial_ap = BoundaryConstraint::new(MEM_P_TRACE_OFFSET, 0, self.pub_inputs.ap_init);
 
-        let final_pc = BoundaryConstraint::new(
-            MEM_A_TRACE_OFFSET,
-            self.pub_inputs.num_steps - 1,
-            self.pub_inputs.pc_final,
-        );
-        let final_ap = BoundaryConstraint::new(
-            MEM_P_TRACE_OFFSET,
-            self.pub_inputs.num_steps - 1,
-            self.pub_inputs.ap_final,
-        );
+        let mut constraints = vec![initial_pc, initial_ap];
+
+        // A column can't have two constraints in the same row, so the final registers of
+        // a single step execution are only checked to be the initial ones, in
+        // `check_configuration`.
+        let last_step = self.pub_inputs.num_steps - 1;
+        if last_step > 0 {
+            let final_pc =
+                BoundaryConstraint::new(MEM_A_TRACE_OFFSET, last_step, self.pub_inputs.pc_final);
+            let final_ap =
+                BoundaryConstraint::new(MEM_P_TRACE_OFFSET, last_step, self.pub_inputs.ap_final);
+            constraints.extend([final_pc, final_ap]);
+        }
 
         // Auxiliary constraint: permutation argument final value
         let final_index = self.trace_length - 1;
 
-        let builtin_offset = self.get_builtin_offset();
-
         let cumulative_product = self
             .pub_inputs
             .public_memory
@@ -811,39 +1232,54 @@ impl AIR for CairoAIR {
             .pow(self.pub_inputs.public_memory.len())
             * cumulative_product;
         let permutation_final_constraint = BoundaryConstraint::new(
-            PERMUTATION_ARGUMENT_COL_3 - builtin_offset,
+            self.layout.memory_permutation[3],
             final_index,
             permutation_final,
         );
 
         let one: FieldElement<Self::Field> = FieldElement::one();
-        let range_check_final_constraint = BoundaryConstraint::new(
-            PERMUTATION_ARGUMENT_RANGE_CHECK_COL_3 - builtin_offset,
-            final_index,
-            one,
+        let range_check_final_constraint =
+            BoundaryConstraint::new(self.layout.range_check_permutation[2], final_index, one);
+
+        // The sorted memory addresses go from zero, the address of the public memory
+        // dummy accesses, to the maximum address, bounding every address in between.
+        let memory_address_min = BoundaryConstraint::new(
+            self.layout.memory_addresses_sorted[0],
+            0,
+            FieldElement::zero(),
         );
 
+        constraints.extend([
+            permutation_final_constraint,
+            range_check_final_constraint,
+            memory_address_min,
+        ]);
+
+        let (rc_min, rc_max) = self
+            .pub_inputs
+            .range_check_bounds()
+            .expect("the range-check bounds are checked by check_configuration");
         let range_check_min = BoundaryConstraint::new(
-            RANGE_CHECK_COL_1 - builtin_offset,
+            self.layout.range_check_sorted[0],
             0,
-            FieldElement::from(self.pub_inputs.range_check_min.unwrap() as u64),
+            FieldElement::from(rc_min as u64),
         );
         let range_check_max = BoundaryConstraint::new(
-            RANGE_CHECK_COL_3 - builtin_offset,
+            self.layout.range_check_sorted[2],
             final_index,
-            FieldElement::from(self.pub_inputs.range_check_max.unwrap() as u64),
+            FieldElement::from(rc_max as u64),
         );
+        constraints.extend([range_check_min, range_check_max]);
 
-        let constraints = vec![
-            initial_pc,
-            initial_ap,
-            final_pc,
-            final_ap,
-            permutation_final_constraint,
-            range_check_final_constraint,
-            range_check_min,
-            range_check_max,
-        ];
+        let memory_address_max = self
+            .pub_inputs
+            .memory_address_max
+            .expect("the maximum memory address is checked by check_configuration");
+        constraints.push(BoundaryConstraint::new(
+            self.layout.memory_addresses_sorted[3],
+            final_index,
+            FieldElement::from(memory_address_max),
+        ));
 
         BoundaryConstraints::from_constraints(constraints)
     }
@@ -863,17 +1299,70 @@ impl AIR for CairoAIR {
     fn pub_inputs(&self) -> &Self::PublicInputs {
         &self.pub_inputs
     }
+
+    /// Commitment to the statement and the whole public inputs: the program and the
+    /// rest of the public memory, the registers, the number of steps and the builtins
+    /// with their segments. See `PublicInputs::statement_commitment`.
+    fn public_parameters(&self) -> Vec<u8> {
+        self.pub_inputs.statement_commitment().to_vec()
+    }
+
+    fn transition_constraint_name(&self, index: usize) -> Option<&'static str> {
+        self.transition_constraints
+            .get(index)
+            .copied()
+            .map(ConstraintId::name)
+    }
+
+    fn symbolic_transition(&self) -> Option<Vec<Expr<Self::Field>>> {
+        Some(symbolic_transition_constraints(&self.layout))
+    }
+
+    fn rap_challenge_names(&self) -> Vec<&'static str> {
+        vec!["alpha_memory", "z_memory", "z_range_check"]
+    }
+
+    /// Every cell of the current row, which covers the boundary constraints and
+    /// keeps every column in the DEEP composition polynomial, and the cells of the
+    /// next row referenced by the transition constraints, like the registers.
+    fn referenced_frame_cells(&self) -> Option<BTreeSet<(usize, usize)>> {
+        let mut cells = (0..self.context.trace_columns)
+            .map(|col| (0, col))
+            .collect();
+        for constraint in symbolic_transition_constraints(&self.layout) {
+            constraint.collect_cells(&mut cells);
+        }
+        Some(cells)
+    }
+
+    /// The instruction is the packing of its offsets and flags, see the instruction
+    /// unpacking constraint, so it isn't committed.
+    fn virtual_columns(&self) -> Vec<VirtualColumn<Self::Field>> {
+        let mut terms = vec![
+            (OFF_DST, FE::one()),
+            (OFF_OP0, FE::from(1_u64 << 16)),
+            (OFF_OP1, FE::from(1_u64 << 32)),
+        ];
+        terms.extend((0..15).map(|flag| (flag, FE::from(1_u64 << (48 + flag)))));
+
+        vec![VirtualColumn {
+            column: FRAME_INST,
+            terms,
+            constant: FE::zero(),
+        }]
+    }
 }
 
 /// From the Cairo whitepaper, section 9.10
 fn compute_instr_constraints(constraints: &mut [FE], frame: &Frame<Stark252PrimeField>) {
     // These constraints are only applied over elements of the same row.
     let curr = frame.get_row(0);
+    let one = FE::one();
 
     // Bit constraints
     for (i, flag) in curr[0..16].iter().enumerate() {
         constraints[i] = match i {
-            0..=14 => flag * (flag - FE::one()),
+            0..=14 => flag * (flag - &one),
             15 => *flag,
             _ => panic!("Unknown flag offset"),
         };
@@ -881,9 +1370,9 @@ fn compute_instr_constraints(constraints: &mut [FE], frame: &Frame<Stark252Prime
 
     // Instruction unpacking
     let two = FE::from(2);
-    let b16 = two.pow(16u32);
-    let b32 = two.pow(32u32);
-    let b48 = two.pow(48u32);
+    let b16 = FE::from(1_u64 << 16);
+    let b32 = FE::from(1_u64 << 32);
+    let b48 = FE::from(1_u64 << 48);
 
     // Named like this to match the Cairo whitepaper's notation.
     let f0_squiggle = &curr[0..15]
@@ -891,7 +1380,7 @@ fn compute_instr_constraints(constraints: &mut [FE], frame: &Frame<Stark252Prime
         .rev()
         .fold(FE::zero(), |acc, flag| flag + &two * acc);
 
-    constraints[INST] =
+    constraints[Inst.index()] =
         (&curr[OFF_DST]) + b16 * (&curr[OFF_OP0]) + b32 * (&curr[OFF_OP1]) + b48 * f0_squiggle
             - &curr[FRAME_INST];
 }
@@ -907,15 +1396,15 @@ fn compute_operand_constraints(constraints: &mut [FE], frame: &Frame<Stark252Pri
     let one = FE::one();
     let b15 = FE::from(2).pow(15u32);
 
-    constraints[DST_ADDR] =
+    constraints[DstAddr.index()] =
         &curr[F_DST_FP] * fp + (&one - &curr[F_DST_FP]) * ap + (&curr[OFF_DST] - &b15)
             - &curr[FRAME_DST_ADDR];
 
-    constraints[OP0_ADDR] =
+    constraints[Op0Addr.index()] =
         &curr[F_OP_0_FP] * fp + (&one - &curr[F_OP_0_FP]) * ap + (&curr[OFF_OP0] - &b15)
             - &curr[FRAME_OP0_ADDR];
 
-    constraints[OP1_ADDR] = &curr[F_OP_1_VAL] * pc
+    constraints[Op1Addr.index()] = &curr[F_OP_1_VAL] * pc
         + &curr[F_OP_1_AP] * ap
         + &curr[F_OP_1_FP] * fp
         + (&one - &curr[F_OP_1_VAL] - &curr[F_OP_1_AP] - &curr[F_OP_1_FP]) * &curr[FRAME_OP0]
@@ -929,145 +1418,138 @@ fn compute_register_constraints(constraints: &mut [FE], frame: &Frame<Stark252Pr
 
     let one = FE::one();
     let two = FE::from(2);
+    let next_pc_without_jump = &curr[FRAME_PC] + frame_inst_size(curr);
 
     // ap and fp constraints
-    constraints[NEXT_AP] = &curr[FRAME_AP]
+    constraints[NextAp.index()] = &curr[FRAME_AP]
         + &curr[F_AP_ADD] * &curr[FRAME_RES]
         + &curr[F_AP_ONE]
         + &curr[F_OPC_CALL] * &two
         - &next[FRAME_AP];
 
-    constraints[NEXT_FP] = &curr[F_OPC_RET] * &curr[FRAME_DST]
+    constraints[NextFp.index()] = &curr[F_OPC_RET] * &curr[FRAME_DST]
         + &curr[F_OPC_CALL] * (&curr[FRAME_AP] + &two)
         + (&one - &curr[F_OPC_RET] - &curr[F_OPC_CALL]) * &curr[FRAME_FP]
         - &next[FRAME_FP];
 
     // pc constraints
-    constraints[NEXT_PC_1] = (&curr[FRAME_T1] - &curr[F_PC_JNZ])
-        * (&next[FRAME_PC] - (&curr[FRAME_PC] + frame_inst_size(curr)));
+    constraints[NextPc1.index()] =
+        (&curr[FRAME_T1] - &curr[F_PC_JNZ]) * (&next[FRAME_PC] - &next_pc_without_jump);
 
-    constraints[NEXT_PC_2] = &curr[FRAME_T0]
+    constraints[NextPc2.index()] = &curr[FRAME_T0]
         * (&next[FRAME_PC] - (&curr[FRAME_PC] + &curr[FRAME_OP1]))
         + (&one - &curr[F_PC_JNZ]) * &next[FRAME_PC]
-        - ((&one - &curr[F_PC_ABS] - &curr[F_PC_REL] - &curr[F_PC_JNZ])
-            * (&curr[FRAME_PC] + frame_inst_size(curr))
+        - ((&one - &curr[F_PC_ABS] - &curr[F_PC_REL] - &curr[F_PC_JNZ]) * &next_pc_without_jump
             + &curr[F_PC_ABS] * &curr[FRAME_RES]
             + &curr[F_PC_REL] * (&curr[FRAME_PC] + &curr[FRAME_RES]));
 
-    constraints[T0] = &curr[F_PC_JNZ] * &curr[FRAME_DST] - &curr[FRAME_T0];
-    constraints[T1] = &curr[FRAME_T0] * &curr[FRAME_RES] - &curr[FRAME_T1];
+    constraints[T0.index()] = &curr[F_PC_JNZ] * &curr[FRAME_DST] - &curr[FRAME_T0];
+    constraints[T1.index()] = &curr[FRAME_T0] * &curr[FRAME_RES] - &curr[FRAME_T1];
 }
 
 fn compute_opcode_constraints(constraints: &mut [FE], frame: &Frame<Stark252PrimeField>) {
     let curr = frame.get_row(0);
     let one = FE::one();
 
-    constraints[MUL_1] = &curr[FRAME_MUL] - (&curr[FRAME_OP0] * &curr[FRAME_OP1]);
+    constraints[Mul1.index()] = &curr[FRAME_MUL] - (&curr[FRAME_OP0] * &curr[FRAME_OP1]);
 
-    constraints[MUL_2] = &curr[F_RES_ADD] * (&curr[FRAME_OP0] + &curr[FRAME_OP1])
+    constraints[Mul2.index()] = &curr[F_RES_ADD] * (&curr[FRAME_OP0] + &curr[FRAME_OP1])
         + &curr[F_RES_MUL] * &curr[FRAME_MUL]
         + (&one - &curr[F_RES_ADD] - &curr[F_RES_MUL] - &curr[F_PC_JNZ]) * &curr[FRAME_OP1]
         - (&one - &curr[F_PC_JNZ]) * &curr[FRAME_RES];
 
-    constraints[CALL_1] = &curr[F_OPC_CALL] * (&curr[FRAME_DST] - &curr[FRAME_FP]);
+    constraints[Call1.index()] = &curr[F_OPC_CALL] * (&curr[FRAME_DST] - &curr[FRAME_FP]);
 
-    constraints[CALL_2] =
+    constraints[Call2.index()] =
         &curr[F_OPC_CALL] * (&curr[FRAME_OP0] - (&curr[FRAME_PC] + frame_inst_size(curr)));
 
-    constraints[ASSERT_EQ] = &curr[F_OPC_AEQ] * (&curr[FRAME_DST] - &curr[FRAME_RES]);
+    constraints[AssertEq.index()] = &curr[F_OPC_AEQ] * (&curr[FRAME_DST] - &curr[FRAME_RES]);
 }
 
 fn enforce_selector(constraints: &mut [FE], frame: &Frame<Stark252PrimeField>) {
-    let curr = frame.get_row(0);
-    for result_cell in constraints.iter_mut().take(ASSERT_EQ + 1).skip(INST) {
-        *result_cell = *result_cell * curr[FRAME_SELECTOR];
+    let selector = &frame.get_row(0)[FRAME_SELECTOR];
+    for result_cell in constraints
+        .iter_mut()
+        .take(AssertEq.index() + 1)
+        .skip(Inst.index())
+    {
+        *result_cell = &*result_cell * selector;
     }
 }
 
 fn memory_is_increasing(
     constraints: &mut [FE],
     frame: &Frame<Stark252PrimeField>,
-    builtin_offset: usize,
+    layout: &TraceLayout,
 ) {
     let curr = frame.get_row(0);
     let next = frame.get_row(1);
     let one = FieldElement::one();
 
-    constraints[MEMORY_INCREASING_0] = (&curr[MEMORY_ADDR_SORTED_0 - builtin_offset]
-        - &curr[MEMORY_ADDR_SORTED_1 - builtin_offset])
-        * (&curr[MEMORY_ADDR_SORTED_1 - builtin_offset]
-            - &curr[MEMORY_ADDR_SORTED_0 - builtin_offset]
-            - &one);
-
-    constraints[MEMORY_INCREASING_1] = (&curr[MEMORY_ADDR_SORTED_1 - builtin_offset]
-        - &curr[MEMORY_ADDR_SORTED_2 - builtin_offset])
-        * (&curr[MEMORY_ADDR_SORTED_2 - builtin_offset]
-            - &curr[MEMORY_ADDR_SORTED_1 - builtin_offset]
-            - &one);
-
-    constraints[MEMORY_INCREASING_2] = (&curr[MEMORY_ADDR_SORTED_2 - builtin_offset]
-        - &curr[MEMORY_ADDR_SORTED_3 - builtin_offset])
-        * (&curr[MEMORY_ADDR_SORTED_3 - builtin_offset]
-            - &curr[MEMORY_ADDR_SORTED_2 - builtin_offset]
-            - &one);
-
-    constraints[MEMORY_INCREASING_3] = (&curr[MEMORY_ADDR_SORTED_3 - builtin_offset]
-        - &next[MEMORY_ADDR_SORTED_0 - builtin_offset])
-        * (&next[MEMORY_ADDR_SORTED_0 - builtin_offset]
-            - &curr[MEMORY_ADDR_SORTED_3 - builtin_offset]
-            - &one);
-
-    constraints[MEMORY_CONSISTENCY_0] = (&curr[MEMORY_VALUES_SORTED_0 - builtin_offset]
-        - &curr[MEMORY_VALUES_SORTED_1 - builtin_offset])
-        * (&curr[MEMORY_ADDR_SORTED_1 - builtin_offset]
-            - &curr[MEMORY_ADDR_SORTED_0 - builtin_offset]
-            - &one);
-
-    constraints[MEMORY_CONSISTENCY_1] = (&curr[MEMORY_VALUES_SORTED_1 - builtin_offset]
-        - &curr[MEMORY_VALUES_SORTED_2 - builtin_offset])
-        * (&curr[MEMORY_ADDR_SORTED_2 - builtin_offset]
-            - &curr[MEMORY_ADDR_SORTED_1 - builtin_offset]
-            - &one);
-
-    constraints[MEMORY_CONSISTENCY_2] = (&curr[MEMORY_VALUES_SORTED_2 - builtin_offset]
-        - &curr[MEMORY_VALUES_SORTED_3 - builtin_offset])
-        * (&curr[MEMORY_ADDR_SORTED_3 - builtin_offset]
-            - &curr[MEMORY_ADDR_SORTED_2 - builtin_offset]
-            - &one);
-
-    constraints[MEMORY_CONSISTENCY_3] = (&curr[MEMORY_VALUES_SORTED_3 - builtin_offset]
-        - &next[MEMORY_VALUES_SORTED_0 - builtin_offset])
-        * (&next[MEMORY_ADDR_SORTED_0 - builtin_offset]
-            - &curr[MEMORY_ADDR_SORTED_3 - builtin_offset]
-            - &one);
+    // Each sorted access is compared with the following one, and the last access of
+    // the row with the first one of the next row.
+    let addresses = [
+        &curr[layout.memory_addresses_sorted[0]],
+        &curr[layout.memory_addresses_sorted[1]],
+        &curr[layout.memory_addresses_sorted[2]],
+        &curr[layout.memory_addresses_sorted[3]],
+        &next[layout.memory_addresses_sorted[0]],
+    ];
+    let values = [
+        &curr[layout.memory_values_sorted[0]],
+        &curr[layout.memory_values_sorted[1]],
+        &curr[layout.memory_values_sorted[2]],
+        &curr[layout.memory_values_sorted[3]],
+        &next[layout.memory_values_sorted[0]],
+    ];
+    let increasing = [
+        MemoryIncreasing0,
+        MemoryIncreasing1,
+        MemoryIncreasing2,
+        MemoryIncreasing3,
+    ];
+    let consistency = [
+        MemoryConsistency0,
+        MemoryConsistency1,
+        MemoryConsistency2,
+        MemoryConsistency3,
+    ];
+
+    for (i, (increasing_id, consistency_id)) in increasing.into_iter().zip(consistency).enumerate()
+    {
+        // Zero exactly when the address increases by one, shared by both constraints
+        let address_gap = addresses[i + 1] - addresses[i] - &one;
+        constraints[increasing_id.index()] = (addresses[i] - addresses[i + 1]) * &address_gap;
+        constraints[consistency_id.index()] = (values[i] - values[i + 1]) * &address_gap;
+    }
 }
 
 fn permutation_argument(
     constraints: &mut [FE],
     frame: &Frame<Stark252PrimeField>,
     rap_challenges: &CairoRAPChallenges,
-    builtin_offset: usize,
+    layout: &TraceLayout,
 ) {
     let curr = frame.get_row(0);
     let next = frame.get_row(1);
     let z = &rap_challenges.z_memory;
     let alpha = &rap_challenges.alpha_memory;
 
-    let p0 = &curr[PERMUTATION_ARGUMENT_COL_0 - builtin_offset];
-    let p0_next = &next[PERMUTATION_ARGUMENT_COL_0 - builtin_offset];
-    let p1 = &curr[PERMUTATION_ARGUMENT_COL_1 - builtin_offset];
-    let p2 = &curr[PERMUTATION_ARGUMENT_COL_2 - builtin_offset];
-    let p3 = &curr[PERMUTATION_ARGUMENT_COL_3 - builtin_offset];
+    let p0 = &curr[layout.memory_permutation[0]];
+    let p0_next = &next[layout.memory_permutation[0]];
+    let p1 = &curr[layout.memory_permutation[1]];
+    let p2 = &curr[layout.memory_permutation[2]];
+    let p3 = &curr[layout.memory_permutation[3]];
 
-    let ap0_next = &next[MEMORY_ADDR_SORTED_0 - builtin_offset];
-    let ap1 = &curr[MEMORY_ADDR_SORTED_1 - builtin_offset];
-    let ap2 = &curr[MEMORY_ADDR_SORTED_2 - builtin_offset];
-    let ap3 = &curr[MEMORY_ADDR_SORTED_3 - builtin_offset];
+    let ap0_next = &next[layout.memory_addresses_sorted[0]];
+    let ap1 = &curr[layout.memory_addresses_sorted[1]];
+    let ap2 = &curr[layout.memory_addresses_sorted[2]];
+    let ap3 = &curr[layout.memory_addresses_sorted[3]];
 
-    let vp0_next = &next[MEMORY_VALUES_SORTED_0 - builtin_offset];
-    let vp1 = &curr[MEMORY_VALUES_SORTED_1 - builtin_offset];
-    let vp2 = &curr[MEMORY_VALUES_SORTED_2 - builtin_offset];
-    let vp3 = &curr[MEMORY_VALUES_SORTED_3 - builtin_offset];
+    let vp0_next = &next[layout.memory_values_sorted[0]];
+    let vp1 = &curr[layout.memory_values_sorted[1]];
+    let vp2 = &curr[layout.memory_values_sorted[2]];
+    let vp3 = &curr[layout.memory_values_sorted[3]];
 
     let a0_next = &next[FRAME_PC];
     let a1 = &curr[FRAME_DST_ADDR];
@@ -1079,13 +1561,13 @@ fn permutation_argument(
     let v2 = &curr[FRAME_OP0];
     let v3 = &curr[FRAME_OP1];
 
-    constraints[PERMUTATION_ARGUMENT_0] =
+    constraints[PermutationArgument0.index()] =
         (z - (ap1 + alpha * vp1)) * p1 - (z - (a1 + alpha * v1)) * p0;
-    constraints[PERMUTATION_ARGUMENT_1] =
+    constraints[PermutationArgument1.index()] =
         (z - (ap2 + alpha * vp2)) * p2 - (z - (a2 + alpha * v2)) * p1;
-    constraints[PERMUTATION_ARGUMENT_2] =
+    constraints[PermutationArgument2.index()] =
         (z - (ap3 + alpha * vp3)) * p3 - (z - (a3 + alpha * v3)) * p2;
-    constraints[PERMUTATION_ARGUMENT_3] =
+    constraints[PermutationArgument3.index()] =
         (z - (ap0_next + alpha * vp0_next)) * p0_next - (z - (a0_next + alpha * v0_next)) * p3;
 }
 
@@ -1093,45 +1575,45 @@ fn permutation_argument_range_check(
     constraints: &mut [FE],
     frame: &Frame<Stark252PrimeField>,
     rap_challenges: &CairoRAPChallenges,
-    builtin_offset: usize,
+    layout: &TraceLayout,
 ) {
     let curr = frame.get_row(0);
     let next = frame.get_row(1);
     let one = FieldElement::one();
     let z = &rap_challenges.z_range_check;
 
-    constraints[RANGE_CHECK_INCREASING_0] = (&curr[RANGE_CHECK_COL_1 - builtin_offset]
-        - &curr[RANGE_CHECK_COL_2 - builtin_offset])
-        * (&curr[RANGE_CHECK_COL_2 - builtin_offset]
-            - &curr[RANGE_CHECK_COL_1 - builtin_offset]
-            - &one);
-    constraints[RANGE_CHECK_INCREASING_1] = (&curr[RANGE_CHECK_COL_2 - builtin_offset]
-        - &curr[RANGE_CHECK_COL_3 - builtin_offset])
-        * (&curr[RANGE_CHECK_COL_3 - builtin_offset]
-            - &curr[RANGE_CHECK_COL_2 - builtin_offset]
-            - &one);
-    constraints[RANGE_CHECK_INCREASING_2] = (&curr[RANGE_CHECK_COL_3 - builtin_offset]
-        - &next[RANGE_CHECK_COL_1 - builtin_offset])
-        * (&next[RANGE_CHECK_COL_1 - builtin_offset]
-            - &curr[RANGE_CHECK_COL_3 - builtin_offset]
-            - &one);
-
-    let p0 = &curr[PERMUTATION_ARGUMENT_RANGE_CHECK_COL_1 - builtin_offset];
-    let p0_next = &next[PERMUTATION_ARGUMENT_RANGE_CHECK_COL_1 - builtin_offset];
-    let p1 = &curr[PERMUTATION_ARGUMENT_RANGE_CHECK_COL_2 - builtin_offset];
-    let p2 = &curr[PERMUTATION_ARGUMENT_RANGE_CHECK_COL_3 - builtin_offset];
-
-    let ap0_next = &next[RANGE_CHECK_COL_1 - builtin_offset];
-    let ap1 = &curr[RANGE_CHECK_COL_2 - builtin_offset];
-    let ap2 = &curr[RANGE_CHECK_COL_3 - builtin_offset];
+    let sorted_offsets = [
+        &curr[layout.range_check_sorted[0]],
+        &curr[layout.range_check_sorted[1]],
+        &curr[layout.range_check_sorted[2]],
+        &next[layout.range_check_sorted[0]],
+    ];
+    let increasing = [
+        RangeCheckIncreasing0,
+        RangeCheckIncreasing1,
+        RangeCheckIncreasing2,
+    ];
+    for (i, increasing_id) in increasing.into_iter().enumerate() {
+        constraints[increasing_id.index()] = (sorted_offsets[i] - sorted_offsets[i + 1])
+            * (sorted_offsets[i + 1] - sorted_offsets[i] - &one);
+    }
+
+    let p0 = &curr[layout.range_check_permutation[0]];
+    let p0_next = &next[layout.range_check_permutation[0]];
+    let p1 = &curr[layout.range_check_permutation[1]];
+    let p2 = &curr[layout.range_check_permutation[2]];
+
+    let ap0_next = &next[layout.range_check_sorted[0]];
+    let ap1 = &curr[layout.range_check_sorted[1]];
+    let ap2 = &curr[layout.range_check_sorted[2]];
 
     let a0_next = &next[OFF_DST];
     let a1 = &curr[OFF_OP0];
     let a2 = &curr[OFF_OP1];
 
-    constraints[RANGE_CHECK_0] = (z - ap1) * p1 - (z - a1) * p0;
-    constraints[RANGE_CHECK_1] = (z - ap2) * p2 - (z - a2) * p1;
-    constraints[RANGE_CHECK_2] = (z - ap0_next) * p0_next - (z - a0_next) * p2;
+    constraints[RangeCheck0.index()] = (z - ap1) * p1 - (z - a1) * p0;
+    constraints[RangeCheck1.index()] = (z - ap2) * p2 - (z - a2) * p1;
+    constraints[RangeCheck2.index()] = (z - ap0_next) * p0_next - (z - a0_next) * p2;
 }
 
 fn frame_inst_size(frame_row: &[FE]) -> FE {
@@ -1141,22 +1623,188 @@ fn frame_inst_size(frame_row: &[FE]) -> FE {
 fn range_check_builtin(
     constraints: &mut [FieldElement<Stark252PrimeField>],
     frame: &Frame<Stark252PrimeField>,
+    index: usize,
+    rc_builtin: &RangeCheckBuiltinColumns,
 ) {
     let curr = frame.get_row(0);
 
-    constraints[RANGE_CHECK_BUILTIN] = evaluate_range_check_builtin_constraint(curr)
+    constraints[index] = evaluate_range_check_builtin_constraint(curr, rc_builtin)
 }
 
-fn evaluate_range_check_builtin_constraint(curr: &[FE]) -> FE {
-    &curr[RC_0]
-        + &curr[RC_1] * &FE::from_hex("10000").unwrap()
-        + &curr[RC_2] * &FE::from_hex("100000000").unwrap()
-        + &curr[RC_3] * &FE::from_hex("1000000000000").unwrap()
-        + &curr[RC_4] * &FE::from_hex("10000000000000000").unwrap()
-        + &curr[RC_5] * &FE::from_hex("100000000000000000000").unwrap()
-        + &curr[RC_6] * &FE::from_hex("1000000000000000000000000").unwrap()
-        + &curr[RC_7] * &FE::from_hex("10000000000000000000000000000").unwrap()
-        - &curr[RC_VALUE]
+fn evaluate_range_check_builtin_constraint(
+    curr: &[FE],
+    rc_builtin: &RangeCheckBuiltinColumns,
+) -> FE {
+    // The packing of the parts, evaluated with Horner's rule
+    let base = FE::from(1_u64 << rc_builtin.layout.part_bits);
+    curr[rc_builtin.parts.clone()]
+        .iter()
+        .rev()
+        .fold(FE::zero(), |packing, part| packing * &base + part)
+        - &curr[rc_builtin.value]
+}
+
+/// Symbolic version of the constraints evaluated in `compute_transition`, used to
+/// export the constraint system. The challenges are `alpha_memory`, `z_memory`
+/// and `z_range_check`, in that order.
+fn symbolic_transition_constraints(layout: &TraceLayout) -> Vec<Expr<Stark252PrimeField>> {
+    type E = Expr<Stark252PrimeField>;
+    let cur = |col: usize| E::cell(0, col);
+    let next = |col: usize| E::cell(1, col);
+    let constant = |hex: &str| E::constant(FE::from_hex(hex).unwrap());
+    let one = E::one();
+    let two = constant("2");
+
+    let mut constraints: Vec<E> = (0..15).map(|flag| cur(flag) * (cur(flag) - &one)).collect();
+    constraints.push(cur(15));
+
+    // Main constraints, enabled by the selector
+    let f0_squiggle = (0..14)
+        .rev()
+        .fold(cur(14), |acc, flag| cur(flag) + &two * acc);
+    let inst = cur(OFF_DST)
+        + constant("10000") * cur(OFF_OP0)
+        + constant("100000000") * cur(OFF_OP1)
+        + constant("1000000000000") * f0_squiggle
+        - cur(FRAME_INST);
+
+    let ap = cur(FRAME_AP);
+    let fp = cur(FRAME_FP);
+    let pc = cur(FRAME_PC);
+    let b15 = constant("8000");
+    let dst_addr = cur(F_DST_FP) * &fp + (&one - cur(F_DST_FP)) * &ap + (cur(OFF_DST) - &b15)
+        - cur(FRAME_DST_ADDR);
+    let op0_addr = cur(F_OP_0_FP) * &fp + (&one - cur(F_OP_0_FP)) * &ap + (cur(OFF_OP0) - &b15)
+        - cur(FRAME_OP0_ADDR);
+    let op1_addr = cur(F_OP_1_VAL) * &pc
+        + cur(F_OP_1_AP) * &ap
+        + cur(F_OP_1_FP) * &fp
+        + (&one - cur(F_OP_1_VAL) - cur(F_OP_1_AP) - cur(F_OP_1_FP)) * cur(FRAME_OP0)
+        + (cur(OFF_OP1) - &b15)
+        - cur(FRAME_OP1_ADDR);
+
+    let inst_size = cur(F_OP_1_VAL) + &one;
+    let next_ap = &ap + cur(F_AP_ADD) * cur(FRAME_RES) + cur(F_AP_ONE) + cur(F_OPC_CALL) * &two
+        - next(FRAME_AP);
+    let next_fp = cur(F_OPC_RET) * cur(FRAME_DST)
+        + cur(F_OPC_CALL) * (&ap + &two)
+        + (&one - cur(F_OPC_RET) - cur(F_OPC_CALL)) * &fp
+        - next(FRAME_FP);
+    let next_pc_1 = (cur(FRAME_T1) - cur(F_PC_JNZ)) * (next(FRAME_PC) - (&pc + &inst_size));
+    let next_pc_2 = cur(FRAME_T0) * (next(FRAME_PC) - (&pc + cur(FRAME_OP1)))
+        + (&one - cur(F_PC_JNZ)) * next(FRAME_PC)
+        - ((&one - cur(F_PC_ABS) - cur(F_PC_REL) - cur(F_PC_JNZ)) * (&pc + &inst_size)
+            + cur(F_PC_ABS) * cur(FRAME_RES)
+            + cur(F_PC_REL) * (&pc + cur(FRAME_RES)));
+    let t0 = cur(F_PC_JNZ) * cur(FRAME_DST) - cur(FRAME_T0);
+    let t1 = cur(FRAME_T0) * cur(FRAME_RES) - cur(FRAME_T1);
+
+    let mul_1 = cur(FRAME_MUL) - cur(FRAME_OP0) * cur(FRAME_OP1);
+    let mul_2 = cur(F_RES_ADD) * (cur(FRAME_OP0) + cur(FRAME_OP1))
+        + cur(F_RES_MUL) * cur(FRAME_MUL)
+        + (&one - cur(F_RES_ADD) - cur(F_RES_MUL) - cur(F_PC_JNZ)) * cur(FRAME_OP1)
+        - (&one - cur(F_PC_JNZ)) * cur(FRAME_RES);
+    let call_1 = cur(F_OPC_CALL) * (cur(FRAME_DST) - &fp);
+    let call_2 = cur(F_OPC_CALL) * (cur(FRAME_OP0) - (&pc + &inst_size));
+    let assert_eq = cur(F_OPC_AEQ) * (cur(FRAME_DST) - cur(FRAME_RES));
+
+    constraints.extend(
+        [
+            inst, dst_addr, op0_addr, op1_addr, next_ap, next_fp, next_pc_1, next_pc_2, t0, t1,
+            mul_1, mul_2, call_1, call_2, assert_eq,
+        ]
+        .into_iter()
+        .map(|constraint| constraint * cur(FRAME_SELECTOR)),
+    );
+
+    // Auxiliary constraints
+    let continuity = |value: E, value_next: E| (&value - &value_next) * (value_next - value - &one);
+
+    let addresses = [
+        cur(layout.memory_addresses_sorted[0]),
+        cur(layout.memory_addresses_sorted[1]),
+        cur(layout.memory_addresses_sorted[2]),
+        cur(layout.memory_addresses_sorted[3]),
+        next(layout.memory_addresses_sorted[0]),
+    ];
+    let values = [
+        cur(layout.memory_values_sorted[0]),
+        cur(layout.memory_values_sorted[1]),
+        cur(layout.memory_values_sorted[2]),
+        cur(layout.memory_values_sorted[3]),
+        next(layout.memory_values_sorted[0]),
+    ];
+    for i in 0..4 {
+        constraints.push(continuity(addresses[i].clone(), addresses[i + 1].clone()));
+    }
+    for i in 0..4 {
+        constraints
+            .push((&values[i] - &values[i + 1]) * (&addresses[i + 1] - &addresses[i] - &one));
+    }
+
+    let alpha = E::challenge(0);
+    let z = E::challenge(1);
+    let permutation = [
+        cur(layout.memory_permutation[0]),
+        cur(layout.memory_permutation[1]),
+        cur(layout.memory_permutation[2]),
+        cur(layout.memory_permutation[3]),
+        next(layout.memory_permutation[0]),
+    ];
+    let accesses = [
+        (cur(FRAME_DST_ADDR), cur(FRAME_DST)),
+        (cur(FRAME_OP0_ADDR), cur(FRAME_OP0)),
+        (cur(FRAME_OP1_ADDR), cur(FRAME_OP1)),
+        (next(FRAME_PC), next(FRAME_INST)),
+    ];
+    for (i, (address, value)) in accesses.into_iter().enumerate() {
+        constraints.push(
+            (&z - (&addresses[i + 1] + &alpha * &values[i + 1])) * &permutation[i + 1]
+                - (&z - (address + &alpha * value)) * &permutation[i],
+        );
+    }
+
+    let offsets_sorted = [
+        cur(layout.range_check_sorted[0]),
+        cur(layout.range_check_sorted[1]),
+        cur(layout.range_check_sorted[2]),
+        next(layout.range_check_sorted[0]),
+    ];
+    for i in 0..3 {
+        constraints.push(continuity(
+            offsets_sorted[i].clone(),
+            offsets_sorted[i + 1].clone(),
+        ));
+    }
+
+    let z = E::challenge(2);
+    let permutation = [
+        cur(layout.range_check_permutation[0]),
+        cur(layout.range_check_permutation[1]),
+        cur(layout.range_check_permutation[2]),
+        next(layout.range_check_permutation[0]),
+    ];
+    let offsets = [cur(OFF_OP0), cur(OFF_OP1), next(OFF_DST)];
+    for (i, offset) in offsets.into_iter().enumerate() {
+        constraints.push(
+            (&z - &offsets_sorted[i + 1]) * &permutation[i + 1] - (&z - offset) * &permutation[i],
+        );
+    }
+
+    for rc_builtin in &layout.range_check_builtins {
+        let base = FE::from(1_u64 << rc_builtin.layout.part_bits);
+        let value = rc_builtin
+            .parts
+            .clone()
+            .enumerate()
+            .skip(1)
+            .fold(cur(rc_builtin.parts.start), |acc, (i, part)| {
+                acc + cur(part) * E::constant(base.pow(i))
+            });
+        constraints.push(value - cur(rc_builtin.value));
+    }
+
+    constraints
 }
 
 /// Wrapper function for generating Cairo proofs without the need to specify
@@ -1167,7 +1815,60 @@ pub fn generate_cairo_proof(
     pub_input: &PublicInputs,
     proof_options: &ProofOptions,
 ) -> Result<StarkProof<Stark252PrimeField>, ProvingError> {
-    prove::<Stark252PrimeField, CairoAIR>(trace, pub_input, proof_options)
+    generate_cairo_proof_with_prover(&CpuProver, trace, pub_input, proof_options)
+}
+
+/// Same as `generate_cairo_proof`, but proving the trace with the given backend.
+pub fn generate_cairo_proof_with_prover<P: IsStarkProver>(
+    prover: &P,
+    trace: &TraceTable<Stark252PrimeField>,
+    pub_input: &PublicInputs,
+    proof_options: &ProofOptions,
+) -> Result<StarkProof<Stark252PrimeField>, ProvingError> {
+    check_proving_args(trace, pub_input, proof_options)?;
+    prover.prove::<Stark252PrimeField, CairoAIR>(trace, pub_input, proof_options)
+}
+
+/// Same as `generate_cairo_proof`, but also returning the metrics of each round of
+/// the prover. See `prove_with_metrics`.
+pub fn generate_cairo_proof_with_metrics(
+    trace: &TraceTable<Stark252PrimeField>,
+    pub_input: &PublicInputs,
+    proof_options: &ProofOptions,
+) -> Result<(StarkProof<Stark252PrimeField>, ProverMetrics), ProvingError> {
+    check_proving_args(trace, pub_input, proof_options)?;
+    prove_with_metrics::<Stark252PrimeField, CairoAIR>(trace, pub_input, proof_options)
+}
+
+/// Same as `generate_cairo_proof`, but with `prove_recursive_friendly`, for a
+/// verifier written in Cairo.
+pub fn generate_cairo_proof_recursive_friendly(
+    trace: &TraceTable<Stark252PrimeField>,
+    pub_input: &PublicInputs,
+    proof_options: &ProofOptions,
+) -> Result<RecursionFriendlyProof, ProvingError> {
+    check_proving_args(trace, pub_input, proof_options)?;
+    prove_recursive_friendly::<CairoAIR>(trace, pub_input, proof_options)
+}
+
+fn check_proving_args(
+    trace: &TraceTable<Stark252PrimeField>,
+    pub_input: &PublicInputs,
+    proof_options: &ProofOptions,
+) -> Result<(), ProvingError> {
+    pub_input
+        .check_trace_consistency(trace)
+        .map_err(|error| ProvingError::WrongParameter(error.to_string()))?;
+
+    // The final state and the memory permutation arguments are constrained on the
+    // last row of the trace, which would be a random one.
+    if proof_options.randomizes_trace() {
+        return Err(ProvingError::WrongParameter(
+            "trace randomization rows are not supported by the Cairo AIR".to_string(),
+        ));
+    }
+
+    Ok(())
 }
 
 /// Wrapper function for verifying Cairo proofs without the need to specify
@@ -1178,7 +1879,80 @@ pub fn verify_cairo_proof(
     pub_input: &PublicInputs,
     proof_options: &ProofOptions,
 ) -> bool {
-    verify::<Stark252PrimeField, CairoAIR>(proof, pub_input, proof_options)
+    verify_cairo_proof_with_address_space(
+        proof,
+        pub_input,
+        proof_options,
+        DEFAULT_ADDRESS_SPACE_BITS,
+    )
+}
+
+/// Same as `verify_cairo_proof`, but telling why the proof was rejected.
+pub fn try_verify_cairo_proof(
+    proof: &StarkProof<Stark252PrimeField>,
+    pub_input: &PublicInputs,
+    proof_options: &ProofOptions,
+) -> Result<(), VerificationError> {
+    if !check_public_inputs(proof, pub_input, DEFAULT_ADDRESS_SPACE_BITS) {
+        return Err(VerificationError::InvalidPublicInputs);
+    }
+    try_verify::<Stark252PrimeField, CairoAIR>(proof, pub_input, proof_options)
+}
+
+/// Same as `verify_cairo_proof`, but with the options the proof was generated
+/// with, if the policy accepts them.
+pub fn verify_cairo_proof_with_policy(
+    proof: &StarkProof<Stark252PrimeField>,
+    pub_input: &PublicInputs,
+    policy: &ProofOptionsPolicy,
+) -> bool {
+    if let Err(error) = policy.check(&proof.options, proof.trace_length) {
+        error!(%error, "The options of the proof are not accepted");
+        return false;
+    }
+
+    verify_cairo_proof(proof, pub_input, &proof.options)
+}
+
+/// Same as `verify_cairo_proof`, but bounding the memory addresses and registers
+/// to `2^address_space_bits` instead of `2^DEFAULT_ADDRESS_SPACE_BITS`.
+pub fn verify_cairo_proof_with_address_space(
+    proof: &StarkProof<Stark252PrimeField>,
+    pub_input: &PublicInputs,
+    proof_options: &ProofOptions,
+    address_space_bits: u32,
+) -> bool {
+    check_public_inputs(proof, pub_input, address_space_bits)
+        && verify::<Stark252PrimeField, CairoAIR>(proof, pub_input, proof_options)
+}
+
+/// Verifies a proof generated by `generate_cairo_proof_recursive_friendly` with the
+/// same options.
+pub fn verify_cairo_proof_recursive_friendly(
+    proof: &StarkProof<Stark252PrimeField>,
+    pub_input: &PublicInputs,
+    proof_options: &ProofOptions,
+) -> bool {
+    check_public_inputs(proof, pub_input, DEFAULT_ADDRESS_SPACE_BITS)
+        && verify_recursive_friendly::<CairoAIR>(proof, pub_input, proof_options)
+}
+
+fn check_public_inputs(
+    proof: &StarkProof<Stark252PrimeField>,
+    pub_input: &PublicInputs,
+    address_space_bits: u32,
+) -> bool {
+    if !pub_input.check_address_bounds(address_space_bits) {
+        error!("Public inputs have addresses out of the address space");
+        return false;
+    }
+
+    if pub_input.check_memory_padding(proof.trace_length).is_err() {
+        error!("Public inputs have memory padding that doesn't fit in the trace");
+        return false;
+    }
+
+    true
 }
 
 #[cfg(test)]
@@ -1186,7 +1960,11 @@ pub fn verify_cairo_proof(
 mod test {
     use crate::{
         cairo::runner::run::{cairo0_program_path, generate_prover_args, CairoVersion},
-        starks::{debug::validate_trace, domain::Domain},
+        starks::{
+            constraints::{compiled::CompiledConstraints, snapshot::ConstraintSnapshot},
+            debug::validate_trace,
+            domain::Domain,
+        },
     };
     use proptest::{prelude::*, prop_compose, proptest};
 
@@ -1196,23 +1974,36 @@ mod test {
 
     #[test]
     fn range_check_eval_works() {
-        let mut row: Vec<FE> = Vec::new();
+        let layout = TraceLayout::new(&MemorySegmentMap::from([(MemorySegment::RangeCheck, 0..1)]));
+        let rc_builtin = &layout.range_check_builtins[0];
+        let mut row = vec![FE::zero(); layout.num_columns()];
 
-        for _ in 0..61 {
-            row.push(FE::zero());
+        for part in rc_builtin.parts.clone() {
+            row[part] = FE::one();
         }
 
-        row[super::RC_0] = FE::one();
-        row[super::RC_1] = FE::one();
-        row[super::RC_2] = FE::one();
-        row[super::RC_3] = FE::one();
-        row[super::RC_4] = FE::one();
-        row[super::RC_5] = FE::one();
-        row[super::RC_6] = FE::one();
-        row[super::RC_7] = FE::one();
+        row[rc_builtin.value] = FE::from_hex("00010001000100010001000100010001").unwrap();
+        assert_eq!(
+            evaluate_range_check_builtin_constraint(&row, rc_builtin),
+            FE::zero()
+        );
 
-        row[super::RC_VALUE] = FE::from_hex("00010001000100010001000100010001").unwrap();
-        assert_eq!(evaluate_range_check_builtin_constraint(&row), FE::zero());
+        // The range-check96 builtin has six parts, so its values go in the column of the
+        // seventh part of the range-check builtin
+        let layout = TraceLayout::new(&MemorySegmentMap::from([(
+            MemorySegment::RangeCheck96,
+            0..1,
+        )]));
+        let rc96_builtin = &layout.range_check_builtins[0];
+        assert_ne!(
+            evaluate_range_check_builtin_constraint(&row, rc96_builtin),
+            FE::zero()
+        );
+        row[rc96_builtin.value] = FE::from_hex("000100010001000100010001").unwrap();
+        assert_eq!(
+            evaluate_range_check_builtin_constraint(&row, rc96_builtin),
+            FE::zero()
+        );
     }
 
     #[test]
@@ -1227,7 +2018,7 @@ mod test {
         let cairo_air = CairoAIR::new(main_trace.n_rows(), &public_input, &proof_options);
         let rap_challenges = cairo_air.build_rap_challenges(&mut transcript);
 
-        let aux_trace = cairo_air.build_auxiliary_trace(&main_trace, &rap_challenges);
+        let aux_trace = cairo_air.build_auxiliary_trace(0, &main_trace, &rap_challenges);
         let aux_polys = aux_trace.compute_trace_polys();
 
         trace_polys.extend_from_slice(&aux_polys);
@@ -1242,6 +2033,141 @@ mod test {
         ));
     }
 
+    #[test]
+    fn symbolic_constraints_match_the_evaluated_ones() {
+        for program in ["simple_program.json", "rc_program.json"] {
+            let program_content = std::fs::read(cairo0_program_path(program)).unwrap();
+            let (main_trace, public_input) =
+                generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+            let proof_options = ProofOptions::default_test_options();
+            let cairo_air = CairoAIR::new(main_trace.n_rows(), &public_input, &proof_options);
+
+            // The constraints are compared over random values, not over a valid trace
+            let n_cols = cairo_air.context().trace_columns;
+            let frame = Frame::new(
+                (0..2 * n_cols)
+                    .map(|_| FE::from(rand::random::<u64>()))
+                    .collect(),
+                n_cols,
+            );
+            let challenges: Vec<FE> = (0..3).map(|_| FE::from(rand::random::<u64>())).collect();
+            let rap_challenges = CairoRAPChallenges {
+                alpha_memory: challenges[0],
+                z_memory: challenges[1],
+                z_range_check: challenges[2],
+            };
+
+            let evaluations = cairo_air.compute_transition(&frame, &rap_challenges);
+            let symbolic_evaluations: Vec<FE> = cairo_air
+                .symbolic_transition()
+                .unwrap()
+                .iter()
+                .map(|constraint| constraint.evaluate(&frame, &challenges))
+                .collect();
+
+            assert_eq!(evaluations, symbolic_evaluations);
+
+            // The declared degrees are upper bounds of the actual ones
+            let inferred_degrees =
+                CompiledConstraints::new(cairo_air.symbolic_transition().unwrap()).degrees();
+            for (inferred, declared) in inferred_degrees
+                .iter()
+                .zip(&cairo_air.context().transition_degrees)
+            {
+                assert!(inferred <= declared);
+            }
+        }
+    }
+
+    #[test]
+    fn deep_composition_leaves_out_unreferenced_cells_of_the_next_row() {
+        let program_content = std::fs::read(cairo0_program_path("simple_program.json")).unwrap();
+        let (main_trace, public_input) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof_options = ProofOptions::default_test_options();
+        let cairo_air = CairoAIR::new(main_trace.n_rows(), &public_input, &proof_options);
+
+        let n_cols = cairo_air.committed_columns().len();
+        let deep_trace_terms = cairo_air.deep_trace_terms();
+        assert!(deep_trace_terms.len() < 2 * n_cols);
+        assert!((0..n_cols).all(|col| deep_trace_terms.contains(&(0, col))));
+        assert!(deep_trace_terms.contains(&(1, FRAME_PC)));
+    }
+
+    #[test]
+    fn public_parameters_bind_every_public_input() {
+        let program_content = std::fs::read(cairo0_program_path("simple_program.json")).unwrap();
+        let (main_trace, public_input) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof_options = ProofOptions::default_test_options();
+        let parameters = |public_input: &PublicInputs| {
+            CairoAIR::new(main_trace.n_rows(), public_input, &proof_options).public_parameters()
+        };
+
+        let mut other_program = public_input.clone();
+        let instruction = other_program.public_memory.get_mut(&FE::one()).unwrap();
+        *instruction = *instruction + FE::one();
+        let mut other_steps = public_input.clone();
+        other_steps.num_steps += 1;
+        let mut other_registers = public_input.clone();
+        other_registers.fp_init = other_registers.fp_init + FE::one();
+
+        let expected = parameters(&public_input);
+        for other in [other_program, other_steps, other_registers] {
+            assert_ne!(parameters(&other), expected);
+        }
+    }
+
+    #[test]
+    fn the_instruction_is_a_virtual_column() {
+        let program_content = std::fs::read(cairo0_program_path("simple_program.json")).unwrap();
+        let (main_trace, public_input) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof_options = ProofOptions::default_test_options();
+        let cairo_air = CairoAIR::new(main_trace.n_rows(), &public_input, &proof_options);
+
+        let committed_columns = cairo_air.committed_columns();
+        assert_eq!(
+            committed_columns.len(),
+            cairo_air.context().trace_columns - 1
+        );
+        assert_eq!(committed_columns.position(FRAME_INST), None);
+
+        // The instructions of the trace are reconstructed from the committed columns
+        let virtual_columns = cairo_air.virtual_columns();
+        let [instruction] = &virtual_columns[..] else {
+            panic!("the instruction is the only virtual column");
+        };
+        for row in 0..main_trace.n_rows() {
+            let row = main_trace.get_row(row);
+            assert_eq!(instruction.evaluate(row), row[FRAME_INST]);
+        }
+    }
+
+    #[test]
+    fn constraint_snapshot_describes_every_constraint() {
+        let program_content = std::fs::read(cairo0_program_path("simple_program.json")).unwrap();
+        let (main_trace, public_input) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof_options = ProofOptions::default_test_options();
+        let cairo_air = CairoAIR::new(main_trace.n_rows(), &public_input, &proof_options);
+
+        let snapshot = ConstraintSnapshot::from_air(&cairo_air).unwrap();
+        assert_eq!(
+            snapshot.constraints.len(),
+            cairo_air.num_transition_constraints()
+        );
+        assert_eq!(
+            snapshot.constraints[AssertEq.index()].name,
+            Some("assert_eq")
+        );
+        assert_eq!(snapshot.constraints[AssertEq.index()].degree, 3);
+        assert!(snapshot
+            .to_text()
+            .contains("[30] assert_eq (degree 3, exemptions 0)"));
+        assert!(snapshot.to_json().contains("z_memory"));
+    }
+
     #[test]
     fn test_build_auxiliary_trace_add_program_in_public_input_section_works() {
         let dummy_public_input = PublicInputs {
@@ -1257,7 +2183,12 @@ mod test {
             ]),
             range_check_max: None,
             range_check_min: None,
+            memory_address_max: None,
             num_steps: 1,
+            memory_padding: MemoryPadding::default(),
+            num_memory_holes: 0,
+            memory_commitment: None,
+            builtins: Vec::new(),
             memory_segments: MemorySegmentMap::new(),
         };
 
@@ -1319,7 +2250,12 @@ mod test {
             ]),
             range_check_max: None,
             range_check_min: None,
+            memory_address_max: None,
             num_steps: 1,
+            memory_padding: MemoryPadding::default(),
+            num_memory_holes: 0,
+            memory_commitment: None,
+            builtins: Vec::new(),
             memory_segments: MemorySegmentMap::from([(MemorySegment::Output, 20..22)]),
         };
 
@@ -1407,6 +2343,16 @@ mod test {
         );
     }
 
+    #[test]
+    fn sort_columns_by_memory_address_keeps_the_order_of_equal_addresses() {
+        let a = vec![FE::from(5), FE::from(3), FE::from(5), FE::from(3)];
+        let v = vec![FE::from(1), FE::from(2), FE::from(3), FE::from(4)];
+
+        let (ap, vp) = sort_columns_by_memory_address(a, v);
+        assert_eq!(ap, [3_u64, 3, 5, 5].map(FE::from));
+        assert_eq!(vp, [2_u64, 4, 1, 3].map(FE::from));
+    }
+
     #[test]
     fn test_build_auxiliary_trace_generate_permutation_argument_column() {
         let a = vec![
@@ -1451,6 +2397,47 @@ mod test {
         );
     }
 
+    #[test]
+    fn memory_segments_of_unsupported_builtins_are_skipped_when_deserializing() {
+        let public_inputs = |builtins: &[&str]| PublicInputs {
+            pc_init: FE::one(),
+            ap_init: FE::one(),
+            fp_init: FE::one(),
+            pc_final: FE::one(),
+            ap_final: FE::one(),
+            public_memory: HashMap::new(),
+            range_check_max: None,
+            range_check_min: None,
+            memory_address_max: None,
+            num_steps: 1,
+            memory_padding: MemoryPadding::default(),
+            num_memory_holes: 0,
+            memory_commitment: None,
+            builtins: builtins.iter().map(|name| name.to_string()).collect(),
+            memory_segments: MemorySegmentMap::from([(MemorySegment::Output, 20..22)]),
+        };
+        // Replaces the output segment by one of a builtin unknown to this verifier
+        let with_unknown_segment = |public_inputs: PublicInputs| {
+            let mut bytes = public_inputs.serialize();
+            let segment = [[1].as_slice(), &20u64.to_be_bytes(), &22u64.to_be_bytes()].concat();
+            let position = bytes
+                .windows(segment.len())
+                .position(|window| window == segment)
+                .unwrap();
+            bytes[position] = 7;
+            bytes
+        };
+
+        let bytes = with_unknown_segment(public_inputs(&["output", "bitwise"]));
+        let deserialized = PublicInputs::deserialize(&bytes).unwrap();
+        assert!(deserialized.memory_segments.is_empty());
+        assert_eq!(deserialized.unsupported_builtins(), ["bitwise"]);
+        assert!(CairoAIR::check_configuration(&deserialized).is_err());
+
+        let bytes = with_unknown_segment(public_inputs(&["output"]));
+        assert!(PublicInputs::deserialize(&bytes).is_err());
+    }
+
     prop_compose! {
         fn some_felt()(base in any::<u64>(), exponent in any::<u128>()) -> FE {
             FE::from(base).pow(exponent)
@@ -1467,7 +2454,13 @@ mod test {
             public_memory in proptest::collection::hash_map(any::<u64>(), any::<u64>(), (8_usize, 16_usize)),
             range_check_max in proptest::option::of(any::<u16>()),
             range_check_min in proptest::option::of(any::<u16>()),
+            memory_address_max in proptest::option::of(any::<u64>()),
             num_steps in any::<usize>(),
+            dummy_accesses in any::<usize>(),
+            padding_rows in any::<usize>(),
+            num_memory_holes in any::<usize>(),
+            builtins in proptest::collection::vec("[a-z_0-9]{1,16}", 0..4),
+            memory_commitment in proptest::option::of(any::<[u8; 32]>()),
         ) -> PublicInputs {
             let public_memory = public_memory.iter().map(|(k, v)| (FE::from(*k), FE::from(*v))).collect();
             let memory_segments = MemorySegmentMap::from([(MemorySegment::Output, 10u64..16u64), (MemorySegment::RangeCheck, 20u64..71u64)]);
@@ -1480,8 +2473,13 @@ mod test {
                 public_memory,
                 range_check_max,
                 range_check_min,
+                memory_address_max,
                 num_steps,
                 memory_segments,
+                memory_padding: MemoryPadding { dummy_accesses, padding_rows },
+                num_memory_holes,
+                builtins,
+                memory_commitment,
             }
         }
     }
@@ -1502,8 +2500,15 @@ mod test {
             prop_assert_eq!(public_inputs.public_memory, deserialized.public_memory);
             prop_assert_eq!(public_inputs.range_check_max, deserialized.range_check_max);
             prop_assert_eq!(public_inputs.range_check_min, deserialized.range_check_min);
+            prop_assert_eq!(public_inputs.memory_address_max, deserialized.memory_address_max);
             prop_assert_eq!(public_inputs.num_steps, deserialized.num_steps);
             prop_assert_eq!(public_inputs.memory_segments, deserialized.memory_segments);
+            prop_assert_eq!(public_inputs.memory_padding, deserialized.memory_padding);
+            prop_assert_eq!(public_inputs.num_memory_holes, deserialized.num_memory_holes);
+            prop_assert_eq!(public_inputs.builtins, deserialized.builtins);
+            prop_assert_eq!(public_inputs.memory_commitment, deserialized.memory_commitment);
+            // The public memory is written in the same order for equal public inputs
+            prop_assert_eq!(deserialized.serialize(), serialized);
         }
     }
 }
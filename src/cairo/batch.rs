@@ -0,0 +1,198 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use thiserror::Error;
+
+use super::air::{generate_cairo_proof, PublicInputs};
+use super::runner::program::CairoProgram;
+use super::runner::run::Error as RunnerError;
+use crate::starks::proof::{options::ProofOptions, stark::StarkProof};
+use crate::starks::prover::ProvingError;
+
+#[derive(Debug, Error)]
+pub enum BatchError {
+    #[error("Failed to run the program: {0}")]
+    Execution(#[from] RunnerError),
+    #[error("Failed to prove the program: {0:?}")]
+    Proving(ProvingError),
+    #[error("Running or proving the program panicked: {0}")]
+    Panicked(String),
+}
+
+/// Result of proving one of the programs of a batch.
+#[derive(Debug)]
+pub struct ProgramProof {
+    pub program: PathBuf,
+    pub result: Result<(StarkProof<Stark252PrimeField>, PublicInputs), BatchError>,
+    /// Time spent running the program and building its trace.
+    pub execution_time: Duration,
+    /// Time spent proving the program. Zero if its execution failed.
+    pub proving_time: Duration,
+}
+
+/// Aggregated report of a batch of proofs.
+#[derive(Debug)]
+pub struct BatchReport {
+    /// Proofs of the programs, in the order they were given.
+    pub proofs: Vec<ProgramProof>,
+    /// Wall-clock time spent proving the whole batch.
+    pub total_time: Duration,
+}
+
+impl BatchReport {
+    pub fn num_proven(&self) -> usize {
+        self.proofs
+            .iter()
+            .filter(|proof| proof.result.is_ok())
+            .count()
+    }
+
+    pub fn num_failed(&self) -> usize {
+        self.proofs.len() - self.num_proven()
+    }
+
+    /// Time spent proving the programs, added over all the workers.
+    pub fn total_proving_time(&self) -> Duration {
+        self.proofs.iter().map(|proof| proof.proving_time).sum()
+    }
+
+    /// Number of programs proven per second of wall-clock time.
+    pub fn throughput(&self) -> f64 {
+        self.num_proven() as f64 / self.total_time.as_secs_f64()
+    }
+}
+
+/// Runs and proves the given compiled Cairo programs, using one worker thread per
+/// available core. See `prove_many_with_workers`.
+pub fn prove_many(programs: &[PathBuf], proof_options: &ProofOptions) -> BatchReport {
+    let num_workers = thread::available_parallelism()
+        .map(usize::from)
+        .unwrap_or(1);
+    prove_many_with_workers(programs, proof_options, num_workers)
+}
+
+/// Runs and proves the given compiled Cairo programs in `num_workers` threads.
+/// Each worker takes the next pending program until all of them are proven, so
+/// programs of very different sizes are balanced between the workers. A failure
+/// to prove one of the programs, even a panic, doesn't stop the rest of the batch.
+///
+/// The workers share the roots of unity of the domains, so they are computed once
+/// for all the programs with traces of the same length. See `roots_of_unity`.
+pub fn prove_many_with_workers(
+    programs: &[PathBuf],
+    proof_options: &ProofOptions,
+    num_workers: usize,
+) -> BatchReport {
+    let timer = Instant::now();
+    let next_program = AtomicUsize::new(0);
+
+    let mut proofs: Vec<(usize, ProgramProof)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..num_workers.clamp(1, programs.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut proofs = Vec::new();
+                    loop {
+                        let index = next_program.fetch_add(1, Ordering::Relaxed);
+                        let Some(program) = programs.get(index) else {
+                            return proofs;
+                        };
+                        proofs.push((index, prove_program(program, proof_options)));
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("batch proving worker panicked"))
+            .collect()
+    });
+    proofs.sort_by_key(|(index, _)| *index);
+
+    BatchReport {
+        proofs: proofs.into_iter().map(|(_, proof)| proof).collect(),
+        total_time: timer.elapsed(),
+    }
+}
+
+fn prove_program(program: &Path, proof_options: &ProofOptions) -> ProgramProof {
+    let timer = Instant::now();
+    let prover_args = catch_panic(|| {
+        CairoProgram::from_file(program)
+            .and_then(|cairo_program| cairo_program.generate_prover_args(&None))
+            .map_err(BatchError::Execution)
+    });
+    let execution_time = timer.elapsed();
+
+    let timer = Instant::now();
+    let result = prover_args.and_then(|(main_trace, pub_inputs)| {
+        catch_panic(|| {
+            generate_cairo_proof(&main_trace, &pub_inputs, proof_options)
+                .map(|proof| (proof, pub_inputs))
+                .map_err(BatchError::Proving)
+        })
+    });
+    let proving_time = timer.elapsed();
+
+    ProgramProof {
+        program: program.to_path_buf(),
+        result,
+        execution_time,
+        proving_time,
+    }
+}
+
+/// Runs `f`, turning a panic into a `BatchError::Panicked` so it only fails the
+/// program it was running.
+fn catch_panic<T>(f: impl FnOnce() -> Result<T, BatchError>) -> Result<T, BatchError> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err(BatchError::Panicked(message))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cairo::air::verify_cairo_proof;
+    use crate::cairo::runner::run::cairo0_program_path;
+
+    #[test]
+    fn batch_proofs_are_reported_in_the_order_of_the_programs() {
+        let programs = [
+            PathBuf::from(cairo0_program_path("fibonacci_5.json")),
+            PathBuf::from(cairo0_program_path("missing_program.json")),
+            PathBuf::from(cairo0_program_path("simple_program.json")),
+        ];
+        let proof_options = ProofOptions::default_test_options();
+
+        let report = prove_many_with_workers(&programs, &proof_options, 2);
+
+        assert_eq!(report.num_proven(), 2);
+        assert_eq!(report.num_failed(), 1);
+        for (program, program_proof) in programs.iter().zip(&report.proofs) {
+            assert_eq!(&program_proof.program, program);
+        }
+        assert!(matches!(
+            report.proofs[1].result,
+            Err(BatchError::Execution(RunnerError::IO(_)))
+        ));
+
+        let (proof, pub_inputs) = report.proofs[0].result.as_ref().unwrap();
+        assert!(verify_cairo_proof(proof, pub_inputs, &proof_options));
+    }
+
+    #[test]
+    fn panics_are_reported_as_failures() {
+        let result = catch_panic::<()>(|| panic!("out of memory"));
+        assert!(matches!(result, Err(BatchError::Panicked(message)) if message == "out of memory"));
+    }
+}
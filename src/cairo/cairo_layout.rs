@@ -1,3 +1,5 @@
+use super::air::MemorySegment;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CairoLayout {
     Plain,
@@ -28,3 +30,175 @@ impl CairoLayout {
         }
     }
 }
+
+impl CairoLayout {
+    /// Builtins available in the layout.
+    pub fn builtins(&self) -> &'static [CairoBuiltin] {
+        use CairoBuiltin::*;
+        match self {
+            CairoLayout::Plain => &[],
+            CairoLayout::Small | CairoLayout::Dex => &[Output, Pedersen, RangeCheck, Ecdsa],
+            CairoLayout::Recursive => &[Output, Pedersen, RangeCheck, Bitwise],
+            CairoLayout::Starknet => {
+                &[Output, Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp, Poseidon]
+            }
+            CairoLayout::StarknetWithKeccak => &[
+                Output, Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp, Keccak, Poseidon,
+            ],
+            CairoLayout::RecursiveLargeOutput => &[Output, Pedersen, RangeCheck, Bitwise, Poseidon],
+            CairoLayout::AllSolidity => &[Output, Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp],
+            CairoLayout::AllCairo | CairoLayout::Dynamic => &[
+                Output,
+                Pedersen,
+                RangeCheck,
+                Ecdsa,
+                Bitwise,
+                EcOp,
+                Keccak,
+                Poseidon,
+                RangeCheck96,
+            ],
+        }
+    }
+
+    pub fn supports(&self, builtin: CairoBuiltin) -> bool {
+        self.builtins().contains(&builtin)
+    }
+
+    /// Chooses a layout supporting all of the given builtins, preferring the
+    /// small layout. Returns `None` if there is no such layout.
+    pub fn for_builtins(builtins: &[CairoBuiltin]) -> Option<Self> {
+        [
+            CairoLayout::Small,
+            CairoLayout::Recursive,
+            CairoLayout::Starknet,
+            CairoLayout::StarknetWithKeccak,
+            CairoLayout::AllCairo,
+        ]
+        .into_iter()
+        .find(|layout| builtins.iter().all(|builtin| layout.supports(*builtin)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CairoBuiltin {
+    Output,
+    Pedersen,
+    RangeCheck,
+    Ecdsa,
+    Bitwise,
+    EcOp,
+    Keccak,
+    Poseidon,
+    RangeCheck96,
+}
+
+impl CairoBuiltin {
+    pub const ALL: [CairoBuiltin; 9] = [
+        CairoBuiltin::Output,
+        CairoBuiltin::Pedersen,
+        CairoBuiltin::RangeCheck,
+        CairoBuiltin::Ecdsa,
+        CairoBuiltin::Bitwise,
+        CairoBuiltin::EcOp,
+        CairoBuiltin::Keccak,
+        CairoBuiltin::Poseidon,
+        CairoBuiltin::RangeCheck96,
+    ];
+
+    /// Builtins with a memory segment handled by the Cairo AIR.
+    pub const PROVABLE: [CairoBuiltin; 3] = [
+        CairoBuiltin::Output,
+        CairoBuiltin::RangeCheck,
+        CairoBuiltin::RangeCheck96,
+    ];
+
+    /// Parses the name of the builtin, as declared in the `builtins` list of a compiled program.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let builtin = match name {
+            "output" => CairoBuiltin::Output,
+            "pedersen" => CairoBuiltin::Pedersen,
+            "range_check" => CairoBuiltin::RangeCheck,
+            "ecdsa" => CairoBuiltin::Ecdsa,
+            "bitwise" => CairoBuiltin::Bitwise,
+            "ec_op" => CairoBuiltin::EcOp,
+            "keccak" => CairoBuiltin::Keccak,
+            "poseidon" => CairoBuiltin::Poseidon,
+            "range_check96" => CairoBuiltin::RangeCheck96,
+            _ => return None,
+        };
+        Some(builtin)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CairoBuiltin::Output => "output",
+            CairoBuiltin::Pedersen => "pedersen",
+            CairoBuiltin::RangeCheck => "range_check",
+            CairoBuiltin::Ecdsa => "ecdsa",
+            CairoBuiltin::Bitwise => "bitwise",
+            CairoBuiltin::EcOp => "ec_op",
+            CairoBuiltin::Keccak => "keccak",
+            CairoBuiltin::Poseidon => "poseidon",
+            CairoBuiltin::RangeCheck96 => "range_check96",
+        }
+    }
+
+    /// Name of the builtin runner in cairo-vm.
+    pub(crate) fn runner_name(&self) -> String {
+        format!("{}_builtin", self.as_str())
+    }
+
+    /// Memory segment of the builtin handled by the Cairo AIR, if any.
+    /// Builtins without one can't be proven yet.
+    pub fn memory_segment(&self) -> Option<MemorySegment> {
+        match self {
+            CairoBuiltin::Output => Some(MemorySegment::Output),
+            CairoBuiltin::RangeCheck => Some(MemorySegment::RangeCheck),
+            CairoBuiltin::RangeCheck96 => Some(MemorySegment::RangeCheck96),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_layout_is_preferred() {
+        assert_eq!(
+            CairoLayout::for_builtins(&[CairoBuiltin::Output, CairoBuiltin::RangeCheck]),
+            Some(CairoLayout::Small)
+        );
+        assert_eq!(CairoLayout::for_builtins(&[]), Some(CairoLayout::Small));
+    }
+
+    #[test]
+    fn layout_supporting_all_builtins_is_chosen() {
+        assert_eq!(
+            CairoLayout::for_builtins(&[CairoBuiltin::Bitwise, CairoBuiltin::Poseidon]),
+            Some(CairoLayout::Starknet)
+        );
+        assert_eq!(
+            CairoLayout::for_builtins(&[CairoBuiltin::Keccak]),
+            Some(CairoLayout::StarknetWithKeccak)
+        );
+    }
+
+    #[test]
+    fn builtin_names_roundtrip() {
+        for builtin in CairoLayout::AllCairo.builtins() {
+            assert_eq!(CairoBuiltin::from_name(builtin.as_str()), Some(*builtin));
+        }
+        assert_eq!(CairoBuiltin::from_name("segment_arena"), None);
+    }
+
+    #[test]
+    fn range_check96_builtin_needs_a_newer_layout() {
+        assert_eq!(
+            CairoLayout::for_builtins(&[CairoBuiltin::RangeCheck, CairoBuiltin::RangeCheck96]),
+            Some(CairoLayout::AllCairo)
+        );
+    }
+}
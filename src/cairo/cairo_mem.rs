@@ -1,10 +1,17 @@
-use crate::FE;
+use crate::{
+    starks::config::{BatchedMerkleTreeBackend, Commitment},
+    FE,
+};
 
 use super::errors::CairoImportError;
-// use crate::FE;
-use lambdaworks_math::traits::ByteConversion;
+use super::felt::{fe_from_bytes_le, fe_from_felt252};
+use super::vm_adapter::Felt252;
+use lambdaworks_crypto::merkle_tree::{merkle::MerkleTree, proof::Proof};
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
 use std::{collections::HashMap, fs};
 
+type MemoryMerkleTree = MerkleTree<BatchedMerkleTreeBackend<Stark252PrimeField>>;
+
 // `FE` is used as the type of values stored in
 // the Cairo memory. We should decide if this is
 // correct or we should consider another type.
@@ -32,6 +39,47 @@ impl CairoMemory {
         self.data.is_empty()
     }
 
+    /// Root of a Keccak256 Merkle tree whose leaves are the `[address, value]` pairs
+    /// of the memory, sorted by address. A leaf is hashed like the rows of the trace
+    /// commitments, so a cell can be opened with the batched backend of the prover.
+    /// `None` if the memory is empty.
+    pub fn commitment(&self) -> Option<Commitment> {
+        self.commitment_tree().map(|(_, tree)| tree.root)
+    }
+
+    /// Openings of the cells at `addresses` against the `commitment` of the memory,
+    /// in their order.
+    /// `None` if any of them isn't in the memory.
+    pub fn open(&self, addresses: &[u64]) -> Option<Vec<MemoryOpening>> {
+        let (sorted_addresses, tree) = self.commitment_tree()?;
+        addresses
+            .iter()
+            .map(|address| {
+                let index = sorted_addresses.binary_search(address).ok()?;
+                Some(MemoryOpening {
+                    address: *address,
+                    value: self.data[address],
+                    index,
+                    path: tree.get_proof_by_pos(index)?,
+                })
+            })
+            .collect()
+    }
+
+    fn commitment_tree(&self) -> Option<(Vec<u64>, MemoryMerkleTree)> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut addresses: Vec<_> = self.data.keys().copied().collect();
+        addresses.sort_unstable();
+        let leaves: Vec<Vec<FE>> = addresses
+            .iter()
+            .map(|address| vec![FE::from(*address), self.data[address]])
+            .collect();
+
+        Some((addresses, MemoryMerkleTree::build(&leaves)))
+    }
+
     pub fn from_bytes_le(bytes: &[u8]) -> Result<Self, CairoImportError> {
         // Each row is an 8 bytes address
         // and a value of 32 bytes (which is a field)
@@ -47,12 +95,7 @@ impl CairoMemory {
         for i in 0..num_rows {
             let address =
                 u64::from_le_bytes(bytes[i * ROW_SIZE..i * ROW_SIZE + 8].try_into().unwrap());
-            let value = FE::from_bytes_le(
-                bytes[i * ROW_SIZE + 8..i * ROW_SIZE + 40]
-                    .try_into()
-                    .unwrap(),
-            )
-            .unwrap();
+            let value = fe_from_bytes_le(&bytes[i * ROW_SIZE + 8..i * ROW_SIZE + 40])?;
 
             data.insert(address, value);
         }
@@ -66,6 +109,129 @@ impl CairoMemory {
     }
 }
 
+/// Proof that the cell at `address` holds `value` in a memory, against its
+/// `commitment`. The cell is the leaf `index` of the tree, its position among the
+/// addresses of the memory.
+#[derive(Clone, Debug)]
+pub struct MemoryOpening {
+    pub address: u64,
+    pub value: FE,
+    pub index: usize,
+    pub path: Proof<Commitment>,
+}
+
+impl MemoryOpening {
+    /// Checks the cell against the commitment of a memory, like the
+    /// `memory_commitment` of the public inputs of its execution.
+    pub fn verify(&self, memory_commitment: &Commitment) -> bool {
+        self.path
+            .verify::<BatchedMerkleTreeBackend<Stark252PrimeField>>(
+                memory_commitment,
+                self.index,
+                &vec![FE::from(self.address), self.value],
+            )
+    }
+}
+
+/// Value of a cell of a memory that wasn't relocated: a field element or a pointer,
+/// given by its segment and its offset in the segment.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SegmentedValue {
+    Felt(FE),
+    Pointer { segment: isize, offset: u64 },
+}
+
+/// Memory of a run that wasn't relocated, as the cells of each of its segments.
+///
+/// Relocating it lays the segments one after the other from address 1, in the
+/// order of their indexes, as cairo-vm does. A segment takes as many addresses as
+/// it has cells, counting the unused ones, which are `None`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SegmentedMemory {
+    segments: Vec<Vec<Option<SegmentedValue>>>,
+    // Relocated address of the first cell of each segment
+    segment_bases: Vec<u64>,
+}
+
+impl SegmentedMemory {
+    pub fn new(segments: Vec<Vec<Option<SegmentedValue>>>) -> Self {
+        let segment_bases = segments
+            .iter()
+            .scan(1, |base, segment| {
+                let segment_base = *base;
+                *base += segment.len() as u64;
+                Some(segment_base)
+            })
+            .collect();
+
+        Self {
+            segments,
+            segment_bases,
+        }
+    }
+
+    pub fn segments(&self) -> &[Vec<Option<SegmentedValue>>] {
+        &self.segments
+    }
+
+    /// Number of cells of the relocated memory, counting the unused ones and the
+    /// address 0.
+    pub fn relocated_len(&self) -> usize {
+        1 + self.segments.iter().map(Vec::len).sum::<usize>()
+    }
+
+    /// Relocated address of the cell at `offset` of `segment`. The offset may be
+    /// past the end of the segment, as the pointers to the end of one are.
+    pub fn relocate_address(&self, segment: isize, offset: u64) -> Option<u64> {
+        let base = self.segment_bases.get(usize::try_from(segment).ok()?)?;
+        Some(base + offset)
+    }
+
+    fn relocate_value(&self, value: &SegmentedValue) -> Result<FE, CairoImportError> {
+        match value {
+            SegmentedValue::Felt(felt) => Ok(*felt),
+            SegmentedValue::Pointer { segment, offset } => self
+                .relocate_address(*segment, *offset)
+                .map(FE::from)
+                .ok_or(CairoImportError::UnknownSegment(*segment)),
+        }
+    }
+}
+
+impl TryFrom<&SegmentedMemory> for CairoMemory {
+    type Error = CairoImportError;
+
+    /// Relocates the memory, translating the pointers stored in it to their
+    /// relocated addresses. Fails on pointers to segments that don't exist, like
+    /// the temporary ones.
+    fn try_from(memory: &SegmentedMemory) -> Result<Self, CairoImportError> {
+        let mut data = HashMap::new();
+        for (cells, base) in memory.segments.iter().zip(&memory.segment_bases) {
+            for (offset, value) in cells.iter().enumerate() {
+                if let Some(value) = value {
+                    data.insert(base + offset as u64, memory.relocate_value(value)?);
+                }
+            }
+        }
+
+        Ok(Self::new(data))
+    }
+}
+
+/// Builds the memory from the relocated memory of cairo-vm, where the value
+/// at address `i` is stored in position `i`.
+impl From<&[Option<Felt252>]> for CairoMemory {
+    fn from(relocated_memory: &[Option<Felt252>]) -> Self {
+        let data = relocated_memory
+            .iter()
+            .enumerate()
+            .filter_map(|(addr, value)| Some((addr as u64, fe_from_felt252(value.as_ref()?))))
+            .collect();
+
+        Self::new(data)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,6 +280,93 @@ mod tests {
         }
     }
 
+    #[test]
+    fn memory_commitment_depends_only_on_the_cells() {
+        let cells = [(1, FE::from(5)), (2, FE::from(7)), (4, FE::from(9))];
+        let memory = CairoMemory::new(cells.into_iter().collect());
+        let reversed = CairoMemory::new(cells.into_iter().rev().collect());
+        assert_eq!(memory.commitment(), reversed.commitment());
+
+        let mut modified = memory.clone();
+        modified.data.insert(4, FE::from(10));
+        assert_ne!(memory.commitment(), modified.commitment());
+
+        let mut moved = memory.clone();
+        let value = moved.data.remove(&4).unwrap();
+        moved.data.insert(3, value);
+        assert_ne!(memory.commitment(), moved.commitment());
+
+        assert_eq!(CairoMemory::new(HashMap::new()).commitment(), None);
+    }
+
+    #[test]
+    fn memory_openings_are_checked_against_the_commitment() {
+        let cells = [(1, FE::from(5)), (2, FE::from(7)), (4, FE::from(9))];
+        let memory = CairoMemory::new(cells.into_iter().collect());
+        let commitment = memory.commitment().unwrap();
+
+        let openings = memory.open(&[4, 1]).unwrap();
+        assert_eq!(openings[0].value, FE::from(9));
+        assert!(openings.iter().all(|opening| opening.verify(&commitment)));
+
+        let mut forged = openings[0].clone();
+        forged.value = FE::from(10);
+        assert!(!forged.verify(&commitment));
+        let mut moved = openings[0].clone();
+        moved.address = 3;
+        assert!(!moved.verify(&commitment));
+
+        let mut modified = memory.clone();
+        modified.data.insert(2, FE::from(8));
+        assert!(!modified.open(&[2]).unwrap()[0].verify(&commitment));
+
+        assert!(memory.open(&[3]).is_none());
+    }
+
+    #[test]
+    fn segmented_memory_is_relocated_after_address_zero() {
+        let memory = SegmentedMemory::new(vec![
+            vec![
+                Some(SegmentedValue::Felt(FE::from(3))),
+                Some(SegmentedValue::Pointer {
+                    segment: 1,
+                    offset: 2,
+                }),
+            ],
+            vec![None, Some(SegmentedValue::Felt(FE::from(5))), None],
+            vec![Some(SegmentedValue::Pointer {
+                segment: 2,
+                offset: 1,
+            })],
+        ]);
+
+        assert_eq!(memory.relocate_address(1, 2), Some(5));
+        assert_eq!(memory.relocate_address(3, 0), None);
+        assert_eq!(memory.relocated_len(), 7);
+        assert_eq!(
+            CairoMemory::try_from(&memory).unwrap(),
+            CairoMemory::new(HashMap::from([
+                (1, FE::from(3)),
+                (2, FE::from(5)),
+                (4, FE::from(5)),
+                (6, FE::from(7)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn pointers_to_unknown_segments_are_rejected() {
+        let memory = SegmentedMemory::new(vec![vec![Some(SegmentedValue::Pointer {
+            segment: -1,
+            offset: 0,
+        })]]);
+
+        assert!(matches!(
+            CairoMemory::try_from(&memory),
+            Err(CairoImportError::UnknownSegment(-1))
+        ));
+    }
+
     #[test]
     fn mem_indexes_are_contiguos_when_loading_from_file_mul_program() {
         let base_dir = env!("CARGO_MANIFEST_DIR");
@@ -0,0 +1,278 @@
+/// Identifiers of the transition constraints of the Cairo AIR.
+///
+/// The discriminant of each variant is the index of the constraint in the
+/// evaluations returned by `compute_transition`, so new constraints must be
+/// appended at the end of the list to keep the existing identifiers stable.
+/// The builtin constraints are the exception: only the ones of the builtins of
+/// the program are evaluated, after the others and in this order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConstraintId {
+    // Flag constraints
+    FlagDstFp = 0,
+    FlagOp0Fp = 1,
+    FlagOp1Val = 2,
+    FlagOp1Fp = 3,
+    FlagOp1Ap = 4,
+    FlagResAdd = 5,
+    FlagResMul = 6,
+    FlagPcAbs = 7,
+    FlagPcRel = 8,
+    FlagPcJnz = 9,
+    FlagApAdd = 10,
+    FlagApOne = 11,
+    FlagOpcCall = 12,
+    FlagOpcRet = 13,
+    FlagOpcAeq = 14,
+    FlagLast = 15,
+
+    // Main constraints
+    Inst = 16,
+    DstAddr = 17,
+    Op0Addr = 18,
+    Op1Addr = 19,
+    NextAp = 20,
+    NextFp = 21,
+    NextPc1 = 22,
+    NextPc2 = 23,
+    T0 = 24,
+    T1 = 25,
+    Mul1 = 26,
+    Mul2 = 27,
+    Call1 = 28,
+    Call2 = 29,
+    AssertEq = 30,
+
+    // Auxiliary constraints
+    MemoryIncreasing0 = 31,
+    MemoryIncreasing1 = 32,
+    MemoryIncreasing2 = 33,
+    MemoryIncreasing3 = 34,
+    MemoryConsistency0 = 35,
+    MemoryConsistency1 = 36,
+    MemoryConsistency2 = 37,
+    MemoryConsistency3 = 38,
+    PermutationArgument0 = 39,
+    PermutationArgument1 = 40,
+    PermutationArgument2 = 41,
+    PermutationArgument3 = 42,
+    RangeCheckIncreasing0 = 43,
+    RangeCheckIncreasing1 = 44,
+    RangeCheckIncreasing2 = 45,
+    RangeCheck0 = 46,
+    RangeCheck1 = 47,
+    RangeCheck2 = 48,
+
+    // Range-check builtins value decomposition constraints
+    RangeCheckBuiltin = 49,
+    RangeCheck96Builtin = 50,
+}
+
+/// Static description of a transition constraint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConstraintInfo {
+    pub name: &'static str,
+    pub degree: usize,
+    /// Number of steps at the end of the trace where the constraint does not apply.
+    pub exemptions: usize,
+    pub description: &'static str,
+}
+
+impl ConstraintId {
+    /// All the constraints, ordered by index.
+    pub const ALL: [ConstraintId; 51] = [
+        Self::FlagDstFp,
+        Self::FlagOp0Fp,
+        Self::FlagOp1Val,
+        Self::FlagOp1Fp,
+        Self::FlagOp1Ap,
+        Self::FlagResAdd,
+        Self::FlagResMul,
+        Self::FlagPcAbs,
+        Self::FlagPcRel,
+        Self::FlagPcJnz,
+        Self::FlagApAdd,
+        Self::FlagApOne,
+        Self::FlagOpcCall,
+        Self::FlagOpcRet,
+        Self::FlagOpcAeq,
+        Self::FlagLast,
+        Self::Inst,
+        Self::DstAddr,
+        Self::Op0Addr,
+        Self::Op1Addr,
+        Self::NextAp,
+        Self::NextFp,
+        Self::NextPc1,
+        Self::NextPc2,
+        Self::T0,
+        Self::T1,
+        Self::Mul1,
+        Self::Mul2,
+        Self::Call1,
+        Self::Call2,
+        Self::AssertEq,
+        Self::MemoryIncreasing0,
+        Self::MemoryIncreasing1,
+        Self::MemoryIncreasing2,
+        Self::MemoryIncreasing3,
+        Self::MemoryConsistency0,
+        Self::MemoryConsistency1,
+        Self::MemoryConsistency2,
+        Self::MemoryConsistency3,
+        Self::PermutationArgument0,
+        Self::PermutationArgument1,
+        Self::PermutationArgument2,
+        Self::PermutationArgument3,
+        Self::RangeCheckIncreasing0,
+        Self::RangeCheckIncreasing1,
+        Self::RangeCheckIncreasing2,
+        Self::RangeCheck0,
+        Self::RangeCheck1,
+        Self::RangeCheck2,
+        Self::RangeCheckBuiltin,
+        Self::RangeCheck96Builtin,
+    ];
+
+    /// Constraints that apply to a trace with the columns of the builtins of
+    /// `builtin_constraints`. The other builtin constraints are skipped.
+    pub fn enabled(
+        builtin_constraints: &[ConstraintId],
+    ) -> impl Iterator<Item = ConstraintId> + '_ {
+        Self::ALL
+            .into_iter()
+            .filter(move |id| !id.is_builtin() || builtin_constraints.contains(id))
+    }
+
+    pub fn is_builtin(self) -> bool {
+        matches!(self, Self::RangeCheckBuiltin | Self::RangeCheck96Builtin)
+    }
+
+    pub fn from_index(index: usize) -> Option<Self> {
+        Self::ALL.get(index).copied()
+    }
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    pub fn degree(self) -> usize {
+        self.info().degree
+    }
+
+    pub fn exemptions(self) -> usize {
+        self.info().exemptions
+    }
+
+    pub fn description(self) -> &'static str {
+        self.info().description
+    }
+
+    /// Registry of the constraints. Degrees and exemptions must match the
+    /// evaluations done in `compute_transition`.
+    #[rustfmt::skip]
+    pub fn info(self) -> ConstraintInfo {
+        let (name, degree, exemptions, description) = match self {
+            Self::FlagDstFp => ("flag_dst_fp", 2, 0, "dst_reg flag is a bit"),
+            Self::FlagOp0Fp => ("flag_op0_fp", 2, 0, "op0_reg flag is a bit"),
+            Self::FlagOp1Val => ("flag_op1_val", 2, 0, "op1_src imm flag is a bit"),
+            Self::FlagOp1Fp => ("flag_op1_fp", 2, 0, "op1_src fp flag is a bit"),
+            Self::FlagOp1Ap => ("flag_op1_ap", 2, 0, "op1_src ap flag is a bit"),
+            Self::FlagResAdd => ("flag_res_add", 2, 0, "res_logic add flag is a bit"),
+            Self::FlagResMul => ("flag_res_mul", 2, 0, "res_logic mul flag is a bit"),
+            Self::FlagPcAbs => ("flag_pc_abs", 2, 0, "pc_update jump_abs flag is a bit"),
+            Self::FlagPcRel => ("flag_pc_rel", 2, 0, "pc_update jump_rel flag is a bit"),
+            Self::FlagPcJnz => ("flag_pc_jnz", 2, 0, "pc_update jnz flag is a bit"),
+            Self::FlagApAdd => ("flag_ap_add", 2, 0, "ap_update add flag is a bit"),
+            Self::FlagApOne => ("flag_ap_one", 2, 0, "ap_update add1 flag is a bit"),
+            Self::FlagOpcCall => ("flag_opc_call", 2, 0, "opcode call flag is a bit"),
+            Self::FlagOpcRet => ("flag_opc_ret", 2, 0, "opcode ret flag is a bit"),
+            Self::FlagOpcAeq => ("flag_opc_aeq", 2, 0, "opcode assert_eq flag is a bit"),
+            Self::FlagLast => ("flag_last", 1, 0, "last flag of the instruction is zero"),
+            Self::Inst => ("inst", 3, 0, "instruction is the packing of its offsets and flags"),
+            Self::DstAddr => ("dst_addr", 3, 0, "dst address is computed from its register"),
+            Self::Op0Addr => ("op0_addr", 3, 0, "op0 address is computed from its register"),
+            Self::Op1Addr => ("op1_addr", 3, 0, "op1 address is computed from its source"),
+            Self::NextAp => ("next_ap", 3, 1, "ap update of the next step"),
+            Self::NextFp => ("next_fp", 3, 1, "fp update of the next step"),
+            Self::NextPc1 => ("next_pc_1", 3, 1, "pc update of the next step when not jumping"),
+            Self::NextPc2 => ("next_pc_2", 3, 1, "pc update of the next step when jumping"),
+            Self::T0 => ("t0", 3, 0, "t0 is the product of the jnz flag and dst"),
+            Self::T1 => ("t1", 3, 0, "t1 is the product of t0 and res"),
+            Self::Mul1 => ("mul_1", 3, 0, "mul is the product of op0 and op1"),
+            Self::Mul2 => ("mul_2", 3, 0, "res is computed from the res_logic flags"),
+            Self::Call1 => ("call_1", 3, 0, "call stores fp at dst"),
+            Self::Call2 => ("call_2", 3, 0, "call stores the return pc at op0"),
+            Self::AssertEq => ("assert_eq", 3, 0, "assert_eq makes dst equal to res"),
+            Self::MemoryIncreasing0 => ("memory_increasing_0", 2, 0, "sorted memory addresses are continuous"),
+            Self::MemoryIncreasing1 => ("memory_increasing_1", 2, 0, "sorted memory addresses are continuous"),
+            Self::MemoryIncreasing2 => ("memory_increasing_2", 2, 0, "sorted memory addresses are continuous"),
+            Self::MemoryIncreasing3 => ("memory_increasing_3", 2, 1, "sorted memory addresses are continuous"),
+            Self::MemoryConsistency0 => ("memory_consistency_0", 2, 0, "sorted memory is single valued"),
+            Self::MemoryConsistency1 => ("memory_consistency_1", 2, 0, "sorted memory is single valued"),
+            Self::MemoryConsistency2 => ("memory_consistency_2", 2, 0, "sorted memory is single valued"),
+            Self::MemoryConsistency3 => ("memory_consistency_3", 2, 1, "sorted memory is single valued"),
+            Self::PermutationArgument0 => ("permutation_argument_0", 2, 0, "memory permutation argument step"),
+            Self::PermutationArgument1 => ("permutation_argument_1", 2, 0, "memory permutation argument step"),
+            Self::PermutationArgument2 => ("permutation_argument_2", 2, 0, "memory permutation argument step"),
+            Self::PermutationArgument3 => ("permutation_argument_3", 2, 1, "memory permutation argument step"),
+            Self::RangeCheckIncreasing0 => ("range_check_increasing_0", 2, 0, "sorted offsets are continuous"),
+            Self::RangeCheckIncreasing1 => ("range_check_increasing_1", 2, 0, "sorted offsets are continuous"),
+            Self::RangeCheckIncreasing2 => ("range_check_increasing_2", 2, 1, "sorted offsets are continuous"),
+            Self::RangeCheck0 => ("range_check_0", 2, 0, "offsets permutation argument step"),
+            Self::RangeCheck1 => ("range_check_1", 2, 0, "offsets permutation argument step"),
+            Self::RangeCheck2 => ("range_check_2", 2, 0, "offsets permutation argument step"),
+            Self::RangeCheckBuiltin => ("range_check_builtin", 1, 0, "range-checked value is the packing of its 16-bit parts"),
+            Self::RangeCheck96Builtin => ("range_check96_builtin", 1, 0, "range-checked value is the packing of its six 16-bit parts"),
+        };
+
+        ConstraintInfo {
+            name,
+            degree,
+            exemptions,
+            description,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constraint_indices_are_contiguous() {
+        for (index, id) in ConstraintId::ALL.iter().enumerate() {
+            assert_eq!(id.index(), index);
+            assert_eq!(ConstraintId::from_index(index), Some(*id));
+        }
+        assert_eq!(ConstraintId::from_index(ConstraintId::ALL.len()), None);
+    }
+
+    #[test]
+    fn constraint_names_are_unique() {
+        let mut names: Vec<_> = ConstraintId::ALL.iter().map(|id| id.name()).collect();
+        names.sort();
+        names.dedup();
+
+        assert_eq!(names.len(), ConstraintId::ALL.len());
+    }
+
+    #[test]
+    fn range_check_builtin_constraints_are_only_enabled_with_their_builtin() {
+        assert_eq!(ConstraintId::enabled(&[]).count(), 49);
+        assert!(!ConstraintId::enabled(&[]).any(ConstraintId::is_builtin));
+
+        let enabled: Vec<_> = ConstraintId::enabled(&[ConstraintId::RangeCheck96Builtin]).collect();
+        assert_eq!(enabled.len(), 50);
+        assert_eq!(enabled[49], ConstraintId::RangeCheck96Builtin);
+
+        let both = [
+            ConstraintId::RangeCheckBuiltin,
+            ConstraintId::RangeCheck96Builtin,
+        ];
+        assert_eq!(ConstraintId::enabled(&both).count(), 51);
+    }
+}
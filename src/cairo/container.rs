@@ -0,0 +1,283 @@
+use lambdaworks_math::{
+    errors::DeserializationError,
+    field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+    traits::{Deserializable, Serializable},
+};
+use sha3::{Digest, Keccak256};
+
+#[cfg(feature = "sidecar")]
+use crate::starks::proof::sidecar::{EncryptedSidecar, SidecarError, SidecarKey};
+
+use super::air::{CairoAIR, PublicInputs};
+use crate::starks::{
+    config::Commitment,
+    proof::{
+        attestation::{Attestation, AttestationError},
+        options::ProofOptions,
+        stark::StarkProof,
+    },
+    traits::AIR,
+};
+
+/// Version of the format of the containers, their first byte. It changes with
+/// the layout of the container or of the parts it holds, so files of another
+/// version are rejected instead of misread.
+pub const CONTAINER_FORMAT_VERSION: u8 = 1;
+
+/// A Cairo proof as written to a file: the format version, then the proof and the
+/// public inputs, each one prefixed by its length, followed by an optional
+/// attestation, also prefixed by its length, and an optional encrypted sidecar.
+/// Lengths are big-endian `u64`.
+///
+/// The sidecars need the `sidecar` feature. Without it, containers with a sidecar
+/// are rejected.
+#[derive(Debug)]
+pub struct CairoProofContainer {
+    pub proof: StarkProof<Stark252PrimeField>,
+    pub pub_inputs: PublicInputs,
+    pub attestation: Option<Attestation>,
+    #[cfg(feature = "sidecar")]
+    pub sidecar: Option<EncryptedSidecar>,
+}
+
+impl CairoProofContainer {
+    pub fn new(proof: StarkProof<Stark252PrimeField>, pub_inputs: PublicInputs) -> Self {
+        Self {
+            proof,
+            pub_inputs,
+            attestation: None,
+            #[cfg(feature = "sidecar")]
+            sidecar: None,
+        }
+    }
+
+    /// Keccak256 of the serialized proof and public inputs. Sidecars are bound to it.
+    pub fn proof_hash(&self) -> Commitment {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.proof.serialize());
+        hasher.update(self.pub_inputs.serialize());
+        hasher.finalize().into()
+    }
+
+    /// Attests that the proof was made by this build with `proof_options`, replacing
+    /// the previous attestation.
+    pub fn attest(&mut self, proof_options: &ProofOptions) {
+        self.attestation = Some(Attestation::new(
+            CairoAIR::VERSION,
+            proof_options,
+            &self.proof_hash(),
+        ));
+    }
+
+    /// The attestation, if there is one, checking it was made for the proof.
+    pub fn checked_attestation(&self) -> Result<Option<&Attestation>, AttestationError> {
+        self.attestation
+            .as_ref()
+            .map(|attestation| {
+                attestation.check(&self.proof_hash())?;
+                Ok(attestation)
+            })
+            .transpose()
+    }
+
+    /// Attaches `data` encrypted with `key`, replacing the previous sidecar. Only the
+    /// holders of the key can read it back with `open_sidecar`.
+    #[cfg(feature = "sidecar")]
+    pub fn seal_sidecar(&mut self, key: &SidecarKey, data: &[u8]) {
+        self.sidecar = Some(EncryptedSidecar::seal(key, &self.proof_hash(), data));
+    }
+
+    /// Decrypts the sidecar, if there is one. Fails if it was sealed with another
+    /// key or for another proof.
+    #[cfg(feature = "sidecar")]
+    pub fn open_sidecar(&self, key: &SidecarKey) -> Result<Option<Vec<u8>>, SidecarError> {
+        self.sidecar
+            .as_ref()
+            .map(|sidecar| sidecar.open(key, &self.proof_hash()))
+            .transpose()
+    }
+}
+
+fn read_length_prefixed(bytes: &[u8]) -> Result<(&[u8], &[u8]), DeserializationError> {
+    let len = u64::from_be_bytes(
+        bytes
+            .get(..8)
+            .ok_or(DeserializationError::InvalidAmountOfBytes)?
+            .try_into()
+            .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
+    );
+    let len = usize::try_from(len).map_err(|_| DeserializationError::InvalidValue)?;
+    let bytes = &bytes[8..];
+    if bytes.len() < len {
+        return Err(DeserializationError::InvalidAmountOfBytes);
+    }
+    Ok(bytes.split_at(len))
+}
+
+impl Serializable for CairoProofContainer {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = vec![CONTAINER_FORMAT_VERSION];
+        let proof_bytes = self.proof.serialize();
+        bytes.extend((proof_bytes.len() as u64).to_be_bytes());
+        bytes.extend(proof_bytes);
+        let pub_inputs_bytes = self.pub_inputs.serialize();
+        bytes.extend((pub_inputs_bytes.len() as u64).to_be_bytes());
+        bytes.extend(pub_inputs_bytes);
+
+        match &self.attestation {
+            Some(attestation) => {
+                bytes.push(1);
+                let attestation_bytes = attestation.serialize();
+                bytes.extend((attestation_bytes.len() as u64).to_be_bytes());
+                bytes.extend(attestation_bytes);
+            }
+            None => bytes.push(0),
+        }
+
+        #[cfg(feature = "sidecar")]
+        match &self.sidecar {
+            Some(sidecar) => {
+                bytes.push(1);
+                bytes.extend(sidecar.serialize());
+            }
+            None => bytes.push(0),
+        }
+        #[cfg(not(feature = "sidecar"))]
+        bytes.push(0);
+
+        bytes
+    }
+}
+
+impl Deserializable for CairoProofContainer {
+    fn deserialize(bytes: &[u8]) -> Result<Self, DeserializationError>
+    where
+        Self: Sized,
+    {
+        let (version, bytes) = bytes
+            .split_first()
+            .ok_or(DeserializationError::InvalidAmountOfBytes)?;
+        if *version != CONTAINER_FORMAT_VERSION {
+            return Err(DeserializationError::InvalidValue);
+        }
+        let (proof_bytes, bytes) = read_length_prefixed(bytes)?;
+        let proof = StarkProof::deserialize_strict(proof_bytes)?;
+        let (pub_inputs_bytes, bytes) = read_length_prefixed(bytes)?;
+        let pub_inputs = PublicInputs::deserialize(pub_inputs_bytes)?;
+
+        let (attestation, bytes) = match bytes.split_first() {
+            Some((0, bytes)) => (None, bytes),
+            Some((1, bytes)) => {
+                let (attestation_bytes, bytes) = read_length_prefixed(bytes)?;
+                (Some(Attestation::deserialize(attestation_bytes)?), bytes)
+            }
+            _ => return Err(DeserializationError::InvalidValue),
+        };
+
+        #[cfg(feature = "sidecar")]
+        let sidecar = match bytes.first() {
+            Some(0) if bytes.len() == 1 => None,
+            Some(1) => Some(EncryptedSidecar::deserialize(&bytes[1..])?),
+            _ => return Err(DeserializationError::InvalidValue),
+        };
+        #[cfg(not(feature = "sidecar"))]
+        if bytes != [0] {
+            return Err(DeserializationError::InvalidValue);
+        }
+
+        Ok(Self {
+            proof,
+            pub_inputs,
+            attestation,
+            #[cfg(feature = "sidecar")]
+            sidecar,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cairo::{
+        air::{generate_cairo_proof, verify_cairo_proof},
+        runner::run::{cairo0_program_path, generate_prover_args, CairoVersion},
+    };
+    use crate::starks::proof::options::ProofOptions;
+
+    #[cfg(feature = "sidecar")]
+    #[test]
+    fn sidecars_are_bound_to_the_proof_of_their_container() {
+        let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+        let (main_trace, pub_inputs) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof_options = ProofOptions::default_test_options();
+        let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+        let key = SidecarKey::random();
+
+        let mut container = CairoProofContainer::new(proof, pub_inputs);
+        assert_eq!(container.open_sidecar(&key), Ok(None));
+        container.seal_sidecar(&key, b"private hints");
+
+        let mut container = CairoProofContainer::deserialize(&container.serialize()).unwrap();
+        assert!(verify_cairo_proof(
+            &container.proof,
+            &container.pub_inputs,
+            &proof_options
+        ));
+        assert_eq!(
+            container.open_sidecar(&key),
+            Ok(Some(b"private hints".to_vec()))
+        );
+
+        // The sidecar can't be moved to a proof of another statement
+        container.pub_inputs.num_steps += 1;
+        assert_eq!(container.open_sidecar(&key), Err(SidecarError::Decryption));
+    }
+
+    #[test]
+    fn attestations_are_read_back_and_bound_to_the_proof_of_their_container() {
+        let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+        let (main_trace, pub_inputs) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof_options = ProofOptions::default_test_options();
+        let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+
+        let mut container = CairoProofContainer::new(proof, pub_inputs);
+        assert_eq!(container.checked_attestation(), Ok(None));
+        container.attest(&proof_options);
+        #[cfg(feature = "sidecar")]
+        container.seal_sidecar(&SidecarKey::random(), b"private hints");
+
+        let mut container = CairoProofContainer::deserialize(&container.serialize()).unwrap();
+        let attestation = container.checked_attestation().unwrap().unwrap();
+        assert_eq!(attestation.air_version, CairoAIR::VERSION);
+        assert!(attestation.has_options(&proof_options));
+        #[cfg(feature = "sidecar")]
+        assert!(container.sidecar.is_some());
+
+        container.pub_inputs.num_steps += 1;
+        assert_eq!(
+            container.checked_attestation(),
+            Err(AttestationError::Mismatch)
+        );
+    }
+
+    #[test]
+    fn containers_of_another_format_version_are_rejected() {
+        let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+        let (main_trace, pub_inputs) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof_options = ProofOptions::default_test_options();
+        let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+
+        let mut bytes = CairoProofContainer::new(proof, pub_inputs).serialize();
+        assert_eq!(bytes[0], CONTAINER_FORMAT_VERSION);
+        assert!(CairoProofContainer::deserialize(&bytes).is_ok());
+
+        bytes[0] = CONTAINER_FORMAT_VERSION + 1;
+        assert!(matches!(
+            CairoProofContainer::deserialize(&bytes),
+            Err(DeserializationError::InvalidValue)
+        ));
+    }
+}
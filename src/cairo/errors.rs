@@ -1,11 +1,25 @@
 use thiserror::Error;
 
+use crate::FE;
+
 #[derive(Error, Debug)]
 pub enum CairoImportError {
     #[error("Bytes should be a multiple of 24 for trace or 40 for memory")]
     IncorrectNumberOfBytes,
     #[error("IO Error")]
     FileError(#[from] std::io::Error),
+    #[error(transparent)]
+    InvalidFelt(#[from] FeltConversionError),
+    #[error("The memory has a pointer to the segment {0}, which doesn't exist")]
+    UnknownSegment(isize),
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum FeltConversionError {
+    #[error("Field elements should be encoded in 32 bytes, got {0}")]
+    InvalidLength(usize),
+    #[error("Value is not smaller than the field modulus")]
+    NonCanonical,
 }
 
 #[derive(Error, Debug, PartialEq)]
@@ -27,3 +41,31 @@ pub enum InstructionDecodingError {
     #[error("Instruction not found in memory")]
     InstructionNotFound,
 }
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PublicInputsError {
+    #[error("The trace has {0} steps, less than the ones declared in the public inputs")]
+    NotEnoughSteps(usize),
+    #[error("The number of steps in the public inputs doesn't match the trace")]
+    NumStepsMismatch,
+    #[error("The {0} register in the public inputs doesn't match the trace")]
+    RegisterMismatch(&'static str),
+    #[error("The public memory value at address {0:?} doesn't match the trace")]
+    PublicMemoryMismatch(FE),
+    #[error("The memory addresses of the trace don't fit in the address space")]
+    AddressOutOfBounds,
+    #[error("The range-check bounds in the public inputs don't match the trace offsets")]
+    RangeCheckBoundsMismatch,
+    #[error("The public memory dummy accesses and padding rows don't match the trace")]
+    MemoryPaddingMismatch,
+}
+
+#[derive(Error, Debug)]
+pub enum PublicInputsJsonError {
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("The field {0} of the public inputs is missing or has an invalid value")]
+    InvalidField(&'static str),
+    #[error("The address {0} appears more than once in the public memory")]
+    DuplicatePublicMemoryAddress(String),
+}
@@ -20,7 +20,9 @@ use super::{
         },
         instruction_offsets::InstructionOffsets,
     },
+    felt::fe_to_u64,
     register_states::RegisterStates,
+    trace_layout::TraceLayout,
 };
 
 pub const MEMORY_COLUMNS: [usize; 8] = [
@@ -50,6 +52,27 @@ pub const ADDR_COLUMNS: [usize; 4] = [FRAME_PC, FRAME_DST_ADDR, FRAME_OP0_ADDR,
 // ├xxxxxxxxxxxxxxxx|x|xx|xxxx|xxxx|xxx|xxx┤
 //
 
+/// Minimum length of the main trace. Executions of one or two steps, like programs
+/// with an empty `main`, are padded to it, so the transition constraints still hold
+/// in rows out of their exemptions and FRI has some layers to fold.
+pub const MIN_TRACE_LENGTH: usize = 8;
+
+/// Estimated length of the main trace of an execution of `num_steps` steps, with
+/// `num_memory_holes` memory holes and `public_memory_len` public memory cells, as
+/// `build_main_trace` pads it. The rows filling the range-check holes aren't known
+/// until the trace is built, so programs with sparse offsets may need the next
+/// power of two.
+pub fn estimate_main_trace_length(
+    num_steps: usize,
+    num_memory_holes: usize,
+    public_memory_len: usize,
+) -> usize {
+    let n_rows = num_steps
+        + div_ceil(num_memory_holes, ADDR_COLUMNS.len())
+        + pub_memory_dummy_rows(public_memory_len);
+    n_rows.next_power_of_two().max(MIN_TRACE_LENGTH)
+}
+
 /// Builds the Cairo main trace (i.e. the trace without the auxiliary columns).
 /// Builds the execution trace, fills the offset range-check holes and memory holes, adds
 /// public memory dummy accesses (See section 9.8 of the Cairo whitepaper) and pads the result
@@ -71,28 +94,51 @@ pub fn build_main_trace(
     public_input.range_check_max = Some(rc_max);
     fill_rc_holes(&mut main_trace, rc_holes);
 
+    // Addresses not fitting in a u64 leave the maximum unset, and the trace is rejected by the prover
+    public_input.memory_address_max = address_cols
+        .iter()
+        .chain(public_input.public_memory.keys())
+        .max_by_key(|addr| addr.representative())
+        .and_then(fe_to_u64);
+
     let mut memory_holes = get_memory_holes(&address_cols, public_input.public_memory.len());
+    public_input.num_memory_holes = memory_holes.len();
 
     if !memory_holes.is_empty() {
         fill_memory_holes(&mut main_trace, &mut memory_holes);
     }
 
-    add_pub_memory_dummy_accesses(&mut main_trace, public_input.public_memory.len());
+    let dummy_accesses =
+        add_pub_memory_dummy_accesses(&mut main_trace, public_input.public_memory.len());
 
-    let trace_len_next_power_of_two = main_trace.n_rows().next_power_of_two();
+    let trace_len_next_power_of_two = main_trace
+        .n_rows()
+        .next_power_of_two()
+        .max(MIN_TRACE_LENGTH);
     let padding = trace_len_next_power_of_two - main_trace.n_rows();
     pad_with_last_row(&mut main_trace, padding);
 
+    public_input.memory_padding = MemoryPadding {
+        dummy_accesses,
+        padding_rows: padding,
+    };
+
     main_trace
 }
 
 /// Artificial `(0, 0)` dummy memory accesses must be added for the public memory.
-/// See section 9.8 of the Cairo whitepaper.
+/// See section 9.8 of the Cairo whitepaper. Returns the number of dummy accesses added.
 fn add_pub_memory_dummy_accesses<F: IsFFTField>(
     main_trace: &mut TraceTable<F>,
     pub_memory_len: usize,
-) {
-    pad_with_last_row_and_zeros(main_trace, (pub_memory_len >> 2) + 1, &MEMORY_COLUMNS)
+) -> usize {
+    let dummy_rows = pub_memory_dummy_rows(pub_memory_len);
+    pad_with_last_row_and_zeros(main_trace, dummy_rows, &MEMORY_COLUMNS);
+    dummy_rows * ADDR_COLUMNS.len()
+}
+
+fn pub_memory_dummy_rows(pub_memory_len: usize) -> usize {
+    (pub_memory_len >> 2) + 1
 }
 
 fn pad_with_last_row<F: IsFFTField>(trace: &mut TraceTable<F>, number_rows: usize) {
@@ -345,26 +391,38 @@ pub fn build_cairo_execution_trace(
     trace_cols.push(mul);
     trace_cols.push(selector);
 
-    if let Some(range_check_builtin_range) = public_inputs
-        .memory_segments
-        .get(&MemorySegment::RangeCheck)
-    {
-        add_rc_builtin_columns(&mut trace_cols, range_check_builtin_range.clone(), memory);
+    let layout = TraceLayout::new(&public_inputs.memory_segments);
+    for rc_builtin in layout.range_check_builtins.iter() {
+        debug_assert_eq!(trace_cols.len(), rc_builtin.parts.start);
+        let range_check_builtin_range =
+            public_inputs.memory_segments[&rc_builtin.layout.segment].clone();
+        add_rc_builtin_columns(
+            &mut trace_cols,
+            range_check_builtin_range,
+            memory,
+            &rc_builtin.layout,
+        );
     }
+    debug_assert_eq!(trace_cols.len(), layout.num_main_columns());
 
     TraceTable::new_from_cols(&trace_cols)
 }
 
-// Build range-check builtin columns: rc_0, rc_1, ... , rc_7, rc_value
+// Build range-check builtin columns: rc_0, rc_1, ... , rc_{n_parts - 1}, rc_value
 fn add_rc_builtin_columns(
     trace_cols: &mut Vec<Vec<FE>>,
     range_check_builtin_range: Range<u64>,
     memory: &CairoMemory,
+    rc_builtin: &RangeCheckBuiltinLayout,
 ) {
     let range_checked_values: Vec<&FE> = range_check_builtin_range
         .map(|addr| memory.get(&addr).unwrap())
         .collect();
-    let mut rc_trace_columns = decompose_rc_values_into_trace_columns(&range_checked_values);
+    let mut rc_trace_columns = decompose_rc_values_into_trace_columns(
+        &range_checked_values,
+        rc_builtin.n_parts,
+        rc_builtin.part_bits,
+    );
 
     // rc decomposition columns are appended with zeros and then pushed to the trace table
     rc_trace_columns.iter_mut().for_each(|column| {
@@ -601,14 +659,21 @@ fn rows_to_cols<const N: usize>(rows: &[[FE; N]]) -> Vec<Vec<FE>> {
         .collect::<Vec<Vec<FE>>>()
 }
 
-fn decompose_rc_values_into_trace_columns(rc_values: &[&FE]) -> [Vec<FE>; 8] {
-    let mask = UnsignedInteger::from_hex("FFFF").unwrap();
+/// Decomposes the values in `n_parts` parts of `part_bits` bits, from the least
+/// significant one. Higher bits are dropped, so the decomposition of values out of
+/// range doesn't pack back to them.
+fn decompose_rc_values_into_trace_columns(
+    rc_values: &[&FE],
+    n_parts: usize,
+    part_bits: usize,
+) -> Vec<Vec<FE>> {
+    let mask = UnsignedInteger::from_u64((1 << part_bits) - 1);
     let mut rc_base_types: Vec<UnsignedInteger<4>> =
         rc_values.iter().map(|x| x.representative()).collect();
 
     let mut decomposition_columns: Vec<Vec<FE>> = Vec::new();
 
-    for _ in 0..8 {
+    for _ in 0..n_parts {
         decomposition_columns.push(
             rc_base_types
                 .iter()
@@ -616,11 +681,10 @@ fn decompose_rc_values_into_trace_columns(rc_values: &[&FE]) -> [Vec<FE>; 8] {
                 .collect(),
         );
 
-        rc_base_types = rc_base_types.iter().map(|&x| x >> 16).collect();
+        rc_base_types = rc_base_types.iter().map(|&x| x >> part_bits).collect();
     }
 
-    // This can't fail since we have 8 pushes
-    decomposition_columns.try_into().unwrap()
+    decomposition_columns
 }
 
 #[cfg(test)]
@@ -640,7 +704,7 @@ mod test {
         let one_two_three = FE::from_hex("00010002000300040005000600070008").unwrap();
 
         let decomposition_columns =
-            decompose_rc_values_into_trace_columns(&[&fifteen, &sixteen, &one_two_three]);
+            decompose_rc_values_into_trace_columns(&[&fifteen, &sixteen, &one_two_three], 8, 16);
 
         for row in &decomposition_columns {
             assert_eq!(row[0], FE::from_hex("F").unwrap());
@@ -1185,10 +1249,10 @@ mod test {
 
     #[test]
     fn test_add_missing_values_to_offsets_column() {
-        let mut main_trace = TraceTable::<Stark252PrimeField> {
-            table: (0..34 * 2).map(FieldElement::from).collect(),
-            n_cols: 34,
-        };
+        let mut main_trace = TraceTable::<Stark252PrimeField>::new(
+            (0..34 * 2).map(FieldElement::from).collect(),
+            34,
+        );
         let missing_values = vec![
             FieldElement::from(1),
             FieldElement::from(2),
@@ -0,0 +1,120 @@
+use lambdaworks_math::traits::ByteConversion;
+
+use super::{errors::FeltConversionError, vm_adapter::Felt252};
+use crate::FE;
+
+/// Size in bytes of the encoding of a Stark252 field element.
+pub const FELT_BYTES: usize = 32;
+
+/// Converts a big-endian encoded value into a field element, rejecting
+/// values that are not smaller than the field modulus.
+pub fn fe_from_bytes_be(bytes: &[u8]) -> Result<FE, FeltConversionError> {
+    if bytes.len() != FELT_BYTES {
+        return Err(FeltConversionError::InvalidLength(bytes.len()));
+    }
+    let value =
+        FE::from_bytes_be(bytes).map_err(|_| FeltConversionError::InvalidLength(bytes.len()))?;
+
+    // Values not smaller than the modulus are reduced, so their encoding changes.
+    if value.to_bytes_be() != bytes {
+        return Err(FeltConversionError::NonCanonical);
+    }
+    Ok(value)
+}
+
+/// Same as `fe_from_bytes_be`, for little-endian encoded values.
+pub fn fe_from_bytes_le(bytes: &[u8]) -> Result<FE, FeltConversionError> {
+    let mut bytes_be = bytes.to_vec();
+    bytes_be.reverse();
+    fe_from_bytes_be(&bytes_be)
+}
+
+/// Converts a cairo-vm felt into a field element. Both represent elements of the
+/// same field, so the conversion never fails.
+pub fn fe_from_felt252(felt: &Felt252) -> FE {
+    fe_from_bytes_be(&felt.to_be_bytes()).expect("Felt252 values are smaller than the modulus")
+}
+
+pub fn felt252_from_fe(value: &FE) -> Felt252 {
+    Felt252::from_bytes_be(&value.to_bytes_be())
+}
+
+/// Returns the value of the field element as a `u64`, if it fits in one.
+pub fn fe_to_u64(value: &FE) -> Option<u64> {
+    let limbs = value.representative().limbs;
+    // Limbs are stored from the most significant to the least significant one
+    match limbs {
+        [0, 0, 0, low] => Some(low),
+        _ => None,
+    }
+}
+
+pub fn fes_from_felt252s(felts: &[Felt252]) -> Vec<FE> {
+    felts.iter().map(fe_from_felt252).collect()
+}
+
+pub fn felt252s_from_fes(values: &[FE]) -> Vec<Felt252> {
+    values.iter().map(felt252_from_fe).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn felt252_conversion_roundtrip() {
+        let values = vec![
+            FE::zero(),
+            FE::one(),
+            FE::from(1234),
+            -FE::one(),
+            FE::from_hex("0x100000000000000000000000000000001").unwrap(),
+        ];
+
+        assert_eq!(fes_from_felt252s(&felt252s_from_fes(&values)), values);
+    }
+
+    #[test]
+    fn felt252_conversion_keeps_the_value() {
+        assert_eq!(fe_from_felt252(&Felt252::from(1234)), FE::from(1234));
+        assert_eq!(
+            fe_from_felt252(&(Felt252::from(0) - Felt252::from(1))),
+            -FE::one()
+        );
+    }
+
+    #[test]
+    fn values_not_smaller_than_the_modulus_are_rejected() {
+        let mut modulus_be = (-FE::one()).to_bytes_be();
+        *modulus_be.last_mut().unwrap() += 1;
+
+        assert_eq!(
+            fe_from_bytes_be(&modulus_be),
+            Err(FeltConversionError::NonCanonical)
+        );
+        assert_eq!(
+            fe_from_bytes_be(&[u8::MAX; FELT_BYTES]),
+            Err(FeltConversionError::NonCanonical)
+        );
+    }
+
+    #[test]
+    fn only_values_fitting_in_u64_are_converted() {
+        assert_eq!(fe_to_u64(&FE::from(u64::MAX)), Some(u64::MAX));
+        assert_eq!(fe_to_u64(&(FE::from(u64::MAX) + FE::one())), None);
+        assert_eq!(fe_to_u64(&-FE::one()), None);
+    }
+
+    #[test]
+    fn little_endian_values_are_converted() {
+        let mut bytes_le = [0u8; FELT_BYTES];
+        bytes_le[0] = 2;
+        bytes_le[1] = 1;
+
+        assert_eq!(fe_from_bytes_le(&bytes_le), Ok(FE::from(258)));
+        assert_eq!(
+            fe_from_bytes_le(&bytes_le[..8]),
+            Err(FeltConversionError::InvalidLength(8))
+        );
+    }
+}
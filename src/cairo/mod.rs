@@ -1,8 +1,17 @@
 pub mod air;
+pub mod batch;
 pub mod cairo_layout;
 pub mod cairo_mem;
+pub mod constraints;
+pub mod container;
 pub mod decode;
 pub mod errors;
 pub mod execution_trace;
+pub mod felt;
+pub mod output_commitment;
+pub mod public_inputs_json;
 pub mod register_states;
 pub mod runner;
+pub mod statement;
+pub mod trace_layout;
+pub mod vm_adapter;
@@ -0,0 +1,184 @@
+//! Commitment to the outputs of a Cairo program, so light clients can check an
+//! output cell with a short Merkle path instead of the whole public memory.
+//!
+//! The outputs are the values of the output builtin segment, which the proof binds
+//! through the public memory. The commitment is the Poseidon hash of the number of
+//! outputs and the root of a Poseidon tree whose leaves are the outputs in order,
+//! cheap to check in Cairo too. The tree pads its leaves by repeating the last one,
+//! so the number of outputs tells the openings of outputs from the ones of padding.
+//!
+//! It's computed from the public inputs and absorbed in the transcript of their
+//! proofs, see `PublicInputs::statement_commitment`, so it can only be trusted once
+//! the proof is verified with them.
+
+use lambdaworks_crypto::merkle_tree::{merkle::MerkleTree, proof::Proof};
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+use super::air::PublicInputs;
+use crate::{
+    starks::{
+        config::Commitment,
+        gadgets::poseidon::PoseidonParameters,
+        poseidon_backend::{felt_from_node, node_from_felt, PoseidonTree},
+    },
+    FE,
+};
+
+type OutputTreeBackend = PoseidonTree<Stark252PrimeField>;
+
+/// An output of the program and the path to it in the output commitment.
+#[derive(Clone, Debug)]
+pub struct OutputOpening {
+    /// Position of the output in the output segment.
+    pub index: usize,
+    pub value: FE,
+    /// Number of outputs of the program.
+    pub num_outputs: usize,
+    /// Root of the tree of the outputs.
+    pub root: Commitment,
+    pub path: Proof<Commitment>,
+}
+
+impl OutputOpening {
+    /// Checks that `value` is the output at `index` of the outputs committed to by
+    /// `output_commitment`.
+    pub fn verify(&self, output_commitment: &Commitment) -> bool {
+        self.index < self.num_outputs
+            && commit_outputs(self.num_outputs, &self.root) == *output_commitment
+            && self
+                .path
+                .verify::<OutputTreeBackend>(&self.root, self.index, &self.value)
+    }
+}
+
+/// Poseidon hash of the number of outputs and the root of their tree.
+fn commit_outputs(num_outputs: usize, root: &Commitment) -> Commitment {
+    node_from_felt(&PoseidonParameters::with_starknet(|params| {
+        params.hash(&FE::from(num_outputs as u64), &felt_from_node(root))
+    }))
+}
+
+impl PublicInputs {
+    fn output_tree(&self) -> Option<(usize, MerkleTree<OutputTreeBackend>)> {
+        let outputs = self.outputs();
+        (!outputs.is_empty()).then(|| (outputs.len(), MerkleTree::build(&outputs)))
+    }
+
+    /// Commitment to the outputs, see `outputs`. `None` if the program has no
+    /// outputs.
+    pub fn output_commitment(&self) -> Option<Commitment> {
+        self.output_tree()
+            .map(|(num_outputs, tree)| commit_outputs(num_outputs, &tree.root))
+    }
+
+    /// The output at `index` with its path in `output_commitment`. `None` if there
+    /// is no such output.
+    pub fn open_output(&self, index: usize) -> Option<OutputOpening> {
+        let value = *self.outputs().get(index)?;
+        let (num_outputs, tree) = self.output_tree()?;
+        let path = tree.get_proof_by_pos(index)?;
+        Some(OutputOpening {
+            index,
+            value,
+            num_outputs,
+            root: tree.root,
+            path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::cairo::air::{MemoryPadding, MemorySegment, MemorySegmentMap};
+
+    fn public_inputs_with_outputs(outputs: &[u64]) -> PublicInputs {
+        let output_start = 10;
+        let public_memory = outputs
+            .iter()
+            .enumerate()
+            .map(|(i, output)| (FE::from(output_start + i as u64), FE::from(*output)))
+            .collect();
+
+        PublicInputs {
+            pc_init: FE::one(),
+            ap_init: FE::zero(),
+            fp_init: FE::zero(),
+            pc_final: FE::zero(),
+            ap_final: FE::zero(),
+            range_check_min: None,
+            range_check_max: None,
+            memory_address_max: None,
+            memory_segments: MemorySegmentMap::from([(
+                MemorySegment::Output,
+                output_start..output_start + outputs.len() as u64,
+            )]),
+            public_memory,
+            num_steps: 1,
+            memory_padding: MemoryPadding::default(),
+            num_memory_holes: 0,
+            builtins: vec!["output".to_string()],
+            memory_commitment: None,
+        }
+    }
+
+    #[test]
+    fn every_output_is_opened_against_the_commitment() {
+        let pub_inputs = public_inputs_with_outputs(&[3, 1, 4, 1, 5]);
+        let commitment = pub_inputs.output_commitment().unwrap();
+
+        for (index, output) in [3, 1, 4, 1, 5].into_iter().enumerate() {
+            let opening = pub_inputs.open_output(index).unwrap();
+            assert_eq!(opening.value, FE::from(output));
+            assert!(opening.verify(&commitment));
+        }
+        assert!(pub_inputs.open_output(5).is_none());
+    }
+
+    #[test]
+    fn openings_of_other_outputs_are_rejected() {
+        let pub_inputs = public_inputs_with_outputs(&[3, 1, 4]);
+        let commitment = pub_inputs.output_commitment().unwrap();
+
+        let mut opening = pub_inputs.open_output(0).unwrap();
+        opening.value = FE::from(4);
+        assert!(!opening.verify(&commitment));
+
+        let mut opening = pub_inputs.open_output(0).unwrap();
+        opening.index = 2;
+        assert!(!opening.verify(&commitment));
+
+        let other_commitment = public_inputs_with_outputs(&[3, 1, 5])
+            .output_commitment()
+            .unwrap();
+        assert!(!pub_inputs.open_output(0).unwrap().verify(&other_commitment));
+    }
+
+    #[test]
+    fn openings_of_the_padding_are_rejected() {
+        // The tree of three outputs repeats the last one, as the one of four does
+        let pub_inputs = public_inputs_with_outputs(&[3, 1, 4]);
+        let padded_pub_inputs = public_inputs_with_outputs(&[3, 1, 4, 4]);
+        let commitment = pub_inputs.output_commitment().unwrap();
+        assert_ne!(padded_pub_inputs.output_commitment().unwrap(), commitment);
+
+        let mut opening = padded_pub_inputs.open_output(3).unwrap();
+        assert!(!opening.verify(&commitment));
+        opening.num_outputs = 3;
+        assert!(!opening.verify(&commitment));
+    }
+
+    #[test]
+    fn programs_without_outputs_have_no_commitment() {
+        let pub_inputs = PublicInputs {
+            memory_segments: MemorySegmentMap::new(),
+            public_memory: HashMap::new(),
+            ..public_inputs_with_outputs(&[])
+        };
+
+        assert_eq!(pub_inputs.output_commitment(), None);
+        assert!(pub_inputs.open_output(0).is_none());
+    }
+}
@@ -0,0 +1,366 @@
+//! JSON representation of the Cairo public inputs, for verifiers that receive them
+//! over the wire instead of sharing the Rust structs with the prover.
+//!
+//! Field elements are hex strings of their canonical representative, prefixed by
+//! `0x`. Counts, bounds and segment addresses are JSON numbers:
+//!
+//! ```json
+//! {
+//!   "pc_init": "0x1",
+//!   "ap_init": "0x17",
+//!   "fp_init": "0x17",
+//!   "pc_final": "0x5",
+//!   "ap_final": "0x1a",
+//!   "range_check_min": 32766,
+//!   "range_check_max": 32769,
+//!   "memory_address_max": 26,
+//!   "num_steps": 7,
+//!   "memory_padding": { "dummy_accesses": 8, "padding_rows": 7 },
+//!   "num_memory_holes": 0,
+//!   "builtins": ["output"],
+//!   "memory_segments": { "output": { "begin": 25, "end": 26 } },
+//!   "public_memory": [{ "address": "0x1", "value": "0x40780017fff7fff" }],
+//!   "memory_commitment": null
+//! }
+//! ```
+//!
+//! `range_check_min`, `range_check_max` and `memory_address_max` are `null` when
+//! unset. The segments are keyed by the name of their builtin (`range_check`,
+//! `output` or `range_check96`). The public memory holds the program, from address
+//! 1, and the output cells, sorted by address. Each address appears once. The memory commitment is the hex
+//! string of the 32 bytes of the root, or `null` when unset.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+use super::{
+    air::{MemoryPadding, MemorySegment, MemorySegmentMap, PublicInputs},
+    errors::PublicInputsJsonError,
+    felt::fe_from_bytes_be,
+};
+use crate::{
+    starks::config::{Commitment, COMMITMENT_SIZE},
+    FE,
+};
+
+impl PublicInputs {
+    /// Serializes the public inputs with the schema of the `public_inputs_json` module.
+    pub fn to_json(&self) -> String {
+        let mut public_memory: Vec<_> = self.public_memory.iter().collect();
+        public_memory.sort_by_key(|(address, _)| address.to_bytes_be());
+        let public_memory: Vec<_> = public_memory
+            .into_iter()
+            .map(|(address, value)| {
+                json!({ "address": felt_to_hex(address), "value": felt_to_hex(value) })
+            })
+            .collect();
+
+        let memory_segments: Map<String, Value> = self
+            .memory_segments
+            .iter()
+            .map(|(segment, range)| {
+                (
+                    segment_name(segment).to_string(),
+                    json!({ "begin": range.start, "end": range.end }),
+                )
+            })
+            .collect();
+
+        let value = json!({
+            "pc_init": felt_to_hex(&self.pc_init),
+            "ap_init": felt_to_hex(&self.ap_init),
+            "fp_init": felt_to_hex(&self.fp_init),
+            "pc_final": felt_to_hex(&self.pc_final),
+            "ap_final": felt_to_hex(&self.ap_final),
+            "range_check_min": self.range_check_min,
+            "range_check_max": self.range_check_max,
+            "memory_address_max": self.memory_address_max,
+            "num_steps": self.num_steps,
+            "memory_padding": {
+                "dummy_accesses": self.memory_padding.dummy_accesses,
+                "padding_rows": self.memory_padding.padding_rows,
+            },
+            "num_memory_holes": self.num_memory_holes,
+            "builtins": self.builtins,
+            "memory_segments": memory_segments,
+            "public_memory": public_memory,
+            "memory_commitment": self.memory_commitment.map(|root| commitment_to_hex(&root)),
+        });
+        serde_json::to_string_pretty(&value).expect("JSON values are always serializable")
+    }
+
+    /// Parses public inputs with the schema of the `public_inputs_json` module. Field
+    /// elements must be canonical, and every field must be present.
+    pub fn from_json(json: &str) -> Result<Self, PublicInputsJsonError> {
+        let value: Value = serde_json::from_str(json)?;
+
+        let felt = |name: &'static str| {
+            value
+                .get(name)
+                .and_then(Value::as_str)
+                .and_then(felt_from_hex)
+                .ok_or(PublicInputsJsonError::InvalidField(name))
+        };
+        let count = |value: &Value, name: &'static str| {
+            value
+                .get(name)
+                .and_then(Value::as_u64)
+                .and_then(|count| usize::try_from(count).ok())
+                .ok_or(PublicInputsJsonError::InvalidField(name))
+        };
+        let optional = |name: &'static str| match value.get(name) {
+            Some(Value::Null) => Ok(None),
+            Some(bound) => bound
+                .as_u64()
+                .map(Some)
+                .ok_or(PublicInputsJsonError::InvalidField(name)),
+            None => Err(PublicInputsJsonError::InvalidField(name)),
+        };
+        let range_check_bound = |name: &'static str| {
+            optional(name)?
+                .map(u16::try_from)
+                .transpose()
+                .map_err(|_| PublicInputsJsonError::InvalidField(name))
+        };
+
+        let memory_padding = value
+            .get("memory_padding")
+            .ok_or(PublicInputsJsonError::InvalidField("memory_padding"))?;
+
+        let builtins = value
+            .get("builtins")
+            .and_then(Value::as_array)
+            .and_then(|builtins| {
+                builtins
+                    .iter()
+                    .map(|name| name.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or(PublicInputsJsonError::InvalidField("builtins"))?;
+
+        let memory_segments = value
+            .get("memory_segments")
+            .and_then(Value::as_object)
+            .and_then(|segments| {
+                segments
+                    .iter()
+                    .map(|(name, range)| {
+                        let segment = segment_from_name(name)?;
+                        let begin = range.get("begin")?.as_u64()?;
+                        let end = range.get("end")?.as_u64()?;
+                        Some((segment, begin..end))
+                    })
+                    .collect::<Option<MemorySegmentMap>>()
+            })
+            .ok_or(PublicInputsJsonError::InvalidField("memory_segments"))?;
+
+        let public_memory_cells = value
+            .get("public_memory")
+            .and_then(Value::as_array)
+            .and_then(|cells| {
+                cells
+                    .iter()
+                    .map(|cell| {
+                        let address = felt_from_hex(cell.get("address")?.as_str()?)?;
+                        let value = felt_from_hex(cell.get("value")?.as_str()?)?;
+                        Some((address, value))
+                    })
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or(PublicInputsJsonError::InvalidField("public_memory"))?;
+        // A repeated address would overwrite the value of the previous one
+        let mut public_memory = HashMap::with_capacity(public_memory_cells.len());
+        for (address, value) in public_memory_cells {
+            if public_memory.insert(address.clone(), value).is_some() {
+                return Err(PublicInputsJsonError::DuplicatePublicMemoryAddress(
+                    felt_to_hex(&address),
+                ));
+            }
+        }
+
+        let memory_commitment = match value.get("memory_commitment") {
+            Some(Value::Null) => None,
+            Some(root) => Some(
+                root.as_str()
+                    .and_then(commitment_from_hex)
+                    .ok_or(PublicInputsJsonError::InvalidField("memory_commitment"))?,
+            ),
+            None => return Err(PublicInputsJsonError::InvalidField("memory_commitment")),
+        };
+
+        Ok(Self {
+            pc_init: felt("pc_init")?,
+            ap_init: felt("ap_init")?,
+            fp_init: felt("fp_init")?,
+            pc_final: felt("pc_final")?,
+            ap_final: felt("ap_final")?,
+            range_check_min: range_check_bound("range_check_min")?,
+            range_check_max: range_check_bound("range_check_max")?,
+            memory_address_max: optional("memory_address_max")?,
+            memory_segments,
+            public_memory,
+            num_steps: count(&value, "num_steps")?,
+            memory_padding: MemoryPadding {
+                dummy_accesses: count(memory_padding, "dummy_accesses")?,
+                padding_rows: count(memory_padding, "padding_rows")?,
+            },
+            num_memory_holes: count(&value, "num_memory_holes")?,
+            builtins,
+            memory_commitment,
+        })
+    }
+}
+
+fn segment_name(segment: &MemorySegment) -> &'static str {
+    match segment {
+        MemorySegment::RangeCheck => "range_check",
+        MemorySegment::Output => "output",
+        MemorySegment::RangeCheck96 => "range_check96",
+    }
+}
+
+fn segment_from_name(name: &str) -> Option<MemorySegment> {
+    match name {
+        "range_check" => Some(MemorySegment::RangeCheck),
+        "output" => Some(MemorySegment::Output),
+        "range_check96" => Some(MemorySegment::RangeCheck96),
+        _ => None,
+    }
+}
+
+fn felt_to_hex(value: &FE) -> String {
+    let hex: String = value
+        .to_bytes_be()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    match hex.trim_start_matches('0') {
+        "" => "0x0".to_string(),
+        digits => format!("0x{digits}"),
+    }
+}
+
+/// Parses a `0x` prefixed hex string, rejecting values that aren't smaller than
+/// the modulus instead of reducing them.
+fn felt_from_hex(hex: &str) -> Option<FE> {
+    let digits = hex.strip_prefix("0x")?;
+    if digits.is_empty() || digits.len() > 64 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits = format!("{digits:0>64}");
+    let bytes: Vec<u8> = (0..64)
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+        .collect::<Result<_, _>>()
+        .ok()?;
+    fe_from_bytes_be(&bytes).ok()
+}
+
+fn commitment_to_hex(commitment: &Commitment) -> String {
+    let hex: String = commitment
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("0x{hex}")
+}
+
+fn commitment_from_hex(hex: &str) -> Option<Commitment> {
+    let digits = hex.strip_prefix("0x")?;
+    if digits.len() != 2 * COMMITMENT_SIZE || !digits.is_ascii() {
+        return None;
+    }
+    let bytes: Vec<u8> = (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+        .collect::<Result<_, _>>()
+        .ok()?;
+    bytes.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cairo::runner::{
+        hints::HintConfig,
+        limits::ExecutionLimits,
+        run::{
+            cairo0_program_path, generate_prover_args, generate_prover_args_with_memory_commitment,
+            CairoVersion,
+        },
+    };
+
+    #[test]
+    fn public_inputs_json_roundtrip() {
+        let program_content = std::fs::read(cairo0_program_path("output_program.json")).unwrap();
+        let pub_inputs = generate_prover_args_with_memory_commitment(
+            &program_content,
+            &CairoVersion::V0,
+            &None,
+            &HintConfig::default(),
+            &ExecutionLimits::unlimited(),
+        )
+        .unwrap()
+        .pub_inputs;
+
+        let json = pub_inputs.to_json();
+        let parsed = PublicInputs::from_json(&json).unwrap();
+
+        assert_eq!(parsed.to_json(), json);
+        assert_eq!(parsed.public_memory, pub_inputs.public_memory);
+        assert_eq!(parsed.memory_segments, pub_inputs.memory_segments);
+        assert_eq!(parsed.pc_final, pub_inputs.pc_final);
+        assert_eq!(parsed.range_check_max, pub_inputs.range_check_max);
+        assert!(pub_inputs.memory_commitment.is_some());
+        assert_eq!(parsed.memory_commitment, pub_inputs.memory_commitment);
+    }
+
+    #[test]
+    fn felts_must_be_canonical() {
+        assert_eq!(felt_from_hex("0x0"), Some(FE::zero()));
+        assert_eq!(felt_to_hex(&FE::from(0x1a)), "0x1a");
+        assert_eq!(felt_from_hex("0x1a"), Some(FE::from(0x1a)));
+        assert_eq!(felt_from_hex(&felt_to_hex(&-FE::one())), Some(-FE::one()));
+
+        // The modulus of the Stark252 field
+        assert_eq!(
+            felt_from_hex("0x800000000000011000000000000000000000000000000000000000000000001"),
+            None
+        );
+        assert_eq!(felt_from_hex("1a"), None);
+        assert_eq!(felt_from_hex("0x"), None);
+    }
+
+    #[test]
+    fn missing_fields_are_reported() {
+        let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+        let (_, pub_inputs) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+
+        let mut value: Value = serde_json::from_str(&pub_inputs.to_json()).unwrap();
+        value.as_object_mut().unwrap().remove("num_steps");
+
+        assert!(matches!(
+            PublicInputs::from_json(&value.to_string()),
+            Err(PublicInputsJsonError::InvalidField("num_steps"))
+        ));
+    }
+
+    #[test]
+    fn duplicate_public_memory_addresses_are_rejected() {
+        let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+        let (_, pub_inputs) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+
+        let mut value: Value = serde_json::from_str(&pub_inputs.to_json()).unwrap();
+        let cells = value["public_memory"].as_array_mut().unwrap();
+        let mut cell = cells[0].clone();
+        cell["value"] = json!("0x2a");
+        cells.push(cell);
+
+        assert!(matches!(
+            PublicInputs::from_json(&value.to_string()),
+            Err(PublicInputsJsonError::DuplicatePublicMemoryAddress(address)) if address == "0x1"
+        ));
+    }
+}
@@ -2,6 +2,7 @@ use super::{
     cairo_mem::CairoMemory,
     decode::{instruction_flags::CairoInstructionFlags, instruction_offsets::InstructionOffsets},
     errors::{CairoImportError, InstructionDecodingError},
+    vm_adapter::RelocatedTraceEntry,
 };
 use std::fs;
 
@@ -83,6 +84,22 @@ impl RegisterStates {
     }
 }
 
+/// Builds the register states from the relocated trace of cairo-vm.
+impl<E: RelocatedTraceEntry> From<&[E]> for RegisterStates {
+    fn from(relocated_trace: &[E]) -> Self {
+        let rows = relocated_trace
+            .iter()
+            .map(|entry| RegistersState {
+                pc: entry.pc(),
+                fp: entry.fp(),
+                ap: entry.ap(),
+            })
+            .collect();
+
+        Self { rows }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{cairo::decode::instruction_flags::*, FE};
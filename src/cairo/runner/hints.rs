@@ -0,0 +1,204 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
+
+use serde_json::Value;
+
+use crate::cairo::{register_states::RegisterStates, vm_adapter::HintFunc};
+
+/// Relocated address where the program segment of a Cairo 0 program starts.
+const PROGRAM_BASE: u64 = 1;
+
+/// Which hints a Cairo 0 program is allowed to contain.
+#[derive(Clone, Debug, Default)]
+pub enum HintPolicy {
+    /// Programs with hints are rejected.
+    Deny,
+    /// The standard hint set of cairo-vm and the registered custom hints are allowed.
+    #[default]
+    AllowStandard,
+    /// Only hints whose code is in the set, or that were registered as custom hints, are allowed.
+    Whitelist(HashSet<String>),
+}
+
+/// Hint configuration used when running a Cairo 0 program.
+///
+/// Cairo 1 programs always run with the hints of their contract class, so this
+/// configuration doesn't apply to them.
+#[derive(Clone, Default)]
+pub struct HintConfig {
+    policy: HintPolicy,
+    custom_hints: HashMap<String, Rc<HintFunc>>,
+}
+
+impl HintConfig {
+    pub fn new(policy: HintPolicy) -> Self {
+        Self {
+            policy,
+            custom_hints: HashMap::new(),
+        }
+    }
+
+    /// Registers an implementation for the hint with code `hint_code`.
+    pub fn with_custom_hint(mut self, hint_code: &str, hint_func: HintFunc) -> Self {
+        self.custom_hints
+            .insert(hint_code.to_string(), Rc::new(hint_func));
+        self
+    }
+
+    pub fn policy(&self) -> &HintPolicy {
+        &self.policy
+    }
+
+    /// Returns `true` if a program is allowed to contain the hint with code `hint_code`.
+    pub fn is_allowed(&self, hint_code: &str) -> bool {
+        match &self.policy {
+            HintPolicy::Deny => false,
+            HintPolicy::AllowStandard => true,
+            HintPolicy::Whitelist(allowed_hints) => {
+                allowed_hints.contains(hint_code) || self.custom_hints.contains_key(hint_code)
+            }
+        }
+    }
+
+    /// Returns the first hint of the program not allowed by the policy, if any.
+    pub(crate) fn find_disallowed_hint<'a>(
+        &self,
+        program_hints: &'a ProgramHints,
+    ) -> Option<&'a str> {
+        program_hints
+            .0
+            .values()
+            .flatten()
+            .map(String::as_str)
+            .find(|hint_code| !self.is_allowed(hint_code))
+    }
+
+    /// Implementations of the custom hints, indexed by their code.
+    pub(crate) fn custom_hints(&self) -> &HashMap<String, Rc<HintFunc>> {
+        &self.custom_hints
+    }
+}
+
+/// Codes of the hints of a compiled Cairo 0 program, indexed by the pc where they run.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ProgramHints(BTreeMap<u64, Vec<String>>);
+
+impl ProgramHints {
+    /// Reads the hints of a compiled Cairo 0 program, stored in its `hints` field.
+    pub(crate) fn from_program_json(program_content: &[u8]) -> Result<Self, serde_json::Error> {
+        let program: Value = serde_json::from_slice(program_content)?;
+
+        let hints = program
+            .get("hints")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(pc, hints)| {
+                let pc = pc.parse::<u64>().ok()?;
+                let codes = hints
+                    .as_array()?
+                    .iter()
+                    .filter_map(|hint| hint.get("code")?.as_str().map(str::to_string))
+                    .collect();
+                Some((pc, codes))
+            })
+            .collect();
+
+        Ok(Self(hints))
+    }
+}
+
+/// Information about a program execution that is not part of the proven statement,
+/// but is useful for verifying parties reasoning about the nondeterminism of the program.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionSummary {
+    /// Number of times each hint ran, indexed by its code.
+    pub executed_hints: BTreeMap<String, usize>,
+}
+
+impl ExecutionSummary {
+    /// Builds the summary of a Cairo 0 execution from its register states. A hint runs
+    /// right before the instruction at its pc, so every step is matched against the hints.
+    pub(crate) fn new(program_hints: &ProgramHints, register_states: &RegisterStates) -> Self {
+        let mut executed_hints = BTreeMap::new();
+
+        for row in register_states.rows.iter() {
+            let Some(hints) = row
+                .pc
+                .checked_sub(PROGRAM_BASE)
+                .and_then(|pc| program_hints.0.get(&pc))
+            else {
+                continue;
+            };
+            for hint_code in hints {
+                *executed_hints.entry(hint_code.clone()).or_insert(0) += 1;
+            }
+        }
+
+        Self { executed_hints }
+    }
+
+    pub fn has_hints(&self) -> bool {
+        !self.executed_hints.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cairo::register_states::RegistersState;
+
+    use super::*;
+
+    const PROGRAM_JSON: &str = r#"{
+        "hints": {
+            "0": [{"accessible_scopes": [], "code": "memory[ap] = 1", "flow_tracking_data": {}}],
+            "3": [{"accessible_scopes": [], "code": "memory[ap] = 2", "flow_tracking_data": {}}]
+        }
+    }"#;
+
+    fn register_states(pcs: &[u64]) -> RegisterStates {
+        RegisterStates {
+            rows: pcs
+                .iter()
+                .map(|pc| RegistersState {
+                    pc: *pc,
+                    fp: 0,
+                    ap: 0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn summary_counts_the_hints_of_the_executed_steps() {
+        let program_hints = ProgramHints::from_program_json(PROGRAM_JSON.as_bytes()).unwrap();
+
+        let summary = ExecutionSummary::new(&program_hints, &register_states(&[1, 2, 1, 3]));
+
+        assert_eq!(
+            summary.executed_hints,
+            BTreeMap::from([("memory[ap] = 1".to_string(), 2)])
+        );
+    }
+
+    #[test]
+    fn whitelist_policy_rejects_hints_not_in_the_list() {
+        let program_hints = ProgramHints::from_program_json(PROGRAM_JSON.as_bytes()).unwrap();
+        let config = HintConfig::new(HintPolicy::Whitelist(HashSet::from([
+            "memory[ap] = 1".to_string()
+        ])));
+
+        assert_eq!(
+            config.find_disallowed_hint(&program_hints),
+            Some("memory[ap] = 2")
+        );
+    }
+
+    #[test]
+    fn deny_policy_accepts_programs_without_hints() {
+        let program_hints = ProgramHints::from_program_json(br#"{"hints": {}}"#).unwrap();
+        let config = HintConfig::new(HintPolicy::Deny);
+
+        assert_eq!(config.find_disallowed_hint(&program_hints), None);
+    }
+}
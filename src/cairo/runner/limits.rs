@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
+/// Number of steps run between two checks of the execution limits.
+pub(crate) const STEPS_PER_LIMITS_CHECK: usize = 1 << 16;
+
+/// Resource limits for running untrusted programs.
+///
+/// The limits are checked every `STEPS_PER_LIMITS_CHECK` steps, so a program can
+/// run past the memory or time limits by at most that amount of steps before
+/// being stopped. The step limit is exact.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionLimits {
+    pub max_steps: Option<usize>,
+    pub max_memory_cells: Option<usize>,
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum LimitExceeded {
+    #[error("The execution exceeded the maximum number of steps ({0})")]
+    Steps(usize),
+    #[error("The execution exceeded the maximum number of memory cells ({0})")]
+    MemoryCells(usize),
+    #[error("The execution exceeded the timeout ({0:?})")]
+    Timeout(Duration),
+}
+
+impl ExecutionLimits {
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    pub fn with_max_memory_cells(mut self, max_memory_cells: usize) -> Self {
+        self.max_memory_cells = Some(max_memory_cells);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self == &Self::unlimited()
+    }
+
+    /// Number of steps to run before the next check, given the steps already run.
+    pub(crate) fn next_chunk_size(&self, steps: usize) -> usize {
+        self.max_steps.map_or(STEPS_PER_LIMITS_CHECK, |max_steps| {
+            max_steps.saturating_sub(steps).min(STEPS_PER_LIMITS_CHECK)
+        })
+    }
+
+    /// Checks the resources used by an unfinished execution.
+    pub(crate) fn check(
+        &self,
+        steps: usize,
+        memory_cells: usize,
+        elapsed: Duration,
+    ) -> Result<(), LimitExceeded> {
+        if let Some(max_steps) = self.max_steps {
+            if steps >= max_steps {
+                return Err(LimitExceeded::Steps(max_steps));
+            }
+        }
+        self.check_memory_cells(memory_cells)?;
+        if let Some(timeout) = self.timeout {
+            if elapsed > timeout {
+                return Err(LimitExceeded::Timeout(timeout));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_memory_cells(&self, memory_cells: usize) -> Result<(), LimitExceeded> {
+        match self.max_memory_cells {
+            Some(max_memory_cells) if memory_cells > max_memory_cells => {
+                Err(LimitExceeded::MemoryCells(max_memory_cells))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_never_run_past_the_step_limit() {
+        let limits = ExecutionLimits::unlimited().with_max_steps(STEPS_PER_LIMITS_CHECK + 10);
+
+        assert_eq!(limits.next_chunk_size(0), STEPS_PER_LIMITS_CHECK);
+        assert_eq!(limits.next_chunk_size(STEPS_PER_LIMITS_CHECK), 10);
+        assert_eq!(
+            limits.check(STEPS_PER_LIMITS_CHECK + 10, 0, Duration::ZERO),
+            Err(LimitExceeded::Steps(STEPS_PER_LIMITS_CHECK + 10))
+        );
+    }
+
+    #[test]
+    fn check_reports_the_exceeded_limit() {
+        let limits = ExecutionLimits::unlimited()
+            .with_max_memory_cells(100)
+            .with_timeout(Duration::from_secs(1));
+
+        assert_eq!(limits.check(1000, 100, Duration::from_secs(1)), Ok(()));
+        assert_eq!(
+            limits.check(1000, 101, Duration::ZERO),
+            Err(LimitExceeded::MemoryCells(100))
+        );
+        assert_eq!(
+            limits.check(1000, 0, Duration::from_secs(2)),
+            Err(LimitExceeded::Timeout(Duration::from_secs(1)))
+        );
+    }
+}
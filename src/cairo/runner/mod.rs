@@ -1,3 +1,7 @@
 pub mod file_writer;
+pub mod hints;
+pub mod limits;
+pub(crate) mod output_stream;
+pub mod program;
 pub mod run;
 pub mod vec_writer;
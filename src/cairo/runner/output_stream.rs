@@ -0,0 +1,33 @@
+use crate::FE;
+
+/// Sends the values a program writes in the output builtin segment to a callback, in
+/// order and each one once, while the program runs.
+///
+/// The execution is run in chunks of `STEPS_PER_LIMITS_CHECK` steps, and the outputs
+/// written in a chunk are sent after it, up to the first cell not written yet or
+/// holding a pointer. The rest are sent once the memory is relocated, at the end of
+/// the run, skipping the cells that were never written.
+pub(crate) struct OutputStream<'a> {
+    on_output: &'a mut dyn FnMut(FE),
+    num_sent: usize,
+}
+
+impl<'a> OutputStream<'a> {
+    pub(crate) fn new(on_output: &'a mut dyn FnMut(FE)) -> Self {
+        Self {
+            on_output,
+            num_sent: 0,
+        }
+    }
+
+    /// Number of outputs sent, which is the offset in the output segment of the next
+    /// one while the program runs.
+    pub(crate) fn num_sent(&self) -> usize {
+        self.num_sent
+    }
+
+    pub(crate) fn send(&mut self, output: FE) {
+        (self.on_output)(output);
+        self.num_sent += 1;
+    }
+}
@@ -0,0 +1,186 @@
+use std::ops::Range;
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::run::{generate_prover_args, proving_layout, CairoVersion, Error};
+use crate::cairo::air::{plan_cairo_proof, CairoProvingPlan, PublicInputs};
+use crate::cairo::cairo_layout::{CairoBuiltin, CairoLayout};
+use crate::starks::{proof::options::ProofOptions, trace::TraceTable};
+use crate::PrimeField;
+
+/// A compiled Cairo program, ready to be run.
+///
+/// Cairo 0 programs are the JSON output of `cairo-compile`, and Cairo 1 programs
+/// are compiled contract classes in CASM format.
+#[derive(Clone, Debug)]
+pub struct CairoProgram {
+    content: Vec<u8>,
+    version: CairoVersion,
+}
+
+impl CairoProgram {
+    /// Loads a compiled program from its JSON content. The Cairo version is
+    /// detected from the format of the program.
+    pub fn from_json_bytes(content: &[u8]) -> Result<Self, Error> {
+        let program: Value = serde_json::from_slice(content)?;
+
+        // Only CASM contract classes have a `bytecode` field
+        let version = if program.get("bytecode").is_some() {
+            CairoVersion::V1
+        } else {
+            CairoVersion::V0
+        };
+
+        Ok(Self {
+            content: content.to_vec(),
+            version,
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let content = std::fs::read(path)?;
+        Self::from_json_bytes(&content)
+    }
+
+    /// Compiles a Cairo 1 contract from its source and loads it.
+    #[cfg(feature = "compiler")]
+    pub fn from_source(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let content = crate::cairo::vm_adapter::compile_cairo1(path.as_ref())?;
+        Ok(Self {
+            content,
+            version: CairoVersion::V1,
+        })
+    }
+
+    /// Builtins declared by the program. See `declared_builtins`.
+    pub fn builtins(&self) -> Result<Vec<CairoBuiltin>, Error> {
+        declared_builtins(&self.content, &self.version)
+    }
+
+    /// Layout the program is run and proven with, the first one supporting all of
+    /// its builtins. Fails if one of them can't be proven yet.
+    pub fn layout(&self) -> Result<CairoLayout, Error> {
+        proving_layout(&self.content, &self.version)
+    }
+
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+
+    pub fn version(&self) -> &CairoVersion {
+        &self.version
+    }
+
+    /// Runs the program and builds the trace and public inputs needed to prove it.
+    /// See `generate_prover_args`.
+    pub fn generate_prover_args(
+        &self,
+        output_range: &Option<Range<u64>>,
+    ) -> Result<(TraceTable<PrimeField>, PublicInputs), Error> {
+        generate_prover_args(&self.content, &self.version, output_range)
+    }
+
+    /// What proving the program with `proof_options` takes, found before running it
+    /// fully. See `plan_cairo_proof`.
+    pub fn proving_plan(&self, proof_options: &ProofOptions) -> Result<CairoProvingPlan, Error> {
+        plan_cairo_proof(&self.content, &self.version, proof_options)
+    }
+}
+
+/// Reads the builtins declared by a compiled program. For Cairo 0 programs they are
+/// in the `builtins` field, and for Cairo 1 contracts in the ones of the entrypoint.
+pub fn declared_builtins(
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+) -> Result<Vec<CairoBuiltin>, Error> {
+    let program: Value = serde_json::from_slice(program_content)?;
+
+    let builtins = match cairo_version {
+        CairoVersion::V0 => program.get("builtins"),
+        // We assume entrypoint 0 for only one function, as the runner does
+        CairoVersion::V1 => program.pointer("/entry_points_by_type/EXTERNAL/0/builtins"),
+    };
+
+    builtins
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|name| {
+            let name = name.as_str().unwrap_or_default();
+            CairoBuiltin::from_name(name).ok_or_else(|| Error::UnknownBuiltin(name.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cairo::runner::run::{cairo0_program_path, cairo1_program_path};
+
+    #[test]
+    fn cairo_version_is_detected_from_the_program() {
+        let cairo0_program =
+            CairoProgram::from_file(cairo0_program_path("fibonacci_5.json")).unwrap();
+        let cairo1_program =
+            CairoProgram::from_file(cairo1_program_path("fibonacci_cairo1.casm")).unwrap();
+
+        assert_eq!(cairo0_program.version(), &CairoVersion::V0);
+        assert_eq!(cairo1_program.version(), &CairoVersion::V1);
+    }
+
+    #[test]
+    fn builtins_are_read_from_the_program() {
+        let program = CairoProgram::from_json_bytes(
+            br#"{"builtins": ["output", "pedersen", "range_check"], "data": []}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            program.builtins().unwrap(),
+            vec![
+                CairoBuiltin::Output,
+                CairoBuiltin::Pedersen,
+                CairoBuiltin::RangeCheck
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_builtins_are_rejected() {
+        let program =
+            CairoProgram::from_json_bytes(br#"{"builtins": ["output", "gas"], "data": []}"#)
+                .unwrap();
+
+        assert!(matches!(
+            program.builtins(),
+            Err(Error::UnknownBuiltin(name)) if name == "gas"
+        ));
+    }
+
+    #[test]
+    fn the_layout_is_detected_from_the_builtins() {
+        let layout = |content: &[u8]| CairoProgram::from_json_bytes(content).unwrap().layout();
+
+        assert_eq!(
+            layout(br#"{"builtins": ["output", "range_check"], "data": []}"#).unwrap(),
+            CairoLayout::Small
+        );
+        assert_eq!(
+            layout(br#"{"builtins": ["range_check96"], "data": []}"#).unwrap(),
+            CairoLayout::AllCairo
+        );
+        assert!(matches!(
+            layout(br#"{"builtins": ["output", "pedersen"], "data": []}"#),
+            Err(Error::UnprovableBuiltin(CairoBuiltin::Pedersen))
+        ));
+    }
+
+    #[test]
+    fn invalid_json_is_rejected() {
+        assert!(matches!(
+            CairoProgram::from_json_bytes(b"not a program"),
+            Err(Error::ProgramParsing(_))
+        ));
+    }
+}
@@ -1,21 +1,19 @@
-use super::vec_writer::VecWriter;
+use super::hints::{ExecutionSummary, HintConfig, ProgramHints};
+use super::limits::{ExecutionLimits, LimitExceeded};
+use super::output_stream::OutputStream;
+use super::program::declared_builtins;
 use crate::cairo::air::{MemorySegment, MemorySegmentMap, PublicInputs};
-use crate::cairo::cairo_layout::CairoLayout;
+use crate::cairo::cairo_layout::{CairoBuiltin, CairoLayout};
 use crate::cairo::cairo_mem::CairoMemory;
-use crate::cairo::execution_trace::build_main_trace;
+use crate::cairo::errors::CairoImportError;
+use crate::cairo::execution_trace::{build_main_trace, estimate_main_trace_length};
 use crate::cairo::register_states::RegisterStates;
-use crate::starks::trace::TraceTable;
-use cairo_lang_starknet::casm_contract_class::CasmContractClass;
-use cairo_vm::cairo_run::{self, EncodeTraceError};
-use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
-use cairo_vm::hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor;
-use cairo_vm::serde::deserialize_program::BuiltinName;
-use cairo_vm::types::{program::Program, relocatable::MaybeRelocatable};
-use cairo_vm::vm::errors::{
-    cairo_run_errors::CairoRunError, trace_errors::TraceError, vm_errors::VirtualMachineError,
+use crate::cairo::vm_adapter::{
+    dry_run_cairo0, run_cairo0, run_cairo1, CairoRunError, EncodeTraceError, FinishedRun,
+    TraceError, VirtualMachineError,
 };
-use cairo_vm::vm::runners::cairo_runner::{CairoArg, CairoRunner, RunResources};
-use cairo_vm::vm::vm_core::VirtualMachine;
+use crate::starks::trace::TraceTable;
+use crate::FE;
 use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
 use std::ops::Range;
 use thiserror::Error;
@@ -32,17 +30,49 @@ pub enum Error {
     VirtualMachine(#[from] VirtualMachineError),
     #[error(transparent)]
     Trace(#[from] TraceError),
+    #[error("Failed to parse the program")]
+    ProgramParsing(#[from] serde_json::Error),
+    #[error("The program contains a hint not allowed by the hint policy: {0}")]
+    DisallowedHint(String),
+    #[error(transparent)]
+    LimitExceeded(#[from] LimitExceeded),
+    #[error("Failed to compile the program: {0}")]
+    Compilation(String),
+    #[error("Unknown builtin: {0}")]
+    UnknownBuiltin(String),
+    #[error("The {} builtin is not supported by the {} layout", .0.as_str(), .1.as_str())]
+    UnsupportedBuiltin(CairoBuiltin, CairoLayout),
+    #[error("There is no layout supporting all the builtins of the program")]
+    NoLayoutForBuiltins,
+    #[error("The {} builtin can't be proven yet", .0.as_str())]
+    UnprovableBuiltin(CairoBuiltin),
+    #[error("Failed to relocate the memory")]
+    MemoryRelocation(#[from] CairoImportError),
+    #[error("The field has no LDE domain for a trace of length {0} with the proof options")]
+    NoLdeDomain(usize),
+}
+
+/// Everything obtained from running a Cairo program that is needed to prove its
+/// execution, along with what the program computed.
+pub struct CairoExecutionResult {
+    pub main_trace: TraceTable<Stark252PrimeField>,
+    pub pub_inputs: PublicInputs,
+    /// Values written by the program in the output builtin segment.
+    pub outputs: Vec<FE>,
+    pub execution_summary: ExecutionSummary,
 }
 
 /// Indicates the version of the Cairo program.
 /// This is used to determine how to parse and run the program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CairoVersion {
     V0 = 0,
     V1 = 1,
 }
 
 /// Runs a cairo program in JSON format and returns trace, memory and program length.
-/// Uses [cairo-rs](https://github.com/lambdaclass/cairo-rs/) project to run the program.
+/// Uses [cairo-rs](https://github.com/lambdaclass/cairo-rs/) project to run the program,
+/// in the release selected by the features of the crate (see `vm_adapter`).
 ///
 ///  # Params
 ///
@@ -67,215 +97,488 @@ pub fn run_program(
     program_content: &[u8],
     cairo_version: &CairoVersion,
 ) -> Result<(RegisterStates, CairoMemory, usize, Option<Range<u64>>), Error> {
+    let (register_states, cairo_mem, data_len, memory_segments, _) = run_program_with_hint_config(
+        entrypoint_function,
+        layout,
+        program_content,
+        cairo_version,
+        &HintConfig::default(),
+    )?;
+    let range_check_builtin_range = memory_segments.get(&MemorySegment::RangeCheck).cloned();
+
+    Ok((
+        register_states,
+        cairo_mem,
+        data_len,
+        range_check_builtin_range,
+    ))
+}
+
+/// Same as `run_program`, but running Cairo 0 programs with the given hint configuration.
+/// Programs containing hints not allowed by the configuration are rejected before running them.
+///
+/// Instead of the range of the range-check builtin, it returns the memory segments of
+/// all the builtins used by the program that have one. Besides, it returns an
+/// `ExecutionSummary` with the hints that ran during the execution.
+#[allow(clippy::type_complexity)]
+pub fn run_program_with_hint_config(
+    entrypoint_function: Option<&str>,
+    layout: CairoLayout,
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+    hint_config: &HintConfig,
+) -> Result<
+    (
+        RegisterStates,
+        CairoMemory,
+        usize,
+        MemorySegmentMap,
+        ExecutionSummary,
+    ),
+    Error,
+> {
+    run_program_sandboxed(
+        entrypoint_function,
+        layout,
+        program_content,
+        cairo_version,
+        hint_config,
+        &ExecutionLimits::unlimited(),
+    )
+}
+
+/// Same as `run_program_with_hint_config`, but stopping the execution with an
+/// `Error::LimitExceeded` as soon as it exceeds one of the given limits. Meant for
+/// running untrusted programs, which could otherwise never finish.
+///
+/// Cairo 0 programs declaring builtins not supported by `layout` are rejected.
+#[allow(clippy::type_complexity)]
+pub fn run_program_sandboxed(
+    entrypoint_function: Option<&str>,
+    layout: CairoLayout,
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+    hint_config: &HintConfig,
+    limits: &ExecutionLimits,
+) -> Result<
+    (
+        RegisterStates,
+        CairoMemory,
+        usize,
+        MemorySegmentMap,
+        ExecutionSummary,
+    ),
+    Error,
+> {
+    run_program_with_relocation(
+        entrypoint_function,
+        layout,
+        program_content,
+        cairo_version,
+        hint_config,
+        limits,
+        true,
+        None,
+    )
+}
+
+/// Same as `run_program_sandboxed`, but calling `on_output` with each value the
+/// program writes in the output builtin segment, in order, while it runs, so the
+/// outputs of long executions can be used before they finish. Values are received in
+/// batches every few thousand steps, and the ones still missing when the program
+/// finishes, like the ones holding pointers, right after relocating the memory.
+/// Cells of the segment the program never writes are skipped, as in
+/// `PublicInputs::outputs`.
+///
+/// Cairo 1 programs only send their outputs once they finish.
+#[allow(clippy::type_complexity)]
+pub fn run_program_streaming_outputs(
+    entrypoint_function: Option<&str>,
+    layout: CairoLayout,
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+    hint_config: &HintConfig,
+    limits: &ExecutionLimits,
+    on_output: &mut dyn FnMut(FE),
+) -> Result<
+    (
+        RegisterStates,
+        CairoMemory,
+        usize,
+        MemorySegmentMap,
+        ExecutionSummary,
+    ),
+    Error,
+> {
+    run_program_with_relocation(
+        entrypoint_function,
+        layout,
+        program_content,
+        cairo_version,
+        hint_config,
+        limits,
+        true,
+        Some(&mut OutputStream::new(on_output)),
+    )
+}
+
+/// Same as `run_program_with_hint_config` for Cairo 0 programs, but cairo-vm leaves
+/// the memory in segments, as in the runs of external runners that skip its
+/// relocation. The memory is then relocated by `CairoMemory`, translating the
+/// pointers stored in it (see `SegmentedMemory`), which gives the same memory
+/// cairo-vm would have relocated.
+#[allow(clippy::type_complexity)]
+pub fn run_program_without_relocation(
+    entrypoint_function: Option<&str>,
+    layout: CairoLayout,
+    program_content: &[u8],
+    hint_config: &HintConfig,
+) -> Result<
+    (
+        RegisterStates,
+        CairoMemory,
+        usize,
+        MemorySegmentMap,
+        ExecutionSummary,
+    ),
+    Error,
+> {
+    run_program_with_relocation(
+        entrypoint_function,
+        layout,
+        program_content,
+        &CairoVersion::V0,
+        hint_config,
+        &ExecutionLimits::unlimited(),
+        false,
+        None,
+    )
+}
+
+/// Runs the program, relocating the memory with cairo-vm if `relocate_mem`. Cairo 1
+/// programs always have their memory relocated by cairo-vm. The outputs are sent to
+/// `output_stream`, if any.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn run_program_with_relocation(
+    entrypoint_function: Option<&str>,
+    layout: CairoLayout,
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+    hint_config: &HintConfig,
+    limits: &ExecutionLimits,
+    relocate_mem: bool,
+    mut output_stream: Option<&mut OutputStream>,
+) -> Result<
+    (
+        RegisterStates,
+        CairoMemory,
+        usize,
+        MemorySegmentMap,
+        ExecutionSummary,
+    ),
+    Error,
+> {
     // default value for entrypoint is "main"
     let entrypoint = entrypoint_function.unwrap_or("main");
 
-    let args = [];
-
-    let (vm, runner) = match cairo_version {
+    let program_hints = match cairo_version {
         CairoVersion::V0 => {
-            let trace_enabled = true;
-            let mut hint_executor = BuiltinHintProcessor::new_empty();
-            let cairo_run_config = cairo_run::CairoRunConfig {
-                entrypoint,
-                trace_enabled,
-                relocate_mem: true,
-                layout: layout.as_str(),
-                proof_mode: false,
-                secure_run: None,
-            };
-
-            let (runner, vm) = match cairo_run::cairo_run(
-                program_content,
-                &cairo_run_config,
-                &mut hint_executor,
-            ) {
-                Ok(runner) => runner,
-                Err(error) => {
-                    eprintln!("{error}");
-                    return Err(Error::Runner(error));
-                }
-            };
-
-            (vm, runner)
-        }
-        CairoVersion::V1 => {
-            let casm_contract: CasmContractClass = serde_json::from_slice(program_content).unwrap();
-            let program: Program = casm_contract.clone().try_into().unwrap();
-            let mut runner = CairoRunner::new(
-                &(casm_contract.clone().try_into().unwrap()),
-                layout.as_str(),
-                false,
-            )
-            .unwrap();
-            let mut vm = VirtualMachine::new(true);
-
-            runner
-                .initialize_function_runner_cairo_1(&mut vm, &[BuiltinName::range_check])
-                .unwrap();
-
-            // Implicit Args
-            let syscall_segment = MaybeRelocatable::from(vm.add_memory_segment());
-
-            let builtins: Vec<&'static str> = runner
-                .get_program_builtins()
-                .iter()
-                .map(|b| b.name())
-                .collect();
-
-            let builtin_segment: Vec<MaybeRelocatable> = vm
-                .get_builtin_runners()
-                .iter()
-                .filter(|b| builtins.contains(&b.name()))
-                .flat_map(|b| b.initial_stack())
-                .collect();
-
-            let initial_gas = MaybeRelocatable::from(usize::MAX);
-
-            let mut implicit_args = builtin_segment;
-            implicit_args.extend([initial_gas]);
-            implicit_args.extend([syscall_segment]);
-
-            // Other args
-
-            // Load builtin costs
-            let builtin_costs: Vec<MaybeRelocatable> =
-                vec![0.into(), 0.into(), 0.into(), 0.into(), 0.into()];
-            let builtin_costs_ptr = vm.add_memory_segment();
-            vm.load_data(builtin_costs_ptr, &builtin_costs).unwrap();
-
-            // Load extra data
-            let core_program_end_ptr = (runner.program_base.unwrap() + program.data_len()).unwrap();
-            let program_extra_data: Vec<MaybeRelocatable> =
-                vec![0x208B7FFF7FFF7FFE.into(), builtin_costs_ptr.into()];
-            vm.load_data(core_program_end_ptr, &program_extra_data)
-                .unwrap();
-
-            // Load calldata
-            let calldata_start = vm.add_memory_segment();
-            let calldata_end = vm.load_data(calldata_start, &args.to_vec()).unwrap();
-
-            // Create entrypoint_args
-
-            let mut entrypoint_args: Vec<CairoArg> = implicit_args
-                .iter()
-                .map(|m| CairoArg::from(m.clone()))
-                .collect();
-            entrypoint_args.extend([
-                MaybeRelocatable::from(calldata_start).into(),
-                MaybeRelocatable::from(calldata_end).into(),
-            ]);
-            let entrypoint_args: Vec<&CairoArg> = entrypoint_args.iter().collect();
-
-            let mut hint_processor = Cairo1HintProcessor::new(&casm_contract.hints);
-
-            // Run contract entrypoint
-            // We assume entrypoint 0 for only one function
-            let mut run_resources = RunResources::default();
-
-            runner
-                .run_from_entrypoint(
-                    0,
-                    &entrypoint_args,
-                    &mut run_resources,
-                    true,
-                    Some(program.data_len() + program_extra_data.len()),
-                    &mut vm,
-                    &mut hint_processor,
-                )
-                .unwrap();
-
-            let _ = runner.relocate(&mut vm, true);
-
-            (vm, runner)
+            let program_hints = ProgramHints::from_program_json(program_content)?;
+            if let Some(hint_code) = hint_config.find_disallowed_hint(&program_hints) {
+                return Err(Error::DisallowedHint(hint_code.to_string()));
+            }
+            program_hints
         }
+        CairoVersion::V1 => ProgramHints::default(),
     };
 
-    let relocated_trace = vm.get_relocated_trace()?;
-
-    let mut trace_vec = Vec::<u8>::new();
-    let mut trace_writer = VecWriter::new(&mut trace_vec);
-    trace_writer.write_encoded_trace(relocated_trace);
-
-    let relocated_memory = &runner.relocated_memory;
+    if let CairoVersion::V0 = cairo_version {
+        for builtin in declared_builtins(program_content, cairo_version)? {
+            if !layout.supports(builtin) {
+                return Err(Error::UnsupportedBuiltin(builtin, layout));
+            }
+        }
+    }
 
-    let mut memory_vec = Vec::<u8>::new();
-    let mut memory_writer = VecWriter::new(&mut memory_vec);
-    memory_writer.write_encoded_memory(relocated_memory);
+    let run = match cairo_version {
+        CairoVersion::V0 => run_cairo0(
+            program_content,
+            entrypoint,
+            layout,
+            hint_config,
+            limits,
+            relocate_mem,
+            output_stream.as_deref_mut(),
+        )?,
+        CairoVersion::V1 => run_cairo1(program_content, layout, limits)?,
+    };
 
-    trace_writer.flush()?;
-    memory_writer.flush()?;
+    let register_states = run.register_states()?;
 
-    //TO DO: Better error handling
-    let cairo_mem = CairoMemory::from_bytes_le(&memory_vec).unwrap();
-    let register_states = RegisterStates::from_bytes_le(&trace_vec).unwrap();
+    limits.check_memory_cells(run.memory_cells())?;
+    let cairo_mem = run.memory()?;
 
-    let data_len = runner.get_program().data_len();
+    let data_len = run.program_size();
 
-    // get range start and end
-    let range_check = vm
-        .get_range_check_builtin()
-        .map(|builtin| {
-            let (idx, stop_offset) = builtin.get_memory_segment_addresses();
-            let stop_offset = stop_offset.unwrap_or_default();
-            let range_check_base =
-                (0..idx).fold(1, |acc, i| acc + vm.get_segment_size(i).unwrap_or_default());
-            let range_check_end = range_check_base + stop_offset;
+    // get the memory segments of the builtins
+    let memory_segments = provable_builtin_segments(&run);
 
-            (range_check_base, range_check_end)
-        })
-        .ok();
+    if let (Some(output_stream), Some(output_segment)) =
+        (output_stream, memory_segments.get(&MemorySegment::Output))
+    {
+        // Like `PublicInputs::outputs`, cells the program never wrote are skipped
+        for address in output_segment.clone().skip(output_stream.num_sent()) {
+            if let Some(output) = cairo_mem.get(&address) {
+                output_stream.send(*output);
+            }
+        }
+    }
 
-    let range_check_builtin_range = range_check.map(|(start, end)| Range {
-        start: start as u64,
-        end: end as u64,
-    });
+    let execution_summary = ExecutionSummary::new(&program_hints, &register_states);
 
     Ok((
         register_states,
         cairo_mem,
         data_len,
-        range_check_builtin_range,
+        memory_segments,
+        execution_summary,
     ))
 }
 
+/// Runs a compiled program and builds the trace and public inputs needed to prove it.
+///
+/// The layout and the memory segments of the builtins are configured from the builtins
+/// declared by the program. `output_range`, if provided, overrides the detected range of
+/// the output builtin segment.
 pub fn generate_prover_args(
     program_content: &[u8],
     cairo_version: &CairoVersion,
     output_range: &Option<Range<u64>>,
 ) -> Result<(TraceTable<Stark252PrimeField>, PublicInputs), Error> {
-    let cairo_layout = match cairo_version {
-        CairoVersion::V0 => CairoLayout::Small,
-        CairoVersion::V1 => CairoLayout::Plain,
-    };
+    let execution_result = generate_prover_args_with_hint_config(
+        program_content,
+        cairo_version,
+        output_range,
+        &HintConfig::default(),
+    )?;
+
+    Ok((execution_result.main_trace, execution_result.pub_inputs))
+}
 
-    let (register_states, memory, program_size, range_check_builtin_range) =
-        run_program(None, cairo_layout, program_content, cairo_version)?;
+/// Same as `generate_prover_args`, but running the program with the given hint configuration.
+/// The trace and public inputs are returned in a `CairoExecutionResult`, together with
+/// the outputs of the program and the `ExecutionSummary` of the execution.
+pub fn generate_prover_args_with_hint_config(
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+    output_range: &Option<Range<u64>>,
+    hint_config: &HintConfig,
+) -> Result<CairoExecutionResult, Error> {
+    generate_prover_args_sandboxed(
+        program_content,
+        cairo_version,
+        output_range,
+        hint_config,
+        &ExecutionLimits::unlimited(),
+    )
+}
 
-    let memory_segments = create_memory_segment_map(range_check_builtin_range, output_range);
+/// Same as `generate_prover_args_with_hint_config`, but running the program with
+/// the given execution limits. See `run_program_sandboxed`.
+pub fn generate_prover_args_sandboxed(
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+    output_range: &Option<Range<u64>>,
+    hint_config: &HintConfig,
+    limits: &ExecutionLimits,
+) -> Result<CairoExecutionResult, Error> {
+    generate_prover_args_with_output_stream(
+        program_content,
+        cairo_version,
+        output_range,
+        hint_config,
+        limits,
+        None,
+        false,
+    )
+}
+
+/// Same as `generate_prover_args_sandboxed`, but also committing to the whole memory
+/// of the execution in the `memory_commitment` of the public inputs. Building the
+/// commitment hashes every cell of the memory, so it's only done when asked.
+pub fn generate_prover_args_with_memory_commitment(
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+    output_range: &Option<Range<u64>>,
+    hint_config: &HintConfig,
+    limits: &ExecutionLimits,
+) -> Result<CairoExecutionResult, Error> {
+    generate_prover_args_with_output_stream(
+        program_content,
+        cairo_version,
+        output_range,
+        hint_config,
+        limits,
+        None,
+        true,
+    )
+}
+
+/// Same as `generate_prover_args_sandboxed`, but calling `on_output` with the outputs
+/// of the program while it runs. See `run_program_streaming_outputs`.
+///
+/// The outputs are the ones of the output builtin segment found by running the
+/// program, even if `output_range` overrides it.
+pub fn generate_prover_args_streaming_outputs(
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+    output_range: &Option<Range<u64>>,
+    hint_config: &HintConfig,
+    limits: &ExecutionLimits,
+    on_output: &mut dyn FnMut(FE),
+) -> Result<CairoExecutionResult, Error> {
+    generate_prover_args_with_output_stream(
+        program_content,
+        cairo_version,
+        output_range,
+        hint_config,
+        limits,
+        Some(&mut OutputStream::new(on_output)),
+        false,
+    )
+}
+
+fn generate_prover_args_with_output_stream(
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+    output_range: &Option<Range<u64>>,
+    hint_config: &HintConfig,
+    limits: &ExecutionLimits,
+    output_stream: Option<&mut OutputStream>,
+    commit_to_memory: bool,
+) -> Result<CairoExecutionResult, Error> {
+    let cairo_layout = proving_layout(program_content, cairo_version)?;
+
+    let (register_states, memory, program_size, mut memory_segments, execution_summary) =
+        run_program_with_relocation(
+            None,
+            cairo_layout,
+            program_content,
+            cairo_version,
+            hint_config,
+            limits,
+            true,
+            output_stream,
+        )?;
+
+    if let Some(output_range) = output_range {
+        memory_segments.insert(MemorySegment::Output, output_range.clone());
+    }
 
     let mut pub_inputs =
         PublicInputs::from_regs_and_mem(&register_states, &memory, program_size, &memory_segments);
+    if commit_to_memory {
+        pub_inputs.memory_commitment = memory.commitment();
+    }
 
     let main_trace = build_main_trace(&register_states, &memory, &mut pub_inputs);
 
-    Ok((main_trace, pub_inputs))
+    let outputs = pub_inputs.outputs();
+
+    Ok(CairoExecutionResult {
+        main_trace,
+        pub_inputs,
+        outputs,
+        execution_summary,
+    })
 }
 
-fn create_memory_segment_map(
-    range_check_builtin_range: Option<Range<u64>>,
-    output_range: &Option<Range<u64>>,
-) -> MemorySegmentMap {
-    let mut memory_segments = MemorySegmentMap::new();
+/// Estimates the length of the main trace `generate_prover_args` would build for the
+/// program, without building it. The program is run without recording its trace,
+/// which is much cheaper than the full run, so a proving service can size the proof
+/// options (see `ProofOptions::sized_for`) and schedule the proof beforehand. See
+/// `estimate_main_trace_length` for the accuracy of the estimate.
+pub fn estimate_trace_length(
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+) -> Result<usize, Error> {
+    Ok(estimate_execution(program_content, cairo_version)?.trace_length)
+}
 
-    if let Some(range_check_builtin_range) = range_check_builtin_range {
-        memory_segments.insert(MemorySegment::RangeCheck, range_check_builtin_range);
-    }
-    if let Some(output_range) = output_range {
-        memory_segments.insert(MemorySegment::Output, output_range.clone());
+/// Size of an execution of a program, found by running it without recording its
+/// trace. See `estimate_execution`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionEstimate {
+    pub num_steps: usize,
+    /// Estimated length of the main trace, see `estimate_trace_length`.
+    pub trace_length: usize,
+    /// Memory segments of the provable builtins used by the program.
+    pub memory_segments: MemorySegmentMap,
+}
+
+/// Same as `estimate_trace_length`, but also returning the steps and the builtin
+/// segments of the execution.
+pub fn estimate_execution(
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+) -> Result<ExecutionEstimate, Error> {
+    let cairo_layout = proving_layout(program_content, cairo_version)?;
+
+    let run = match cairo_version {
+        CairoVersion::V0 => dry_run_cairo0(
+            program_content,
+            "main",
+            cairo_layout,
+            &HintConfig::default(),
+        )?,
+        CairoVersion::V1 => {
+            run_cairo1(program_content, cairo_layout, &ExecutionLimits::unlimited())?
+        }
+    };
+
+    let execution_size = run.execution_size()?;
+    let memory_segments = provable_builtin_segments(&run);
+    let output_len = memory_segments
+        .get(&MemorySegment::Output)
+        .map_or(0, |segment| (segment.end - segment.start) as usize);
+
+    Ok(ExecutionEstimate {
+        num_steps: execution_size.steps,
+        trace_length: estimate_main_trace_length(
+            execution_size.steps,
+            execution_size.memory_holes,
+            run.program_size() + output_len,
+        ),
+        memory_segments,
+    })
+}
+
+/// Memory segments of the builtins the Cairo AIR handles, among the ones used by
+/// the program.
+fn provable_builtin_segments(run: &impl FinishedRun) -> MemorySegmentMap {
+    CairoBuiltin::PROVABLE
+        .into_iter()
+        .filter_map(|builtin| Some((builtin.memory_segment()?, run.builtin_segment(builtin)?)))
+        .collect()
+}
+
+/// Layout the program is proven with, configured from the builtins it declares.
+pub(crate) fn proving_layout(
+    program_content: &[u8],
+    cairo_version: &CairoVersion,
+) -> Result<CairoLayout, Error> {
+    let builtins = declared_builtins(program_content, cairo_version)?;
+    if let Some(builtin) = builtins
+        .iter()
+        .find(|builtin| builtin.memory_segment().is_none())
+    {
+        return Err(Error::UnprovableBuiltin(*builtin));
     }
 
-    memory_segments
+    match cairo_version {
+        CairoVersion::V0 => CairoLayout::for_builtins(&builtins).ok_or(Error::NoLayoutForBuiltins),
+        CairoVersion::V1 => Ok(CairoLayout::Plain),
+    }
 }
 
 pub fn cairo0_program_path(program_name: &str) -> String {
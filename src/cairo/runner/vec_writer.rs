@@ -1,4 +1,4 @@
-use cairo_vm::felt::Felt252;
+use crate::cairo::vm_adapter::{Felt252, RelocatedTraceEntry};
 use std::io::{self, Write};
 
 pub struct VecWriter<'a> {
@@ -24,17 +24,11 @@ impl<'a> VecWriter<'a> {
         self.buf_writer.flush()
     }
 
-    pub fn write_encoded_trace(
-        &mut self,
-        relocated_trace: &[cairo_vm::vm::trace::trace_entry::TraceEntry],
-    ) {
+    pub fn write_encoded_trace<E: RelocatedTraceEntry>(&mut self, relocated_trace: &[E]) {
         for entry in relocated_trace.iter() {
-            self.buf_writer
-                .extend_from_slice(&((entry.ap as u64).to_le_bytes()));
-            self.buf_writer
-                .extend_from_slice(&((entry.fp as u64).to_le_bytes()));
-            self.buf_writer
-                .extend_from_slice(&((entry.pc as u64).to_le_bytes()));
+            self.buf_writer.extend_from_slice(&entry.ap().to_le_bytes());
+            self.buf_writer.extend_from_slice(&entry.fp().to_le_bytes());
+            self.buf_writer.extend_from_slice(&entry.pc().to_le_bytes());
         }
     }
 
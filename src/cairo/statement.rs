@@ -0,0 +1,174 @@
+//! The minimal statement of a Cairo proof: the hash of the program and the hash of
+//! its outputs, the shape on-chain verifiers want instead of the whole public input.
+//!
+//! The proof container carries the full public inputs, which the proof is verified
+//! against as usual. Their transcript absorbs `statement_commitment`, which binds the
+//! hashes of the statement and the rest of the public inputs (registers, segments,
+//! padding) to the proof. `verify_cairo_proof_with_statement` checks that the public
+//! inputs of a valid proof hash to the statement given by the caller, so the rest
+//! only needs to be trusted through the proof.
+
+use lambdaworks_math::traits::{ByteConversion, Serializable};
+use sha3::{Digest, Keccak256};
+use tracing::error;
+
+use super::{
+    air::{verify_cairo_proof, PublicInputs},
+    container::CairoProofContainer,
+};
+use crate::{
+    starks::{config::Commitment, proof::options::ProofOptions},
+    FE,
+};
+
+/// What a Cairo proof proves, given by hashes: the program with `program_hash` ran
+/// and output the values with `output_hash`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CairoStatement {
+    pub program_hash: Commitment,
+    pub output_hash: Commitment,
+}
+
+impl PublicInputs {
+    /// Cells of the public memory holding the program, every one outside the output
+    /// segment, sorted by address.
+    pub fn program(&self) -> Vec<(FE, FE)> {
+        let mut cells: Vec<_> = self
+            .public_memory
+            .iter()
+            .filter(|(address, _)| !self.is_output_address(address))
+            .map(|(address, value)| (*address, *value))
+            .collect();
+        cells.sort_by_key(|(address, _)| address.to_bytes_be());
+        cells
+    }
+
+    /// Keccak256 of the `[address, value]` pairs of `program`, each element in big
+    /// endian.
+    pub fn program_hash(&self) -> Commitment {
+        let mut hasher = Keccak256::new();
+        for (address, value) in self.program() {
+            hasher.update(address.to_bytes_be());
+            hasher.update(value.to_bytes_be());
+        }
+        hasher.finalize().into()
+    }
+
+    /// Keccak256 of the outputs, see `outputs`, each one in big endian.
+    pub fn output_hash(&self) -> Commitment {
+        let mut hasher = Keccak256::new();
+        for output in self.outputs() {
+            hasher.update(output.to_bytes_be());
+        }
+        hasher.finalize().into()
+    }
+
+    pub fn statement(&self) -> CairoStatement {
+        CairoStatement {
+            program_hash: self.program_hash(),
+            output_hash: self.output_hash(),
+        }
+    }
+
+    /// Keccak256 of the hashes of the statement, the output commitment and the
+    /// serialized public inputs, absorbed in the transcript of their proofs. See
+    /// `CairoAIR::public_parameters`.
+    pub fn statement_commitment(&self) -> Commitment {
+        let statement = self.statement();
+        let mut hasher = Keccak256::new();
+        hasher.update(statement.program_hash);
+        hasher.update(statement.output_hash);
+        hasher.update(self.output_commitment().unwrap_or_default());
+        hasher.update(Keccak256::digest(self.serialize()));
+        hasher.finalize().into()
+    }
+}
+
+/// Verifies the proof of `container` against its public inputs, if they are the
+/// ones of `statement`.
+pub fn verify_cairo_proof_with_statement(
+    container: &CairoProofContainer,
+    statement: &CairoStatement,
+    proof_options: &ProofOptions,
+) -> bool {
+    // The public inputs are untrusted until the proof is verified, which checks their
+    // segments against the memory addresses before they are hashed.
+    if !verify_cairo_proof(&container.proof, &container.pub_inputs, proof_options) {
+        return false;
+    }
+
+    if container.pub_inputs.statement() != *statement {
+        error!("The public inputs of the proof are not the ones of the statement");
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::cairo::air::{MemoryPadding, MemorySegment, MemorySegmentMap};
+
+    // The program is at the addresses from 1 and the outputs from 10
+    fn public_inputs(program: &[u64], outputs: &[u64]) -> PublicInputs {
+        let output_start = 10;
+        let program_cells = program
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (FE::from(1 + i as u64), FE::from(*value)));
+        let output_cells = outputs
+            .iter()
+            .enumerate()
+            .map(|(i, output)| (FE::from(output_start + i as u64), FE::from(*output)));
+
+        PublicInputs {
+            pc_init: FE::one(),
+            ap_init: FE::zero(),
+            fp_init: FE::zero(),
+            pc_final: FE::zero(),
+            ap_final: FE::zero(),
+            range_check_min: None,
+            range_check_max: None,
+            memory_address_max: None,
+            memory_segments: MemorySegmentMap::from([(
+                MemorySegment::Output,
+                output_start..output_start + outputs.len() as u64,
+            )]),
+            public_memory: program_cells.chain(output_cells).collect::<HashMap<_, _>>(),
+            num_steps: 1,
+            memory_padding: MemoryPadding::default(),
+            num_memory_holes: 0,
+            builtins: vec!["output".to_string()],
+            memory_commitment: None,
+        }
+    }
+
+    #[test]
+    fn the_program_is_the_public_memory_outside_the_outputs() {
+        let pub_inputs = public_inputs(&[7, 8, 9], &[3, 1]);
+
+        assert_eq!(
+            pub_inputs.program(),
+            vec![
+                (FE::from(1), FE::from(7)),
+                (FE::from(2), FE::from(8)),
+                (FE::from(3), FE::from(9)),
+            ]
+        );
+    }
+
+    #[test]
+    fn each_hash_depends_only_on_its_part_of_the_statement() {
+        let statement = public_inputs(&[7, 8, 9], &[3, 1]).statement();
+
+        let other_outputs = public_inputs(&[7, 8, 9], &[3, 2]).statement();
+        assert_eq!(other_outputs.program_hash, statement.program_hash);
+        assert_ne!(other_outputs.output_hash, statement.output_hash);
+
+        let other_program = public_inputs(&[7, 9, 8], &[3, 1]).statement();
+        assert_ne!(other_program.program_hash, statement.program_hash);
+        assert_eq!(other_program.output_hash, statement.output_hash);
+    }
+}
@@ -0,0 +1,108 @@
+use std::ops::Range;
+
+use super::air::{MemorySegmentMap, RangeCheckBuiltinLayout};
+
+/// Number of columns of the CPU: the decoded instruction, the registers, the memory
+/// accesses and the values derived from them, up to the selector.
+pub const CPU_COLUMNS: usize = 34;
+
+/// Columns of a range-check builtin in the main trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeCheckBuiltinColumns {
+    pub layout: RangeCheckBuiltinLayout,
+    /// Columns of the parts of the values, from the least significant one.
+    pub parts: Range<usize>,
+    pub value: usize,
+}
+
+/// Indices of the columns of the Cairo trace that depend on the builtins of the
+/// program. The main trace has the CPU columns followed by the columns of the
+/// range-check builtins, and the auxiliary trace goes after them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceLayout {
+    pub range_check_builtins: Vec<RangeCheckBuiltinColumns>,
+    pub range_check_sorted: [usize; 3],
+    pub memory_addresses_sorted: [usize; 4],
+    pub memory_values_sorted: [usize; 4],
+    pub memory_permutation: [usize; 4],
+    pub range_check_permutation: [usize; 3],
+}
+
+impl TraceLayout {
+    /// Layout of the trace of a program with the given builtin segments.
+    pub fn new(memory_segments: &MemorySegmentMap) -> Self {
+        let mut next_column = CPU_COLUMNS;
+
+        let range_check_builtins = RangeCheckBuiltinLayout::used_in(memory_segments)
+            .into_iter()
+            .map(|layout| {
+                let parts = next_column..next_column + layout.n_parts;
+                let value = parts.end;
+                next_column += layout.n_columns();
+                RangeCheckBuiltinColumns {
+                    layout,
+                    parts,
+                    value,
+                }
+            })
+            .collect();
+
+        Self {
+            range_check_builtins,
+            range_check_sorted: take_columns(&mut next_column),
+            memory_addresses_sorted: take_columns(&mut next_column),
+            memory_values_sorted: take_columns(&mut next_column),
+            memory_permutation: take_columns(&mut next_column),
+            range_check_permutation: take_columns(&mut next_column),
+        }
+    }
+
+    pub fn num_main_columns(&self) -> usize {
+        self.range_check_sorted[0]
+    }
+
+    pub fn num_auxiliary_columns(&self) -> usize {
+        self.num_columns() - self.num_main_columns()
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.range_check_permutation[2] + 1
+    }
+}
+
+fn take_columns<const N: usize>(next_column: &mut usize) -> [usize; N] {
+    let first_column = *next_column;
+    *next_column += N;
+    std::array::from_fn(|i| first_column + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cairo::air::{MemorySegment, RANGE_CHECK96_BUILTIN_LAYOUT};
+
+    #[test]
+    fn auxiliary_columns_go_after_the_builtin_columns() {
+        let layout = TraceLayout::new(&MemorySegmentMap::new());
+        assert_eq!(layout.num_main_columns(), CPU_COLUMNS);
+        assert_eq!(layout.num_auxiliary_columns(), 3 + 12 + 3);
+        assert_eq!(layout.memory_addresses_sorted, [37, 38, 39, 40]);
+
+        let layout = TraceLayout::new(&MemorySegmentMap::from([
+            (MemorySegment::Output, 10..16),
+            (MemorySegment::RangeCheck96, 20..22),
+            (MemorySegment::RangeCheck, 30..32),
+        ]));
+        assert_eq!(layout.range_check_builtins[0].parts, 34..42);
+        assert_eq!(layout.range_check_builtins[0].value, 42);
+        assert_eq!(
+            layout.range_check_builtins[1].layout,
+            RANGE_CHECK96_BUILTIN_LAYOUT
+        );
+        assert_eq!(layout.range_check_builtins[1].parts, 43..49);
+        assert_eq!(layout.range_check_builtins[1].value, 49);
+        assert_eq!(layout.num_main_columns(), 50);
+        assert_eq!(layout.range_check_permutation, [65, 66, 67]);
+        assert_eq!(layout.num_columns(), 68);
+    }
+}
@@ -0,0 +1,101 @@
+//! Compatibility layer over the releases of cairo-vm the crate supports, selected
+//! with the `cairo-vm-0-6` feature, the default one, or the `cairo-vm-0-8` one.
+//! If both are enabled, like with `--all-features`, cairo-vm 0.8 is used.
+//!
+//! The rest of the crate only uses cairo-vm through this module. Running a
+//! program and reading what the prover needs from the run, whose API changes
+//! between releases, go through `FinishedRun` and `RelocatedTraceEntry`. The
+//! types whose API is the same in every supported release, like `Felt252`, are
+//! re-exported as they are.
+
+use std::ops::Range;
+
+#[cfg(feature = "compiler")]
+use super::runner::run::Error;
+use super::{
+    cairo_layout::CairoBuiltin,
+    cairo_mem::{CairoMemory, SegmentedMemory},
+    errors::CairoImportError,
+    register_states::RegisterStates,
+};
+
+#[cfg(not(any(feature = "cairo-vm-0-6", feature = "cairo-vm-0-8")))]
+compile_error!("one of the features `cairo-vm-0-6` and `cairo-vm-0-8` must be enabled");
+
+#[cfg(all(feature = "cairo-vm-0-6", not(feature = "cairo-vm-0-8")))]
+mod v0_6;
+#[cfg(all(feature = "cairo-vm-0-6", not(feature = "cairo-vm-0-8")))]
+use v0_6 as vm;
+
+#[cfg(feature = "cairo-vm-0-8")]
+mod v0_8;
+#[cfg(feature = "cairo-vm-0-8")]
+use v0_8 as vm;
+
+pub(crate) use vm::{dry_run_cairo0, run_cairo0, run_cairo1};
+pub use vm::{CairoRunError, EncodeTraceError, Felt252, HintFunc, TraceError, VirtualMachineError};
+
+/// Compiles a Cairo 1 contract from its source into a CASM contract class, in the
+/// JSON format `run_cairo1` reads with every supported release.
+#[cfg(feature = "compiler")]
+pub(crate) fn compile_cairo1(path: &std::path::Path) -> Result<Vec<u8>, Error> {
+    use cairo_lang_compiler::CompilerConfig;
+    use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+    use cairo_lang_starknet::contract_class::compile_path;
+
+    let contract_class = compile_path(
+        path,
+        CompilerConfig {
+            replace_ids: true,
+            ..CompilerConfig::default()
+        },
+    )
+    .map_err(|error| Error::Compilation(error.to_string()))?;
+    let casm_contract_class = CasmContractClass::from_contract_class(contract_class, false)
+        .map_err(|error| Error::Compilation(error.to_string()))?;
+
+    Ok(serde_json::to_vec(&casm_contract_class)?)
+}
+
+/// Registers of a step of the relocated trace of cairo-vm.
+pub trait RelocatedTraceEntry {
+    fn pc(&self) -> u64;
+    fn ap(&self) -> u64;
+    fn fp(&self) -> u64;
+}
+
+/// Size of an execution, as counted by cairo-vm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ExecutionSize {
+    pub steps: usize,
+    /// Unused cells of the memory segments of the builtins.
+    pub memory_holes: usize,
+}
+
+/// What the prover reads from a run of cairo-vm. The trace is always relocated,
+/// while the memory may be relocated by cairo-vm or left in segments.
+pub(crate) trait FinishedRun {
+    /// Registers of every step of the execution.
+    fn register_states(&self) -> Result<RegisterStates, TraceError>;
+
+    /// Number of cells of the relocated memory, counting the unused ones.
+    fn memory_cells(&self) -> usize;
+
+    /// The relocated memory. If cairo-vm didn't relocate it, it's relocated from
+    /// `segmented_memory`.
+    fn memory(&self) -> Result<CairoMemory, CairoImportError>;
+
+    /// The memory before relocation, by segment.
+    fn segmented_memory(&self) -> SegmentedMemory;
+
+    /// Number of cells of the program segment.
+    fn program_size(&self) -> usize;
+
+    /// Relocated addresses of the memory segment of the builtin, if the program
+    /// uses it.
+    fn builtin_segment(&self, builtin: CairoBuiltin) -> Option<Range<u64>>;
+
+    /// Steps and memory holes of the execution. Unlike the rest, it's available
+    /// for the runs without trace of `dry_run_cairo0`.
+    fn execution_size(&self) -> Result<ExecutionSize, TraceError>;
+}
@@ -0,0 +1,425 @@
+//! Runs of cairo-vm 0.6, where the run resources limiting the steps of an
+//! execution are passed to the runner.
+
+use std::ops::Range;
+use std::time::Instant;
+
+use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+use cairo_vm::cairo_run::{self, CairoRunConfig};
+use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+use cairo_vm::hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor;
+use cairo_vm::hint_processor::hint_processor_definition::HintProcessor;
+use cairo_vm::serde::deserialize_program::BuiltinName;
+use cairo_vm::types::{
+    program::Program,
+    relocatable::{MaybeRelocatable, Relocatable},
+};
+use cairo_vm::vm::errors::runner_errors::RunnerError;
+use cairo_vm::vm::runners::cairo_runner::{CairoArg, CairoRunner, RunResources};
+use cairo_vm::vm::security::verify_secure_runner;
+use cairo_vm::vm::trace::trace_entry::TraceEntry;
+use cairo_vm::vm::vm_core::VirtualMachine;
+
+pub use cairo_vm::cairo_run::EncodeTraceError;
+pub use cairo_vm::felt::Felt252;
+pub use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::HintFunc;
+pub use cairo_vm::vm::errors::{
+    cairo_run_errors::CairoRunError, trace_errors::TraceError, vm_errors::VirtualMachineError,
+};
+
+use super::{ExecutionSize, FinishedRun, RelocatedTraceEntry};
+use crate::cairo::{
+    cairo_layout::{CairoBuiltin, CairoLayout},
+    cairo_mem::{CairoMemory, SegmentedMemory, SegmentedValue},
+    errors::CairoImportError,
+    felt::fe_from_felt252,
+    register_states::RegisterStates,
+    runner::{hints::HintConfig, limits::ExecutionLimits, output_stream::OutputStream, run::Error},
+};
+
+pub(crate) struct VmRun {
+    runner: CairoRunner,
+    vm: VirtualMachine,
+}
+
+impl RelocatedTraceEntry for TraceEntry {
+    fn pc(&self) -> u64 {
+        self.pc as u64
+    }
+
+    fn ap(&self) -> u64 {
+        self.ap as u64
+    }
+
+    fn fp(&self) -> u64 {
+        self.fp as u64
+    }
+}
+
+impl FinishedRun for VmRun {
+    fn register_states(&self) -> Result<RegisterStates, TraceError> {
+        Ok(RegisterStates::from(
+            self.vm.get_relocated_trace()?.as_slice(),
+        ))
+    }
+
+    fn memory_cells(&self) -> usize {
+        if self.runner.relocated_memory.is_empty() {
+            1 + (0..self.vm.segments.num_segments())
+                .map(|index| self.vm.get_segment_size(index).unwrap_or_default())
+                .sum::<usize>()
+        } else {
+            self.runner.relocated_memory.len()
+        }
+    }
+
+    fn memory(&self) -> Result<CairoMemory, CairoImportError> {
+        if self.runner.relocated_memory.is_empty() {
+            CairoMemory::try_from(&self.segmented_memory())
+        } else {
+            Ok(CairoMemory::from(self.runner.relocated_memory.as_slice()))
+        }
+    }
+
+    fn segmented_memory(&self) -> SegmentedMemory {
+        let segments = (0..self.vm.segments.num_segments())
+            .map(|index| {
+                let size = self.vm.get_segment_size(index).unwrap_or_default();
+                (0..size)
+                    .map(|offset| {
+                        let address = Relocatable::from((index as isize, offset));
+                        Some(segmented_value(self.vm.get_maybe(&address)?))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        SegmentedMemory::new(segments)
+    }
+
+    fn program_size(&self) -> usize {
+        self.runner.get_program().data_len()
+    }
+
+    fn builtin_segment(&self, builtin: CairoBuiltin) -> Option<Range<u64>> {
+        let builtin_runner = self
+            .vm
+            .get_builtin_runners()
+            .iter()
+            .find(|builtin_runner| builtin_runner.name() == builtin.runner_name())?;
+        let (idx, stop_offset) = builtin_runner.get_memory_segment_addresses();
+        let stop_offset = stop_offset.unwrap_or_default();
+        let segment_base = (0..idx).fold(1, |acc, i| {
+            acc + self.vm.get_segment_size(i).unwrap_or_default()
+        });
+        let segment_end = segment_base + stop_offset;
+
+        Some(segment_base as u64..segment_end as u64)
+    }
+
+    fn execution_size(&self) -> Result<ExecutionSize, TraceError> {
+        let resources = self.runner.get_execution_resources(&self.vm)?;
+        Ok(ExecutionSize {
+            steps: resources.n_steps,
+            memory_holes: resources.n_memory_holes,
+        })
+    }
+}
+
+fn segmented_value(value: MaybeRelocatable) -> SegmentedValue {
+    match value {
+        MaybeRelocatable::Int(felt) => SegmentedValue::Felt(fe_from_felt252(&felt)),
+        MaybeRelocatable::RelocatableValue(pointer) => SegmentedValue::Pointer {
+            segment: pointer.segment_index,
+            offset: pointer.offset as u64,
+        },
+    }
+}
+
+fn hint_processor(hint_config: &HintConfig) -> BuiltinHintProcessor {
+    let mut hint_processor = BuiltinHintProcessor::new_empty();
+    for (hint_code, hint_func) in hint_config.custom_hints() {
+        hint_processor.add_hint(hint_code.clone(), hint_func.clone());
+    }
+    hint_processor
+}
+
+/// Runs a Cairo 0 program, relocating its memory if `relocate_mem`. The trace is
+/// always relocated. The outputs written while running are sent to `output_stream`,
+/// if any.
+pub(crate) fn run_cairo0(
+    program_content: &[u8],
+    entrypoint: &str,
+    layout: CairoLayout,
+    hint_config: &HintConfig,
+    limits: &ExecutionLimits,
+    relocate_mem: bool,
+    output_stream: Option<&mut OutputStream>,
+) -> Result<VmRun, Error> {
+    let mut hint_executor = hint_processor(hint_config);
+    let cairo_run_config = CairoRunConfig {
+        entrypoint,
+        trace_enabled: true,
+        relocate_mem,
+        layout: layout.as_str(),
+        proof_mode: false,
+        secure_run: None,
+    };
+
+    let (runner, vm) = if limits.is_unlimited() && output_stream.is_none() {
+        cairo_run::cairo_run(program_content, &cairo_run_config, &mut hint_executor)?
+    } else {
+        run_cairo0_with_limits(
+            program_content,
+            &cairo_run_config,
+            &mut hint_executor,
+            limits,
+            output_stream,
+        )?
+    };
+
+    Ok(VmRun { runner, vm })
+}
+
+/// Runs a Cairo 0 program without recording its trace nor relocating its memory,
+/// which is enough to count its steps with `FinishedRun::execution_size`.
+pub(crate) fn dry_run_cairo0(
+    program_content: &[u8],
+    entrypoint: &str,
+    layout: CairoLayout,
+    hint_config: &HintConfig,
+) -> Result<VmRun, Error> {
+    let mut hint_executor = hint_processor(hint_config);
+    let cairo_run_config = CairoRunConfig {
+        entrypoint,
+        trace_enabled: false,
+        relocate_mem: false,
+        layout: layout.as_str(),
+        proof_mode: false,
+        secure_run: None,
+    };
+
+    let (runner, vm) =
+        cairo_run::cairo_run(program_content, &cairo_run_config, &mut hint_executor)?;
+
+    Ok(VmRun { runner, vm })
+}
+
+/// Sends the outputs written since the last call, up to the first cell of the
+/// output segment that isn't written yet or holds a pointer.
+fn stream_outputs(vm: &VirtualMachine, output_stream: &mut OutputStream) {
+    let Some(output_runner) = vm
+        .get_builtin_runners()
+        .iter()
+        .find(|builtin_runner| builtin_runner.name() == CairoBuiltin::Output.runner_name())
+    else {
+        return;
+    };
+    let (segment, _) = output_runner.get_memory_segment_addresses();
+
+    loop {
+        let address = Relocatable::from((segment as isize, output_stream.num_sent()));
+        match vm.get_maybe(&address) {
+            Some(MaybeRelocatable::Int(felt)) => output_stream.send(fe_from_felt252(&felt)),
+            _ => break,
+        }
+    }
+}
+
+/// Runs until `end` in chunks of steps, checking the execution limits and sending
+/// the outputs between them.
+///
+/// While running, the memory used is bounded by the allocation pointer of the
+/// execution segment. The exact amount of memory cells is checked once relocated.
+fn run_until_pc_with_limits(
+    runner: &mut CairoRunner,
+    vm: &mut VirtualMachine,
+    end: Relocatable,
+    hint_processor: &mut dyn HintProcessor,
+    limits: &ExecutionLimits,
+    mut output_stream: Option<&mut OutputStream>,
+) -> Result<(), Error> {
+    let start_time = Instant::now();
+
+    let mut steps = 0;
+    loop {
+        let chunk_size = limits.next_chunk_size(steps);
+        let mut run_resources = RunResources::new(chunk_size);
+
+        let result = runner.run_until_pc(end, &mut run_resources, vm, hint_processor);
+        if let Some(output_stream) = output_stream.as_deref_mut() {
+            stream_outputs(vm, output_stream);
+        }
+        match result {
+            Ok(()) => return Ok(()),
+            Err(_) if run_resources.consumed() => {
+                steps += chunk_size;
+                limits.check(steps, vm.get_ap().offset, start_time.elapsed())?;
+            }
+            Err(error) => return Err(CairoRunError::from(error).into()),
+        }
+    }
+}
+
+/// Runs a Cairo 0 program like `cairo_run::cairo_run`, but with
+/// `run_until_pc_with_limits`.
+fn run_cairo0_with_limits(
+    program_content: &[u8],
+    cairo_run_config: &CairoRunConfig,
+    hint_executor: &mut BuiltinHintProcessor,
+    limits: &ExecutionLimits,
+    output_stream: Option<&mut OutputStream>,
+) -> Result<(CairoRunner, VirtualMachine), Error> {
+    let program = Program::from_bytes(program_content, Some(cairo_run_config.entrypoint))
+        .map_err(CairoRunError::from)?;
+    let mut runner = CairoRunner::new(
+        &program,
+        cairo_run_config.layout,
+        cairo_run_config.proof_mode,
+    )
+    .map_err(CairoRunError::from)?;
+    let mut vm = VirtualMachine::new(cairo_run_config.trace_enabled);
+    let end = runner.initialize(&mut vm).map_err(CairoRunError::from)?;
+
+    run_until_pc_with_limits(
+        &mut runner,
+        &mut vm,
+        end,
+        hint_executor,
+        limits,
+        output_stream,
+    )?;
+
+    runner
+        .end_run(false, false, &mut vm, hint_executor)
+        .map_err(CairoRunError::from)?;
+    vm.verify_auto_deductions().map_err(CairoRunError::from)?;
+    runner
+        .read_return_values(&mut vm)
+        .map_err(CairoRunError::from)?;
+    let secure_run = cairo_run_config
+        .secure_run
+        .unwrap_or(!cairo_run_config.proof_mode);
+    if secure_run {
+        verify_secure_runner(&runner, true, None, &mut vm).map_err(CairoRunError::from)?;
+    }
+    runner
+        .relocate(&mut vm, cairo_run_config.relocate_mem)
+        .map_err(CairoRunError::from)?;
+
+    Ok((runner, vm))
+}
+
+/// Runs the first entrypoint of a Cairo 1 contract without arguments and
+/// relocates its memory, with the execution limits checked like in
+/// `run_cairo0_with_limits`.
+pub(crate) fn run_cairo1(
+    program_content: &[u8],
+    layout: CairoLayout,
+    limits: &ExecutionLimits,
+) -> Result<VmRun, Error> {
+    let args = [];
+
+    let casm_contract: CasmContractClass = serde_json::from_slice(program_content)?;
+    let program: Program = casm_contract
+        .clone()
+        .try_into()
+        .map_err(CairoRunError::from)?;
+    let mut runner =
+        CairoRunner::new(&program, layout.as_str(), false).map_err(CairoRunError::from)?;
+    let mut vm = VirtualMachine::new(true);
+
+    runner
+        .initialize_function_runner_cairo_1(&mut vm, &[BuiltinName::range_check])
+        .map_err(CairoRunError::from)?;
+
+    // Implicit Args
+    let syscall_segment = MaybeRelocatable::from(vm.add_memory_segment());
+
+    let builtins: Vec<&'static str> = runner
+        .get_program_builtins()
+        .iter()
+        .map(|b| b.name())
+        .collect();
+
+    let builtin_segment: Vec<MaybeRelocatable> = vm
+        .get_builtin_runners()
+        .iter()
+        .filter(|b| builtins.contains(&b.name()))
+        .flat_map(|b| b.initial_stack())
+        .collect();
+
+    let initial_gas = MaybeRelocatable::from(usize::MAX);
+
+    let mut implicit_args = builtin_segment;
+    implicit_args.extend([initial_gas]);
+    implicit_args.extend([syscall_segment]);
+
+    // Other args
+
+    // Load builtin costs
+    let builtin_costs: Vec<MaybeRelocatable> =
+        vec![0.into(), 0.into(), 0.into(), 0.into(), 0.into()];
+    let builtin_costs_ptr = vm.add_memory_segment();
+    vm.load_data(builtin_costs_ptr, &builtin_costs)
+        .map_err(CairoRunError::from)?;
+
+    // Load extra data
+    let program_base = runner
+        .program_base
+        .ok_or(CairoRunError::Runner(RunnerError::NoProgBase))?;
+    let core_program_end_ptr =
+        (program_base + program.data_len()).map_err(VirtualMachineError::from)?;
+    let program_extra_data: Vec<MaybeRelocatable> =
+        vec![0x208B7FFF7FFF7FFE.into(), builtin_costs_ptr.into()];
+    vm.load_data(core_program_end_ptr, &program_extra_data)
+        .map_err(CairoRunError::from)?;
+
+    // Load calldata
+    let calldata_start = vm.add_memory_segment();
+    let calldata_end = vm
+        .load_data(calldata_start, &args.to_vec())
+        .map_err(CairoRunError::from)?;
+
+    // Create entrypoint_args
+
+    let mut entrypoint_args: Vec<CairoArg> = implicit_args
+        .iter()
+        .map(|m| CairoArg::from(m.clone()))
+        .collect();
+    entrypoint_args.extend([
+        MaybeRelocatable::from(calldata_start).into(),
+        MaybeRelocatable::from(calldata_end).into(),
+    ]);
+
+    let mut hint_processor = Cairo1HintProcessor::new(&casm_contract.hints);
+
+    // Run contract entrypoint like `CairoRunner::run_from_entrypoint`, but with the
+    // limits checked while running. We assume entrypoint 0 for only one function
+    let stack = entrypoint_args
+        .iter()
+        .map(|arg| vm.segments.gen_cairo_arg(arg))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(CairoRunError::from)?;
+    let end = runner
+        .initialize_function_entrypoint(&mut vm, 0, stack, MaybeRelocatable::from(0))
+        .map_err(CairoRunError::from)?;
+    runner.initialize_vm(&mut vm).map_err(CairoRunError::from)?;
+    run_until_pc_with_limits(&mut runner, &mut vm, end, &mut hint_processor, limits, None)?;
+    runner
+        .end_run(true, false, &mut vm, &mut hint_processor)
+        .map_err(CairoRunError::from)?;
+    verify_secure_runner(
+        &runner,
+        false,
+        Some(program.data_len() + program_extra_data.len()),
+        &mut vm,
+    )
+    .map_err(CairoRunError::from)?;
+
+    runner
+        .relocate(&mut vm, true)
+        .map_err(CairoRunError::from)?;
+
+    Ok(VmRun { runner, vm })
+}
@@ -0,0 +1,19 @@
+//! Error of the whole crate, for applications running, proving and verifying Cairo
+//! programs that would otherwise handle the error of each step separately.
+
+use thiserror::Error;
+
+use crate::{
+    cairo::runner::run::Error as RunnerError,
+    starks::{prover::ProvingError, verifier::VerificationError},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Runner(#[from] RunnerError),
+    #[error(transparent)]
+    Proving(#[from] ProvingError),
+    #[error(transparent)]
+    Verification(#[from] VerificationError),
+}
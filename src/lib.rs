@@ -7,7 +7,10 @@ use lambdaworks_math::field::{
 };
 
 pub mod cairo;
+pub mod errors;
 pub mod starks;
 
+pub use errors::Error;
+
 pub type PrimeField = Stark252PrimeField;
 pub type FE = FieldElement<PrimeField>;
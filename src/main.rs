@@ -1,50 +1,69 @@
 use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
 use lambdaworks_math::traits::{Deserializable, Serializable};
-use lambdaworks_stark::cairo::air::{generate_cairo_proof, verify_cairo_proof, PublicInputs};
-use lambdaworks_stark::cairo::runner::run::{generate_prover_args, CairoVersion};
+use lambdaworks_stark::cairo::air::{
+    generate_cairo_proof_with_metrics, verify_cairo_proof, PublicInputs,
+};
+use lambdaworks_stark::cairo::batch::prove_many;
+use lambdaworks_stark::cairo::container::CairoProofContainer;
+use lambdaworks_stark::cairo::runner::program::CairoProgram;
+use lambdaworks_stark::cairo::runner::run::CairoVersion;
 use lambdaworks_stark::starks::proof::options::ProofOptions;
 use lambdaworks_stark::starks::proof::stark::StarkProof;
 use std::env;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
+#[cfg(feature = "metrics-alloc")]
+#[global_allocator]
+static ALLOCATOR: lambdaworks_stark::starks::metrics::PeakAllocator =
+    lambdaworks_stark::starks::metrics::PeakAllocator::system();
+
 fn generate_proof(
     input_path: &String,
     proof_options: &ProofOptions,
 ) -> Option<(StarkProof<Stark252PrimeField>, PublicInputs)> {
     let timer = Instant::now();
 
-    let cairo_version = if input_path.contains(".casm") {
+    let Ok(program) = CairoProgram::from_file(input_path) else {
+        println!("Error opening {input_path} file");
+        return None;
+    };
+
+    if program.version() == &CairoVersion::V1 {
         println!("Running casm on CairoVM and generating trace ...");
-        CairoVersion::V1
     } else {
         println!("Running program on CairoVM and generating trace ...");
-        CairoVersion::V0
-    };
+    }
 
-    let Ok(program_content) = std::fs::read(input_path) else {
-        println!("Error opening {input_path} file");
+    let Ok((main_trace, pub_inputs)) = program.generate_prover_args(&None) else {
+        println!("Error generating prover args");
         return None;
     };
 
-    let Ok((main_trace, pub_inputs)) =
-        generate_prover_args(&program_content, &cairo_version, &None) else {
-            println!("Error generating prover args");
-            return None;
-        };
-
     println!("  Time spent: {:?} \n", timer.elapsed());
 
     let timer = Instant::now();
     println!("Making proof ...");
-    let proof = match generate_cairo_proof(&main_trace, &pub_inputs, proof_options) {
-        Ok(p) => p,
-        Err(e) => {
-            println!("Error generating proof: {:?}", e);
-            return None;
-        }
-    };
+    let (proof, metrics) =
+        match generate_cairo_proof_with_metrics(&main_trace, &pub_inputs, proof_options) {
+            Ok(p) => p,
+            Err(e) => {
+                println!("Error generating proof: {:?}", e);
+                return None;
+            }
+        };
 
-    println!("Time spent in proving: {:?} \n", timer.elapsed());
+    println!("Time spent in proving: {:?}", timer.elapsed());
+    if let Some(peak_allocated_bytes) = metrics.peak_allocated_bytes() {
+        println!(
+            "  Peak allocated memory: {} MiB",
+            peak_allocated_bytes >> 20
+        );
+    }
+    if let Some(peak_rss_bytes) = metrics.peak_rss_bytes() {
+        println!("  Peak resident memory: {} MiB", peak_rss_bytes >> 20);
+    }
+    println!();
 
     Some((proof, pub_inputs))
 }
@@ -69,7 +88,32 @@ fn verify_proof(
     proof_verified
 }
 
+fn write_proof(output_path: &Path, container: &CairoProofContainer) {
+    let Ok(()) = std::fs::write(output_path, container.serialize()) else {
+        println!("Error writing proof to file: {}", output_path.display());
+        return;
+    };
+    println!("Proof written to {}", output_path.display());
+}
+
+/// Prints the spans of the prover and verifier when they close, with the time spent
+/// in them. The verbosity can be changed with the `RUST_LOG` environment variable.
+#[cfg(feature = "instruments")]
+fn init_instruments() {
+    use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
+
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .with_span_events(FmtSpan::CLOSE)
+        .init();
+}
+
 fn main() {
+    #[cfg(feature = "instruments")]
+    init_instruments();
+
     let proof_options = ProofOptions::default_test_options();
 
     let args: Vec<String> = env::args().collect();
@@ -84,7 +128,7 @@ fn main() {
     match command.as_str() {
         "prove" => {
             if args.len() < 4 {
-                println!("Usage: cargo run prove <input_path> <output_path>");
+                println!("Usage: cargo run prove <input_path> <output_path> [public_inputs_path]");
                 return;
             }
 
@@ -95,21 +139,60 @@ fn main() {
                 return;
             };
 
-            let mut bytes = vec![];
-            let proof_bytes = proof.serialize();
-            bytes.extend(proof_bytes.len().to_be_bytes());
-            bytes.extend(proof_bytes);
-            bytes.extend(pub_inputs.serialize());
+            if let Some(public_inputs_path) = args.get(4) {
+                let Ok(()) = std::fs::write(public_inputs_path, pub_inputs.to_json()) else {
+                    println!("Error writing public inputs to file: {public_inputs_path}");
+                    return;
+                };
+                println!("Public inputs written to {public_inputs_path}");
+            }
 
-            let Ok(()) = std::fs::write(output_path, bytes) else {
-                println!("Error writing proof to file: {output_path}");
+            let mut container = CairoProofContainer::new(proof, pub_inputs);
+            container.attest(&proof_options);
+            write_proof(Path::new(output_path), &container);
+        }
+        "prove_many" => {
+            if args.len() < 4 {
+                println!("Usage: cargo run prove_many <output_dir> <input_path>...");
                 return;
-            };
-            println!("Proof written to {output_path}");
+            }
+
+            let output_dir = Path::new(&args[2]);
+            let programs: Vec<PathBuf> = args[3..].iter().map(PathBuf::from).collect();
+
+            println!("Proving {} programs ...", programs.len());
+            let report = prove_many(&programs, &proof_options);
+            let summary = format!(
+                "\nProven {} of {} programs in {:?} ({:.2} proofs/s)",
+                report.num_proven(),
+                report.proofs.len(),
+                report.total_time,
+                report.throughput()
+            );
+
+            for program_proof in report.proofs {
+                let program = program_proof.program.display();
+                match program_proof.result {
+                    Ok((proof, pub_inputs)) => {
+                        println!(
+                            "{program}: executed in {:?}, proven in {:?}",
+                            program_proof.execution_time, program_proof.proving_time
+                        );
+                        let file_name = program_proof.program.with_extension("proof");
+                        let output_path = output_dir.join(file_name.file_name().unwrap());
+                        let mut container = CairoProofContainer::new(proof, pub_inputs);
+                        container.attest(&proof_options);
+                        write_proof(&output_path, &container);
+                    }
+                    Err(error) => println!("{program}: {error}"),
+                }
+            }
+
+            println!("{summary}");
         }
         "verify" => {
             if args.len() < 3 {
-                println!("Usage: cargo run verify <input_path>");
+                println!("Usage: cargo run verify <input_path> [public_inputs_path]");
                 return;
             }
 
@@ -118,29 +201,40 @@ fn main() {
                 println!("Error opening {input_path} file");
                 return;
             };
-            let mut bytes = program_content.as_slice();
-            if bytes.len() < 8 {
-                println!("Error reading proof from file: {input_path}");
-                return;
-            }
-            let proof_len = usize::from_be_bytes(bytes[0..8].try_into().unwrap());
-            bytes = &bytes[8..];
-            if bytes.len() < proof_len {
-                println!("Error reading proof from file: {input_path}");
-                return;
-            }
-            let Ok(proof) = StarkProof::<Stark252PrimeField>::deserialize(&bytes[0..proof_len]) else {
+            let Ok(container) = CairoProofContainer::deserialize(&program_content) else {
                 println!("Error reading proof from file: {input_path}");
                 return;
             };
-            bytes = &bytes[proof_len..];
 
-            let Ok(pub_inputs) = PublicInputs::deserialize(bytes) else {
-                println!("Error reading proof from file: {input_path}");
-                return;
+            match container.checked_attestation() {
+                Ok(Some(attestation)) => println!(
+                    "Proof made by version {} of the prover, with version {} of the AIR\n",
+                    attestation.prover_version, attestation.air_version
+                ),
+                Ok(None) => {}
+                Err(error) => println!("Ignoring the attestation of the proof: {error}\n"),
+            }
+
+            // The public inputs of the container are replaced by the ones given by the
+            // verifier, if any, so the proof is checked against its own statement.
+            let pub_inputs = match args.get(3) {
+                Some(public_inputs_path) => {
+                    let Ok(json) = std::fs::read_to_string(public_inputs_path) else {
+                        println!("Error opening {public_inputs_path} file");
+                        return;
+                    };
+                    match PublicInputs::from_json(&json) {
+                        Ok(pub_inputs) => pub_inputs,
+                        Err(error) => {
+                            println!("Error reading public inputs: {error}");
+                            return;
+                        }
+                    }
+                }
+                None => container.pub_inputs,
             };
 
-            verify_proof(proof, pub_inputs, &proof_options);
+            verify_proof(container.proof, pub_inputs, &proof_options);
         }
         "prove_and_verify" => {
             if args.len() < 3 {
@@ -0,0 +1,86 @@
+//! Merkle tree backends hashing with Blake3, for `CommitmentHash::Blake3`. Like the
+//! Keccak256 backends, a leaf is hashed from the big-endian bytes of its field
+//! elements, and a parent from the concatenation of its children.
+
+use std::marker::PhantomData;
+
+use lambdaworks_crypto::merkle_tree::traits::IsMerkleTreeBackend;
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsField},
+    traits::ByteConversion,
+};
+
+use super::config::Commitment;
+
+/// Backend of the trees whose leaves are field elements.
+#[derive(Clone)]
+pub struct Blake3Tree<F> {
+    phantom: PhantomData<F>,
+}
+
+impl<F> Default for Blake3Tree<F> {
+    fn default() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<F> IsMerkleTreeBackend for Blake3Tree<F>
+where
+    F: IsField,
+    FieldElement<F>: ByteConversion,
+{
+    type Node = Commitment;
+    type Data = FieldElement<F>;
+
+    fn hash_data(input: &FieldElement<F>) -> Commitment {
+        blake3::hash(&input.to_bytes_be()).into()
+    }
+
+    fn hash_new_parent(left: &Commitment, right: &Commitment) -> Commitment {
+        hash_parent(left, right)
+    }
+}
+
+/// Backend of the trees whose leaves are vectors of field elements.
+#[derive(Clone)]
+pub struct BatchBlake3Tree<F> {
+    phantom: PhantomData<F>,
+}
+
+impl<F> Default for BatchBlake3Tree<F> {
+    fn default() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<F> IsMerkleTreeBackend for BatchBlake3Tree<F>
+where
+    F: IsField,
+    FieldElement<F>: ByteConversion,
+{
+    type Node = Commitment;
+    type Data = Vec<FieldElement<F>>;
+
+    fn hash_data(input: &Vec<FieldElement<F>>) -> Commitment {
+        let mut hasher = blake3::Hasher::new();
+        for element in input.iter() {
+            hasher.update(&element.to_bytes_be());
+        }
+        hasher.finalize().into()
+    }
+
+    fn hash_new_parent(left: &Commitment, right: &Commitment) -> Commitment {
+        hash_parent(left, right)
+    }
+}
+
+fn hash_parent(left: &Commitment, right: &Commitment) -> Commitment {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
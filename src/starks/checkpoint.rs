@@ -0,0 +1,375 @@
+use std::fmt;
+
+use lambdaworks_math::{
+    errors::DeserializationError,
+    field::{element::FieldElement, traits::IsFFTField},
+    traits::{ByteConversion, Deserializable, Serializable},
+};
+
+use super::config::{Commitment, Salt, COMMITMENT_SIZE};
+use super::utils::check_fully_read;
+
+/// Intermediate state of a `ProverSession`, taken after round 1, 2 or 3 of the
+/// protocol, or once the FRI layers of round 4 are committed.
+///
+/// Only the polynomials and commitments sent so far are stored, and the evaluations
+/// of the FRI layers, which would take the most time to recompute. Everything else
+/// (LDE evaluations, Merkle trees, transcript state and challenges) is deterministically
+/// recomputed from them when the session is resumed.
+///
+/// Lengths are serialized as big-endian `u64`, so checkpoints can be resumed on
+/// machines of another word size.
+#[derive(Clone)]
+pub struct ProverCheckpoint<F: IsFFTField> {
+    // Number of rounds of the protocol already run
+    pub completed_rounds: u8,
+    // Length of the execution trace
+    pub trace_length: usize,
+    // Coefficients of the trace polynomials tⱼ, main trace first
+    pub trace_polys: Vec<Vec<FieldElement<F>>>,
+    // [tⱼ]
+    pub lde_trace_merkle_roots: Vec<Commitment>,
+    // Coefficients of H₁ and H₂. Empty before round 2.
+    pub composition_poly_even: Vec<FieldElement<F>>,
+    pub composition_poly_odd: Vec<FieldElement<F>>,
+    // Coefficients of the masking polynomial M. Empty before round 2 or if the
+    // trace is not randomized.
+    pub composition_poly_mask: Vec<FieldElement<F>>,
+    // [H₁] and [H₂]. Only meaningful after round 2.
+    pub composition_poly_root: Commitment,
+    // Evaluations of the FRI layers pₖ over their domains. Empty before the FRI
+    // layers are committed.
+    pub fri_layers_evaluations: Vec<Vec<FieldElement<F>>>,
+    // [pₖ]
+    pub fri_layers_merkle_roots: Vec<Commitment>,
+    // Coefficients of pₙ
+    pub fri_last_poly: Vec<FieldElement<F>>,
+    // Seed of the salts of the commitments, if they are salted. Like the trace, it
+    // must be kept private for the proof to be zero-knowledge.
+    pub salt_seed: Option<Salt>,
+}
+
+// Written by hand so that the salt seed doesn't end up in logs.
+impl<F: IsFFTField> fmt::Debug for ProverCheckpoint<F>
+where
+    FieldElement<F>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProverCheckpoint")
+            .field("completed_rounds", &self.completed_rounds)
+            .field("trace_length", &self.trace_length)
+            .field("trace_polys", &self.trace_polys)
+            .field("lde_trace_merkle_roots", &self.lde_trace_merkle_roots)
+            .field("composition_poly_even", &self.composition_poly_even)
+            .field("composition_poly_odd", &self.composition_poly_odd)
+            .field("composition_poly_mask", &self.composition_poly_mask)
+            .field("composition_poly_root", &self.composition_poly_root)
+            .field("fri_layers_evaluations", &self.fri_layers_evaluations)
+            .field("fri_layers_merkle_roots", &self.fri_layers_merkle_roots)
+            .field("fri_last_poly", &self.fri_last_poly)
+            .field("salt_seed", &self.salt_seed.map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+fn serialize_felts<F>(bytes: &mut Vec<u8>, felts: &[FieldElement<F>])
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    serialize_len(bytes, felts.len());
+    for felt in felts {
+        bytes.extend(felt.to_bytes_be());
+    }
+}
+
+fn serialize_len(bytes: &mut Vec<u8>, len: usize) {
+    bytes.extend((len as u64).to_be_bytes());
+}
+
+fn deserialize_len(bytes: &[u8]) -> Result<(usize, &[u8]), DeserializationError> {
+    let value = u64::from_be_bytes(
+        bytes
+            .get(..8)
+            .ok_or(DeserializationError::InvalidAmountOfBytes)?
+            .try_into()
+            .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
+    );
+    let value = usize::try_from(value).map_err(|_| DeserializationError::InvalidValue)?;
+    Ok((value, &bytes[8..]))
+}
+
+fn deserialize_commitment(bytes: &[u8]) -> Result<(Commitment, &[u8]), DeserializationError> {
+    let commitment = bytes
+        .get(..COMMITMENT_SIZE)
+        .ok_or(DeserializationError::InvalidAmountOfBytes)?
+        .try_into()
+        .map_err(|_| DeserializationError::InvalidAmountOfBytes)?;
+    Ok((commitment, &bytes[COMMITMENT_SIZE..]))
+}
+
+fn deserialize_felts<F>(bytes: &[u8]) -> Result<(Vec<FieldElement<F>>, &[u8]), DeserializationError>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    let felt_len = FieldElement::<F>::zero().to_bytes_be().len();
+    let (felts_len, mut bytes) = deserialize_len(bytes)?;
+
+    let mut felts = vec![];
+    for _ in 0..felts_len {
+        let felt = FieldElement::from_bytes_be(
+            bytes
+                .get(..felt_len)
+                .ok_or(DeserializationError::InvalidAmountOfBytes)?,
+        )?;
+        felts.push(felt);
+        bytes = &bytes[felt_len..];
+    }
+
+    Ok((felts, bytes))
+}
+
+impl<F> Serializable for ProverCheckpoint<F>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = vec![self.completed_rounds];
+        serialize_len(&mut bytes, self.trace_length);
+
+        serialize_len(&mut bytes, self.trace_polys.len());
+        for poly in &self.trace_polys {
+            serialize_felts(&mut bytes, poly);
+        }
+
+        serialize_len(&mut bytes, self.lde_trace_merkle_roots.len());
+        for commitment in &self.lde_trace_merkle_roots {
+            bytes.extend(commitment);
+        }
+
+        serialize_felts(&mut bytes, &self.composition_poly_even);
+        serialize_felts(&mut bytes, &self.composition_poly_odd);
+        serialize_felts(&mut bytes, &self.composition_poly_mask);
+        bytes.extend(self.composition_poly_root);
+
+        serialize_len(&mut bytes, self.fri_layers_evaluations.len());
+        for evaluations in &self.fri_layers_evaluations {
+            serialize_felts(&mut bytes, evaluations);
+        }
+
+        serialize_len(&mut bytes, self.fri_layers_merkle_roots.len());
+        for commitment in &self.fri_layers_merkle_roots {
+            bytes.extend(commitment);
+        }
+
+        serialize_felts(&mut bytes, &self.fri_last_poly);
+
+        match &self.salt_seed {
+            Some(salt_seed) => {
+                bytes.push(1);
+                bytes.extend(salt_seed);
+            }
+            None => bytes.push(0),
+        }
+
+        bytes
+    }
+}
+
+impl<F> Deserializable for ProverCheckpoint<F>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    fn deserialize(bytes: &[u8]) -> Result<Self, DeserializationError>
+    where
+        Self: Sized,
+    {
+        let completed_rounds = *bytes
+            .first()
+            .ok_or(DeserializationError::InvalidAmountOfBytes)?;
+        let bytes = &bytes[1..];
+
+        let (trace_length, bytes) = deserialize_len(bytes)?;
+
+        let (trace_polys_len, mut bytes) = deserialize_len(bytes)?;
+        let mut trace_polys = vec![];
+        for _ in 0..trace_polys_len {
+            let poly;
+            (poly, bytes) = deserialize_felts(bytes)?;
+            trace_polys.push(poly);
+        }
+
+        let lde_trace_merkle_roots_len;
+        (lde_trace_merkle_roots_len, bytes) = deserialize_len(bytes)?;
+        let mut lde_trace_merkle_roots = vec![];
+        for _ in 0..lde_trace_merkle_roots_len {
+            let commitment;
+            (commitment, bytes) = deserialize_commitment(bytes)?;
+            lde_trace_merkle_roots.push(commitment);
+        }
+
+        let (composition_poly_even, bytes) = deserialize_felts(bytes)?;
+        let (composition_poly_odd, bytes) = deserialize_felts(bytes)?;
+        let (composition_poly_mask, bytes) = deserialize_felts(bytes)?;
+        let (composition_poly_root, mut bytes) = deserialize_commitment(bytes)?;
+
+        let fri_layers_len;
+        (fri_layers_len, bytes) = deserialize_len(bytes)?;
+        let mut fri_layers_evaluations = vec![];
+        for _ in 0..fri_layers_len {
+            let evaluations;
+            (evaluations, bytes) = deserialize_felts(bytes)?;
+            fri_layers_evaluations.push(evaluations);
+        }
+
+        let fri_layers_merkle_roots_len;
+        (fri_layers_merkle_roots_len, bytes) = deserialize_len(bytes)?;
+        let mut fri_layers_merkle_roots = vec![];
+        for _ in 0..fri_layers_merkle_roots_len {
+            let commitment;
+            (commitment, bytes) = deserialize_commitment(bytes)?;
+            fri_layers_merkle_roots.push(commitment);
+        }
+
+        let (fri_last_poly, bytes) = deserialize_felts(bytes)?;
+
+        // Salts have the same size as commitments
+        let (salt_seed, bytes) = match bytes.split_first() {
+            Some((0, bytes)) => (None, bytes),
+            Some((1, bytes)) => {
+                let (salt_seed, bytes) = deserialize_commitment(bytes)?;
+                (Some(salt_seed), bytes)
+            }
+            _ => return Err(DeserializationError::InvalidAmountOfBytes),
+        };
+        check_fully_read(bytes)?;
+
+        Ok(ProverCheckpoint {
+            completed_rounds,
+            trace_length,
+            trace_polys,
+            lde_trace_merkle_roots,
+            composition_poly_even,
+            composition_poly_odd,
+            composition_poly_mask,
+            composition_poly_root,
+            fri_layers_evaluations,
+            fri_layers_merkle_roots,
+            fri_last_poly,
+            salt_seed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::traits::{Deserializable, Serializable};
+
+    use crate::{PrimeField, FE};
+
+    use super::ProverCheckpoint;
+
+    #[test]
+    fn serialize_and_deserialize_checkpoint() {
+        let checkpoint = ProverCheckpoint {
+            completed_rounds: 2,
+            trace_length: 8,
+            trace_polys: vec![vec![FE::from(1), FE::from(2)], vec![], vec![FE::from(3)]],
+            lde_trace_merkle_roots: vec![[1; 32], [2; 32]],
+            composition_poly_even: vec![FE::from(4)],
+            composition_poly_odd: vec![FE::from(5), FE::from(6)],
+            composition_poly_mask: vec![FE::from(9)],
+            composition_poly_root: [7; 32],
+            fri_layers_evaluations: vec![vec![FE::from(10), FE::from(11)], vec![FE::from(12)]],
+            fri_layers_merkle_roots: vec![[13; 32], [14; 32]],
+            fri_last_poly: vec![FE::from(15)],
+            salt_seed: Some([8; 32]),
+        };
+
+        let deserialized =
+            ProverCheckpoint::<PrimeField>::deserialize(&checkpoint.serialize()).unwrap();
+
+        assert_eq!(checkpoint.completed_rounds, deserialized.completed_rounds);
+        assert_eq!(checkpoint.trace_length, deserialized.trace_length);
+        assert_eq!(checkpoint.trace_polys, deserialized.trace_polys);
+        assert_eq!(
+            checkpoint.lde_trace_merkle_roots,
+            deserialized.lde_trace_merkle_roots
+        );
+        assert_eq!(
+            checkpoint.composition_poly_even,
+            deserialized.composition_poly_even
+        );
+        assert_eq!(
+            checkpoint.composition_poly_odd,
+            deserialized.composition_poly_odd
+        );
+        assert_eq!(
+            checkpoint.composition_poly_mask,
+            deserialized.composition_poly_mask
+        );
+        assert_eq!(
+            checkpoint.composition_poly_root,
+            deserialized.composition_poly_root
+        );
+        assert_eq!(
+            checkpoint.fri_layers_evaluations,
+            deserialized.fri_layers_evaluations
+        );
+        assert_eq!(
+            checkpoint.fri_layers_merkle_roots,
+            deserialized.fri_layers_merkle_roots
+        );
+        assert_eq!(checkpoint.fri_last_poly, deserialized.fri_last_poly);
+        assert_eq!(checkpoint.salt_seed, deserialized.salt_seed);
+    }
+
+    #[test]
+    fn deserialize_truncated_or_extended_checkpoint_fails() {
+        let checkpoint = ProverCheckpoint {
+            completed_rounds: 1,
+            trace_length: 8,
+            trace_polys: vec![vec![FE::from(1), FE::from(2)]],
+            lde_trace_merkle_roots: vec![[1; 32]],
+            composition_poly_even: vec![],
+            composition_poly_odd: vec![],
+            composition_poly_mask: vec![],
+            composition_poly_root: [0; 32],
+            fri_layers_evaluations: vec![],
+            fri_layers_merkle_roots: vec![],
+            fri_last_poly: vec![],
+            salt_seed: None,
+        };
+        let mut bytes = checkpoint.serialize();
+
+        assert!(ProverCheckpoint::<PrimeField>::deserialize(&bytes[..bytes.len() - 1]).is_err());
+        bytes.push(0);
+        assert!(ProverCheckpoint::<PrimeField>::deserialize(&bytes).is_err());
+    }
+
+    #[test]
+    fn debug_output_redacts_the_salt_seed() {
+        let checkpoint = ProverCheckpoint::<PrimeField> {
+            completed_rounds: 1,
+            trace_length: 8,
+            trace_polys: vec![],
+            lde_trace_merkle_roots: vec![],
+            composition_poly_even: vec![],
+            composition_poly_odd: vec![],
+            composition_poly_mask: vec![],
+            composition_poly_root: [0; 32],
+            fri_layers_evaluations: vec![],
+            fri_layers_merkle_roots: vec![],
+            fri_last_poly: vec![],
+            salt_seed: Some([173; 32]),
+        };
+
+        let debug = format!("{checkpoint:?}");
+
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains("173"));
+    }
+}
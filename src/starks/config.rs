@@ -1,14 +1,31 @@
+#[cfg(feature = "mmap")]
+use std::sync::Arc;
+use std::{io, marker::PhantomData};
+
 use lambdaworks_crypto::merkle_tree::{
     backends::types::{BatchKeccak256Tree, Keccak256Tree},
     merkle::MerkleTree,
+    proof::Proof,
+    traits::IsMerkleTreeBackend,
+};
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsField},
+    traits::ByteConversion,
 };
 
+#[cfg(feature = "blake3")]
+use super::blake3_backend::{BatchBlake3Tree, Blake3Tree};
+#[cfg(feature = "mmap")]
+use super::disk_merkle::DiskMerkleTree;
+use super::poseidon_backend::{BatchPoseidonTree, PoseidonTree};
+use super::proof::options::{CommitmentHash, MerkleTreeStorage};
+
 // Merkle Trees configuration
 
 // Security of both hashes should match
 
 pub type FriMerkleTreeBackend<F> = Keccak256Tree<F>;
-pub type FriMerkleTree<F> = MerkleTree<FriMerkleTreeBackend<F>>;
+pub type FriMerkleTree<F> = CommitmentTree<FriLeaves<F>>;
 
 // If using hashes with 256-bit security, commitment size should be 32
 // If using hashes with 512-bit security, commitment size should be 64
@@ -16,5 +33,378 @@ pub type FriMerkleTree<F> = MerkleTree<FriMerkleTreeBackend<F>>;
 pub const COMMITMENT_SIZE: usize = 32;
 pub type Commitment = [u8; COMMITMENT_SIZE];
 
+/// The nodes of a level of a Merkle tree, from left to right, sent instead of its
+/// root so the paths of the openings stop at that level. A cap of height `h` has
+/// the `2^h` nodes of the level `h` below the root, and a cap of height zero is the
+/// root alone. See `ProofOptions::merkle_cap_height`.
+pub type MerkleCap = Vec<Commitment>;
+
+// Random bytes hashed with each leaf of the trace and composition polynomial commitments
+pub const SALT_SIZE: usize = 32;
+pub type Salt = [u8; SALT_SIZE];
+
 pub type BatchedMerkleTreeBackend<F> = BatchKeccak256Tree<F>;
-pub type BatchedMerkleTree<F> = MerkleTree<BatchedMerkleTreeBackend<F>>;
+pub type BatchedMerkleTree<F> = CommitmentTree<BatchedLeaves<F>>;
+
+// Vector commitments, see `IsVectorCommitment`
+
+/// Commitment to the rows of the LDE of the trace and of the composition polynomial.
+pub type BatchedVectorCommitment<F> = BatchedMerkleTree<F>;
+/// Commitment to the evaluations of a FRI layer.
+pub type FriVectorCommitment<F> = FriMerkleTree<F>;
+
+/// Leaves of a kind of Merkle tree of the commitments, with the backend hashing
+/// them with each `CommitmentHash`.
+pub trait CommitmentLeaves {
+    type Data;
+    type Keccak256: IsMerkleTreeBackend<Node = Commitment, Data = Self::Data> + Clone;
+    #[cfg(feature = "blake3")]
+    type Blake3: IsMerkleTreeBackend<Node = Commitment, Data = Self::Data> + Clone;
+    type Poseidon: IsMerkleTreeBackend<Node = Commitment, Data = Self::Data> + Clone;
+}
+
+/// Rows of field elements, the leaves of the trace and composition polynomial trees.
+pub struct BatchedLeaves<F>(PhantomData<F>);
+
+impl<F> CommitmentLeaves for BatchedLeaves<F>
+where
+    F: IsField,
+    FieldElement<F>: ByteConversion,
+{
+    type Data = Vec<FieldElement<F>>;
+    type Keccak256 = BatchedMerkleTreeBackend<F>;
+    #[cfg(feature = "blake3")]
+    type Blake3 = BatchBlake3Tree<F>;
+    type Poseidon = BatchPoseidonTree<F>;
+}
+
+/// Field elements, the leaves of the FRI layer trees.
+pub struct FriLeaves<F>(PhantomData<F>);
+
+impl<F> CommitmentLeaves for FriLeaves<F>
+where
+    F: IsField,
+    FieldElement<F>: ByteConversion,
+{
+    type Data = FieldElement<F>;
+    type Keccak256 = FriMerkleTreeBackend<F>;
+    #[cfg(feature = "blake3")]
+    type Blake3 = Blake3Tree<F>;
+    type Poseidon = PoseidonTree<F>;
+}
+
+/// Merkle tree of a commitment, built with the hash and stored where chosen in the
+/// proof options. The hash is chosen once per tree, so building it costs the same as
+/// with a fixed backend.
+pub enum CommitmentTree<L: CommitmentLeaves> {
+    Keccak256(StoredMerkleTree<L::Keccak256>),
+    #[cfg(feature = "blake3")]
+    Blake3(StoredMerkleTree<L::Blake3>),
+    Poseidon(StoredMerkleTree<L::Poseidon>),
+}
+
+impl<L: CommitmentLeaves> CommitmentTree<L> {
+    /// Fails if the tree is stored on disk and it can't be written.
+    pub fn build(
+        hash: CommitmentHash,
+        storage: &MerkleTreeStorage,
+        leaves: &[L::Data],
+    ) -> io::Result<Self> {
+        Ok(match hash {
+            CommitmentHash::Keccak256 => Self::Keccak256(StoredMerkleTree::build(storage, leaves)?),
+            #[cfg(feature = "blake3")]
+            CommitmentHash::Blake3 => Self::Blake3(StoredMerkleTree::build(storage, leaves)?),
+            CommitmentHash::Poseidon => Self::Poseidon(StoredMerkleTree::build(storage, leaves)?),
+        })
+    }
+
+    pub fn root(&self) -> Commitment {
+        match self {
+            Self::Keccak256(tree) => tree.root(),
+            #[cfg(feature = "blake3")]
+            Self::Blake3(tree) => tree.root(),
+            Self::Poseidon(tree) => tree.root(),
+        }
+    }
+
+    pub fn get_proof_by_pos(&self, pos: usize) -> Option<Proof<Commitment>> {
+        match self {
+            Self::Keccak256(tree) => tree.get_proof_by_pos(pos),
+            #[cfg(feature = "blake3")]
+            Self::Blake3(tree) => tree.get_proof_by_pos(pos),
+            Self::Poseidon(tree) => tree.get_proof_by_pos(pos),
+        }
+    }
+
+    /// Paths of the leaves at each of `positions`, in their order. Same as calling
+    /// `get_proof_by_pos` for each of them, but the nodes of trees stored on disk
+    /// that are shared by several paths are only read once.
+    pub fn get_proofs_by_pos(&self, positions: &[usize]) -> Option<Vec<Proof<Commitment>>> {
+        match self {
+            Self::Keccak256(tree) => tree.get_proofs_by_pos(positions),
+            #[cfg(feature = "blake3")]
+            Self::Blake3(tree) => tree.get_proofs_by_pos(positions),
+            Self::Poseidon(tree) => tree.get_proofs_by_pos(positions),
+        }
+    }
+
+    /// Height of the tree: the length of the paths from its leaves to its root.
+    pub fn depth(&self) -> usize {
+        self.get_proof_by_pos(0)
+            .map_or(0, |proof| proof.merkle_path.len())
+    }
+
+    /// The cap of the tree of height `height`, or the whole level of the leaves if
+    /// the tree isn't that high.
+    pub fn cap(&self, height: usize) -> MerkleCap {
+        let depth = self.depth();
+        let height = height.min(depth);
+        if height == 0 {
+            return vec![self.root()];
+        }
+        // A node of the cap is the sibling of the other child of its parent, so it's
+        // in the paths of the leaves below that one
+        let path_index = depth - height;
+        (0..1 << height)
+            .map(|node: usize| {
+                let leaf = (node ^ 1) << path_index;
+                self.get_proof_by_pos(leaf)
+                    .expect("the leaf is in the tree")
+                    .merkle_path[path_index]
+            })
+            .collect()
+    }
+
+    /// Paths of the leaves at each of `positions` up to the cap of height
+    /// `cap_height`, like the ones of `get_proofs_by_pos` without their last nodes.
+    pub fn get_proofs_by_pos_to_cap(
+        &self,
+        positions: &[usize],
+        cap_height: usize,
+    ) -> Option<Vec<Proof<Commitment>>> {
+        let path_len = self.depth().saturating_sub(cap_height);
+        let mut proofs = self.get_proofs_by_pos(positions)?;
+        for proof in &mut proofs {
+            proof.merkle_path.truncate(path_len);
+        }
+        Some(proofs)
+    }
+
+    /// Checks that `proof` opens the leaf `index` of the tree of root `root`, built
+    /// with `hash`, to `leaf`.
+    pub fn verify(
+        hash: CommitmentHash,
+        proof: &Proof<Commitment>,
+        root: &Commitment,
+        index: usize,
+        leaf: &L::Data,
+    ) -> bool {
+        match hash {
+            CommitmentHash::Keccak256 => proof.verify::<L::Keccak256>(root, index, leaf),
+            #[cfg(feature = "blake3")]
+            CommitmentHash::Blake3 => proof.verify::<L::Blake3>(root, index, leaf),
+            CommitmentHash::Poseidon => proof.verify::<L::Poseidon>(root, index, leaf),
+        }
+    }
+
+    /// Checks that `proof` opens the leaf `index` of the tree with cap `cap`, built
+    /// with `hash`, to `leaf`. The path goes from the leaf to the node of the cap
+    /// above it, so the bits of `index` above the ones of the path choose it, modulo
+    /// the size of the cap.
+    pub fn verify_with_cap(
+        hash: CommitmentHash,
+        proof: &Proof<Commitment>,
+        cap: &MerkleCap,
+        index: usize,
+        leaf: &L::Data,
+    ) -> bool {
+        if cap.is_empty() {
+            return false;
+        }
+        let node_index = index
+            .checked_shr(proof.merkle_path.len() as u32)
+            .unwrap_or(0)
+            % cap.len();
+        Self::verify(hash, proof, &cap[node_index], index, leaf)
+    }
+
+    /// Root of the tree with cap `cap`, built with `hash`. `None` if the size of
+    /// the cap isn't a power of two.
+    pub fn cap_root(hash: CommitmentHash, cap: &MerkleCap) -> Option<Commitment> {
+        if !cap.len().is_power_of_two() {
+            return None;
+        }
+        let mut nodes = cap.clone();
+        while nodes.len() > 1 {
+            nodes = nodes
+                .chunks(2)
+                .map(|pair| Self::hash_new_parent(hash, &pair[0], &pair[1]))
+                .collect();
+        }
+        nodes.pop()
+    }
+
+    /// Hash of the parent of two nodes of a tree built with `hash`.
+    pub fn hash_new_parent(
+        hash: CommitmentHash,
+        left: &Commitment,
+        right: &Commitment,
+    ) -> Commitment {
+        match hash {
+            CommitmentHash::Keccak256 => L::Keccak256::hash_new_parent(left, right),
+            #[cfg(feature = "blake3")]
+            CommitmentHash::Blake3 => L::Blake3::hash_new_parent(left, right),
+            CommitmentHash::Poseidon => L::Poseidon::hash_new_parent(left, right),
+        }
+    }
+}
+
+/// Merkle tree of a backend, stored as chosen with `MerkleTreeStorage`. The trees
+/// stored on disk are shared by their clones.
+pub enum StoredMerkleTree<B: IsMerkleTreeBackend<Node = Commitment>> {
+    InMemory(MerkleTree<B>),
+    #[cfg(feature = "mmap")]
+    OnDisk(Arc<DiskMerkleTree<B>>),
+}
+
+impl<B> StoredMerkleTree<B>
+where
+    B: IsMerkleTreeBackend<Node = Commitment> + Clone,
+{
+    pub fn build(storage: &MerkleTreeStorage, leaves: &[B::Data]) -> io::Result<Self> {
+        Ok(match storage {
+            MerkleTreeStorage::InMemory => Self::InMemory(MerkleTree::build(leaves)),
+            #[cfg(feature = "mmap")]
+            MerkleTreeStorage::OnDisk {
+                directory,
+                cached_levels,
+            } => Self::OnDisk(Arc::new(DiskMerkleTree::build(
+                leaves,
+                directory,
+                *cached_levels,
+            )?)),
+        })
+    }
+
+    pub fn root(&self) -> Commitment {
+        match self {
+            Self::InMemory(tree) => tree.root,
+            #[cfg(feature = "mmap")]
+            Self::OnDisk(tree) => tree.root,
+        }
+    }
+
+    pub fn get_proof_by_pos(&self, pos: usize) -> Option<Proof<Commitment>> {
+        match self {
+            Self::InMemory(tree) => tree.get_proof_by_pos(pos),
+            #[cfg(feature = "mmap")]
+            Self::OnDisk(tree) => tree.get_proof_by_pos(pos),
+        }
+    }
+
+    pub fn get_proofs_by_pos(&self, positions: &[usize]) -> Option<Vec<Proof<Commitment>>> {
+        match self {
+            // The nodes of the trees of lambdaworks can only be read through their
+            // paths, so there is nothing to share between them
+            Self::InMemory(tree) => positions
+                .iter()
+                .map(|pos| tree.get_proof_by_pos(*pos))
+                .collect(),
+            #[cfg(feature = "mmap")]
+            Self::OnDisk(tree) => tree.get_proofs_by_pos(positions),
+        }
+    }
+}
+
+impl<B> Clone for StoredMerkleTree<B>
+where
+    B: IsMerkleTreeBackend<Node = Commitment> + Clone,
+{
+    fn clone(&self) -> Self {
+        match self {
+            Self::InMemory(tree) => Self::InMemory(tree.clone()),
+            #[cfg(feature = "mmap")]
+            Self::OnDisk(tree) => Self::OnDisk(Arc::clone(tree)),
+        }
+    }
+}
+
+impl<L: CommitmentLeaves> Clone for CommitmentTree<L> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Keccak256(tree) => Self::Keccak256(tree.clone()),
+            #[cfg(feature = "blake3")]
+            Self::Blake3(tree) => Self::Blake3(tree.clone()),
+            Self::Poseidon(tree) => Self::Poseidon(tree.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    #[test]
+    fn paths_to_the_cap_open_the_leaves_and_the_cap_hashes_to_the_root() {
+        let leaves: Vec<FE> = (0..16).map(FE::from).collect();
+        for hash in [CommitmentHash::Keccak256, CommitmentHash::Poseidon] {
+            let tree =
+                FriMerkleTree::<F>::build(hash, &MerkleTreeStorage::InMemory, &leaves).unwrap();
+            assert_eq!(tree.depth(), 4);
+
+            for cap_height in [0, 2, 4, 6] {
+                let cap = tree.cap(cap_height);
+                assert_eq!(cap.len(), 1 << cap_height.min(4));
+                assert_eq!(FriMerkleTree::<F>::cap_root(hash, &cap), Some(tree.root()));
+
+                let positions: Vec<_> = (0..16).collect();
+                let proofs = tree
+                    .get_proofs_by_pos_to_cap(&positions, cap_height)
+                    .unwrap();
+                for (position, proof) in positions.into_iter().zip(proofs) {
+                    assert_eq!(proof.merkle_path.len(), 4 - cap_height.min(4));
+                    assert!(FriMerkleTree::<F>::verify_with_cap(
+                        hash,
+                        &proof,
+                        &cap,
+                        position,
+                        &leaves[position]
+                    ));
+                    // Indexes are taken modulo the number of leaves
+                    assert!(FriMerkleTree::<F>::verify_with_cap(
+                        hash,
+                        &proof,
+                        &cap,
+                        position + 16,
+                        &leaves[position]
+                    ));
+                    assert!(!FriMerkleTree::<F>::verify_with_cap(
+                        hash,
+                        &proof,
+                        &cap,
+                        position ^ 8,
+                        &leaves[position]
+                    ));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn caps_with_a_size_other_than_a_power_of_two_have_no_root() {
+        let cap = vec![[1; COMMITMENT_SIZE]; 3];
+        assert_eq!(
+            FriMerkleTree::<F>::cap_root(CommitmentHash::Keccak256, &cap),
+            None
+        );
+        assert_eq!(
+            FriMerkleTree::<F>::cap_root(CommitmentHash::Keccak256, &MerkleCap::new()),
+            None
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
+
+use super::symbolic::Expr;
+use crate::starks::frame::Frame;
+
+/// Node of the evaluation graph. Operands are indices of previous nodes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Node {
+    Cell { row: usize, col: usize },
+    Challenge(usize),
+    Constant(usize),
+    Neg(usize),
+    Add(usize, usize),
+    Sub(usize, usize),
+    Mul(usize, usize),
+}
+
+/// Transition constraints defined as symbolic expressions, compiled into an
+/// evaluation graph.
+///
+/// AIRs can use it instead of hand-writing `compute_transition`: the degrees of
+/// the constraints are inferred from the expressions, and subexpressions shared
+/// between constraints are evaluated only once.
+#[derive(Clone, Debug)]
+pub struct CompiledConstraints<F: IsFFTField> {
+    expressions: Vec<Expr<F>>,
+    nodes: Vec<Node>,
+    constants: Vec<FieldElement<F>>,
+    outputs: Vec<usize>,
+}
+
+impl<F: IsFFTField> CompiledConstraints<F> {
+    pub fn new(expressions: Vec<Expr<F>>) -> Self {
+        let mut compiled = Self {
+            expressions: Vec::new(),
+            nodes: Vec::new(),
+            constants: Vec::new(),
+            outputs: Vec::new(),
+        };
+
+        let mut cache = HashMap::new();
+        compiled.outputs = expressions
+            .iter()
+            .map(|expr| compiled.intern(expr, &mut cache))
+            .collect();
+        compiled.expressions = expressions;
+        compiled
+    }
+
+    /// Adds the nodes of `expr` to the graph, reusing the ones already in it,
+    /// and returns the index of its root node.
+    fn intern(&mut self, expr: &Expr<F>, cache: &mut HashMap<Node, usize>) -> usize {
+        let node = match expr {
+            Expr::Cell { row, col } => Node::Cell {
+                row: *row,
+                col: *col,
+            },
+            Expr::Challenge(index) => Node::Challenge(*index),
+            Expr::Constant(value) => {
+                let index = match self.constants.iter().position(|c| c == value) {
+                    Some(index) => index,
+                    None => {
+                        self.constants.push(value.clone());
+                        self.constants.len() - 1
+                    }
+                };
+                Node::Constant(index)
+            }
+            Expr::Neg(expr) => Node::Neg(self.intern(expr, cache)),
+            Expr::Add(lhs, rhs) => Node::Add(self.intern(lhs, cache), self.intern(rhs, cache)),
+            Expr::Sub(lhs, rhs) => Node::Sub(self.intern(lhs, cache), self.intern(rhs, cache)),
+            Expr::Mul(lhs, rhs) => Node::Mul(self.intern(lhs, cache), self.intern(rhs, cache)),
+        };
+
+        *cache.entry(node).or_insert_with(|| {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        })
+    }
+
+    pub fn expressions(&self) -> &[Expr<F>] {
+        &self.expressions
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Number of distinct subexpressions evaluated for each frame.
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Degrees of the constraints, to be used as the transition degrees of the AIR.
+    pub fn degrees(&self) -> Vec<usize> {
+        self.expressions.iter().map(Expr::degree).collect()
+    }
+
+    fn evaluate_node(
+        &self,
+        node: &Node,
+        values: &[FieldElement<F>],
+        frame: &Frame<F>,
+        challenges: &[FieldElement<F>],
+    ) -> FieldElement<F> {
+        match node {
+            Node::Cell { row, col } => frame.get_row(*row)[*col].clone(),
+            Node::Challenge(index) => challenges[*index].clone(),
+            Node::Constant(index) => self.constants[*index].clone(),
+            Node::Neg(operand) => -&values[*operand],
+            Node::Add(lhs, rhs) => &values[*lhs] + &values[*rhs],
+            Node::Sub(lhs, rhs) => &values[*lhs] - &values[*rhs],
+            Node::Mul(lhs, rhs) => &values[*lhs] * &values[*rhs],
+        }
+    }
+
+    /// Evaluates the constraints over a frame, in the same way `compute_transition` does.
+    pub fn evaluate(
+        &self,
+        frame: &Frame<F>,
+        challenges: &[FieldElement<F>],
+    ) -> Vec<FieldElement<F>> {
+        let mut values = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let value = self.evaluate_node(node, &values, frame, challenges);
+            values.push(value);
+        }
+
+        self.outputs
+            .iter()
+            .map(|output| values[*output].clone())
+            .collect()
+    }
+
+    /// Evaluates the constraints over many frames at once. Each node of the graph
+    /// is evaluated over all the frames before moving to the next one, so every
+    /// operation is applied to a whole vector of values.
+    ///
+    /// Returns the evaluations of each frame, as `evaluate` does.
+    pub fn evaluate_batch(
+        &self,
+        frames: &[Frame<F>],
+        challenges: &[FieldElement<F>],
+    ) -> Vec<Vec<FieldElement<F>>> {
+        let mut values: Vec<Vec<FieldElement<F>>> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let node_values = match node {
+                Node::Cell { row, col } => frames
+                    .iter()
+                    .map(|frame| frame.get_row(*row)[*col].clone())
+                    .collect(),
+                Node::Challenge(index) => vec![challenges[*index].clone(); frames.len()],
+                Node::Constant(index) => vec![self.constants[*index].clone(); frames.len()],
+                Node::Neg(operand) => values[*operand].iter().map(|value| -value).collect(),
+                Node::Add(lhs, rhs) => values[*lhs]
+                    .iter()
+                    .zip(&values[*rhs])
+                    .map(|(lhs, rhs)| lhs + rhs)
+                    .collect(),
+                Node::Sub(lhs, rhs) => values[*lhs]
+                    .iter()
+                    .zip(&values[*rhs])
+                    .map(|(lhs, rhs)| lhs - rhs)
+                    .collect(),
+                Node::Mul(lhs, rhs) => values[*lhs]
+                    .iter()
+                    .zip(&values[*rhs])
+                    .map(|(lhs, rhs)| lhs * rhs)
+                    .collect(),
+            };
+            values.push(node_values);
+        }
+
+        (0..frames.len())
+            .map(|frame_idx| {
+                self.outputs
+                    .iter()
+                    .map(|output| values[*output][frame_idx].clone())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambdaworks_math::field::fields::u64_prime_field::F17;
+
+    type FE = FieldElement<F17>;
+
+    fn constraints() -> CompiledConstraints<F17> {
+        let a = Expr::cell(0, 0);
+        let b = Expr::cell(1, 0);
+        let z = Expr::challenge(0);
+
+        CompiledConstraints::new(vec![
+            &a * &b - &z,
+            &a * &b * &a + Expr::constant(FE::from(3)),
+            -(&b - &z),
+        ])
+    }
+
+    #[test]
+    fn shared_subexpressions_are_evaluated_once() {
+        // a, b, z, a * b, a * b - z, a * b * a, 3, a * b * a + 3, b - z, -(b - z)
+        assert_eq!(constraints().num_nodes(), 10);
+    }
+
+    #[test]
+    fn degrees_are_inferred_from_the_expressions() {
+        assert_eq!(constraints().degrees(), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn compiled_constraints_evaluate_as_the_expressions() {
+        let constraints = constraints();
+        let frames = vec![
+            Frame::new(vec![FE::from(2), FE::from(5)], 1),
+            Frame::new(vec![FE::from(7), FE::from(11)], 1),
+        ];
+        let challenges = vec![FE::from(4)];
+
+        for frame in &frames {
+            let expected: Vec<_> = constraints
+                .expressions()
+                .iter()
+                .map(|expr| expr.evaluate(frame, &challenges))
+                .collect();
+            assert_eq!(constraints.evaluate(frame, &challenges), expected);
+        }
+
+        let batch = constraints.evaluate_batch(&frames, &challenges);
+        assert_eq!(batch[0], constraints.evaluate(&frames[0], &challenges));
+        assert_eq!(batch[1], constraints.evaluate(&frames[1], &challenges));
+    }
+}
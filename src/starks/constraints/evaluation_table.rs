@@ -24,11 +24,81 @@ impl<F: IsField> ConstraintEvaluationTable<F> {
         }
     }
 
-    pub fn compute_composition_poly(&self, offset: &FieldElement<F>) -> Polynomial<FieldElement<F>>
+    /// Returns the even and odd parts `H_1` and `H_2` of the composition polynomial
+    /// `H(x) = H_1(x²) + x H_2(x²)`, given the points of the LDE coset where the
+    /// table was evaluated.
+    ///
+    /// The split is done in evaluation form: the points `x` and `-x` are half the
+    /// coset apart, so `H_1(x²) = (H(x) + H(-x)) / 2` and
+    /// `H_2(x²) = (H(x) - H(-x)) / 2x`, and each part is interpolated over the
+    /// squares of the first half of the coset. This takes two interpolations of half
+    /// the size of the coset instead of one of `H` over the whole of it.
+    pub fn compute_composition_poly_parts(
+        &self,
+        lde_coset: &[FieldElement<F>],
+    ) -> (Polynomial<FieldElement<F>>, Polynomial<FieldElement<F>>)
     where
         F: IsFFTField,
         Polynomial<FieldElement<F>>: FFTPoly<F>,
     {
-        Polynomial::interpolate_offset_fft(&self.evaluations_acc, offset).unwrap()
+        let evaluations = &self.evaluations_acc;
+        debug_assert_eq!(evaluations.len(), lde_coset.len());
+        let half = evaluations.len() / 2;
+        let (h_x, h_minus_x) = evaluations.split_at(half);
+
+        let mut two_x_inverses: Vec<_> = lde_coset[..half].iter().map(|x| x + x).collect();
+        FieldElement::inplace_batch_inverse(&mut two_x_inverses);
+        let two_inverse = FieldElement::<F>::from(2).inv();
+
+        let (even_evaluations, odd_evaluations): (Vec<_>, Vec<_>) = h_x
+            .iter()
+            .zip(h_minus_x)
+            .zip(&two_x_inverses)
+            .map(|((h_x, h_minus_x), two_x_inverse)| {
+                (
+                    (h_x + h_minus_x) * &two_inverse,
+                    (h_x - h_minus_x) * two_x_inverse,
+                )
+            })
+            .unzip();
+
+        let offset_squared = lde_coset[0].square();
+        (
+            Polynomial::interpolate_offset_fft(&even_evaluations, &offset_squared).unwrap(),
+            Polynomial::interpolate_offset_fft(&odd_evaluations, &offset_squared).unwrap(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambdaworks_math::{
+        fft::cpu::roots_of_unity::get_powers_of_primitive_root_coset,
+        field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+    };
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    #[test]
+    fn composition_poly_parts_match_the_decomposition_of_the_interpolation() {
+        let offset = FE::from(3);
+        let lde_coset =
+            get_powers_of_primitive_root_coset::<Stark252PrimeField>(5, 32, &offset).unwrap();
+        let coefficients: Vec<_> = (0..16_u64).map(|i| FE::from(i * i + 7)).collect();
+        let composition_poly = Polynomial::new(&coefficients);
+
+        let table = ConstraintEvaluationTable {
+            evaluations_acc: lde_coset
+                .iter()
+                .map(|x| composition_poly.evaluate(x))
+                .collect(),
+            trace_length: lde_coset.len(),
+        };
+
+        assert_eq!(
+            table.compute_composition_poly_parts(&lde_coset),
+            composition_poly.even_odd_decomposition()
+        );
     }
 }
@@ -21,16 +21,18 @@ use crate::starks::traits::AIR;
 
 use super::{boundary::BoundaryConstraints, evaluation_table::ConstraintEvaluationTable};
 
-pub struct ConstraintEvaluator<F: IsFFTField, A: AIR> {
-    air: A,
+/// Evaluates the constraints of an AIR over the LDE of a trace. It borrows the AIR
+/// instead of copying it, so AIRs holding large tables cost nothing extra here.
+pub struct ConstraintEvaluator<'a, F: IsFFTField, A: AIR> {
+    air: &'a A,
     boundary_constraints: BoundaryConstraints<F>,
 }
-impl<F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<F, A> {
-    pub fn new(air: &A, rap_challenges: &A::RAPChallenges) -> Self {
+impl<'a, F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<'a, F, A> {
+    pub fn new(air: &'a A, rap_challenges: &A::RAPChallenges) -> Self {
         let boundary_constraints = air.boundary_constraints(rap_challenges);
 
         Self {
-            air: air.clone(),
+            air,
             boundary_constraints,
         }
     }
@@ -72,8 +74,10 @@ impl<F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<F, A> {
                 .collect::<Vec<Vec<FieldElement<F>>>>();
 
         let trace_length = self.air.trace_length();
-        let composition_poly_degree_bound = self.air.composition_poly_degree_bound();
-        let boundary_term_degree_adjustment = composition_poly_degree_bound - trace_length;
+        let boundary_term_degree_adjustment = self
+            .air
+            .degree_adjustment(1)
+            .expect("the degree bound covers the boundary constraints");
         // Maybe we can do this more efficiently by taking the offset's power and then using successors for roots of unity
         let d_adjustment_power = domain
             .lde_roots_of_unity_coset
@@ -129,34 +133,70 @@ impl<F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<F, A> {
 
         let transition_exemptions_evaluations =
             evaluate_transition_exemptions(transition_exemptions, domain);
-        let num_exemptions = self.air.context().num_transition_exemptions;
+        let zone_exemptions_evaluations: Vec<Option<Vec<FieldElement<F>>>> = self
+            .air
+            .transition_exemption_zones()
+            .iter()
+            .map(|zones| {
+                zones.as_ref().map(|zones| {
+                    evaluate_polynomial_on_lde_domain(
+                        zones,
+                        domain.blowup_factor,
+                        domain.interpolation_domain_size,
+                        &domain.coset_offset,
+                    )
+                    .unwrap()
+                })
+            })
+            .collect();
         let context = self.air.context();
-        let max_transition_degree = *context.transition_degrees.iter().max().unwrap();
+        let num_exemptions = context.num_effective_transition_exemptions();
+        let effective_exemptions = context.effective_transition_exemptions();
+
+        // The exponents of the degree adjustments depend on the degree and the exemption
+        // zones of each constraint, see `AIR::transition_degree_adjustment`. Each distinct
+        // one is evaluated once.
+        let transition_degree_adjustments: Vec<u64> = (0..context.num_transition_constraints())
+            .map(|constraint| {
+                self.air
+                    .transition_degree_adjustment(constraint)
+                    .expect("the degree bound covers the transition constraints")
+            })
+            .collect();
+        let adjustment_exponents: Vec<u64> = transition_degree_adjustments
+            .iter()
+            .copied()
+            .unique()
+            .collect();
+        let adjustment_indexes: Vec<usize> = transition_degree_adjustments
+            .iter()
+            .map(|exponent| {
+                adjustment_exponents
+                    .iter()
+                    .position(|other| other == exponent)
+                    .expect("every exponent is in the unique ones")
+            })
+            .collect();
 
         #[cfg(feature = "parallel")]
-        let degree_adjustments_iter = (1..=max_transition_degree).into_par_iter();
+        let degree_adjustments_iter = adjustment_exponents.par_iter();
 
         #[cfg(not(feature = "parallel"))]
-        let degree_adjustments_iter = 1..=max_transition_degree;
+        let degree_adjustments_iter = adjustment_exponents.iter();
 
         let degree_adjustments: Vec<Vec<FieldElement<F>>> = degree_adjustments_iter
-            .map(|transition_degree| {
+            .map(|exponent| {
                 domain
                     .lde_roots_of_unity_coset
                     .iter()
-                    .map(|d| {
-                        let degree_adjustment = composition_poly_degree_bound
-                            - (trace_length * (transition_degree - 1));
-                        d.pow(degree_adjustment)
-                    })
+                    .map(|d| d.pow(*exponent))
                     .collect()
             })
             .collect();
 
         let blowup_factor_order = u64::from(blowup_factor.trailing_zeros());
 
-        let offset = FieldElement::<F>::from(self.air.context().proof_options.coset_offset);
-        let offset_pow = offset.pow(trace_length);
+        let offset_pow = domain.coset_offset.pow(trace_length as u64);
         let one = FieldElement::<F>::one();
         let mut zerofier_evaluations = get_powers_of_primitive_root_coset(
             blowup_factor_order,
@@ -170,7 +210,9 @@ impl<F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<F, A> {
 
         FieldElement::inplace_batch_inverse(&mut zerofier_evaluations);
 
-        // Iterate over trace and domain and compute transitions
+        // Iterate over trace and domain and compute transitions. Each evaluation is
+        // reduced by a single thread and they are collected in the order of the domain,
+        // so the result doesn't depend on the number of threads.
         let evaluations_t_iter;
         let zerofier_iter;
         #[cfg(feature = "parallel")]
@@ -203,33 +245,35 @@ impl<F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<F, A> {
                 transition_evaluations.push(evaluations_transition.clone());
 
                 let acc_transition = evaluations_transition
-                    .iter()
-                    .zip(&self.air.context().transition_exemptions)
-                    .zip(&self.air.context().transition_degrees)
+                    .into_iter()
+                    .zip(&zone_exemptions_evaluations)
+                    .map(|(eval, zones)| match zones {
+                        Some(zones) => eval * &zones[i],
+                        None => eval,
+                    })
+                    .zip(&effective_exemptions)
+                    .zip(&adjustment_indexes)
                     .zip(alpha_and_beta_transition_coefficients)
                     .fold(
                         FieldElement::zero(),
-                        |acc, (((eval, exemption), degree), (alpha, beta))| {
+                        |acc, (((eval, exemption), adjustment), (alpha, beta))| {
                             #[cfg(feature = "parallel")]
                             let zerofier = zerofier.clone();
 
                             if *exemption == 0 {
                                 acc + zerofier
-                                    * (alpha * &degree_adjustments[degree - 1][i] + beta)
+                                    * (alpha * &degree_adjustments[*adjustment][i] + beta)
                                     * eval
                             } else {
                                 //TODO: change how exemptions are indexed!
                                 if num_exemptions == 1 {
                                     acc + zerofier
-                                        * (alpha * &degree_adjustments[degree - 1][i] + beta)
+                                        * (alpha * &degree_adjustments[*adjustment][i] + beta)
                                         * eval
                                         * &transition_exemptions_evaluations[0][i]
                                 } else {
                                     // This case is not used for Cairo Programs, it can be improved in the future
-                                    let vector = &self
-                                        .air
-                                        .context()
-                                        .transition_exemptions
+                                    let vector = &effective_exemptions
                                         .iter()
                                         .cloned()
                                         .filter(|elem| elem > &0)
@@ -241,7 +285,7 @@ impl<F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<F, A> {
                                         .expect("is there");
 
                                     acc + zerofier
-                                        * (alpha * &degree_adjustments[degree - 1][i] + beta)
+                                        * (alpha * &degree_adjustments[*adjustment][i] + beta)
                                         * eval
                                         * &transition_exemptions_evaluations[index][i]
                                 }
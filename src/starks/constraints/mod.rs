@@ -1,3 +1,6 @@
 pub mod boundary;
+pub mod compiled;
 pub mod evaluation_table;
 pub mod evaluator;
+pub mod snapshot;
+pub mod symbolic;
@@ -0,0 +1,89 @@
+use std::fmt::Write;
+
+use serde_json::json;
+
+use super::symbolic::{default_cell_name, default_challenge_name};
+use crate::starks::traits::AIR;
+
+/// Description of a transition constraint of an AIR.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintDescription {
+    pub index: usize,
+    pub name: Option<&'static str>,
+    pub degree: usize,
+    pub exemptions: usize,
+    pub expression: String,
+}
+
+/// Human readable description of the transition constraints of an AIR, meant
+/// to be reviewed and diffed across versions of the constraint system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintSnapshot {
+    pub constraints: Vec<ConstraintDescription>,
+}
+
+impl ConstraintSnapshot {
+    /// Builds the snapshot from the symbolic transition constraints of the AIR.
+    /// Returns `None` if the AIR doesn't describe its constraints symbolically.
+    pub fn from_air<A: AIR>(air: &A) -> Option<Self> {
+        let expressions = air.symbolic_transition()?;
+        let challenge_names = air.rap_challenge_names();
+        let challenge_name = |index: usize| match challenge_names.get(index) {
+            Some(name) => name.to_string(),
+            None => default_challenge_name(index),
+        };
+
+        let context = air.context();
+        let constraints = expressions
+            .iter()
+            .enumerate()
+            .map(|(index, expression)| ConstraintDescription {
+                index,
+                name: air.transition_constraint_name(index),
+                degree: context.transition_degrees[index],
+                exemptions: context.transition_exemptions[index],
+                expression: expression
+                    .display_with(&default_cell_name, &challenge_name)
+                    .to_string(),
+            })
+            .collect();
+
+        Some(Self { constraints })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for constraint in &self.constraints {
+            // Writing to a `String` never fails
+            let _ = writeln!(
+                text,
+                "[{}] {} (degree {}, exemptions {})\n    {} = 0",
+                constraint.index,
+                constraint.name.unwrap_or("unnamed"),
+                constraint.degree,
+                constraint.exemptions,
+                constraint.expression,
+            );
+        }
+        text
+    }
+
+    pub fn to_json(&self) -> String {
+        let constraints: Vec<_> = self
+            .constraints
+            .iter()
+            .map(|constraint| {
+                json!({
+                    "index": constraint.index,
+                    "name": constraint.name,
+                    "degree": constraint.degree,
+                    "exemptions": constraint.exemptions,
+                    "expression": constraint.expression,
+                })
+            })
+            .collect();
+
+        serde_json::to_string_pretty(&json!({ "constraints": constraints }))
+            .expect("JSON values are always serializable")
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+use std::ops::{Add, Mul, Neg, Sub};
+
+use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
+
+use crate::starks::frame::Frame;
+
+/// Symbolic expression of a constraint over the cells of a frame and the
+/// challenges of the RAP rounds.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr<F: IsFFTField> {
+    /// Value of column `col` in the row `row` of the frame.
+    Cell {
+        row: usize,
+        col: usize,
+    },
+    /// RAP challenge, by its position in the list of challenges of the AIR.
+    Challenge(usize),
+    Constant(FieldElement<F>),
+    Neg(Box<Expr<F>>),
+    Add(Box<Expr<F>>, Box<Expr<F>>),
+    Sub(Box<Expr<F>>, Box<Expr<F>>),
+    Mul(Box<Expr<F>>, Box<Expr<F>>),
+}
+
+impl<F: IsFFTField> Expr<F> {
+    pub fn cell(row: usize, col: usize) -> Self {
+        Self::Cell { row, col }
+    }
+
+    pub fn challenge(index: usize) -> Self {
+        Self::Challenge(index)
+    }
+
+    pub fn constant(value: FieldElement<F>) -> Self {
+        Self::Constant(value)
+    }
+
+    pub fn one() -> Self {
+        Self::Constant(FieldElement::one())
+    }
+
+    /// Evaluates the expression over the given frame and challenges.
+    pub fn evaluate(&self, frame: &Frame<F>, challenges: &[FieldElement<F>]) -> FieldElement<F> {
+        match self {
+            Self::Cell { row, col } => frame.get_row(*row)[*col].clone(),
+            Self::Challenge(index) => challenges[*index].clone(),
+            Self::Constant(value) => value.clone(),
+            Self::Neg(expr) => -expr.evaluate(frame, challenges),
+            Self::Add(lhs, rhs) => {
+                lhs.evaluate(frame, challenges) + rhs.evaluate(frame, challenges)
+            }
+            Self::Sub(lhs, rhs) => {
+                lhs.evaluate(frame, challenges) - rhs.evaluate(frame, challenges)
+            }
+            Self::Mul(lhs, rhs) => {
+                lhs.evaluate(frame, challenges) * rhs.evaluate(frame, challenges)
+            }
+        }
+    }
+
+    /// Degree of the expression as a polynomial in the trace cells. Challenges and
+    /// constants have degree zero.
+    pub fn degree(&self) -> usize {
+        match self {
+            Self::Cell { .. } => 1,
+            Self::Challenge(_) | Self::Constant(_) => 0,
+            Self::Neg(expr) => expr.degree(),
+            Self::Add(lhs, rhs) | Self::Sub(lhs, rhs) => lhs.degree().max(rhs.degree()),
+            Self::Mul(lhs, rhs) => lhs.degree() + rhs.degree(),
+        }
+    }
+
+    /// Adds the cells the expression references to `cells`, as `(row, column)` pairs.
+    pub fn collect_cells(&self, cells: &mut BTreeSet<(usize, usize)>) {
+        match self {
+            Self::Cell { row, col } => {
+                cells.insert((*row, *col));
+            }
+            Self::Challenge(_) | Self::Constant(_) => {}
+            Self::Neg(expr) => expr.collect_cells(cells),
+            Self::Add(lhs, rhs) | Self::Sub(lhs, rhs) | Self::Mul(lhs, rhs) => {
+                lhs.collect_cells(cells);
+                rhs.collect_cells(cells);
+            }
+        }
+    }
+
+    /// Formats the expression, naming cells and challenges with the given functions.
+    pub fn display_with<'a>(
+        &'a self,
+        cell_name: &'a dyn Fn(usize, usize) -> String,
+        challenge_name: &'a dyn Fn(usize) -> String,
+    ) -> impl Display + 'a {
+        ExprDisplay {
+            expr: self,
+            cell_name,
+            challenge_name,
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Add(..) | Self::Sub(..) => 1,
+            Self::Mul(..) => 2,
+            Self::Neg(..) => 3,
+            Self::Cell { .. } | Self::Challenge(_) | Self::Constant(_) => 4,
+        }
+    }
+}
+
+/// Default name of a cell: `cur[col]` and `next[col]` for the first two rows of
+/// the frame, and `row{row}[col]` for the rest.
+pub fn default_cell_name(row: usize, col: usize) -> String {
+    match row {
+        0 => format!("cur[{col}]"),
+        1 => format!("next[{col}]"),
+        _ => format!("row{row}[{col}]"),
+    }
+}
+
+pub fn default_challenge_name(index: usize) -> String {
+    format!("challenge[{index}]")
+}
+
+struct ExprDisplay<'a, F: IsFFTField> {
+    expr: &'a Expr<F>,
+    cell_name: &'a dyn Fn(usize, usize) -> String,
+    challenge_name: &'a dyn Fn(usize) -> String,
+}
+
+impl<'a, F: IsFFTField> ExprDisplay<'a, F> {
+    fn with(&self, expr: &'a Expr<F>) -> Self {
+        Self {
+            expr,
+            cell_name: self.cell_name,
+            challenge_name: self.challenge_name,
+        }
+    }
+
+    /// Writes `expr`, adding parentheses if it binds looser than `min_precedence`.
+    fn write_operand(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        expr: &'a Expr<F>,
+        min_precedence: u8,
+    ) -> fmt::Result {
+        if expr.precedence() < min_precedence {
+            write!(f, "({})", self.with(expr))
+        } else {
+            write!(f, "{}", self.with(expr))
+        }
+    }
+}
+
+impl<'a, F: IsFFTField> Display for ExprDisplay<'a, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.expr {
+            Expr::Cell { row, col } => write!(f, "{}", (self.cell_name)(*row, *col)),
+            Expr::Challenge(index) => write!(f, "{}", (self.challenge_name)(*index)),
+            Expr::Constant(value) => write!(f, "{}", value.representative()),
+            Expr::Neg(expr) => {
+                write!(f, "-")?;
+                self.write_operand(f, expr, 4)
+            }
+            Expr::Add(lhs, rhs) => {
+                self.write_operand(f, lhs, 1)?;
+                write!(f, " + ")?;
+                self.write_operand(f, rhs, 1)
+            }
+            // The right operand of a subtraction needs parentheses if it's a sum
+            Expr::Sub(lhs, rhs) => {
+                self.write_operand(f, lhs, 1)?;
+                write!(f, " - ")?;
+                self.write_operand(f, rhs, 2)
+            }
+            Expr::Mul(lhs, rhs) => {
+                self.write_operand(f, lhs, 2)?;
+                write!(f, " * ")?;
+                self.write_operand(f, rhs, 2)
+            }
+        }
+    }
+}
+
+impl<F: IsFFTField> Display for Expr<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.display_with(&default_cell_name, &default_challenge_name)
+        )
+    }
+}
+
+impl<F: IsFFTField> Neg for Expr<F> {
+    type Output = Expr<F>;
+
+    fn neg(self) -> Self::Output {
+        Expr::Neg(Box::new(self))
+    }
+}
+
+impl<F: IsFFTField> Neg for &Expr<F> {
+    type Output = Expr<F>;
+
+    fn neg(self) -> Self::Output {
+        -self.clone()
+    }
+}
+
+macro_rules! impl_binary_op {
+    ($trait:ident, $method:ident, $variant:ident) => {
+        impl<F: IsFFTField> $trait<Expr<F>> for Expr<F> {
+            type Output = Expr<F>;
+
+            fn $method(self, rhs: Expr<F>) -> Self::Output {
+                Expr::$variant(Box::new(self), Box::new(rhs))
+            }
+        }
+
+        impl<F: IsFFTField> $trait<&Expr<F>> for Expr<F> {
+            type Output = Expr<F>;
+
+            fn $method(self, rhs: &Expr<F>) -> Self::Output {
+                Expr::$variant(Box::new(self), Box::new(rhs.clone()))
+            }
+        }
+
+        impl<F: IsFFTField> $trait<Expr<F>> for &Expr<F> {
+            type Output = Expr<F>;
+
+            fn $method(self, rhs: Expr<F>) -> Self::Output {
+                Expr::$variant(Box::new(self.clone()), Box::new(rhs))
+            }
+        }
+
+        impl<F: IsFFTField> $trait<&Expr<F>> for &Expr<F> {
+            type Output = Expr<F>;
+
+            fn $method(self, rhs: &Expr<F>) -> Self::Output {
+                Expr::$variant(Box::new(self.clone()), Box::new(rhs.clone()))
+            }
+        }
+    };
+}
+
+impl_binary_op!(Add, add, Add);
+impl_binary_op!(Sub, sub, Sub);
+impl_binary_op!(Mul, mul, Mul);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambdaworks_math::field::fields::u64_prime_field::F17;
+
+    type FE = FieldElement<F17>;
+
+    #[test]
+    fn expressions_are_evaluated_over_the_frame() {
+        let frame = Frame::new(vec![FE::from(2), FE::from(3), FE::from(5), FE::from(7)], 2);
+        let expr = Expr::cell(0, 0) * Expr::cell(1, 1) - Expr::challenge(0) + Expr::one();
+
+        assert_eq!(expr.evaluate(&frame, &[FE::from(4)]), FE::from(11));
+    }
+
+    #[test]
+    fn cells_are_collected_once() {
+        let expr =
+            Expr::<F17>::cell(1, 0) * Expr::cell(0, 2) - Expr::cell(1, 0) + Expr::challenge(0);
+        let mut cells = BTreeSet::new();
+        expr.collect_cells(&mut cells);
+
+        assert_eq!(cells.into_iter().collect::<Vec<_>>(), vec![(0, 2), (1, 0)]);
+    }
+
+    #[test]
+    fn degree_counts_the_trace_cells_in_products() {
+        let a = Expr::<F17>::cell(0, 0);
+        let z = Expr::<F17>::challenge(0);
+
+        assert_eq!((&z * &z).degree(), 0);
+        assert_eq!((&a * &z + Expr::one()).degree(), 1);
+        assert_eq!((&a * (&a - &z) * &a).degree(), 3);
+    }
+
+    #[test]
+    fn expressions_are_displayed_with_the_needed_parentheses() {
+        let a = Expr::<F17>::cell(0, 0);
+        let b = Expr::<F17>::cell(1, 2);
+        let c = Expr::<F17>::challenge(1);
+
+        assert_eq!(
+            (&a + &b * &c).to_string(),
+            "cur[0] + next[2] * challenge[1]"
+        );
+        assert_eq!(
+            ((&a + &b) * &c).to_string(),
+            "(cur[0] + next[2]) * challenge[1]"
+        );
+        assert_eq!(
+            (&a - (&b - &c)).to_string(),
+            "cur[0] - (next[2] - challenge[1])"
+        );
+        assert_eq!((-(&a + &b)).to_string(), "-(cur[0] + next[2])");
+    }
+}
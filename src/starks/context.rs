@@ -1,3 +1,5 @@
+use itertools::Itertools;
+
 use super::proof::options::ProofOptions;
 
 #[derive(Clone, Debug)]
@@ -11,17 +13,63 @@ pub struct AirContext {
     /// in one method (`compute_transitions`), this vector needs to include the
     /// offsets that are needed to compute EVERY transition constraint, even if some
     /// constraints don't use all of the indexes in said offsets.
+    /// The out-of-domain frame of a proof has exactly one row for each of them, in
+    /// the same order, so they don't need to be consecutive.
     pub transition_offsets: Vec<usize>,
+    /// Number of steps at the end of the trace where each transition constraint
+    /// doesn't apply.
     pub transition_exemptions: Vec<usize>,
+    /// Other steps where each transition constraint doesn't apply, or empty if no
+    /// constraint has any. See `ExemptionZones`.
+    pub transition_exemption_zones: Vec<ExemptionZones>,
     pub num_transition_constraints: usize,
     pub num_transition_exemptions: usize,
 }
 
+/// Steps of the trace where a transition constraint doesn't apply besides the last
+/// ones of `AirContext::transition_exemptions`, like warm-up rows at the start of
+/// the trace or the rows where two executions are stitched together.
+///
+/// Like the ones at the end, each exempted step is a root less of the zerofier of
+/// the constraint, so it raises the degree of its quotient by one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExemptionZones {
+    /// Number of steps at the start of the trace
+    pub start: usize,
+    /// Any other steps, in any order
+    pub steps: Vec<usize>,
+}
+
+/// Smallest blowup factor of the LDE of a transition constraint of degree `degree`.
+/// Evaluated on the trace polynomials, the constraint has degree up to `degree`
+/// times the trace length, so the LDE domain must be that many times larger than
+/// the trace for its evaluations to determine it.
+pub fn min_blowup_factor_of_degree(degree: usize) -> usize {
+    degree.max(1).next_power_of_two()
+}
+
 impl AirContext {
     pub fn num_transition_constraints(&self) -> usize {
         self.num_transition_constraints
     }
 
+    /// Smallest blowup factor the transition constraints allow, the one of the
+    /// constraint of highest degree. See `min_blowup_factor_of_degree`.
+    pub fn min_blowup_factor(&self) -> usize {
+        self.transition_degrees
+            .iter()
+            .map(|degree| min_blowup_factor_of_degree(*degree))
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Row of the evaluation frame holding the row of `offset` of the trace.
+    pub fn frame_row_of_offset(&self, offset: usize) -> Option<usize> {
+        self.transition_offsets
+            .iter()
+            .position(|frame_offset| *frame_offset == offset)
+    }
+
     pub fn transition_degrees(&self) -> &[usize] {
         &self.transition_degrees
     }
@@ -29,4 +77,107 @@ impl AirContext {
     pub fn transition_degrees_len(&self) -> usize {
         self.transition_degrees.len()
     }
+
+    /// Number of steps at the end of the trace where each transition constraint
+    /// does not apply. On top of the exemptions of the AIR, no constraint applies
+    /// to the random rows appended by the prover.
+    pub fn effective_transition_exemptions(&self) -> Vec<usize> {
+        self.effective_transition_exemptions_iter().collect()
+    }
+
+    /// Same as `effective_transition_exemptions`, without allocating them.
+    pub fn effective_transition_exemptions_iter(&self) -> impl Iterator<Item = usize> + '_ {
+        let randomization_rows = self.proof_options.trace_randomization_rows;
+        self.transition_exemptions
+            .iter()
+            .map(move |exemptions| exemptions + randomization_rows)
+    }
+
+    /// Steps of a trace of `trace_length` steps where the exemption zones of the
+    /// transition constraint apply, in increasing order. The ones already exempted
+    /// at the end of the trace, see `effective_transition_exemptions`, aren't
+    /// included, so each exempted step is in either of them once.
+    pub fn zone_exempted_steps(&self, constraint: usize, trace_length: usize) -> Vec<usize> {
+        let Some(zones) = self.transition_exemption_zones.get(constraint) else {
+            return Vec::new();
+        };
+        let end_exemptions = self
+            .effective_transition_exemptions_iter()
+            .nth(constraint)
+            .unwrap_or(0);
+        let end_zone = trace_length.saturating_sub(end_exemptions);
+
+        (0..zones.start)
+            .chain(zones.steps.iter().copied())
+            .filter(|step| *step < end_zone)
+            .sorted_unstable()
+            .dedup()
+            .collect()
+    }
+
+    /// Number of distinct non-zero effective exemptions. See
+    /// `effective_transition_exemptions`.
+    pub fn num_effective_transition_exemptions(&self) -> usize {
+        if self.proof_options.randomizes_trace() {
+            self.transition_exemptions.iter().unique().count()
+        } else {
+            self.num_transition_exemptions
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context_with_zones(
+        transition_exemptions: Vec<usize>,
+        transition_exemption_zones: Vec<ExemptionZones>,
+    ) -> AirContext {
+        AirContext {
+            proof_options: ProofOptions::default_test_options(),
+            trace_columns: 1,
+            transition_degrees: vec![1; transition_exemptions.len()],
+            transition_offsets: vec![0, 1],
+            num_transition_constraints: transition_exemptions.len(),
+            transition_exemptions,
+            transition_exemption_zones,
+            num_transition_exemptions: 1,
+        }
+    }
+
+    #[test]
+    fn zones_exempt_the_start_and_the_steps_not_exempted_at_the_end() {
+        let context = context_with_zones(
+            vec![2, 0],
+            vec![
+                ExemptionZones {
+                    start: 2,
+                    steps: vec![5, 1, 7, 14, 5],
+                },
+                ExemptionZones::default(),
+            ],
+        );
+
+        assert_eq!(context.zone_exempted_steps(0, 16), vec![0, 1, 5, 7]);
+        assert_eq!(context.zone_exempted_steps(1, 16), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn the_minimum_blowup_factor_is_the_one_of_the_highest_degree() {
+        let mut context = context_with_zones(vec![0; 3], Vec::new());
+        assert_eq!(context.min_blowup_factor(), 1);
+
+        context.transition_degrees = vec![1, 3, 2];
+        assert_eq!(context.min_blowup_factor(), 4);
+        assert_eq!(min_blowup_factor_of_degree(2), 2);
+        assert_eq!(min_blowup_factor_of_degree(5), 8);
+    }
+
+    #[test]
+    fn constraints_without_zones_have_no_zone_exempted_steps() {
+        let context = context_with_zones(vec![1], Vec::new());
+
+        assert_eq!(context.zone_exempted_steps(0, 16), Vec::<usize>::new());
+    }
 }
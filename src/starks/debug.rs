@@ -5,12 +5,49 @@ use super::domain::Domain;
 use super::traits::AIR;
 use lambdaworks_math::fft::polynomial::FFTPoly;
 use lambdaworks_math::{
-    field::{element::FieldElement, traits::IsFFTField},
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
     polynomial::Polynomial,
 };
-use log::{error, info};
+use tracing::{error, info};
 
-/// Validates that the trace is valid with respect to the supplied AIR constraints
+/// Constraints of an AIR not satisfied by a trace, found by `validate_trace_report`.
+#[derive(Clone, Debug)]
+pub struct TraceValidationReport<F: IsField> {
+    pub boundary_failures: Vec<BoundaryConstraintFailure<F>>,
+    pub transition_failures: Vec<TransitionConstraintFailure<F>>,
+}
+
+/// A cell of the trace with another value than the one of its boundary constraint.
+#[derive(Clone, Debug)]
+pub struct BoundaryConstraintFailure<F: IsField> {
+    pub col: usize,
+    pub step: usize,
+    pub expected: FieldElement<F>,
+    pub found: FieldElement<F>,
+}
+
+/// A step of the trace where a transition constraint doesn't evaluate to zero.
+#[derive(Clone, Debug)]
+pub struct TransitionConstraintFailure<F: IsField> {
+    pub constraint: usize,
+    /// See `AIR::transition_constraint_name`.
+    pub name: Option<&'static str>,
+    pub step: usize,
+    pub evaluation: FieldElement<F>,
+}
+
+impl<F: IsField> TraceValidationReport<F> {
+    /// Whether the trace satisfies all the constraints.
+    pub fn is_valid(&self) -> bool {
+        self.boundary_failures.is_empty() && self.transition_failures.is_empty()
+    }
+}
+
+/// Validates that the trace is valid with respect to the supplied AIR constraints,
+/// logging the constraints it doesn't satisfy.
 pub fn validate_trace<F: IsFFTField, A: AIR<Field = F>>(
     air: &A,
     trace_polys: &[Polynomial<FieldElement<A::Field>>],
@@ -18,7 +55,43 @@ pub fn validate_trace<F: IsFFTField, A: AIR<Field = F>>(
     rap_challenges: &A::RAPChallenges,
 ) -> bool {
     info!("Starting constraints validation over trace...");
-    let mut ret = true;
+    let report = validate_trace_report(air, trace_polys, domain, rap_challenges);
+
+    for failure in &report.boundary_failures {
+        error!(
+            step = failure.step,
+            col = failure.col,
+            expected = %failure.expected.representative(),
+            found = %failure.found.representative(),
+            "Boundary constraint inconsistency"
+        );
+    }
+    for failure in &report.transition_failures {
+        error!(
+            transition = failure.constraint,
+            name = failure.name.unwrap_or("unnamed"),
+            step = failure.step,
+            found = %failure.evaluation.representative(),
+            "Inconsistent evaluation of transition, expected 0"
+        );
+    }
+
+    info!("Constraints validation check ended");
+    report.is_valid()
+}
+
+/// Finds the boundary and transition constraints of the AIR not satisfied by the
+/// trace interpolated by `trace_polys`.
+pub fn validate_trace_report<F: IsFFTField, A: AIR<Field = F>>(
+    air: &A,
+    trace_polys: &[Polynomial<FieldElement<A::Field>>],
+    domain: &Domain<A::Field>,
+    rap_challenges: &A::RAPChallenges,
+) -> TraceValidationReport<F> {
+    let mut report = TraceValidationReport {
+        boundary_failures: Vec::new(),
+        transition_failures: Vec::new(),
+    };
 
     let trace_columns: Vec<_> = trace_polys
         .iter()
@@ -40,20 +113,30 @@ pub fn validate_trace<F: IsFFTField, A: AIR<Field = F>>(
             let trace_value = trace.get(step, col);
 
             if boundary_value != trace_value {
-                ret = false;
-                error!("Boundary constraint inconsistency - Expected value {} in step {} and column {}, found: {}", boundary_value.representative(), step, col, trace_value.representative());
+                report.boundary_failures.push(BoundaryConstraintFailure {
+                    col,
+                    step,
+                    expected: boundary_value,
+                    found: trace_value,
+                });
             }
         });
 
     // --------- VALIDATE TRANSITION CONSTRAINTS -----------
     let n_transition_constraints = air.context().num_transition_constraints();
-    let transition_exemptions = &air.context().transition_exemptions;
+    let transition_exemptions = &air.context().effective_transition_exemptions();
 
     let exemption_steps: Vec<usize> = vec![trace.n_rows(); n_transition_constraints]
         .iter()
         .zip(transition_exemptions)
         .map(|(trace_steps, exemptions)| trace_steps - exemptions)
         .collect();
+    let zone_exempted_steps: Vec<Vec<usize>> = (0..n_transition_constraints)
+        .map(|constraint| {
+            air.context()
+                .zone_exempted_steps(constraint, trace.n_rows())
+        })
+        .collect();
 
     // Iterate over trace and compute transitions
     for step in 0..trace.n_rows() {
@@ -66,19 +149,22 @@ pub fn validate_trace<F: IsFFTField, A: AIR<Field = F>>(
         evaluations.iter().enumerate().for_each(|(i, eval)| {
             // Check that all the transition constraint evaluations of the trace are zero.
             // We don't take into account the transition exemptions.
-            if step < exemption_steps[i] && eval != &FieldElement::<F>::zero() {
-                ret = false;
-                error!(
-                    "Inconsistent evaluation of transition {} in step {} - expected 0, got {}",
-                    i,
-                    step,
-                    eval.representative()
-                );
+            if step < exemption_steps[i]
+                && zone_exempted_steps[i].binary_search(&step).is_err()
+                && eval != &FieldElement::<F>::zero()
+            {
+                report
+                    .transition_failures
+                    .push(TransitionConstraintFailure {
+                        constraint: i,
+                        name: air.transition_constraint_name(i),
+                        step,
+                        evaluation: eval.clone(),
+                    });
             }
         })
     }
-    info!("Constraints validation check ended");
-    ret
+    report
 }
 
 pub fn check_boundary_polys_divisibility<F: IsFFTField>(
@@ -92,7 +178,58 @@ pub fn check_boundary_polys_divisibility<F: IsFFTField>(
     {
         let (_, b) = poly.clone().long_division_with_remainder(z);
         if b != Polynomial::zero() {
-            error!("Boundary poly {} is not divisible by its zerofier", i);
+            error!(
+                boundary_poly = i,
+                "Boundary poly is not divisible by its zerofier"
+            );
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+    use crate::starks::{
+        example::simple_fibonacci::{fibonacci_trace, FibonacciAIR, FibonacciPublicInputs},
+        proof::options::ProofOptions,
+    };
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    #[test]
+    fn boundary_and_transition_failures_are_reported_separately() {
+        let pub_inputs = FibonacciPublicInputs {
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+        let air = FibonacciAIR::new(8, &pub_inputs, &ProofOptions::default_test_options());
+        let domain = Domain::new(&air);
+
+        let trace = fibonacci_trace([FE::one(), FE::one()], 8);
+        let report = validate_trace_report(&air, &trace.compute_trace_polys(), &domain, &());
+        assert!(report.is_valid());
+
+        // Changing the second value breaks its boundary constraint and the
+        // transitions of the first two steps, which read it
+        let mut column = trace.cols().remove(0);
+        column[1] = FE::from(2);
+        let trace = TraceTable::new_from_cols(&[column]);
+        let report = validate_trace_report(&air, &trace.compute_trace_polys(), &domain, &());
+        assert!(!report.is_valid());
+
+        assert_eq!(report.boundary_failures.len(), 1);
+        let boundary_failure = &report.boundary_failures[0];
+        assert_eq!((boundary_failure.col, boundary_failure.step), (0, 1));
+        assert_eq!(boundary_failure.expected, FE::one());
+        assert_eq!(boundary_failure.found, FE::from(2));
+
+        let failed_steps: Vec<_> = report
+            .transition_failures
+            .iter()
+            .map(|failure| (failure.constraint, failure.step))
+            .collect();
+        assert_eq!(failed_steps, vec![(0, 0), (0, 1)]);
+    }
+}
@@ -0,0 +1,183 @@
+//! Merkle trees whose nodes are stored in a memory-mapped file, for the commitments
+//! of domains too large to hold their trees in memory. See
+//! `MerkleTreeStorage::OnDisk`.
+//!
+//! The nodes are laid out as in the trees of lambdaworks: the root first and each
+//! level after the previous one, with the leaves completed to a power of two by
+//! repeating the last one. So the roots and the paths are the same as the ones of a
+//! `MerkleTree` with the same backend.
+
+use std::{collections::HashMap, fs::File, io, marker::PhantomData, path::Path};
+
+use lambdaworks_crypto::merkle_tree::{proof::Proof, traits::IsMerkleTreeBackend};
+use memmap2::MmapMut;
+
+use super::config::{Commitment, COMMITMENT_SIZE};
+
+/// Number of leaves hashed at once while building a tree.
+const LEAVES_PER_CHUNK: usize = 1 << 12;
+
+pub struct DiskMerkleTree<B> {
+    pub root: Commitment,
+    /// Nodes of the first levels of the tree, which are in every path.
+    cached_nodes: Vec<Commitment>,
+    nodes: MmapMut,
+    num_nodes: usize,
+    // Keeps the file mapped until the tree is dropped. It's deleted then.
+    _file: File,
+    phantom: PhantomData<B>,
+}
+
+impl<B> DiskMerkleTree<B>
+where
+    B: IsMerkleTreeBackend<Node = Commitment>,
+{
+    /// Builds the tree of `leaves` in a temporary file of `directory`, keeping the
+    /// nodes of its first `cached_levels` levels in memory.
+    pub fn build(leaves: &[B::Data], directory: &Path, cached_levels: usize) -> io::Result<Self> {
+        assert!(!leaves.is_empty(), "a Merkle tree needs at least one leaf");
+        let num_leaves = leaves.len().next_power_of_two();
+        let num_nodes = 2 * num_leaves - 1;
+
+        let file = tempfile::tempfile_in(directory)?;
+        file.set_len((num_nodes * COMMITMENT_SIZE) as u64)?;
+        // SAFETY: the file is created here and deleted when closed, so no other
+        // process or mapping can change it while it's mapped.
+        let mut nodes = unsafe { MmapMut::map_mut(&file)? };
+
+        // Hash the leaves a chunk at a time, so the hashes of all of them are never
+        // held in memory
+        let first_leaf = num_leaves - 1;
+        for (i, chunk) in leaves.chunks(LEAVES_PER_CHUNK).enumerate() {
+            let first = first_leaf + i * LEAVES_PER_CHUNK;
+            for (j, hash) in B::hash_leaves(chunk).iter().enumerate() {
+                write_node(&mut nodes, first + j, hash);
+            }
+        }
+        let last_leaf = read_node(&nodes, first_leaf + leaves.len() - 1);
+        for index in first_leaf + leaves.len()..num_nodes {
+            write_node(&mut nodes, index, &last_leaf);
+        }
+
+        // Every parent comes before its children, so each one is hashed after them
+        for index in (0..first_leaf).rev() {
+            let parent = B::hash_new_parent(
+                &read_node(&nodes, 2 * index + 1),
+                &read_node(&nodes, 2 * index + 2),
+            );
+            write_node(&mut nodes, index, &parent);
+        }
+
+        let num_cached_nodes = num_nodes.min((1 << cached_levels.min(63)) - 1);
+        let cached_nodes = (0..num_cached_nodes)
+            .map(|index| read_node(&nodes, index))
+            .collect();
+
+        Ok(Self {
+            root: read_node(&nodes, 0),
+            cached_nodes,
+            nodes,
+            num_nodes,
+            _file: file,
+            phantom: PhantomData,
+        })
+    }
+
+    pub fn get_proof_by_pos(&self, pos: usize) -> Option<Proof<Commitment>> {
+        self.get_proofs_by_pos(&[pos])
+            .map(|mut proofs| proofs.remove(0))
+    }
+
+    /// Paths of the leaves at each of `positions`, in their order. Each node is read
+    /// from the file once, even if it's in several of the paths.
+    pub fn get_proofs_by_pos(&self, positions: &[usize]) -> Option<Vec<Proof<Commitment>>> {
+        let mut read_nodes = HashMap::new();
+        positions
+            .iter()
+            .map(|pos| {
+                let mut index = pos.checked_add(self.num_nodes / 2)?;
+                if index >= self.num_nodes {
+                    return None;
+                }
+
+                let mut merkle_path = Vec::new();
+                while index != 0 {
+                    let sibling = if index % 2 == 0 { index - 1 } else { index + 1 };
+                    let node = read_nodes
+                        .entry(sibling)
+                        .or_insert_with(|| self.node(sibling));
+                    merkle_path.push(*node);
+                    index = (index - 1) / 2;
+                }
+                Some(Proof { merkle_path })
+            })
+            .collect()
+    }
+
+    fn node(&self, index: usize) -> Commitment {
+        match self.cached_nodes.get(index) {
+            Some(node) => *node,
+            None => read_node(&self.nodes, index),
+        }
+    }
+}
+
+fn read_node(nodes: &[u8], index: usize) -> Commitment {
+    let start = index * COMMITMENT_SIZE;
+    nodes[start..start + COMMITMENT_SIZE].try_into().unwrap()
+}
+
+fn write_node(nodes: &mut [u8], index: usize, node: &Commitment) {
+    let start = index * COMMITMENT_SIZE;
+    nodes[start..start + COMMITMENT_SIZE].copy_from_slice(node);
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_crypto::merkle_tree::merkle::MerkleTree;
+    use lambdaworks_math::field::{
+        element::FieldElement, fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+    };
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::starks::config::FriMerkleTreeBackend;
+
+    type Backend = FriMerkleTreeBackend<Stark252PrimeField>;
+
+    proptest! {
+        #[test]
+        fn disk_tree_matches_the_tree_in_memory(
+            leaves in prop::collection::vec(any::<u64>(), 1..100),
+            cached_levels in 0_usize..5,
+        ) {
+            let leaves: Vec<_> = leaves.into_iter().map(FieldElement::from).collect();
+            let in_memory = MerkleTree::<Backend>::build(&leaves);
+            let on_disk =
+                DiskMerkleTree::<Backend>::build(&leaves, &std::env::temp_dir(), cached_levels)
+                    .unwrap();
+
+            prop_assert_eq!(on_disk.root, in_memory.root);
+            for pos in 0..leaves.len() {
+                prop_assert_eq!(
+                    on_disk.get_proof_by_pos(pos).unwrap().merkle_path,
+                    in_memory.get_proof_by_pos(pos).unwrap().merkle_path
+                );
+            }
+            prop_assert!(on_disk.get_proof_by_pos(leaves.len().next_power_of_two()).is_none());
+
+            let positions: Vec<_> = (0..leaves.len()).rev().chain(0..leaves.len()).collect();
+            let paths: Vec<_> = on_disk
+                .get_proofs_by_pos(&positions)
+                .unwrap()
+                .into_iter()
+                .map(|proof| proof.merkle_path)
+                .collect();
+            let expected_paths: Vec<_> = positions
+                .iter()
+                .map(|pos| in_memory.get_proof_by_pos(*pos).unwrap().merkle_path)
+                .collect();
+            prop_assert_eq!(paths, expected_paths);
+        }
+    }
+}
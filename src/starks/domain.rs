@@ -1,47 +1,52 @@
-use lambdaworks_math::{
-    fft::cpu::roots_of_unity::get_powers_of_primitive_root_coset,
-    field::{element::FieldElement, traits::IsFFTField},
-};
+use std::sync::Arc;
 
+use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
+
+use super::proof::options::CosetOffset;
+use super::roots_of_unity::cached_powers_of_primitive_root_coset;
 use super::traits::AIR;
 
 pub struct Domain<F: IsFFTField> {
-    pub(crate) root_order: u32,
-    pub(crate) lde_roots_of_unity_coset: Vec<FieldElement<F>>,
-    pub(crate) lde_root_order: u32,
+    pub(crate) root_order: u64,
+    pub(crate) lde_roots_of_unity_coset: Arc<Vec<FieldElement<F>>>,
+    pub(crate) lde_root_order: u64,
     pub(crate) trace_primitive_root: FieldElement<F>,
-    pub(crate) trace_roots_of_unity: Vec<FieldElement<F>>,
+    pub(crate) trace_roots_of_unity: Arc<Vec<FieldElement<F>>>,
     pub(crate) coset_offset: FieldElement<F>,
     pub(crate) blowup_factor: usize,
     pub(crate) interpolation_domain_size: usize,
 }
 
 impl<F: IsFFTField> Domain<F> {
+    /// Builds the domains of the AIR. The roots of unity are shared with the other
+    /// domains of the same size built by the process, see `roots_of_unity`.
     pub fn new<A>(air: &A) -> Self
     where
         A: AIR<Field = F>,
+        F: 'static,
+        FieldElement<F>: Send + Sync,
     {
         // Initial definitions
         let blowup_factor = air.options().blowup_factor as usize;
-        let coset_offset = FieldElement::<F>::from(air.options().coset_offset);
+        debug_assert!(blowup_factor.is_power_of_two());
+        let coset_offset = air.options().coset_offset.to_field_element::<F>();
         let interpolation_domain_size = air.trace_length();
-        let root_order = air.trace_length().trailing_zeros();
+        let root_order = u64::from(air.trace_length().trailing_zeros());
         // * Generate Coset
-        let trace_primitive_root = F::get_primitive_root_of_unity(root_order as u64).unwrap();
-        let trace_roots_of_unity = get_powers_of_primitive_root_coset(
-            root_order as u64,
+        let trace_primitive_root = F::get_primitive_root_of_unity(root_order).unwrap();
+        let trace_roots_of_unity = cached_powers_of_primitive_root_coset(
+            root_order,
             interpolation_domain_size,
-            &FieldElement::<F>::one(),
-        )
-        .unwrap();
+            &CosetOffset::from(1),
+        );
 
-        let lde_root_order = (air.trace_length() * blowup_factor).trailing_zeros();
-        let lde_roots_of_unity_coset = get_powers_of_primitive_root_coset(
-            lde_root_order as u64,
-            air.trace_length() * blowup_factor,
-            &coset_offset,
-        )
-        .unwrap();
+        let lde_domain_size = air.trace_length() * blowup_factor;
+        let lde_root_order = u64::from(lde_domain_size.trailing_zeros());
+        let lde_roots_of_unity_coset = cached_powers_of_primitive_root_coset(
+            lde_root_order,
+            lde_domain_size,
+            &air.options().coset_offset,
+        );
 
         Self {
             root_order,
@@ -55,3 +60,14 @@ impl<F: IsFFTField> Domain<F> {
         }
     }
 }
+
+/// Size of the LDE domain of a trace of `trace_length` rows, if it's a power of two
+/// that fits in a `usize` and `F` has a subgroup of that size. Traces of more than
+/// `2^32` rows are supported in 64-bit targets, as long as the two-adicity of the
+/// field allows it.
+pub fn lde_domain_size<F: IsFFTField>(trace_length: usize, blowup_factor: u8) -> Option<usize> {
+    let lde_domain_size = trace_length.checked_mul(blowup_factor as usize)?;
+    (lde_domain_size.is_power_of_two()
+        && u64::from(lde_domain_size.trailing_zeros()) <= F::TWO_ADICITY)
+        .then_some(lde_domain_size)
+}
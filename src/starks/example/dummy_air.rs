@@ -34,6 +34,7 @@ impl AIR for DummyAIR {
             trace_columns: 2,
             transition_degrees: vec![2, 1],
             transition_exemptions: vec![0, 2],
+            transition_exemption_zones: Vec::new(),
             transition_offsets: vec![0, 1, 2],
             num_transition_constraints: 2,
             num_transition_exemptions: 1,
@@ -47,6 +48,7 @@ impl AIR for DummyAIR {
 
     fn build_auxiliary_trace(
         &self,
+        _round: usize,
         _main_trace: &TraceTable<Self::Field>,
         _rap_challenges: &Self::RAPChallenges,
     ) -> TraceTable<Self::Field> {
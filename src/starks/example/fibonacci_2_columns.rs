@@ -39,6 +39,7 @@ where
             proof_options: proof_options.clone(),
             transition_degrees: vec![1, 1],
             transition_exemptions: vec![1, 1],
+            transition_exemption_zones: Vec::new(),
             transition_offsets: vec![0, 1],
             num_transition_constraints: 2,
             trace_columns: 2,
@@ -54,6 +55,7 @@ where
 
     fn build_auxiliary_trace(
         &self,
+        _round: usize,
         _main_trace: &TraceTable<Self::Field>,
         _rap_challenges: &Self::RAPChallenges,
     ) -> TraceTable<Self::Field> {
@@ -0,0 +1,177 @@
+use std::ops::Div;
+
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    traits::ByteConversion,
+};
+
+use crate::starks::{
+    constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+    context::AirContext,
+    frame::Frame,
+    proof::options::ProofOptions,
+    trace::TraceTable,
+    traits::AIR,
+    transcript::transcript_to_field,
+};
+
+use super::fibonacci_rap::FibonacciRAPPublicInputs;
+
+/// Same statement as `FibonacciRAP`, but the permutation between the two main
+/// columns is checked twice, with challenges sampled in two different interaction
+/// rounds. Each round adds one grand product column to the auxiliary trace.
+#[derive(Clone)]
+pub struct FibonacciMultiRAP<F>
+where
+    F: IsFFTField,
+{
+    context: AirContext,
+    trace_length: usize,
+    pub_inputs: FibonacciRAPPublicInputs<F>,
+}
+
+fn permutation_column<F: IsFFTField>(
+    main_trace: &TraceTable<F>,
+    challenge: &FieldElement<F>,
+) -> Vec<FieldElement<F>> {
+    let main_segment_cols = main_trace.cols();
+    let not_perm = &main_segment_cols[0];
+    let perm = &main_segment_cols[1];
+
+    let mut aux_col = vec![FieldElement::<F>::one()];
+    for i in 1..main_trace.n_rows() {
+        let z_i = &aux_col[i - 1];
+        let n_p_term = &not_perm[i - 1] + challenge;
+        let p_term = &perm[i - 1] + challenge;
+
+        aux_col.push(z_i * n_p_term.div(p_term));
+    }
+    aux_col
+}
+
+impl<F> AIR for FibonacciMultiRAP<F>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    type Field = F;
+    // One challenge for each interaction round
+    type RAPChallenges = Vec<FieldElement<Self::Field>>;
+    type PublicInputs = FibonacciRAPPublicInputs<Self::Field>;
+
+    fn new(
+        trace_length: usize,
+        pub_inputs: &Self::PublicInputs,
+        proof_options: &ProofOptions,
+    ) -> Self {
+        let exemptions = 3 + trace_length - pub_inputs.steps - 1;
+
+        let context = AirContext {
+            proof_options: proof_options.clone(),
+            trace_columns: 4,
+            transition_degrees: vec![1, 2, 2],
+            transition_offsets: vec![0, 1, 2],
+            transition_exemptions: vec![exemptions, 1, 1],
+            transition_exemption_zones: Vec::new(),
+            num_transition_constraints: 3,
+            num_transition_exemptions: 2,
+        };
+
+        Self {
+            context,
+            trace_length,
+            pub_inputs: pub_inputs.clone(),
+        }
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        round: usize,
+        main_trace: &TraceTable<Self::Field>,
+        rap_challenges: &Self::RAPChallenges,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::new_from_cols(&[permutation_column(main_trace, &rap_challenges[round])])
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges {
+        vec![transcript_to_field(transcript)]
+    }
+
+    fn extend_rap_challenges<T: Transcript>(
+        &self,
+        _round: usize,
+        rap_challenges: &mut Self::RAPChallenges,
+        transcript: &mut T,
+    ) {
+        rap_challenges.push(transcript_to_field(transcript));
+    }
+
+    fn number_of_rap_rounds(&self) -> usize {
+        2
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        2
+    }
+
+    fn number_auxiliary_rap_columns_in_round(&self, _round: usize) -> usize {
+        1
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::Field>,
+        rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::Field>> {
+        // Main constraints
+        let first_row = frame.get_row(0);
+        let second_row = frame.get_row(1);
+        let third_row = frame.get_row(2);
+
+        let mut constraints = vec![&third_row[0] - &second_row[0] - &first_row[0]];
+
+        // Auxiliary constraints, one for each interaction round
+        let a_i = &first_row[0];
+        let b_i = &first_row[1];
+        for (round, challenge) in rap_challenges.iter().enumerate() {
+            let z_i = &first_row[2 + round];
+            let z_i_plus_one = &second_row[2 + round];
+
+            constraints.push(z_i_plus_one * (b_i + challenge) - z_i * (a_i + challenge));
+        }
+
+        constraints
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> BoundaryConstraints<Self::Field> {
+        // Main boundary constraints
+        let a0 = BoundaryConstraint::new_simple(0, FieldElement::<Self::Field>::one());
+        let a1 = BoundaryConstraint::new_simple(1, FieldElement::<Self::Field>::one());
+
+        // Auxiliary boundary constraints
+        let a0_aux_first_round = BoundaryConstraint::new(2, 0, FieldElement::<Self::Field>::one());
+        let a0_aux_second_round = BoundaryConstraint::new(3, 0, FieldElement::<Self::Field>::one());
+
+        BoundaryConstraints::from_constraints(vec![a0, a1, a0_aux_first_round, a0_aux_second_round])
+    }
+
+    fn context(&self) -> &AirContext {
+        &self.context
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.trace_length()
+    }
+
+    fn trace_length(&self) -> usize {
+        self.trace_length
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.pub_inputs
+    }
+}
@@ -59,6 +59,7 @@ where
             transition_degrees: vec![1, 2],
             transition_offsets: vec![0, 1, 2],
             transition_exemptions: vec![exemptions, 1],
+            transition_exemption_zones: Vec::new(),
             num_transition_constraints: 2,
             num_transition_exemptions: 2,
         };
@@ -72,6 +73,7 @@ where
 
     fn build_auxiliary_trace(
         &self,
+        _round: usize,
         main_trace: &TraceTable<Self::Field>,
         gamma: &Self::RAPChallenges,
     ) -> TraceTable<Self::Field> {
@@ -0,0 +1,249 @@
+//! The permutation argument of `FibonacciRAP`, with its challenge and its
+//! auxiliary column in a quadratic extension of the field of the main trace.
+//!
+//! With small fields, a challenge of the base field can be guessed with too high a
+//! probability, so it's sampled from an extension instead. The prover and the
+//! verifier work over a single field, so each element of the extension
+//! `F[u] / (u² - non_residue())` is stored as its two coordinates: the auxiliary
+//! column is split in two base field columns, and its constraint in one constraint
+//! for each coordinate. This AIR is the template for the deployments over small
+//! fields.
+
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    traits::ByteConversion,
+};
+
+use super::fibonacci_rap::FibonacciRAPPublicInputs;
+use crate::starks::{
+    constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+    context::AirContext,
+    frame::Frame,
+    proof::options::ProofOptions,
+    trace::TraceTable,
+    traits::AIR,
+    transcript::transcript_to_field,
+};
+
+/// Square of the generator `u` of the extension, a quadratic non-residue of `F`.
+///
+/// It's the primitive root of unity of order `2^TWO_ADICITY`. `TWO_ADICITY` is the
+/// largest power of two dividing `p - 1`, so this root isn't in the subgroup of
+/// order `(p - 1) / 2` of the squares.
+pub fn non_residue<F: IsFFTField>() -> FieldElement<F> {
+    F::get_primitive_root_of_unity(F::TWO_ADICITY).unwrap()
+}
+
+/// Coordinates `[c₀, c₁]` of the element `c₀ + c₁·u` of the extension.
+pub type ExtensionElement<F> = [FieldElement<F>; 2];
+
+fn extension_mul<F: IsFFTField>(
+    a: &ExtensionElement<F>,
+    b: &ExtensionElement<F>,
+) -> ExtensionElement<F> {
+    [
+        &a[0] * &b[0] + non_residue::<F>() * &a[1] * &b[1],
+        &a[0] * &b[1] + &a[1] * &b[0],
+    ]
+}
+
+fn extension_inv<F: IsFFTField>(a: &ExtensionElement<F>) -> ExtensionElement<F> {
+    // (c₀ + c₁·u)(c₀ - c₁·u) = c₀² - u²·c₁², which is in the base field
+    let norm = a[0].square() - non_residue::<F>() * a[1].square();
+    let norm_inv = norm.inv();
+    [&a[0] * &norm_inv, -(&a[1] * &norm_inv)]
+}
+
+/// Adds an element of the base field to one of the extension.
+fn extension_add_base<F: IsFFTField>(
+    a: &ExtensionElement<F>,
+    b: &FieldElement<F>,
+) -> ExtensionElement<F> {
+    [&a[0] + b, a[1].clone()]
+}
+
+#[derive(Clone)]
+pub struct FibonacciRAPExtension<F>
+where
+    F: IsFFTField,
+{
+    context: AirContext,
+    trace_length: usize,
+    pub_inputs: FibonacciRAPPublicInputs<F>,
+}
+
+impl<F> AIR for FibonacciRAPExtension<F>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    type Field = F;
+    type RAPChallenges = ExtensionElement<Self::Field>;
+    type PublicInputs = FibonacciRAPPublicInputs<Self::Field>;
+
+    fn new(
+        trace_length: usize,
+        pub_inputs: &Self::PublicInputs,
+        proof_options: &ProofOptions,
+    ) -> Self {
+        let exemptions = 3 + trace_length - pub_inputs.steps - 1;
+
+        let context = AirContext {
+            proof_options: proof_options.clone(),
+            trace_columns: 4,
+            transition_degrees: vec![1, 2, 2],
+            transition_offsets: vec![0, 1, 2],
+            transition_exemptions: vec![exemptions, 1, 1],
+            transition_exemption_zones: Vec::new(),
+            num_transition_constraints: 3,
+            num_transition_exemptions: 2,
+        };
+
+        Self {
+            context,
+            trace_length,
+            pub_inputs: pub_inputs.clone(),
+        }
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _round: usize,
+        main_trace: &TraceTable<Self::Field>,
+        gamma: &Self::RAPChallenges,
+    ) -> TraceTable<Self::Field> {
+        let main_segment_cols = main_trace.cols();
+        let not_perm = &main_segment_cols[0];
+        let perm = &main_segment_cols[1];
+
+        let trace_len = main_trace.n_rows();
+
+        let mut aux_col: Vec<ExtensionElement<F>> = Vec::with_capacity(trace_len);
+        aux_col.push([FieldElement::one(), FieldElement::zero()]);
+        for i in 1..trace_len {
+            let n_p_term = extension_add_base(gamma, &not_perm[i - 1]);
+            let p_term = extension_add_base(gamma, &perm[i - 1]);
+            let z_i = extension_mul(&aux_col[i - 1], &n_p_term);
+            aux_col.push(extension_mul(&z_i, &extension_inv(&p_term)));
+        }
+
+        let (aux_col_0, aux_col_1): (Vec<_>, Vec<_>) =
+            aux_col.into_iter().map(|[c_0, c_1]| (c_0, c_1)).unzip();
+        TraceTable::new_from_cols(&[aux_col_0, aux_col_1])
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges {
+        [
+            transcript_to_field(transcript),
+            transcript_to_field(transcript),
+        ]
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        2
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::Field>,
+        gamma: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::Field>> {
+        // Main constraints
+        let first_row = frame.get_row(0);
+        let second_row = frame.get_row(1);
+        let third_row = frame.get_row(2);
+
+        let mut constraints = vec![&third_row[0] - &second_row[0] - &first_row[0]];
+
+        // Auxiliary constraints, one for each coordinate of
+        // z_{i+1}·(b_i + γ) - z_i·(a_i + γ)
+        let z_i = [first_row[2].clone(), first_row[3].clone()];
+        let z_i_plus_one = [second_row[2].clone(), second_row[3].clone()];
+
+        let a_i = &first_row[0];
+        let b_i = &first_row[1];
+
+        let lhs = extension_mul(&z_i_plus_one, &extension_add_base(gamma, b_i));
+        let rhs = extension_mul(&z_i, &extension_add_base(gamma, a_i));
+
+        constraints.push(&lhs[0] - &rhs[0]);
+        constraints.push(&lhs[1] - &rhs[1]);
+        constraints
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> BoundaryConstraints<Self::Field> {
+        // Main boundary constraints
+        let a0 = BoundaryConstraint::new_simple(0, self.pub_inputs.a0.clone());
+        let a1 = BoundaryConstraint::new_simple(1, self.pub_inputs.a1.clone());
+
+        // Auxiliary boundary constraints: the product starts at one
+        let a0_aux_0 = BoundaryConstraint::new(2, 0, FieldElement::<Self::Field>::one());
+        let a0_aux_1 = BoundaryConstraint::new(3, 0, FieldElement::<Self::Field>::zero());
+
+        BoundaryConstraints::from_constraints(vec![a0, a1, a0_aux_0, a0_aux_1])
+    }
+
+    fn context(&self) -> &AirContext {
+        &self.context
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.trace_length()
+    }
+
+    fn trace_length(&self) -> usize {
+        self.trace_length
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.pub_inputs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lambdaworks_math::field::fields::u64_prime_field::{F17, FE17};
+
+    use super::*;
+    use crate::starks::example::fibonacci_rap::fibonacci_rap_trace;
+
+    #[test]
+    fn non_residue_is_not_a_square() {
+        let non_residue = non_residue::<F17>();
+        assert!((0..17).all(|x| FE17::from(x).square() != non_residue));
+    }
+
+    #[test]
+    fn extension_inverse_is_the_inverse_of_the_product() {
+        let a = [FE17::from(5), FE17::from(11)];
+        assert_eq!(
+            extension_mul(&a, &extension_inv(&a)),
+            [FE17::one(), FE17::zero()]
+        );
+    }
+
+    #[test]
+    fn aux_cols_end_in_one() {
+        let trace = fibonacci_rap_trace([FE17::from(1), FE17::from(1)], 64);
+        let pub_inputs = FibonacciRAPPublicInputs {
+            steps: 64,
+            a0: FE17::one(),
+            a1: FE17::one(),
+        };
+        let air = FibonacciRAPExtension::<F17>::new(
+            trace.n_rows(),
+            &pub_inputs,
+            &ProofOptions::default_test_options(),
+        );
+        let gamma = [FE17::from(10), FE17::from(7)];
+
+        let aux_cols = air.build_auxiliary_trace(0, &trace, &gamma).cols();
+
+        assert_eq!(aux_cols[0].last().unwrap(), &FE17::one());
+        assert_eq!(aux_cols[1].last().unwrap(), &FE17::zero());
+    }
+}
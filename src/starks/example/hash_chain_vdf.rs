@@ -0,0 +1,221 @@
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::{
+    element::FieldElement, fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+};
+
+use crate::starks::{
+    constraints::{boundary::BoundaryConstraints, compiled::CompiledConstraints, symbolic::Expr},
+    context::AirContext,
+    frame::Frame,
+    gadgets::{
+        poseidon::PoseidonParameters,
+        poseidon_merkle_path::{
+            PoseidonMerklePathGadget, CONSTRAINT_NAMES, DIRECTION, HASH_START, NUM_COLUMNS, STATE,
+        },
+    },
+    proof::{options::ProofOptions, stark::StarkProof},
+    prover::{prove, ProvingError},
+    trace::TraceTable,
+    traits::AIR,
+    verifier::verify,
+};
+
+type FE = FieldElement<Stark252PrimeField>;
+
+/// Names of the constraints added to the ones of the gadget.
+const CHAIN_CONSTRAINT_NAMES: [&str; 2] = ["direction_is_zero", "second_input_is_zero"];
+
+/// A verifiable delay function evaluated by iterating the Poseidon of Starknet,
+/// `xᵢ₊₁ = hash(xᵢ, 0)` from the seed `x₀`. Each iteration depends on the previous
+/// one, so the output takes sequential work to compute, while checking its proof
+/// takes polylogarithmic time in the number of iterations.
+///
+/// The chain is a Merkle path whose siblings are zero and whose nodes are left
+/// children, see `PoseidonMerklePathGadget`, so each iteration takes a hash of
+/// rows of a trace of few columns.
+#[derive(Clone)]
+pub struct HashChainVdfAIR {
+    context: AirContext,
+    trace_length: usize,
+    pub_inputs: HashChainVdfPublicInputs,
+    gadget: PoseidonMerklePathGadget<Stark252PrimeField>,
+    constraints: CompiledConstraints<Stark252PrimeField>,
+}
+
+#[derive(Clone, Debug)]
+pub struct HashChainVdfPublicInputs {
+    pub seed: FE,
+    pub output: FE,
+    /// Number of hashes from the seed to the output, which is at least one.
+    pub iterations: usize,
+}
+
+fn gadget() -> PoseidonMerklePathGadget<Stark252PrimeField> {
+    PoseidonMerklePathGadget::new(PoseidonParameters::starknet(), 0, [0, 1, 2])
+}
+
+impl AIR for HashChainVdfAIR {
+    type Field = Stark252PrimeField;
+    type RAPChallenges = ();
+    type PublicInputs = HashChainVdfPublicInputs;
+
+    fn new(
+        trace_length: usize,
+        pub_inputs: &Self::PublicInputs,
+        proof_options: &ProofOptions,
+    ) -> Self {
+        let gadget = gadget();
+
+        // The path of the gadget may hash with any sibling on either side, the chain
+        // only with a zero on the right
+        let mut transition_constraints = gadget.transition_constraints();
+        transition_constraints.push(Expr::cell(0, DIRECTION));
+        transition_constraints.push(Expr::cell(0, HASH_START) * Expr::cell(0, STATE + 1));
+        let constraints = CompiledConstraints::new(transition_constraints);
+        let mut transition_exemptions = gadget.transition_exemptions();
+        transition_exemptions.extend([0; CHAIN_CONSTRAINT_NAMES.len()]);
+
+        let context = AirContext {
+            proof_options: proof_options.clone(),
+            trace_columns: NUM_COLUMNS,
+            transition_degrees: constraints.degrees(),
+            transition_offsets: gadget.transition_offsets().to_vec(),
+            transition_exemptions,
+            transition_exemption_zones: Vec::new(),
+            num_transition_constraints: constraints.num_constraints(),
+            num_transition_exemptions: 2,
+        };
+
+        Self {
+            context,
+            trace_length,
+            pub_inputs: pub_inputs.clone(),
+            gadget,
+            constraints,
+        }
+    }
+
+    fn check_configuration(pub_inputs: &Self::PublicInputs) -> Result<(), String> {
+        if pub_inputs.iterations == 0 {
+            return Err("the hash chain has no iterations".to_string());
+        }
+        Ok(())
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _round: usize,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::Field>> {
+        self.constraints.evaluate(frame, &[])
+    }
+
+    fn symbolic_transition(&self) -> Option<Vec<Expr<Self::Field>>> {
+        Some(self.constraints.expressions().to_vec())
+    }
+
+    fn transition_constraint_name(&self, index: usize) -> Option<&'static str> {
+        CONSTRAINT_NAMES
+            .iter()
+            .chain(&CHAIN_CONSTRAINT_NAMES)
+            .nth(index)
+            .copied()
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> BoundaryConstraints<Self::Field> {
+        BoundaryConstraints::from_constraints(self.gadget.boundary_constraints(
+            &self.pub_inputs.seed,
+            &self.pub_inputs.output,
+            self.pub_inputs.iterations,
+        ))
+    }
+
+    /// The number of iterations, which fixes the row of the output.
+    fn public_parameters(&self) -> Vec<u8> {
+        (self.pub_inputs.iterations as u64).to_be_bytes().to_vec()
+    }
+
+    fn context(&self) -> &AirContext {
+        &self.context
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        2 * self.trace_length()
+    }
+
+    fn trace_length(&self) -> usize {
+        self.trace_length
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.pub_inputs
+    }
+}
+
+/// Output of the function: the seed hashed `iterations` times.
+pub fn vdf_output(seed: &FE, iterations: usize) -> FE {
+    PoseidonParameters::with_starknet(|parameters| {
+        (0..iterations).fold(seed.clone(), |x, _| parameters.hash(&x, &FE::zero()))
+    })
+}
+
+/// Trace of the chain of `iterations` hashes from `seed`, and the public inputs
+/// with its output.
+pub fn hash_chain_vdf_trace(
+    seed: FE,
+    iterations: usize,
+) -> (TraceTable<Stark252PrimeField>, HashChainVdfPublicInputs) {
+    let gadget = gadget();
+    let siblings = vec![FE::zero(); iterations];
+    let columns = gadget.trace_columns(&seed, 0, &siblings, gadget.trace_length(iterations));
+    let output = gadget.root(&seed, 0, &siblings);
+
+    (
+        TraceTable::new_from_cols(&columns),
+        HashChainVdfPublicInputs {
+            seed,
+            output,
+            iterations,
+        },
+    )
+}
+
+/// Evaluates the function on `seed` and proves its output. Fails if `iterations`
+/// is zero.
+pub fn prove_vdf(
+    seed: FE,
+    iterations: usize,
+    proof_options: &ProofOptions,
+) -> Result<(StarkProof<Stark252PrimeField>, HashChainVdfPublicInputs), ProvingError> {
+    let (trace, pub_inputs) = hash_chain_vdf_trace(seed, iterations);
+    let proof = prove::<Stark252PrimeField, HashChainVdfAIR>(&trace, &pub_inputs, proof_options)?;
+    Ok((proof, pub_inputs))
+}
+
+/// Checks that the output of `pub_inputs` is the one of its seed and number of
+/// iterations.
+pub fn verify_vdf(
+    proof: &StarkProof<Stark252PrimeField>,
+    pub_inputs: &HashChainVdfPublicInputs,
+    proof_options: &ProofOptions,
+) -> bool {
+    verify::<Stark252PrimeField, HashChainVdfAIR>(proof, pub_inputs, proof_options)
+}
@@ -1,5 +1,11 @@
 pub mod dummy_air;
 pub mod fibonacci_2_columns;
+pub mod fibonacci_multi_rap;
 pub mod fibonacci_rap;
+pub mod fibonacci_rap_extension;
+pub mod hash_chain_vdf;
+pub mod poseidon_merkle_path;
 pub mod quadratic_air;
 pub mod simple_fibonacci;
+pub mod stitched_fibonacci;
+pub mod strided_counter;
@@ -0,0 +1,162 @@
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::{
+    element::FieldElement, fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+};
+
+use crate::starks::{
+    constraints::{boundary::BoundaryConstraints, compiled::CompiledConstraints, symbolic::Expr},
+    context::AirContext,
+    frame::Frame,
+    gadgets::{
+        poseidon::PoseidonParameters,
+        poseidon_merkle_path::{PoseidonMerklePathGadget, CONSTRAINT_NAMES, NUM_COLUMNS},
+    },
+    proof::options::ProofOptions,
+    trace::TraceTable,
+    traits::AIR,
+};
+
+/// Proves that a leaf is in a Merkle tree hashed with the Poseidon of Starknet,
+/// knowing its authentication path. The path and the index of the leaf stay
+/// private. See `PoseidonMerklePathGadget` for the layout of the trace.
+#[derive(Clone)]
+pub struct PoseidonMerklePathAIR {
+    context: AirContext,
+    trace_length: usize,
+    pub_inputs: PoseidonMerklePathPublicInputs,
+    gadget: PoseidonMerklePathGadget<Stark252PrimeField>,
+    constraints: CompiledConstraints<Stark252PrimeField>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PoseidonMerklePathPublicInputs {
+    pub leaf: FieldElement<Stark252PrimeField>,
+    pub root: FieldElement<Stark252PrimeField>,
+    /// Number of levels of the path, which is at least one.
+    pub depth: usize,
+}
+
+impl AIR for PoseidonMerklePathAIR {
+    type Field = Stark252PrimeField;
+    type RAPChallenges = ();
+    type PublicInputs = PoseidonMerklePathPublicInputs;
+
+    fn new(
+        trace_length: usize,
+        pub_inputs: &Self::PublicInputs,
+        proof_options: &ProofOptions,
+    ) -> Self {
+        let gadget = PoseidonMerklePathGadget::new(PoseidonParameters::starknet(), 0, [0, 1, 2]);
+        let constraints = CompiledConstraints::new(gadget.transition_constraints());
+
+        let context = AirContext {
+            proof_options: proof_options.clone(),
+            trace_columns: NUM_COLUMNS,
+            transition_degrees: constraints.degrees(),
+            transition_offsets: gadget.transition_offsets().to_vec(),
+            transition_exemptions: gadget.transition_exemptions(),
+            transition_exemption_zones: Vec::new(),
+            num_transition_constraints: constraints.num_constraints(),
+            num_transition_exemptions: 2,
+        };
+
+        Self {
+            context,
+            trace_length,
+            pub_inputs: pub_inputs.clone(),
+            gadget,
+            constraints,
+        }
+    }
+
+    fn check_configuration(pub_inputs: &Self::PublicInputs) -> Result<(), String> {
+        if pub_inputs.depth == 0 {
+            return Err("the Merkle path has no levels".to_string());
+        }
+        Ok(())
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _round: usize,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::Field>> {
+        self.constraints.evaluate(frame, &[])
+    }
+
+    fn symbolic_transition(&self) -> Option<Vec<Expr<Self::Field>>> {
+        Some(self.constraints.expressions().to_vec())
+    }
+
+    fn transition_constraint_name(&self, index: usize) -> Option<&'static str> {
+        CONSTRAINT_NAMES.get(index).copied()
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> BoundaryConstraints<Self::Field> {
+        BoundaryConstraints::from_constraints(self.gadget.boundary_constraints(
+            &self.pub_inputs.leaf,
+            &self.pub_inputs.root,
+            self.pub_inputs.depth,
+        ))
+    }
+
+    /// The depth of the path, which fixes the row of the root.
+    fn public_parameters(&self) -> Vec<u8> {
+        (self.pub_inputs.depth as u64).to_be_bytes().to_vec()
+    }
+
+    fn context(&self) -> &AirContext {
+        &self.context
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        2 * self.trace_length()
+    }
+
+    fn trace_length(&self) -> usize {
+        self.trace_length
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.pub_inputs
+    }
+}
+
+/// Trace of the path of `siblings` of the leaf at `index`, and the public inputs
+/// with the root of its tree.
+pub fn poseidon_merkle_path_trace(
+    leaf: FieldElement<Stark252PrimeField>,
+    index: usize,
+    siblings: &[FieldElement<Stark252PrimeField>],
+) -> (
+    TraceTable<Stark252PrimeField>,
+    PoseidonMerklePathPublicInputs,
+) {
+    let gadget = PoseidonMerklePathGadget::new(PoseidonParameters::starknet(), 0, [0, 1, 2]);
+    let depth = siblings.len();
+    let columns = gadget.trace_columns(&leaf, index, siblings, gadget.trace_length(depth));
+    let root = gadget.root(&leaf, index, siblings);
+
+    (
+        TraceTable::new_from_cols(&columns),
+        PoseidonMerklePathPublicInputs { leaf, root, depth },
+    )
+}
@@ -2,7 +2,11 @@ use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
 use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
 
 use crate::starks::{
-    constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+    constraints::{
+        boundary::{BoundaryConstraint, BoundaryConstraints},
+        compiled::CompiledConstraints,
+        symbolic::Expr,
+    },
     context::AirContext,
     frame::Frame,
     proof::options::ProofOptions,
@@ -18,6 +22,7 @@ where
     context: AirContext,
     trace_length: usize,
     pub_inputs: QuadraticPublicInputs<F>,
+    constraints: CompiledConstraints<F>,
 }
 
 #[derive(Clone, Debug)]
@@ -41,13 +46,18 @@ where
         pub_inputs: &Self::PublicInputs,
         proof_options: &ProofOptions,
     ) -> Self {
+        // a_{i+1} = a_i^2
+        let constraints =
+            CompiledConstraints::new(vec![Expr::cell(1, 0) - Expr::cell(0, 0) * Expr::cell(0, 0)]);
+
         let context = AirContext {
             proof_options: proof_options.clone(),
             trace_columns: 1,
-            transition_degrees: vec![2],
+            transition_degrees: constraints.degrees(),
             transition_exemptions: vec![1],
+            transition_exemption_zones: Vec::new(),
             transition_offsets: vec![0, 1],
-            num_transition_constraints: 1,
+            num_transition_constraints: constraints.num_constraints(),
             num_transition_exemptions: 1,
         };
 
@@ -55,11 +65,13 @@ where
             trace_length,
             context,
             pub_inputs: pub_inputs.clone(),
+            constraints,
         }
     }
 
     fn build_auxiliary_trace(
         &self,
+        _round: usize,
         _main_trace: &TraceTable<Self::Field>,
         _rap_challenges: &Self::RAPChallenges,
     ) -> TraceTable<Self::Field> {
@@ -73,10 +85,11 @@ where
         frame: &Frame<Self::Field>,
         _rap_challenges: &Self::RAPChallenges,
     ) -> Vec<FieldElement<Self::Field>> {
-        let first_row = frame.get_row(0);
-        let second_row = frame.get_row(1);
+        self.constraints.evaluate(frame, &[])
+    }
 
-        vec![&second_row[0] - &first_row[0] * &first_row[0]]
+    fn symbolic_transition(&self) -> Option<Vec<Expr<Self::Field>>> {
+        Some(self.constraints.expressions().to_vec())
     }
 
     fn number_auxiliary_rap_columns(&self) -> usize {
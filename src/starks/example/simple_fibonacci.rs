@@ -47,6 +47,7 @@ where
             trace_columns: 1,
             transition_degrees: vec![1],
             transition_exemptions: vec![2],
+            transition_exemption_zones: Vec::new(),
             transition_offsets: vec![0, 1, 2],
             num_transition_constraints: 1,
             num_transition_exemptions: 1,
@@ -65,6 +66,7 @@ where
 
     fn build_auxiliary_trace(
         &self,
+        _round: usize,
         _main_trace: &TraceTable<Self::Field>,
         _rap_challenges: &Self::RAPChallenges,
     ) -> TraceTable<Self::Field> {
@@ -0,0 +1,190 @@
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
+
+use crate::starks::{
+    constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+    context::{AirContext, ExemptionZones},
+    frame::Frame,
+    proof::options::ProofOptions,
+    trace::TraceTable,
+    traits::AIR,
+};
+
+/// Two Fibonacci sequences of half the trace length each, one after the other. The
+/// transition constraint of the sequence doesn't hold at the two steps before the
+/// second one starts, which are exempted with `ExemptionZones`.
+#[derive(Clone)]
+pub struct StitchedFibonacciAIR<F>
+where
+    F: IsFFTField,
+{
+    context: AirContext,
+    trace_length: usize,
+    pub_inputs: StitchedFibonacciPublicInputs<F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct StitchedFibonacciPublicInputs<F>
+where
+    F: IsFFTField,
+{
+    /// First two values of both sequences
+    pub a0: FieldElement<F>,
+    pub a1: FieldElement<F>,
+}
+
+impl<F> AIR for StitchedFibonacciAIR<F>
+where
+    F: IsFFTField,
+{
+    type Field = F;
+    type RAPChallenges = ();
+    type PublicInputs = StitchedFibonacciPublicInputs<Self::Field>;
+
+    fn new(
+        trace_length: usize,
+        pub_inputs: &Self::PublicInputs,
+        proof_options: &ProofOptions,
+    ) -> Self {
+        let stitch = trace_length / 2;
+        let context = AirContext {
+            proof_options: proof_options.clone(),
+            trace_columns: 1,
+            transition_degrees: vec![1],
+            transition_exemptions: vec![2],
+            transition_exemption_zones: vec![ExemptionZones {
+                start: 0,
+                steps: vec![stitch - 2, stitch - 1],
+            }],
+            transition_offsets: vec![0, 1, 2],
+            num_transition_constraints: 1,
+            num_transition_exemptions: 1,
+        };
+
+        Self {
+            pub_inputs: pub_inputs.clone(),
+            context,
+            trace_length,
+        }
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.trace_length()
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _round: usize,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::Field>> {
+        let first_row = frame.get_row(0);
+        let second_row = frame.get_row(1);
+        let third_row = frame.get_row(2);
+
+        vec![&third_row[0] - &second_row[0] - &first_row[0]]
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> BoundaryConstraints<Self::Field> {
+        let stitch = self.trace_length / 2;
+        let a0 = &self.pub_inputs.a0;
+        let a1 = &self.pub_inputs.a1;
+
+        BoundaryConstraints::from_constraints(vec![
+            BoundaryConstraint::new_simple(0, a0.clone()),
+            BoundaryConstraint::new_simple(1, a1.clone()),
+            BoundaryConstraint::new_simple(stitch, a0.clone()),
+            BoundaryConstraint::new_simple(stitch + 1, a1.clone()),
+        ])
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &AirContext {
+        &self.context
+    }
+
+    fn trace_length(&self) -> usize {
+        self.trace_length
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.pub_inputs
+    }
+}
+
+pub fn stitched_fibonacci_trace<F: IsFFTField>(
+    initial_values: [FieldElement<F>; 2],
+    trace_length: usize,
+) -> TraceTable<F> {
+    let mut sequence = initial_values.to_vec();
+    for i in 2..trace_length / 2 {
+        sequence.push(&sequence[i - 1] + &sequence[i - 2]);
+    }
+
+    TraceTable::new_from_cols(&[[sequence.clone(), sequence].concat()])
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+    use crate::starks::{debug::validate_trace_report, domain::Domain};
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    #[test]
+    fn only_the_steps_before_the_stitch_are_exempted() {
+        let pub_inputs = StitchedFibonacciPublicInputs {
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+        let mut air =
+            StitchedFibonacciAIR::new(16, &pub_inputs, &ProofOptions::default_test_options());
+        let domain = Domain::new(&air);
+        let trace_polys =
+            stitched_fibonacci_trace([FE::one(), FE::one()], 16).compute_trace_polys();
+
+        assert!(validate_trace_report(&air, &trace_polys, &domain, &()).is_valid());
+
+        air.context.transition_exemption_zones.clear();
+        let report = validate_trace_report(&air, &trace_polys, &domain, &());
+        let failing_steps: Vec<_> = report
+            .transition_failures
+            .iter()
+            .map(|failure| failure.step)
+            .collect();
+        assert_eq!(failing_steps, vec![6, 7]);
+    }
+
+    #[test]
+    fn the_quotient_is_lifted_by_less_than_the_degree_of_its_zones() {
+        let pub_inputs = StitchedFibonacciPublicInputs {
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+        let air = StitchedFibonacciAIR::new(16, &pub_inputs, &ProofOptions::default_test_options());
+
+        assert_eq!(
+            air.transition_degree_adjustment(0),
+            Some(air.degree_adjustment(0).unwrap() - 2)
+        );
+        assert!(air.degree_bound_covers_constraints());
+    }
+}
@@ -0,0 +1,147 @@
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
+
+use crate::starks::{
+    constraints::{
+        boundary::{BoundaryConstraint, BoundaryConstraints},
+        compiled::CompiledConstraints,
+        symbolic::Expr,
+    },
+    context::AirContext,
+    frame::Frame,
+    proof::options::ProofOptions,
+    trace::TraceTable,
+    traits::{ExtraOpeningPoint, AIR},
+};
+
+/// Two counters interleaved in a single column, each one incremented every other
+/// row. Its frame skips the next row, so it has the rows of offsets 0 and 2.
+#[derive(Clone)]
+pub struct StridedCounterAIR<F>
+where
+    F: IsFFTField,
+{
+    context: AirContext,
+    trace_length: usize,
+    pub_inputs: StridedCounterPublicInputs<F>,
+    constraints: CompiledConstraints<F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct StridedCounterPublicInputs<F>
+where
+    F: IsFFTField,
+{
+    pub a0: FieldElement<F>,
+    pub a1: FieldElement<F>,
+    /// Extra point where the composition polynomial parts are opened, if any.
+    pub extra_opening: Option<ExtraOpeningPoint>,
+}
+
+impl<F> AIR for StridedCounterAIR<F>
+where
+    F: IsFFTField,
+{
+    type Field = F;
+    type RAPChallenges = ();
+    type PublicInputs = StridedCounterPublicInputs<Self::Field>;
+
+    fn new(
+        trace_length: usize,
+        pub_inputs: &Self::PublicInputs,
+        proof_options: &ProofOptions,
+    ) -> Self {
+        // a_{i+2} = a_i + 1
+        let constraints = CompiledConstraints::new(vec![
+            Expr::cell(1, 0) - Expr::cell(0, 0) - Expr::constant(FieldElement::one()),
+        ]);
+
+        let context = AirContext {
+            proof_options: proof_options.clone(),
+            trace_columns: 1,
+            transition_degrees: constraints.degrees(),
+            transition_exemptions: vec![2],
+            transition_exemption_zones: Vec::new(),
+            transition_offsets: vec![0, 2],
+            num_transition_constraints: constraints.num_constraints(),
+            num_transition_exemptions: 1,
+        };
+
+        Self {
+            trace_length,
+            context,
+            pub_inputs: pub_inputs.clone(),
+            constraints,
+        }
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _round: usize,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::Field>> {
+        self.constraints.evaluate(frame, &[])
+    }
+
+    fn symbolic_transition(&self) -> Option<Vec<Expr<Self::Field>>> {
+        Some(self.constraints.expressions().to_vec())
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> BoundaryConstraints<Self::Field> {
+        let a0 = BoundaryConstraint::new_simple(0, self.pub_inputs.a0.clone());
+        let a1 = BoundaryConstraint::new_simple(1, self.pub_inputs.a1.clone());
+
+        BoundaryConstraints::from_constraints(vec![a0, a1])
+    }
+
+    fn composition_poly_extra_opening(&self) -> Option<ExtraOpeningPoint> {
+        self.pub_inputs.extra_opening
+    }
+
+    fn context(&self) -> &AirContext {
+        &self.context
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.trace_length()
+    }
+
+    fn trace_length(&self) -> usize {
+        self.trace_length
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.pub_inputs
+    }
+}
+
+pub fn strided_counter_trace<F: IsFFTField>(
+    initial_values: [FieldElement<F>; 2],
+    trace_length: usize,
+) -> TraceTable<F> {
+    let mut ret: Vec<FieldElement<F>> = initial_values.to_vec();
+
+    for i in 2..(trace_length) {
+        ret.push(ret[i - 2].clone() + FieldElement::one());
+    }
+
+    TraceTable::new_from_cols(&[ret])
+}
@@ -5,22 +5,58 @@ use lambdaworks_math::{
     traits::{ByteConversion, Deserializable, Serializable},
 };
 
-use super::trace::TraceTable;
+use super::{
+    trace::TraceTable,
+    utils::{deserialize_felt, deserialize_felt_len, deserialize_len},
+};
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct Frame<F: IsFFTField> {
-    // Vector of rows
-    data: Vec<FieldElement<F>>,
+/// The rows of a trace an AIR evaluates its transition constraints on.
+///
+/// A frame either owns its values, like the out-of-domain frame of the proofs, or
+/// is a view over the rows of a trace, see `read_from_trace`. Views don't copy any
+/// value, so the constraints can be evaluated over every point of the LDE without
+/// allocating a frame for each one.
+#[derive(Clone, Debug)]
+pub struct Frame<'t, F: IsFFTField> {
+    rows: FrameRows<'t, F>,
     row_width: usize,
 }
 
-impl<F: IsFFTField> Frame<F> {
+#[derive(Clone, Debug)]
+enum FrameRows<'t, F: IsFFTField> {
+    // Values of the rows, one after the other
+    Owned(Vec<FieldElement<F>>),
+    // The rows `step + offset * blowup` of the trace, wrapping around it
+    View {
+        trace: &'t TraceTable<F>,
+        step: usize,
+        blowup: usize,
+        offsets: &'t [usize],
+    },
+}
+
+impl<'t, F: IsFFTField> Frame<'t, F> {
     pub fn new(data: Vec<FieldElement<F>>, row_width: usize) -> Self {
-        Self { data, row_width }
+        Self {
+            rows: FrameRows::Owned(data),
+            row_width,
+        }
+    }
+
+    /// Whether the frame has columns and its values fill whole rows, as the ones of
+    /// the proofs of any AIR.
+    pub fn has_whole_rows(&self) -> bool {
+        match &self.rows {
+            FrameRows::Owned(data) => self.row_width > 0 && data.len() % self.row_width == 0,
+            FrameRows::View { .. } => self.row_width > 0,
+        }
     }
 
     pub fn num_rows(&self) -> usize {
-        self.data.len() / self.row_width
+        match &self.rows {
+            FrameRows::Owned(data) => data.len() / self.row_width,
+            FrameRows::View { offsets, .. } => offsets.len(),
+        }
     }
 
     pub fn num_columns(&self) -> usize {
@@ -28,34 +64,61 @@ impl<F: IsFFTField> Frame<F> {
     }
 
     pub fn get_row(&self, row_idx: usize) -> &[FieldElement<F>] {
-        let row_offset = row_idx * self.row_width;
-        &self.data[row_offset..row_offset + self.row_width]
+        match &self.rows {
+            FrameRows::Owned(data) => {
+                let row_offset = row_idx * self.row_width;
+                &data[row_offset..row_offset + self.row_width]
+            }
+            FrameRows::View {
+                trace,
+                step,
+                blowup,
+                offsets,
+            } => trace.get_row((step + offsets[row_idx] * blowup) % trace.n_rows()),
+        }
     }
 
+    /// Mutable access to a row. A view copies the rows it borrows first, so
+    /// changing them doesn't change the trace.
     pub fn get_row_mut(&mut self, row_idx: usize) -> &mut [FieldElement<F>] {
+        if matches!(self.rows, FrameRows::View { .. }) {
+            self.rows = FrameRows::Owned(self.values());
+        }
+        let FrameRows::Owned(data) = &mut self.rows else {
+            unreachable!("views were just copied");
+        };
         let row_offset = row_idx * self.row_width;
-        &mut self.data[row_offset..row_offset + self.row_width]
+        &mut data[row_offset..row_offset + self.row_width]
+    }
+
+    /// The values of the frame, row by row.
+    pub fn values(&self) -> Vec<FieldElement<F>> {
+        match &self.rows {
+            FrameRows::Owned(data) => data.clone(),
+            FrameRows::View { .. } => (0..self.num_rows())
+                .flat_map(|row_idx| self.get_row(row_idx).iter().cloned())
+                .collect(),
+        }
     }
 
+    /// A view over the rows `step + offset * blowup` of `trace` for each one of the
+    /// `offsets`, wrapping around the trace. With the LDE of a trace and its blowup
+    /// factor, these are the rows of the trace steps the offsets are relative to.
     pub fn read_from_trace(
-        trace: &TraceTable<F>,
+        trace: &'t TraceTable<F>,
         step: usize,
         blowup: u8,
-        offsets: &[usize],
+        offsets: &'t [usize],
     ) -> Self {
-        // Get trace length to apply module with it when getting elements of
-        // the frame from the trace.
-        let trace_steps = trace.n_rows();
-        let data = offsets
-            .iter()
-            .flat_map(|frame_row_idx| {
-                trace
-                    .get_row((step + (frame_row_idx * blowup as usize)) % trace_steps)
-                    .to_vec()
-            })
-            .collect();
-
-        Self::new(data, trace.n_cols)
+        Self {
+            rows: FrameRows::View {
+                trace,
+                step,
+                blowup: blowup as usize,
+                offsets,
+            },
+            row_width: trace.n_cols,
+        }
     }
 
     /// Given a slice of trace polynomials, an evaluation point `x`, the frame offsets
@@ -83,21 +146,30 @@ impl<F: IsFFTField> Frame<F> {
     }
 }
 
-impl<F> Serializable for Frame<F>
+impl<F: IsFFTField> PartialEq for Frame<'_, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.row_width == other.row_width
+            && self.num_rows() == other.num_rows()
+            && (0..self.num_rows()).all(|row_idx| self.get_row(row_idx) == other.get_row(row_idx))
+    }
+}
+
+impl<F> Serializable for Frame<'_, F>
 where
     F: IsFFTField,
     FieldElement<F>: ByteConversion,
 {
     fn serialize(&self) -> Vec<u8> {
+        let data = self.values();
         let mut bytes = vec![];
-        bytes.extend(self.data.len().to_be_bytes());
-        let felt_len = if self.data.is_empty() {
+        bytes.extend(data.len().to_be_bytes());
+        let felt_len = if data.is_empty() {
             0
         } else {
-            self.data[0].to_bytes_be().len()
+            data[0].to_bytes_be().len()
         };
         bytes.extend(felt_len.to_be_bytes());
-        for felt in &self.data {
+        for felt in &data {
             bytes.extend(felt.to_bytes_be());
         }
         bytes.extend(self.row_width.to_be_bytes());
@@ -105,7 +177,7 @@ where
     }
 }
 
-impl<F> Deserializable for Frame<F>
+impl<F> Deserializable for Frame<'_, F>
 where
     F: IsFFTField,
     FieldElement<F>: ByteConversion,
@@ -114,40 +186,33 @@ where
     where
         Self: Sized,
     {
-        let mut bytes = bytes;
-        let data_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
-        bytes = &bytes[8..];
+        let (data_len, mut bytes) = deserialize_len(bytes, 1, usize::MAX)?;
 
-        let felt_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
-        bytes = &bytes[8..];
+        // Empty frames are serialized with elements of no bytes
+        let felt_len = if data_len == 0 {
+            if bytes.get(..8) != Some(&0usize.to_be_bytes()[..]) {
+                return Err(DeserializationError::InvalidAmountOfBytes);
+            }
+            bytes = &bytes[8..];
+            0
+        } else {
+            let felt_len;
+            (felt_len, bytes) = deserialize_felt_len::<F>(bytes)?;
+            felt_len
+        };
+        if data_len > bytes.len() / felt_len.max(1) {
+            return Err(DeserializationError::InvalidAmountOfBytes);
+        }
 
-        let mut data = vec![];
+        let mut data = Vec::with_capacity(data_len);
         for _ in 0..data_len {
-            let felt = FieldElement::<F>::from_bytes_be(
-                bytes
-                    .get(..felt_len)
-                    .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-            )?;
+            let felt;
+            (felt, bytes) = deserialize_felt(bytes, felt_len)?;
             data.push(felt);
-            bytes = &bytes[felt_len..];
         }
 
         let row_width = usize::from_be_bytes(
             bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
                 .try_into()
                 .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
         );
@@ -163,7 +228,7 @@ mod tests {
     };
     use proptest::{collection, prelude::*, prop_compose, proptest};
 
-    use crate::starks::frame::Frame;
+    use crate::starks::{frame::Frame, trace::TraceTable};
     use lambdaworks_math::traits::{Deserializable, Serializable};
 
     type FE = FieldElement<Stark252PrimeField>;
@@ -188,8 +253,42 @@ mod tests {
             let serialized = frame.serialize();
             let deserialized: Frame<Stark252PrimeField> = Frame::deserialize(&serialized).unwrap();
 
-            prop_assert_eq!(frame.data, deserialized.data);
+            prop_assert_eq!(frame.values(), deserialized.values());
             prop_assert_eq!(frame.row_width, deserialized.row_width);
         }
     }
+
+    #[test]
+    fn views_read_the_rows_of_the_steps_of_the_lde() {
+        let columns: Vec<Vec<FE>> = vec![
+            (0..8u64).map(FE::from).collect(),
+            (0..8u64).map(|i| FE::from(i * i)).collect(),
+        ];
+        let trace = TraceTable::new_from_cols(&columns);
+        let offsets = [0, 1, 3];
+
+        let frame = Frame::read_from_trace(&trace, 6, 2, &offsets);
+
+        assert_eq!(frame.num_rows(), 3);
+        assert_eq!(frame.num_columns(), 2);
+        assert_eq!(frame.get_row(0), &[FE::from(6), FE::from(36)]);
+        assert_eq!(frame.get_row(1), &[FE::from(0), FE::from(0)]);
+        assert_eq!(frame.get_row(2), &[FE::from(4), FE::from(16)]);
+        assert_eq!(
+            frame,
+            Frame::new([6, 36, 0, 0, 4, 16].into_iter().map(FE::from).collect(), 2)
+        );
+    }
+
+    #[test]
+    fn changing_a_view_doesnt_change_the_trace() {
+        let trace = TraceTable::new_from_cols(&[(0..4u64).map(FE::from).collect::<Vec<_>>()]);
+        let offsets = [0, 1];
+
+        let mut frame = Frame::read_from_trace(&trace, 1, 1, &offsets);
+        frame.get_row_mut(1)[0] = FE::from(42);
+
+        assert_eq!(frame.values(), vec![FE::from(1), FE::from(42)]);
+        assert_eq!(trace.get_row(2), &[FE::from(2)]);
+    }
 }
@@ -1,3 +1,6 @@
+use std::{collections::BTreeMap, io};
+
+use lambdaworks_crypto::merkle_tree::proof::Proof;
 use lambdaworks_math::{
     fft::polynomial::FFTPoly,
     field::{
@@ -8,7 +11,11 @@ use lambdaworks_math::{
     traits::ByteConversion,
 };
 
-use crate::starks::config::FriMerkleTree;
+use crate::starks::{
+    config::{Commitment, FriVectorCommitment, MerkleCap},
+    proof::options::ProofOptions,
+    vector_commitment::IsVectorCommitment,
+};
 
 #[derive(Clone)]
 pub struct FriLayer<F>
@@ -17,7 +24,7 @@ where
     FieldElement<F>: ByteConversion,
 {
     pub evaluation: Vec<FieldElement<F>>,
-    pub merkle_tree: FriMerkleTree<F>,
+    pub merkle_tree: FriVectorCommitment<F>,
     pub coset_offset: FieldElement<F>,
     pub domain_size: usize,
 }
@@ -31,18 +38,111 @@ where
         poly: &Polynomial<FieldElement<F>>,
         coset_offset: &FieldElement<F>,
         domain_size: usize,
-    ) -> Self {
+        options: &ProofOptions,
+    ) -> io::Result<Self> {
         let evaluation = poly
             .evaluate_offset_fft(1, Some(domain_size), coset_offset)
             .unwrap(); // TODO: return error
 
-        let merkle_tree = FriMerkleTree::build(&evaluation);
+        Self::from_evaluations(evaluation, coset_offset, domain_size, options)
+    }
 
-        Self {
+    /// The layer of the evaluations of its polynomial over the coset of
+    /// `coset_offset` of size `domain_size`, like the ones of a `ProverCheckpoint`.
+    pub fn from_evaluations(
+        evaluation: Vec<FieldElement<F>>,
+        coset_offset: &FieldElement<F>,
+        domain_size: usize,
+        options: &ProofOptions,
+    ) -> io::Result<Self> {
+        let merkle_tree = FriVectorCommitment::commit(options, &evaluation)?;
+
+        Ok(Self {
             evaluation,
             merkle_tree,
             coset_offset: coset_offset.clone(),
             domain_size,
+        })
+    }
+
+    /// Drops the evaluations and the Merkle tree of the layer once the query indexes
+    /// are known, keeping only the evaluations at `positions` with their
+    /// authentication paths up to the cap of the options. The paths are extracted at
+    /// once, so the nodes shared by several of them are read once.
+    pub fn prune(self, positions: &[usize], options: &ProofOptions) -> PrunedFriLayer<F> {
+        let auth_paths = self
+            .merkle_tree
+            .open(options, positions)
+            .expect("the positions are in the layer");
+        let openings = positions
+            .iter()
+            .zip(auth_paths)
+            .map(|(position, auth_path)| {
+                (*position, (self.evaluation[*position].clone(), auth_path))
+            })
+            .collect();
+
+        PrunedFriLayer {
+            cap: self.merkle_tree.commitment(options),
+            coset_offset: self.coset_offset,
+            domain_size: self.domain_size,
+            openings,
+        }
+    }
+}
+
+/// A FRI layer with the evaluations and authentication paths of its opened
+/// positions only. See `FriLayer::prune`.
+#[derive(Clone)]
+pub struct PrunedFriLayer<F: IsField> {
+    pub cap: MerkleCap,
+    pub coset_offset: FieldElement<F>,
+    pub domain_size: usize,
+    openings: BTreeMap<usize, (FieldElement<F>, Proof<Commitment>)>,
+}
+
+impl<F: IsField> PrunedFriLayer<F> {
+    /// The evaluation at `position` and its authentication path, if it was kept.
+    pub fn opening(&self, position: usize) -> Option<&(FieldElement<F>, Proof<Commitment>)> {
+        self.openings.get(&position)
+    }
+
+    /// Number of positions kept.
+    pub fn num_openings(&self) -> usize {
+        self.openings.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    #[test]
+    fn pruned_layers_keep_the_openings_of_the_given_positions() {
+        let poly = Polynomial::new(&[FE::from(3), FE::from(1), FE::from(4), FE::from(1)]);
+        let options = ProofOptions {
+            merkle_cap_height: 1,
+            ..ProofOptions::default_test_options()
+        };
+        let layer = FriLayer::new(&poly, &FE::from(3), 16, &options).unwrap();
+        let evaluation = layer.evaluation.clone();
+        let cap = layer.merkle_tree.commitment(&options);
+
+        let pruned = layer.prune(&[2, 9, 2], &options);
+        assert_eq!(pruned.cap, cap);
+        assert_eq!(pruned.num_openings(), 2);
+        assert!(pruned.opening(3).is_none());
+        for position in [2, 9] {
+            let (value, auth_path) = pruned.opening(position).unwrap();
+            assert_eq!(value, &evaluation[position]);
+            assert!(FriVectorCommitment::<F>::verify_opening(
+                &options, &cap, auth_path, position, value
+            ));
         }
     }
 }
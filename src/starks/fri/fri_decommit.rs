@@ -6,8 +6,18 @@ use lambdaworks_math::field::traits::IsField;
 use lambdaworks_math::traits::{ByteConversion, Deserializable, Serializable};
 
 use crate::starks::config::Commitment;
-use crate::starks::utils::{deserialize_proof, serialize_proof};
-
+use crate::starks::proof::stark::ProofLimits;
+use crate::starks::utils::{
+    check_fully_read, deserialize_felt, deserialize_felt_len, deserialize_len, deserialize_proof,
+    serialize_proof,
+};
+
+/// Openings of the FRI layers for a query 𝜄ₛ.
+///
+/// The symmetric openings cover every layer, while `layers_auth_paths` and
+/// `layers_evaluations` start at the second one: the evaluation of the first layer
+/// at 𝜐ₛ is the DEEP composition polynomial evaluation, which the verifier
+/// reconstructs from the trace and composition polynomial openings.
 #[derive(Debug, Clone)]
 pub struct FriDecommitment<F: IsField> {
     pub layers_auth_paths_sym: Vec<Proof<Commitment>>,
@@ -27,7 +37,7 @@ where
         for proof in &self.layers_auth_paths_sym {
             bytes.extend(serialize_proof(proof));
         }
-        let felt_len = self.layers_evaluations[0].to_bytes_be().len();
+        let felt_len = self.layers_evaluations_sym[0].to_bytes_be().len();
         bytes.extend(felt_len.to_be_bytes());
         bytes.extend(self.layers_evaluations_sym.len().to_be_bytes());
         for evaluation in &self.layers_evaluations_sym {
@@ -54,87 +64,59 @@ where
     where
         Self: Sized,
     {
-        let mut bytes = bytes;
-        let mut layers_auth_paths_sym = vec![];
-        let layers_auth_paths_sym_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
-        bytes = &bytes[8..];
+        Self::deserialize_with_limits(bytes, &ProofLimits::NONE)
+    }
+}
 
+impl<F> FriDecommitment<F>
+where
+    F: IsField,
+    FieldElement<F>: ByteConversion,
+{
+    /// Deserializes a decommitment of at most `limits.fri_layers` layers, whose paths
+    /// have at most `limits.merkle_path_len` nodes.
+    pub(crate) fn deserialize_with_limits(
+        bytes: &[u8],
+        limits: &ProofLimits,
+    ) -> Result<Self, DeserializationError> {
+        let (layers_auth_paths_sym_len, mut bytes) = deserialize_len(bytes, 8, limits.fri_layers)?;
+        let mut layers_auth_paths_sym = Vec::with_capacity(layers_auth_paths_sym_len);
         for _ in 0..layers_auth_paths_sym_len {
             let proof;
-            (proof, bytes) = deserialize_proof(bytes)?;
+            (proof, bytes) = deserialize_proof(bytes, limits.merkle_path_len)?;
             layers_auth_paths_sym.push(proof);
         }
 
-        let felt_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
-        bytes = &bytes[8..];
-
-        let layers_evaluations_sym_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
-        bytes = &bytes[8..];
-
-        let mut layers_evaluations_sym = vec![];
+        let felt_len;
+        (felt_len, bytes) = deserialize_felt_len::<F>(bytes)?;
+
+        let layers_evaluations_sym_len;
+        (layers_evaluations_sym_len, bytes) = deserialize_len(bytes, felt_len, limits.fri_layers)?;
+        let mut layers_evaluations_sym = Vec::with_capacity(layers_evaluations_sym_len);
         for _ in 0..layers_evaluations_sym_len {
-            let evaluation = FieldElement::<F>::from_bytes_be(
-                bytes
-                    .get(..felt_len)
-                    .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-            )?;
-            bytes = &bytes[felt_len..];
+            let evaluation;
+            (evaluation, bytes) = deserialize_felt(bytes, felt_len)?;
             layers_evaluations_sym.push(evaluation);
         }
 
-        let layer_evaluations_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
-        bytes = &bytes[8..];
-
-        let mut layers_evaluations = vec![];
+        let layer_evaluations_len;
+        (layer_evaluations_len, bytes) = deserialize_len(bytes, felt_len, limits.fri_layers)?;
+        let mut layers_evaluations = Vec::with_capacity(layer_evaluations_len);
         for _ in 0..layer_evaluations_len {
-            let evaluation = FieldElement::<F>::from_bytes_be(
-                bytes
-                    .get(..felt_len)
-                    .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-            )?;
-            bytes = &bytes[felt_len..];
+            let evaluation;
+            (evaluation, bytes) = deserialize_felt(bytes, felt_len)?;
             layers_evaluations.push(evaluation);
         }
 
-        let mut layers_auth_paths = vec![];
-        let layers_auth_paths_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
-        bytes = &bytes[8..];
-
+        let layers_auth_paths_len;
+        (layers_auth_paths_len, bytes) = deserialize_len(bytes, 8, limits.fri_layers)?;
+        let mut layers_auth_paths = Vec::with_capacity(layers_auth_paths_len);
         for _ in 0..layers_auth_paths_len {
             let proof;
-            (proof, bytes) = deserialize_proof(bytes)?;
+            (proof, bytes) = deserialize_proof(bytes, limits.merkle_path_len)?;
             layers_auth_paths.push(proof);
         }
+        check_fully_read(bytes)?;
 
         Ok(Self {
             layers_auth_paths_sym,
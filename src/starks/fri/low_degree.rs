@@ -0,0 +1,363 @@
+//! FRI as a low-degree test on its own, without the rest of the STARK protocol, to
+//! commit to a polynomial and prove it has degree less than a bound.
+//!
+//! The protocol is the one of the FRI part of the STARK proofs, configured by the
+//! same `ProofOptions`: the blowup factor, the queries, the layers, the coset offset
+//! and the commitments. Grinding isn't done. Unlike in the STARK proofs, where the
+//! verifier gets it from the DEEP openings, the evaluation of the first layer at each
+//! queried point is opened too.
+
+use lambdaworks_crypto::{fiat_shamir::transcript::Transcript, merkle_tree::proof::Proof};
+use lambdaworks_math::{
+    fft::polynomial::FFTPoly,
+    field::{element::FieldElement, traits::IsFFTField},
+    polynomial::Polynomial,
+    traits::ByteConversion,
+};
+
+use super::{
+    fri_commit_phase, fri_decommit::FriDecommitment, open_fri_layers, verify_fri_query,
+    FriCommitPhase,
+};
+use crate::starks::{
+    config::{Commitment, FriVectorCommitment, MerkleCap},
+    proof::{errors::InsecureOptionError, options::ProofOptions},
+    prover::ProvingError,
+    transcript::{sample_query_indexes, transcript_to_field},
+    vector_commitment::IsVectorCommitment,
+};
+
+/// Openings of a query 𝜄ₛ: the first layer at 𝜐ₛ, and every layer at −𝜐ₛ and the
+/// rest at 𝜐ₛ as in the STARK proofs.
+#[derive(Debug, Clone)]
+pub struct FriQuery<F: IsFFTField> {
+    pub evaluation: FieldElement<F>,
+    pub auth_path: Proof<Commitment>,
+    pub decommitment: FriDecommitment<F>,
+}
+
+/// Proof that the polynomial committed to in the first layer has degree less than
+/// the bound it was proven for.
+#[derive(Debug, Clone)]
+pub struct FriProof<F: IsFFTField> {
+    pub layers_merkle_caps: Vec<MerkleCap>,
+    pub last_poly: Vec<FieldElement<F>>,
+    pub queries: Vec<FriQuery<F>>,
+}
+
+impl<F: IsFFTField> FriProof<F> {
+    /// The commitment to the polynomial, the cap of the first layer. `None` if the
+    /// proof has no layers.
+    pub fn commitment(&self) -> Option<&MerkleCap> {
+        self.layers_merkle_caps.first()
+    }
+}
+
+/// Absorbs the degree bound, so proofs for one bound aren't valid for another one.
+fn append_degree_bound<T: Transcript>(transcript: &mut T, degree_bound: usize) {
+    transcript.append(&(degree_bound as u64).to_be_bytes());
+}
+
+/// Size of the domain of the first layer. `None` if the degree bound isn't a power
+/// of two of at least 2.
+fn lde_domain_size(options: &ProofOptions, degree_bound: usize) -> Option<usize> {
+    if degree_bound < 2 || !degree_bound.is_power_of_two() {
+        return None;
+    }
+    degree_bound.checked_mul(options.blowup_factor as usize)
+}
+
+pub struct FriProver {
+    options: ProofOptions,
+}
+
+impl FriProver {
+    /// Fails if the options are invalid, see `ProofOptions::validate`.
+    pub fn new(options: ProofOptions) -> Result<Self, ProvingError> {
+        options
+            .validate()
+            .map_err(|error| ProvingError::WrongParameter(error.to_string()))?;
+        Ok(Self { options })
+    }
+
+    /// Proves `poly` has degree less than `degree_bound`, a power of two, by
+    /// committing to its evaluations over a domain `blowup_factor` times larger. The
+    /// proof of a polynomial of a larger degree doesn't verify. Fails if the degree
+    /// bound isn't a power of two of at least 2, or if the layers are stored on disk
+    /// and they can't be written.
+    pub fn prove<F, T>(
+        &self,
+        poly: &Polynomial<FieldElement<F>>,
+        degree_bound: usize,
+        transcript: &mut T,
+    ) -> Result<FriProof<F>, ProvingError>
+    where
+        F: IsFFTField,
+        FieldElement<F>: ByteConversion,
+        T: Transcript,
+    {
+        let options = &self.options;
+        let domain_size = lde_domain_size(options, degree_bound).ok_or_else(|| {
+            ProvingError::WrongParameter(format!(
+                "the degree bound {degree_bound} is not a power of two of at least 2"
+            ))
+        })?;
+        append_degree_bound(transcript, degree_bound);
+
+        let (last_poly, fri_layers) = fri_commit_phase(
+            options.fri_number_of_layers(degree_bound),
+            options.fri_last_poly_length(degree_bound),
+            poly.clone(),
+            transcript,
+            &options.coset_offset.to_field_element(),
+            domain_size,
+            options,
+        )?;
+        let layers_merkle_caps = fri_layers
+            .iter()
+            .map(|layer| layer.merkle_tree.commitment(options))
+            .collect();
+
+        let iotas = sample_query_indexes(
+            transcript,
+            options.fri_number_of_queries,
+            domain_size,
+            options.deduplicate_queries,
+        );
+        let queries = open_fri_layers(fri_layers, &iotas, options, true)
+            .into_iter()
+            .map(|mut decommitment| FriQuery {
+                evaluation: decommitment.layers_evaluations.remove(0),
+                auth_path: decommitment.layers_auth_paths.remove(0),
+                decommitment,
+            })
+            .collect();
+
+        Ok(FriProof {
+            layers_merkle_caps,
+            last_poly,
+            queries,
+        })
+    }
+
+    /// Proves `evaluations`, over the coset of `offset · ⟨ω⟩` of their size, are the
+    /// ones of a polynomial of degree less than their number divided by the blowup
+    /// factor. Fails if that degree bound isn't a power of two of at least 2.
+    pub fn prove_evaluations<F, T>(
+        &self,
+        evaluations: &[FieldElement<F>],
+        transcript: &mut T,
+    ) -> Result<FriProof<F>, ProvingError>
+    where
+        F: IsFFTField,
+        FieldElement<F>: ByteConversion,
+        T: Transcript,
+    {
+        let offset = self.options.coset_offset.to_field_element();
+        let poly = Polynomial::interpolate_offset_fft(evaluations, &offset)
+            .map_err(|error| ProvingError::WrongParameter(format!("{error:?}")))?;
+        let degree_bound = evaluations.len() / self.options.blowup_factor as usize;
+        self.prove(&poly, degree_bound, transcript)
+    }
+}
+
+pub struct FriVerifier {
+    options: ProofOptions,
+}
+
+impl FriVerifier {
+    /// Fails if the options are invalid, see `ProofOptions::validate`.
+    pub fn new(options: ProofOptions) -> Result<Self, InsecureOptionError> {
+        options.validate()?;
+        Ok(Self { options })
+    }
+
+    /// Verifies the polynomial committed to by `proof` has degree less than
+    /// `degree_bound`, a power of two, with the transcript in the state the prover
+    /// started from. Proofs without layers, and degree bounds that aren't powers of
+    /// two of at least 2, are rejected.
+    pub fn verify<F, T>(&self, proof: &FriProof<F>, degree_bound: usize, transcript: &mut T) -> bool
+    where
+        F: IsFFTField,
+        FieldElement<F>: ByteConversion,
+        T: Transcript,
+    {
+        let options = &self.options;
+        let Some(domain_size) = lde_domain_size(options, degree_bound) else {
+            return false;
+        };
+        let lde_root_order = u64::from(domain_size.trailing_zeros());
+
+        // Verify the shape of the proof
+        let number_of_layers = options.fri_number_of_layers(degree_bound);
+        let cap_len = |depth: usize| 1 << options.merkle_cap_height.min(depth);
+        let Some(first_layer_cap) = proof.commitment() else {
+            return false;
+        };
+        if proof.layers_merkle_caps.len() != number_of_layers
+            || proof.last_poly.len() != options.fri_last_poly_length(degree_bound)
+            || proof
+                .layers_merkle_caps
+                .iter()
+                .enumerate()
+                .any(|(k, cap)| cap.len() != cap_len(lde_root_order as usize - k))
+        {
+            return false;
+        }
+        let Some(roots) = proof
+            .layers_merkle_caps
+            .iter()
+            .map(|cap| FriVectorCommitment::<F>::commitment_root(options, cap))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return false;
+        };
+
+        // Replay the transcript of the prover
+        append_degree_bound(transcript, degree_bound);
+        let zetas: Vec<FieldElement<F>> = roots
+            .iter()
+            .map(|root| {
+                transcript.append(root);
+                transcript_to_field(transcript)
+            })
+            .collect();
+        for coefficient in &proof.last_poly {
+            transcript.append(&coefficient.to_bytes_be());
+        }
+        let iotas = sample_query_indexes(
+            transcript,
+            options.fri_number_of_queries,
+            domain_size,
+            options.deduplicate_queries,
+        );
+        if iotas.len() != proof.queries.len() {
+            return false;
+        }
+
+        let commit_phase = FriCommitPhase {
+            layers_caps: &proof.layers_merkle_caps,
+            zetas: &zetas,
+            last_poly: &proof.last_poly,
+            lde_root_order,
+            options,
+        };
+        let primitive_root = F::get_primitive_root_of_unity(lde_root_order).unwrap();
+        let coset_offset: FieldElement<F> = options.coset_offset.to_field_element();
+        let two_inv = FieldElement::from(2).inv();
+
+        iotas
+            .iter()
+            .zip(&proof.queries)
+            .fold(true, |result, (iota, query)| {
+                let evaluation_point = &coset_offset * primitive_root.pow(*iota as u64);
+                let first_opening = FriVectorCommitment::<F>::verify_opening(
+                    options,
+                    first_layer_cap,
+                    &query.auth_path,
+                    *iota,
+                    &query.evaluation,
+                );
+                result
+                    & first_opening
+                    & verify_fri_query(
+                        &commit_phase,
+                        *iota,
+                        &query.decommitment,
+                        &evaluation_point,
+                        evaluation_point.inv(),
+                        &two_inv,
+                        &query.evaluation,
+                    )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_crypto::fiat_shamir::default_transcript::DefaultTranscript;
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    fn poly(degree: u64) -> Polynomial<FE> {
+        Polynomial::new(
+            &(0..=degree)
+                .map(|i| FE::from(i * i + 7))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn options() -> ProofOptions {
+        ProofOptions {
+            merkle_cap_height: 2,
+            ..ProofOptions::default_test_options()
+        }
+    }
+
+    #[test]
+    fn polynomials_of_degree_less_than_the_bound_are_verified() {
+        let proof = FriProver::new(options())
+            .unwrap()
+            .prove(&poly(31), 32, &mut DefaultTranscript::new())
+            .unwrap();
+
+        let verifier = FriVerifier::new(options()).unwrap();
+        assert!(verifier.verify(&proof, 32, &mut DefaultTranscript::new()));
+        assert!(!verifier.verify(&proof, 64, &mut DefaultTranscript::new()));
+    }
+
+    #[test]
+    fn polynomials_of_degree_past_the_bound_are_rejected() {
+        let proof = FriProver::new(options())
+            .unwrap()
+            .prove(&poly(40), 32, &mut DefaultTranscript::new())
+            .unwrap();
+
+        let verifier = FriVerifier::new(options()).unwrap();
+        assert!(!verifier.verify(&proof, 32, &mut DefaultTranscript::new()));
+    }
+
+    #[test]
+    fn evaluations_are_proven_over_the_coset() {
+        let options = options();
+        let offset = options.coset_offset.to_field_element();
+        let evaluations = poly(15)
+            .evaluate_offset_fft(1, Some(16 * options.blowup_factor as usize), &offset)
+            .unwrap();
+
+        let proof = FriProver::new(options.clone())
+            .unwrap()
+            .prove_evaluations(&evaluations, &mut DefaultTranscript::new())
+            .unwrap();
+
+        let verifier = FriVerifier::new(options).unwrap();
+        assert!(verifier.verify(&proof, 16, &mut DefaultTranscript::new()));
+    }
+
+    #[test]
+    fn invalid_options_degree_bounds_and_proofs_are_rejected() {
+        let without_layers = ProofOptions {
+            fri_max_layers: Some(0),
+            ..options()
+        };
+        assert!(FriProver::new(without_layers.clone()).is_err());
+        assert!(FriVerifier::new(without_layers).is_err());
+
+        let prover = FriProver::new(options()).unwrap();
+        assert!(prover
+            .prove(&poly(31), 24, &mut DefaultTranscript::new())
+            .is_err());
+
+        let mut proof = prover
+            .prove(&poly(31), 32, &mut DefaultTranscript::new())
+            .unwrap();
+        let verifier = FriVerifier::new(options()).unwrap();
+        assert!(!verifier.verify(&proof, 24, &mut DefaultTranscript::new()));
+
+        proof.layers_merkle_caps.clear();
+        assert!(!verifier.verify(&proof, 32, &mut DefaultTranscript::new()));
+    }
+}
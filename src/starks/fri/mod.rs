@@ -1,6 +1,9 @@
 pub mod fri_commitment;
 pub mod fri_decommit;
 mod fri_functions;
+pub mod low_degree;
+
+use std::io;
 
 use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
 use lambdaworks_math::field::traits::{IsFFTField, IsField};
@@ -10,31 +13,41 @@ pub use lambdaworks_math::{
     polynomial::Polynomial,
 };
 
-use self::fri_commitment::FriLayer;
+use self::fri_commitment::{FriLayer, PrunedFriLayer};
 use self::fri_decommit::FriDecommitment;
 use self::fri_functions::fold_polynomial;
 
+pub use self::low_degree::{FriProof, FriProver, FriQuery, FriVerifier};
+
+use super::config::{FriVectorCommitment, MerkleCap};
+use super::proof::options::ProofOptions;
 use super::traits::AIR;
-use super::transcript::{transcript_to_field, transcript_to_usize};
+use super::transcript::{sample_query_indexes, transcript_to_field};
+use super::vector_commitment::IsVectorCommitment;
 
+/// Commits to `number_layers` FRI layers, starting from `p_0`, and returns them
+/// together with the coefficients of the polynomial obtained by folding the last
+/// one, resized to `last_poly_length`.
 pub fn fri_commit_phase<F: IsField + IsFFTField, T: Transcript>(
     number_layers: usize,
+    last_poly_length: usize,
     p_0: Polynomial<FieldElement<F>>,
     transcript: &mut T,
     coset_offset: &FieldElement<F>,
     domain_size: usize,
-) -> (FieldElement<F>, Vec<FriLayer<F>>)
+    options: &ProofOptions,
+) -> io::Result<(Vec<FieldElement<F>>, Vec<FriLayer<F>>)>
 where
     FieldElement<F>: ByteConversion,
 {
     let mut domain_size = domain_size;
 
     let mut fri_layer_list = Vec::with_capacity(number_layers);
-    let mut current_layer = FriLayer::new(&p_0, coset_offset, domain_size);
+    let mut current_layer = FriLayer::new(&p_0, coset_offset, domain_size, options)?;
     fri_layer_list.push(current_layer.clone());
     let mut current_poly = p_0;
     // >>>> Send commitment: [p₀]
-    transcript.append(&current_layer.merkle_tree.root);
+    transcript.append(&current_layer.merkle_tree.root());
 
     let mut coset_offset = coset_offset.clone();
 
@@ -46,8 +59,8 @@ where
 
         // Compute layer polynomial and domain
         current_poly = fold_polynomial(&current_poly, &zeta);
-        current_layer = FriLayer::new(&current_poly, &coset_offset, domain_size);
-        let new_data = &current_layer.merkle_tree.root;
+        current_layer = FriLayer::new(&current_poly, &coset_offset, domain_size, options)?;
+        let new_data = &current_layer.merkle_tree.root();
         fri_layer_list.push(current_layer.clone()); // TODO: remove this clone
 
         // >>>> Send commitment: [pₖ]
@@ -59,22 +72,23 @@ where
 
     let last_poly = fold_polynomial(&current_poly, &zeta);
 
-    let last_value = last_poly
-        .coefficients()
-        .get(0)
-        .unwrap_or(&FieldElement::zero())
-        .clone();
+    // Its degree is bounded by the one of p₀, so only zeros are removed for a valid trace
+    let mut last_poly_coefficients = last_poly.coefficients().to_vec();
+    last_poly_coefficients.resize(last_poly_length, FieldElement::zero());
 
     // >>>> Send value: pₙ
-    transcript.append(&last_value.to_bytes_be());
+    for coefficient in &last_poly_coefficients {
+        transcript.append(&coefficient.to_bytes_be());
+    }
 
-    (last_value, fri_layer_list)
+    Ok((last_poly_coefficients, fri_layer_list))
 }
 
+/// Samples the query indexes and opens the FRI layers at them.
 pub fn fri_query_phase<F, A, T>(
     air: &A,
     domain_size: usize,
-    fri_layers: &Vec<FriLayer<F>>,
+    fri_layers: Vec<FriLayer<F>>,
     transcript: &mut T,
 ) -> (Vec<FriDecommitment<F>>, Vec<usize>)
 where
@@ -83,45 +97,205 @@ where
     T: Transcript,
     FieldElement<F>: ByteConversion,
 {
-    if !fri_layers.is_empty() {
-        let number_of_queries = air.options().fri_number_of_queries;
-        let iotas = (0..number_of_queries)
-            .map(|_| transcript_to_usize(transcript) % domain_size)
-            .collect::<Vec<usize>>();
-        let query_list = iotas
-            .iter()
-            .map(|iota_s| {
-                // <<<< Receive challenge 𝜄ₛ (iota_s)
-                let mut layers_auth_paths_sym = vec![];
-                let mut layers_evaluations_sym = vec![];
-                let mut layers_evaluations = vec![];
-                let mut layers_auth_paths = vec![];
-
-                for layer in fri_layers {
-                    // symmetric element
-                    let index = iota_s % layer.domain_size;
-                    let index_sym = (iota_s + layer.domain_size / 2) % layer.domain_size;
-                    let evaluation_sym = layer.evaluation[index_sym].clone();
-                    let auth_path_sym = layer.merkle_tree.get_proof_by_pos(index_sym).unwrap();
-                    let evaluation = layer.evaluation[index].clone();
-                    let auth_path = layer.merkle_tree.get_proof_by_pos(index).unwrap();
-                    layers_auth_paths_sym.push(auth_path_sym);
-                    layers_evaluations_sym.push(evaluation_sym);
+    if fri_layers.is_empty() {
+        return (vec![], vec![]);
+    }
+
+    let options = air.options();
+    let iotas = sample_query_indexes(
+        transcript,
+        options.fri_number_of_queries,
+        domain_size,
+        options.deduplicate_queries,
+    );
+    // The evaluation of p₀ at 𝜐ₛ is the one of the DEEP composition polynomial,
+    // which the verifier gets from the DEEP openings
+    let query_list = open_fri_layers(fri_layers, &iotas, options, false);
+
+    (query_list, iotas)
+}
+
+/// Opens the FRI layers at the queries `iotas`: every layer at −𝜐ₛ, and at 𝜐ₛ every
+/// layer but the first one, unless `open_first_layer`. The layers are pruned to the
+/// opened positions first, so their evaluations and Merkle trees are dropped before
+/// the decommitments are assembled.
+pub(crate) fn open_fri_layers<F>(
+    fri_layers: Vec<FriLayer<F>>,
+    iotas: &[usize],
+    options: &ProofOptions,
+    open_first_layer: bool,
+) -> Vec<FriDecommitment<F>>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    let opened_indexes = |k: usize, layer_domain_size: usize, iota_s: usize| {
+        let index = iota_s % layer_domain_size;
+        let index_sym = (iota_s + layer_domain_size / 2) % layer_domain_size;
+        std::iter::once(index_sym).chain((k > 0 || open_first_layer).then_some(index))
+    };
+
+    // The deeper layers are smaller, so more of the paths of their queries are
+    // shared.
+    let pruned_layers: Vec<PrunedFriLayer<F>> = fri_layers
+        .into_iter()
+        .enumerate()
+        .map(|(k, layer)| {
+            let positions: Vec<_> = iotas
+                .iter()
+                .flat_map(|iota_s| opened_indexes(k, layer.domain_size, *iota_s))
+                .collect();
+            layer.prune(&positions, options)
+        })
+        .collect();
+
+    iotas
+        .iter()
+        .map(|iota_s| {
+            // <<<< Receive challenge 𝜄ₛ (iota_s)
+            let mut layers_auth_paths_sym = vec![];
+            let mut layers_evaluations_sym = vec![];
+            let mut layers_evaluations = vec![];
+            let mut layers_auth_paths = vec![];
+
+            for (k, layer) in pruned_layers.iter().enumerate() {
+                let mut indexes = opened_indexes(k, layer.domain_size, *iota_s);
+
+                // symmetric element
+                let (evaluation_sym, auth_path_sym) =
+                    layer.opening(indexes.next().unwrap()).unwrap().clone();
+                layers_evaluations_sym.push(evaluation_sym);
+                layers_auth_paths_sym.push(auth_path_sym);
+
+                if let Some(index) = indexes.next() {
+                    let (evaluation, auth_path) = layer.opening(index).unwrap().clone();
                     layers_evaluations.push(evaluation);
                     layers_auth_paths.push(auth_path);
                 }
+            }
 
-                FriDecommitment {
-                    layers_auth_paths_sym,
-                    layers_evaluations_sym,
-                    layers_evaluations,
-                    layers_auth_paths,
-                }
-            })
-            .collect();
+            FriDecommitment {
+                layers_auth_paths_sym,
+                layers_evaluations_sym,
+                layers_evaluations,
+                layers_auth_paths,
+            }
+        })
+        .collect()
+}
+
+/// What the verifier receives and samples in the commit phase of FRI, shared by the
+/// checks of all the queries.
+pub(crate) struct FriCommitPhase<'a, F: IsField> {
+    pub layers_caps: &'a [MerkleCap],
+    pub zetas: &'a [FieldElement<F>],
+    pub last_poly: &'a [FieldElement<F>],
+    /// Order of the roots of unity of the domain of the first layer.
+    pub lde_root_order: u64,
+    pub options: &'a ProofOptions,
+}
+
+/// Checks the decommitment of the query 𝜄ₛ, where the first layer evaluates to
+/// `first_evaluation` at `evaluation_point`, 𝜐ₛ. The opening of the first layer at
+/// 𝜐ₛ isn't in the decommitment, so the caller checks where `first_evaluation` comes
+/// from.
+pub(crate) fn verify_fri_query<F>(
+    commit_phase: &FriCommitPhase<F>,
+    iota: usize,
+    fri_decommitment: &FriDecommitment<F>,
+    evaluation_point: &FieldElement<F>,
+    evaluation_point_inv: FieldElement<F>,
+    two_inv: &FieldElement<F>,
+    first_evaluation: &FieldElement<F>,
+) -> bool
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    let fri_layers_merkle_caps = commit_phase.layers_caps;
+    let options = commit_phase.options;
+    let evaluation_point_vec: Vec<FieldElement<F>> =
+        core::iter::successors(Some(evaluation_point_inv), |evaluation_point| {
+            Some(evaluation_point.square())
+        })
+        .take(fri_layers_merkle_caps.len())
+        .collect();
 
-        (query_list, iotas)
-    } else {
-        (vec![], vec![])
+    // Every layer is opened at −𝜐ₛ, and every layer but the first one at 𝜐ₛ
+    let num_layers = fri_layers_merkle_caps.len();
+    if fri_decommitment.layers_evaluations.len() + 1 != num_layers
+        || fri_decommitment.layers_auth_paths.len() + 1 != num_layers
+        || fri_decommitment.layers_evaluations_sym.len() != num_layers
+        || fri_decommitment.layers_auth_paths_sym.len() != num_layers
+    {
+        return false;
     }
+
+    let mut v = first_evaluation.clone();
+    // For each fri layer merkle proof check:
+    // That each merkle path verifies
+
+    // Sample beta with fiat shamir
+    // Compute v = [P_i(z_i) + P_i(-z_i)] / 2 + beta * [P_i(z_i) - P_i(-z_i)] / (2 * z_i)
+    // Where P_i is the folded polynomial of the i-th fiat shamir round
+    // z_i is obtained from the first z (that was derived through Fiat-Shamir) through a known calculation
+    // The calculation is, given the index, index % length_of_evaluation_domain
+
+    // Check that v = P_{i+1}(z_i)
+
+    // For each (merkle_cap, merkle_auth_path) / fold
+    // With the auth path containining the element that the path proves it's existence
+    fri_layers_merkle_caps
+        .iter()
+        .enumerate()
+        .zip(&fri_decommitment.layers_auth_paths_sym)
+        .zip(&fri_decommitment.layers_evaluations_sym)
+        .zip(evaluation_point_vec)
+        .fold(
+            true,
+            |result, ((((k, merkle_cap), auth_path_sym), evaluation_sym), evaluation_point_inv)| {
+                let domain_length = 1 << (commit_phase.lde_root_order - k as u64);
+                let layer_evaluation_index_sym = (iota + domain_length / 2) % domain_length;
+                // Since we always derive the current layer from the previous layer
+                // We start with the second one, skipping the first, so previous is layer is the first one
+                // This is the current layer's evaluation domain length.
+                // We need it to know what the decommitment index for the current
+                // layer is, so we can check the merkle paths at the right index.
+
+                // Verify opening Open(pₖ(Dₖ), −𝜐ₛ^(2ᵏ))
+                let auth_sym = &FriVectorCommitment::<F>::verify_opening(
+                    options,
+                    merkle_cap,
+                    auth_path_sym,
+                    layer_evaluation_index_sym,
+                    evaluation_sym,
+                );
+                // Verify opening Open(pₖ(Dₖ), 𝜐ₛ). For p₀ it's checked by the caller
+                let auth_point = k == 0
+                    || FriVectorCommitment::<F>::verify_opening(
+                        options,
+                        merkle_cap,
+                        &fri_decommitment.layers_auth_paths[k - 1],
+                        iota,
+                        &fri_decommitment.layers_evaluations[k - 1],
+                    );
+                let beta = &commit_phase.zetas[k];
+                // v is the calculated element for the co linearity check
+                v = (&v + evaluation_sym) * two_inv
+                    + beta * (&v - evaluation_sym) * two_inv * evaluation_point_inv;
+
+                // Check that next value is the given by the prover
+                if k < fri_decommitment.layers_evaluations.len() {
+                    let next_layer_evaluation = &fri_decommitment.layers_evaluations[k];
+                    result & (v == *next_layer_evaluation) & auth_point & auth_sym
+                } else {
+                    // pₙ is evaluated at 𝜐ₛ^(2ⁿ)
+                    let last_evaluation_point =
+                        evaluation_point.pow(1_u64 << fri_layers_merkle_caps.len());
+                    let last_value =
+                        Polynomial::new(commit_phase.last_poly).evaluate(&last_evaluation_point);
+                    result & (v == last_value) & auth_point & auth_sym
+                }
+            },
+        )
 }
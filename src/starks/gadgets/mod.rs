@@ -0,0 +1,2 @@
+pub mod poseidon;
+pub mod poseidon_merkle_path;
@@ -0,0 +1,370 @@
+//! Constraints checking a Merkle authentication path of a tree hashed with
+//! Poseidon, to be embedded in the trace of another AIR or used on their own by
+//! `PoseidonMerklePathAIR`.
+//!
+//! Each level of the path takes `rows_per_hash` rows: one for the state before each
+//! round of the permutation and one for the state after the last one, whose first
+//! element is the node of the next level. The columns, counted from the first
+//! column of the gadget, are:
+//!
+//! - `STATE`: the three elements of the state of the permutation.
+//! - `CUBES`: the cubes of the elements of the state plus the round constants, so
+//!   the round constraints have degree two.
+//! - `ROUND_CONSTANTS`, `FULL_ROUND`, `PARTIAL_ROUND`, `HASH_START` and `HASH_END`:
+//!   the constants of each round and flags with the kind of each row. They repeat
+//!   every `rows_per_hash` rows, which is enforced by the transition constraints,
+//!   and their values in the rows of the first level by the boundary constraints.
+//! - `NODE`: the node hashed in the level, starting with the leaf.
+//! - `DIRECTION`: whether the node is the right child in the level, that is, the
+//!   bit of the level in the index of the leaf.
+//!
+//! The sibling of each level is the other input of its hash, so it never has a
+//! column of its own. The levels past the depth of the path, which fill the trace
+//! up to a power of two, hash with zero siblings and aren't checked.
+
+use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
+
+use super::poseidon::{PoseidonParameters, STATE_SIZE};
+use crate::starks::constraints::{boundary::BoundaryConstraint, symbolic::Expr};
+
+pub const STATE: usize = 0;
+pub const CUBES: usize = 3;
+pub const ROUND_CONSTANTS: usize = 6;
+pub const FULL_ROUND: usize = 9;
+pub const PARTIAL_ROUND: usize = 10;
+pub const HASH_START: usize = 11;
+pub const HASH_END: usize = 12;
+pub const NODE: usize = 13;
+pub const DIRECTION: usize = 14;
+pub const NUM_COLUMNS: usize = 15;
+
+/// The columns fixed by the gadget, which repeat every hash.
+const FIXED_COLUMNS: std::ops::Range<usize> = ROUND_CONSTANTS..HASH_END + 1;
+
+/// Names of the transition constraints, in the order of `transition_constraints`.
+pub const CONSTRAINT_NAMES: [&str; 19] = [
+    "cube_0",
+    "cube_1",
+    "cube_2",
+    "round_0",
+    "round_1",
+    "round_2",
+    "hash_end_is_next_node",
+    "node_continuity",
+    "direction_continuity",
+    "direction_is_bit",
+    "hash_start_inputs",
+    "hash_start_capacity",
+    "round_constant_0_period",
+    "round_constant_1_period",
+    "round_constant_2_period",
+    "full_round_period",
+    "partial_round_period",
+    "hash_start_period",
+    "hash_end_period",
+];
+
+#[derive(Clone, Debug)]
+pub struct PoseidonMerklePathGadget<F: IsFFTField> {
+    parameters: PoseidonParameters<F>,
+    first_column: usize,
+    frame_rows: [usize; 3],
+}
+
+impl<F: IsFFTField> PoseidonMerklePathGadget<F> {
+    /// A gadget whose columns start at `first_column` of the trace and whose first
+    /// level starts at its first row. `frame_rows` are the rows of the frame of the
+    /// AIR holding the offsets of `transition_offsets`, in their order.
+    pub fn new(
+        parameters: PoseidonParameters<F>,
+        first_column: usize,
+        frame_rows: [usize; 3],
+    ) -> Self {
+        Self {
+            parameters,
+            first_column,
+            frame_rows,
+        }
+    }
+
+    pub fn parameters(&self) -> &PoseidonParameters<F> {
+        &self.parameters
+    }
+
+    pub fn rows_per_hash(&self) -> usize {
+        self.parameters.n_rounds() + 1
+    }
+
+    /// Offsets of the rows read by the constraints: the current row, the next one
+    /// and the one of the next hash.
+    pub fn transition_offsets(&self) -> [usize; 3] {
+        [0, 1, self.rows_per_hash()]
+    }
+
+    /// Length of the trace of a path of `depth` levels.
+    pub fn trace_length(&self, depth: usize) -> usize {
+        (depth * self.rows_per_hash()).next_power_of_two()
+    }
+
+    /// Row of the output of the hash of the last level of a path of `depth` levels.
+    pub fn root_step(&self, depth: usize) -> usize {
+        (depth - 1) * self.rows_per_hash() + self.parameters.n_rounds()
+    }
+
+    pub fn transition_constraints(&self) -> Vec<Expr<F>> {
+        let cell = |frame_row: usize, col: usize| {
+            Expr::cell(self.frame_rows[frame_row], self.first_column + col)
+        };
+        let current = |col| cell(0, col);
+        let next = |col| cell(1, col);
+        let one = Expr::one();
+
+        let state: Vec<_> = (0..STATE_SIZE).map(|i| current(STATE + i)).collect();
+        let cubes: Vec<_> = (0..STATE_SIZE).map(|i| current(CUBES + i)).collect();
+        let with_constants: Vec<_> = (0..STATE_SIZE)
+            .map(|i| &state[i] + current(ROUND_CONSTANTS + i))
+            .collect();
+
+        let mut constraints: Vec<_> = cubes
+            .iter()
+            .zip(&with_constants)
+            .map(|(cube, value)| cube - value * value * value)
+            .collect();
+
+        // The partial rounds apply the S-box to the last element only
+        let partial_round_sbox = [
+            with_constants[0].clone(),
+            with_constants[1].clone(),
+            cubes[2].clone(),
+        ];
+        let mix = |row: &[FieldElement<F>; STATE_SIZE], values: &[Expr<F>]| {
+            row.iter()
+                .zip(values)
+                .map(|(coefficient, value)| Expr::constant(coefficient.clone()) * value)
+                .reduce(|acc, term| acc + term)
+                .unwrap()
+        };
+        for (i, row) in self.parameters.mds_matrix.iter().enumerate() {
+            let full_round = next(STATE + i) - mix(row, &cubes);
+            let partial_round = next(STATE + i) - mix(row, &partial_round_sbox);
+            constraints
+                .push(current(FULL_ROUND) * full_round + current(PARTIAL_ROUND) * partial_round);
+        }
+
+        let hash_end = current(HASH_END);
+        let direction = current(DIRECTION);
+        constraints.push(&hash_end * (next(NODE) - &state[0]));
+        constraints.push((&one - &hash_end) * (next(NODE) - current(NODE)));
+        constraints.push((&one - &hash_end) * (next(DIRECTION) - &direction));
+        constraints.push(&direction * (&direction - &one));
+
+        // The node is the left input of the hash, or the right one if the direction
+        // is one. The other input is the sibling.
+        let hash_start = current(HASH_START);
+        let input = &state[0] + &direction * (&state[1] - &state[0]);
+        constraints.push(&hash_start * (input - current(NODE)));
+        constraints.push(&hash_start * (&state[2] - Expr::constant(FieldElement::from(2))));
+
+        constraints.extend(FIXED_COLUMNS.map(|col| cell(2, col) - current(col)));
+        constraints
+    }
+
+    /// Exemptions of the constraints, in the order of `transition_constraints`.
+    pub fn transition_exemptions(&self) -> Vec<usize> {
+        let mut exemptions = vec![0; STATE_SIZE];
+        exemptions.extend([1; STATE_SIZE]);
+        exemptions.extend([1, 1, 1, 0, 0, 0]);
+        exemptions.extend(FIXED_COLUMNS.map(|_| self.rows_per_hash()));
+        exemptions
+    }
+
+    /// Fixes the columns that repeat every hash, the leaf and the root of a path of
+    /// `depth` levels.
+    pub fn boundary_constraints(
+        &self,
+        leaf: &FieldElement<F>,
+        root: &FieldElement<F>,
+        depth: usize,
+    ) -> Vec<BoundaryConstraint<F>> {
+        let mut constraints: Vec<_> = (0..self.rows_per_hash())
+            .flat_map(|step| {
+                FIXED_COLUMNS
+                    .zip(self.fixed_row(step))
+                    .map(move |(col, value)| {
+                        BoundaryConstraint::new(self.first_column + col, step, value)
+                    })
+            })
+            .collect();
+        constraints.push(BoundaryConstraint::new(
+            self.first_column + NODE,
+            0,
+            leaf.clone(),
+        ));
+        constraints.push(BoundaryConstraint::new(
+            self.first_column + STATE,
+            self.root_step(depth),
+            root.clone(),
+        ));
+        constraints
+    }
+
+    /// Root of the tree with the leaf at `index` and the path of `siblings`, from
+    /// the leaf up.
+    pub fn root(
+        &self,
+        leaf: &FieldElement<F>,
+        index: usize,
+        siblings: &[FieldElement<F>],
+    ) -> FieldElement<F> {
+        siblings
+            .iter()
+            .enumerate()
+            .fold(leaf.clone(), |node, (level, sibling)| {
+                if level_direction(index, level) == 0 {
+                    self.parameters.hash(&node, sibling)
+                } else {
+                    self.parameters.hash(sibling, &node)
+                }
+            })
+    }
+
+    /// Columns of the gadget for the leaf at `index` and the path of `siblings`,
+    /// with `trace_length` rows.
+    pub fn trace_columns(
+        &self,
+        leaf: &FieldElement<F>,
+        index: usize,
+        siblings: &[FieldElement<F>],
+        trace_length: usize,
+    ) -> Vec<Vec<FieldElement<F>>> {
+        let mut columns = vec![Vec::with_capacity(trace_length); NUM_COLUMNS];
+        let mut node = leaf.clone();
+        let mut level = 0;
+        while columns[0].len() < trace_length {
+            let (sibling, direction) = match siblings.get(level) {
+                Some(sibling) => (sibling.clone(), level_direction(index, level)),
+                None => (FieldElement::zero(), 0),
+            };
+            let mut state = if direction == 0 {
+                [node.clone(), sibling, FieldElement::from(2)]
+            } else {
+                [sibling, node.clone(), FieldElement::from(2)]
+            };
+
+            for step in 0..self.rows_per_hash() {
+                let fixed = self.fixed_row(step);
+                for i in 0..STATE_SIZE {
+                    columns[STATE + i].push(state[i].clone());
+                    columns[CUBES + i].push((&state[i] + &fixed[i]).pow(3_u64));
+                }
+                for (col, value) in FIXED_COLUMNS.zip(fixed) {
+                    columns[col].push(value);
+                }
+                columns[NODE].push(node.clone());
+                columns[DIRECTION].push(FieldElement::from(direction as u64));
+
+                if step < self.parameters.n_rounds() {
+                    self.parameters.apply_round(&mut state, step);
+                }
+            }
+
+            node = state[0].clone();
+            level += 1;
+        }
+
+        for column in columns.iter_mut() {
+            column.truncate(trace_length);
+        }
+        columns
+    }
+
+    /// Values of the fixed columns in the row `step` of every hash.
+    fn fixed_row(&self, step: usize) -> Vec<FieldElement<F>> {
+        let n_rounds = self.parameters.n_rounds();
+        let flag = |value: bool| {
+            if value {
+                FieldElement::one()
+            } else {
+                FieldElement::zero()
+            }
+        };
+
+        let mut row = match self.parameters.round_constants.get(step) {
+            Some(constants) if step < n_rounds => constants.to_vec(),
+            _ => vec![FieldElement::zero(); STATE_SIZE],
+        };
+        let is_round = step < n_rounds;
+        row.push(flag(is_round && self.parameters.is_full_round(step)));
+        row.push(flag(is_round && !self.parameters.is_full_round(step)));
+        row.push(flag(step == 0));
+        row.push(flag(step == n_rounds));
+        row
+    }
+}
+
+/// Bit of `level` in `index`: zero if the node of the level is a left child.
+fn level_direction(index: usize, level: usize) -> usize {
+    index.checked_shr(level as u32).unwrap_or(0) & 1
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+    use crate::starks::{
+        constraints::compiled::CompiledConstraints, frame::Frame, trace::TraceTable,
+    };
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    fn gadget() -> PoseidonMerklePathGadget<Stark252PrimeField> {
+        PoseidonMerklePathGadget::new(PoseidonParameters::starknet(), 0, [0, 1, 2])
+    }
+
+    #[test]
+    fn the_trace_satisfies_the_constraints() {
+        let gadget = gadget();
+        let leaf = FE::from(7);
+        let siblings = [FE::from(11), FE::from(13), FE::from(17)];
+        let index = 0b101;
+        let trace_length = gadget.trace_length(siblings.len());
+
+        let trace =
+            TraceTable::new_from_cols(&gadget.trace_columns(&leaf, index, &siblings, trace_length));
+        let constraints = CompiledConstraints::new(gadget.transition_constraints());
+        let exemptions = gadget.transition_exemptions();
+        assert_eq!(constraints.num_constraints(), CONSTRAINT_NAMES.len());
+        assert_eq!(exemptions.len(), CONSTRAINT_NAMES.len());
+
+        let offsets = gadget.transition_offsets();
+        for step in 0..trace_length {
+            let frame = Frame::read_from_trace(&trace, step, 1, &offsets);
+            for (i, evaluation) in constraints.evaluate(&frame, &[]).iter().enumerate() {
+                if step < trace_length - exemptions[i] {
+                    assert_eq!(
+                        evaluation,
+                        &FE::zero(),
+                        "{} at step {step}",
+                        CONSTRAINT_NAMES[i]
+                    );
+                }
+            }
+        }
+
+        let root = gadget.root(&leaf, index, &siblings);
+        for constraint in gadget.boundary_constraints(&leaf, &root, siblings.len()) {
+            assert_eq!(trace.get(constraint.step, constraint.col), constraint.value);
+        }
+    }
+
+    #[test]
+    fn root_hashes_each_node_on_the_side_of_its_direction() {
+        let gadget = gadget();
+        let parameters = PoseidonParameters::starknet();
+        let (leaf, siblings) = (FE::from(1), [FE::from(2), FE::from(3)]);
+
+        let left_then_right = parameters.hash(&siblings[1], &parameters.hash(&leaf, &siblings[0]));
+        assert_eq!(gadget.root(&leaf, 0b10, &siblings), left_then_right);
+    }
+}
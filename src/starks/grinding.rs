@@ -1,5 +1,18 @@
+use std::time::{Duration, Instant};
+
 use sha3::{Digest, Keccak256};
 
+use super::proof::options::GrindingConfig;
+
+/// The work done by the prover to find the nonce of a proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GrindingWork {
+    pub nonce: u64,
+    /// Number of nonces hashed, including the one found. Zero without grinding.
+    pub attempts: u64,
+    pub elapsed: Duration,
+}
+
 /// Build data with the concatenation of transcript hash and value.
 /// Computes the hash of this element and returns the number of
 /// leading zeros in the resulting value (in the big-endian representation).
@@ -47,10 +60,41 @@ pub fn generate_nonce_with_grinding(
     })
 }
 
+/// Finds the nonce of a proof for the grinding `config`, measuring the work it
+/// takes. Without grinding, the nonce is zero and nothing is hashed.
+pub fn grind(transcript_challenge: &[u8; 32], config: GrindingConfig) -> Option<GrindingWork> {
+    let start = Instant::now();
+    let nonce = match config {
+        GrindingConfig::Disabled => 0,
+        GrindingConfig::Bits(bits) => generate_nonce_with_grinding(transcript_challenge, bits)?,
+    };
+    // The candidates are tried in order from zero
+    let attempts = if config.is_enabled() { nonce + 1 } else { 0 };
+
+    Some(GrindingWork {
+        nonce,
+        attempts,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Whether `nonce` is a valid one for the grinding `config`. Without grinding only
+/// a zero nonce is, so each proof has a single encoding.
+pub fn is_valid_nonce(transcript_challenge: &[u8; 32], nonce: u64, config: GrindingConfig) -> bool {
+    match config {
+        GrindingConfig::Disabled => nonce == 0,
+        GrindingConfig::Bits(bits) => {
+            hash_transcript_with_int_and_get_leading_zeros(transcript_challenge, nonce) >= bits
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use sha3::{Digest, Keccak256};
 
+    use crate::starks::proof::options::GrindingConfig;
+
     #[test]
     fn hash_transcript_with_int_and_get_leading_zeros_works() {
         let transcript_challenge = [
@@ -75,4 +119,25 @@ mod test {
 
         assert!(trailing_zeors >= grinding_factor);
     }
+
+    #[test]
+    fn grinding_work_counts_the_hashed_nonces() {
+        let transcript_challenge = [7; 32];
+
+        let work = super::grind(&transcript_challenge, GrindingConfig::Bits(8)).unwrap();
+        assert_eq!(work.attempts, work.nonce + 1);
+        assert!(super::is_valid_nonce(
+            &transcript_challenge,
+            work.nonce,
+            GrindingConfig::Bits(8)
+        ));
+
+        let work = super::grind(&transcript_challenge, GrindingConfig::Disabled).unwrap();
+        assert_eq!((work.nonce, work.attempts), (0, 0));
+        assert!(!super::is_valid_nonce(
+            &transcript_challenge,
+            1,
+            GrindingConfig::Disabled
+        ));
+    }
 }
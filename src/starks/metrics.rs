@@ -0,0 +1,224 @@
+//! Metrics of the stages of the prover: the time each round takes and the peak
+//! memory it uses, to choose the size of the machines that run the prover.
+//!
+//! The peak of the allocated bytes is measured by `PeakAllocator`, with the
+//! `metrics-alloc` feature, which the binary must install as its global allocator:
+//!
+//!     #[global_allocator]
+//!     static ALLOCATOR: PeakAllocator = PeakAllocator::system();
+//!
+//! Both the allocated bytes and the resident set size are of the whole process, so
+//! the metrics of provers running at the same time include each other's memory.
+
+use std::time::{Duration, Instant};
+
+use super::grinding::GrindingWork;
+
+#[cfg(feature = "metrics-alloc")]
+pub use allocator::PeakAllocator;
+
+/// Metrics of a stage of the prover.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StageMetrics {
+    pub name: &'static str,
+    pub elapsed: Duration,
+    /// Most bytes allocated at once during the stage, including the ones allocated
+    /// before it and still in use. `None` if `PeakAllocator` isn't the global
+    /// allocator.
+    pub peak_allocated_bytes: Option<usize>,
+    /// Peak resident set size of the process up to the end of the stage. `None`
+    /// outside Linux.
+    pub peak_rss_bytes: Option<usize>,
+}
+
+/// Metrics of the stages of a proof, in the order they were run. See
+/// `prove_with_metrics`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProverMetrics {
+    pub stages: Vec<StageMetrics>,
+    /// The work it took to find the nonce of the proof, once round 4 finished.
+    pub grinding: Option<GrindingWork>,
+}
+
+impl ProverMetrics {
+    pub fn stage(&self, name: &str) -> Option<&StageMetrics> {
+        self.stages.iter().find(|stage| stage.name == name)
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.stages.iter().map(|stage| stage.elapsed).sum()
+    }
+
+    /// Most bytes allocated at once over all the stages.
+    pub fn peak_allocated_bytes(&self) -> Option<usize> {
+        self.stages
+            .iter()
+            .filter_map(|stage| stage.peak_allocated_bytes)
+            .max()
+    }
+
+    /// Peak resident set size of the process at the end of the proof.
+    pub fn peak_rss_bytes(&self) -> Option<usize> {
+        self.stages
+            .iter()
+            .filter_map(|stage| stage.peak_rss_bytes)
+            .max()
+    }
+
+    /// Runs `f` as the stage `name` and records its metrics.
+    pub(crate) fn measure<R>(&mut self, name: &'static str, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "metrics-alloc")]
+        allocator::reset_peak();
+        let timer = Instant::now();
+        let result = f();
+        self.stages.push(StageMetrics {
+            name,
+            elapsed: timer.elapsed(),
+            peak_allocated_bytes: peak_allocated_bytes(),
+            peak_rss_bytes: peak_rss_bytes(),
+        });
+        result
+    }
+}
+
+#[cfg(feature = "metrics-alloc")]
+fn peak_allocated_bytes() -> Option<usize> {
+    allocator::peak_bytes()
+}
+
+#[cfg(not(feature = "metrics-alloc"))]
+fn peak_allocated_bytes() -> Option<usize> {
+    None
+}
+
+/// The high water mark of the resident set size, `VmHWM` in `/proc/self/status`.
+fn peak_rss_bytes() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(feature = "metrics-alloc")]
+mod allocator {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+    static PEAK: AtomicUsize = AtomicUsize::new(0);
+    static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+    /// Global allocator that keeps the count of the allocated bytes and their peak,
+    /// allocating with `inner`.
+    pub struct PeakAllocator<A = System> {
+        inner: A,
+    }
+
+    impl PeakAllocator<System> {
+        pub const fn system() -> Self {
+            Self::new(System)
+        }
+    }
+
+    impl<A> PeakAllocator<A> {
+        pub const fn new(inner: A) -> Self {
+            Self { inner }
+        }
+    }
+
+    fn allocated(size: usize) {
+        let allocated = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+        INSTALLED.store(true, Ordering::Relaxed);
+    }
+
+    fn deallocated(size: usize) {
+        ALLOCATED.fetch_sub(size, Ordering::Relaxed);
+    }
+
+    unsafe impl<A: GlobalAlloc> GlobalAlloc for PeakAllocator<A> {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = self.inner.alloc(layout);
+            if !ptr.is_null() {
+                allocated(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = self.inner.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                allocated(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.inner.dealloc(ptr, layout);
+            deallocated(layout.size());
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = self.inner.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                deallocated(layout.size());
+                allocated(new_size);
+            }
+            new_ptr
+        }
+    }
+
+    /// Starts a new peak from the bytes allocated now.
+    pub(super) fn reset_peak() {
+        PEAK.store(ALLOCATED.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    /// The peak since the last `reset_peak`, if the allocator is installed.
+    pub(super) fn peak_bytes() -> Option<usize> {
+        INSTALLED
+            .load(Ordering::Relaxed)
+            .then(|| PEAK.load(Ordering::Relaxed))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn the_peak_counts_the_bytes_allocated_at_once() {
+            let allocator = PeakAllocator::system();
+            let layout = Layout::from_size_align(1 << 20, 8).unwrap();
+
+            reset_peak();
+            unsafe {
+                let ptr = allocator.alloc(layout);
+                allocator.dealloc(ptr, layout);
+            }
+            // Other tests may be allocating at the same time with the allocator
+            assert!(peak_bytes().unwrap() >= 1 << 20);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages_are_recorded_in_order() {
+        let mut metrics = ProverMetrics::default();
+        let vector = metrics.measure("allocate", || vec![0u8; 1 << 16]);
+        metrics.measure("drop", || drop(vector));
+
+        let names: Vec<_> = metrics.stages.iter().map(|stage| stage.name).collect();
+        assert_eq!(names, ["allocate", "drop"]);
+        assert_eq!(
+            metrics.total_time(),
+            metrics.stages[0].elapsed + metrics.stages[1].elapsed
+        );
+        assert!(metrics.stage("allocate").is_some());
+        if cfg!(target_os = "linux") {
+            assert!(metrics.peak_rss_bytes().unwrap() > 0);
+        }
+    }
+}
@@ -1,19 +1,39 @@
+#[cfg(feature = "blake3")]
+pub mod blake3_backend;
+pub mod checkpoint;
 pub mod constraints;
 pub mod context;
-#[cfg(debug_assertions)]
 pub mod debug;
+#[cfg(feature = "mmap")]
+pub mod disk_merkle;
 pub mod domain;
 pub mod example;
 pub mod frame;
 pub mod fri;
+pub mod gadgets;
 pub mod grinding;
+pub mod metrics;
+pub mod plan;
+pub mod poseidon_backend;
+pub mod poseidon_transcript;
 pub mod proof;
 pub mod prover;
+pub mod prover_handle;
+pub mod public_coin;
+pub mod randomization;
+pub mod roots_of_unity;
+pub mod salt;
+#[cfg(feature = "sharded-trace")]
+pub mod sharded_trace;
 pub mod trace;
 pub mod traits;
 pub mod transcript;
 pub mod utils;
+pub mod vector_commitment;
 pub mod verifier;
+pub mod virtual_columns;
+#[cfg(feature = "winterfell")]
+pub mod winterfell;
 
 /// Configurations of the Prover available in compile time
 pub mod config;
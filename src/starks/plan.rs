@@ -0,0 +1,154 @@
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    traits::ByteConversion,
+};
+
+use super::config::{COMMITMENT_SIZE, SALT_SIZE};
+use super::domain::lde_domain_size;
+use super::traits::AIR;
+
+/// What proving a trace of an AIR takes with its options, known without running the
+/// prover, so services can schedule and price the proofs beforehand.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProvingPlan {
+    /// Length of the trace, padded to a power of two.
+    pub trace_length: usize,
+    pub blowup_factor: usize,
+    pub lde_domain_size: usize,
+    pub fri_number_of_layers: usize,
+    pub fri_last_poly_length: usize,
+    /// Commitments sent in the proof: the ones of the trace, the one of the
+    /// composition polynomial and the ones of the FRI layers.
+    pub num_commitments: usize,
+    /// Rough size in bytes of the serialized proof: the commitments, evaluations,
+    /// openings and paths it carries, without the lengths prefixed by the
+    /// serialization. Every query is counted, even the repeated ones.
+    pub estimated_proof_size: usize,
+}
+
+impl ProvingPlan {
+    /// The plan of a proof of a trace of `air.trace_length()` rows, or `None` if the
+    /// field has no LDE domain for it, in which case the prover would fail.
+    pub fn for_air<F, A>(air: &A) -> Option<Self>
+    where
+        F: IsFFTField,
+        FieldElement<F>: ByteConversion,
+        A: AIR<Field = F>,
+    {
+        let options = air.options();
+        let trace_length = air.trace_length();
+        let blowup_factor = options.blowup_factor as usize;
+        let lde_domain_size = lde_domain_size::<F>(trace_length, options.blowup_factor)?;
+        let fri_number_of_layers = options.fri_number_of_layers(trace_length);
+        let fri_last_poly_length = options.fri_last_poly_length(trace_length);
+
+        // The auxiliary columns of each round are committed apart from the main ones
+        let num_committed_columns = air.committed_columns().len();
+        let num_auxiliary_columns = air.number_auxiliary_rap_columns();
+        let num_trace_commitments = (0..air.number_of_rap_rounds())
+            .map(|round| air.number_auxiliary_rap_columns_in_round(round))
+            .chain([num_committed_columns - num_auxiliary_columns])
+            .map(|num_columns| options.commitment_layout.column_groups(num_columns).len())
+            .sum::<usize>();
+        let num_commitments = num_trace_commitments + 1 + fri_number_of_layers;
+
+        let felt_size = FieldElement::<F>::zero().to_bytes_be().len();
+        let cap_height = options.merkle_cap_height;
+        let depth = lde_domain_size.trailing_zeros() as usize;
+        let cap_size = |depth: usize| (1 << cap_height.min(depth)) * COMMITMENT_SIZE;
+        let path_size = |depth: usize| depth.saturating_sub(cap_height) * COMMITMENT_SIZE;
+        let num_composition_polys = 2 + usize::from(options.randomizes_trace());
+        let num_salted_commitments = if options.salt_commitments {
+            num_trace_commitments + 1
+        } else {
+            0
+        };
+
+        let caps_size = (num_trace_commitments + 1) * cap_size(depth)
+            + (0..fri_number_of_layers)
+                .map(|k| cap_size(depth - k))
+                .sum::<usize>();
+        let ood_size = (air.context().transition_offsets.len() * num_committed_columns
+            + num_composition_polys
+            + 2 * usize::from(air.composition_poly_extra_opening().is_some()))
+            * felt_size;
+        // Every layer is opened at −𝜐ₛ, and every layer but the first one at 𝜐ₛ
+        let query_size = (num_committed_columns + num_composition_polys) * felt_size
+            + (num_trace_commitments + 1) * path_size(depth)
+            + num_salted_commitments * SALT_SIZE
+            + (0..fri_number_of_layers)
+                .map(|k| (felt_size + path_size(depth - k)) * if k == 0 { 1 } else { 2 })
+                .sum::<usize>();
+        let estimated_proof_size = caps_size
+            + ood_size
+            + fri_last_poly_length * felt_size
+            + options.fri_number_of_queries * query_size
+            // Nonce of the grinding
+            + 8;
+
+        Some(Self {
+            trace_length,
+            blowup_factor,
+            lde_domain_size,
+            fri_number_of_layers,
+            fri_last_poly_length,
+            num_commitments,
+            estimated_proof_size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::{
+        field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+        traits::Serializable,
+    };
+
+    use super::*;
+    use crate::{
+        starks::{
+            example::simple_fibonacci::{self, FibonacciAIR, FibonacciPublicInputs},
+            proof::options::{CommitmentLayout, ProofOptions},
+            prover::prove,
+        },
+        FE,
+    };
+
+    type F = Stark252PrimeField;
+
+    #[test]
+    fn the_plan_matches_the_proof() {
+        let trace = simple_fibonacci::fibonacci_trace([FE::one(), FE::one()], 64);
+        let proof_options = ProofOptions {
+            commitment_layout: CommitmentLayout::ColumnGrouped { group_size: 1 },
+            merkle_cap_height: 2,
+            ..ProofOptions::default_test_options()
+        };
+        let pub_inputs = FibonacciPublicInputs {
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+        let air = FibonacciAIR::new(trace.n_rows(), &pub_inputs, &proof_options);
+
+        let plan = ProvingPlan::for_air(&air).unwrap();
+        let proof = prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+
+        assert_eq!(plan.trace_length, 64);
+        assert_eq!(plan.lde_domain_size, 64 * 4);
+        assert_eq!(
+            plan.fri_number_of_layers,
+            proof.fri_layers_merkle_caps.len()
+        );
+        assert_eq!(plan.fri_last_poly_length, proof.fri_last_poly.len());
+        assert_eq!(
+            plan.num_commitments,
+            proof.lde_trace_merkle_caps.len() + 1 + proof.fri_layers_merkle_caps.len()
+        );
+
+        // The estimate leaves out the lengths prefixed by the serialization
+        let proof_size = proof.serialize().len();
+        assert!(plan.estimated_proof_size <= proof_size);
+        assert!(plan.estimated_proof_size >= proof_size / 2);
+    }
+}
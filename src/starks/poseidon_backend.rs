@@ -0,0 +1,129 @@
+//! Merkle tree backends hashing with the Poseidon of Starknet, for
+//! `CommitmentHash::Poseidon`. Hashing field elements instead of bytes, the trees
+//! are cheap to check in Cairo, which makes them the choice for proofs verified
+//! recursively.
+//!
+//! A leaf is hashed with `hash_many` from its elements, and a parent with `hash`
+//! from its children. The nodes are Stark252 elements, stored as their big-endian
+//! bytes. The elements of other fields are taken as the Stark252 elements with the
+//! same big-endian bytes, so the fields must not be larger than Stark252.
+
+use std::marker::PhantomData;
+
+use lambdaworks_crypto::merkle_tree::traits::IsMerkleTreeBackend;
+use lambdaworks_math::{
+    field::{
+        element::FieldElement, fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+        traits::IsField,
+    },
+    traits::ByteConversion,
+};
+
+use super::{
+    config::{Commitment, COMMITMENT_SIZE},
+    gadgets::poseidon::PoseidonParameters,
+};
+
+type Felt = FieldElement<Stark252PrimeField>;
+
+/// Backend of the trees whose leaves are field elements.
+#[derive(Clone)]
+pub struct PoseidonTree<F> {
+    phantom: PhantomData<F>,
+}
+
+impl<F> Default for PoseidonTree<F> {
+    fn default() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<F> IsMerkleTreeBackend for PoseidonTree<F>
+where
+    F: IsField,
+    FieldElement<F>: ByteConversion,
+{
+    type Node = Commitment;
+    type Data = FieldElement<F>;
+
+    fn hash_data(input: &FieldElement<F>) -> Commitment {
+        hash_leaf(std::slice::from_ref(input))
+    }
+
+    fn hash_new_parent(left: &Commitment, right: &Commitment) -> Commitment {
+        hash_parent(left, right)
+    }
+}
+
+/// Backend of the trees whose leaves are vectors of field elements.
+#[derive(Clone)]
+pub struct BatchPoseidonTree<F> {
+    phantom: PhantomData<F>,
+}
+
+impl<F> Default for BatchPoseidonTree<F> {
+    fn default() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<F> IsMerkleTreeBackend for BatchPoseidonTree<F>
+where
+    F: IsField,
+    FieldElement<F>: ByteConversion,
+{
+    type Node = Commitment;
+    type Data = Vec<FieldElement<F>>;
+
+    fn hash_data(input: &Vec<FieldElement<F>>) -> Commitment {
+        hash_leaf(input)
+    }
+
+    fn hash_new_parent(left: &Commitment, right: &Commitment) -> Commitment {
+        hash_parent(left, right)
+    }
+}
+
+/// The Stark252 element with the big-endian bytes of `element`.
+pub fn felt_from_element<F>(element: &FieldElement<F>) -> Felt
+where
+    F: IsField,
+    FieldElement<F>: ByteConversion,
+{
+    let bytes = element.to_bytes_be();
+    let bytes = &bytes[bytes.len().saturating_sub(COMMITMENT_SIZE)..];
+    let mut padded_bytes = [0; COMMITMENT_SIZE];
+    padded_bytes[COMMITMENT_SIZE - bytes.len()..].copy_from_slice(bytes);
+    Felt::from_bytes_be(&padded_bytes).expect("the bytes fit in a Stark252 element")
+}
+
+pub fn felt_from_node(node: &Commitment) -> Felt {
+    Felt::from_bytes_be(node).expect("nodes have the size of a Stark252 element")
+}
+
+pub fn node_from_felt(felt: &Felt) -> Commitment {
+    felt.to_bytes_be()
+        .try_into()
+        .expect("Stark252 elements have the size of a node")
+}
+
+fn hash_leaf<F>(leaf: &[FieldElement<F>]) -> Commitment
+where
+    F: IsField,
+    FieldElement<F>: ByteConversion,
+{
+    let leaf: Vec<_> = leaf.iter().map(felt_from_element).collect();
+    node_from_felt(&PoseidonParameters::with_starknet(|params| {
+        params.hash_many(&leaf)
+    }))
+}
+
+fn hash_parent(left: &Commitment, right: &Commitment) -> Commitment {
+    node_from_felt(&PoseidonParameters::with_starknet(|params| {
+        params.hash(&felt_from_node(left), &felt_from_node(right))
+    }))
+}
@@ -0,0 +1,119 @@
+//! Fiat-Shamir transcript over the Poseidon of Starknet, cheap to replay in Cairo.
+//! See `prove_recursive_friendly`.
+
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::{
+    field::{
+        element::FieldElement, fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+    },
+    traits::ByteConversion,
+};
+
+use super::gadgets::poseidon::{PoseidonParameters, RATE, STATE_SIZE};
+
+type Felt = FieldElement<Stark252PrimeField>;
+
+/// Number of bytes of the words the appended data is split in.
+const WORD_SIZE: usize = 32;
+
+/// A duplex sponge over the Poseidon permutation of Starknet.
+///
+/// The data of each `append` is absorbed as its length in bytes, followed by the
+/// 32-byte words it's split in, the last one padded with zeros at the end. Each
+/// word is taken as a big-endian Stark252 element, so field elements and Poseidon
+/// commitments, the data of the proofs, are absorbed as they are. Other words not
+/// smaller than the modulus are reduced.
+///
+/// A challenge permutes the state with the elements absorbed since the previous
+/// permutation, and is the first element of the new state.
+#[derive(Clone, Debug)]
+pub struct PoseidonTranscript {
+    state: [Felt; STATE_SIZE],
+    // Elements absorbed since the last permutation, at most `RATE` of them
+    pending: Vec<Felt>,
+}
+
+impl PoseidonTranscript {
+    pub fn new() -> Self {
+        Self {
+            state: [Felt::zero(), Felt::zero(), Felt::zero()],
+            pending: Vec::with_capacity(RATE),
+        }
+    }
+
+    fn absorb(&mut self, element: Felt) {
+        if self.pending.len() == RATE {
+            self.permute();
+        }
+        self.pending.push(element);
+    }
+
+    fn permute(&mut self) {
+        for (element, value) in self.state.iter_mut().zip(self.pending.drain(..)) {
+            *element = &*element + value;
+        }
+        PoseidonParameters::with_starknet(|params| params.permute(&mut self.state));
+    }
+}
+
+impl Default for PoseidonTranscript {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transcript for PoseidonTranscript {
+    fn append(&mut self, new_data: &[u8]) {
+        self.absorb(Felt::from(new_data.len() as u64));
+        for word in new_data.chunks(WORD_SIZE) {
+            let mut padded_word = [0; WORD_SIZE];
+            padded_word[..word.len()].copy_from_slice(word);
+            self.absorb(Felt::from_bytes_be(&padded_word).expect("words have 32 bytes"));
+        }
+    }
+
+    fn challenge(&mut self) -> [u8; 32] {
+        self.permute();
+        self.state[0]
+            .to_bytes_be()
+            .try_into()
+            .expect("Stark252 elements have 32 bytes")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenges_depend_on_how_the_data_is_split() {
+        let mut transcript = PoseidonTranscript::new();
+        transcript.append(&[1, 2]);
+        let challenge = transcript.challenge();
+
+        let mut split_transcript = PoseidonTranscript::new();
+        split_transcript.append(&[1]);
+        split_transcript.append(&[2]);
+
+        assert_ne!(challenge, split_transcript.challenge());
+    }
+
+    #[test]
+    fn consecutive_challenges_are_different() {
+        let mut transcript = PoseidonTranscript::new();
+        transcript.append(&Felt::from(42).to_bytes_be());
+
+        assert_ne!(transcript.challenge(), transcript.challenge());
+    }
+
+    #[test]
+    fn field_elements_are_absorbed_as_they_are() {
+        let element = Felt::from_hex_unchecked("123456789");
+        let mut transcript = PoseidonTranscript::new();
+        transcript.append(&element.to_bytes_be());
+
+        let mut state = [Felt::from(32), element, Felt::zero()];
+        PoseidonParameters::starknet().permute(&mut state);
+        assert_eq!(transcript.challenge().to_vec(), state[0].to_bytes_be());
+    }
+}
@@ -0,0 +1,204 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use lambdaworks_math::{
+    errors::DeserializationError,
+    traits::{Deserializable, Serializable},
+};
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+use super::options::ProofOptions;
+use crate::starks::config::Commitment;
+
+/// Version of the crate that builds the proofs.
+pub const PROVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AttestationError {
+    #[error("The attestation was changed, or it's bound to another proof")]
+    Mismatch,
+}
+
+/// Keccak256 of the serialized options.
+pub fn options_digest(options: &ProofOptions) -> Commitment {
+    Keccak256::digest(options.serialize()).into()
+}
+
+/// Metadata of the build that made a proof, so the operators of the provers can
+/// audit which build made each proof and reject the ones of builds older than a
+/// soundness fix. It isn't absorbed by the transcript, so it doesn't change the
+/// proof, but its digest covers the metadata along with the hash of the proof, so
+/// an attestation can't be changed or moved to another proof without noticing.
+///
+/// The digest is a plain hash, not a signature: it only authenticates the metadata
+/// for whoever trusts where the proof comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attestation {
+    pub prover_version: String,
+    /// `AIR::VERSION` of the AIR of the proof.
+    pub air_version: u32,
+    pub options_digest: Commitment,
+    /// Seconds since the Unix epoch when the proof was attested.
+    pub timestamp: u64,
+    digest: Commitment,
+}
+
+impl Attestation {
+    /// Attests the proof of hash `proof_hash`, made now by this build with `options`.
+    pub fn new(air_version: u32, options: &ProofOptions, proof_hash: &Commitment) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self::with_metadata(
+            PROVER_VERSION.to_string(),
+            air_version,
+            options_digest(options),
+            timestamp,
+            proof_hash,
+        )
+    }
+
+    pub fn with_metadata(
+        prover_version: String,
+        air_version: u32,
+        options_digest: Commitment,
+        timestamp: u64,
+        proof_hash: &Commitment,
+    ) -> Self {
+        let mut attestation = Self {
+            prover_version,
+            air_version,
+            options_digest,
+            timestamp,
+            digest: [0; 32],
+        };
+        attestation.digest = attestation.compute_digest(proof_hash);
+        attestation
+    }
+
+    fn metadata_bytes(&self) -> Vec<u8> {
+        let mut bytes = (self.prover_version.len() as u64).to_be_bytes().to_vec();
+        bytes.extend(self.prover_version.as_bytes());
+        bytes.extend(self.air_version.to_be_bytes());
+        bytes.extend(self.options_digest);
+        bytes.extend(self.timestamp.to_be_bytes());
+        bytes
+    }
+
+    fn compute_digest(&self, proof_hash: &Commitment) -> Commitment {
+        let mut hasher = Keccak256::new();
+        hasher.update(proof_hash);
+        hasher.update(self.metadata_bytes());
+        hasher.finalize().into()
+    }
+
+    pub fn digest(&self) -> &Commitment {
+        &self.digest
+    }
+
+    /// Checks that the metadata is the one attested for the proof of hash `proof_hash`.
+    pub fn check(&self, proof_hash: &Commitment) -> Result<(), AttestationError> {
+        if self.compute_digest(proof_hash) != self.digest {
+            return Err(AttestationError::Mismatch);
+        }
+        Ok(())
+    }
+
+    pub fn has_options(&self, options: &ProofOptions) -> bool {
+        self.options_digest == options_digest(options)
+    }
+
+    /// Whether the proof was made by the version `minimum_version` of the prover or
+    /// a later one. Versions are compared by their numeric components, so
+    /// pre-release suffixes are ignored.
+    pub fn prover_version_at_least(&self, minimum_version: &str) -> bool {
+        fn components(version: &str) -> Vec<u64> {
+            version
+                .split(['.', '-', '+'])
+                .map_while(|component| component.parse().ok())
+                .collect()
+        }
+        components(&self.prover_version) >= components(minimum_version)
+    }
+}
+
+impl Serializable for Attestation {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = self.metadata_bytes();
+        bytes.extend(self.digest);
+        bytes
+    }
+}
+
+fn split_bytes(bytes: &[u8], len: usize) -> Result<(&[u8], &[u8]), DeserializationError> {
+    if bytes.len() < len {
+        return Err(DeserializationError::InvalidAmountOfBytes);
+    }
+    Ok(bytes.split_at(len))
+}
+
+impl Deserializable for Attestation {
+    fn deserialize(bytes: &[u8]) -> Result<Self, DeserializationError>
+    where
+        Self: Sized,
+    {
+        let (version_len, bytes) = split_bytes(bytes, 8)?;
+        let version_len = u64::from_be_bytes(version_len.try_into().unwrap());
+        let version_len =
+            usize::try_from(version_len).map_err(|_| DeserializationError::InvalidAmountOfBytes)?;
+        let (prover_version, bytes) = split_bytes(bytes, version_len)?;
+        let prover_version = String::from_utf8(prover_version.to_vec())
+            .map_err(|_| DeserializationError::InvalidValue)?;
+        let (air_version, bytes) = split_bytes(bytes, 4)?;
+        let (options_digest, bytes) = split_bytes(bytes, 32)?;
+        let (timestamp, digest) = split_bytes(bytes, 8)?;
+        if digest.len() != 32 {
+            return Err(DeserializationError::InvalidAmountOfBytes);
+        }
+
+        Ok(Self {
+            prover_version,
+            air_version: u32::from_be_bytes(air_version.try_into().unwrap()),
+            options_digest: options_digest.try_into().unwrap(),
+            timestamp: u64::from_be_bytes(timestamp.try_into().unwrap()),
+            digest: digest.try_into().unwrap(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attestations_are_bound_to_their_metadata_and_proof() {
+        let options = ProofOptions::default_test_options();
+        let proof_hash = [1; 32];
+        let attestation = Attestation::new(2, &options, &proof_hash);
+
+        assert_eq!(attestation.prover_version, PROVER_VERSION);
+        assert!(attestation.has_options(&options));
+        assert_eq!(attestation.check(&proof_hash), Ok(()));
+        assert_eq!(attestation.check(&[2; 32]), Err(AttestationError::Mismatch));
+
+        let mut older_build = attestation.clone();
+        older_build.prover_version = "0.0.1".to_string();
+        assert_eq!(
+            older_build.check(&proof_hash),
+            Err(AttestationError::Mismatch)
+        );
+
+        let deserialized = Attestation::deserialize(&attestation.serialize()).unwrap();
+        assert_eq!(deserialized, attestation);
+    }
+
+    #[test]
+    fn prover_versions_are_compared_by_their_components() {
+        let attestation = Attestation::with_metadata("0.10.2".to_string(), 1, [0; 32], 0, &[0; 32]);
+
+        assert!(attestation.prover_version_at_least("0.10.2"));
+        assert!(attestation.prover_version_at_least("0.9.7"));
+        assert!(!attestation.prover_version_at_least("0.10.3"));
+        assert!(!attestation.prover_version_at_least("1.0.0"));
+    }
+}
@@ -6,4 +6,28 @@ pub enum InsecureOptionError {
     FieldSize,
     #[error("The number of security bits is not large enough")]
     SecurityBits,
+    #[error("The blowup factor must be a power of two")]
+    BlowupFactor,
+    #[error("The blowup factor must be at most 255")]
+    BlowupFactorTooLarge,
+    #[error("FRI needs at least one layer")]
+    FriMaxLayers,
+    #[error("Column groups of the commitment layout must not be empty")]
+    EmptyColumnGroups,
+    #[error("The coset offset must not be in the LDE domain")]
+    CosetOffset,
+    #[error("The number of queries is larger than the verifier accepts")]
+    TooManyQueries,
+    #[error("Grinding of zero bits must be disabled with `GrindingConfig::Disabled`")]
+    ZeroGrindingBits,
+    #[error("Proofs without grinding are not accepted")]
+    GrindingDisabled,
+    #[error("The trace is longer than the verifier accepts")]
+    TraceTooLong,
+}
+
+#[derive(Debug, Error)]
+pub enum FeltExportError {
+    #[error("A commitment is not a field element, so it was not hashed with Poseidon")]
+    NonFeltCommitment,
 }
@@ -0,0 +1,189 @@
+//! Encoding of the proofs over the Stark252 field as arrays of field elements, the
+//! input of a verifier written in Cairo.
+//!
+//! The fields of the proof are written in the order of `StarkProof`, each list
+//! after its length:
+//!
+//! - the trace length and the caps of the commitments of the trace,
+//! - the number of rows and columns of the out-of-domain frame, and its
+//!   evaluations row by row,
+//! - the cap of the commitment of the composition polynomial and H₁(z²) and H₂(z²),
+//! - a one followed by M(z²) if the DEEP composition polynomial is masked, else a zero,
+//! - a one followed by H₁(w) and H₂(w) if the AIR opens them at an extra point w,
+//!   else a zero,
+//! - the caps of the commitments of the FRI layers and the coefficients of the last
+//!   one,
+//! - the FRI decommitments, each one the symmetric Merkle paths and evaluations
+//!   followed by the other ones,
+//! - the DEEP openings, each one the Merkle path of the composition polynomial,
+//!   H₁(𝜐₀), H₂(𝜐₀), M(𝜐₀) as the mask above, its salt as the mask, the Merkle paths
+//!   of the trace, its evaluations and its salts,
+//! - the nonce of the grinding.
+//!
+//! A cap is written as the list of its nodes. A salt is written as the four elements
+//! it's hashed as in the leaves, see `salted_leaf`. The options aren't written, as a
+//! verifier has them fixed.
+
+use lambdaworks_crypto::merkle_tree::proof::Proof;
+use lambdaworks_math::{
+    field::{
+        element::FieldElement, fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+    },
+    traits::ByteConversion,
+};
+
+use super::{
+    errors::FeltExportError,
+    stark::{DeepPolynomialOpenings, StarkProof},
+};
+use crate::starks::{
+    config::{Commitment, MerkleCap, Salt},
+    fri::fri_decommit::FriDecommitment,
+    poseidon_backend::felt_from_node,
+    salt::salted_leaf,
+};
+
+type Felt = FieldElement<Stark252PrimeField>;
+
+impl StarkProof<Stark252PrimeField> {
+    /// The proof as an array of field elements. Fails if its commitments weren't
+    /// hashed with `CommitmentHash::Poseidon`, as then they aren't field elements.
+    pub fn to_felts(&self) -> Result<Vec<Felt>, FeltExportError> {
+        let mut felts = FeltWriter(vec![]);
+
+        felts.write_usize(self.trace_length);
+        felts.write_caps(&self.lde_trace_merkle_caps)?;
+
+        let frame = &self.trace_ood_frame_evaluations;
+        felts.write_usize(frame.num_rows());
+        felts.write_usize(frame.num_columns());
+        for row in 0..frame.num_rows() {
+            felts.0.extend_from_slice(frame.get_row(row));
+        }
+
+        felts.write_commitments(&self.composition_poly_cap)?;
+        felts
+            .0
+            .push(self.composition_poly_even_ood_evaluation.clone());
+        felts
+            .0
+            .push(self.composition_poly_odd_ood_evaluation.clone());
+        felts.write_optional_felt(&self.composition_poly_mask_ood_evaluation);
+        match &self.composition_poly_extra_ood_evaluations {
+            Some((even, odd)) => {
+                felts.0.push(Felt::one());
+                felts.0.push(even.clone());
+                felts.0.push(odd.clone());
+            }
+            None => felts.0.push(Felt::zero()),
+        }
+
+        felts.write_caps(&self.fri_layers_merkle_caps)?;
+        felts.write_felts(&self.fri_last_poly);
+
+        felts.write_usize(self.query_list.len());
+        for decommitment in &self.query_list {
+            felts.write_fri_decommitment(decommitment)?;
+        }
+
+        felts.write_usize(self.deep_poly_openings.len());
+        for openings in &self.deep_poly_openings {
+            felts.write_deep_openings(openings)?;
+        }
+
+        felts.0.push(Felt::from(self.nonce));
+        Ok(felts.0)
+    }
+}
+
+struct FeltWriter(Vec<Felt>);
+
+impl FeltWriter {
+    fn write_usize(&mut self, value: usize) {
+        self.0.push(Felt::from(value as u64));
+    }
+
+    fn write_felts(&mut self, felts: &[Felt]) {
+        self.write_usize(felts.len());
+        self.0.extend_from_slice(felts);
+    }
+
+    fn write_optional_felt(&mut self, felt: &Option<Felt>) {
+        match felt {
+            Some(felt) => {
+                self.0.push(Felt::one());
+                self.0.push(felt.clone());
+            }
+            None => self.0.push(Felt::zero()),
+        }
+    }
+
+    fn write_commitment(&mut self, commitment: &Commitment) -> Result<(), FeltExportError> {
+        let felt = felt_from_node(commitment);
+        if felt.to_bytes_be() != commitment {
+            return Err(FeltExportError::NonFeltCommitment);
+        }
+        self.0.push(felt);
+        Ok(())
+    }
+
+    fn write_commitments(&mut self, commitments: &[Commitment]) -> Result<(), FeltExportError> {
+        self.write_usize(commitments.len());
+        commitments
+            .iter()
+            .try_for_each(|commitment| self.write_commitment(commitment))
+    }
+
+    fn write_caps(&mut self, caps: &[MerkleCap]) -> Result<(), FeltExportError> {
+        self.write_usize(caps.len());
+        caps.iter().try_for_each(|cap| self.write_commitments(cap))
+    }
+
+    fn write_merkle_paths(&mut self, paths: &[Proof<Commitment>]) -> Result<(), FeltExportError> {
+        self.write_usize(paths.len());
+        paths
+            .iter()
+            .try_for_each(|path| self.write_commitments(&path.merkle_path))
+    }
+
+    fn write_salt(&mut self, salt: &Salt) {
+        self.0.extend(salted_leaf::<Stark252PrimeField>(&[], salt));
+    }
+
+    fn write_fri_decommitment(
+        &mut self,
+        decommitment: &FriDecommitment<Stark252PrimeField>,
+    ) -> Result<(), FeltExportError> {
+        self.write_merkle_paths(&decommitment.layers_auth_paths_sym)?;
+        self.write_felts(&decommitment.layers_evaluations_sym);
+        self.write_merkle_paths(&decommitment.layers_auth_paths)?;
+        self.write_felts(&decommitment.layers_evaluations);
+        Ok(())
+    }
+
+    fn write_deep_openings(
+        &mut self,
+        openings: &DeepPolynomialOpenings<Stark252PrimeField>,
+    ) -> Result<(), FeltExportError> {
+        self.write_commitments(&openings.lde_composition_poly_proof.merkle_path)?;
+        self.0
+            .push(openings.lde_composition_poly_even_evaluation.clone());
+        self.0
+            .push(openings.lde_composition_poly_odd_evaluation.clone());
+        self.write_optional_felt(&openings.lde_composition_poly_mask_evaluation);
+        match &openings.lde_composition_poly_salt {
+            Some(salt) => {
+                self.0.push(Felt::one());
+                self.write_salt(salt);
+            }
+            None => self.0.push(Felt::zero()),
+        }
+        self.write_merkle_paths(&openings.lde_trace_merkle_proofs)?;
+        self.write_felts(&openings.lde_trace_evaluations);
+        self.write_usize(openings.lde_trace_salts.len());
+        for salt in &openings.lde_trace_salts {
+            self.write_salt(salt);
+        }
+        Ok(())
+    }
+}
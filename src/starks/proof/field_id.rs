@@ -0,0 +1,67 @@
+use lambdaworks_math::field::fields::{
+    fft_friendly::stark_252_prime_field::Stark252PrimeField, u64_prime_field::F17,
+};
+
+/// Identifier of the field a proof was generated over. It's the first byte of a
+/// serialized proof, so a proof can't be read or verified as one over another field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FieldId(u8);
+
+impl FieldId {
+    pub const STARK_252: FieldId = FieldId(1);
+    pub const F17: FieldId = FieldId(2);
+
+    /// An identifier for a field of another crate. It must differ from the ones of
+    /// the other fields whose proofs the same verifiers read, like `STARK_252` and
+    /// `F17`.
+    pub const fn new(byte: u8) -> Self {
+        Self(byte)
+    }
+
+    /// The identifier of the field `F`.
+    pub fn of<F: IdentifiedField>() -> Self {
+        F::FIELD_ID
+    }
+
+    /// The identifier of a serialized proof, read from its header.
+    pub fn of_proof_bytes(bytes: &[u8]) -> Option<Self> {
+        bytes.first().copied().map(Self::from_byte)
+    }
+
+    pub const fn from_byte(byte: u8) -> Self {
+        Self(byte)
+    }
+
+    pub const fn to_byte(self) -> u8 {
+        self.0
+    }
+}
+
+/// A field proofs can be generated over, identified in them by `FIELD_ID`.
+pub trait IdentifiedField: 'static {
+    const FIELD_ID: FieldId;
+}
+
+impl IdentifiedField for Stark252PrimeField {
+    const FIELD_ID: FieldId = FieldId::STARK_252;
+}
+
+impl IdentifiedField for F17 {
+    const FIELD_ID: FieldId = FieldId::F17;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_have_distinct_ids() {
+        assert_eq!(FieldId::of::<Stark252PrimeField>(), FieldId::STARK_252);
+        assert_eq!(FieldId::of::<F17>(), FieldId::F17);
+        assert_ne!(FieldId::STARK_252, FieldId::F17);
+
+        for id in [FieldId::STARK_252, FieldId::F17] {
+            assert_eq!(FieldId::from_byte(id.to_byte()), id);
+        }
+    }
+}
@@ -1,3 +1,9 @@
+pub mod attestation;
 pub mod errors;
+pub mod felts;
+pub mod field_id;
 pub mod options;
+#[cfg(feature = "sidecar")]
+pub mod sidecar;
 pub mod stark;
+pub mod stream;
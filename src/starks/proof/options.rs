@@ -1,6 +1,18 @@
-use lambdaworks_math::field::traits::IsPrimeField;
+use std::ops::Range;
+#[cfg(feature = "mmap")]
+use std::path::PathBuf;
+
+use lambdaworks_math::{
+    errors::DeserializationError,
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField, IsPrimeField},
+    },
+    traits::{ByteConversion, Deserializable, Serializable},
+};
 
 use super::errors::InsecureOptionError;
+use crate::starks::context::min_blowup_factor_of_degree;
 
 pub enum SecurityLevel {
     Conjecturable80Bits,
@@ -11,18 +23,186 @@ pub enum SecurityLevel {
     Provable128Bits,
 }
 
+/// How the columns of the LDE trace are committed to.
+///
+/// - `RowBatched`: every row of the trace is a leaf of a single Merkle tree.
+/// - `ColumnGrouped`: columns are split in contiguous groups of `group_size`
+///   columns, and each group is committed with its own Merkle tree. `group_size`
+///   must not be zero.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum CommitmentLayout {
+    #[default]
+    RowBatched,
+    ColumnGrouped {
+        group_size: usize,
+    },
+}
+
+impl CommitmentLayout {
+    /// Returns the ranges of columns committed together for a trace of `n_cols` columns.
+    /// There is one Merkle tree per returned range.
+    pub fn column_groups(&self, n_cols: usize) -> Vec<Range<usize>> {
+        if n_cols == 0 {
+            return Vec::new();
+        }
+        match self {
+            CommitmentLayout::RowBatched => vec![0..n_cols],
+            CommitmentLayout::ColumnGrouped { group_size } => {
+                let group_size = (*group_size).max(1);
+                (0..n_cols)
+                    .step_by(group_size)
+                    .map(|start| start..(start + group_size).min(n_cols))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Hash function of the Merkle trees of the trace, composition polynomial and FRI
+/// commitments.
+///
+/// - `Keccak256`: the default one.
+/// - `Blake3`: several times faster in software, making the commitments, one of the
+///   largest costs of the prover, cheaper. Needs the `blake3` feature.
+/// - `Poseidon`: the Poseidon of Starknet, much slower in software but cheap to
+///   check in Cairo, for proofs verified recursively. See `prove_recursive_friendly`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommitmentHash {
+    #[default]
+    Keccak256,
+    #[cfg(feature = "blake3")]
+    Blake3,
+    Poseidon,
+}
+
+/// Proof of work the prover does before the FRI queries are sampled, so each
+/// attempt of a cheating prover at other queries costs that work. See `grinding`.
+///
+/// - `Disabled`: no proof of work. The proofs carry a zero nonce.
+/// - `Bits(bits)`: the hash of the transcript challenge and the nonce must have at
+///   least `bits` trailing zeros, which takes `2^bits` attempts on average. It must
+///   not be zero, use `Disabled` instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrindingConfig {
+    Disabled,
+    Bits(u8),
+}
+
+impl GrindingConfig {
+    /// Bits of security the grinding adds, zero if it's disabled.
+    pub fn bits(&self) -> u8 {
+        match self {
+            GrindingConfig::Disabled => 0,
+            GrindingConfig::Bits(bits) => *bits,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        matches!(self, GrindingConfig::Bits(_))
+    }
+}
+
+/// Where the prover stores the Merkle trees of the trace and FRI commitments. It
+/// doesn't change the proofs, only the memory the prover needs.
+///
+/// - `InMemory`: the default one.
+/// - `OnDisk`: in temporary files of `directory`, mapped to memory, so the trees of
+///   domains larger than the memory of the machine fit. The nodes of the first
+///   `cached_levels` levels of each tree, which are in every Merkle path, are kept
+///   in memory. Needs the `mmap` feature.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum MerkleTreeStorage {
+    #[default]
+    InMemory,
+    #[cfg(feature = "mmap")]
+    OnDisk {
+        directory: PathBuf,
+        cached_levels: usize,
+    },
+}
+
+/// Offset of the coset of the LDE domain, as the big-endian bytes of a field element,
+/// so the options don't depend on the field of the proof. Leading zeros are stripped,
+/// so equal offsets have equal bytes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CosetOffset(Vec<u8>);
+
+impl CosetOffset {
+    pub fn from_field_element<F: IsField>(offset: &FieldElement<F>) -> Self
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        Self::from_bytes_be(&offset.to_bytes_be())
+    }
+
+    pub fn from_bytes_be(bytes: &[u8]) -> Self {
+        let first_non_zero = bytes.iter().position(|byte| *byte != 0);
+        Self(first_non_zero.map_or_else(Vec::new, |start| bytes[start..].to_vec()))
+    }
+
+    pub fn as_bytes_be(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The offset as an element of `F`. Offsets larger than the modulus of `F` are
+    /// reduced.
+    pub fn to_field_element<F: IsField>(&self) -> FieldElement<F> {
+        let byte_base = FieldElement::<F>::from(256);
+        self.0.iter().fold(FieldElement::zero(), |acc, byte| {
+            acc * &byte_base + FieldElement::from(*byte as u64)
+        })
+    }
+}
+
+impl From<u64> for CosetOffset {
+    fn from(offset: u64) -> Self {
+        Self::from_bytes_be(&offset.to_be_bytes())
+    }
+}
+
 /// The options for the proof
 ///
 /// - `blowup_factor`: the blowup factor for the trace
 /// - `fri_number_of_queries`: the number of queries for the FRI layer
-/// - `coset_offset`: the offset for the coset
-/// - `grinding_factor`: the number of leading zeros that we want for the Hash(hash || nonce)
+/// - `coset_offset`: the offset for the coset. It must not be in the LDE domain, see
+///   `validate_coset_offset`.
+/// - `grinding`: the proof of work of the prover, see `GrindingConfig`
+/// - `commitment_layout`: how the trace columns are grouped into Merkle trees
+/// - `commitment_hash`: hash function of the Merkle trees of the commitments
+/// - `deduplicate_queries`: whether repeated query indexes are opened only once
+/// - `fri_max_layers`: maximum number of FRI layers. When FRI stops before folding
+///   to a constant, the prover sends the coefficients of the remaining polynomial,
+///   trading proof size for fewer Merkle paths to check.
+/// - `salt_commitments`: whether a random salt is hashed with each leaf of the trace
+///   and composition polynomial commitments, so the openings don't leak the values
+///   of other leaves. This is the first component of zero-knowledge proofs.
+/// - `trace_randomization_rows`: number of random rows appended to the trace. The
+///   transition constraints are exempted on them, and the DEEP composition polynomial
+///   is masked with a random polynomial. Together with salted commitments, this makes
+///   the proofs zero-knowledge. See `with_zero_knowledge`.
+/// - `merkle_cap_height`: height of the caps sent instead of the roots of the Merkle
+///   trees, see `MerkleCap`. Each path of the openings is shorter by as many nodes,
+///   trading `2^merkle_cap_height` nodes in each commitment for smaller openings
+///   when there are many queries. Trees lower than it are sent whole.
+/// - `merkle_tree_storage`: where the prover stores the Merkle trees of the
+///   commitments. The verifier ignores it.
+///
+/// The proofs carry the options they were generated with, serialized without
+/// `merkle_tree_storage`.
 #[derive(Clone, Debug)]
 pub struct ProofOptions {
     pub blowup_factor: u8,
     pub fri_number_of_queries: usize,
-    pub coset_offset: u64,
-    pub grinding_factor: u8,
+    pub coset_offset: CosetOffset,
+    pub grinding: GrindingConfig,
+    pub commitment_layout: CommitmentLayout,
+    pub commitment_hash: CommitmentHash,
+    pub deduplicate_queries: bool,
+    pub fri_max_layers: Option<usize>,
+    pub salt_commitments: bool,
+    pub trace_randomization_rows: usize,
+    pub merkle_cap_height: usize,
+    pub merkle_tree_storage: MerkleTreeStorage,
 }
 
 impl ProofOptions {
@@ -30,73 +210,288 @@ impl ProofOptions {
     const EXTENSION_DEGREE: usize = 1;
     // Estimated maximum domain size. 2^40 = 1 TB
     const NUM_BITS_MAX_DOMAIN_SIZE: usize = 40;
+    // Queries sampled per element of the LDE domain before `sized_for` gives up,
+    // as by then nearly every element has been queried
+    const MAX_QUERIES_PER_DOMAIN_ELEMENT: usize = 4;
+
+    /// Creates proof options without checking their security, only that the
+    /// LDE domain they define can be built.
+    pub fn new(
+        blowup_factor: u8,
+        fri_number_of_queries: usize,
+        coset_offset: impl Into<CosetOffset>,
+        grinding: GrindingConfig,
+    ) -> Result<Self, InsecureOptionError> {
+        let options = ProofOptions {
+            blowup_factor,
+            fri_number_of_queries,
+            coset_offset: coset_offset.into(),
+            grinding,
+            commitment_layout: CommitmentLayout::RowBatched,
+            commitment_hash: CommitmentHash::Keccak256,
+            deduplicate_queries: false,
+            fri_max_layers: None,
+            salt_commitments: false,
+            trace_randomization_rows: 0,
+            merkle_cap_height: 0,
+            merkle_tree_storage: MerkleTreeStorage::InMemory,
+        };
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// Checks that the options define a valid LDE domain. The domain is a subgroup
+    /// of order `trace_length * blowup_factor`, so the blowup factor must be a
+    /// power of two as the trace length is.
+    pub fn validate(&self) -> Result<(), InsecureOptionError> {
+        if !self.blowup_factor.is_power_of_two() {
+            return Err(InsecureOptionError::BlowupFactor);
+        }
+        if self.fri_max_layers == Some(0) {
+            return Err(InsecureOptionError::FriMaxLayers);
+        }
+        if self.grinding == GrindingConfig::Bits(0) {
+            return Err(InsecureOptionError::ZeroGrindingBits);
+        }
+        if self.commitment_layout == (CommitmentLayout::ColumnGrouped { group_size: 0 }) {
+            return Err(InsecureOptionError::EmptyColumnGroups);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the coset offset, as an element of `F`, is not in the LDE domain of
+    /// size `lde_domain_size`. Otherwise the coset is the domain itself, where the
+    /// zerofiers vanish, and the domains of the FRI layers, obtained by squaring it,
+    /// would be subgroups too.
+    pub fn validate_coset_offset<F: IsFFTField>(
+        &self,
+        lde_domain_size: usize,
+    ) -> Result<(), InsecureOptionError> {
+        let coset_offset = self.coset_offset.to_field_element::<F>();
+        if coset_offset == FieldElement::zero()
+            || coset_offset.pow(lde_domain_size) == FieldElement::one()
+        {
+            return Err(InsecureOptionError::CosetOffset);
+        }
+
+        Ok(())
+    }
+
+    /// Returns these options with `coset_offset` as the offset of the LDE domain.
+    pub fn with_coset_offset<F: IsField>(self, coset_offset: &FieldElement<F>) -> Self
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        Self {
+            coset_offset: CosetOffset::from_field_element(coset_offset),
+            ..self
+        }
+    }
+
+    /// Number of FRI layers for a trace of `trace_length` rows. The DEEP composition
+    /// polynomial has degree less than the trace length, so without a maximum FRI
+    /// folds it until it's a constant.
+    pub fn fri_number_of_layers(&self, trace_length: usize) -> usize {
+        let number_of_layers = trace_length.trailing_zeros() as usize;
+        self.fri_max_layers.map_or(number_of_layers, |max_layers| {
+            max_layers.min(number_of_layers)
+        })
+    }
+
+    /// Number of coefficients of the last FRI polynomial for a trace of
+    /// `trace_length` rows, as each layer halves the degree.
+    pub fn fri_last_poly_length(&self, trace_length: usize) -> usize {
+        let number_of_layers = self.fri_number_of_layers(trace_length);
+        (trace_length >> number_of_layers).max(1)
+    }
+
+    /// Returns these options with salted commitments and `randomization_rows` random
+    /// rows at the end of the trace, giving honest-verifier zero-knowledge proofs as
+    /// long as the AIR leaves those rows out of its boundary constraints.
+    ///
+    /// Each query and each row of the out-of-domain frame reveals one evaluation of
+    /// every trace polynomial, so the trace stays hidden when `randomization_rows` is
+    /// at least the number of queries plus the number of frame rows.
+    pub fn with_zero_knowledge(self, randomization_rows: usize) -> Self {
+        Self {
+            salt_commitments: true,
+            trace_randomization_rows: randomization_rows,
+            ..self
+        }
+    }
+
+    /// Options hashing the commitments with Poseidon, for proofs verified in Cairo.
+    /// See `prove_recursive_friendly`.
+    pub fn with_poseidon_commitments(self) -> Self {
+        Self {
+            commitment_hash: CommitmentHash::Poseidon,
+            ..self
+        }
+    }
+
+    /// Whether the trace is extended with random rows and the DEEP composition
+    /// polynomial is masked.
+    pub fn randomizes_trace(&self) -> bool {
+        self.trace_randomization_rows > 0
+    }
+
+    /// Whether the proofs of a trace with these options are always the same, which
+    /// needs the commitments unsalted and the trace not randomized. They are the same
+    /// bytes whatever the number of threads of the `parallel` feature, as the parallel
+    /// computations of the prover are collected in order.
+    pub fn is_deterministic(&self) -> bool {
+        !self.salt_commitments && !self.randomizes_trace()
+    }
+
+    /// Whether these options and `other` give the same proofs, that is, whether they
+    /// are equal except for the options of the prover alone, like the storage of the
+    /// Merkle trees.
+    pub fn same_proofs_as(&self, other: &ProofOptions) -> bool {
+        self.serialize() == other.serialize()
+    }
 
     /// See section 5.10.1 of https://eprint.iacr.org/2021/582.pdf
-    pub fn new_secure(security_level: SecurityLevel, coset_offset: u64) -> Self {
+    pub fn new_secure(security_level: SecurityLevel, coset_offset: impl Into<CosetOffset>) -> Self {
+        let coset_offset = coset_offset.into();
         match security_level {
             SecurityLevel::Conjecturable80Bits => ProofOptions {
                 blowup_factor: 4,
                 fri_number_of_queries: 31,
                 coset_offset,
-                grinding_factor: 20,
+                grinding: GrindingConfig::Bits(20),
+                commitment_layout: CommitmentLayout::RowBatched,
+                commitment_hash: CommitmentHash::Keccak256,
+                deduplicate_queries: false,
+                fri_max_layers: None,
+                salt_commitments: false,
+                trace_randomization_rows: 0,
+                merkle_cap_height: 0,
+                merkle_tree_storage: MerkleTreeStorage::InMemory,
             },
             SecurityLevel::Conjecturable100Bits => ProofOptions {
                 blowup_factor: 4,
                 fri_number_of_queries: 41,
                 coset_offset,
-                grinding_factor: 20,
+                grinding: GrindingConfig::Bits(20),
+                commitment_layout: CommitmentLayout::RowBatched,
+                commitment_hash: CommitmentHash::Keccak256,
+                deduplicate_queries: false,
+                fri_max_layers: None,
+                salt_commitments: false,
+                trace_randomization_rows: 0,
+                merkle_cap_height: 0,
+                merkle_tree_storage: MerkleTreeStorage::InMemory,
             },
             SecurityLevel::Conjecturable128Bits => ProofOptions {
                 blowup_factor: 4,
                 fri_number_of_queries: 55,
                 coset_offset,
-                grinding_factor: 20,
+                grinding: GrindingConfig::Bits(20),
+                commitment_layout: CommitmentLayout::RowBatched,
+                commitment_hash: CommitmentHash::Keccak256,
+                deduplicate_queries: false,
+                fri_max_layers: None,
+                salt_commitments: false,
+                trace_randomization_rows: 0,
+                merkle_cap_height: 0,
+                merkle_tree_storage: MerkleTreeStorage::InMemory,
             },
             SecurityLevel::Provable80Bits => ProofOptions {
                 blowup_factor: 4,
                 fri_number_of_queries: 80,
                 coset_offset,
-                grinding_factor: 20,
+                grinding: GrindingConfig::Bits(20),
+                commitment_layout: CommitmentLayout::RowBatched,
+                commitment_hash: CommitmentHash::Keccak256,
+                deduplicate_queries: false,
+                fri_max_layers: None,
+                salt_commitments: false,
+                trace_randomization_rows: 0,
+                merkle_cap_height: 0,
+                merkle_tree_storage: MerkleTreeStorage::InMemory,
             },
             SecurityLevel::Provable100Bits => ProofOptions {
                 blowup_factor: 4,
                 fri_number_of_queries: 104,
                 coset_offset,
-                grinding_factor: 20,
+                grinding: GrindingConfig::Bits(20),
+                commitment_layout: CommitmentLayout::RowBatched,
+                commitment_hash: CommitmentHash::Keccak256,
+                deduplicate_queries: false,
+                fri_max_layers: None,
+                salt_commitments: false,
+                trace_randomization_rows: 0,
+                merkle_cap_height: 0,
+                merkle_tree_storage: MerkleTreeStorage::InMemory,
             },
             SecurityLevel::Provable128Bits => ProofOptions {
                 blowup_factor: 4,
                 fri_number_of_queries: 140,
                 coset_offset,
-                grinding_factor: 20,
+                grinding: GrindingConfig::Bits(20),
+                commitment_layout: CommitmentLayout::RowBatched,
+                commitment_hash: CommitmentHash::Keccak256,
+                deduplicate_queries: false,
+                fri_max_layers: None,
+                salt_commitments: false,
+                trace_randomization_rows: 0,
+                merkle_cap_height: 0,
+                merkle_tree_storage: MerkleTreeStorage::InMemory,
             },
         }
     }
 
+    /// Same as `new_secure`, with the blowup factor raised to the minimum of the
+    /// constraints of degree up to `max_constraint_degree`, see
+    /// `min_blowup_factor_of_degree`. The number of queries is kept, as a larger
+    /// blowup factor only adds security. Fails if that minimum doesn't fit in the
+    /// blowup factor.
+    pub fn new_secure_for_degree(
+        security_level: SecurityLevel,
+        coset_offset: impl Into<CosetOffset>,
+        max_constraint_degree: usize,
+    ) -> Result<Self, InsecureOptionError> {
+        Self::new_secure(security_level, coset_offset)
+            .with_min_blowup_factor(min_blowup_factor_of_degree(max_constraint_degree))
+    }
+
+    /// Returns these options with the blowup factor raised to `min_blowup_factor`,
+    /// like the one of `AIR::min_blowup_factor`, if it's smaller. Fails if
+    /// `min_blowup_factor` is larger than 255, as the constraints can't be
+    /// evaluated with any smaller blowup factor.
+    pub fn with_min_blowup_factor(
+        self,
+        min_blowup_factor: usize,
+    ) -> Result<Self, InsecureOptionError> {
+        let min_blowup_factor = u8::try_from(min_blowup_factor)
+            .map_err(|_| InsecureOptionError::BlowupFactorTooLarge)?;
+        Ok(Self {
+            blowup_factor: self.blowup_factor.max(min_blowup_factor),
+            ..self
+        })
+    }
+
     /// Checks security of proof options given 128 bits of security
     pub fn new_with_checked_security<F: IsPrimeField>(
         blowup_factor: u8,
         fri_number_of_queries: usize,
-        coset_offset: u64,
-        grinding_factor: u8,
+        coset_offset: impl Into<CosetOffset>,
+        grinding: GrindingConfig,
         security_target: u8,
     ) -> Result<Self, InsecureOptionError> {
         Self::check_field_security::<F>(security_target)?;
+        let options = Self::new(blowup_factor, fri_number_of_queries, coset_offset, grinding)?;
 
         let num_bits_blowup_factor = blowup_factor.trailing_zeros() as usize;
 
         if security_target as usize
-            >= grinding_factor as usize + num_bits_blowup_factor * fri_number_of_queries - 1
+            >= grinding.bits() as usize + num_bits_blowup_factor * fri_number_of_queries - 1
         {
             return Err(InsecureOptionError::SecurityBits);
         }
 
-        Ok(ProofOptions {
-            blowup_factor,
-            fri_number_of_queries,
-            coset_offset,
-            grinding_factor,
-        })
+        Ok(options)
     }
 
     /// Checks provable security of proof options given 128 bits of security
@@ -105,26 +500,65 @@ impl ProofOptions {
     pub fn new_with_checked_provable_security<F: IsPrimeField>(
         blowup_factor: u8,
         fri_number_of_queries: usize,
-        coset_offset: u64,
-        grinding_factor: u8,
+        coset_offset: impl Into<CosetOffset>,
+        grinding: GrindingConfig,
         security_target: u8,
     ) -> Result<Self, InsecureOptionError> {
         Self::check_field_security::<F>(security_target)?;
+        let options = Self::new(blowup_factor, fri_number_of_queries, coset_offset, grinding)?;
 
         let num_bits_blowup_factor = blowup_factor.leading_zeros() as usize;
 
         if (security_target as usize)
-            < grinding_factor as usize + num_bits_blowup_factor * fri_number_of_queries / 2
+            < grinding.bits() as usize + num_bits_blowup_factor * fri_number_of_queries / 2
         {
             return Err(InsecureOptionError::SecurityBits);
         }
 
-        Ok(ProofOptions {
-            blowup_factor,
-            fri_number_of_queries,
-            coset_offset,
-            grinding_factor,
-        })
+        Ok(options)
+    }
+
+    /// Expected number of distinct indexes among the `fri_number_of_queries` ones
+    /// sampled from an LDE domain of size `lde_domain_size`. Repeated queries don't
+    /// add soundness, so this is the same whether they are deduplicated or not.
+    pub fn expected_distinct_queries(&self, lde_domain_size: usize) -> f64 {
+        let domain_size = lde_domain_size as f64;
+        let queries = self.fri_number_of_queries as i32;
+        domain_size * (1.0 - (1.0 - 1.0 / domain_size).powi(queries))
+    }
+
+    /// Conjectured security bits of the options for an LDE domain of size
+    /// `lde_domain_size`, using the same estimate as `new_with_checked_security`
+    /// but counting only the distinct queries. The checked constructors assume
+    /// domains large enough for repetitions to be negligible.
+    pub fn conjectured_security_bits(&self, lde_domain_size: usize) -> usize {
+        let num_bits_blowup_factor = self.blowup_factor.trailing_zeros() as usize;
+        let distinct_queries = self.expected_distinct_queries(lde_domain_size).round() as usize;
+
+        (self.grinding.bits() as usize + num_bits_blowup_factor * distinct_queries)
+            .saturating_sub(1)
+    }
+
+    /// Returns these options with the fewest queries giving `security_bits`
+    /// conjectured bits of security (see `conjectured_security_bits`) for a trace of
+    /// `trace_length` rows, like the one estimated before running a program. The
+    /// FRI layers follow from the trace length, see `fri_number_of_layers`.
+    ///
+    /// Fails if no number of queries reaches the target, as the distinct queries
+    /// are at most the size of the LDE domain.
+    pub fn sized_for(
+        self,
+        trace_length: usize,
+        security_bits: usize,
+    ) -> Result<Self, InsecureOptionError> {
+        let lde_domain_size = trace_length * self.blowup_factor as usize;
+        (1..=lde_domain_size * Self::MAX_QUERIES_PER_DOMAIN_ELEMENT)
+            .map(|fri_number_of_queries| Self {
+                fri_number_of_queries,
+                ..self.clone()
+            })
+            .find(|options| options.conjectured_security_bits(lde_domain_size) >= security_bits)
+            .ok_or(InsecureOptionError::SecurityBits)
     }
 
     fn check_field_security<F: IsPrimeField>(
@@ -145,21 +579,211 @@ impl ProofOptions {
         Self {
             blowup_factor: 4,
             fri_number_of_queries: 3,
-            coset_offset: 3,
-            grinding_factor: 1,
+            coset_offset: CosetOffset::from(3),
+            grinding: GrindingConfig::Bits(1),
+            commitment_layout: CommitmentLayout::RowBatched,
+            commitment_hash: CommitmentHash::Keccak256,
+            deduplicate_queries: false,
+            fri_max_layers: None,
+            salt_commitments: false,
+            trace_randomization_rows: 0,
+            merkle_cap_height: 0,
+            merkle_tree_storage: MerkleTreeStorage::InMemory,
+        }
+    }
+}
+
+impl Serializable for ProofOptions {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = vec![self.blowup_factor];
+        bytes.extend((self.fri_number_of_queries as u64).to_be_bytes());
+        let coset_offset = self.coset_offset.as_bytes_be();
+        bytes.extend((coset_offset.len() as u64).to_be_bytes());
+        bytes.extend(coset_offset);
+        // Zero for disabled grinding, as `Bits(0)` is invalid
+        bytes.push(self.grinding.bits());
+        match self.commitment_layout {
+            CommitmentLayout::RowBatched => bytes.push(0),
+            CommitmentLayout::ColumnGrouped { group_size } => {
+                bytes.push(1);
+                bytes.extend((group_size as u64).to_be_bytes());
+            }
+        }
+        bytes.push(match self.commitment_hash {
+            CommitmentHash::Keccak256 => 0,
+            #[cfg(feature = "blake3")]
+            CommitmentHash::Blake3 => 1,
+            CommitmentHash::Poseidon => 2,
+        });
+        bytes.push(self.deduplicate_queries as u8);
+        match self.fri_max_layers {
+            None => bytes.push(0),
+            Some(max_layers) => {
+                bytes.push(1);
+                bytes.extend((max_layers as u64).to_be_bytes());
+            }
+        }
+        bytes.push(self.salt_commitments as u8);
+        bytes.extend((self.trace_randomization_rows as u64).to_be_bytes());
+        bytes.extend((self.merkle_cap_height as u64).to_be_bytes());
+        bytes
+    }
+}
+
+impl Deserializable for ProofOptions {
+    /// Reads options serialized by `serialize`, with the Merkle trees in memory.
+    fn deserialize(bytes: &[u8]) -> Result<Self, DeserializationError>
+    where
+        Self: Sized,
+    {
+        let mut reader = OptionsReader(bytes);
+        let blowup_factor = reader.read_u8()?;
+        let fri_number_of_queries = reader.read_usize()?;
+        let coset_offset_len = reader.read_usize()?;
+        let coset_offset_bytes = reader.read_bytes(coset_offset_len)?;
+        let coset_offset = CosetOffset::from_bytes_be(coset_offset_bytes);
+        // Offsets are serialized without leading zeros
+        if coset_offset.as_bytes_be() != coset_offset_bytes {
+            return Err(DeserializationError::InvalidValue);
+        }
+        let grinding = match reader.read_u8()? {
+            0 => GrindingConfig::Disabled,
+            bits => GrindingConfig::Bits(bits),
+        };
+        let commitment_layout = match reader.read_u8()? {
+            0 => CommitmentLayout::RowBatched,
+            1 => match reader.read_usize()? {
+                0 => return Err(DeserializationError::InvalidValue),
+                group_size => CommitmentLayout::ColumnGrouped { group_size },
+            },
+            _ => return Err(DeserializationError::InvalidValue),
+        };
+        let commitment_hash = match reader.read_u8()? {
+            0 => CommitmentHash::Keccak256,
+            #[cfg(feature = "blake3")]
+            1 => CommitmentHash::Blake3,
+            2 => CommitmentHash::Poseidon,
+            _ => return Err(DeserializationError::InvalidValue),
+        };
+        let deduplicate_queries = reader.read_bool()?;
+        let fri_max_layers = match reader.read_u8()? {
+            0 => None,
+            1 => Some(reader.read_usize()?),
+            _ => return Err(DeserializationError::InvalidValue),
+        };
+        let salt_commitments = reader.read_bool()?;
+        let trace_randomization_rows = reader.read_usize()?;
+        let merkle_cap_height = reader.read_usize()?;
+        if !reader.0.is_empty() {
+            return Err(DeserializationError::InvalidAmountOfBytes);
+        }
+
+        Ok(Self {
+            blowup_factor,
+            fri_number_of_queries,
+            coset_offset,
+            grinding,
+            commitment_layout,
+            commitment_hash,
+            deduplicate_queries,
+            fri_max_layers,
+            salt_commitments,
+            trace_randomization_rows,
+            merkle_cap_height,
+            merkle_tree_storage: MerkleTreeStorage::InMemory,
+        })
+    }
+}
+
+/// The serialized options left to read.
+struct OptionsReader<'a>(&'a [u8]);
+
+impl<'a> OptionsReader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DeserializationError> {
+        if self.0.len() < len {
+            return Err(DeserializationError::InvalidAmountOfBytes);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DeserializationError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_bool(&mut self) -> Result<bool, DeserializationError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DeserializationError::InvalidValue),
+        }
+    }
+
+    fn read_usize(&mut self) -> Result<usize, DeserializationError> {
+        let bytes = self.read_bytes(8)?.try_into().unwrap();
+        usize::try_from(u64::from_be_bytes(bytes)).map_err(|_| DeserializationError::InvalidValue)
+    }
+}
+
+/// Options a verifier accepts in the proofs, which carry the ones they were
+/// generated with. This lets each prover pick its own, like a larger blowup factor
+/// with fewer queries, as long as the proofs are secure enough. See
+/// `verifier::verify_with_policy`.
+#[derive(Clone, Debug)]
+pub struct ProofOptionsPolicy {
+    /// Minimum security of the options, as estimated by `conjectured_security_bits`
+    /// for the LDE domain of the proof.
+    pub min_security_bits: usize,
+    /// Largest number of queries, which bounds the work of the verifier.
+    pub max_fri_number_of_queries: usize,
+    /// Whether proofs without grinding are accepted, as long as they are secure
+    /// enough without it.
+    pub allow_disabled_grinding: bool,
+    /// Largest number of rows of the trace, which bounds the domains the verifier
+    /// builds.
+    pub max_trace_length: usize,
+}
+
+impl ProofOptionsPolicy {
+    /// Checks the options of a proof of a trace of `trace_length` rows.
+    pub fn check(
+        &self,
+        options: &ProofOptions,
+        trace_length: usize,
+    ) -> Result<(), InsecureOptionError> {
+        options.validate()?;
+        if trace_length > self.max_trace_length {
+            return Err(InsecureOptionError::TraceTooLong);
+        }
+        if options.fri_number_of_queries > self.max_fri_number_of_queries {
+            return Err(InsecureOptionError::TooManyQueries);
+        }
+        if !options.grinding.is_enabled() && !self.allow_disabled_grinding {
+            return Err(InsecureOptionError::GrindingDisabled);
+        }
+        let lde_domain_size = trace_length.saturating_mul(options.blowup_factor as usize);
+        if options.conjectured_security_bits(lde_domain_size) < self.min_security_bits {
+            return Err(InsecureOptionError::SecurityBits);
         }
+
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use lambdaworks_math::field::fields::{
-        fft_friendly::stark_252_prime_field::Stark252PrimeField, u64_prime_field::F17,
+    use lambdaworks_math::field::{
+        element::FieldElement,
+        fields::{fft_friendly::stark_252_prime_field::Stark252PrimeField, u64_prime_field::F17},
+        traits::IsFFTField,
     };
 
     use crate::starks::proof::{errors::InsecureOptionError, options::SecurityLevel};
 
-    use super::ProofOptions;
+    use lambdaworks_math::traits::{Deserializable, Serializable};
+
+    use super::{CommitmentLayout, CosetOffset, GrindingConfig, ProofOptions, ProofOptionsPolicy};
 
     #[test]
     fn u64_prime_field_is_not_large_enough_to_be_secure() {
@@ -167,14 +791,15 @@ mod tests {
             blowup_factor,
             fri_number_of_queries,
             coset_offset,
-            grinding_factor,
+            grinding,
+            ..
         } = ProofOptions::new_secure(SecurityLevel::Conjecturable128Bits, 1);
 
         let u64_options = ProofOptions::new_with_checked_security::<F17>(
             blowup_factor,
             fri_number_of_queries,
             coset_offset,
-            grinding_factor,
+            grinding,
             128,
         );
 
@@ -187,14 +812,15 @@ mod tests {
             blowup_factor,
             fri_number_of_queries,
             coset_offset,
-            grinding_factor,
+            grinding,
+            ..
         } = ProofOptions::new_secure(SecurityLevel::Conjecturable128Bits, 1);
 
         let secure_options = ProofOptions::new_with_checked_security::<Stark252PrimeField>(
             blowup_factor,
             fri_number_of_queries,
             coset_offset,
-            grinding_factor,
+            grinding,
             128,
         );
 
@@ -207,14 +833,15 @@ mod tests {
             blowup_factor,
             fri_number_of_queries,
             coset_offset,
-            grinding_factor,
+            grinding,
+            ..
         } = ProofOptions::new_secure(SecurityLevel::Conjecturable128Bits, 1);
 
         let insecure_options = ProofOptions::new_with_checked_security::<Stark252PrimeField>(
             blowup_factor,
             fri_number_of_queries - 1,
             coset_offset,
-            grinding_factor,
+            grinding,
             128,
         );
 
@@ -230,14 +857,15 @@ mod tests {
             blowup_factor,
             fri_number_of_queries,
             coset_offset,
-            grinding_factor,
+            grinding,
+            ..
         } = ProofOptions::new_secure(SecurityLevel::Conjecturable100Bits, 1);
 
         let secure_options = ProofOptions::new_with_checked_security::<Stark252PrimeField>(
             blowup_factor,
             fri_number_of_queries,
             coset_offset,
-            grinding_factor,
+            grinding,
             100,
         );
 
@@ -250,17 +878,323 @@ mod tests {
             blowup_factor,
             fri_number_of_queries,
             coset_offset,
-            grinding_factor,
+            grinding,
+            ..
         } = ProofOptions::new_secure(SecurityLevel::Conjecturable80Bits, 1);
 
         let secure_options = ProofOptions::new_with_checked_security::<Stark252PrimeField>(
             blowup_factor,
             fri_number_of_queries,
             coset_offset,
-            grinding_factor,
+            grinding,
             80,
         );
 
         assert!(secure_options.is_ok());
     }
+
+    #[test]
+    fn secure_options_are_raised_to_the_blowup_factor_of_the_constraints() {
+        let options = ProofOptions::new_secure(SecurityLevel::Conjecturable80Bits, 1);
+        let for_degree = |degree| {
+            ProofOptions::new_secure_for_degree(SecurityLevel::Conjecturable80Bits, 1, degree)
+                .unwrap()
+        };
+
+        assert_eq!(for_degree(3).blowup_factor, options.blowup_factor);
+        assert_eq!(for_degree(7).blowup_factor, 8);
+        assert_eq!(
+            for_degree(7).fri_number_of_queries,
+            options.fri_number_of_queries
+        );
+        assert!(matches!(
+            ProofOptions::new_secure_for_degree(SecurityLevel::Conjecturable80Bits, 1, 1000),
+            Err(InsecureOptionError::BlowupFactorTooLarge)
+        ));
+    }
+
+    #[test]
+    fn blowup_factors_that_are_not_powers_of_two_are_rejected() {
+        assert!(ProofOptions::new(4, 3, 3, GrindingConfig::Bits(1)).is_ok());
+        assert!(matches!(
+            ProofOptions::new(6, 3, 3, GrindingConfig::Bits(1)),
+            Err(InsecureOptionError::BlowupFactor)
+        ));
+        assert!(matches!(
+            ProofOptions::new(0, 3, 3, GrindingConfig::Bits(1)),
+            Err(InsecureOptionError::BlowupFactor)
+        ));
+        assert!(matches!(
+            ProofOptions::new_with_checked_security::<Stark252PrimeField>(
+                12,
+                55,
+                1,
+                GrindingConfig::Bits(20),
+                128
+            ),
+            Err(InsecureOptionError::BlowupFactor)
+        ));
+    }
+
+    #[test]
+    fn security_estimate_counts_only_distinct_queries() {
+        let options = ProofOptions::new(4, 30, 3, GrindingConfig::Bits(20)).unwrap();
+
+        // In large domains almost every query is distinct
+        assert_eq!(options.conjectured_security_bits(1 << 30), 20 + 2 * 30 - 1);
+        // In a domain of 8 elements there can't be more than 8 distinct queries
+        assert!(options.expected_distinct_queries(8) <= 8.0);
+        assert!(options.conjectured_security_bits(8) <= 20 + 2 * 8 - 1);
+    }
+
+    #[test]
+    fn sized_options_have_the_fewest_queries_for_the_target() {
+        let options = ProofOptions::new(4, 1, 3, GrindingConfig::Bits(20)).unwrap();
+
+        let sized_options = options.clone().sized_for(1 << 16, 100).unwrap();
+        assert_eq!(sized_options.fri_number_of_queries, 41);
+        assert!(sized_options.conjectured_security_bits(1 << 18) >= 100);
+
+        // A domain of 32 elements has at most 32 distinct queries
+        assert!(matches!(
+            options.sized_for(8, 100),
+            Err(InsecureOptionError::SecurityBits)
+        ));
+    }
+
+    #[test]
+    fn fri_layers_are_capped_by_the_maximum() {
+        let options = ProofOptions::default_test_options();
+        assert_eq!(options.fri_number_of_layers(256), 8);
+        assert_eq!(options.fri_last_poly_length(256), 1);
+
+        let options = ProofOptions {
+            fri_max_layers: Some(3),
+            ..ProofOptions::default_test_options()
+        };
+        assert_eq!(options.fri_number_of_layers(256), 3);
+        assert_eq!(options.fri_number_of_layers(4), 2);
+        assert_eq!(options.fri_last_poly_length(256), 32);
+        assert_eq!(options.fri_last_poly_length(4), 1);
+
+        let options = ProofOptions {
+            fri_max_layers: Some(0),
+            ..ProofOptions::default_test_options()
+        };
+        assert!(matches!(
+            options.validate(),
+            Err(InsecureOptionError::FriMaxLayers)
+        ));
+    }
+
+    #[test]
+    fn zero_knowledge_options_salt_and_randomize_the_trace() {
+        let options = ProofOptions::default_test_options();
+        assert!(!options.randomizes_trace());
+        assert!(options.is_deterministic());
+
+        let options = options.with_zero_knowledge(8);
+        assert!(options.salt_commitments);
+        assert!(options.randomizes_trace());
+        assert!(!options.is_deterministic());
+        assert_eq!(options.trace_randomization_rows, 8);
+    }
+
+    #[test]
+    fn coset_offsets_are_field_elements() {
+        type FE = FieldElement<Stark252PrimeField>;
+
+        let large_offset = FE::from_hex_unchecked("123456789abcdef0123456789abcdef");
+        let options = ProofOptions::default_test_options().with_coset_offset(&large_offset);
+        assert_eq!(options.coset_offset.to_field_element(), large_offset);
+        assert_eq!(
+            CosetOffset::from(3),
+            CosetOffset::from_field_element(&FE::from(3))
+        );
+        assert!(options
+            .validate_coset_offset::<Stark252PrimeField>(64)
+            .is_ok());
+    }
+
+    #[test]
+    fn coset_offsets_in_the_lde_domain_are_rejected() {
+        let root_of_unity = Stark252PrimeField::get_primitive_root_of_unity(6).unwrap();
+        let options = ProofOptions::default_test_options().with_coset_offset(&root_of_unity);
+        assert!(matches!(
+            options.validate_coset_offset::<Stark252PrimeField>(64),
+            Err(InsecureOptionError::CosetOffset)
+        ));
+
+        for coset_offset in [0, 1] {
+            let options = ProofOptions {
+                coset_offset: coset_offset.into(),
+                ..ProofOptions::default_test_options()
+            };
+            assert!(matches!(
+                options.validate_coset_offset::<Stark252PrimeField>(64),
+                Err(InsecureOptionError::CosetOffset)
+            ));
+        }
+    }
+
+    #[test]
+    fn row_batched_layout_commits_all_columns_together() {
+        let groups = CommitmentLayout::RowBatched.column_groups(5);
+        assert_eq!(groups, vec![0..5]);
+    }
+
+    #[test]
+    fn column_grouped_layout_splits_columns_in_contiguous_groups() {
+        let groups = CommitmentLayout::ColumnGrouped { group_size: 2 }.column_groups(5);
+        assert_eq!(groups, vec![0..2, 2..4, 4..5]);
+    }
+
+    #[test]
+    fn commitment_layout_of_empty_trace_has_no_groups() {
+        assert!(CommitmentLayout::RowBatched.column_groups(0).is_empty());
+        assert!(CommitmentLayout::ColumnGrouped { group_size: 3 }
+            .column_groups(0)
+            .is_empty());
+    }
+
+    #[test]
+    fn empty_column_groups_are_rejected() {
+        let options = ProofOptions {
+            commitment_layout: CommitmentLayout::ColumnGrouped { group_size: 0 },
+            ..ProofOptions::default_test_options()
+        };
+        assert!(matches!(
+            options.validate(),
+            Err(InsecureOptionError::EmptyColumnGroups)
+        ));
+        assert!(ProofOptions::deserialize(&options.serialize()).is_err());
+    }
+
+    #[test]
+    fn serialized_options_are_read_back() {
+        let options = ProofOptions {
+            commitment_layout: CommitmentLayout::ColumnGrouped { group_size: 3 },
+            deduplicate_queries: true,
+            fri_max_layers: Some(5),
+            merkle_cap_height: 3,
+            ..ProofOptions::default_test_options().with_zero_knowledge(8)
+        };
+
+        let deserialized = ProofOptions::deserialize(&options.serialize()).unwrap();
+        assert!(deserialized.same_proofs_as(&options));
+        assert_eq!(deserialized.coset_offset, options.coset_offset);
+        assert_eq!(deserialized.fri_max_layers, Some(5));
+        assert_eq!(deserialized.merkle_cap_height, 3);
+        assert!(!deserialized.same_proofs_as(&ProofOptions::default_test_options()));
+
+        let mut bytes = options.serialize();
+        bytes.push(0);
+        assert!(ProofOptions::deserialize(&bytes).is_err());
+        assert!(ProofOptions::deserialize(&bytes[..bytes.len() - 2]).is_err());
+
+        // The same offset with a leading zero
+        let mut bytes = options.serialize();
+        let offset_len = options.coset_offset.as_bytes_be().len() as u64;
+        bytes[9..17].copy_from_slice(&(offset_len + 1).to_be_bytes());
+        bytes.insert(17, 0);
+        assert!(ProofOptions::deserialize(&bytes).is_err());
+    }
+
+    #[test]
+    fn policy_accepts_options_of_other_blowup_factors_as_secure() {
+        let policy = ProofOptionsPolicy {
+            min_security_bits: 100,
+            max_fri_number_of_queries: 64,
+            allow_disabled_grinding: false,
+            max_trace_length: 1 << 20,
+        };
+        let trace_length = 1 << 16;
+
+        let blowup_4 = ProofOptions {
+            blowup_factor: 4,
+            fri_number_of_queries: 41,
+            grinding: GrindingConfig::Bits(20),
+            ..ProofOptions::default_test_options()
+        };
+        let blowup_16 = ProofOptions {
+            blowup_factor: 16,
+            fri_number_of_queries: 21,
+            ..blowup_4.clone()
+        };
+        assert!(policy.check(&blowup_4, trace_length).is_ok());
+        assert!(policy.check(&blowup_16, trace_length).is_ok());
+
+        let fewer_queries = ProofOptions {
+            fri_number_of_queries: 20,
+            ..blowup_4.clone()
+        };
+        assert!(matches!(
+            policy.check(&fewer_queries, trace_length),
+            Err(InsecureOptionError::SecurityBits)
+        ));
+        let more_queries = ProofOptions {
+            fri_number_of_queries: 65,
+            ..blowup_4
+        };
+        assert!(matches!(
+            policy.check(&more_queries, trace_length),
+            Err(InsecureOptionError::TooManyQueries)
+        ));
+    }
+
+    #[test]
+    fn policy_accepts_disabled_grinding_only_if_allowed() {
+        let options = ProofOptions {
+            blowup_factor: 4,
+            fri_number_of_queries: 60,
+            grinding: GrindingConfig::Disabled,
+            ..ProofOptions::default_test_options()
+        };
+        let mut policy = ProofOptionsPolicy {
+            min_security_bits: 100,
+            max_fri_number_of_queries: 64,
+            allow_disabled_grinding: false,
+            max_trace_length: 1 << 20,
+        };
+        assert!(matches!(
+            policy.check(&options, 1 << 16),
+            Err(InsecureOptionError::GrindingDisabled)
+        ));
+
+        policy.allow_disabled_grinding = true;
+        assert!(policy.check(&options, 1 << 16).is_ok());
+    }
+
+    #[test]
+    fn policy_rejects_traces_longer_than_the_maximum() {
+        let options = ProofOptions {
+            blowup_factor: 4,
+            fri_number_of_queries: 60,
+            ..ProofOptions::default_test_options()
+        };
+        let policy = ProofOptionsPolicy {
+            min_security_bits: 100,
+            max_fri_number_of_queries: 64,
+            allow_disabled_grinding: true,
+            max_trace_length: 1 << 16,
+        };
+
+        assert!(policy.check(&options, 1 << 16).is_ok());
+        assert!(matches!(
+            policy.check(&options, 1 << 17),
+            Err(InsecureOptionError::TraceTooLong)
+        ));
+    }
+
+    #[test]
+    fn grinding_of_zero_bits_must_be_disabled_explicitly() {
+        assert!(matches!(
+            ProofOptions::new(4, 3, 3, GrindingConfig::Bits(0)),
+            Err(InsecureOptionError::ZeroGrindingBits)
+        ));
+
+        let options = ProofOptions::new(4, 3, 3, GrindingConfig::Disabled).unwrap();
+        let deserialized = ProofOptions::deserialize(&options.serialize()).unwrap();
+        assert_eq!(deserialized.grinding, GrindingConfig::Disabled);
+    }
 }
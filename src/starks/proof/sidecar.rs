@@ -0,0 +1,140 @@
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use lambdaworks_math::{
+    errors::DeserializationError,
+    traits::{Deserializable, Serializable},
+};
+use thiserror::Error;
+
+use crate::starks::config::Commitment;
+
+pub const SIDECAR_NONCE_SIZE: usize = 24;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SidecarError {
+    #[error("The sidecar can't be decrypted with the key, or it's bound to another proof")]
+    Decryption,
+}
+
+/// Symmetric key of the encrypted sidecars. It's only known by the prover and the
+/// auditors of its proofs, the verifiers never need it.
+#[derive(Clone)]
+pub struct SidecarKey([u8; 32]);
+
+impl SidecarKey {
+    pub fn new(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+}
+
+/// Auxiliary data of a proof, like the hints or the nondeterministic values of the
+/// execution, encrypted with XChaCha20-Poly1305. The hash of the proof is
+/// authenticated along with the data, so a sidecar attached to another proof
+/// fails to decrypt. It's not part of the statement, so it doesn't change what the
+/// proof shows.
+///
+/// The length of the ciphertext is serialized as a big-endian `u64`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedSidecar {
+    nonce: [u8; SIDECAR_NONCE_SIZE],
+    ciphertext: Vec<u8>,
+}
+
+impl EncryptedSidecar {
+    /// Encrypts `data` with a random nonce, binding it to the proof of hash `proof_hash`.
+    pub fn seal(key: &SidecarKey, proof_hash: &Commitment, data: &[u8]) -> Self {
+        let nonce: [u8; SIDECAR_NONCE_SIZE] = rand::random();
+        let ciphertext = XChaCha20Poly1305::new(Key::from_slice(&key.0))
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: data,
+                    aad: proof_hash,
+                },
+            )
+            .expect("the data is shorter than the maximum length of a message");
+        Self { nonce, ciphertext }
+    }
+
+    /// Decrypts the data, checking that the sidecar was sealed for the proof of
+    /// hash `proof_hash`.
+    pub fn open(&self, key: &SidecarKey, proof_hash: &Commitment) -> Result<Vec<u8>, SidecarError> {
+        XChaCha20Poly1305::new(Key::from_slice(&key.0))
+            .decrypt(
+                XNonce::from_slice(&self.nonce),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: proof_hash,
+                },
+            )
+            .map_err(|_| SidecarError::Decryption)
+    }
+}
+
+impl Serializable for EncryptedSidecar {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = self.nonce.to_vec();
+        bytes.extend((self.ciphertext.len() as u64).to_be_bytes());
+        bytes.extend(&self.ciphertext);
+        bytes
+    }
+}
+
+impl Deserializable for EncryptedSidecar {
+    fn deserialize(bytes: &[u8]) -> Result<Self, DeserializationError>
+    where
+        Self: Sized,
+    {
+        let nonce = bytes
+            .get(..SIDECAR_NONCE_SIZE)
+            .ok_or(DeserializationError::InvalidAmountOfBytes)?
+            .try_into()
+            .map_err(|_| DeserializationError::InvalidAmountOfBytes)?;
+        let bytes = &bytes[SIDECAR_NONCE_SIZE..];
+
+        let ciphertext_len = u64::from_be_bytes(
+            bytes
+                .get(..8)
+                .ok_or(DeserializationError::InvalidAmountOfBytes)?
+                .try_into()
+                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
+        );
+        let ciphertext_len =
+            usize::try_from(ciphertext_len).map_err(|_| DeserializationError::InvalidValue)?;
+        let ciphertext = bytes
+            .get(8..)
+            .filter(|ciphertext| ciphertext.len() == ciphertext_len)
+            .ok_or(DeserializationError::InvalidAmountOfBytes)?
+            .to_vec();
+
+        Ok(Self { nonce, ciphertext })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sidecars_are_only_opened_with_their_key_and_proof() {
+        let key = SidecarKey::random();
+        let proof_hash = [1; 32];
+        let sidecar = EncryptedSidecar::seal(&key, &proof_hash, b"hints");
+
+        assert_eq!(sidecar.open(&key, &proof_hash).unwrap(), b"hints");
+        assert_eq!(
+            sidecar.open(&SidecarKey::random(), &proof_hash),
+            Err(SidecarError::Decryption)
+        );
+        assert_eq!(sidecar.open(&key, &[2; 32]), Err(SidecarError::Decryption));
+
+        let deserialized = EncryptedSidecar::deserialize(&sidecar.serialize()).unwrap();
+        assert_eq!(deserialized, sidecar);
+    }
+}
@@ -5,51 +5,324 @@ use lambdaworks_math::{
     traits::{ByteConversion, Deserializable, Serializable},
 };
 
+use super::{
+    field_id::{FieldId, IdentifiedField},
+    options::{CosetOffset, ProofOptions},
+};
 use crate::starks::{
-    config::Commitment,
+    config::{
+        BatchedVectorCommitment, Commitment, FriVectorCommitment, MerkleCap, Salt, SALT_SIZE,
+    },
+    domain::lde_domain_size,
     frame::Frame,
     fri::fri_decommit::FriDecommitment,
-    utils::{deserialize_proof, serialize_proof},
+    utils::{
+        check_fully_read, deserialize_cap, deserialize_felt, deserialize_felt_len, deserialize_len,
+        deserialize_proof, serialize_cap, serialize_proof,
+    },
+    vector_commitment::IsVectorCommitment,
 };
 
-use core::mem;
-
 #[derive(Debug, Clone)]
 pub struct DeepPolynomialOpenings<F: IsFFTField> {
     pub lde_composition_poly_proof: Proof<Commitment>,
     pub lde_composition_poly_even_evaluation: FieldElement<F>,
     pub lde_composition_poly_odd_evaluation: FieldElement<F>,
+    // Opening of M, if the DEEP composition polynomial is masked
+    pub lde_composition_poly_mask_evaluation: Option<FieldElement<F>>,
+    // Salt of the opened leaf of [H₁] and [H₂], if the commitments are salted
+    pub lde_composition_poly_salt: Option<Salt>,
     pub lde_trace_merkle_proofs: Vec<Proof<Commitment>>,
     pub lde_trace_evaluations: Vec<FieldElement<F>>,
+    // Salts of the opened leaves of [tⱼ], empty if the commitments are not salted
+    pub lde_trace_salts: Vec<Salt>,
+}
+
+/// Evaluations of the trace polynomials opened at a query, split into the columns
+/// of the main trace and the ones of the auxiliary trace of the RAP.
+#[derive(Debug, Clone)]
+pub struct TraceOpenings<'a, F: IsFFTField> {
+    pub main: &'a [FieldElement<F>],
+    pub auxiliary: &'a [FieldElement<F>],
+}
+
+/// Evaluations of the parts of the composition polynomial opened at a query.
+#[derive(Debug, Clone)]
+pub struct CompositionPolyOpenings<'a, F: IsFFTField> {
+    // H₁(𝜐₀)
+    pub even: &'a FieldElement<F>,
+    // H₂(𝜐₀)
+    pub odd: &'a FieldElement<F>,
+    // M(𝜐₀), if the DEEP composition polynomial is masked
+    pub mask: Option<&'a FieldElement<F>>,
+}
+
+impl<F: IsFFTField> DeepPolynomialOpenings<F> {
+    /// The opened trace evaluations, the first `num_main_columns` of them being the
+    /// ones of the main trace. If there are fewer evaluations, they are all main.
+    pub fn trace_openings(&self, num_main_columns: usize) -> TraceOpenings<'_, F> {
+        let (main, auxiliary) = self
+            .lde_trace_evaluations
+            .split_at(num_main_columns.min(self.lde_trace_evaluations.len()));
+        TraceOpenings { main, auxiliary }
+    }
+
+    pub fn composition_poly_openings(&self) -> CompositionPolyOpenings<'_, F> {
+        CompositionPolyOpenings {
+            even: &self.lde_composition_poly_even_evaluation,
+            odd: &self.lde_composition_poly_odd_evaluation,
+            mask: self.lde_composition_poly_mask_evaluation.as_ref(),
+        }
+    }
+
+    /// The unsalted leaf of [H₁] and [H₂] opened: H₁(𝜐₀), H₂(𝜐₀) and M(𝜐₀), if
+    /// there is a mask.
+    pub fn composition_poly_leaf(&self) -> Vec<FieldElement<F>> {
+        let openings = self.composition_poly_openings();
+        let mut leaf = vec![openings.even.clone(), openings.odd.clone()];
+        leaf.extend(openings.mask.cloned());
+        leaf
+    }
+}
+
+/// Everything a proof opens at a query: the index 𝜐ₛ of the LDE domain sampled
+/// by the verifier, the DEEP openings of the trace and composition polynomials and
+/// the FRI decommitment.
+#[derive(Debug, Clone)]
+pub struct QueryOpenings<'a, F: IsFFTField> {
+    pub iota: usize,
+    pub deep_poly_openings: &'a DeepPolynomialOpenings<F>,
+    pub fri_decommitment: &'a FriDecommitment<F>,
 }
 
 #[derive(Debug)]
 pub struct StarkProof<F: IsFFTField> {
+    // Field of the proof, written first when it's serialized
+    pub field_id: FieldId,
     // Length of the execution trace
     pub trace_length: usize,
-    // Commitments of the trace columns
+    // Options the proof was generated with
+    pub options: ProofOptions,
+    // Commitments of the trace columns, each one the cap of its tree
     // [tⱼ]
-    pub lde_trace_merkle_roots: Vec<Commitment>,
-    // tⱼ(zgᵏ)
-    pub trace_ood_frame_evaluations: Frame<F>,
-    // [H₁] and [H₂]
-    pub composition_poly_root: Commitment,
+    pub lde_trace_merkle_caps: Vec<MerkleCap>,
+    // tⱼ(zgᵏ), a row for each offset k of the frame of the AIR
+    pub trace_ood_frame_evaluations: Frame<'static, F>,
+    // [H₁, H₂], a single tree whose leaves hold H₁, H₂ and M
+    pub composition_poly_cap: MerkleCap,
     // H₁(z²)
     pub composition_poly_even_ood_evaluation: FieldElement<F>,
     // H₂(z²)
     pub composition_poly_odd_ood_evaluation: FieldElement<F>,
-    // [pₖ]
-    pub fri_layers_merkle_roots: Vec<Commitment>,
-    // pₙ
-    pub fri_last_value: FieldElement<F>,
-    // Open(p₀(D₀), 𝜐ₛ), Opwn(pₖ(Dₖ), −𝜐ₛ^(2ᵏ))
+    // M(z²), if the DEEP composition polynomial is masked
+    pub composition_poly_mask_ood_evaluation: Option<FieldElement<F>>,
+    // H₁(w) and H₂(w), if the AIR opens them at an extra point w
+    pub composition_poly_extra_ood_evaluations: Option<(FieldElement<F>, FieldElement<F>)>,
+    // [pₖ], starting with p₀, the batch of every DEEP term
+    pub fri_layers_merkle_caps: Vec<MerkleCap>,
+    // pₙ, by its coefficients
+    pub fri_last_poly: Vec<FieldElement<F>>,
+    // Open(pₖ(Dₖ), 𝜐ₛ^(2ᵏ)) for k > 0, Open(pₖ(Dₖ), −𝜐ₛ^(2ᵏ))
     pub query_list: Vec<FriDecommitment<F>>,
-    // Open(H₁(D_LDE, 𝜐₀), Open(H₂(D_LDE, 𝜐₀), Open(tⱼ(D_LDE), 𝜐₀)
+    // Open((H₁, H₂)(D_LDE), 𝜐ₛ), Open(tⱼ(D_LDE), 𝜐ₛ), from which p₀(𝜐ₛ) is computed
     pub deep_poly_openings: Vec<DeepPolynomialOpenings<F>>,
     // nonce obtained from grinding
     pub nonce: u64,
 }
 
+/// Roots of the commitments of a proof, computed from their caps. They are the
+/// commitments absorbed in the transcript, so the challenges don't depend on the
+/// height of the caps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommitmentRoots {
+    // [tⱼ]
+    pub lde_trace: Vec<Commitment>,
+    // [H₁, H₂]
+    pub composition_poly: Commitment,
+    // [pₖ]
+    pub fri_layers: Vec<Commitment>,
+}
+
+/// Maximum lengths of the lists of a proof, each one checked before the list is
+/// read, so a proof can't make its reader allocate or loop more than its
+/// parameters justify. See `StarkProof::deserialize_strict`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofLimits {
+    /// FRI layers, also bounding the openings of each query in them
+    pub fri_layers: usize,
+    /// Coefficients of the last FRI polynomial
+    pub fri_last_poly_len: usize,
+    /// Queries, each one with a FRI decommitment and DEEP openings
+    pub queries: usize,
+    /// Nodes of each Merkle path, the height of the trees over the LDE domain
+    pub merkle_path_len: usize,
+    /// Nodes of each Merkle cap
+    pub merkle_cap_len: usize,
+    /// Trace commitments, also bounding the trace paths and salts of each query
+    pub trace_commitments: usize,
+    /// Trace columns, also bounding the trace evaluations of each query
+    pub trace_columns: usize,
+}
+
+impl ProofLimits {
+    /// Largest LDE domain of the proofs accepted by `deserialize_strict` and the
+    /// verifiers, which build the whole domain: its roots of unity take 8 GiB with
+    /// the 32-byte elements of Stark252. Larger trace lengths would make them abort
+    /// out of memory instead of rejecting the proof.
+    pub const MAX_LDE_DOMAIN_SIZE: usize = 1 << 28;
+
+    /// No limits but the size of the input.
+    pub const NONE: Self = Self {
+        fri_layers: usize::MAX,
+        fri_last_poly_len: usize::MAX,
+        queries: usize::MAX,
+        merkle_path_len: usize::MAX,
+        merkle_cap_len: usize::MAX,
+        trace_commitments: usize::MAX,
+        trace_columns: usize::MAX,
+    };
+
+    /// Limits of a proof over `F` of a trace of `trace_length` rows, generated with
+    /// `options`. `None` if they define no LDE domain, or one larger than
+    /// `MAX_LDE_DOMAIN_SIZE`. The trace commitments and columns are left unbounded,
+    /// as they depend on the AIR.
+    pub fn of_proof<F: IsFFTField>(trace_length: usize, options: &ProofOptions) -> Option<Self> {
+        let lde_domain_size = lde_domain_size::<F>(trace_length, options.blowup_factor)
+            .filter(|size| *size <= Self::MAX_LDE_DOMAIN_SIZE)?;
+        let merkle_path_len = lde_domain_size.trailing_zeros() as usize;
+        Some(Self {
+            fri_layers: options.fri_number_of_layers(trace_length),
+            fri_last_poly_len: options.fri_last_poly_length(trace_length),
+            queries: options.fri_number_of_queries,
+            merkle_path_len,
+            merkle_cap_len: 1 << options.merkle_cap_height.min(merkle_path_len),
+            ..Self::NONE
+        })
+    }
+}
+
+/// Serializes an optional field element as a flag byte followed by its value.
+fn serialize_optional_felt<F>(bytes: &mut Vec<u8>, felt: &Option<FieldElement<F>>)
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    match felt {
+        Some(felt) => {
+            bytes.push(1);
+            bytes.extend(felt.to_bytes_be());
+        }
+        None => bytes.push(0),
+    }
+}
+
+fn deserialize_optional_felt<F>(
+    bytes: &[u8],
+    felt_len: usize,
+) -> Result<(Option<FieldElement<F>>, &[u8]), DeserializationError>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    match bytes.first() {
+        Some(0) => Ok((None, &bytes[1..])),
+        Some(1) => {
+            let (felt, bytes) = deserialize_felt(&bytes[1..], felt_len)?;
+            Ok((Some(felt), bytes))
+        }
+        _ => Err(DeserializationError::InvalidAmountOfBytes),
+    }
+}
+
+/// Serializes an optional pair of field elements as a flag byte followed by
+/// both values.
+fn serialize_optional_felt_pair<F>(
+    bytes: &mut Vec<u8>,
+    felts: &Option<(FieldElement<F>, FieldElement<F>)>,
+) where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    match felts {
+        Some((first, second)) => {
+            bytes.push(1);
+            bytes.extend(first.to_bytes_be());
+            bytes.extend(second.to_bytes_be());
+        }
+        None => bytes.push(0),
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn deserialize_optional_felt_pair<F>(
+    bytes: &[u8],
+    felt_len: usize,
+) -> Result<(Option<(FieldElement<F>, FieldElement<F>)>, &[u8]), DeserializationError>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    match bytes.first() {
+        Some(0) => Ok((None, &bytes[1..])),
+        Some(1) => {
+            let (first, bytes) = deserialize_felt(&bytes[1..], felt_len)?;
+            let (second, bytes) = deserialize_felt(bytes, felt_len)?;
+            Ok((Some((first, second)), bytes))
+        }
+        _ => Err(DeserializationError::InvalidAmountOfBytes),
+    }
+}
+
+impl<F: IsFFTField> StarkProof<F> {
+    /// The openings of each query, paired with the indexes `iotas` sampled by the
+    /// verifier. Queries past the shortest of the three lists are skipped, so their
+    /// lengths must be checked before.
+    pub fn query_openings<'a>(
+        &'a self,
+        iotas: &'a [usize],
+    ) -> impl Iterator<Item = QueryOpenings<'a, F>> + 'a {
+        iotas
+            .iter()
+            .zip(&self.deep_poly_openings)
+            .zip(&self.query_list)
+            .map(
+                |((iota, deep_poly_openings), fri_decommitment)| QueryOpenings {
+                    iota: *iota,
+                    deep_poly_openings,
+                    fri_decommitment,
+                },
+            )
+    }
+}
+
+impl<F> StarkProof<F>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    /// The roots of the commitments of the proof, hashing their caps with the hash
+    /// of its options. `None` if a cap has a size other than a power of two.
+    pub fn commitment_roots(&self) -> Option<CommitmentRoots> {
+        let options = &self.options;
+        Some(CommitmentRoots {
+            lde_trace: self
+                .lde_trace_merkle_caps
+                .iter()
+                .map(|cap| BatchedVectorCommitment::<F>::commitment_root(options, cap))
+                .collect::<Option<_>>()?,
+            composition_poly: BatchedVectorCommitment::<F>::commitment_root(
+                options,
+                &self.composition_poly_cap,
+            )?,
+            fri_layers: self
+                .fri_layers_merkle_caps
+                .iter()
+                .map(|cap| FriVectorCommitment::<F>::commitment_root(options, cap))
+                .collect::<Option<_>>()?,
+        })
+    }
+}
+
 impl<F> Serializable for DeepPolynomialOpenings<F>
 where
     F: IsFFTField,
@@ -72,6 +345,18 @@ where
         for evaluation in &self.lde_trace_evaluations {
             bytes.extend(evaluation.to_bytes_be());
         }
+        match &self.lde_composition_poly_salt {
+            Some(salt) => {
+                bytes.push(1);
+                bytes.extend(salt);
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend(self.lde_trace_salts.len().to_be_bytes());
+        for salt in &self.lde_trace_salts {
+            bytes.extend(salt);
+        }
+        serialize_optional_felt(&mut bytes, &self.lde_composition_poly_mask_evaluation);
         bytes
     }
 }
@@ -85,75 +370,89 @@ where
     where
         Self: Sized,
     {
-        let mut bytes = bytes;
-        let lde_composition_poly_proof;
-        (lde_composition_poly_proof, bytes) = deserialize_proof(bytes)?;
-
-        let felt_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
-        bytes = &bytes[8..];
-
-        let lde_composition_poly_even_evaluation = FieldElement::from_bytes_be(
-            bytes
-                .get(..felt_len)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-        )?;
-        bytes = &bytes[felt_len..];
-
-        let lde_composition_poly_odd_evaluation = FieldElement::from_bytes_be(
-            bytes
-                .get(..felt_len)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-        )?;
-        bytes = &bytes[felt_len..];
-
-        let lde_trace_merkle_proofs_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
-        bytes = &bytes[8..];
+        Self::deserialize_with_limits(bytes, &ProofLimits::NONE)
+    }
+}
 
-        let mut lde_trace_merkle_proofs = vec![];
+impl<F> DeepPolynomialOpenings<F>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    /// Deserializes openings of at most `limits.trace_commitments` trace
+    /// commitments and `limits.trace_columns` columns, whose paths have at most
+    /// `limits.merkle_path_len` nodes.
+    pub(crate) fn deserialize_with_limits(
+        bytes: &[u8],
+        limits: &ProofLimits,
+    ) -> Result<Self, DeserializationError> {
+        let (lde_composition_poly_proof, bytes) = deserialize_proof(bytes, limits.merkle_path_len)?;
+
+        let (felt_len, bytes) = deserialize_felt_len::<F>(bytes)?;
+
+        let (lde_composition_poly_even_evaluation, bytes) = deserialize_felt(bytes, felt_len)?;
+        let (lde_composition_poly_odd_evaluation, mut bytes) = deserialize_felt(bytes, felt_len)?;
+
+        let lde_trace_merkle_proofs_len;
+        (lde_trace_merkle_proofs_len, bytes) = deserialize_len(bytes, 8, limits.trace_commitments)?;
+        let mut lde_trace_merkle_proofs = Vec::with_capacity(lde_trace_merkle_proofs_len);
         for _ in 0..lde_trace_merkle_proofs_len {
             let proof;
-            (proof, bytes) = deserialize_proof(bytes)?;
+            (proof, bytes) = deserialize_proof(bytes, limits.merkle_path_len)?;
             lde_trace_merkle_proofs.push(proof);
         }
 
-        let lde_trace_evaluations_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
-        bytes = &bytes[8..];
-
-        let mut lde_trace_evaluations = vec![];
+        let lde_trace_evaluations_len;
+        (lde_trace_evaluations_len, bytes) =
+            deserialize_len(bytes, felt_len, limits.trace_columns)?;
+        let mut lde_trace_evaluations = Vec::with_capacity(lde_trace_evaluations_len);
         for _ in 0..lde_trace_evaluations_len {
-            let evaluation = FieldElement::from_bytes_be(
-                bytes
-                    .get(..felt_len)
-                    .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-            )?;
-            bytes = &bytes[felt_len..];
+            let evaluation;
+            (evaluation, bytes) = deserialize_felt(bytes, felt_len)?;
             lde_trace_evaluations.push(evaluation);
         }
 
+        let lde_composition_poly_salt = match bytes.first() {
+            Some(0) => {
+                bytes = &bytes[1..];
+                None
+            }
+            Some(1) => {
+                let salt = bytes
+                    .get(1..1 + SALT_SIZE)
+                    .ok_or(DeserializationError::InvalidAmountOfBytes)?
+                    .try_into()
+                    .map_err(|_| DeserializationError::InvalidAmountOfBytes)?;
+                bytes = &bytes[1 + SALT_SIZE..];
+                Some(salt)
+            }
+            _ => return Err(DeserializationError::InvalidAmountOfBytes),
+        };
+
+        let lde_trace_salts_len;
+        (lde_trace_salts_len, bytes) = deserialize_len(bytes, SALT_SIZE, limits.trace_commitments)?;
+        let mut lde_trace_salts = Vec::with_capacity(lde_trace_salts_len);
+        for _ in 0..lde_trace_salts_len {
+            let salt = bytes[..SALT_SIZE]
+                .try_into()
+                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?;
+            bytes = &bytes[SALT_SIZE..];
+            lde_trace_salts.push(salt);
+        }
+
+        let (lde_composition_poly_mask_evaluation, bytes) =
+            deserialize_optional_felt(bytes, felt_len)?;
+        check_fully_read(bytes)?;
+
         Ok(DeepPolynomialOpenings {
             lde_composition_poly_proof,
             lde_composition_poly_even_evaluation,
             lde_composition_poly_odd_evaluation,
+            lde_composition_poly_mask_evaluation,
+            lde_composition_poly_salt,
             lde_trace_merkle_proofs,
             lde_trace_evaluations,
+            lde_trace_salts,
         })
     }
 }
@@ -164,20 +463,44 @@ where
     FieldElement<F>: ByteConversion,
 {
     fn serialize(&self) -> Vec<u8> {
-        let mut bytes = vec![];
+        self.serialize_with_queries(&self.query_list, &self.deep_poly_openings)
+    }
+}
+
+impl<F> StarkProof<F>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    /// Serializes the proof without the openings of its queries. It deserializes
+    /// to the same proof with no queries, see `proof::stream`.
+    pub(crate) fn serialize_preamble(&self) -> Vec<u8> {
+        self.serialize_with_queries(&[], &[])
+    }
+
+    fn serialize_with_queries(
+        &self,
+        query_list: &[FriDecommitment<F>],
+        deep_poly_openings: &[DeepPolynomialOpenings<F>],
+    ) -> Vec<u8> {
+        let mut bytes = vec![self.field_id.to_byte()];
 
         // Serialize trace length
-        bytes.extend(self.trace_length.to_be_bytes());
+        bytes.extend((self.trace_length as u64).to_be_bytes());
 
-        bytes.extend(self.lde_trace_merkle_roots.len().to_be_bytes());
-        for commitment in &self.lde_trace_merkle_roots {
-            bytes.extend(commitment);
+        let options_bytes = self.options.serialize();
+        bytes.extend(options_bytes.len().to_be_bytes());
+        bytes.extend(options_bytes);
+
+        bytes.extend(self.lde_trace_merkle_caps.len().to_be_bytes());
+        for cap in &self.lde_trace_merkle_caps {
+            bytes.extend(serialize_cap(cap));
         }
         let trace_ood_frame_evaluations_bytes = self.trace_ood_frame_evaluations.serialize();
         bytes.extend(trace_ood_frame_evaluations_bytes.len().to_be_bytes());
         bytes.extend(trace_ood_frame_evaluations_bytes);
 
-        bytes.extend(self.composition_poly_root);
+        bytes.extend(serialize_cap(&self.composition_poly_cap));
 
         let composition_poly_even_ood_evaluation_bytes =
             self.composition_poly_even_ood_evaluation.to_bytes_be();
@@ -188,23 +511,28 @@ where
         );
         bytes.extend(composition_poly_even_ood_evaluation_bytes);
         bytes.extend(self.composition_poly_odd_ood_evaluation.to_bytes_be());
+        serialize_optional_felt(&mut bytes, &self.composition_poly_mask_ood_evaluation);
+        serialize_optional_felt_pair(&mut bytes, &self.composition_poly_extra_ood_evaluations);
 
-        bytes.extend(self.fri_layers_merkle_roots.len().to_be_bytes());
-        for commitment in &self.fri_layers_merkle_roots {
-            bytes.extend(commitment);
+        bytes.extend(self.fri_layers_merkle_caps.len().to_be_bytes());
+        for cap in &self.fri_layers_merkle_caps {
+            bytes.extend(serialize_cap(cap));
         }
 
-        bytes.extend(self.fri_last_value.to_bytes_be());
+        bytes.extend(self.fri_last_poly.len().to_be_bytes());
+        for coefficient in &self.fri_last_poly {
+            bytes.extend(coefficient.to_bytes_be());
+        }
 
-        bytes.extend(self.query_list.len().to_be_bytes());
-        for query in &self.query_list {
+        bytes.extend(query_list.len().to_be_bytes());
+        for query in query_list {
             let query_bytes = query.serialize();
             bytes.extend(query_bytes.len().to_be_bytes());
             bytes.extend(query_bytes);
         }
 
-        bytes.extend(self.deep_poly_openings.len().to_be_bytes());
-        for opening in &self.deep_poly_openings {
+        bytes.extend(deep_poly_openings.len().to_be_bytes());
+        for opening in deep_poly_openings {
             let opening_bytes = opening.serialize();
             bytes.extend(opening_bytes.len().to_be_bytes());
             bytes.extend(opening_bytes);
@@ -219,217 +547,191 @@ where
 
 impl<F> Deserializable for StarkProof<F>
 where
-    F: IsFFTField,
+    F: IsFFTField + IdentifiedField,
     FieldElement<F>: ByteConversion,
 {
+    /// Only the bytes `serialize` writes are accepted: the field elements and the
+    /// coset offset must be canonical and nothing may follow the nonce, so hashes of
+    /// the serialized proofs identify them.
     fn deserialize(bytes: &[u8]) -> Result<Self, DeserializationError>
     where
         Self: Sized,
     {
-        let mut bytes = bytes;
-        let trace_length_buffer_size = mem::size_of::<usize>();
-        let trace_length = usize::from_be_bytes(
-            bytes
-                .get(..trace_length_buffer_size)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
+        Self::deserialize_with_limits(bytes, false)
+    }
+}
 
-        bytes = &bytes[8..];
+impl<F> StarkProof<F>
+where
+    F: IsFFTField + IdentifiedField,
+    FieldElement<F>: ByteConversion,
+{
+    /// Deserializes a proof from untrusted bytes. Besides the checks of
+    /// `deserialize`, the length of each list is checked against the
+    /// `ProofLimits::of_proof` of its trace length and options before it's read,
+    /// the openings of each query against the trace commitments and out-of-domain
+    /// frame, and the frame must be made of whole rows.
+    pub fn deserialize_strict(bytes: &[u8]) -> Result<Self, DeserializationError> {
+        Self::deserialize_with_limits(bytes, true)
+    }
 
-        let lde_trace_merkle_roots_len = usize::from_be_bytes(
+    fn deserialize_with_limits(bytes: &[u8], strict: bool) -> Result<Self, DeserializationError> {
+        let (field_id, mut bytes) = bytes
+            .split_first()
+            .ok_or(DeserializationError::InvalidAmountOfBytes)?;
+        // Proofs over another field are rejected before reading their elements
+        let field_id = FieldId::from_byte(*field_id);
+        if field_id != F::FIELD_ID {
+            return Err(DeserializationError::InvalidValue);
+        }
+        let trace_length = u64::from_be_bytes(
             bytes
                 .get(..8)
                 .ok_or(DeserializationError::InvalidAmountOfBytes)?
                 .try_into()
                 .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
         );
+        let trace_length =
+            usize::try_from(trace_length).map_err(|_| DeserializationError::InvalidValue)?;
 
         bytes = &bytes[8..];
 
-        let mut lde_trace_merkle_roots: Vec<[u8; 32]> = vec![];
-        for _ in 0..lde_trace_merkle_roots_len {
-            let commitment = bytes
-                .get(..32)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?;
+        let options_len;
+        (options_len, bytes) = deserialize_len(bytes, 1, usize::MAX)?;
 
-            lde_trace_merkle_roots.push(commitment);
-            bytes = &bytes[32..];
+        let options = ProofOptions::deserialize(&bytes[..options_len])?;
+        // The offset is an element of `F`, so only its canonical encoding is accepted
+        if CosetOffset::from_field_element(&options.coset_offset.to_field_element::<F>())
+            != options.coset_offset
+        {
+            return Err(DeserializationError::InvalidValue);
         }
 
-        let trace_ood_frame_evaluations_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
+        bytes = &bytes[options_len..];
 
-        bytes = &bytes[8..];
+        let mut limits = if strict {
+            ProofLimits::of_proof::<F>(trace_length, &options)
+                .ok_or(DeserializationError::InvalidValue)?
+        } else {
+            ProofLimits::NONE
+        };
 
-        let trace_ood_frame_evaluations: Frame<F> = Frame::deserialize(
-            bytes
-                .get(..trace_ood_frame_evaluations_len)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-        )?;
+        let lde_trace_merkle_caps_len;
+        (lde_trace_merkle_caps_len, bytes) = deserialize_len(bytes, 8, limits.trace_commitments)?;
 
-        bytes = &bytes[trace_ood_frame_evaluations_len..];
+        let mut lde_trace_merkle_caps = Vec::with_capacity(lde_trace_merkle_caps_len);
+        for _ in 0..lde_trace_merkle_caps_len {
+            let cap;
+            (cap, bytes) = deserialize_cap(bytes, limits.merkle_cap_len)?;
+            lde_trace_merkle_caps.push(cap);
+        }
 
-        let composition_poly_root = bytes
-            .get(..32)
-            .ok_or(DeserializationError::InvalidAmountOfBytes)?
-            .try_into()
-            .map_err(|_| DeserializationError::InvalidAmountOfBytes)?;
+        let trace_ood_frame_evaluations_len;
+        (trace_ood_frame_evaluations_len, bytes) = deserialize_len(bytes, 1, usize::MAX)?;
 
-        bytes = &bytes[32..];
+        let trace_ood_frame_evaluations: Frame<F> =
+            Frame::deserialize(&bytes[..trace_ood_frame_evaluations_len])?;
 
-        let felt_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
+        bytes = &bytes[trace_ood_frame_evaluations_len..];
 
-        bytes = &bytes[8..];
+        if strict {
+            if !trace_ood_frame_evaluations.has_whole_rows() {
+                return Err(DeserializationError::InvalidValue);
+            }
+            limits.trace_commitments = lde_trace_merkle_caps.len();
+            limits.trace_columns = trace_ood_frame_evaluations.num_columns();
+        }
 
-        let composition_poly_even_ood_evaluation = FieldElement::from_bytes_be(
-            bytes
-                .get(..felt_len)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-        )?;
+        let composition_poly_cap;
+        (composition_poly_cap, bytes) = deserialize_cap(bytes, limits.merkle_cap_len)?;
 
-        bytes = &bytes[felt_len..];
+        let felt_len;
+        (felt_len, bytes) = deserialize_felt_len::<F>(bytes)?;
 
-        let composition_poly_odd_ood_evaluation = FieldElement::from_bytes_be(
-            bytes
-                .get(..felt_len)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-        )?;
+        let composition_poly_even_ood_evaluation;
+        (composition_poly_even_ood_evaluation, bytes) = deserialize_felt(bytes, felt_len)?;
 
-        bytes = &bytes[felt_len..];
+        let composition_poly_odd_ood_evaluation;
+        (composition_poly_odd_ood_evaluation, bytes) = deserialize_felt(bytes, felt_len)?;
 
-        let fri_layers_merkle_roots_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
+        let composition_poly_mask_ood_evaluation;
+        (composition_poly_mask_ood_evaluation, bytes) = deserialize_optional_felt(bytes, felt_len)?;
 
-        bytes = &bytes[8..];
+        let composition_poly_extra_ood_evaluations;
+        (composition_poly_extra_ood_evaluations, bytes) =
+            deserialize_optional_felt_pair(bytes, felt_len)?;
 
-        let mut fri_layers_merkle_roots: Vec<[u8; 32]> = vec![];
-        for _ in 0..fri_layers_merkle_roots_len {
-            let commitment = bytes
-                .get(..32)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?;
-            fri_layers_merkle_roots.push(commitment);
-            bytes = &bytes[32..];
-        }
+        let fri_layers_merkle_caps_len;
+        (fri_layers_merkle_caps_len, bytes) = deserialize_len(bytes, 8, limits.fri_layers)?;
 
-        let fri_last_value = FieldElement::from_bytes_be(
-            bytes
-                .get(..felt_len)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-        )?;
+        let mut fri_layers_merkle_caps = Vec::with_capacity(fri_layers_merkle_caps_len);
+        for _ in 0..fri_layers_merkle_caps_len {
+            let cap;
+            (cap, bytes) = deserialize_cap(bytes, limits.merkle_cap_len)?;
+            fri_layers_merkle_caps.push(cap);
+        }
 
-        bytes = &bytes[felt_len..];
+        let fri_last_poly_len;
+        (fri_last_poly_len, bytes) = deserialize_len(bytes, felt_len, limits.fri_last_poly_len)?;
 
-        let query_list_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
+        let mut fri_last_poly = Vec::with_capacity(fri_last_poly_len);
+        for _ in 0..fri_last_poly_len {
+            let coefficient;
+            (coefficient, bytes) = deserialize_felt(bytes, felt_len)?;
+            fri_last_poly.push(coefficient);
+        }
 
-        bytes = &bytes[8..];
+        let query_list_len;
+        (query_list_len, bytes) = deserialize_len(bytes, 8, limits.queries)?;
 
-        let mut query_list = vec![];
+        let mut query_list = Vec::with_capacity(query_list_len);
         for _ in 0..query_list_len {
-            let query_len = usize::from_be_bytes(
-                bytes
-                    .get(..8)
-                    .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                    .try_into()
-                    .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-            );
+            let query_len;
+            (query_len, bytes) = deserialize_len(bytes, 1, usize::MAX)?;
 
-            bytes = &bytes[8..];
-
-            let query = FriDecommitment::deserialize(
-                bytes
-                    .get(..query_len)
-                    .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-            )?;
+            let query = FriDecommitment::deserialize_with_limits(&bytes[..query_len], &limits)?;
 
             bytes = &bytes[query_len..];
 
             query_list.push(query);
         }
 
-        let deep_poly_openings_len = usize::from_be_bytes(
-            bytes
-                .get(..8)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                .try_into()
-                .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-        );
-
-        bytes = &bytes[8..];
+        let deep_poly_openings_len;
+        (deep_poly_openings_len, bytes) = deserialize_len(bytes, 8, limits.queries)?;
 
-        let mut deep_poly_openings = vec![];
+        let mut deep_poly_openings = Vec::with_capacity(deep_poly_openings_len);
         for _ in 0..deep_poly_openings_len {
-            let opening_len = usize::from_be_bytes(
-                bytes
-                    .get(..8)
-                    .ok_or(DeserializationError::InvalidAmountOfBytes)?
-                    .try_into()
-                    .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
-            );
+            let opening_len;
+            (opening_len, bytes) = deserialize_len(bytes, 1, usize::MAX)?;
 
-            bytes = &bytes[8..];
-
-            let opening = DeepPolynomialOpenings::deserialize(
-                bytes
-                    .get(..opening_len)
-                    .ok_or(DeserializationError::InvalidAmountOfBytes)?,
-            )?;
+            let opening =
+                DeepPolynomialOpenings::deserialize_with_limits(&bytes[..opening_len], &limits)?;
 
             bytes = &bytes[opening_len..];
 
             deep_poly_openings.push(opening);
         }
 
-        // deserialize nonce
-        let start_nonce = bytes
-            .len()
-            .checked_sub(core::mem::size_of::<u64>())
-            .ok_or(DeserializationError::InvalidAmountOfBytes)?;
-
+        // deserialize nonce, the last field of the proof
         let nonce = u64::from_be_bytes(
             bytes
-                .get(start_nonce..)
-                .ok_or(DeserializationError::InvalidAmountOfBytes)?
                 .try_into()
                 .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
         );
 
         Ok(StarkProof {
+            field_id,
             trace_length,
-            lde_trace_merkle_roots,
+            options,
+            lde_trace_merkle_caps,
             trace_ood_frame_evaluations,
-            composition_poly_root,
+            composition_poly_cap,
             composition_poly_even_ood_evaluation,
             composition_poly_odd_ood_evaluation,
-            fri_layers_merkle_roots,
-            fri_last_value,
+            composition_poly_mask_ood_evaluation,
+            composition_poly_extra_ood_evaluations,
+            fri_layers_merkle_caps,
+            fri_last_poly,
             query_list,
             deep_poly_openings,
             nonce,
@@ -443,7 +745,10 @@ mod test {
     use lambdaworks_math::{
         errors::DeserializationError,
         field::{
-            element::FieldElement, fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+            element::FieldElement,
+            fields::{
+                fft_friendly::stark_252_prime_field::Stark252PrimeField, u64_prime_field::F17,
+            },
         },
     };
     use proptest::{collection, prelude::*, prop_compose, proptest};
@@ -454,15 +759,16 @@ mod test {
             runner::run::{cairo0_program_path, generate_prover_args, CairoVersion},
         },
         starks::{
-            config::{Commitment, COMMITMENT_SIZE},
+            config::{Commitment, MerkleCap, COMMITMENT_SIZE},
             frame::Frame,
             fri::fri_decommit::FriDecommitment,
             proof::options::ProofOptions,
+            utils::{deserialize_cap, serialize_cap},
         },
     };
-    use lambdaworks_math::traits::{Deserializable, Serializable};
+    use lambdaworks_math::traits::{ByteConversion, Deserializable, Serializable};
 
-    use super::{DeepPolynomialOpenings, StarkProof};
+    use super::{DeepPolynomialOpenings, FieldId, ProofLimits, StarkProof};
 
     type FE = FieldElement<Stark252PrimeField>;
 
@@ -481,6 +787,12 @@ mod test {
         }
     }
 
+    prop_compose! {
+        fn cap_vec()(vec in collection::vec(commitment_vec(), (1_usize, 4_usize))) -> Vec<MerkleCap> {
+            vec
+        }
+    }
+
     prop_compose! {
         fn some_proof()(merkle_path in commitment_vec()) -> Proof<Commitment> {
             Proof{merkle_path}
@@ -532,15 +844,21 @@ mod test {
             lde_composition_poly_proof in some_proof(),
             lde_composition_poly_even_evaluation in some_felt(),
             lde_composition_poly_odd_evaluation in some_felt(),
+            lde_composition_poly_mask_evaluation in proptest::option::of(some_felt()),
+            lde_composition_poly_salt in proptest::option::of(some_commitment()),
             lde_trace_merkle_proofs in proof_vec(),
-            lde_trace_evaluations in field_vec()
+            lde_trace_evaluations in field_vec(),
+            lde_trace_salts in commitment_vec()
         ) -> DeepPolynomialOpenings<Stark252PrimeField> {
             DeepPolynomialOpenings {
                 lde_composition_poly_proof,
                 lde_composition_poly_even_evaluation,
                 lde_composition_poly_odd_evaluation,
+                lde_composition_poly_mask_evaluation,
+                lde_composition_poly_salt,
                 lde_trace_merkle_proofs,
-                lde_trace_evaluations
+                lde_trace_evaluations,
+                lde_trace_salts
             }
         }
     }
@@ -552,7 +870,7 @@ mod test {
     }
 
     prop_compose! {
-        fn some_frame()(data in field_vec(), row_width in any::<usize>()) -> Frame<Stark252PrimeField> {
+        fn some_frame()(data in field_vec(), row_width in any::<usize>()) -> Frame<'static, Stark252PrimeField> {
             Frame::new(data, row_width)
         }
     }
@@ -566,26 +884,32 @@ mod test {
     prop_compose! {
         fn some_stark_proof()(
             trace_length in some_usize(),
-            lde_trace_merkle_roots in commitment_vec(),
+            lde_trace_merkle_caps in cap_vec(),
             trace_ood_frame_evaluations in some_frame(),
-            composition_poly_root in some_commitment(),
+            composition_poly_cap in commitment_vec(),
             composition_poly_even_ood_evaluation in some_felt(),
             composition_poly_odd_ood_evaluation in some_felt(),
-            fri_layers_merkle_roots in commitment_vec(),
-            fri_last_value in some_felt(),
+            composition_poly_mask_ood_evaluation in proptest::option::of(some_felt()),
+            composition_poly_extra_ood_evaluations in proptest::option::of((some_felt(), some_felt())),
+            fri_layers_merkle_caps in cap_vec(),
+            fri_last_poly in field_vec(),
             query_list in fri_decommitment_vec(),
             deep_poly_openings in deep_polynomial_openings_vec()
 
     ) -> StarkProof<Stark252PrimeField> {
             StarkProof {
+                field_id: FieldId::STARK_252,
                 trace_length,
-                lde_trace_merkle_roots,
+                options: ProofOptions::default_test_options(),
+                lde_trace_merkle_caps,
                 trace_ood_frame_evaluations,
-                composition_poly_root,
+                composition_poly_cap,
                 composition_poly_even_ood_evaluation,
                 composition_poly_odd_ood_evaluation,
-                fri_layers_merkle_roots,
-                fri_last_value,
+                composition_poly_mask_ood_evaluation,
+                composition_poly_extra_ood_evaluations,
+                fri_layers_merkle_caps,
+                fri_last_poly,
                 query_list,
                 deep_poly_openings,
                 nonce: 0
@@ -609,6 +933,26 @@ mod test {
             prop_assert_eq!(deep_polynomial_openings.lde_composition_poly_odd_evaluation, deserialized.lde_composition_poly_odd_evaluation);
             prop_assert_eq!(deep_polynomial_openings.lde_composition_poly_proof.merkle_path, deserialized.lde_composition_poly_proof.merkle_path);
             prop_assert_eq!(deep_polynomial_openings.lde_trace_evaluations, deserialized.lde_trace_evaluations);
+            prop_assert_eq!(deep_polynomial_openings.lde_composition_poly_salt, deserialized.lde_composition_poly_salt);
+            prop_assert_eq!(deep_polynomial_openings.lde_trace_salts, deserialized.lde_trace_salts);
+            prop_assert_eq!(deep_polynomial_openings.lde_composition_poly_mask_evaluation, deserialized.lde_composition_poly_mask_evaluation);
+        }
+
+        #[test]
+        fn test_deep_polynomial_openings_accessors(
+            deep_polynomial_openings in some_deep_polynomial_openings(),
+            num_main_columns in 0_usize..20,
+        ) {
+            let evaluations = &deep_polynomial_openings.lde_trace_evaluations;
+            let trace_openings = deep_polynomial_openings.trace_openings(num_main_columns);
+            prop_assert_eq!(trace_openings.main.len(), num_main_columns.min(evaluations.len()));
+            prop_assert_eq!(&[trace_openings.main, trace_openings.auxiliary].concat(), evaluations);
+
+            let leaf = deep_polynomial_openings.composition_poly_leaf();
+            prop_assert_eq!(&leaf[0], &deep_polynomial_openings.lde_composition_poly_even_evaluation);
+            prop_assert_eq!(&leaf[1], &deep_polynomial_openings.lde_composition_poly_odd_evaluation);
+            prop_assert_eq!(leaf.get(2), deep_polynomial_openings.lde_composition_poly_mask_evaluation.as_ref());
+            prop_assert!(leaf.len() <= 3);
         }
     }
 
@@ -621,9 +965,10 @@ mod test {
             let serialized = stark_proof.serialize();
             let deserialized = StarkProof::<Stark252PrimeField>::deserialize(&serialized).unwrap();
 
+            prop_assert_eq!(stark_proof.field_id, deserialized.field_id);
             prop_assert_eq!(
-                stark_proof.lde_trace_merkle_roots,
-                deserialized.lde_trace_merkle_roots
+                stark_proof.lde_trace_merkle_caps,
+                deserialized.lde_trace_merkle_caps
             );
             prop_assert_eq!(
                 stark_proof.trace_ood_frame_evaluations.num_columns(),
@@ -634,8 +979,8 @@ mod test {
                 deserialized.trace_ood_frame_evaluations.num_rows()
             );
             prop_assert_eq!(
-                stark_proof.composition_poly_root,
-                deserialized.composition_poly_root
+                stark_proof.composition_poly_cap,
+                deserialized.composition_poly_cap
             );
             prop_assert_eq!(
                 stark_proof.composition_poly_even_ood_evaluation,
@@ -646,10 +991,18 @@ mod test {
                 deserialized.composition_poly_odd_ood_evaluation
             );
             prop_assert_eq!(
-                stark_proof.fri_layers_merkle_roots,
-                deserialized.fri_layers_merkle_roots
+                stark_proof.composition_poly_mask_ood_evaluation,
+                deserialized.composition_poly_mask_ood_evaluation
             );
-            prop_assert_eq!(stark_proof.fri_last_value, deserialized.fri_last_value);
+            prop_assert_eq!(
+                stark_proof.composition_poly_extra_ood_evaluations,
+                deserialized.composition_poly_extra_ood_evaluations
+            );
+            prop_assert_eq!(
+                stark_proof.fri_layers_merkle_caps,
+                deserialized.fri_layers_merkle_caps
+            );
+            prop_assert_eq!(&stark_proof.fri_last_poly, &deserialized.fri_last_poly);
 
             for (a, b) in stark_proof
                 .query_list
@@ -748,7 +1101,8 @@ mod test {
         drop(main_trace);
         drop(proof);
 
-        for byte in proof_bytes.iter_mut().take(21664) {
+        // The field identifier is kept, so the proof is read until the bytes run out
+        for byte in proof_bytes.iter_mut().skip(1).take(21663) {
             *byte = 255;
         }
         proof_bytes = proof_bytes[0..517].to_vec();
@@ -761,6 +1115,26 @@ mod test {
         );
     }
 
+    #[test]
+    fn deserialize_proof_over_another_field_should_give_error() {
+        let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+        let (main_trace, pub_inputs) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof_options = ProofOptions::default_test_options();
+        let proof_bytes = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options)
+            .unwrap()
+            .serialize();
+
+        assert_eq!(
+            FieldId::of_proof_bytes(&proof_bytes),
+            Some(FieldId::STARK_252)
+        );
+        assert_eq!(
+            DeserializationError::InvalidValue,
+            StarkProof::<F17>::deserialize(&proof_bytes).err().unwrap()
+        );
+    }
+
     #[test]
     fn deserialize_empty_proof_should_give_error() {
         assert_eq!(
@@ -770,4 +1144,145 @@ mod test {
                 .unwrap()
         );
     }
+
+    #[test]
+    fn strict_deserialization_accepts_honest_proofs_and_rejects_extra_queries() {
+        let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+        let (main_trace, pub_inputs) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof_options = ProofOptions::default_test_options();
+        let mut proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+
+        let proof_bytes = proof.serialize();
+        let deserialized = StarkProof::<Stark252PrimeField>::deserialize_strict(&proof_bytes);
+        assert_eq!(deserialized.unwrap().serialize(), proof_bytes);
+
+        // One query more than the options ask for
+        proof.query_list.push(proof.query_list[0].clone());
+        proof
+            .deep_poly_openings
+            .push(proof.deep_poly_openings[0].clone());
+        let proof_bytes = proof.serialize();
+        assert!(StarkProof::<Stark252PrimeField>::deserialize(&proof_bytes).is_ok());
+        assert_eq!(
+            DeserializationError::InvalidAmountOfBytes,
+            StarkProof::<Stark252PrimeField>::deserialize_strict(&proof_bytes)
+                .err()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn proofs_have_a_single_encoding() {
+        let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+        let (main_trace, pub_inputs) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof_options = ProofOptions::default_test_options();
+        let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+        let proof_bytes = proof.serialize();
+
+        // Bytes after the nonce
+        let mut bytes = proof_bytes.clone();
+        bytes.push(0);
+        assert!(StarkProof::<Stark252PrimeField>::deserialize(&bytes).is_err());
+        assert!(StarkProof::<Stark252PrimeField>::deserialize_strict(&bytes).is_err());
+
+        // Bytes after the openings of a query, inside its length
+        let mut opening = proof.deep_poly_openings[0].serialize();
+        opening.push(0);
+        assert_eq!(
+            DeserializationError::InvalidAmountOfBytes,
+            DeepPolynomialOpenings::<Stark252PrimeField>::deserialize(&opening)
+                .err()
+                .unwrap()
+        );
+
+        // H₁(z²) written as a value larger than the modulus
+        let evaluation = proof.composition_poly_even_ood_evaluation.to_bytes_be();
+        let position = proof_bytes
+            .windows(evaluation.len())
+            .position(|window| window == evaluation)
+            .unwrap();
+        let mut bytes = proof_bytes;
+        bytes[position..position + evaluation.len()].fill(255);
+        assert_eq!(
+            DeserializationError::InvalidValue,
+            StarkProof::<Stark252PrimeField>::deserialize(&bytes)
+                .err()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn proofs_of_lde_domains_larger_than_the_limit_are_rejected() {
+        let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+        let (main_trace, pub_inputs) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof_options = ProofOptions::default_test_options();
+        let mut proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+
+        // A domain of the field, but one the verifier can't build
+        proof.trace_length = ProofLimits::MAX_LDE_DOMAIN_SIZE;
+        let bytes = proof.serialize();
+        assert!(StarkProof::<Stark252PrimeField>::deserialize(&bytes).is_ok());
+        assert_eq!(
+            DeserializationError::InvalidValue,
+            StarkProof::<Stark252PrimeField>::deserialize_strict(&bytes)
+                .err()
+                .unwrap()
+        );
+        assert!(!verify_cairo_proof(&proof, &pub_inputs, &proof_options));
+    }
+
+    #[test]
+    fn lists_longer_than_the_input_are_rejected_before_reading_them() {
+        // A decommitment claiming usize::MAX paths, each one of usize::MAX nodes
+        let mut bytes = usize::MAX.to_be_bytes().to_vec();
+        bytes.extend(usize::MAX.to_be_bytes());
+        bytes.extend([0; COMMITMENT_SIZE]);
+
+        assert_eq!(
+            DeserializationError::InvalidAmountOfBytes,
+            FriDecommitment::<Stark252PrimeField>::deserialize(&bytes)
+                .err()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn paths_longer_than_the_height_of_the_lde_domain_are_rejected() {
+        let limits =
+            ProofLimits::of_proof::<Stark252PrimeField>(16, &ProofOptions::default_test_options())
+                .unwrap();
+        let path = Proof {
+            merkle_path: vec![[0; COMMITMENT_SIZE]; limits.merkle_path_len + 1],
+        };
+        let decommitment = FriDecommitment::<Stark252PrimeField> {
+            layers_auth_paths_sym: vec![path],
+            layers_evaluations_sym: vec![FE::one()],
+            layers_auth_paths: vec![],
+            layers_evaluations: vec![],
+        };
+
+        let bytes = decommitment.serialize();
+        assert!(FriDecommitment::<Stark252PrimeField>::deserialize(&bytes).is_ok());
+        assert!(
+            FriDecommitment::<Stark252PrimeField>::deserialize_with_limits(&bytes, &limits)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn caps_with_more_nodes_than_the_options_are_rejected() {
+        let options = ProofOptions {
+            merkle_cap_height: 2,
+            ..ProofOptions::default_test_options()
+        };
+        let limits = ProofLimits::of_proof::<Stark252PrimeField>(16, &options).unwrap();
+        assert_eq!(limits.merkle_cap_len, 4);
+
+        let bytes = serialize_cap(&vec![[0; COMMITMENT_SIZE]; 5]);
+        assert!(deserialize_cap(&bytes, usize::MAX).is_ok());
+        assert!(deserialize_cap(&bytes, limits.merkle_cap_len).is_err());
+    }
 }
@@ -0,0 +1,132 @@
+//! Streamed format of the proofs, for verifiers that can't hold a whole proof in
+//! memory. See `verifier::verify_stream`.
+//!
+//! The grinding nonce is serialized after the openings in the format of
+//! `StarkProof`, but the queries can't be sampled without it. So the stream starts
+//! with the proof without its openings, which has the commitments, the
+//! out-of-domain evaluations, the last FRI layer and the nonce. The openings of
+//! each query follow, in the order of the queries:
+//!
+//! - the length of the proof without openings, as a big-endian `u64`, and its
+//!   bytes, in the format of `StarkProof`,
+//! - the number of queries, as a big-endian `u64`,
+//! - for each query, the length and the bytes of its `FriDecommitment`, followed
+//!   by the ones of its `DeepPolynomialOpenings`.
+//!
+//! Nothing follows the openings of the last query.
+
+use std::io::{self, Read, Write};
+
+use lambdaworks_math::{
+    errors::DeserializationError,
+    field::{element::FieldElement, traits::IsFFTField},
+    traits::{ByteConversion, Deserializable, Serializable},
+};
+use thiserror::Error;
+
+use super::field_id::IdentifiedField;
+use super::stark::{DeepPolynomialOpenings, StarkProof};
+use crate::starks::{fri::fri_decommit::FriDecommitment, utils::check_fully_read};
+
+/// Largest part of a stream read at once. Longer lengths are rejected before
+/// allocating anything.
+pub const MAX_STREAM_CHUNK_SIZE: usize = 1 << 24;
+
+#[derive(Debug, Error)]
+pub enum ProofStreamError {
+    #[error("Could not read the proof stream: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid part of the proof stream: {0:?}")]
+    Deserialization(DeserializationError),
+    #[error("A part of the proof stream is {0} bytes long, more than the maximum")]
+    ChunkTooLong(u64),
+}
+
+impl From<DeserializationError> for ProofStreamError {
+    fn from(error: DeserializationError) -> Self {
+        Self::Deserialization(error)
+    }
+}
+
+/// Writes `proof` in the streamed format.
+pub fn write_proof_stream<F, W>(proof: &StarkProof<F>, writer: &mut W) -> io::Result<()>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+    W: Write,
+{
+    write_chunk(writer, &proof.serialize_preamble())?;
+    writer.write_all(&(proof.query_list.len() as u64).to_be_bytes())?;
+    for (fri_decommitment, deep_poly_openings) in
+        proof.query_list.iter().zip(&proof.deep_poly_openings)
+    {
+        write_chunk(writer, &fri_decommitment.serialize())?;
+        write_chunk(writer, &deep_poly_openings.serialize())?;
+    }
+    Ok(())
+}
+
+/// Reads a proof in the streamed format, one part at a time.
+pub struct ProofStreamReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> ProofStreamReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    /// Reads the proof without its openings, followed by the number of queries.
+    pub fn read_preamble<F>(&mut self) -> Result<(StarkProof<F>, usize), ProofStreamError>
+    where
+        F: IsFFTField + IdentifiedField,
+        FieldElement<F>: ByteConversion,
+    {
+        let proof = StarkProof::deserialize_strict(&self.read_chunk()?)?;
+        let num_queries = self.read_u64()? as usize;
+        Ok((proof, num_queries))
+    }
+
+    /// Reads the openings of the next query.
+    pub fn read_query<F>(
+        &mut self,
+    ) -> Result<(FriDecommitment<F>, DeepPolynomialOpenings<F>), ProofStreamError>
+    where
+        F: IsFFTField,
+        FieldElement<F>: ByteConversion,
+    {
+        let fri_decommitment = FriDecommitment::deserialize(&self.read_chunk()?)?;
+        let deep_poly_openings = DeepPolynomialOpenings::deserialize(&self.read_chunk()?)?;
+        Ok((fri_decommitment, deep_poly_openings))
+    }
+
+    /// Checks that the stream ends after the openings of the last query, so each
+    /// proof has a single streamed encoding.
+    pub fn finish(&mut self) -> Result<(), ProofStreamError> {
+        let mut rest = Vec::new();
+        self.reader.by_ref().take(1).read_to_end(&mut rest)?;
+        check_fully_read(&rest)?;
+        Ok(())
+    }
+
+    fn read_u64(&mut self) -> Result<u64, ProofStreamError> {
+        let mut bytes = [0; 8];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    fn read_chunk(&mut self) -> Result<Vec<u8>, ProofStreamError> {
+        let len = self.read_u64()?;
+        if len > MAX_STREAM_CHUNK_SIZE as u64 {
+            return Err(ProofStreamError::ChunkTooLong(len));
+        }
+        let mut bytes = vec![0; len as usize];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+fn write_chunk<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(&(bytes.len() as u64).to_be_bytes())?;
+    writer.write_all(bytes)
+}
@@ -1,20 +1,24 @@
-#[cfg(feature = "instruments")]
-use std::time::Instant;
+use std::sync::Arc;
 
 #[cfg(not(feature = "test_fiat_shamir"))]
 use lambdaworks_crypto::fiat_shamir::default_transcript::DefaultTranscript;
 use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_crypto::merkle_tree::proof::Proof;
 
 #[cfg(feature = "test_fiat_shamir")]
 use lambdaworks_crypto::fiat_shamir::test_transcript::TestTranscript;
 
 use lambdaworks_math::fft::{errors::FFTError, polynomial::FFTPoly};
 use lambdaworks_math::{
-    field::{element::FieldElement, traits::IsFFTField},
+    field::{
+        element::FieldElement, fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+        traits::IsFFTField,
+    },
     polynomial::Polynomial,
     traits::ByteConversion,
 };
-use log::info;
+use thiserror::Error;
+use tracing::{info, info_span};
 
 #[cfg(feature = "parallel")]
 use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
@@ -24,22 +28,46 @@ use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 use crate::starks::debug::validate_trace;
 use crate::starks::transcript::sample_z_ood;
 
-use super::config::{BatchedMerkleTree, Commitment};
+use super::checkpoint::ProverCheckpoint;
+use super::config::{BatchedVectorCommitment, Commitment, MerkleCap, Salt, COMMITMENT_SIZE};
 use super::constraints::evaluator::ConstraintEvaluator;
-use super::domain::Domain;
+use super::domain::{lde_domain_size, Domain};
 use super::frame::Frame;
-use super::fri::fri_decommit::FriDecommitment;
 use super::fri::{fri_commit_phase, fri_query_phase};
-use super::grinding::generate_nonce_with_grinding;
-use super::proof::options::ProofOptions;
+use super::fri::{fri_commitment::FriLayer, fri_decommit::FriDecommitment};
+use super::grinding::{grind, GrindingWork};
+use super::metrics::ProverMetrics;
+use super::poseidon_transcript::PoseidonTranscript;
+use super::proof::field_id::IdentifiedField;
+use super::proof::options::{CommitmentLayout, ProofOptions};
 use super::proof::stark::{DeepPolynomialOpenings, StarkProof};
+use super::public_coin::{ChallengeProvider, PublicCoinTranscript};
+use super::randomization::{append_random_rows, random_polynomial, randomize_last_rows};
+use super::salt::{random_salt, LeafSalts};
 use super::trace::TraceTable;
 use super::traits::AIR;
-use super::transcript::{batch_sample_challenges, transcript_to_field};
+use super::transcript::{
+    append_public_parameters, batch_sample_challenges, transcript_to_field, RapTranscript,
+    TranscriptRef,
+};
+use super::utils::in_timed_span;
+use super::vector_commitment::IsVectorCommitment;
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum ProvingError {
+    #[error("Wrong parameter: {0}")]
     WrongParameter(String),
+    #[error("Invalid state of the prover session: {0}")]
+    InvalidSessionState(String),
+    #[error("Invalid checkpoint: {0}")]
+    InvalidCheckpoint(String),
+    #[error("The proof was cancelled")]
+    Cancelled,
+    #[error("Failed to store a commitment: {0}")]
+    Storage(#[from] std::io::Error),
+    #[cfg(feature = "sharded-trace")]
+    #[error(transparent)]
+    Sharding(#[from] super::sharded_trace::ShardingError),
 }
 
 struct Round1<F, A>
@@ -48,11 +76,32 @@ where
     A: AIR<Field = F>,
     FieldElement<F>: ByteConversion,
 {
+    // Polynomials of the committed columns
     trace_polys: Vec<Polynomial<FieldElement<F>>>,
+    // LDE of all the columns of the AIR, the virtual ones included
     lde_trace: TraceTable<F>,
-    lde_trace_merkle_trees: Vec<BatchedMerkleTree<F>>,
+    lde_trace_merkle_trees: Vec<Box<dyn TraceCommitment>>,
     lde_trace_merkle_roots: Vec<Commitment>,
     rap_challenges: A::RAPChallenges,
+    salt_seed: Option<Salt>,
+}
+
+impl<F, A> Round1<F, A>
+where
+    F: IsFFTField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion,
+{
+    fn trace_salts(&self, commitment_index: usize) -> Option<LeafSalts> {
+        self.salt_seed
+            .as_ref()
+            .map(|seed| LeafSalts::new(seed, commitment_index))
+    }
+
+    /// The composition polynomial commitment comes after the trace ones.
+    fn composition_poly_salts(&self) -> Option<LeafSalts> {
+        self.trace_salts(self.lde_trace_merkle_roots.len())
+    }
 }
 
 struct Round2<F>
@@ -62,24 +111,40 @@ where
 {
     composition_poly_even: Polynomial<FieldElement<F>>,
     lde_composition_poly_even_evaluations: Vec<FieldElement<F>>,
-    composition_poly_merkle_tree: BatchedMerkleTree<F>,
+    composition_poly_merkle_tree: BatchedVectorCommitment<F>,
     composition_poly_root: Commitment,
     composition_poly_odd: Polynomial<FieldElement<F>>,
     lde_composition_poly_odd_evaluations: Vec<FieldElement<F>>,
+    // Masking polynomial M, committed along with H₁ and H₂ when the trace is randomized
+    composition_poly_mask: Option<Polynomial<FieldElement<F>>>,
+    lde_composition_poly_mask_evaluations: Vec<FieldElement<F>>,
 }
 
 struct Round3<F: IsFFTField> {
     trace_ood_evaluations: Vec<Vec<FieldElement<F>>>,
     composition_poly_even_ood_evaluation: FieldElement<F>,
     composition_poly_odd_ood_evaluation: FieldElement<F>,
+    composition_poly_mask_ood_evaluation: Option<FieldElement<F>>,
+    // H₁(w) and H₂(w), if the AIR opens them at an extra point w
+    composition_poly_extra_ood_evaluations: Option<(FieldElement<F>, FieldElement<F>)>,
+}
+
+/// The FRI layers of round 4, committed before the queries are sampled.
+struct Round4Commit<F>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    fri_last_poly: Vec<FieldElement<F>>,
+    fri_layers: Vec<FriLayer<F>>,
 }
 
 struct Round4<F: IsFFTField> {
-    fri_last_value: FieldElement<F>,
-    fri_layers_merkle_roots: Vec<Commitment>,
+    fri_last_poly: Vec<FieldElement<F>>,
+    fri_layers_merkle_caps: Vec<MerkleCap>,
     deep_poly_openings: Vec<DeepPolynomialOpenings<F>>,
     query_list: Vec<FriDecommitment<F>>,
-    nonce: u64,
+    grinding_work: GrindingWork,
 }
 
 #[cfg(feature = "test_fiat_shamir")]
@@ -93,14 +158,111 @@ fn round_0_transcript_initialization() -> DefaultTranscript {
     DefaultTranscript::new()
 }
 
-fn batch_commit<F>(vectors: &[Vec<FieldElement<F>>]) -> (BatchedMerkleTree<F>, Commitment)
+fn batch_commit<F>(
+    vectors: &[Vec<FieldElement<F>>],
+    salts: Option<LeafSalts>,
+    options: &ProofOptions,
+) -> Result<(BatchedVectorCommitment<F>, Commitment), ProvingError>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    let committed = match salts {
+        Some(salts) => BatchedVectorCommitment::<F>::commit(options, &salts.salt_leaves(vectors))?,
+        None => BatchedVectorCommitment::<F>::commit(options, vectors)?,
+    };
+    let commitment = committed.root();
+    Ok((committed, commitment))
+}
+
+/// Commitment to a group of columns of the LDE trace, opened at the queries of the
+/// last round.
+pub trait TraceCommitment {
+    /// Digest the transcript absorbs.
+    fn root(&self) -> Commitment;
+
+    /// Cap of the Merkle tree sent in the proof, of height `merkle_cap_height`.
+    fn commitment(&self, options: &ProofOptions) -> MerkleCap;
+
+    /// Paths of the rows at each of `positions`, in their order, up to the cap.
+    fn open(
+        &self,
+        options: &ProofOptions,
+        positions: &[usize],
+    ) -> Result<Vec<Proof<Commitment>>, ProvingError>;
+}
+
+impl<F> TraceCommitment for BatchedVectorCommitment<F>
 where
     F: IsFFTField,
     FieldElement<F>: ByteConversion,
 {
-    let tree = BatchedMerkleTree::<F>::build(vectors);
-    let commitment = tree.root;
-    (tree, commitment)
+    fn root(&self) -> Commitment {
+        IsVectorCommitment::root(self)
+    }
+
+    fn commitment(&self, options: &ProofOptions) -> MerkleCap {
+        IsVectorCommitment::commitment(self, options)
+    }
+
+    fn open(
+        &self,
+        options: &ProofOptions,
+        positions: &[usize],
+    ) -> Result<Vec<Proof<Commitment>>, ProvingError> {
+        IsVectorCommitment::open(self, options, positions).ok_or_else(|| {
+            ProvingError::WrongParameter("a query is outside of the LDE domain".to_string())
+        })
+    }
+}
+
+/// Extends and commits the columns of the trace in round 1. `LocalTraceCommitter`
+/// does it in the prover, and a `sharded_trace::Coordinator` splits it between its
+/// workers.
+pub trait TraceCommitter<F: IsFFTField> {
+    /// Evaluations of each of `trace_polys` on the LDE domain.
+    fn low_degree_extension(
+        &self,
+        trace_polys: &[Polynomial<FieldElement<F>>],
+        domain: &Domain<F>,
+    ) -> Result<Vec<Vec<FieldElement<F>>>, ProvingError>;
+
+    /// Commitment to the rows of a group of columns of the LDE trace. It must have
+    /// the root of the `BatchedVectorCommitment` of `rows` built with `options`.
+    fn commit(
+        &self,
+        rows: Vec<Vec<FieldElement<F>>>,
+        options: &ProofOptions,
+    ) -> Result<Box<dyn TraceCommitment>, ProvingError>;
+}
+
+/// Extends and commits the trace in the prover, in parallel if the `parallel`
+/// feature is enabled.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalTraceCommitter;
+
+impl<F> TraceCommitter<F> for LocalTraceCommitter
+where
+    F: IsFFTField + 'static,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    fn low_degree_extension(
+        &self,
+        trace_polys: &[Polynomial<FieldElement<F>>],
+        domain: &Domain<F>,
+    ) -> Result<Vec<Vec<FieldElement<F>>>, ProvingError> {
+        Ok(compute_lde_trace_evaluations(trace_polys, domain))
+    }
+
+    fn commit(
+        &self,
+        rows: Vec<Vec<FieldElement<F>>>,
+        options: &ProofOptions,
+    ) -> Result<Box<dyn TraceCommitment>, ProvingError> {
+        Ok(Box::new(BatchedVectorCommitment::<F>::commit(
+            options, &rows,
+        )?))
+    }
 }
 
 pub fn evaluate_polynomial_on_lde_domain<F>(
@@ -123,39 +285,95 @@ where
 }
 
 #[allow(clippy::type_complexity)]
-fn interpolate_and_commit<T, F>(
+fn interpolate_and_commit<F>(
     trace: &TraceTable<F>,
     domain: &Domain<F>,
-    transcript: &mut T,
-) -> (
-    Vec<Polynomial<FieldElement<F>>>,
-    Vec<Vec<FieldElement<F>>>,
-    BatchedMerkleTree<F>,
-    Commitment,
-)
+    commitment_layout: &CommitmentLayout,
+    trace_committer: &dyn TraceCommitter<F>,
+    options: &ProofOptions,
+    salt_seed: Option<&Salt>,
+    first_commitment_index: usize,
+) -> Result<
+    (
+        Vec<Polynomial<FieldElement<F>>>,
+        Vec<Vec<FieldElement<F>>>,
+        Vec<Box<dyn TraceCommitment>>,
+        Vec<Commitment>,
+    ),
+    ProvingError,
+>
 where
-    T: Transcript,
     F: IsFFTField,
     FieldElement<F>: ByteConversion + Send + Sync,
 {
     let trace_polys = trace.compute_trace_polys();
+    let (lde_trace_evaluations, lde_trace_merkle_trees, lde_trace_merkle_roots) =
+        commit_trace_polys(
+            &trace_polys,
+            domain,
+            commitment_layout,
+            trace_committer,
+            options,
+            salt_seed,
+            first_commitment_index,
+        )?;
+
+    Ok((
+        trace_polys,
+        lde_trace_evaluations,
+        lde_trace_merkle_trees,
+        lde_trace_merkle_roots,
+    ))
+}
 
+#[allow(clippy::type_complexity)]
+fn commit_trace_polys<F>(
+    trace_polys: &[Polynomial<FieldElement<F>>],
+    domain: &Domain<F>,
+    commitment_layout: &CommitmentLayout,
+    trace_committer: &dyn TraceCommitter<F>,
+    options: &ProofOptions,
+    salt_seed: Option<&Salt>,
+    first_commitment_index: usize,
+) -> Result<
+    (
+        Vec<Vec<FieldElement<F>>>,
+        Vec<Box<dyn TraceCommitment>>,
+        Vec<Commitment>,
+    ),
+    ProvingError,
+>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
     // Evaluate those polynomials t_j on the large domain D_LDE.
-    let lde_trace_evaluations = compute_lde_trace_evaluations(&trace_polys, domain);
-
-    // Compute commitments [t_j].
-    let lde_trace = TraceTable::new_from_cols(&lde_trace_evaluations);
-    let (lde_trace_merkle_tree, lde_trace_merkle_root) = batch_commit(&lde_trace.rows());
+    let lde_trace_evaluations = trace_committer.low_degree_extension(trace_polys, domain)?;
 
-    // >>>> Send commitments: [tⱼ]
-    transcript.append(&lde_trace_merkle_root);
+    // Compute commitments [t_j], one for each group of columns of the layout.
+    let lde_trace_merkle_trees = commitment_layout
+        .column_groups(lde_trace_evaluations.len())
+        .into_iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let rows = TraceTable::new_from_cols(&lde_trace_evaluations[group]).rows();
+            let rows = match salt_seed {
+                Some(seed) => LeafSalts::new(seed, first_commitment_index + i).salt_leaves(&rows),
+                None => rows,
+            };
+            trace_committer.commit(rows, options)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let lde_trace_merkle_roots = lde_trace_merkle_trees
+        .iter()
+        .map(|tree| tree.root())
+        .collect();
 
-    (
-        trace_polys,
+    Ok((
         lde_trace_evaluations,
-        lde_trace_merkle_tree,
-        lde_trace_merkle_root,
-    )
+        lde_trace_merkle_trees,
+        lde_trace_merkle_roots,
+    ))
 }
 
 fn compute_lde_trace_evaluations<F>(
@@ -188,31 +406,68 @@ fn round_1_randomized_air_with_preprocessing<F: IsFFTField, A: AIR<Field = F>, T
     air: &A,
     main_trace: &TraceTable<F>,
     domain: &Domain<F>,
+    trace_committer: &dyn TraceCommitter<F>,
+    salt_seed: Option<Salt>,
     transcript: &mut T,
 ) -> Result<Round1<F, A>, ProvingError>
 where
     FieldElement<F>: ByteConversion + Send + Sync,
 {
-    let (mut trace_polys, mut evaluations, main_merkle_tree, main_merkle_root) =
-        interpolate_and_commit(main_trace, domain, transcript);
+    let options = air.options();
+    let commitment_layout = &options.commitment_layout;
+    // The virtual columns aren't committed, see `AIR::virtual_columns`
+    let committed_columns = air.committed_columns();
+    let (mut trace_polys, mut evaluations, mut lde_trace_merkle_trees, mut lde_trace_merkle_roots) =
+        interpolate_and_commit(
+            &committed_columns.select_main_trace(main_trace),
+            domain,
+            commitment_layout,
+            trace_committer,
+            options,
+            salt_seed.as_ref(),
+            0,
+        )?;
+
+    // >>>> Send commitments: [tⱼ]
+    let mut transcript = RapTranscript::new(transcript);
+    transcript.append_main_trace_roots(&lde_trace_merkle_roots);
 
-    let rap_challenges = air.build_rap_challenges(transcript);
+    let mut rap_challenges = transcript.build_rap_challenges(air);
 
-    let aux_trace = air.build_auxiliary_trace(main_trace, &rap_challenges);
+    for round in 0..air.number_of_rap_rounds() {
+        if round > 0 {
+            transcript.extend_rap_challenges(air, round, &mut rap_challenges);
+        }
+
+        let mut aux_trace = air.build_auxiliary_trace(round, main_trace, &rap_challenges);
+        randomize_last_rows(&mut aux_trace, air.options().trace_randomization_rows);
+
+        let mut aux_merkle_roots = vec![];
+        if !aux_trace.is_empty() {
+            // Check that this is valid for interpolation
+            let (aux_trace_polys, aux_trace_polys_evaluations, aux_merkle_trees, roots) =
+                interpolate_and_commit(
+                    &aux_trace,
+                    domain,
+                    commitment_layout,
+                    trace_committer,
+                    options,
+                    salt_seed.as_ref(),
+                    lde_trace_merkle_roots.len(),
+                )?;
+            trace_polys.extend_from_slice(&aux_trace_polys);
+            evaluations.extend_from_slice(&aux_trace_polys_evaluations);
+            lde_trace_merkle_trees.extend(aux_merkle_trees);
+            aux_merkle_roots = roots;
+        }
 
-    let mut lde_trace_merkle_trees = vec![main_merkle_tree];
-    let mut lde_trace_merkle_roots = vec![main_merkle_root];
-    if !aux_trace.is_empty() {
-        // Check that this is valid for interpolation
-        let (aux_trace_polys, aux_trace_polys_evaluations, aux_merkle_tree, aux_merkle_root) =
-            interpolate_and_commit(&aux_trace, domain, transcript);
-        trace_polys.extend_from_slice(&aux_trace_polys);
-        evaluations.extend_from_slice(&aux_trace_polys_evaluations);
-        lde_trace_merkle_trees.push(aux_merkle_tree);
-        lde_trace_merkle_roots.push(aux_merkle_root);
+        // >>>> Send commitments: [tⱼ] of the auxiliary segment
+        transcript.append_aux_trace_roots(round, &aux_merkle_roots);
+        lde_trace_merkle_roots.extend(aux_merkle_roots);
     }
 
-    let lde_trace = TraceTable::new_from_cols(&evaluations);
+    // The constraints are evaluated over all the columns of the AIR
+    let lde_trace = TraceTable::new_from_cols(&committed_columns.expand_columns(evaluations));
 
     Ok(Round1 {
         trace_polys,
@@ -220,6 +475,7 @@ where
         lde_trace_merkle_roots,
         lde_trace_merkle_trees,
         rap_challenges,
+        salt_seed,
     })
 }
 
@@ -229,7 +485,7 @@ fn round_2_compute_composition_polynomial<F, A>(
     round_1_result: &Round1<F, A>,
     transition_coeffs: &[(FieldElement<F>, FieldElement<F>)],
     boundary_coeffs: &[(FieldElement<F>, FieldElement<F>)],
-) -> Round2<F>
+) -> Result<Round2<F>, ProvingError>
 where
     F: IsFFTField,
     A: AIR<Field = F> + Send + Sync,
@@ -247,10 +503,39 @@ where
         &round_1_result.rap_challenges,
     );
 
-    // Get the composition poly H
-    let composition_poly = constraint_evaluations.compute_composition_poly(&domain.coset_offset);
-    let (composition_poly_even, composition_poly_odd) = composition_poly.even_odd_decomposition();
+    // Get the even and odd parts of the composition poly H
+    let (composition_poly_even, composition_poly_odd) =
+        constraint_evaluations.compute_composition_poly_parts(&domain.lde_roots_of_unity_coset);
+
+    // The mask has the degree of the trace polynomials, so it hides all the terms
+    // of the DEEP composition polynomial.
+    let composition_poly_mask = air
+        .options()
+        .randomizes_trace()
+        .then(|| random_polynomial(air.trace_length()));
+
+    commit_composition_polynomial(
+        domain,
+        composition_poly_even,
+        composition_poly_odd,
+        composition_poly_mask,
+        round_1_result.composition_poly_salts(),
+        air.options(),
+    )
+}
 
+fn commit_composition_polynomial<F>(
+    domain: &Domain<F>,
+    composition_poly_even: Polynomial<FieldElement<F>>,
+    composition_poly_odd: Polynomial<FieldElement<F>>,
+    composition_poly_mask: Option<Polynomial<FieldElement<F>>>,
+    salts: Option<LeafSalts>,
+    options: &ProofOptions,
+) -> Result<Round2<F>, ProvingError>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
     let lde_composition_poly_even_evaluations = evaluate_polynomial_on_lde_domain(
         &composition_poly_even,
         domain.blowup_factor,
@@ -266,23 +551,43 @@ where
     )
     .unwrap();
 
+    let lde_composition_poly_mask_evaluations = composition_poly_mask
+        .as_ref()
+        .map(|mask| {
+            evaluate_polynomial_on_lde_domain(
+                mask,
+                domain.blowup_factor,
+                domain.interpolation_domain_size,
+                &domain.coset_offset,
+            )
+            .unwrap()
+        })
+        .unwrap_or_default();
+
     // TODO: Remove clones
     let composition_poly_evaluations: Vec<Vec<_>> = lde_composition_poly_even_evaluations
         .iter()
         .zip(&lde_composition_poly_odd_evaluations)
-        .map(|(a, b)| vec![a.clone(), b.clone()])
+        .enumerate()
+        .map(|(i, (a, b))| {
+            let mut leaf = vec![a.clone(), b.clone()];
+            leaf.extend(lde_composition_poly_mask_evaluations.get(i).cloned());
+            leaf
+        })
         .collect();
     let (composition_poly_merkle_tree, composition_poly_root) =
-        batch_commit(&composition_poly_evaluations);
+        batch_commit(&composition_poly_evaluations, salts, options)?;
 
-    Round2 {
+    Ok(Round2 {
         composition_poly_even,
         lde_composition_poly_even_evaluations,
         composition_poly_merkle_tree,
         composition_poly_root,
         composition_poly_odd,
         lde_composition_poly_odd_evaluations,
-    }
+        composition_poly_mask,
+        lde_composition_poly_mask_evaluations,
+    })
 }
 
 fn round_3_evaluate_polynomials_in_out_of_domain_element<F: IsFFTField, A: AIR<Field = F>>(
@@ -302,6 +607,20 @@ where
         round_2_result.composition_poly_even.evaluate(&z_squared);
     let composition_poly_odd_ood_evaluation =
         round_2_result.composition_poly_odd.evaluate(&z_squared);
+    // Evaluate M in z^2, if there is a mask.
+    let composition_poly_mask_ood_evaluation = round_2_result
+        .composition_poly_mask
+        .as_ref()
+        .map(|mask| mask.evaluate(&z_squared));
+    // Evaluate H_1 and H_2 in the extra point w, if the AIR opens them there.
+    let composition_poly_extra_ood_evaluations =
+        air.composition_poly_extra_opening().map(|opening| {
+            let w = opening.point(z, &domain.trace_primitive_root);
+            (
+                round_2_result.composition_poly_even.evaluate(&w),
+                round_2_result.composition_poly_odd.evaluate(&w),
+            )
+        });
 
     // Returns the Out of Domain Frame for the given trace polynomials, out of domain evaluation point (called `z` in the literature),
     // frame offsets given by the AIR and primitive root used for interpolating the trace polynomials.
@@ -321,38 +640,77 @@ where
         trace_ood_evaluations,
         composition_poly_even_ood_evaluation,
         composition_poly_odd_ood_evaluation,
+        composition_poly_mask_ood_evaluation,
+        composition_poly_extra_ood_evaluations,
     }
 }
 
-fn round_4_compute_and_run_fri_on_the_deep_composition_polynomial<
-    F: IsFFTField,
-    A: AIR<Field = F>,
-    T: Transcript,
->(
+/// Challenges of the DEEP composition polynomial.
+struct DeepCompositionCoefficients<F: IsFFTField> {
+    // 𝛾, 𝛾'
+    composition_poly: [FieldElement<F>; 2],
+    // 𝛾ₘ, if there is a mask
+    mask: Option<FieldElement<F>>,
+    // 𝛾ₑ, 𝛾ₑ', if there is an extra opening
+    extra_opening: Option<[FieldElement<F>; 2]>,
+    // 𝛾ⱼ, 𝛾ⱼ'
+    trace_polys: Vec<FieldElement<F>>,
+}
+
+fn sample_deep_composition_coefficients<F: IsFFTField, A: AIR<Field = F>, T: Transcript>(
     air: &A,
-    domain: &Domain<F>,
-    round_1_result: &Round1<F, A>,
-    round_2_result: &Round2<F>,
     round_3_result: &Round3<F>,
-    z: &FieldElement<F>,
     transcript: &mut T,
-) -> Round4<F>
+) -> DeepCompositionCoefficients<F>
 where
     FieldElement<F>: ByteConversion,
 {
-    let coset_offset_u64 = air.context().proof_options.coset_offset;
-    let coset_offset = FieldElement::<F>::from(coset_offset_u64);
-
     // <<<< Receive challenges: 𝛾, 𝛾'
-    let composition_poly_coeffients = [
+    let composition_poly = [
         transcript_to_field(transcript),
         transcript_to_field(transcript),
     ];
+    // <<<< Receive challenge: 𝛾ₘ, if there is a mask
+    let mask = round_3_result
+        .composition_poly_mask_ood_evaluation
+        .as_ref()
+        .map(|_| transcript_to_field(transcript));
+    // <<<< Receive challenges: 𝛾ₑ, 𝛾ₑ', if there is an extra opening
+    let extra_opening = round_3_result
+        .composition_poly_extra_ood_evaluations
+        .as_ref()
+        .map(|_| {
+            [
+                transcript_to_field(transcript),
+                transcript_to_field(transcript),
+            ]
+        });
     // <<<< Receive challenges: 𝛾ⱼ, 𝛾ⱼ'
-    let trace_poly_coeffients = batch_sample_challenges::<F, T>(
-        air.context().transition_offsets.len() * air.context().trace_columns,
-        transcript,
-    );
+    let trace_polys = batch_sample_challenges::<F, T>(air.deep_trace_terms().len(), transcript);
+
+    DeepCompositionCoefficients {
+        composition_poly,
+        mask,
+        extra_opening,
+        trace_polys,
+    }
+}
+
+/// First part of round 4: computes the DEEP composition polynomial and commits to
+/// its FRI layers.
+fn round_4_commit_fri_layers<F: IsFFTField, A: AIR<Field = F>, T: Transcript>(
+    air: &A,
+    domain: &Domain<F>,
+    round_1_result: &Round1<F, A>,
+    round_2_result: &Round2<F>,
+    round_3_result: &Round3<F>,
+    z: &FieldElement<F>,
+    transcript: &mut T,
+) -> Result<Round4Commit<F>, ProvingError>
+where
+    FieldElement<F>: ByteConversion,
+{
+    let coefficients = sample_deep_composition_coefficients(air, round_3_result, transcript);
 
     // Compute p₀ (deep composition polynomial)
     let deep_composition_poly = compute_deep_composition_poly(
@@ -362,45 +720,80 @@ where
         round_3_result,
         z,
         &domain.trace_primitive_root,
-        &composition_poly_coeffients,
-        &trace_poly_coeffients,
+        &coefficients.composition_poly,
+        coefficients.mask.as_ref(),
+        coefficients.extra_opening.as_ref(),
+        &coefficients.trace_polys,
     );
 
-    let domain_size = domain.lde_roots_of_unity_coset.len();
-
-    // FRI commit and query phases
-    let (fri_last_value, fri_layers) = fri_commit_phase(
-        domain.root_order as usize,
+    // FRI commit phase
+    let options = air.options();
+    let (fri_last_poly, fri_layers) = fri_commit_phase(
+        options.fri_number_of_layers(air.trace_length()),
+        options.fri_last_poly_length(air.trace_length()),
         deep_composition_poly,
         transcript,
-        &coset_offset,
-        domain_size,
-    );
+        &domain.coset_offset,
+        domain.lde_roots_of_unity_coset.len(),
+        options,
+    )?;
+
+    Ok(Round4Commit {
+        fri_last_poly,
+        fri_layers,
+    })
+}
+
+/// Second part of round 4: grinds the nonce and opens the FRI layers and the
+/// commitments of the previous rounds at the queries.
+fn round_4_open_fri_layers<F: IsFFTField, A: AIR<Field = F>, T: Transcript>(
+    air: &A,
+    domain: &Domain<F>,
+    round_1_result: &Round1<F, A>,
+    round_2_result: &Round2<F>,
+    round_4_commit: Round4Commit<F>,
+    transcript: &mut T,
+) -> Result<Round4<F>, ProvingError>
+where
+    FieldElement<F>: ByteConversion,
+{
+    let Round4Commit {
+        fri_last_poly,
+        fri_layers,
+    } = round_4_commit;
+    let domain_size = domain.lde_roots_of_unity_coset.len();
+    let options = air.options();
 
     // grinding: generate nonce and append it to the transcript
-    let grinding_factor = air.context().proof_options.grinding_factor;
+    let grinding = options.grinding;
     let transcript_challenge = transcript.challenge();
-    let nonce = generate_nonce_with_grinding(&transcript_challenge, grinding_factor)
-        .expect("nonce not found");
-    transcript.append(&nonce.to_be_bytes());
-
-    let (query_list, iotas) = fri_query_phase(air, domain_size, &fri_layers, transcript);
+    let grinding_work = grind(&transcript_challenge, grinding).expect("nonce not found");
+    info!(
+        bits = grinding.bits(),
+        attempts = grinding_work.attempts,
+        elapsed_us = grinding_work.elapsed.as_micros() as u64,
+        "grinding finished"
+    );
+    transcript.append(&grinding_work.nonce.to_be_bytes());
 
-    let fri_layers_merkle_roots: Vec<_> = fri_layers
+    let fri_layers_merkle_caps: Vec<_> = fri_layers
         .iter()
-        .map(|layer| layer.merkle_tree.root)
+        .map(|layer| layer.merkle_tree.commitment(options))
         .collect();
 
+    // FRI query phase
+    let (query_list, iotas) = fri_query_phase(air, domain_size, fri_layers, transcript);
+
     let deep_poly_openings =
-        open_deep_composition_poly(domain, round_1_result, round_2_result, &iotas);
+        open_deep_composition_poly(air, domain, round_1_result, round_2_result, &iotas)?;
 
-    Round4 {
-        fri_last_value,
-        fri_layers_merkle_roots,
+    Ok(Round4 {
+        fri_last_poly,
+        fri_layers_merkle_caps,
         deep_poly_openings,
         query_list,
-        nonce,
-    }
+        grinding_work,
+    })
 }
 
 /// Returns the DEEP composition polynomial that the prover then commits to using
@@ -415,6 +808,8 @@ fn compute_deep_composition_poly<A, F>(
     z: &FieldElement<F>,
     primitive_root: &FieldElement<F>,
     composition_poly_gammas: &[FieldElement<F>; 2],
+    mask_gamma: Option<&FieldElement<F>>,
+    extra_opening_gammas: Option<&[FieldElement<F>; 2]>,
     trace_terms_gammas: &[FieldElement<F>],
 ) -> Polynomial<FieldElement<F>>
 where
@@ -439,66 +834,97 @@ where
     let mut h_2_term = gamma_p * (h_2 - h_2_z2);
     h_2_term.ruffini_division_inplace(&z_squared);
 
+    // 𝛾ₘ ( M − M(z²) ) / ( X − z² )
+    let mask_term = match (
+        &round_2_result.composition_poly_mask,
+        &round_3_result.composition_poly_mask_ood_evaluation,
+        mask_gamma,
+    ) {
+        (Some(mask), Some(mask_z2), Some(gamma_m)) => {
+            let mut mask_term = gamma_m * (mask - mask_z2);
+            mask_term.ruffini_division_inplace(&z_squared);
+            mask_term
+        }
+        _ => Polynomial::zero(),
+    };
+
+    // 𝛾ₑ ( H₁ − H₁(w) ) / ( X − w ) + 𝛾ₑ' ( H₂ − H₂(w) ) / ( X − w )
+    let extra_opening_term = match (
+        air.composition_poly_extra_opening(),
+        &round_3_result.composition_poly_extra_ood_evaluations,
+        extra_opening_gammas,
+    ) {
+        (Some(opening), Some((h_1_w, h_2_w)), Some([gamma_e, gamma_e_p])) => {
+            let w = opening.point(z, primitive_root);
+            let mut extra_opening_term = gamma_e * (h_1 - h_1_w) + gamma_e_p * (h_2 - h_2_w);
+            extra_opening_term.ruffini_division_inplace(&w);
+            extra_opening_term
+        }
+        _ => Polynomial::zero(),
+    };
+
     // Get trace evaluations needed for the trace terms of the deep composition polynomial
     let transition_offsets = &air.context().transition_offsets;
     let trace_frame_evaluations = &round_3_result.trace_ood_evaluations;
 
     // Compute the sum of all the trace terms of the deep composition polynomial.
-    // There is one term for every trace polynomial and for every row in the frame.
+    // There is one term for every trace polynomial and for every row in the frame,
+    // except for the cells the AIR doesn't reference (see `referenced_frame_cells`).
     // ∑ ⱼₖ [ 𝛾ₖ ( tⱼ − tⱼ(z) ) / ( X − zgᵏ )]
+    let frame_points: Vec<_> = transition_offsets
+        .iter()
+        .map(|offset| z * primitive_root.pow(*offset))
+        .collect();
+    let trace_term = air
+        .deep_trace_terms()
+        .into_iter()
+        .zip(trace_terms_gammas)
+        .fold(
+            Polynomial::zero(),
+            |trace_terms, ((row, col), trace_gamma)| {
+                let t_j_z = &trace_frame_evaluations[row][col];
+                let mut poly = &trace_polys[col] - t_j_z;
+                poly.ruffini_division_inplace(&frame_points[row]);
+                trace_terms + poly * trace_gamma
+            },
+        );
 
-    // @@@ this could be const
-    let trace_frame_length = trace_frame_evaluations.len();
-    let trace_term =
-        trace_polys
-            .iter()
-            .enumerate()
-            .fold(Polynomial::zero(), |trace_terms, (i, t_j)| {
-                let i_times_trace_frame_evaluation = i * trace_frame_length;
-                let iter_trace_gammas = trace_terms_gammas
-                    .iter()
-                    .skip(i_times_trace_frame_evaluation);
-                let trace_int = trace_frame_evaluations
-                    .iter()
-                    .zip(transition_offsets)
-                    .zip(iter_trace_gammas)
-                    .fold(
-                        Polynomial::zero(),
-                        |trace_agg, ((eval, offset), trace_gamma)| {
-                            // @@@ we can avoid this clone
-                            let t_j_z = &eval[i];
-                            // @@@ this can be pre-computed
-                            let z_shifted = z * primitive_root.pow(*offset);
-                            let mut poly = t_j - t_j_z;
-                            poly.ruffini_division_inplace(&z_shifted);
-                            trace_agg + poly * trace_gamma
-                        },
-                    );
-
-                trace_terms + trace_int
-            });
-
-    h_1_term + h_2_term + trace_term
+    h_1_term + h_2_term + mask_term + extra_opening_term + trace_term
 }
 
 fn open_deep_composition_poly<F: IsFFTField, A: AIR<Field = F>>(
+    air: &A,
     domain: &Domain<F>,
     round_1_result: &Round1<F, A>,
     round_2_result: &Round2<F>,
     indexes_to_open: &[usize], // list of iotas
-) -> Vec<DeepPolynomialOpenings<F>>
+) -> Result<Vec<DeepPolynomialOpenings<F>>, ProvingError>
 where
     FieldElement<F>: ByteConversion,
 {
-    indexes_to_open
+    let committed_columns = air.committed_columns();
+    let indexes: Vec<_> = indexes_to_open
         .iter()
-        .map(|index_to_open| {
-            let index = index_to_open % domain.lde_roots_of_unity_coset.len();
+        .map(|index_to_open| index_to_open % domain.lde_roots_of_unity_coset.len())
+        .collect();
 
-            let lde_composition_poly_proof = round_2_result
-                .composition_poly_merkle_tree
-                .get_proof_by_pos(index)
-                .unwrap();
+    // Extract the paths of all the queries at once in each tree
+    let options = air.options();
+    let mut lde_composition_poly_proofs = round_2_result
+        .composition_poly_merkle_tree
+        .open(options, &indexes)
+        .unwrap()
+        .into_iter();
+    let mut lde_trace_merkle_proofs_of_queries = round_1_result
+        .lde_trace_merkle_trees
+        .iter()
+        .map(|tree| Ok(tree.open(options, &indexes)?.into_iter()))
+        .collect::<Result<Vec<_>, ProvingError>>()?;
+
+    Ok(indexes
+        .into_iter()
+        .map(|index| {
+            let lde_composition_poly_proof = lde_composition_poly_proofs.next().unwrap();
 
             // H₁ openings
             let lde_composition_poly_even_evaluation =
@@ -508,272 +934,1076 @@ where
             let lde_composition_poly_odd_evaluation =
                 round_2_result.lde_composition_poly_odd_evaluations[index].clone();
 
+            // M openings
+            let lde_composition_poly_mask_evaluation = round_2_result
+                .lde_composition_poly_mask_evaluations
+                .get(index)
+                .cloned();
+
+            let lde_composition_poly_salt = round_1_result
+                .composition_poly_salts()
+                .map(|salts| salts.salt(index));
+
             // Trace polynomials openings
-            let lde_trace_merkle_proofs = round_1_result
-                .lde_trace_merkle_trees
-                .iter()
-                .map(|tree| tree.get_proof_by_pos(index).unwrap())
+            let lde_trace_merkle_proofs = lde_trace_merkle_proofs_of_queries
+                .iter_mut()
+                .map(|proofs| proofs.next().unwrap())
                 .collect();
 
-            let lde_trace_evaluations = round_1_result.lde_trace.get_row(index).to_vec();
+            let lde_trace_evaluations =
+                committed_columns.select_row(round_1_result.lde_trace.get_row(index));
+
+            let lde_trace_salts = (0..round_1_result.lde_trace_merkle_trees.len())
+                .filter_map(|i| round_1_result.trace_salts(i))
+                .map(|salts| salts.salt(index))
+                .collect();
 
             DeepPolynomialOpenings {
                 lde_composition_poly_proof,
                 lde_composition_poly_even_evaluation,
                 lde_composition_poly_odd_evaluation,
+                lde_composition_poly_mask_evaluation,
+                lde_composition_poly_salt,
                 lde_trace_merkle_proofs,
                 lde_trace_evaluations,
+                lde_trace_salts,
             }
         })
-        .collect()
+        .collect())
 }
 
-// FIXME remove unwrap() calls and return errors
-pub fn prove<F, A>(
-    main_trace: &TraceTable<F>,
-    pub_inputs: &A::PublicInputs,
-    proof_options: &ProofOptions,
-) -> Result<StarkProof<F>, ProvingError>
+/// Transcript used by the prover when none is given.
+#[cfg(feature = "test_fiat_shamir")]
+pub type ProverTranscript = TestTranscript;
+
+/// Transcript used by the prover when none is given.
+#[cfg(not(feature = "test_fiat_shamir"))]
+pub type ProverTranscript = DefaultTranscript;
+
+/// A proof generation in progress.
+///
+/// The rounds of the protocol are run one at a time, and after any of the
+/// first three rounds, or once the FRI layers of round 4 are committed, the session
+/// can be turned into a `ProverCheckpoint`.
+/// The checkpoint can be serialized to disk and later used to resume the
+/// session, so long proofs can survive restarts of the proving machine.
+///
+/// The challenges are drawn from the transcript `T`. By default it's the
+/// Fiat-Shamir one, see `with_transcript` to use another one.
+pub struct ProverSession<F, A, T = ProverTranscript>
 where
     F: IsFFTField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion,
+    T: Transcript,
+{
+    air: A,
+    domain: Domain<F>,
+    transcript: T,
+    trace_committer: Arc<dyn TraceCommitter<F>>,
+    // Seed of the salts of the commitments, if they are salted
+    salt_seed: Option<Salt>,
+    round_1_result: Option<Round1<F, A>>,
+    round_2_result: Option<Round2<F>>,
+    round_3_result: Option<(FieldElement<F>, Round3<F>)>,
+    round_4_commit: Option<Round4Commit<F>>,
+}
+
+impl<F, A> ProverSession<F, A>
+where
+    F: IsFFTField + IdentifiedField,
     A: AIR<Field = F> + Send + Sync,
     A::RAPChallenges: Send + Sync,
     FieldElement<F>: ByteConversion + Send + Sync,
 {
-    info!("Started proof generation...");
-    #[cfg(feature = "instruments")]
-    println!("- Started round 0: Transcript Initialization");
-    #[cfg(feature = "instruments")]
-    let timer0 = Instant::now();
+    pub fn new(
+        trace_length: usize,
+        pub_inputs: &A::PublicInputs,
+        proof_options: &ProofOptions,
+    ) -> Result<Self, ProvingError> {
+        Self::with_transcript(
+            trace_length,
+            pub_inputs,
+            proof_options,
+            round_0_transcript_initialization(),
+        )
+    }
 
-    let air = A::new(main_trace.n_rows(), pub_inputs, proof_options);
-    let domain = Domain::new(&air);
-    let mut transcript = round_0_transcript_initialization();
+    /// Resumes a session from a checkpoint. The public inputs and proof options
+    /// must be the same ones used when the checkpoint was taken.
+    pub fn resume(
+        checkpoint: &ProverCheckpoint<F>,
+        pub_inputs: &A::PublicInputs,
+        proof_options: &ProofOptions,
+    ) -> Result<Self, ProvingError> {
+        if !(1..=4).contains(&checkpoint.completed_rounds) {
+            return Err(ProvingError::InvalidCheckpoint(format!(
+                "cannot resume from round {}",
+                checkpoint.completed_rounds
+            )));
+        }
 
-    #[cfg(feature = "instruments")]
-    let elapsed0 = timer0.elapsed();
-    #[cfg(feature = "instruments")]
-    println!("  Time spent: {:?}", elapsed0);
+        if checkpoint.salt_seed.is_some() != proof_options.salt_commitments {
+            return Err(ProvingError::InvalidCheckpoint(
+                "salting of the commitments does not match the proof options".to_string(),
+            ));
+        }
 
-    // ===================================
-    // ==========|   Round 1   |==========
-    // ===================================
+        let mut session = Self::new(checkpoint.trace_length, pub_inputs, proof_options)?;
+        session.salt_seed = checkpoint.salt_seed;
+        session.restore_round_1(checkpoint)?;
 
-    #[cfg(feature = "instruments")]
-    println!("- Started round 1: RAP");
-    #[cfg(feature = "instruments")]
-    let timer1 = Instant::now();
+        if checkpoint.completed_rounds >= 2 {
+            session.restore_round_2(checkpoint)?;
+        }
+        if checkpoint.completed_rounds >= 3 {
+            // Round 3 is cheap and only depends on the previous rounds, so it's recomputed.
+            session.run_round_3()?;
+        }
+        if checkpoint.completed_rounds == 4 {
+            session.restore_fri_layers(checkpoint)?;
+        }
 
-    let round_1_result = round_1_randomized_air_with_preprocessing::<F, A, _>(
-        &air,
-        main_trace,
-        &domain,
-        &mut transcript,
-    )?;
+        Ok(session)
+    }
+}
 
-    #[cfg(debug_assertions)]
-    validate_trace(
-        &air,
-        &round_1_result.trace_polys,
-        &domain,
-        &round_1_result.rap_challenges,
-    );
+impl<F, A, T> ProverSession<F, A, T>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F> + Send + Sync,
+    A::RAPChallenges: Send + Sync,
+    FieldElement<F>: ByteConversion + Send + Sync,
+    T: Transcript,
+{
+    /// Creates a session whose challenges are drawn from `transcript`, which may
+    /// already contain messages of a larger protocol.
+    pub fn with_transcript(
+        trace_length: usize,
+        pub_inputs: &A::PublicInputs,
+        proof_options: &ProofOptions,
+        transcript: T,
+    ) -> Result<Self, ProvingError> {
+        proof_options
+            .validate()
+            .map_err(|error| ProvingError::WrongParameter(error.to_string()))?;
+
+        if !trace_length.is_power_of_two() {
+            return Err(ProvingError::WrongParameter(format!(
+                "the trace length, including the trace randomization rows, must be a power of two, found {trace_length}"
+            )));
+        }
 
-    #[cfg(feature = "instruments")]
-    let elapsed1 = timer1.elapsed();
-    #[cfg(feature = "instruments")]
-    println!("  Time spent: {:?}", elapsed1);
+        let Some(lde_domain_size) = lde_domain_size::<F>(trace_length, proof_options.blowup_factor)
+        else {
+            return Err(ProvingError::WrongParameter(format!(
+                "the field has no LDE domain for a trace of length {trace_length} and blowup factor {}",
+                proof_options.blowup_factor
+            )));
+        };
+        proof_options
+            .validate_coset_offset::<F>(lde_domain_size)
+            .map_err(|error| ProvingError::WrongParameter(error.to_string()))?;
+        A::check_configuration(pub_inputs).map_err(ProvingError::WrongParameter)?;
+
+        let air = A::new(trace_length, pub_inputs, proof_options);
+        if !air.degree_bound_covers_constraints() {
+            return Err(ProvingError::WrongParameter(format!(
+                "the degree bound of the composition polynomial, {}, is smaller than the constraints",
+                air.composition_poly_degree_bound()
+            )));
+        }
+        if (proof_options.blowup_factor as usize) < air.min_blowup_factor() {
+            return Err(ProvingError::WrongParameter(format!(
+                "the blowup factor {} is smaller than {}, the minimum for the degrees of the constraints",
+                proof_options.blowup_factor,
+                air.min_blowup_factor()
+            )));
+        }
+        let domain = Domain::new(&air);
+        let mut transcript = transcript;
+        append_public_parameters(&air, &mut transcript);
+
+        Ok(Self {
+            air,
+            domain,
+            transcript,
+            trace_committer: Arc::new(LocalTraceCommitter),
+            salt_seed: proof_options.salt_commitments.then(random_salt),
+            round_1_result: None,
+            round_2_result: None,
+            round_3_result: None,
+            round_4_commit: None,
+        })
+    }
 
-    // ===================================
-    // ==========|   Round 2   |==========
-    // ===================================
+    /// Extends and commits the trace with `trace_committer` instead of in the prover,
+    /// like with the workers of a `sharded_trace::Coordinator`. It must be set before
+    /// running round 1.
+    pub fn with_trace_committer(mut self, trace_committer: Arc<dyn TraceCommitter<F>>) -> Self {
+        self.trace_committer = trace_committer;
+        self
+    }
 
-    #[cfg(feature = "instruments")]
-    println!("- Started round 2: Compute composition polynomial");
-    #[cfg(feature = "instruments")]
-    let timer2 = Instant::now();
+    /// Number of rounds of the protocol already run by this session. It's 4 once the
+    /// FRI layers of round 4 are committed by `commit_fri_layers`, before `finish`
+    /// opens them.
+    pub fn completed_rounds(&self) -> u8 {
+        match (
+            &self.round_1_result,
+            &self.round_2_result,
+            &self.round_3_result,
+            &self.round_4_commit,
+        ) {
+            (None, _, _, _) => 0,
+            (Some(_), None, _, _) => 1,
+            (Some(_), Some(_), None, _) => 2,
+            (Some(_), Some(_), Some(_), None) => 3,
+            (Some(_), Some(_), Some(_), Some(_)) => 4,
+        }
+    }
 
-    // <<<< Receive challenges: 𝛼_j^B
-    let boundary_coeffs_alphas = batch_sample_challenges(
-        air.boundary_constraints(&round_1_result.rap_challenges)
-            .constraints
-            .len(),
-        &mut transcript,
-    );
-    // <<<< Receive challenges: 𝛽_j^B
-    let boundary_coeffs_betas = batch_sample_challenges(
-        air.boundary_constraints(&round_1_result.rap_challenges)
-            .constraints
-            .len(),
-        &mut transcript,
-    );
-    // <<<< Receive challenges: 𝛼_j^T
-    let transition_coeffs_alphas =
-        batch_sample_challenges(air.context().num_transition_constraints, &mut transcript);
-    // <<<< Receive challenges: 𝛽_j^T
-    let transition_coeffs_betas =
-        batch_sample_challenges(air.context().num_transition_constraints, &mut transcript);
-
-    let boundary_coeffs: Vec<_> = boundary_coeffs_alphas
-        .into_iter()
-        .zip(boundary_coeffs_betas)
-        .collect();
-    let transition_coeffs: Vec<_> = transition_coeffs_alphas
-        .into_iter()
-        .zip(transition_coeffs_betas)
-        .collect();
+    /// Returns a checkpoint of the current state of the session.
+    pub fn checkpoint(&self) -> Result<ProverCheckpoint<F>, ProvingError> {
+        let round_1_result = self.round_1_result.as_ref().ok_or_else(|| {
+            ProvingError::InvalidSessionState("round 1 has not been run yet".to_string())
+        })?;
+
+        let (composition_poly_even, composition_poly_odd, composition_poly_root) =
+            match &self.round_2_result {
+                Some(round_2_result) => (
+                    round_2_result.composition_poly_even.coefficients().to_vec(),
+                    round_2_result.composition_poly_odd.coefficients().to_vec(),
+                    round_2_result.composition_poly_root,
+                ),
+                None => (vec![], vec![], [0; COMMITMENT_SIZE]),
+            };
+        let composition_poly_mask = self
+            .round_2_result
+            .as_ref()
+            .and_then(|round_2_result| round_2_result.composition_poly_mask.as_ref())
+            .map(|mask| mask.coefficients().to_vec())
+            .unwrap_or_default();
+
+        let (fri_layers_evaluations, fri_layers_merkle_roots, fri_last_poly) =
+            match &self.round_4_commit {
+                Some(round_4_commit) => (
+                    round_4_commit
+                        .fri_layers
+                        .iter()
+                        .map(|layer| layer.evaluation.clone())
+                        .collect(),
+                    round_4_commit
+                        .fri_layers
+                        .iter()
+                        .map(|layer| layer.merkle_tree.root())
+                        .collect(),
+                    round_4_commit.fri_last_poly.clone(),
+                ),
+                None => (vec![], vec![], vec![]),
+            };
+
+        Ok(ProverCheckpoint {
+            completed_rounds: self.completed_rounds(),
+            trace_length: self.air.trace_length(),
+            trace_polys: round_1_result
+                .trace_polys
+                .iter()
+                .map(|poly| poly.coefficients().to_vec())
+                .collect(),
+            lde_trace_merkle_roots: round_1_result.lde_trace_merkle_roots.clone(),
+            composition_poly_even,
+            composition_poly_odd,
+            composition_poly_mask,
+            composition_poly_root,
+            fri_layers_evaluations,
+            fri_layers_merkle_roots,
+            fri_last_poly,
+            salt_seed: self.salt_seed,
+        })
+    }
 
-    let round_2_result = round_2_compute_composition_polynomial(
-        &air,
-        &domain,
-        &round_1_result,
-        &transition_coeffs,
-        &boundary_coeffs,
-    );
+    fn check_completed_rounds(&self, expected: u8) -> Result<(), ProvingError> {
+        let completed_rounds = self.completed_rounds();
+        if completed_rounds != expected {
+            return Err(ProvingError::InvalidSessionState(format!(
+                "expected {expected} completed rounds, but the session has {completed_rounds}"
+            )));
+        }
+        Ok(())
+    }
 
-    // >>>> Send commitments: [H₁], [H₂]
-    transcript.append(&round_2_result.composition_poly_root);
+    /// Runs round 1 on the main trace. When the trace is randomized, the random rows
+    /// are appended here, so the main trace must have `trace_randomization_rows` rows
+    /// less than the trace length of the session.
+    pub fn run_round_1(&mut self, main_trace: &TraceTable<F>) -> Result<(), ProvingError> {
+        self.check_completed_rounds(0)?;
+
+        let randomization_rows = self.air.options().trace_randomization_rows;
+        if main_trace.n_rows() + randomization_rows != self.air.trace_length() {
+            return Err(ProvingError::WrongParameter(format!(
+                "expected a main trace of {} rows, found {}",
+                self.air.trace_length() - randomization_rows,
+                main_trace.n_rows()
+            )));
+        }
+        let mut main_trace = append_random_rows(main_trace, randomization_rows);
+        // The auxiliary trace is built from the virtual columns the verifier sees
+        self.air
+            .committed_columns()
+            .fill_virtual_columns(&mut main_trace);
+
+        let round_1_result = round_1_randomized_air_with_preprocessing::<F, A, _>(
+            &self.air,
+            &main_trace,
+            &self.domain,
+            self.trace_committer.as_ref(),
+            self.salt_seed,
+            &mut self.transcript,
+        )?;
+
+        #[cfg(debug_assertions)]
+        validate_trace(
+            &self.air,
+            &self
+                .air
+                .committed_columns()
+                .expand_polys(&round_1_result.trace_polys),
+            &self.domain,
+            &round_1_result.rap_challenges,
+        );
 
-    #[cfg(feature = "instruments")]
-    let elapsed2 = timer2.elapsed();
-    #[cfg(feature = "instruments")]
-    println!("  Time spent: {:?}", elapsed2);
+        self.round_1_result = Some(round_1_result);
+        Ok(())
+    }
 
-    // ===================================
-    // ==========|   Round 3   |==========
-    // ===================================
+    fn restore_round_1(&mut self, checkpoint: &ProverCheckpoint<F>) -> Result<(), ProvingError> {
+        let committed_columns = self.air.committed_columns();
+        let num_columns = committed_columns.len();
+        if checkpoint.trace_polys.len() != num_columns {
+            return Err(ProvingError::InvalidCheckpoint(format!(
+                "expected {num_columns} trace polynomials, found {}",
+                checkpoint.trace_polys.len()
+            )));
+        }
 
-    #[cfg(feature = "instruments")]
-    println!("- Started round 3: Evaluate polynomial in out of domain elements");
-    #[cfg(feature = "instruments")]
-    let timer3 = Instant::now();
+        let trace_polys: Vec<_> = checkpoint
+            .trace_polys
+            .iter()
+            .map(|coefficients| Polynomial::new(coefficients))
+            .collect();
+        let num_main_columns = num_columns - self.air.number_auxiliary_rap_columns();
+        let options = self.air.options();
+        let commitment_layout = &options.commitment_layout;
+
+        // Replay the commitments of round 1 to restore the transcript state.
+        let (mut evaluations, mut lde_trace_merkle_trees, mut lde_trace_merkle_roots) =
+            commit_trace_polys(
+                &trace_polys[..num_main_columns],
+                &self.domain,
+                commitment_layout,
+                self.trace_committer.as_ref(),
+                options,
+                self.salt_seed.as_ref(),
+                0,
+            )?;
+
+        let mut transcript = RapTranscript::new(&mut self.transcript);
+        transcript.append_main_trace_roots(&lde_trace_merkle_roots);
+
+        let mut rap_challenges = transcript.build_rap_challenges(&self.air);
+
+        let mut offset = num_main_columns;
+        for round in 0..self.air.number_of_rap_rounds() {
+            if round > 0 {
+                transcript.extend_rap_challenges(&self.air, round, &mut rap_challenges);
+            }
 
-    // <<<< Receive challenge: z
-    let z = sample_z_ood(
-        &domain.lde_roots_of_unity_coset,
-        &domain.trace_roots_of_unity,
-        &mut transcript,
-    );
+            let num_round_columns = self.air.number_auxiliary_rap_columns_in_round(round);
+            let mut aux_merkle_roots = vec![];
+            if num_round_columns > 0 {
+                let (aux_evaluations, aux_merkle_trees, roots) = commit_trace_polys(
+                    &trace_polys[offset..offset + num_round_columns],
+                    &self.domain,
+                    commitment_layout,
+                    self.trace_committer.as_ref(),
+                    options,
+                    self.salt_seed.as_ref(),
+                    lde_trace_merkle_roots.len(),
+                )?;
+                evaluations.extend(aux_evaluations);
+                lde_trace_merkle_trees.extend(aux_merkle_trees);
+                aux_merkle_roots = roots;
+            }
+            transcript.append_aux_trace_roots(round, &aux_merkle_roots);
+            lde_trace_merkle_roots.extend(aux_merkle_roots);
+            offset += num_round_columns;
+        }
 
-    let round_3_result = round_3_evaluate_polynomials_in_out_of_domain_element(
-        &air,
-        &domain,
-        &round_1_result,
-        &round_2_result,
-        &z,
-    );
+        if lde_trace_merkle_roots != checkpoint.lde_trace_merkle_roots {
+            return Err(ProvingError::InvalidCheckpoint(
+                "trace commitments do not match the checkpoint".to_string(),
+            ));
+        }
 
-    // >>>> Send value: H₁(z²)
-    transcript.append(
-        &round_3_result
-            .composition_poly_even_ood_evaluation
-            .to_bytes_be(),
-    );
+        self.round_1_result = Some(Round1 {
+            trace_polys,
+            lde_trace: TraceTable::new_from_cols(&committed_columns.expand_columns(evaluations)),
+            lde_trace_merkle_trees,
+            lde_trace_merkle_roots,
+            rap_challenges,
+            salt_seed: self.salt_seed,
+        });
+        Ok(())
+    }
 
-    // >>>> Send value: H₂(z²)
-    transcript.append(
-        &round_3_result
-            .composition_poly_odd_ood_evaluation
-            .to_bytes_be(),
-    );
-    // >>>> Send values: tⱼ(zgᵏ)
-    for row in round_3_result.trace_ood_evaluations.iter() {
-        for element in row.iter() {
-            transcript.append(&element.to_bytes_be());
+    #[allow(clippy::type_complexity)]
+    fn sample_composition_poly_coefficients(
+        &mut self,
+        rap_challenges: &A::RAPChallenges,
+    ) -> (
+        Vec<(FieldElement<F>, FieldElement<F>)>,
+        Vec<(FieldElement<F>, FieldElement<F>)>,
+    ) {
+        let num_boundary_constraints = self
+            .air
+            .boundary_constraints(rap_challenges)
+            .constraints
+            .len();
+        let num_transition_constraints = self.air.context().num_transition_constraints;
+
+        // <<<< Receive challenges: 𝛼_j^B
+        let boundary_coeffs_alphas =
+            batch_sample_challenges(num_boundary_constraints, &mut self.transcript);
+        // <<<< Receive challenges: 𝛽_j^B
+        let boundary_coeffs_betas =
+            batch_sample_challenges(num_boundary_constraints, &mut self.transcript);
+        // <<<< Receive challenges: 𝛼_j^T
+        let transition_coeffs_alphas =
+            batch_sample_challenges(num_transition_constraints, &mut self.transcript);
+        // <<<< Receive challenges: 𝛽_j^T
+        let transition_coeffs_betas =
+            batch_sample_challenges(num_transition_constraints, &mut self.transcript);
+
+        let boundary_coeffs: Vec<_> = boundary_coeffs_alphas
+            .into_iter()
+            .zip(boundary_coeffs_betas)
+            .collect();
+        let transition_coeffs: Vec<_> = transition_coeffs_alphas
+            .into_iter()
+            .zip(transition_coeffs_betas)
+            .collect();
+
+        (transition_coeffs, boundary_coeffs)
+    }
+
+    pub fn run_round_2(&mut self) -> Result<(), ProvingError> {
+        self.check_completed_rounds(1)?;
+        let round_1_result = self.round_1_result.take().expect("round 1 was run");
+
+        let (transition_coeffs, boundary_coeffs) =
+            self.sample_composition_poly_coefficients(&round_1_result.rap_challenges);
+
+        let round_2_result = round_2_compute_composition_polynomial(
+            &self.air,
+            &self.domain,
+            &round_1_result,
+            &transition_coeffs,
+            &boundary_coeffs,
+        );
+        self.round_1_result = Some(round_1_result);
+        let round_2_result = round_2_result?;
+
+        // >>>> Send commitments: [H₁], [H₂]
+        self.transcript
+            .append(&round_2_result.composition_poly_root);
+
+        self.round_2_result = Some(round_2_result);
+        Ok(())
+    }
+
+    fn restore_round_2(&mut self, checkpoint: &ProverCheckpoint<F>) -> Result<(), ProvingError> {
+        let round_1_result = self.round_1_result.take().expect("round 1 was restored");
+
+        // The coefficients are not needed, but sampling them restores the transcript state.
+        self.sample_composition_poly_coefficients(&round_1_result.rap_challenges);
+
+        if checkpoint.composition_poly_mask.is_empty() == self.air.options().randomizes_trace() {
+            return Err(ProvingError::InvalidCheckpoint(
+                "masking of the composition polynomial does not match the proof options"
+                    .to_string(),
+            ));
         }
+        let composition_poly_mask = (!checkpoint.composition_poly_mask.is_empty())
+            .then(|| Polynomial::new(&checkpoint.composition_poly_mask));
+
+        let round_2_result = commit_composition_polynomial(
+            &self.domain,
+            Polynomial::new(&checkpoint.composition_poly_even),
+            Polynomial::new(&checkpoint.composition_poly_odd),
+            composition_poly_mask,
+            round_1_result.composition_poly_salts(),
+            self.air.options(),
+        );
+        self.round_1_result = Some(round_1_result);
+        let round_2_result = round_2_result?;
+
+        if round_2_result.composition_poly_root != checkpoint.composition_poly_root {
+            return Err(ProvingError::InvalidCheckpoint(
+                "composition polynomial commitment does not match the checkpoint".to_string(),
+            ));
+        }
+
+        // >>>> Send commitments: [H₁], [H₂]
+        self.transcript
+            .append(&round_2_result.composition_poly_root);
+
+        self.round_2_result = Some(round_2_result);
+        Ok(())
     }
 
-    #[cfg(feature = "instruments")]
-    let elapsed3 = timer3.elapsed();
-    #[cfg(feature = "instruments")]
-    println!("  Time spent: {:?}", elapsed3);
+    pub fn run_round_3(&mut self) -> Result<(), ProvingError> {
+        self.check_completed_rounds(2)?;
+        let round_1_result = self.round_1_result.as_ref().expect("round 1 was run");
+        let round_2_result = self.round_2_result.as_ref().expect("round 2 was run");
 
-    // ===================================
-    // ==========|   Round 4   |==========
-    // ===================================
+        // <<<< Receive challenge: z
+        let z = sample_z_ood(
+            &self.domain.lde_roots_of_unity_coset,
+            &self.domain.trace_roots_of_unity,
+            &mut self.transcript,
+        );
 
-    #[cfg(feature = "instruments")]
-    println!("- Started round 4: FRI");
-    #[cfg(feature = "instruments")]
-    let timer4 = Instant::now();
-
-    // Part of this round is running FRI, which is an interactive
-    // protocol on its own. Therefore we pass it the transcript
-    // to simulate the interactions with the verifier.
-    let round_4_result = round_4_compute_and_run_fri_on_the_deep_composition_polynomial(
-        &air,
-        &domain,
-        &round_1_result,
-        &round_2_result,
-        &round_3_result,
-        &z,
-        &mut transcript,
-    );
+        let round_3_result = round_3_evaluate_polynomials_in_out_of_domain_element(
+            &self.air,
+            &self.domain,
+            round_1_result,
+            round_2_result,
+            &z,
+        );
 
-    #[cfg(feature = "instruments")]
-    let elapsed4 = timer4.elapsed();
-    #[cfg(feature = "instruments")]
-    println!("  Time spent: {:?}", elapsed4);
+        // >>>> Send value: H₁(z²)
+        self.transcript.append(
+            &round_3_result
+                .composition_poly_even_ood_evaluation
+                .to_bytes_be(),
+        );
 
-    #[cfg(feature = "instruments")]
-    {
-        let total_time = elapsed1 + elapsed2 + elapsed3 + elapsed4;
-        println!(
-            " Fraction of proving time per round: {:.4} {:.4} {:.4} {:.4} {:.4}",
-            elapsed0.as_nanos() as f64 / total_time.as_nanos() as f64,
-            elapsed1.as_nanos() as f64 / total_time.as_nanos() as f64,
-            elapsed2.as_nanos() as f64 / total_time.as_nanos() as f64,
-            elapsed3.as_nanos() as f64 / total_time.as_nanos() as f64,
-            elapsed4.as_nanos() as f64 / total_time.as_nanos() as f64
+        // >>>> Send value: H₂(z²)
+        self.transcript.append(
+            &round_3_result
+                .composition_poly_odd_ood_evaluation
+                .to_bytes_be(),
         );
+        // >>>> Send value: M(z²)
+        if let Some(evaluation) = &round_3_result.composition_poly_mask_ood_evaluation {
+            self.transcript.append(&evaluation.to_bytes_be());
+        }
+        // >>>> Send values: H₁(w) and H₂(w)
+        if let Some((even, odd)) = &round_3_result.composition_poly_extra_ood_evaluations {
+            self.transcript.append(&even.to_bytes_be());
+            self.transcript.append(&odd.to_bytes_be());
+        }
+        // >>>> Send values: tⱼ(zgᵏ)
+        for row in round_3_result.trace_ood_evaluations.iter() {
+            for element in row.iter() {
+                self.transcript.append(&element.to_bytes_be());
+            }
+        }
+
+        self.round_3_result = Some((z, round_3_result));
+        Ok(())
     }
 
-    info!("End proof generation");
+    /// Runs the first part of round 4: computes the DEEP composition polynomial and
+    /// commits to its FRI layers, the most expensive part of the round. The session
+    /// can be checkpointed afterwards, and `finish` opens the layers.
+    pub fn commit_fri_layers(&mut self) -> Result<(), ProvingError> {
+        self.check_completed_rounds(3)?;
+        let round_1_result = self.round_1_result.as_ref().expect("round 1 was run");
+        let round_2_result = self.round_2_result.as_ref().expect("round 2 was run");
+        let (z, round_3_result) = self.round_3_result.as_ref().expect("round 3 was run");
+
+        // Part of this round is running FRI, which is an interactive
+        // protocol on its own. Therefore we pass it the transcript
+        // to simulate the interactions with the verifier.
+        let round_4_commit = round_4_commit_fri_layers(
+            &self.air,
+            &self.domain,
+            round_1_result,
+            round_2_result,
+            round_3_result,
+            z,
+            &mut self.transcript,
+        )?;
+
+        self.round_4_commit = Some(round_4_commit);
+        Ok(())
+    }
 
-    let trace_ood_frame_evaluations = Frame::new(
-        round_3_result
-            .trace_ood_evaluations
-            .into_iter()
-            .flatten()
-            .collect(),
-        round_1_result.trace_polys.len(),
-    );
+    fn restore_fri_layers(&mut self, checkpoint: &ProverCheckpoint<F>) -> Result<(), ProvingError> {
+        let (_, round_3_result) = self.round_3_result.as_ref().expect("round 3 was run");
+
+        // The coefficients are not needed, but sampling them restores the transcript state.
+        sample_deep_composition_coefficients(&self.air, round_3_result, &mut self.transcript);
+
+        let options = self.air.options();
+        let trace_length = self.air.trace_length();
+        if checkpoint.fri_layers_evaluations.len() != options.fri_number_of_layers(trace_length)
+            || checkpoint.fri_layers_merkle_roots.len() != checkpoint.fri_layers_evaluations.len()
+            || checkpoint.fri_last_poly.len() != options.fri_last_poly_length(trace_length)
+        {
+            return Err(ProvingError::InvalidCheckpoint(
+                "the FRI layers do not match the proof options".to_string(),
+            ));
+        }
 
-    Ok(StarkProof {
-        // [tⱼ]
-        lde_trace_merkle_roots: round_1_result.lde_trace_merkle_roots,
-        // tⱼ(zgᵏ)
-        trace_ood_frame_evaluations,
-        // [H₁] and [H₂]
-        composition_poly_root: round_2_result.composition_poly_root,
-        // H₁(z²)
-        composition_poly_even_ood_evaluation: round_3_result.composition_poly_even_ood_evaluation,
-        // H₂(z²)
-        composition_poly_odd_ood_evaluation: round_3_result.composition_poly_odd_ood_evaluation,
-        // [pₖ]
-        fri_layers_merkle_roots: round_4_result.fri_layers_merkle_roots,
-        // pₙ
-        fri_last_value: round_4_result.fri_last_value,
-        // Open(p₀(D₀), 𝜐ₛ), Open(pₖ(Dₖ), −𝜐ₛ^(2ᵏ))
-        query_list: round_4_result.query_list,
-        // Open(H₁(D_LDE, 𝜐₀), Open(H₂(D_LDE, 𝜐₀), Open(tⱼ(D_LDE), 𝜐₀)
-        deep_poly_openings: round_4_result.deep_poly_openings,
-        // nonce obtained from grinding
-        nonce: round_4_result.nonce,
-
-        trace_length: air.trace_length(),
-    })
+        let mut domain_size = self.domain.lde_roots_of_unity_coset.len();
+        let mut coset_offset = self.domain.coset_offset.clone();
+        let mut fri_layers = Vec::with_capacity(checkpoint.fri_layers_evaluations.len());
+        for (k, (evaluation, root)) in checkpoint
+            .fri_layers_evaluations
+            .iter()
+            .zip(&checkpoint.fri_layers_merkle_roots)
+            .enumerate()
+        {
+            if k > 0 {
+                // <<<< Receive challenge 𝜁ₖ₋₁
+                transcript_to_field::<F, _>(&mut self.transcript);
+                coset_offset = coset_offset.square();
+                domain_size /= 2;
+            }
+            if evaluation.len() != domain_size {
+                return Err(ProvingError::InvalidCheckpoint(format!(
+                    "the FRI layer {k} has {} evaluations, expected {domain_size}",
+                    evaluation.len()
+                )));
+            }
+
+            let layer = FriLayer::from_evaluations(
+                evaluation.clone(),
+                &coset_offset,
+                domain_size,
+                options,
+            )?;
+            if layer.merkle_tree.root() != *root {
+                return Err(ProvingError::InvalidCheckpoint(format!(
+                    "the commitment of the FRI layer {k} does not match the checkpoint"
+                )));
+            }
+            // >>>> Send commitment: [pₖ]
+            self.transcript.append(root);
+            fri_layers.push(layer);
+        }
+
+        // <<<< Receive challenge: 𝜁ₙ₋₁
+        transcript_to_field::<F, _>(&mut self.transcript);
+        // >>>> Send value: pₙ
+        for coefficient in &checkpoint.fri_last_poly {
+            self.transcript.append(&coefficient.to_bytes_be());
+        }
+
+        self.round_4_commit = Some(Round4Commit {
+            fri_last_poly: checkpoint.fri_last_poly.clone(),
+            fri_layers,
+        });
+        Ok(())
+    }
+
+    /// Runs round 4, or its second part if the FRI layers are already committed,
+    /// and returns the proof.
+    pub fn finish(self) -> Result<StarkProof<F>, ProvingError> {
+        self.finish_with_grinding_work().map(|(proof, _)| proof)
+    }
+
+    /// Same as `finish`, but also returns the work it took to find the nonce.
+    pub fn finish_with_grinding_work(
+        mut self,
+    ) -> Result<(StarkProof<F>, GrindingWork), ProvingError> {
+        if self.completed_rounds() == 3 {
+            self.commit_fri_layers()?;
+        }
+        self.check_completed_rounds(4)?;
+        let round_1_result = self.round_1_result.expect("round 1 was run");
+        let round_2_result = self.round_2_result.expect("round 2 was run");
+        let (_, round_3_result) = self.round_3_result.expect("round 3 was run");
+        let round_4_commit = self.round_4_commit.expect("the FRI layers were committed");
+
+        let round_4_result = round_4_open_fri_layers(
+            &self.air,
+            &self.domain,
+            &round_1_result,
+            &round_2_result,
+            round_4_commit,
+            &mut self.transcript,
+        )?;
+
+        let trace_ood_frame_evaluations = Frame::new(
+            round_3_result
+                .trace_ood_evaluations
+                .into_iter()
+                .flatten()
+                .collect(),
+            round_1_result.trace_polys.len(),
+        );
+
+        let options = self.air.options();
+        let proof = StarkProof {
+            field_id: F::FIELD_ID,
+            // [tⱼ]
+            lde_trace_merkle_caps: round_1_result
+                .lde_trace_merkle_trees
+                .iter()
+                .map(|tree| tree.commitment(options))
+                .collect(),
+            // tⱼ(zgᵏ)
+            trace_ood_frame_evaluations,
+            // [H₁] and [H₂]
+            composition_poly_cap: round_2_result
+                .composition_poly_merkle_tree
+                .commitment(options),
+            // H₁(z²)
+            composition_poly_even_ood_evaluation: round_3_result
+                .composition_poly_even_ood_evaluation,
+            // H₂(z²)
+            composition_poly_odd_ood_evaluation: round_3_result.composition_poly_odd_ood_evaluation,
+            // M(z²)
+            composition_poly_mask_ood_evaluation: round_3_result
+                .composition_poly_mask_ood_evaluation,
+            // H₁(w) and H₂(w)
+            composition_poly_extra_ood_evaluations: round_3_result
+                .composition_poly_extra_ood_evaluations,
+            // [pₖ]
+            fri_layers_merkle_caps: round_4_result.fri_layers_merkle_caps,
+            // pₙ
+            fri_last_poly: round_4_result.fri_last_poly,
+            // Open(p₀(D₀), 𝜐ₛ), Open(pₖ(Dₖ), −𝜐ₛ^(2ᵏ))
+            query_list: round_4_result.query_list,
+            // Open(H₁(D_LDE, 𝜐₀), Open(H₂(D_LDE, 𝜐₀), Open(tⱼ(D_LDE), 𝜐₀)
+            deep_poly_openings: round_4_result.deep_poly_openings,
+            // nonce obtained from grinding
+            nonce: round_4_result.grinding_work.nonce,
+
+            trace_length: self.air.trace_length(),
+            options: options.clone(),
+        };
+        Ok((proof, round_4_result.grinding_work))
+    }
+}
+
+/// A backend generating STARK proofs of the AIRs of this crate, like an accelerated
+/// or remote prover. The proofs must follow the protocol of `CpuProver`, so they
+/// are checked by the same `verify`.
+pub trait IsStarkProver {
+    /// Proves that `main_trace` satisfies the constraints of the AIR `A`, sampling
+    /// the challenges from `transcript`.
+    fn prove_with_transcript<F, A, T>(
+        &self,
+        main_trace: &TraceTable<F>,
+        pub_inputs: &A::PublicInputs,
+        proof_options: &ProofOptions,
+        transcript: T,
+    ) -> Result<StarkProof<F>, ProvingError>
+    where
+        F: IsFFTField + IdentifiedField,
+        A: AIR<Field = F> + Send + Sync,
+        A::RAPChallenges: Send + Sync,
+        FieldElement<F>: ByteConversion + Send + Sync,
+        T: Transcript;
+
+    /// Proves that `main_trace` satisfies the constraints of the AIR `A`, with the
+    /// challenges of the Fiat-Shamir transform. See `verify`.
+    fn prove<F, A>(
+        &self,
+        main_trace: &TraceTable<F>,
+        pub_inputs: &A::PublicInputs,
+        proof_options: &ProofOptions,
+    ) -> Result<StarkProof<F>, ProvingError>
+    where
+        F: IsFFTField + IdentifiedField,
+        A: AIR<Field = F> + Send + Sync,
+        A::RAPChallenges: Send + Sync,
+        FieldElement<F>: ByteConversion + Send + Sync,
+    {
+        self.prove_with_transcript::<F, A, _>(
+            main_trace,
+            pub_inputs,
+            proof_options,
+            round_0_transcript_initialization(),
+        )
+    }
+}
+
+/// The prover of this crate, running all the rounds in the CPU, in parallel if the
+/// `parallel` feature is enabled.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuProver;
+
+impl IsStarkProver for CpuProver {
+    fn prove_with_transcript<F, A, T>(
+        &self,
+        main_trace: &TraceTable<F>,
+        pub_inputs: &A::PublicInputs,
+        proof_options: &ProofOptions,
+        mut transcript: T,
+    ) -> Result<StarkProof<F>, ProvingError>
+    where
+        F: IsFFTField + IdentifiedField,
+        A: AIR<Field = F> + Send + Sync,
+        A::RAPChallenges: Send + Sync,
+        FieldElement<F>: ByteConversion + Send + Sync,
+        T: Transcript,
+    {
+        prove_with_transcript::<F, A, T>(main_trace, pub_inputs, proof_options, &mut transcript)
+    }
+}
+
+// FIXME remove unwrap() calls and return errors
+pub fn prove<F, A>(
+    main_trace: &TraceTable<F>,
+    pub_inputs: &A::PublicInputs,
+    proof_options: &ProofOptions,
+) -> Result<StarkProof<F>, ProvingError>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F> + Send + Sync,
+    A::RAPChallenges: Send + Sync,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    CpuProver.prove::<F, A>(main_trace, pub_inputs, proof_options)
+}
+
+/// Same as `prove`, but the challenges are taken from `challenge_provider` instead
+/// of the Fiat-Shamir transform. The proof must be verified with `verify_interactive`
+/// and a provider giving the same challenges.
+pub fn prove_interactive<F, A, P>(
+    main_trace: &TraceTable<F>,
+    pub_inputs: &A::PublicInputs,
+    proof_options: &ProofOptions,
+    challenge_provider: P,
+) -> Result<StarkProof<F>, ProvingError>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F> + Send + Sync,
+    A::RAPChallenges: Send + Sync,
+    FieldElement<F>: ByteConversion + Send + Sync,
+    P: ChallengeProvider,
+{
+    CpuProver.prove_with_transcript::<F, A, _>(
+        main_trace,
+        pub_inputs,
+        proof_options,
+        PublicCoinTranscript::new(challenge_provider),
+    )
+}
+
+/// A proof for a verifier written in Cairo, and its encoding as field elements.
+pub struct RecursionFriendlyProof {
+    pub proof: StarkProof<Stark252PrimeField>,
+    /// The proof as the verifier reads it, see `StarkProof::to_felts`.
+    pub felts: Vec<FieldElement<Stark252PrimeField>>,
+}
+
+/// Same as `prove`, but making the proof cheap to verify in Cairo: the commitments
+/// are hashed with Poseidon, whatever the hash of the options, the challenges are
+/// drawn from a `PoseidonTranscript`, and the proof is also encoded as field
+/// elements. The proof must be verified with `verify_recursive_friendly`.
+pub fn prove_recursive_friendly<A>(
+    main_trace: &TraceTable<Stark252PrimeField>,
+    pub_inputs: &A::PublicInputs,
+    proof_options: &ProofOptions,
+) -> Result<RecursionFriendlyProof, ProvingError>
+where
+    A: AIR<Field = Stark252PrimeField> + Send + Sync,
+    A::RAPChallenges: Send + Sync,
+{
+    let proof = prove_with_transcript::<Stark252PrimeField, A, _>(
+        main_trace,
+        pub_inputs,
+        &proof_options.clone().with_poseidon_commitments(),
+        &mut PoseidonTranscript::new(),
+    )?;
+    let felts = proof
+        .to_felts()
+        .expect("the Poseidon commitments are field elements");
+
+    Ok(RecursionFriendlyProof { proof, felts })
+}
+
+/// Same as `prove`, but continuing `transcript` instead of starting a new one. The
+/// transcript may already hold the messages of a larger protocol, so the proof can
+/// be a step of it, and it keeps the messages of the proof for the next steps. The
+/// proof must be verified with `verify_with_transcript` and a transcript in the same
+/// state.
+pub fn prove_with_transcript<F, A, T>(
+    main_trace: &TraceTable<F>,
+    pub_inputs: &A::PublicInputs,
+    proof_options: &ProofOptions,
+    transcript: &mut T,
+) -> Result<StarkProof<F>, ProvingError>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F> + Send + Sync,
+    A::RAPChallenges: Send + Sync,
+    FieldElement<F>: ByteConversion + Send + Sync,
+    T: Transcript,
+{
+    prove_with_metrics_and_transcript::<F, A, T>(
+        main_trace,
+        pub_inputs,
+        proof_options,
+        transcript,
+        Arc::new(LocalTraceCommitter),
+        &mut ProverMetrics::default(),
+    )
+}
+
+/// Same as `prove`, but extending and committing the trace with `trace_committer`.
+/// The proof is the same one, checked by `verify`.
+pub fn prove_with_trace_committer<F, A>(
+    main_trace: &TraceTable<F>,
+    pub_inputs: &A::PublicInputs,
+    proof_options: &ProofOptions,
+    trace_committer: Arc<dyn TraceCommitter<F>>,
+) -> Result<StarkProof<F>, ProvingError>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F> + Send + Sync,
+    A::RAPChallenges: Send + Sync,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    prove_with_metrics_and_transcript::<F, A, _>(
+        main_trace,
+        pub_inputs,
+        proof_options,
+        &mut round_0_transcript_initialization(),
+        trace_committer,
+        &mut ProverMetrics::default(),
+    )
+}
+
+/// Same as `prove`, but also returning the metrics of each round, like the time it
+/// took and the peak memory it used. See the `metrics` module.
+pub fn prove_with_metrics<F, A>(
+    main_trace: &TraceTable<F>,
+    pub_inputs: &A::PublicInputs,
+    proof_options: &ProofOptions,
+) -> Result<(StarkProof<F>, ProverMetrics), ProvingError>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F> + Send + Sync,
+    A::RAPChallenges: Send + Sync,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    let mut metrics = ProverMetrics::default();
+    let proof = prove_with_metrics_and_transcript::<F, A, _>(
+        main_trace,
+        pub_inputs,
+        proof_options,
+        &mut round_0_transcript_initialization(),
+        Arc::new(LocalTraceCommitter),
+        &mut metrics,
+    )?;
+    Ok((proof, metrics))
+}
+
+fn prove_with_metrics_and_transcript<F, A, T>(
+    main_trace: &TraceTable<F>,
+    pub_inputs: &A::PublicInputs,
+    proof_options: &ProofOptions,
+    transcript: &mut T,
+    trace_committer: Arc<dyn TraceCommitter<F>>,
+    metrics: &mut ProverMetrics,
+) -> Result<StarkProof<F>, ProvingError>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F> + Send + Sync,
+    A::RAPChallenges: Send + Sync,
+    FieldElement<F>: ByteConversion + Send + Sync,
+    T: Transcript,
+{
+    let trace_length = main_trace.n_rows() + proof_options.trace_randomization_rows;
+    let _span = info_span!(
+        "prove",
+        trace_length,
+        trace_columns = main_trace.n_cols,
+        blowup_factor = proof_options.blowup_factor,
+        num_queries = proof_options.fri_number_of_queries,
+    )
+    .entered();
+
+    let mut session = metrics.measure("round_0_transcript_initialization", || {
+        in_timed_span(info_span!("round_0_transcript_initialization"), || {
+            ProverSession::<F, A, _>::with_transcript(
+                trace_length,
+                pub_inputs,
+                proof_options,
+                TranscriptRef(transcript),
+            )
+            .map(|session| session.with_trace_committer(trace_committer))
+        })
+    })?;
+
+    // ===================================
+    // ==========|   Round 1   |==========
+    // ===================================
+
+    metrics.measure("round_1_rap", || {
+        in_timed_span(info_span!("round_1_rap"), || {
+            session.run_round_1(main_trace)
+        })
+    })?;
+
+    // ===================================
+    // ==========|   Round 2   |==========
+    // ===================================
+
+    metrics.measure("round_2_composition_polynomial", || {
+        in_timed_span(info_span!("round_2_composition_polynomial"), || {
+            session.run_round_2()
+        })
+    })?;
+
+    // ===================================
+    // ==========|   Round 3   |==========
+    // ===================================
+
+    metrics.measure("round_3_out_of_domain_evaluations", || {
+        in_timed_span(info_span!("round_3_out_of_domain_evaluations"), || {
+            session.run_round_3()
+        })
+    })?;
+
+    // ===================================
+    // ==========|   Round 4   |==========
+    // ===================================
+
+    let (proof, grinding_work) = metrics.measure("round_4_fri", || {
+        in_timed_span(info_span!("round_4_fri"), || {
+            session.finish_with_grinding_work()
+        })
+    })?;
+    metrics.grinding = Some(grinding_work);
+    Ok(proof)
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
         starks::{
-            example::simple_fibonacci::{self, FibonacciPublicInputs},
-            proof::options::ProofOptions,
+            example::{
+                fibonacci_rap::{fibonacci_rap_trace, FibonacciRAP, FibonacciRAPPublicInputs},
+                simple_fibonacci::{self, FibonacciPublicInputs},
+            },
+            proof::options::{
+                CommitmentHash, CosetOffset, GrindingConfig, MerkleTreeStorage, ProofOptions,
+            },
         },
         FE,
     };
+    use lambdaworks_crypto::fiat_shamir::default_transcript::DefaultTranscript;
+    use lambdaworks_math::field::fields::u64_prime_field::F17;
+    use lambdaworks_math::traits::{Deserializable, Serializable};
 
     use super::*;
     use lambdaworks_math::{
@@ -792,15 +2022,23 @@ mod tests {
         };
         let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
         let trace_length = trace.n_rows();
-        let coset_offset = 3;
+        let coset_offset = FE::from(3);
         let blowup_factor: usize = 2;
-        let grinding_factor = 20;
+        let grinding = GrindingConfig::Bits(20);
 
         let proof_options = ProofOptions {
             blowup_factor: blowup_factor as u8,
             fri_number_of_queries: 1,
-            coset_offset,
-            grinding_factor,
+            coset_offset: CosetOffset::from_field_element(&coset_offset),
+            grinding,
+            commitment_layout: CommitmentLayout::RowBatched,
+            commitment_hash: CommitmentHash::Keccak256,
+            deduplicate_queries: false,
+            fri_max_layers: None,
+            salt_commitments: false,
+            trace_randomization_rows: 0,
+            merkle_cap_height: 0,
+            merkle_tree_storage: MerkleTreeStorage::InMemory,
         };
 
         let domain = Domain::new(&simple_fibonacci::FibonacciAIR::new(
@@ -810,12 +2048,12 @@ mod tests {
         ));
         assert_eq!(domain.blowup_factor, 2);
         assert_eq!(domain.interpolation_domain_size, trace_length);
-        assert_eq!(domain.root_order, trace_length.trailing_zeros());
+        assert_eq!(domain.root_order, u64::from(trace_length.trailing_zeros()));
         assert_eq!(
             domain.lde_root_order,
-            (trace_length * blowup_factor).trailing_zeros()
+            u64::from((trace_length * blowup_factor).trailing_zeros())
         );
-        assert_eq!(domain.coset_offset, FieldElement::from(coset_offset));
+        assert_eq!(domain.coset_offset, coset_offset);
 
         let primitive_root = Stark252PrimeField::get_primitive_root_of_unity(
             (trace_length * blowup_factor).trailing_zeros() as u64,
@@ -829,11 +2067,71 @@ mod tests {
         for i in 0..(trace_length * blowup_factor) {
             assert_eq!(
                 domain.lde_roots_of_unity_coset[i],
-                FieldElement::from(coset_offset) * primitive_root.pow(i)
+                &coset_offset * primitive_root.pow(i)
             );
         }
     }
 
+    // Needs around 16 GB of memory for the roots of unity of the domains
+    #[test]
+    #[ignore]
+    fn test_domain_of_size_2_to_the_28() {
+        let pub_inputs = FibonacciPublicInputs {
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+        let trace_length = 1 << 26;
+        let proof_options = ProofOptions::default_test_options();
+        let lde_domain_size =
+            lde_domain_size::<Stark252PrimeField>(trace_length, proof_options.blowup_factor)
+                .unwrap();
+        assert_eq!(lde_domain_size, 1 << 28);
+
+        let domain = Domain::new(&simple_fibonacci::FibonacciAIR::new(
+            trace_length,
+            &pub_inputs,
+            &proof_options,
+        ));
+        assert_eq!(domain.root_order, 26);
+        assert_eq!(domain.lde_root_order, 28);
+        assert_eq!(domain.lde_roots_of_unity_coset.len(), lde_domain_size);
+
+        let primitive_root = Stark252PrimeField::get_primitive_root_of_unity(28).unwrap();
+        let last_index = lde_domain_size - 1;
+        assert_eq!(
+            domain.lde_roots_of_unity_coset[last_index],
+            &domain.coset_offset * primitive_root.pow(last_index)
+        );
+        assert_eq!(
+            domain.trace_roots_of_unity[trace_length - 1],
+            domain.trace_primitive_root.pow(trace_length - 1)
+        );
+
+        let mut transcript = DefaultTranscript::new();
+        let iotas = crate::starks::transcript::sample_query_indexes(
+            &mut transcript,
+            64,
+            lde_domain_size,
+            false,
+        );
+        assert!(iotas.iter().all(|iota| *iota < lde_domain_size));
+    }
+
+    #[test]
+    fn test_lde_domains_are_bounded_by_the_two_adicity_of_the_field() {
+        assert_eq!(
+            lde_domain_size::<Stark252PrimeField>(1 << 20, 4),
+            Some(1 << 22)
+        );
+        assert_eq!(lde_domain_size::<F17>(4, 4), Some(16));
+        assert_eq!(lde_domain_size::<F17>(8, 4), None);
+        assert_eq!(lde_domain_size::<Stark252PrimeField>(6, 4), None);
+        assert_eq!(
+            lde_domain_size::<Stark252PrimeField>(usize::MAX / 2 + 1, 4),
+            None
+        );
+    }
+
     #[test]
     fn test_evaluate_polynomial_on_lde_domain_on_trace_polys() {
         let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
@@ -880,4 +2178,162 @@ mod tests {
             assert_eq!(*eval, poly.evaluate(&(&offset * &primitive_root.pow(i))));
         }
     }
+
+    #[test]
+    fn test_resumed_session_generates_the_same_proof() {
+        let steps = 16;
+        let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
+        let proof_options = ProofOptions::default_test_options();
+        let pub_inputs = FibonacciRAPPublicInputs {
+            steps,
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+
+        let expected_proof = prove::<Stark252PrimeField, FibonacciRAP<Stark252PrimeField>>(
+            &trace,
+            &pub_inputs,
+            &proof_options,
+        )
+        .unwrap();
+
+        for checkpoint_round in 1..=4 {
+            let mut session = ProverSession::<Stark252PrimeField, FibonacciRAP<_>>::new(
+                trace.n_rows(),
+                &pub_inputs,
+                &proof_options,
+            )
+            .unwrap();
+            session.run_round_1(&trace).unwrap();
+            if checkpoint_round >= 2 {
+                session.run_round_2().unwrap();
+            }
+            if checkpoint_round >= 3 {
+                session.run_round_3().unwrap();
+            }
+            if checkpoint_round == 4 {
+                session.commit_fri_layers().unwrap();
+            }
+
+            let checkpoint_bytes = session.checkpoint().unwrap().serialize();
+            let checkpoint = ProverCheckpoint::deserialize(&checkpoint_bytes).unwrap();
+
+            let mut resumed_session = ProverSession::<Stark252PrimeField, FibonacciRAP<_>>::resume(
+                &checkpoint,
+                &pub_inputs,
+                &proof_options,
+            )
+            .unwrap();
+            assert_eq!(resumed_session.completed_rounds(), checkpoint_round);
+            if checkpoint_round < 2 {
+                resumed_session.run_round_2().unwrap();
+            }
+            if checkpoint_round < 3 {
+                resumed_session.run_round_3().unwrap();
+            }
+            let proof = resumed_session.finish().unwrap();
+
+            assert_eq!(proof.serialize(), expected_proof.serialize());
+        }
+    }
+
+    #[test]
+    fn test_proof_with_metrics_is_the_proof_with_the_metrics_of_every_round() {
+        let steps = 16;
+        let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
+        let proof_options = ProofOptions::default_test_options();
+        let pub_inputs = FibonacciRAPPublicInputs {
+            steps,
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+
+        let expected_proof = prove::<Stark252PrimeField, FibonacciRAP<Stark252PrimeField>>(
+            &trace,
+            &pub_inputs,
+            &proof_options,
+        )
+        .unwrap();
+        let (proof, metrics) = prove_with_metrics::<
+            Stark252PrimeField,
+            FibonacciRAP<Stark252PrimeField>,
+        >(&trace, &pub_inputs, &proof_options)
+        .unwrap();
+
+        assert_eq!(proof.serialize(), expected_proof.serialize());
+        let stages: Vec<_> = metrics.stages.iter().map(|stage| stage.name).collect();
+        assert_eq!(
+            stages,
+            [
+                "round_0_transcript_initialization",
+                "round_1_rap",
+                "round_2_composition_polynomial",
+                "round_3_out_of_domain_evaluations",
+                "round_4_fri",
+            ]
+        );
+        let grinding_work = metrics.grinding.unwrap();
+        assert_eq!(grinding_work.nonce, proof.nonce);
+        assert_eq!(grinding_work.attempts, proof.nonce + 1);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_deterministic_proofs_are_the_same_with_any_number_of_threads() {
+        let steps = 64;
+        let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
+        let proof_options = ProofOptions::default_test_options();
+        let pub_inputs = FibonacciRAPPublicInputs {
+            steps,
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+        assert!(proof_options.is_deterministic());
+
+        let prove_in_threads = |num_threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .unwrap()
+                .install(|| {
+                    prove::<Stark252PrimeField, FibonacciRAP<Stark252PrimeField>>(
+                        &trace,
+                        &pub_inputs,
+                        &proof_options,
+                    )
+                    .unwrap()
+                    .serialize()
+                })
+        };
+
+        let sequential_proof = prove_in_threads(1);
+        for num_threads in [2, 3, 8] {
+            assert_eq!(prove_in_threads(num_threads), sequential_proof);
+        }
+    }
+
+    #[test]
+    fn test_session_rejects_rounds_out_of_order() {
+        let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+        let pub_inputs = FibonacciPublicInputs {
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+        let mut session =
+            ProverSession::<Stark252PrimeField, simple_fibonacci::FibonacciAIR<_>>::new(
+                trace.n_rows(),
+                &pub_inputs,
+                &ProofOptions::default_test_options(),
+            )
+            .unwrap();
+
+        assert!(matches!(
+            session.run_round_2(),
+            Err(ProvingError::InvalidSessionState(_))
+        ));
+        assert!(matches!(
+            session.checkpoint(),
+            Err(ProvingError::InvalidSessionState(_))
+        ));
+    }
 }
@@ -0,0 +1,248 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, Receiver, Sender},
+    Arc,
+};
+use std::thread::JoinHandle;
+
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    traits::ByteConversion,
+};
+
+use super::proof::{field_id::IdentifiedField, options::ProofOptions, stark::StarkProof};
+use super::prover::{ProverSession, ProvingError};
+use super::trace::TraceTable;
+use super::traits::AIR;
+
+/// Stages of the proof generation reported by a `ProverHandle`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProvingStage {
+    Rap,
+    CompositionPolynomial,
+    OutOfDomainEvaluation,
+    Fri,
+}
+
+/// Progress update sent when a stage of the proof generation is finished.
+/// `percent` is the fraction of the stages already completed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProvingProgress {
+    pub stage: ProvingStage,
+    pub percent: u8,
+}
+
+/// Flag used to cooperatively cancel a running proof generation.
+/// Cancellation is checked between the rounds of the protocol.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A proof generation running on a background thread.
+pub struct ProverHandle<F>
+where
+    F: IsFFTField,
+{
+    thread: JoinHandle<Result<StarkProof<F>, ProvingError>>,
+    progress: Receiver<ProvingProgress>,
+    cancellation_token: CancellationToken,
+}
+
+impl<F> ProverHandle<F>
+where
+    F: IsFFTField + IdentifiedField,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    /// Starts proving `main_trace` on a background thread.
+    pub fn spawn<A>(
+        main_trace: TraceTable<F>,
+        pub_inputs: A::PublicInputs,
+        proof_options: ProofOptions,
+    ) -> Self
+    where
+        A: AIR<Field = F> + Send + Sync + 'static,
+        A::RAPChallenges: Send + Sync,
+        A::PublicInputs: Send + 'static,
+    {
+        Self::spawn_with_token::<A>(
+            main_trace,
+            pub_inputs,
+            proof_options,
+            CancellationToken::new(),
+        )
+    }
+
+    /// Same as `spawn`, but using a cancellation token provided by the caller,
+    /// so a single token can cancel several jobs at once.
+    pub fn spawn_with_token<A>(
+        main_trace: TraceTable<F>,
+        pub_inputs: A::PublicInputs,
+        proof_options: ProofOptions,
+        cancellation_token: CancellationToken,
+    ) -> Self
+    where
+        A: AIR<Field = F> + Send + Sync + 'static,
+        A::RAPChallenges: Send + Sync,
+        A::PublicInputs: Send + 'static,
+    {
+        let (sender, progress) = mpsc::channel();
+        let token = cancellation_token.clone();
+
+        let thread = std::thread::spawn(move || {
+            run_session::<F, A>(&main_trace, &pub_inputs, &proof_options, &token, &sender)
+        });
+
+        Self {
+            thread,
+            progress,
+            cancellation_token,
+        }
+    }
+
+    /// Receiver of the progress updates. The channel is closed once the proof
+    /// generation finishes, so it can be consumed as a blocking iterator.
+    pub fn progress(&self) -> &Receiver<ProvingProgress> {
+        &self.progress
+    }
+
+    /// Requests the cancellation of the proof generation. The prover stops
+    /// before starting its next round and `join` returns `ProvingError::Cancelled`.
+    pub fn cancel(&self) {
+        self.cancellation_token.cancel();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the proof generation to finish and returns its result.
+    /// If the prover panicked, the panic is propagated to the caller.
+    pub fn join(self) -> Result<StarkProof<F>, ProvingError> {
+        self.thread.join().unwrap_or_else(std::panic::resume_unwind)
+    }
+}
+
+fn run_session<F, A>(
+    main_trace: &TraceTable<F>,
+    pub_inputs: &A::PublicInputs,
+    proof_options: &ProofOptions,
+    cancellation_token: &CancellationToken,
+    progress: &Sender<ProvingProgress>,
+) -> Result<StarkProof<F>, ProvingError>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F> + Send + Sync,
+    A::RAPChallenges: Send + Sync,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    let check_cancellation = || {
+        if cancellation_token.is_cancelled() {
+            Err(ProvingError::Cancelled)
+        } else {
+            Ok(())
+        }
+    };
+    // The receiver may have been dropped if the caller is not interested in the progress.
+    let report = |stage, percent| {
+        let _ = progress.send(ProvingProgress { stage, percent });
+    };
+
+    check_cancellation()?;
+    let trace_length = main_trace.n_rows() + proof_options.trace_randomization_rows;
+    let mut session = ProverSession::<F, A>::new(trace_length, pub_inputs, proof_options)?;
+    session.run_round_1(main_trace)?;
+    report(ProvingStage::Rap, 25);
+
+    check_cancellation()?;
+    session.run_round_2()?;
+    report(ProvingStage::CompositionPolynomial, 50);
+
+    check_cancellation()?;
+    session.run_round_3()?;
+    report(ProvingStage::OutOfDomainEvaluation, 75);
+
+    check_cancellation()?;
+    let proof = session.finish()?;
+    report(ProvingStage::Fri, 100);
+
+    Ok(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        starks::{
+            example::simple_fibonacci::{self, FibonacciAIR, FibonacciPublicInputs},
+            verifier::verify,
+        },
+        PrimeField, FE,
+    };
+
+    use super::*;
+
+    fn fibonacci_job() -> (TraceTable<PrimeField>, FibonacciPublicInputs<PrimeField>) {
+        let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+        let pub_inputs = FibonacciPublicInputs {
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+        (trace, pub_inputs)
+    }
+
+    #[test]
+    fn handle_reports_progress_and_returns_a_valid_proof() {
+        let (trace, pub_inputs) = fibonacci_job();
+        let proof_options = ProofOptions::default_test_options();
+
+        let handle = ProverHandle::spawn::<FibonacciAIR<PrimeField>>(
+            trace,
+            pub_inputs.clone(),
+            proof_options.clone(),
+        );
+        let updates: Vec<_> = handle.progress().iter().collect();
+        let proof = handle.join().unwrap();
+
+        assert_eq!(
+            updates
+                .iter()
+                .map(|update| update.percent)
+                .collect::<Vec<_>>(),
+            vec![25, 50, 75, 100]
+        );
+        assert_eq!(updates.last().unwrap().stage, ProvingStage::Fri);
+        assert!(verify::<PrimeField, FibonacciAIR<PrimeField>>(
+            &proof,
+            &pub_inputs,
+            &proof_options
+        ));
+    }
+
+    #[test]
+    fn cancelled_handle_returns_cancelled_error() {
+        let (trace, pub_inputs) = fibonacci_job();
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let handle = ProverHandle::spawn_with_token::<FibonacciAIR<PrimeField>>(
+            trace,
+            pub_inputs,
+            ProofOptions::default_test_options(),
+            token,
+        );
+
+        assert!(matches!(handle.join(), Err(ProvingError::Cancelled)));
+    }
+}
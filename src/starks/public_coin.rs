@@ -0,0 +1,106 @@
+use lambdaworks_crypto::fiat_shamir::{
+    default_transcript::DefaultTranscript, transcript::Transcript,
+};
+use sha3::{Digest, Keccak256};
+
+/// Source of the challenges of the verifier, for interactive executions of the
+/// protocol.
+///
+/// Every message of the prover is passed to `receive` before the challenges that
+/// depend on it are requested, in the same order by the prover and the verifier.
+/// The protocol is sound only if the prover can't predict a challenge before it
+/// sends the messages preceding it.
+pub trait ChallengeProvider {
+    /// Receives a message sent by the prover.
+    fn receive(&mut self, message: &[u8]);
+
+    /// Returns the random bytes of the next challenge.
+    fn challenge(&mut self) -> [u8; 32];
+}
+
+/// The Fiat-Shamir transform as a challenge provider. Proving interactively with
+/// it gives the same proofs as the non-interactive prover, which is useful for
+/// differential testing.
+impl ChallengeProvider for DefaultTranscript {
+    fn receive(&mut self, message: &[u8]) {
+        self.append(message);
+    }
+
+    fn challenge(&mut self) -> [u8; 32] {
+        Transcript::challenge(self)
+    }
+}
+
+/// Challenges derived from a random seed supplied by the verifier, or by a
+/// randomness beacon shared by both parties. The messages of the prover are
+/// ignored, so a prover that learns the seed before committing to the trace can
+/// forge proofs.
+#[derive(Clone, Debug)]
+pub struct SeededChallengeProvider {
+    seed: [u8; 32],
+    counter: u64,
+}
+
+impl SeededChallengeProvider {
+    pub fn new(seed: [u8; 32]) -> Self {
+        Self { seed, counter: 0 }
+    }
+}
+
+impl ChallengeProvider for SeededChallengeProvider {
+    fn receive(&mut self, _message: &[u8]) {}
+
+    fn challenge(&mut self) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.seed);
+        hasher.update(self.counter.to_be_bytes());
+        self.counter += 1;
+        hasher.finalize().into()
+    }
+}
+
+/// Transcript that takes the challenges from a `ChallengeProvider` instead of
+/// hashing the messages of the prover.
+pub struct PublicCoinTranscript<P: ChallengeProvider> {
+    provider: P,
+}
+
+impl<P: ChallengeProvider> PublicCoinTranscript<P> {
+    pub fn new(provider: P) -> Self {
+        Self { provider }
+    }
+
+    pub fn into_provider(self) -> P {
+        self.provider
+    }
+}
+
+impl<P: ChallengeProvider> Transcript for PublicCoinTranscript<P> {
+    fn append(&mut self, new_data: &[u8]) {
+        self.provider.receive(new_data);
+    }
+
+    fn challenge(&mut self) -> [u8; 32] {
+        self.provider.challenge()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_challenges_only_depend_on_the_seed() {
+        let mut provider = SeededChallengeProvider::new([1; 32]);
+        let mut other_provider = SeededChallengeProvider::new([1; 32]);
+        other_provider.receive(b"ignored message");
+
+        let first_challenge = provider.challenge();
+        assert_eq!(first_challenge, other_provider.challenge());
+        assert_ne!(first_challenge, provider.challenge());
+        assert_ne!(
+            first_challenge,
+            SeededChallengeProvider::new([2; 32]).challenge()
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
+    polynomial::Polynomial,
+};
+use rand::RngCore;
+
+use super::trace::TraceTable;
+
+/// Number of random 64-bit limbs combined into a field element. Taken modulo a
+/// prime of up to 256 bits, 320 random bits are statistically close to uniform.
+const RANDOM_LIMBS: usize = 5;
+
+pub fn random_field_element<F: IsField>() -> FieldElement<F> {
+    let mut rng = rand::thread_rng();
+    let limb_base = FieldElement::<F>::from(u64::MAX) + FieldElement::one();
+    (0..RANDOM_LIMBS).fold(FieldElement::zero(), |acc, _| {
+        acc * &limb_base + FieldElement::from(rng.next_u64())
+    })
+}
+
+pub fn random_polynomial<F: IsField>(num_coefficients: usize) -> Polynomial<FieldElement<F>> {
+    let coefficients: Vec<_> = (0..num_coefficients)
+        .map(|_| random_field_element())
+        .collect();
+    Polynomial::new(&coefficients)
+}
+
+/// Returns the trace extended with `rows` random rows.
+pub fn append_random_rows<F: IsFFTField>(trace: &TraceTable<F>, rows: usize) -> TraceTable<F> {
+    let mut table = trace.table.clone();
+    table.extend((0..rows * trace.n_cols).map(|_| random_field_element::<F>()));
+    TraceTable::new(table, trace.n_cols).with_rows_per_step(trace.rows_per_step())
+}
+
+/// Replaces the values of the last `rows` rows of the trace with random ones.
+pub fn randomize_last_rows<F: IsFFTField>(trace: &mut TraceTable<F>, rows: usize) {
+    let start = trace.table.len().saturating_sub(rows * trace.n_cols);
+    for value in &mut trace.table[start..] {
+        *value = random_field_element();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    #[test]
+    fn random_rows_are_appended_after_the_trace() {
+        let trace = TraceTable::new((0..6).map(FE::from).collect(), 2).with_rows_per_step(3);
+        let extended = append_random_rows(&trace, 2);
+
+        assert_eq!(extended.n_rows(), 5);
+        assert_eq!(extended.rows_per_step(), 3);
+        assert_eq!(&extended.table[..6], &trace.table[..]);
+        assert_ne!(extended.get_row(3), extended.get_row(4));
+    }
+
+    #[test]
+    fn only_the_last_rows_are_randomized() {
+        let mut trace = TraceTable::new(vec![FE::zero(); 8], 2);
+        randomize_last_rows(&mut trace, 1);
+
+        assert_eq!(&trace.table[..6], &[FE::zero(); 6]);
+        assert_ne!(trace.get(3, 0), FE::zero());
+        assert_ne!(trace.get(3, 0), trace.get(3, 1));
+    }
+}
@@ -0,0 +1,157 @@
+use std::any::{Any, TypeId};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use lambdaworks_math::{
+    fft::cpu::roots_of_unity::get_powers_of_primitive_root_coset,
+    field::{element::FieldElement, traits::IsFFTField},
+};
+
+use super::proof::options::CosetOffset;
+
+/// Field, order of the primitive root, number of powers and coset offset of a table.
+type TableKey = (TypeId, u64, usize, CosetOffset);
+
+/// Maximum number of field elements kept by the cache. Since the verifier builds
+/// domains of the trace length claimed by a proof, the oldest tables are evicted
+/// past it, and larger tables aren't cached at all.
+pub const MAX_CACHED_ELEMENTS: usize = 1 << 23;
+
+/// Tables of powers of roots of unity computed by the process. Proofs of traces of
+/// the same length and with the same options share the same domains, so they are
+/// computed only once, which matters for provers of many small programs.
+static TABLES: Mutex<Tables> = Mutex::new(Tables::new());
+
+/// Cached tables, with their keys in the order they were inserted.
+struct Tables {
+    tables: BTreeMap<TableKey, Arc<dyn Any + Send + Sync>>,
+    insertion_order: VecDeque<TableKey>,
+    cached_elements: usize,
+}
+
+impl Tables {
+    const fn new() -> Self {
+        Self {
+            tables: BTreeMap::new(),
+            insertion_order: VecDeque::new(),
+            cached_elements: 0,
+        }
+    }
+
+    /// Inserts a table of `len` elements, evicting the oldest ones to stay within
+    /// `MAX_CACHED_ELEMENTS`.
+    fn insert(&mut self, key: TableKey, len: usize, table: Arc<dyn Any + Send + Sync>) {
+        if len > MAX_CACHED_ELEMENTS || self.tables.contains_key(&key) {
+            return;
+        }
+        while self.cached_elements + len > MAX_CACHED_ELEMENTS {
+            let Some(oldest) = self.insertion_order.pop_front() else {
+                break;
+            };
+            self.tables.remove(&oldest);
+            self.cached_elements -= oldest.2;
+        }
+        self.tables.insert(key.clone(), table);
+        self.insertion_order.push_back(key);
+        self.cached_elements += len;
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// Returns `offset * ω^i` for `i` in `0..len`, with `ω` the primitive root of unity
+/// of order `2^order`. Tables are cached for the rest of the process, up to
+/// `MAX_CACHED_ELEMENTS`, see `clear_cached_tables`.
+pub fn cached_powers_of_primitive_root_coset<F>(
+    order: u64,
+    len: usize,
+    offset: &CosetOffset,
+) -> Arc<Vec<FieldElement<F>>>
+where
+    F: IsFFTField + 'static,
+    FieldElement<F>: Send + Sync,
+{
+    let key = (TypeId::of::<F>(), order, len, offset.clone());
+    if let Some(table) = lock_tables().tables.get(&key) {
+        return Arc::clone(table)
+            .downcast()
+            .expect("tables are keyed by their field");
+    }
+
+    // The table is computed without holding the lock, so workers proving traces of
+    // different lengths don't wait for each other.
+    let table = Arc::new(
+        get_powers_of_primitive_root_coset(order, len, &offset.to_field_element::<F>())
+            .expect("the field has roots of unity of the order of the domain"),
+    );
+    lock_tables().insert(key, len, table.clone());
+    table
+}
+
+/// Frees the cached tables. Domains already built keep theirs alive until dropped.
+pub fn clear_cached_tables() {
+    lock_tables().clear();
+}
+
+fn lock_tables() -> MutexGuard<'static, Tables> {
+    // The tables are only inserted once they are complete, so they are valid even
+    // if another thread panicked while holding the lock.
+    TABLES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    #[test]
+    fn cached_tables_are_shared_and_match_the_computed_ones() {
+        let table = cached_powers_of_primitive_root_coset::<Stark252PrimeField>(4, 16, &3.into());
+        let expected = get_powers_of_primitive_root_coset(4, 16, &FieldElement::from(3)).unwrap();
+
+        assert_eq!(*table, expected);
+        assert!(Arc::ptr_eq(
+            &table,
+            &cached_powers_of_primitive_root_coset::<Stark252PrimeField>(4, 16, &3.into())
+        ));
+        assert!(!Arc::ptr_eq(
+            &table,
+            &cached_powers_of_primitive_root_coset::<Stark252PrimeField>(4, 16, &1.into())
+        ));
+    }
+
+    #[test]
+    fn cache_evicts_the_oldest_tables_past_its_capacity() {
+        let table = |offset: u64| -> Arc<dyn Any + Send + Sync> { Arc::new(offset) };
+        let key =
+            |offset: u64, len: usize| (TypeId::of::<Stark252PrimeField>(), 1, len, offset.into());
+        let mut tables = Tables::new();
+
+        tables.insert(
+            key(1, MAX_CACHED_ELEMENTS / 2),
+            MAX_CACHED_ELEMENTS / 2,
+            table(1),
+        );
+        tables.insert(
+            key(2, MAX_CACHED_ELEMENTS / 2),
+            MAX_CACHED_ELEMENTS / 2,
+            table(2),
+        );
+        assert_eq!(tables.tables.len(), 2);
+
+        tables.insert(key(3, 1), 1, table(3));
+        assert!(!tables.tables.contains_key(&key(1, MAX_CACHED_ELEMENTS / 2)));
+        assert_eq!(tables.tables.len(), 2);
+        assert_eq!(tables.cached_elements, MAX_CACHED_ELEMENTS / 2 + 1);
+
+        tables.insert(
+            key(4, MAX_CACHED_ELEMENTS + 1),
+            MAX_CACHED_ELEMENTS + 1,
+            table(4),
+        );
+        assert!(!tables.tables.contains_key(&key(4, MAX_CACHED_ELEMENTS + 1)));
+    }
+}
@@ -0,0 +1,101 @@
+use lambdaworks_math::field::{element::FieldElement, traits::IsField};
+use rand::RngCore;
+use sha3::{Digest, Keccak256};
+
+use super::config::{Salt, SALT_SIZE};
+
+/// Salts of the leaves of a Merkle commitment.
+///
+/// The salt of each leaf is derived from a secret seed, the index of the commitment
+/// in the proof and the index of the leaf. This way the prover doesn't need to keep
+/// them around, and gets the same ones when resuming a session from a checkpoint.
+#[derive(Clone, Copy, Debug)]
+pub struct LeafSalts<'a> {
+    seed: &'a Salt,
+    commitment_index: usize,
+}
+
+impl<'a> LeafSalts<'a> {
+    pub fn new(seed: &'a Salt, commitment_index: usize) -> Self {
+        Self {
+            seed,
+            commitment_index,
+        }
+    }
+
+    pub fn salt(&self, leaf_index: usize) -> Salt {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.seed);
+        hasher.update((self.commitment_index as u64).to_be_bytes());
+        hasher.update((leaf_index as u64).to_be_bytes());
+        hasher.finalize().into()
+    }
+
+    /// Returns the leaves with their salts appended. See `salted_leaf`.
+    pub fn salt_leaves<F: IsField>(
+        &self,
+        leaves: &[Vec<FieldElement<F>>],
+    ) -> Vec<Vec<FieldElement<F>>> {
+        leaves
+            .iter()
+            .enumerate()
+            .map(|(leaf_index, leaf)| salted_leaf(leaf, &self.salt(leaf_index)))
+            .collect()
+    }
+}
+
+pub fn random_salt() -> Salt {
+    let mut salt = [0; SALT_SIZE];
+    rand::thread_rng().fill_bytes(&mut salt);
+    salt
+}
+
+/// Data hashed for a salted leaf: its values followed by the salt, split in 8-byte
+/// chunks taken as field elements.
+pub fn salted_leaf<F: IsField>(leaf: &[FieldElement<F>], salt: &Salt) -> Vec<FieldElement<F>> {
+    let mut salted_leaf = leaf.to_vec();
+    salted_leaf.extend(salt.chunks_exact(8).map(|chunk| {
+        let chunk: [u8; 8] = chunk.try_into().expect("chunks have 8 bytes");
+        FieldElement::from(u64::from_be_bytes(chunk))
+    }));
+    salted_leaf
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    #[test]
+    fn salts_depend_on_the_commitment_and_the_leaf() {
+        let seed = [1; SALT_SIZE];
+        let salts = LeafSalts::new(&seed, 0);
+
+        assert_eq!(salts.salt(3), LeafSalts::new(&seed, 0).salt(3));
+        assert_ne!(salts.salt(3), salts.salt(4));
+        assert_ne!(salts.salt(3), LeafSalts::new(&seed, 1).salt(3));
+        assert_ne!(salts.salt(3), LeafSalts::new(&[2; SALT_SIZE], 0).salt(3));
+    }
+
+    #[test]
+    fn salted_leaves_keep_their_values_first() {
+        let leaf = vec![FE::from(5), FE::from(7)];
+        let mut salt = [0; SALT_SIZE];
+        salt[7] = 1;
+
+        assert_eq!(
+            salted_leaf(&leaf, &salt),
+            vec![
+                FE::from(5),
+                FE::from(7),
+                FE::from(1),
+                FE::zero(),
+                FE::zero(),
+                FE::zero()
+            ]
+        );
+    }
+}
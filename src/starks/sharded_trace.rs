@@ -0,0 +1,753 @@
+//! Experimental sharding of the trace commitments of the prover between workers.
+//! A `Coordinator` splits the low degree extension of the trace by columns, and the
+//! Merkle trees of the trace by contiguous ranges of rows. Each worker keeps the
+//! subtree of its range and returns its root, and the coordinator merges them into
+//! the root of the whole tree, the same one a single prover computes. The queries
+//! are opened by asking each worker for the paths in its subtree and completing
+//! them with the levels above the subtrees, so the proofs are checked by `verify`.
+//!
+//! Only the trace is sharded. `prove_with_sharded_trace` runs the other rounds in
+//! the process, which holds the whole LDE trace to evaluate the constraints, and
+//! commits to the composition polynomial and the FRI layers itself. It spreads the
+//! work of the trace commitments, not the memory of the prover.
+//!
+//! Workers are reached through a `WorkerTransport`. `ChannelWorker` runs one in a
+//! thread of the process, and remote workers implement the trait over their
+//! connection, running a `Worker` on their side.
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+use lambdaworks_crypto::merkle_tree::proof::Proof;
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    polynomial::Polynomial,
+    traits::ByteConversion,
+};
+use thiserror::Error;
+
+use super::config::{
+    BatchedLeaves, BatchedMerkleTree, Commitment, CommitmentLeaves, CommitmentTree, MerkleCap,
+};
+use super::domain::Domain;
+use super::proof::field_id::IdentifiedField;
+use super::proof::options::{CommitmentHash, MerkleTreeStorage, ProofOptions};
+use super::proof::stark::StarkProof;
+use super::prover::{
+    evaluate_polynomial_on_lde_domain, prove_with_trace_committer, ProvingError, TraceCommitment,
+    TraceCommitter,
+};
+use super::trace::TraceTable;
+use super::traits::AIR;
+
+#[derive(Debug, Error)]
+pub enum ShardingError {
+    #[error("There are no workers to distribute the work")]
+    NoWorkers,
+    #[error("Failed to reach worker {0}: {1}")]
+    Transport(usize, String),
+    #[error("Worker {0} failed: {1}")]
+    Worker(usize, String),
+    #[error("Worker {0} sent a response not matching its task")]
+    UnexpectedResponse(usize),
+    #[error("Position {0} is outside of the committed leaves")]
+    PositionOutOfRange(usize),
+}
+
+/// Work sent by the coordinator to a worker.
+pub enum Task<F: IsFFTField> {
+    /// Evaluate the polynomials on the LDE domain.
+    Extend {
+        polys: Vec<Polynomial<FieldElement<F>>>,
+        blowup_factor: usize,
+        domain_size: usize,
+        offset: FieldElement<F>,
+    },
+    /// Build the Merkle tree of a range of rows of the LDE trace, and keep it as
+    /// `commitment` until it's released. Answered with its root and its cap of
+    /// height `cap_height`.
+    CommitRows {
+        commitment: usize,
+        rows: Vec<Vec<FieldElement<F>>>,
+        hash: CommitmentHash,
+        cap_height: usize,
+    },
+    /// Paths of the leaves at `positions` of the tree kept as `commitment`, in
+    /// their order. The positions are the ones in the range of the worker.
+    Open {
+        commitment: usize,
+        positions: Vec<usize>,
+    },
+    /// Drop the tree kept as `commitment`.
+    Release { commitment: usize },
+}
+
+/// Result of a `Task`, sent back by the worker.
+pub enum Response<F: IsFFTField> {
+    Evaluations(Vec<Vec<FieldElement<F>>>),
+    Committed { root: Commitment, cap: MerkleCap },
+    Paths(Vec<Proof<Commitment>>),
+    Released,
+    Failed(String),
+}
+
+/// Runs the tasks received by a worker, keeping the trees it commits until the
+/// coordinator releases them.
+pub struct Worker<F>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    trees: HashMap<usize, BatchedMerkleTree<F>>,
+}
+
+impl<F> Default for Worker<F>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion,
+{
+    fn default() -> Self {
+        Self {
+            trees: HashMap::new(),
+        }
+    }
+}
+
+impl<F> Worker<F>
+where
+    F: IsFFTField,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    pub fn run(&mut self, task: Task<F>) -> Response<F> {
+        match task {
+            Task::Extend {
+                polys,
+                blowup_factor,
+                domain_size,
+                offset,
+            } => polys
+                .iter()
+                .map(|poly| {
+                    evaluate_polynomial_on_lde_domain(poly, blowup_factor, domain_size, &offset)
+                })
+                .collect::<Result<_, _>>()
+                .map_or_else(
+                    |error| Response::Failed(format!("{error:?}")),
+                    Response::Evaluations,
+                ),
+            Task::CommitRows {
+                commitment,
+                rows,
+                hash,
+                cap_height,
+            } => {
+                let tree = match BatchedMerkleTree::<F>::build(
+                    hash,
+                    &MerkleTreeStorage::InMemory,
+                    &rows,
+                ) {
+                    Ok(tree) => tree,
+                    Err(error) => return Response::Failed(error.to_string()),
+                };
+                let response = Response::Committed {
+                    root: tree.root(),
+                    cap: tree.cap(cap_height),
+                };
+                self.trees.insert(commitment, tree);
+                response
+            }
+            Task::Open {
+                commitment,
+                positions,
+            } => {
+                let Some(tree) = self.trees.get(&commitment) else {
+                    return Response::Failed(format!("no commitment {commitment}"));
+                };
+                tree.get_proofs_by_pos(&positions).map_or_else(
+                    || Response::Failed("a position is outside of the range".to_string()),
+                    Response::Paths,
+                )
+            }
+            Task::Release { commitment } => {
+                self.trees.remove(&commitment);
+                Response::Released
+            }
+        }
+    }
+}
+
+/// Connection of the coordinator to a worker. The coordinator sends the tasks of
+/// all the workers before waiting for their responses, so they run concurrently.
+pub trait WorkerTransport<F: IsFFTField> {
+    fn send(&self, task: Task<F>) -> Result<(), String>;
+
+    /// Waits for the response to the oldest task sent and not answered yet.
+    fn receive(&self) -> Result<Response<F>, String>;
+}
+
+/// Worker running in a thread of the process, connected by channels. The thread
+/// finishes when the worker is dropped.
+pub struct ChannelWorker<F: IsFFTField> {
+    tasks: Sender<Task<F>>,
+    responses: Receiver<Response<F>>,
+}
+
+impl<F> ChannelWorker<F>
+where
+    F: IsFFTField + 'static,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    pub fn spawn() -> Self {
+        let (tasks, task_receiver) = mpsc::channel::<Task<F>>();
+        let (response_sender, responses) = mpsc::channel();
+        thread::spawn(move || {
+            let mut worker = Worker::default();
+            for task in task_receiver {
+                if response_sender.send(worker.run(task)).is_err() {
+                    return;
+                }
+            }
+        });
+        Self { tasks, responses }
+    }
+}
+
+impl<F: IsFFTField> WorkerTransport<F> for ChannelWorker<F> {
+    fn send(&self, task: Task<F>) -> Result<(), String> {
+        self.tasks
+            .send(task)
+            .map_err(|_| "the worker thread finished".to_string())
+    }
+
+    fn receive(&self) -> Result<Response<F>, String> {
+        self.responses
+            .recv()
+            .map_err(|_| "the worker thread finished".to_string())
+    }
+}
+
+/// Splits the trace commitments of a proof between its workers. Its clones share the
+/// workers.
+pub struct Coordinator<W> {
+    // Locked while a batch of tasks runs, so each response is matched with its task
+    workers: Arc<Mutex<Vec<W>>>,
+    num_workers: usize,
+    next_commitment: Arc<AtomicUsize>,
+}
+
+impl<W> Clone for Coordinator<W> {
+    fn clone(&self) -> Self {
+        Self {
+            workers: Arc::clone(&self.workers),
+            num_workers: self.num_workers,
+            next_commitment: Arc::clone(&self.next_commitment),
+        }
+    }
+}
+
+impl<W> Coordinator<W> {
+    pub fn new(workers: Vec<W>) -> Result<Self, ShardingError> {
+        if workers.is_empty() {
+            return Err(ShardingError::NoWorkers);
+        }
+        Ok(Self {
+            num_workers: workers.len(),
+            workers: Arc::new(Mutex::new(workers)),
+            next_commitment: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn num_workers(&self) -> usize {
+        self.num_workers
+    }
+
+    /// Evaluates the trace polynomials on the LDE domain, giving each worker a
+    /// contiguous range of the columns.
+    pub fn low_degree_extension<F>(
+        &self,
+        trace_polys: &[Polynomial<FieldElement<F>>],
+        domain: &Domain<F>,
+    ) -> Result<Vec<Vec<FieldElement<F>>>, ShardingError>
+    where
+        F: IsFFTField,
+        W: WorkerTransport<F>,
+    {
+        let chunk_size = num_integer::div_ceil(trace_polys.len(), self.num_workers()).max(1);
+        let tasks = trace_polys.chunks(chunk_size).map(|polys| Task::Extend {
+            polys: polys.to_vec(),
+            blowup_factor: domain.blowup_factor,
+            domain_size: domain.interpolation_domain_size,
+            offset: domain.coset_offset.clone(),
+        });
+
+        let mut lde_trace = Vec::with_capacity(trace_polys.len());
+        for (worker, response) in self.run(tasks)? {
+            match response {
+                Response::Evaluations(evaluations) => lde_trace.extend(evaluations),
+                _ => return Err(ShardingError::UnexpectedResponse(worker)),
+            }
+        }
+        Ok(lde_trace)
+    }
+
+    /// Commits to the rows of the LDE trace. It has the root and the cap of height
+    /// `cap_height` of the `BatchedMerkleTree` of `rows` built with `hash`, and
+    /// opens to its paths.
+    pub fn commit_rows<F>(
+        &self,
+        rows: Vec<Vec<FieldElement<F>>>,
+        hash: CommitmentHash,
+        cap_height: usize,
+    ) -> Result<ShardedCommitment<F, W>, ShardingError>
+    where
+        F: IsFFTField,
+        FieldElement<F>: ByteConversion,
+        W: WorkerTransport<F>,
+    {
+        let num_leaves = rows.len();
+        let shards = self.shard(rows);
+        // The caps higher than the subtrees are levels of the tree above them, and
+        // the lower ones are made of the caps of the subtrees
+        let top_height = shards.len().trailing_zeros() as usize;
+        let shard_cap_height = cap_height.saturating_sub(top_height);
+        let commitment = self.next_commitment.fetch_add(1, Ordering::Relaxed);
+        let tasks = shards.into_iter().map(|rows| Task::CommitRows {
+            commitment,
+            rows,
+            hash,
+            cap_height: shard_cap_height,
+        });
+
+        let mut roots = Vec::new();
+        let mut caps = Vec::new();
+        for (worker, response) in self.run(tasks)? {
+            match response {
+                Response::Committed { root, cap } => {
+                    roots.push(root);
+                    caps.push(cap);
+                }
+                _ => return Err(ShardingError::UnexpectedResponse(worker)),
+            }
+        }
+
+        let top_levels = merge_subtree_roots::<BatchedLeaves<F>>(hash, roots);
+        let cap = match top_height.checked_sub(cap_height) {
+            Some(level) => top_levels[level].clone(),
+            None => caps.concat(),
+        };
+        Ok(ShardedCommitment {
+            coordinator: self.clone(),
+            commitment,
+            num_leaves,
+            top_levels,
+            cap_height,
+            cap,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Splits the leaves in a power of two number of ranges of the same length, so
+    /// each one is a subtree of the Merkle tree of all of them. Trees with a number
+    /// of leaves other than a power of two are padded, so they aren't split.
+    fn shard<T>(&self, mut leaves: Vec<T>) -> Vec<Vec<T>> {
+        let num_leaves = leaves.len();
+        if !num_leaves.is_power_of_two() {
+            return vec![leaves];
+        }
+
+        let num_workers = 1 << (usize::BITS - 1 - self.num_workers().leading_zeros());
+        let num_shards = num_workers.min(num_leaves);
+        let shard_size = num_leaves / num_shards;
+        let mut shards = Vec::with_capacity(num_shards);
+        while !leaves.is_empty() {
+            let rest = leaves.split_off(shard_size);
+            shards.push(leaves);
+            leaves = rest;
+        }
+        shards
+    }
+
+    /// Sends each task to a worker, in order, and collects their responses. There
+    /// must be at most one task per worker.
+    fn run<F>(
+        &self,
+        tasks: impl Iterator<Item = Task<F>>,
+    ) -> Result<Vec<(usize, Response<F>)>, ShardingError>
+    where
+        F: IsFFTField,
+        W: WorkerTransport<F>,
+    {
+        let workers = self.workers.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut num_tasks = 0;
+        let mut send_error = None;
+        for (worker, task) in workers.iter().zip(tasks) {
+            if let Err(error) = worker.send(task) {
+                send_error = Some(ShardingError::Transport(num_tasks, error));
+                break;
+            }
+            num_tasks += 1;
+        }
+
+        // Every task sent is answered, even after a failure, so the responses to
+        // the next tasks match them
+        let responses: Vec<_> = workers[..num_tasks]
+            .iter()
+            .map(|worker| worker.receive())
+            .collect();
+        if let Some(error) = send_error {
+            return Err(error);
+        }
+
+        responses
+            .into_iter()
+            .enumerate()
+            .map(|(index, response)| {
+                match response.map_err(|error| ShardingError::Transport(index, error))? {
+                    Response::Failed(error) => Err(ShardingError::Worker(index, error)),
+                    response => Ok((index, response)),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Commitment to leaves split between the workers of a `Coordinator`, the range
+/// of the `i`-th subtree kept by its `i`-th worker. The workers drop their subtrees
+/// when the commitment is dropped.
+pub struct ShardedCommitment<F: IsFFTField, W: WorkerTransport<F>> {
+    coordinator: Coordinator<W>,
+    commitment: usize,
+    num_leaves: usize,
+    // Levels of the tree from the roots of the subtrees up to its root
+    top_levels: Vec<Vec<Commitment>>,
+    cap_height: usize,
+    cap: MerkleCap,
+    phantom: PhantomData<F>,
+}
+
+impl<F: IsFFTField, W: WorkerTransport<F>> ShardedCommitment<F, W> {
+    pub fn root(&self) -> Commitment {
+        self.top_levels[self.top_levels.len() - 1][0]
+    }
+
+    /// Cap of the tree, of the height it was committed with.
+    pub fn cap(&self) -> &MerkleCap {
+        &self.cap
+    }
+
+    /// Paths of the leaves at each of `positions` up to the cap, in their order.
+    /// They are the ones of `CommitmentTree::get_proofs_by_pos_to_cap`.
+    pub fn open(&self, positions: &[usize]) -> Result<Vec<Proof<Commitment>>, ShardingError> {
+        let num_shards = self.top_levels[0].len();
+        let shard_size = num_integer::div_ceil(self.num_leaves, num_shards);
+        let mut shard_positions = vec![Vec::new(); num_shards];
+        for &position in positions {
+            if position >= self.num_leaves {
+                return Err(ShardingError::PositionOutOfRange(position));
+            }
+            shard_positions[position / shard_size].push(position % shard_size);
+        }
+
+        let tasks = shard_positions.into_iter().map(|positions| Task::Open {
+            commitment: self.commitment,
+            positions,
+        });
+        let mut shard_paths = Vec::with_capacity(num_shards);
+        for (worker, response) in self.coordinator.run(tasks)? {
+            match response {
+                Response::Paths(paths) => shard_paths.push(paths.into_iter()),
+                _ => return Err(ShardingError::UnexpectedResponse(worker)),
+            }
+        }
+
+        positions
+            .iter()
+            .map(|position| {
+                let shard = position / shard_size;
+                let mut proof = shard_paths[shard]
+                    .next()
+                    .ok_or(ShardingError::UnexpectedResponse(shard))?;
+                // The path from the root of the subtree to the root of the tree
+                proof.merkle_path.extend(
+                    self.top_levels[..self.top_levels.len() - 1]
+                        .iter()
+                        .enumerate()
+                        .map(|(level, nodes)| nodes[(shard >> level) ^ 1]),
+                );
+                let path_len = proof.merkle_path.len().saturating_sub(self.cap_height);
+                proof.merkle_path.truncate(path_len);
+                Ok(proof)
+            })
+            .collect()
+    }
+}
+
+impl<F: IsFFTField, W: WorkerTransport<F>> Drop for ShardedCommitment<F, W> {
+    fn drop(&mut self) {
+        let tasks = self.top_levels[0].iter().map(|_| Task::Release {
+            commitment: self.commitment,
+        });
+        // A worker that can't be reached has nothing left to release
+        let _ = self.coordinator.run(tasks);
+    }
+}
+
+impl<F, W> TraceCommitment for ShardedCommitment<F, W>
+where
+    F: IsFFTField,
+    W: WorkerTransport<F>,
+{
+    fn root(&self) -> Commitment {
+        ShardedCommitment::root(self)
+    }
+
+    fn commitment(&self, _options: &ProofOptions) -> MerkleCap {
+        self.cap.clone()
+    }
+
+    fn open(
+        &self,
+        _options: &ProofOptions,
+        positions: &[usize],
+    ) -> Result<Vec<Proof<Commitment>>, ProvingError> {
+        Ok(ShardedCommitment::open(self, positions)?)
+    }
+}
+
+impl<F, W> TraceCommitter<F> for Coordinator<W>
+where
+    F: IsFFTField + 'static,
+    FieldElement<F>: ByteConversion,
+    W: WorkerTransport<F> + 'static,
+{
+    fn low_degree_extension(
+        &self,
+        trace_polys: &[Polynomial<FieldElement<F>>],
+        domain: &Domain<F>,
+    ) -> Result<Vec<Vec<FieldElement<F>>>, ProvingError> {
+        Ok(Coordinator::low_degree_extension(
+            self,
+            trace_polys,
+            domain,
+        )?)
+    }
+
+    fn commit(
+        &self,
+        rows: Vec<Vec<FieldElement<F>>>,
+        options: &ProofOptions,
+    ) -> Result<Box<dyn TraceCommitment>, ProvingError> {
+        let commitment =
+            self.commit_rows(rows, options.commitment_hash, options.merkle_cap_height)?;
+        Ok(Box::new(commitment))
+    }
+}
+
+/// Same as `prove`, but extending and committing the trace with the workers of
+/// `coordinator`. The other rounds run in the process, and the proof is checked by
+/// `verify`.
+pub fn prove_with_sharded_trace<F, A, W>(
+    coordinator: &Coordinator<W>,
+    main_trace: &TraceTable<F>,
+    pub_inputs: &A::PublicInputs,
+    proof_options: &ProofOptions,
+) -> Result<StarkProof<F>, ProvingError>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F> + Send + Sync,
+    A::RAPChallenges: Send + Sync,
+    FieldElement<F>: ByteConversion + Send + Sync,
+    W: WorkerTransport<F> + 'static,
+{
+    prove_with_trace_committer::<F, A>(
+        main_trace,
+        pub_inputs,
+        proof_options,
+        Arc::new(coordinator.clone()),
+    )
+}
+
+/// Levels of the tree whose leaves are the roots of consecutive subtrees of the
+/// same height, from those roots up to the root of the whole tree. Their number
+/// must be a power of two.
+fn merge_subtree_roots<L: CommitmentLeaves>(
+    hash: CommitmentHash,
+    roots: Vec<Commitment>,
+) -> Vec<Vec<Commitment>> {
+    debug_assert!(roots.len().is_power_of_two());
+    let mut levels = vec![roots];
+    while levels[levels.len() - 1].len() > 1 {
+        let parents = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| CommitmentTree::<L>::hash_new_parent(hash, &pair[0], &pair[1]))
+            .collect();
+        levels.push(parents);
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+    use crate::starks::{
+        example::simple_fibonacci::{self, FibonacciAIR, FibonacciPublicInputs},
+        proof::options::ProofOptions,
+        traits::AIR,
+        verifier::verify,
+    };
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    fn workers(num_workers: usize) -> Coordinator<ChannelWorker<F>> {
+        Coordinator::new((0..num_workers).map(|_| ChannelWorker::spawn()).collect()).unwrap()
+    }
+
+    fn assert_same_commitment<L: CommitmentLeaves>(
+        sharded: &ShardedCommitment<F, ChannelWorker<F>>,
+        tree: &CommitmentTree<L>,
+        cap_height: usize,
+        num_leaves: usize,
+    ) {
+        assert_eq!(sharded.root(), tree.root());
+        assert_eq!(sharded.cap(), &tree.cap(cap_height));
+
+        let positions: Vec<_> = (0..num_leaves).chain([num_leaves - 1, 0]).collect();
+        let paths = sharded.open(&positions).unwrap();
+        let expected = tree
+            .get_proofs_by_pos_to_cap(&positions, cap_height)
+            .unwrap();
+        for (path, expected) in paths.iter().zip(&expected) {
+            assert_eq!(path.merkle_path, expected.merkle_path);
+        }
+        assert_eq!(paths.len(), expected.len());
+
+        assert!(matches!(
+            sharded.open(&[num_leaves]),
+            Err(ShardingError::PositionOutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn sharded_commitments_match_the_ones_of_a_single_prover() {
+        let rows: Vec<Vec<FE>> = (0..64_u64)
+            .map(|i| vec![FE::from(i), FE::from(i * i)])
+            .collect();
+
+        let hash = CommitmentHash::Keccak256;
+        let rows_tree =
+            BatchedMerkleTree::<F>::build(hash, &MerkleTreeStorage::InMemory, &rows).unwrap();
+
+        for num_workers in [1, 3, 4, 100] {
+            let coordinator = workers(num_workers);
+            for cap_height in [0, 1, 3, 10] {
+                let sharded = coordinator
+                    .commit_rows(rows.clone(), hash, cap_height)
+                    .unwrap();
+                assert_same_commitment(&sharded, &rows_tree, cap_height, rows.len());
+            }
+        }
+
+        let padded_rows = rows[..10].to_vec();
+        let padded_tree =
+            BatchedMerkleTree::<F>::build(hash, &MerkleTreeStorage::InMemory, &padded_rows)
+                .unwrap();
+        let sharded = workers(4).commit_rows(padded_rows, hash, 1).unwrap();
+        assert_same_commitment(&sharded, &padded_tree, 1, 10);
+    }
+
+    #[test]
+    fn released_commitments_cannot_be_opened() {
+        let coordinator = workers(2);
+        let rows: Vec<Vec<FE>> = (0..8_u64).map(|i| vec![FE::from(i)]).collect();
+        let sharded = coordinator
+            .commit_rows(rows, CommitmentHash::Keccak256, 0)
+            .unwrap();
+        let commitment = sharded.commitment;
+        drop(sharded);
+
+        let tasks = (0..2).map(|_| Task::Open {
+            commitment,
+            positions: vec![0],
+        });
+        assert!(matches!(
+            coordinator.run(tasks),
+            Err(ShardingError::Worker(0, _))
+        ));
+    }
+
+    #[test]
+    fn proofs_with_sharded_traces_are_verified() {
+        let trace = simple_fibonacci::fibonacci_trace([FE::one(), FE::one()], 16);
+        let pub_inputs = FibonacciPublicInputs {
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+        let proof_options = ProofOptions {
+            merkle_cap_height: 2,
+            ..ProofOptions::default_test_options()
+        };
+
+        let proof = prove_with_sharded_trace::<F, FibonacciAIR<F>, _>(
+            &workers(3),
+            &trace,
+            &pub_inputs,
+            &proof_options,
+        )
+        .unwrap();
+        assert!(verify::<F, FibonacciAIR<F>>(
+            &proof,
+            &pub_inputs,
+            &proof_options
+        ));
+    }
+
+    #[test]
+    fn sharded_low_degree_extension_matches_the_one_of_a_single_prover() {
+        let trace = simple_fibonacci::fibonacci_trace([FE::one(), FE::one()], 16);
+        let pub_inputs = FibonacciPublicInputs {
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+        let air = FibonacciAIR::new(
+            trace.n_rows(),
+            &pub_inputs,
+            &ProofOptions::default_test_options(),
+        );
+        let domain = Domain::new(&air);
+        let trace_polys = trace.compute_trace_polys();
+
+        let expected: Vec<_> = trace_polys
+            .iter()
+            .map(|poly| {
+                evaluate_polynomial_on_lde_domain(
+                    poly,
+                    domain.blowup_factor,
+                    domain.interpolation_domain_size,
+                    &domain.coset_offset,
+                )
+                .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            workers(2)
+                .low_degree_extension(&trace_polys, &domain)
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn coordinators_need_workers() {
+        assert!(matches!(
+            Coordinator::<ChannelWorker<F>>::new(Vec::new()),
+            Err(ShardingError::NoWorkers)
+        ));
+    }
+}
@@ -1,23 +1,41 @@
-use lambdaworks_math::fft::errors::FFTError;
-use lambdaworks_math::fft::polynomial::FFTPoly;
+#[cfg(feature = "metal")]
+use lambdaworks_math::fft::{errors::FFTError, polynomial::FFTPoly};
+#[cfg(not(feature = "metal"))]
+use lambdaworks_math::{
+    fft::cpu::{
+        bit_reversing::in_place_bit_reverse_permute, fft::in_place_nr_2radix_fft,
+        roots_of_unity::get_twiddles,
+    },
+    field::traits::RootsConfig,
+};
 use lambdaworks_math::{
     field::{element::FieldElement, traits::IsFFTField},
     polynomial::Polynomial,
 };
+#[cfg(all(feature = "parallel", not(feature = "metal")))]
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 
-#[derive(Clone, Default, Debug, PartialEq, Eq)]
+/// A trace of an execution, stored row by row.
+///
+/// A step of the execution can span more than one row of the table. `rows_per_step`
+/// is the number of consecutive rows used by each step, and it's one by default.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TraceTable<F: IsFFTField> {
     /// `table` is row-major trace element description
     pub table: Vec<FieldElement<F>>,
     pub n_cols: usize,
+    rows_per_step: usize,
+}
+
+impl<F: IsFFTField> Default for TraceTable<F> {
+    fn default() -> Self {
+        Self::empty()
+    }
 }
 
 impl<F: IsFFTField> TraceTable<F> {
     pub fn empty() -> Self {
-        Self {
-            table: Vec::new(),
-            n_cols: 0,
-        }
+        Self::new(Vec::new(), 0)
     }
 
     pub fn is_empty(&self) -> bool {
@@ -25,7 +43,59 @@ impl<F: IsFFTField> TraceTable<F> {
     }
 
     pub fn new(table: Vec<FieldElement<F>>, n_cols: usize) -> Self {
-        Self { table, n_cols }
+        Self {
+            table,
+            n_cols,
+            rows_per_step: 1,
+        }
+    }
+
+    /// Sets the number of rows used by each step of the execution.
+    pub fn with_rows_per_step(mut self, rows_per_step: usize) -> Self {
+        debug_assert!(rows_per_step > 0);
+        self.rows_per_step = rows_per_step;
+        self
+    }
+
+    pub fn rows_per_step(&self) -> usize {
+        self.rows_per_step
+    }
+
+    /// Number of steps of the execution in the table. Incomplete steps at the end
+    /// of the table are not counted.
+    pub fn n_steps(&self) -> usize {
+        self.n_rows() / self.rows_per_step
+    }
+
+    /// Returns the rows of the given step, concatenated.
+    pub fn get_step(&self, step: usize) -> &[FieldElement<F>] {
+        let step_size = self.rows_per_step * self.n_cols;
+        &self.table[step * step_size..(step + 1) * step_size]
+    }
+
+    /// Iterates over the rows of the table, without copying them.
+    pub fn row_views(&self) -> impl Iterator<Item = &[FieldElement<F>]> {
+        // `chunks_exact` panics with a zero chunk size, and an empty table has no rows
+        self.table
+            .chunks_exact(self.n_cols.max(1))
+            .take(self.n_rows())
+    }
+
+    /// Builds a table with the columns of all the given tables, in order.
+    /// The tables must have the same number of rows.
+    pub fn concatenate_tables(tables: &[&Self]) -> Self {
+        let n_rows = tables.first().map_or(0, |table| table.n_rows());
+        debug_assert!(tables.iter().all(|table| table.n_rows() == n_rows));
+
+        let n_cols = tables.iter().map(|table| table.n_cols).sum();
+        let mut table = Vec::with_capacity(n_rows * n_cols);
+        for row_idx in 0..n_rows {
+            for sub_table in tables {
+                table.extend_from_slice(sub_table.get_row(row_idx));
+            }
+        }
+
+        Self::new(table, n_cols)
     }
 
     pub fn get_cols(&self, columns: &[usize]) -> Self {
@@ -36,10 +106,7 @@ impl<F: IsFFTField> TraceTable<F> {
             }
         }
 
-        Self {
-            table,
-            n_cols: columns.len(),
-        }
+        Self::new(table, columns.len()).with_rows_per_step(self.rows_per_step)
     }
 
     pub fn new_from_cols(cols: &[Vec<FieldElement<F>>]) -> Self {
@@ -55,7 +122,7 @@ impl<F: IsFFTField> TraceTable<F> {
                 table.push(col[row_idx].clone());
             }
         }
-        Self { table, n_cols }
+        Self::new(table, n_cols)
     }
 
     pub fn n_rows(&self) -> usize {
@@ -95,13 +162,19 @@ impl<F: IsFFTField> TraceTable<F> {
             .collect()
     }
 
-    /// Given a step and a column index, gives stored value in that position
-    pub fn get(&self, step: usize, col: usize) -> FieldElement<F> {
-        let idx = step * self.n_cols + col;
+    /// Given a row and a column index, gives stored value in that position
+    pub fn get(&self, row: usize, col: usize) -> FieldElement<F> {
+        let idx = row * self.n_cols + col;
         self.table[idx].clone()
     }
 
-    pub fn compute_trace_polys(&self) -> Vec<Polynomial<FieldElement<F>>> {
+    /// Interpolates the columns of the table, whose number of rows must be a power
+    /// of two. With the `metal` feature each column is interpolated in the GPU.
+    #[cfg(feature = "metal")]
+    pub fn compute_trace_polys(&self) -> Vec<Polynomial<FieldElement<F>>>
+    where
+        FieldElement<F>: Send + Sync,
+    {
         self.cols()
             .iter()
             .map(|col| Polynomial::interpolate_fft(col))
@@ -109,6 +182,55 @@ impl<F: IsFFTField> TraceTable<F> {
             .unwrap()
     }
 
+    /// Interpolates the columns of the table, whose number of rows must be a power
+    /// of two, with an inverse FFT batched over all of them. The twiddles are
+    /// computed once, the columns are copied out of the table in a single pass over
+    /// its rows, and they are transformed in parallel with the `parallel` feature.
+    #[cfg(not(feature = "metal"))]
+    pub fn compute_trace_polys(&self) -> Vec<Polynomial<FieldElement<F>>>
+    where
+        FieldElement<F>: Send + Sync,
+    {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let n_rows = self.n_rows();
+        assert!(
+            n_rows.is_power_of_two(),
+            "the trace length must be a power of two, found {n_rows}"
+        );
+
+        let twiddles = get_twiddles::<F>(
+            n_rows.trailing_zeros() as u64,
+            RootsConfig::BitReverseInversed,
+        )
+        .unwrap();
+        let n_rows_inv = FieldElement::<F>::from(n_rows as u64).inv();
+
+        let mut columns = vec![Vec::with_capacity(n_rows); self.n_cols];
+        for row in self.row_views() {
+            for (column, value) in columns.iter_mut().zip(row) {
+                column.push(value.clone());
+            }
+        }
+
+        #[cfg(not(feature = "parallel"))]
+        let columns_iter = columns.into_iter();
+        #[cfg(feature = "parallel")]
+        let columns_iter = columns.into_par_iter();
+
+        columns_iter
+            .map(|mut column| {
+                in_place_nr_2radix_fft(&mut column, &twiddles);
+                in_place_bit_reverse_permute(&mut column);
+                for coefficient in column.iter_mut() {
+                    *coefficient = &*coefficient * &n_rows_inv;
+                }
+                Polynomial::new(&column)
+            })
+            .collect()
+    }
+
     pub fn concatenate(&self, new_cols: Vec<FieldElement<F>>, n_cols: usize) -> Self {
         let mut new_table = Vec::new();
         let mut i = 0;
@@ -117,19 +239,36 @@ impl<F: IsFFTField> TraceTable<F> {
             new_table.append(&mut new_cols[i..(i + n_cols)].to_vec());
             i += n_cols;
         }
-        TraceTable {
-            table: new_table,
-            n_cols: self.n_cols + n_cols,
-        }
+        Self::new(new_table, self.n_cols + n_cols).with_rows_per_step(self.rows_per_step)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::TraceTable;
-    use lambdaworks_math::field::{element::FieldElement, fields::u64_prime_field::F17};
+    use lambdaworks_math::{
+        fft::polynomial::FFTPoly,
+        field::{element::FieldElement, fields::u64_prime_field::F17},
+        polynomial::Polynomial,
+    };
     type FE = FieldElement<F17>;
 
+    #[test]
+    fn test_trace_polys_match_the_interpolation_of_each_column() {
+        let cols: Vec<Vec<FE>> = (0..5_u64)
+            .map(|col| (0..16_u64).map(|row| FE::from(row * row + col)).collect())
+            .collect();
+        let trace_table = TraceTable::new_from_cols(&cols);
+
+        let expected: Vec<_> = cols
+            .iter()
+            .map(|col| Polynomial::interpolate_fft(col).unwrap())
+            .collect();
+
+        assert_eq!(trace_table.compute_trace_polys(), expected);
+        assert!(TraceTable::<F17>::empty().compute_trace_polys().is_empty());
+    }
+
     #[test]
     fn test_cols() {
         let col_1 = vec![FE::from(1), FE::from(2), FE::from(5), FE::from(13)];
@@ -154,7 +293,7 @@ mod test {
             FE::new(2),
             FE::new(3),
         ];
-        let trace_table = TraceTable { table, n_cols: 3 };
+        let trace_table = TraceTable::new(table, 3);
         let subtable = trace_table.get_cols(&[0, 1]);
         assert_eq!(
             subtable.table,
@@ -203,4 +342,51 @@ mod test {
         let table1 = TraceTable::new_from_cols(&table1_columns);
         assert_eq!(table1.concatenate(new_columns, 2), expected_table)
     }
+
+    #[test]
+    fn test_steps_span_rows_per_step_rows() {
+        let col_1 = vec![FE::new(1), FE::new(2), FE::new(3), FE::new(4)];
+        let col_2 = vec![FE::new(5), FE::new(6), FE::new(7), FE::new(8)];
+        let trace_table = TraceTable::new_from_cols(&[col_1, col_2]);
+
+        assert_eq!(trace_table.rows_per_step(), 1);
+        assert_eq!(trace_table.n_steps(), 4);
+        assert_eq!(trace_table.get_step(1), &[FE::new(2), FE::new(6)]);
+
+        let trace_table = trace_table.with_rows_per_step(2);
+        assert_eq!(trace_table.n_rows(), 4);
+        assert_eq!(trace_table.n_steps(), 2);
+        assert_eq!(
+            trace_table.get_step(1),
+            &[FE::new(3), FE::new(7), FE::new(4), FE::new(8)]
+        );
+    }
+
+    #[test]
+    fn test_row_views_match_rows() {
+        let trace_table = TraceTable::new_from_cols(&[
+            vec![FE::new(1), FE::new(2)],
+            vec![FE::new(3), FE::new(4)],
+        ]);
+        let rows: Vec<_> = trace_table.row_views().map(|row| row.to_vec()).collect();
+
+        assert_eq!(rows, trace_table.rows());
+        assert_eq!(TraceTable::<F17>::empty().row_views().count(), 0);
+    }
+
+    #[test]
+    fn test_concatenate_tables_works() {
+        let table_1 = TraceTable::new(vec![FE::new(1), FE::new(2)], 1);
+        let table_2 = TraceTable::new(vec![FE::new(3), FE::new(4), FE::new(5), FE::new(6)], 2);
+        let expected_table = TraceTable::new_from_cols(&[
+            vec![FE::new(1), FE::new(2)],
+            vec![FE::new(3), FE::new(5)],
+            vec![FE::new(4), FE::new(6)],
+        ]);
+
+        assert_eq!(
+            TraceTable::concatenate_tables(&[&table_1, &table_2]),
+            expected_table
+        );
+    }
 }
@@ -1,3 +1,5 @@
+use std::collections::BTreeSet;
+
 use itertools::Itertools;
 use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
 use lambdaworks_math::{
@@ -5,36 +7,164 @@ use lambdaworks_math::{
     field::{element::FieldElement, traits::IsFFTField},
     polynomial::Polynomial,
 };
+use num_integer::div_ceil;
 
 use super::{
-    constraints::boundary::BoundaryConstraints, context::AirContext, frame::Frame,
-    proof::options::ProofOptions, trace::TraceTable,
+    constraints::{boundary::BoundaryConstraints, symbolic::Expr},
+    context::AirContext,
+    frame::Frame,
+    proof::options::ProofOptions,
+    trace::TraceTable,
+    virtual_columns::{CommittedColumns, VirtualColumn},
 };
 
+/// Point, other than z², where the prover additionally opens the composition
+/// polynomial parts H₁ and H₂, as the DEEP variants of some other provers do. See
+/// `AIR::composition_poly_extra_opening`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExtraOpeningPoint {
+    /// −z
+    NegatedZ,
+    /// z·g, with g the primitive root of the trace domain
+    ShiftedZ,
+}
+
+impl ExtraOpeningPoint {
+    /// The point for the out-of-domain element `z`.
+    pub fn point<F: IsFFTField>(
+        &self,
+        z: &FieldElement<F>,
+        trace_primitive_root: &FieldElement<F>,
+    ) -> FieldElement<F> {
+        match self {
+            Self::NegatedZ => -z,
+            Self::ShiftedZ => z * trace_primitive_root,
+        }
+    }
+}
+
 /// AIR is a representation of the Constraints
-pub trait AIR: Clone {
+///
+/// The prover and the verifier only borrow the AIR, so it doesn't need to be
+/// `Clone`, and it may hold large data, like lookup tables.
+pub trait AIR {
     type Field: IsFFTField;
     type RAPChallenges;
     type PublicInputs;
 
+    /// Version of the constraints of the AIR, to be increased when they change, like
+    /// after a soundness fix. It's reported in the attestations of the proofs, see
+    /// `Attestation`.
+    const VERSION: u32 = 0;
+
     fn new(
         trace_length: usize,
         pub_inputs: &Self::PublicInputs,
         proof_options: &ProofOptions,
     ) -> Self;
 
+    /// Checks that the AIR supports the configuration declared by the public inputs,
+    /// like the builtins of a Cairo program. The prover and the verifier call it
+    /// before `new`, so proofs of configurations with other columns or constraints
+    /// are rejected with the reason instead of a mismatch in their number.
+    fn check_configuration(_pub_inputs: &Self::PublicInputs) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Builds the auxiliary trace segment of the interaction round `round`,
+    /// using the challenges sampled up to that round.
     fn build_auxiliary_trace(
         &self,
+        round: usize,
         main_trace: &TraceTable<Self::Field>,
         rap_challenges: &Self::RAPChallenges,
     ) -> TraceTable<Self::Field>;
 
+    /// Samples the challenges of the first interaction round.
     fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges;
 
+    /// Samples the challenges of the interaction round `round` and adds them to
+    /// `rap_challenges`. It's called after the auxiliary segments of the previous
+    /// rounds are committed, so only AIRs with more than one round need it.
+    fn extend_rap_challenges<T: Transcript>(
+        &self,
+        _round: usize,
+        _rap_challenges: &mut Self::RAPChallenges,
+        _transcript: &mut T,
+    ) {
+    }
+
+    /// Number of interaction rounds. Each round samples challenges and commits
+    /// one auxiliary trace segment.
+    fn number_of_rap_rounds(&self) -> usize {
+        1
+    }
+
+    /// Total number of auxiliary columns, over all the interaction rounds.
     fn number_auxiliary_rap_columns(&self) -> usize;
 
+    /// Number of auxiliary columns built in the interaction round `round`.
+    fn number_auxiliary_rap_columns_in_round(&self, round: usize) -> usize {
+        if round == 0 {
+            self.number_auxiliary_rap_columns()
+        } else {
+            0
+        }
+    }
+
     fn composition_poly_degree_bound(&self) -> usize;
 
+    /// Exponent of the degree adjustment of the constraint quotients of degree up to
+    /// `trace_length_multiple` times the trace length, which lifts them to the degree
+    /// bound of the composition polynomial. It's one for the boundary constraints, and
+    /// `d - 1` for the transition constraints of degree `d`.
+    ///
+    /// The degree of the quotients is computed in `u64`, so it doesn't overflow on
+    /// 32-bit targets, but the degree bound is the `usize` the AIR computes.
+    /// `None` if the quotients exceed the degree bound.
+    fn degree_adjustment(&self, trace_length_multiple: usize) -> Option<u64> {
+        let quotient_degree =
+            (self.trace_length() as u64).checked_mul(trace_length_multiple as u64)?;
+        (self.composition_poly_degree_bound() as u64).checked_sub(quotient_degree)
+    }
+
+    /// Exponent of the degree adjustment of the quotient of the transition constraint
+    /// `constraint`, see `degree_adjustment`. Its exemption zones multiply the quotient
+    /// by a polynomial of degree the number of steps they exempt, see
+    /// `transition_exemption_zones`, so the adjustment is lowered by as much. `None`
+    /// if the quotient exceeds the degree bound.
+    fn transition_degree_adjustment(&self, constraint: usize) -> Option<u64> {
+        let degree = *self.context().transition_degrees().get(constraint)?;
+        let zones_degree = self
+            .context()
+            .zone_exempted_steps(constraint, self.trace_length())
+            .len();
+        self.degree_adjustment(degree.saturating_sub(1))?
+            .checked_sub(zones_degree as u64)
+    }
+
+    /// Whether the degree bound of the composition polynomial covers the quotients
+    /// of all the constraints, with the ones of their exemption zones. See
+    /// `transition_degree_adjustment`.
+    fn degree_bound_covers_constraints(&self) -> bool {
+        self.degree_adjustment(1).is_some()
+            && (0..self.context().num_transition_constraints())
+                .all(|constraint| self.transition_degree_adjustment(constraint).is_some())
+    }
+
+    /// Smallest blowup factor the AIR can be proven with: the LDE domain must hold
+    /// the evaluations of every transition constraint, see
+    /// `AirContext::min_blowup_factor`, and the composition polynomial.
+    fn min_blowup_factor(&self) -> usize {
+        let composition_poly_blowup_factor = div_ceil(
+            self.composition_poly_degree_bound(),
+            self.trace_length().max(1),
+        );
+        self.context()
+            .min_blowup_factor()
+            .max(composition_poly_blowup_factor.next_power_of_two())
+    }
+
     fn compute_transition(
         &self,
         frame: &Frame<Self::Field>,
@@ -60,7 +190,7 @@ pub trait AIR: Clone {
         let x = Polynomial::new_monomial(FieldElement::one(), 1);
 
         self.context()
-            .transition_exemptions
+            .effective_transition_exemptions()
             .iter()
             .unique_by(|elem| *elem)
             .filter(|v| *v > &0_usize)
@@ -77,6 +207,34 @@ pub trait AIR: Clone {
             })
             .collect()
     }
+
+    /// For each transition constraint, the product of (x - gˢ) over the steps s of
+    /// its exemption zones, see `AirContext::zone_exempted_steps`, or `None` if it
+    /// has none. It multiplies the polynomial of `transition_exemptions`.
+    fn transition_exemption_zones(&self) -> Vec<Option<Polynomial<FieldElement<Self::Field>>>> {
+        let trace_length = self.trace_length();
+        let roots_of_unity = get_powers_of_primitive_root_coset(
+            trace_length.trailing_zeros() as u64,
+            trace_length,
+            &FieldElement::<Self::Field>::one(),
+        )
+        .unwrap();
+
+        let x = Polynomial::new_monomial(FieldElement::one(), 1);
+
+        (0..self.context().num_transition_constraints())
+            .map(|constraint| {
+                let steps = self.context().zone_exempted_steps(constraint, trace_length);
+                (!steps.is_empty()).then(|| {
+                    steps.iter().fold(
+                        Polynomial::new_monomial(FieldElement::one(), 0),
+                        |acc, step| acc * (&x - &roots_of_unity[*step]),
+                    )
+                })
+            })
+            .collect()
+    }
+
     fn context(&self) -> &AirContext;
 
     fn trace_length(&self) -> usize;
@@ -95,6 +253,85 @@ pub trait AIR: Clone {
 
     fn pub_inputs(&self) -> &Self::PublicInputs;
 
+    /// Static parameters of the AIR, such as the program or the builtins it was
+    /// configured for, that must be bound to the proof. They are absorbed in the
+    /// transcript before any commitment, so the verifier draws different challenges
+    /// for an AIR configured with other parameters. Nothing is absorbed when they
+    /// are empty.
+    fn public_parameters(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Human readable name of a transition constraint, used when reporting
+    /// failing constraints. AIRs without named constraints use the index only.
+    fn transition_constraint_name(&self, _index: usize) -> Option<&'static str> {
+        None
+    }
+
+    /// Symbolic description of the transition constraints, in the same order as
+    /// the evaluations of `compute_transition`. Used to export the constraint
+    /// system for review. See `ConstraintSnapshot`.
+    fn symbolic_transition(&self) -> Option<Vec<Expr<Self::Field>>> {
+        None
+    }
+
+    /// Cells of the out-of-domain frame, as `(row, column)` pairs, referenced by the
+    /// constraints of the AIR, both the transition and the boundary ones. The DEEP
+    /// composition polynomial leaves the other cells out, saving their challenges and
+    /// the work of the verifier on them. `None`, the default, keeps every cell.
+    ///
+    /// The DEEP composition polynomial is what ties the out-of-domain frame to the
+    /// committed trace, so leaving out a referenced cell makes the proofs unsound.
+    /// The columns left out entirely aren't checked to be of low degree.
+    fn referenced_frame_cells(&self) -> Option<BTreeSet<(usize, usize)>> {
+        None
+    }
+
+    /// Trace terms of the DEEP composition polynomial, as `(row, column)` cells of the
+    /// out-of-domain frame of the proofs, whose columns are the committed ones, in the
+    /// order their coefficients are sampled: by column, and by row within each column.
+    /// See `referenced_frame_cells` and `committed_columns`.
+    fn deep_trace_terms(&self) -> Vec<(usize, usize)> {
+        let num_rows = self.context().transition_offsets.len();
+        let committed_columns = self.committed_columns();
+        let referenced_cells = self
+            .referenced_frame_cells()
+            .map(|cells| committed_columns.committed_cells(&cells));
+
+        (0..committed_columns.len())
+            .flat_map(|col| (0..num_rows).map(move |row| (row, col)))
+            .filter(|cell| {
+                referenced_cells
+                    .as_ref()
+                    .map_or(true, |cells| cells.contains(cell))
+            })
+            .collect()
+    }
+
+    /// Columns of the main trace that are a linear combination of other columns of
+    /// the same row. They aren't committed, see the `virtual_columns` module. None,
+    /// the default, commits every column.
+    fn virtual_columns(&self) -> Vec<VirtualColumn<Self::Field>> {
+        Vec::new()
+    }
+
+    /// The columns of the trace the prover commits to: all but the virtual ones.
+    fn committed_columns(&self) -> CommittedColumns<Self::Field> {
+        CommittedColumns::new(self.context().trace_columns, self.virtual_columns())
+    }
+
+    /// Point where H₁ and H₂ are opened on top of z², adding their evaluations
+    /// there to the proof and a term for each one to the DEEP composition
+    /// polynomial. `None`, the default, opens them at z² only.
+    fn composition_poly_extra_opening(&self) -> Option<ExtraOpeningPoint> {
+        None
+    }
+
+    /// Names of the RAP challenges referenced by the symbolic constraints.
+    fn rap_challenge_names(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
     fn transition_exemptions_verifier(
         &self,
         root: &FieldElement<Self::Field>,
@@ -103,11 +340,11 @@ pub trait AIR: Clone {
 
         let max = self
             .context()
-            .transition_exemptions
-            .iter()
+            .effective_transition_exemptions()
+            .into_iter()
             .max()
             .expect("has maximum");
-        (1..=*max)
+        (1..=max)
             .map(|index| {
                 (1..=index).fold(
                     Polynomial::new_monomial(FieldElement::one(), 0),
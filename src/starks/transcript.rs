@@ -7,6 +7,22 @@ use lambdaworks_math::{
     traits::ByteConversion,
 };
 
+use super::{config::Commitment, traits::AIR};
+
+/// A transcript borrowed from a larger protocol, so the proof continues it and the
+/// protocol can keep using it afterwards. See `prove_with_transcript`.
+pub struct TranscriptRef<'a, T: Transcript>(pub &'a mut T);
+
+impl<'a, T: Transcript> Transcript for TranscriptRef<'a, T> {
+    fn append(&mut self, new_data: &[u8]) {
+        self.0.append(new_data);
+    }
+
+    fn challenge(&mut self) -> [u8; 32] {
+        self.0.challenge()
+    }
+}
+
 /// Uses randomness from the transcript to create a FieldElement
 /// One bit less than the max used by the FieldElement is used as randomness. For StarkFields, this would be 251 bits randomness.
 /// Randomness is interpreted as limbs in BigEndian, and each Limb is ordered in BigEndian
@@ -50,6 +66,58 @@ pub fn transcript_to_usize<T: Transcript>(transcript: &mut T) -> usize {
     usize::from_be_bytes(value)
 }
 
+/// Number of bytes of the words a challenge is split in by `sample_index`.
+const INDEX_WORD_SIZE: usize = 8;
+
+/// Samples an index uniformly from `0..bound`.
+///
+/// A challenge of the transcript is split in four big-endian 64-bit words. Reducing
+/// a word modulo `bound` would favour the smallest `2^64 mod bound` indexes, with a
+/// bias of up to `bound / 2^64`, when `bound` is not a power of two. Instead, the
+/// words in the last, incomplete interval of `bound` values are rejected, and the
+/// next word is tried, drawing another challenge once the four are rejected. As a
+/// word is rejected with probability less than `bound / 2^64`, for any domain that
+/// fits in memory this is practically never needed.
+///
+/// For powers of two nothing is rejected, and the index is the first word of a
+/// single challenge modulo `bound`, as `transcript_to_usize` gives.
+pub fn sample_index<T: Transcript>(transcript: &mut T, bound: usize) -> usize {
+    assert!(bound > 0, "indexes are sampled from a non-empty range");
+    let bound = bound as u64;
+    // 2^64 mod bound, the number of values of the incomplete interval
+    let rejected_values = (u64::MAX - bound + 1) % bound;
+
+    loop {
+        let challenge = transcript.challenge();
+        for word in challenge.chunks_exact(INDEX_WORD_SIZE) {
+            let word = u64::from_be_bytes(word.try_into().unwrap());
+            if word <= u64::MAX - rejected_values {
+                return (word % bound) as usize;
+            }
+        }
+    }
+}
+
+/// Samples the indexes of the FRI queries in a domain of size `domain_size`, each
+/// one with `sample_index`. With
+/// `deduplicate`, repeated indexes are dropped keeping the first occurrence, so
+/// prover and verifier get the same list while sampling the same challenges.
+pub fn sample_query_indexes<T: Transcript>(
+    transcript: &mut T,
+    number_of_queries: usize,
+    domain_size: usize,
+    deduplicate: bool,
+) -> Vec<usize> {
+    let mut indexes = Vec::with_capacity(number_of_queries);
+    for _ in 0..number_of_queries {
+        let index = sample_index(transcript, domain_size);
+        if !deduplicate || !indexes.contains(&index) {
+            indexes.push(index);
+        }
+    }
+    indexes
+}
+
 pub fn sample_z_ood<F: IsPrimeField, T: Transcript>(
     lde_roots_of_unity_coset: &[FieldElement<F>],
     trace_roots_of_unity: &[FieldElement<F>],
@@ -68,6 +136,102 @@ where
     }
 }
 
+/// Absorbs the public parameters of `air` at the start of the proof. See
+/// `AIR::public_parameters`.
+pub fn append_public_parameters<A: AIR, T: Transcript>(air: &A, transcript: &mut T) {
+    let public_parameters = air.public_parameters();
+    if !public_parameters.is_empty() {
+        transcript.append(&(public_parameters.len() as u64).to_be_bytes());
+        transcript.append(&public_parameters);
+    }
+}
+
+/// Stage of a transcript in the interaction rounds of round 1. See `RapTranscript`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RapStage {
+    /// Waiting for the commitments of the main trace.
+    MainTrace,
+    /// Waiting for the challenges of the interaction round `round`.
+    Challenges { round: usize },
+    /// Waiting for the commitments of the auxiliary segment of `round`.
+    AuxTrace { round: usize },
+}
+
+/// The transcript through round 1, which enforces the order of its commitments
+/// and challenges: the main trace is absorbed first, and the challenges of each
+/// interaction round are sampled after the commitments of the previous segments
+/// and before the ones of its own segment. Challenges sampled earlier don't
+/// depend on the trace, and commitments absorbed earlier can depend on the
+/// challenges they should be bound to, so both the prover and the verifier go
+/// through it and panic on any other order.
+pub struct RapTranscript<'a, T: Transcript> {
+    transcript: &'a mut T,
+    stage: RapStage,
+}
+
+impl<'a, T: Transcript> RapTranscript<'a, T> {
+    pub fn new(transcript: &'a mut T) -> Self {
+        Self {
+            transcript,
+            stage: RapStage::MainTrace,
+        }
+    }
+
+    pub fn stage(&self) -> RapStage {
+        self.stage
+    }
+
+    pub fn append_main_trace_roots(&mut self, roots: &[Commitment]) {
+        self.advance(RapStage::MainTrace, RapStage::Challenges { round: 0 });
+        for root in roots {
+            self.transcript.append(root);
+        }
+    }
+
+    /// Samples the challenges of the first interaction round with
+    /// `AIR::build_rap_challenges`.
+    pub fn build_rap_challenges<A: AIR>(&mut self, air: &A) -> A::RAPChallenges {
+        self.advance(
+            RapStage::Challenges { round: 0 },
+            RapStage::AuxTrace { round: 0 },
+        );
+        air.build_rap_challenges(self.transcript)
+    }
+
+    /// Samples the challenges of the interaction round `round`, after the first
+    /// one, with `AIR::extend_rap_challenges`.
+    pub fn extend_rap_challenges<A: AIR>(
+        &mut self,
+        air: &A,
+        round: usize,
+        rap_challenges: &mut A::RAPChallenges,
+    ) {
+        assert!(round > 0, "the challenges of the first round are built");
+        self.advance(RapStage::Challenges { round }, RapStage::AuxTrace { round });
+        air.extend_rap_challenges(round, rap_challenges, self.transcript);
+    }
+
+    /// Absorbs the commitments of the auxiliary segment of `round`, which may
+    /// have none.
+    pub fn append_aux_trace_roots(&mut self, round: usize, roots: &[Commitment]) {
+        self.advance(
+            RapStage::AuxTrace { round },
+            RapStage::Challenges { round: round + 1 },
+        );
+        for root in roots {
+            self.transcript.append(root);
+        }
+    }
+
+    fn advance(&mut self, expected: RapStage, next: RapStage) {
+        assert_eq!(
+            self.stage, expected,
+            "round 1 of the transcript out of order"
+        );
+        self.stage = next;
+    }
+}
+
 pub fn batch_sample_challenges<F: IsFFTField, T: Transcript>(
     size: usize,
     transcript: &mut T,
@@ -91,7 +255,81 @@ mod tests {
         unsigned_integer::element::U256,
     };
 
-    use crate::starks::transcript::randomness_to_field;
+    use lambdaworks_crypto::fiat_shamir::default_transcript::DefaultTranscript;
+
+    use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+
+    use crate::starks::{
+        example::{
+            fibonacci_multi_rap::FibonacciMultiRAP, fibonacci_rap::FibonacciRAPPublicInputs,
+        },
+        proof::options::ProofOptions,
+        traits::AIR,
+        transcript::{
+            randomness_to_field, sample_index, sample_query_indexes, transcript_to_usize, RapStage,
+            RapTranscript,
+        },
+    };
+
+    /// Transcript returning the given challenges in order.
+    struct FixedChallenges(Vec<[u8; 32]>);
+
+    impl Transcript for FixedChallenges {
+        fn append(&mut self, _new_data: &[u8]) {}
+
+        fn challenge(&mut self) -> [u8; 32] {
+            self.0.remove(0)
+        }
+    }
+
+    fn challenge_of_words(words: [u64; 4]) -> [u8; 32] {
+        let mut challenge = [0; 32];
+        for (bytes, word) in challenge.chunks_exact_mut(8).zip(words) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        challenge
+    }
+
+    #[test]
+    fn indexes_in_power_of_two_ranges_are_reduced_words() {
+        let index = sample_index(&mut DefaultTranscript::new(), 1 << 10);
+        let word = transcript_to_usize(&mut DefaultTranscript::new());
+        assert_eq!(index, word % (1 << 10));
+
+        let mut transcript = FixedChallenges(vec![challenge_of_words([u64::MAX, 0, 0, 0])]);
+        assert_eq!(sample_index(&mut transcript, 8), 7);
+    }
+
+    #[test]
+    fn words_in_the_incomplete_interval_are_rejected() {
+        // 2^64 mod 3 = 1, so only u64::MAX is rejected
+        let mut transcript = FixedChallenges(vec![challenge_of_words([u64::MAX, 5, 0, 0])]);
+        assert_eq!(sample_index(&mut transcript, 3), 2);
+
+        let mut transcript = FixedChallenges(vec![
+            challenge_of_words([u64::MAX; 4]),
+            challenge_of_words([u64::MAX - 1, 0, 0, 0]),
+        ]);
+        assert_eq!(
+            sample_index(&mut transcript, 3),
+            ((u64::MAX - 1) % 3) as usize
+        );
+    }
+
+    #[test]
+    fn deduplicated_query_indexes_keep_the_sampling_order() {
+        let indexes = sample_query_indexes(&mut DefaultTranscript::new(), 32, 8, false);
+        let deduplicated = sample_query_indexes(&mut DefaultTranscript::new(), 32, 8, true);
+
+        let mut expected = vec![];
+        for index in indexes {
+            if !expected.contains(&index) {
+                expected.push(index);
+            }
+        }
+        assert_eq!(deduplicated, expected);
+        assert!(deduplicated.len() <= 8);
+    }
 
     #[test]
     fn test_stark_prime_field_random_to_field_32() {
@@ -213,4 +451,56 @@ mod tests {
 
         assert_eq!(field_element, expected_fe)
     }
+
+    fn multi_rap_air() -> FibonacciMultiRAP<Stark252PrimeField> {
+        let pub_inputs = FibonacciRAPPublicInputs {
+            steps: 16,
+            a0: FieldElement::one(),
+            a1: FieldElement::one(),
+        };
+        FibonacciMultiRAP::new(32, &pub_inputs, &ProofOptions::default_test_options())
+    }
+
+    #[test]
+    fn rap_transcript_goes_through_every_interaction_round() {
+        let air = multi_rap_air();
+        let mut transcript = DefaultTranscript::new();
+        let mut transcript = RapTranscript::new(&mut transcript);
+
+        transcript.append_main_trace_roots(&[[1; 32]]);
+        let mut rap_challenges = transcript.build_rap_challenges(&air);
+        transcript.append_aux_trace_roots(0, &[[2; 32]]);
+        transcript.extend_rap_challenges(&air, 1, &mut rap_challenges);
+        transcript.append_aux_trace_roots(1, &[[3; 32]]);
+
+        assert_eq!(rap_challenges.len(), 2);
+        assert_eq!(transcript.stage(), RapStage::Challenges { round: 2 });
+    }
+
+    #[test]
+    #[should_panic(expected = "out of order")]
+    fn rap_challenges_cannot_be_sampled_before_the_main_trace_is_absorbed() {
+        let mut transcript = DefaultTranscript::new();
+        RapTranscript::new(&mut transcript).build_rap_challenges(&multi_rap_air());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of order")]
+    fn auxiliary_trace_cannot_be_absorbed_before_its_challenges() {
+        let mut transcript = DefaultTranscript::new();
+        let mut transcript = RapTranscript::new(&mut transcript);
+        transcript.append_main_trace_roots(&[[1; 32]]);
+        transcript.append_aux_trace_roots(0, &[[2; 32]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of order")]
+    fn challenges_of_a_round_cannot_be_sampled_before_the_previous_segment_is_absorbed() {
+        let air = multi_rap_air();
+        let mut transcript = DefaultTranscript::new();
+        let mut transcript = RapTranscript::new(&mut transcript);
+        transcript.append_main_trace_roots(&[[1; 32]]);
+        let mut rap_challenges = transcript.build_rap_challenges(&air);
+        transcript.extend_rap_challenges(&air, 1, &mut rap_challenges);
+    }
 }
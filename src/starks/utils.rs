@@ -1,7 +1,14 @@
+use std::time::Instant;
+
 use lambdaworks_crypto::merkle_tree::proof::Proof;
-use lambdaworks_math::errors::DeserializationError;
+use lambdaworks_math::{
+    errors::DeserializationError,
+    field::{element::FieldElement, traits::IsField},
+    traits::ByteConversion,
+};
+use tracing::{debug, Span};
 
-use super::config::Commitment;
+use super::config::{Commitment, MerkleCap, COMMITMENT_SIZE};
 
 pub fn serialize_proof(proof: &Proof<Commitment>) -> Vec<u8> {
     let mut bytes = vec![];
@@ -12,17 +19,86 @@ pub fn serialize_proof(proof: &Proof<Commitment>) -> Vec<u8> {
     bytes
 }
 
-pub fn deserialize_proof(bytes: &[u8]) -> Result<(Proof<Commitment>, &[u8]), DeserializationError> {
-    let mut bytes = bytes;
-    let mut merkle_path = vec![];
-    let merkle_path_len = usize::from_be_bytes(
+/// Reads the length prefixed to a list whose items take at least `item_size` bytes.
+/// Lengths over `max_len`, or over the number of items that fit in the rest of
+/// `bytes`, are rejected before anything is read or allocated for the list.
+pub fn deserialize_len(
+    bytes: &[u8],
+    item_size: usize,
+    max_len: usize,
+) -> Result<(usize, &[u8]), DeserializationError> {
+    let len = usize::from_be_bytes(
+        bytes
+            .get(..8)
+            .ok_or(DeserializationError::InvalidAmountOfBytes)?
+            .try_into()
+            .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
+    );
+    let bytes = &bytes[8..];
+    if len > max_len || len > bytes.len() / item_size.max(1) {
+        return Err(DeserializationError::InvalidAmountOfBytes);
+    }
+    Ok((len, bytes))
+}
+
+/// Reads the length of the serialized field elements that follow, which must be
+/// the one of the elements of `F`.
+pub fn deserialize_felt_len<F>(bytes: &[u8]) -> Result<(usize, &[u8]), DeserializationError>
+where
+    F: IsField,
+    FieldElement<F>: ByteConversion,
+{
+    let felt_len = usize::from_be_bytes(
         bytes
             .get(..8)
             .ok_or(DeserializationError::InvalidAmountOfBytes)?
             .try_into()
             .map_err(|_| DeserializationError::InvalidAmountOfBytes)?,
     );
-    bytes = &bytes[8..];
+    if felt_len != FieldElement::<F>::zero().to_bytes_be().len() {
+        return Err(DeserializationError::InvalidAmountOfBytes);
+    }
+    Ok((felt_len, &bytes[8..]))
+}
+
+/// Reads a field element of `felt_len` bytes. Only its canonical encoding, the one
+/// of `to_bytes_be`, is accepted: values not smaller than the modulus are rejected
+/// instead of reduced, so each element is read from a single byte string.
+pub fn deserialize_felt<F>(
+    bytes: &[u8],
+    felt_len: usize,
+) -> Result<(FieldElement<F>, &[u8]), DeserializationError>
+where
+    F: IsField,
+    FieldElement<F>: ByteConversion,
+{
+    let felt_bytes = bytes
+        .get(..felt_len)
+        .ok_or(DeserializationError::InvalidAmountOfBytes)?;
+    let felt = FieldElement::from_bytes_be(felt_bytes)?;
+    if felt.to_bytes_be() != felt_bytes {
+        return Err(DeserializationError::InvalidValue);
+    }
+    Ok((felt, &bytes[felt_len..]))
+}
+
+/// Checks nothing is left to read after the last field of a value, so bytes
+/// appended to a serialized value are rejected.
+pub fn check_fully_read(bytes: &[u8]) -> Result<(), DeserializationError> {
+    if bytes.is_empty() {
+        Ok(())
+    } else {
+        Err(DeserializationError::InvalidAmountOfBytes)
+    }
+}
+
+/// Reads a Merkle path of at most `max_path_len` nodes.
+pub fn deserialize_proof(
+    bytes: &[u8],
+    max_path_len: usize,
+) -> Result<(Proof<Commitment>, &[u8]), DeserializationError> {
+    let (merkle_path_len, mut bytes) = deserialize_len(bytes, COMMITMENT_SIZE, max_path_len)?;
+    let mut merkle_path = Vec::with_capacity(merkle_path_len);
 
     for _ in 0..merkle_path_len {
         let commitment = bytes
@@ -36,3 +112,31 @@ pub fn deserialize_proof(bytes: &[u8]) -> Result<(Proof<Commitment>, &[u8]), Des
 
     Ok((Proof { merkle_path }, bytes))
 }
+
+/// Serializes a Merkle cap as its number of nodes followed by them, like a path.
+pub fn serialize_cap(cap: &MerkleCap) -> Vec<u8> {
+    let mut bytes = cap.len().to_be_bytes().to_vec();
+    for node in cap {
+        bytes.extend(node);
+    }
+    bytes
+}
+
+/// Reads a Merkle cap of at most `max_cap_len` nodes.
+pub fn deserialize_cap(
+    bytes: &[u8],
+    max_cap_len: usize,
+) -> Result<(MerkleCap, &[u8]), DeserializationError> {
+    let (proof, bytes) = deserialize_proof(bytes, max_cap_len)?;
+    Ok((proof.merkle_path, bytes))
+}
+
+/// Runs `f` inside `span` and emits an event of the span with the time it took,
+/// so subscribers can report the time of each round without timing the spans.
+pub(crate) fn in_timed_span<R>(span: Span, f: impl FnOnce() -> R) -> R {
+    let _entered = span.enter();
+    let timer = Instant::now();
+    let result = f();
+    debug!(elapsed_us = timer.elapsed().as_micros() as u64, "finished");
+    result
+}
@@ -0,0 +1,163 @@
+//! The vector commitments of the trace, the composition polynomial and the FRI
+//! layers. The rounds of the prover and the checks of the verifier only commit,
+//! open and verify through `IsVectorCommitment`, so the Merkle trees can be swapped
+//! for another scheme by changing `BatchedVectorCommitment` and `FriVectorCommitment`
+//! in the configuration.
+//!
+//! The proofs store the commitments and openings of the Merkle trees, so a scheme
+//! with other ones also needs its own fields in `StarkProof`.
+
+use std::io;
+
+use lambdaworks_crypto::merkle_tree::proof::Proof;
+
+use super::config::{self, CommitmentLeaves, CommitmentTree, MerkleCap};
+use super::proof::options::ProofOptions;
+
+/// A commitment to a vector, which can be opened at any of its positions. The
+/// scheme is configured by the proof options, read by both the prover and the
+/// verifier.
+pub trait IsVectorCommitment: Sized {
+    /// Elements of the vector.
+    type Data;
+    /// What the prover sends to commit to the vector.
+    type Commitment: Clone;
+    /// Proof that the element at a position is the committed one.
+    type Opening: Clone;
+
+    /// Fails if the commitment is stored, like on disk, and it can't be written.
+    fn commit(options: &ProofOptions, vector: &[Self::Data]) -> io::Result<Self>;
+
+    /// Digest of the commitment the transcript absorbs.
+    fn root(&self) -> config::Commitment;
+
+    fn commitment(&self, options: &ProofOptions) -> Self::Commitment;
+
+    /// Openings of each of `positions`, in their order.
+    fn open(&self, options: &ProofOptions, positions: &[usize]) -> Option<Vec<Self::Opening>>;
+
+    /// The digest `root` of the committer of `commitment`, for the verifier to
+    /// replay the transcript. `None` if the commitment is malformed.
+    fn commitment_root(
+        options: &ProofOptions,
+        commitment: &Self::Commitment,
+    ) -> Option<config::Commitment>;
+
+    /// Checks that `opening` opens the position `position` of the vector committed
+    /// to by `commitment` to `data`.
+    fn verify_opening(
+        options: &ProofOptions,
+        commitment: &Self::Commitment,
+        opening: &Self::Opening,
+        position: usize,
+        data: &Self::Data,
+    ) -> bool;
+}
+
+/// Merkle trees built with the hash and stored as chosen in the options, committed
+/// to by their caps of height `merkle_cap_height`.
+impl<L: CommitmentLeaves> IsVectorCommitment for CommitmentTree<L> {
+    type Data = L::Data;
+    type Commitment = MerkleCap;
+    type Opening = Proof<config::Commitment>;
+
+    fn commit(options: &ProofOptions, vector: &[Self::Data]) -> io::Result<Self> {
+        Self::build(
+            options.commitment_hash,
+            &options.merkle_tree_storage,
+            vector,
+        )
+    }
+
+    fn root(&self) -> config::Commitment {
+        CommitmentTree::root(self)
+    }
+
+    fn commitment(&self, options: &ProofOptions) -> MerkleCap {
+        self.cap(options.merkle_cap_height)
+    }
+
+    fn open(&self, options: &ProofOptions, positions: &[usize]) -> Option<Vec<Self::Opening>> {
+        self.get_proofs_by_pos_to_cap(positions, options.merkle_cap_height)
+    }
+
+    fn commitment_root(
+        options: &ProofOptions,
+        commitment: &MerkleCap,
+    ) -> Option<config::Commitment> {
+        Self::cap_root(options.commitment_hash, commitment)
+    }
+
+    fn verify_opening(
+        options: &ProofOptions,
+        commitment: &MerkleCap,
+        opening: &Self::Opening,
+        position: usize,
+        data: &Self::Data,
+    ) -> bool {
+        Self::verify_with_cap(options.commitment_hash, opening, commitment, position, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::{
+        element::FieldElement, fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+    };
+
+    use super::*;
+    use crate::starks::config::{BatchedVectorCommitment, FriVectorCommitment};
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    fn check_openings<VC: IsVectorCommitment>(options: &ProofOptions, vector: &[VC::Data])
+    where
+        VC::Data: PartialEq,
+    {
+        let committed = VC::commit(options, vector).unwrap();
+        let commitment = committed.commitment(options);
+        assert_eq!(
+            VC::commitment_root(options, &commitment),
+            Some(committed.root())
+        );
+
+        let positions: Vec<_> = (0..vector.len()).collect();
+        let openings = committed.open(options, &positions).unwrap();
+        for (position, opening) in positions.into_iter().zip(openings) {
+            assert!(VC::verify_opening(
+                options,
+                &commitment,
+                &opening,
+                position,
+                &vector[position]
+            ));
+            let other_position = (position + 1) % vector.len();
+            if vector[other_position] != vector[position] {
+                assert!(!VC::verify_opening(
+                    options,
+                    &commitment,
+                    &opening,
+                    position,
+                    &vector[other_position]
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn merkle_trees_open_the_committed_vectors() {
+        let options = ProofOptions {
+            merkle_cap_height: 1,
+            ..ProofOptions::default_test_options()
+        };
+        let evaluations: Vec<FE> = (0..8).map(FE::from).collect();
+        let rows: Vec<Vec<FE>> = evaluations
+            .iter()
+            .map(|evaluation| vec![evaluation.clone(), evaluation.square()])
+            .collect();
+
+        check_openings::<FriVectorCommitment<F>>(&options, &evaluations);
+        check_openings::<BatchedVectorCommitment<F>>(&options, &rows);
+    }
+}
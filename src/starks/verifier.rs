@@ -1,11 +1,11 @@
-#[cfg(feature = "instruments")]
-use std::time::Instant;
+use std::{io::Read, ops::Range};
 
 //use itertools::multizip;
 #[cfg(not(feature = "test_fiat_shamir"))]
 use lambdaworks_crypto::fiat_shamir::default_transcript::DefaultTranscript;
 use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
-use log::error;
+use thiserror::Error;
+use tracing::{error, info_span};
 
 #[cfg(feature = "test_fiat_shamir")]
 use lambdaworks_crypto::fiat_shamir::test_transcript::TestTranscript;
@@ -13,21 +13,50 @@ use lambdaworks_crypto::fiat_shamir::test_transcript::TestTranscript;
 use lambdaworks_math::{
     field::{
         element::FieldElement,
+        fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
         traits::{IsFFTField, IsField},
     },
     traits::ByteConversion,
 };
 
 use super::{
-    config::{BatchedMerkleTreeBackend, FriMerkleTreeBackend},
-    domain::Domain,
-    fri::fri_decommit::FriDecommitment,
-    grinding::hash_transcript_with_int_and_get_leading_zeros,
-    proof::{options::ProofOptions, stark::StarkProof},
+    config::{BatchedVectorCommitment, Salt},
+    domain::{lde_domain_size, Domain},
+    fri::{verify_fri_query, FriCommitPhase},
+    grinding::is_valid_nonce,
+    poseidon_transcript::PoseidonTranscript,
+    proof::{
+        field_id::IdentifiedField,
+        options::{ProofOptions, ProofOptionsPolicy},
+        stark::{CommitmentRoots, DeepPolynomialOpenings, ProofLimits, StarkProof},
+        stream::ProofStreamReader,
+    },
+    public_coin::{ChallengeProvider, PublicCoinTranscript},
+    salt::salted_leaf,
     traits::AIR,
-    transcript::{batch_sample_challenges, sample_z_ood, transcript_to_field, transcript_to_usize},
+    transcript::{
+        append_public_parameters, batch_sample_challenges, sample_query_indexes, sample_z_ood,
+        transcript_to_field, RapTranscript,
+    },
+    utils::in_timed_span,
+    vector_commitment::IsVectorCommitment,
 };
 
+/// Step of the verification a proof failed. The details are logged when it fails.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum VerificationError {
+    #[error("The public inputs are not valid for the proof")]
+    InvalidPublicInputs,
+    #[error("The proof does not have the shape of the AIR and the options")]
+    InvalidShape,
+    #[error("The composition polynomial does not match the constraints")]
+    CompositionPolynomial,
+    #[error("The DEEP composition polynomial does not match the openings")]
+    DeepCompositionPolynomial,
+    #[error("The FRI decommitments are not valid")]
+    Fri,
+}
+
 #[cfg(feature = "test_fiat_shamir")]
 fn step_1_transcript_initialization() -> TestTranscript {
     TestTranscript::new()
@@ -47,18 +76,23 @@ where
     z: FieldElement<F>,
     boundary_coeffs: Vec<(FieldElement<F>, FieldElement<F>)>,
     transition_coeffs: Vec<(FieldElement<F>, FieldElement<F>)>,
-    trace_term_coeffs: Vec<Vec<FieldElement<F>>>,
+    // Coefficient of each trace term, with its `(row, column)` cell of the frame
+    trace_term_coeffs: Vec<((usize, usize), FieldElement<F>)>,
     gamma_even: FieldElement<F>,
     gamma_odd: FieldElement<F>,
+    gamma_mask: Option<FieldElement<F>>,
+    // 𝛾ₑ and 𝛾ₑ', if the AIR opens H₁ and H₂ at an extra point
+    gamma_extra: Option<(FieldElement<F>, FieldElement<F>)>,
     zetas: Vec<FieldElement<F>>,
     iotas: Vec<usize>,
     rap_challenges: A::RAPChallenges,
-    leading_zeros_count: u8, // number of leading zeros in the grinding
+    valid_nonce: bool, // whether the nonce satisfies the grinding
 }
 
 fn step_1_replay_rounds_and_recover_challenges<F, A, T>(
     air: &A,
     proof: &StarkProof<F>,
+    roots: &CommitmentRoots,
     domain: &Domain<F>,
     transcript: &mut T,
 ) -> Challenges<F, A>
@@ -68,19 +102,37 @@ where
     A: AIR<Field = F>,
     T: Transcript,
 {
+    append_public_parameters(air, transcript);
+
     // ===================================
     // ==========|   Round 1   |==========
     // ===================================
 
     // <<<< Receive commitments:[tⱼ]
-    let total_columns = air.context().trace_columns;
-
-    transcript.append(&proof.lde_trace_merkle_roots[0]);
-
-    let rap_challenges = air.build_rap_challenges(transcript);
+    let total_columns = air.committed_columns().len();
+
+    let commitment_layout = &air.options().commitment_layout;
+    let num_main_columns = total_columns - air.number_auxiliary_rap_columns();
+    let mut lde_trace_merkle_roots = roots.lde_trace.iter().copied();
+    let mut take_roots = |num_columns: usize| -> Vec<_> {
+        lde_trace_merkle_roots
+            .by_ref()
+            .take(commitment_layout.column_groups(num_columns).len())
+            .collect()
+    };
+
+    let mut rap_transcript = RapTranscript::new(&mut *transcript);
+    rap_transcript.append_main_trace_roots(&take_roots(num_main_columns));
+
+    let mut rap_challenges = rap_transcript.build_rap_challenges(air);
+
+    for round in 0..air.number_of_rap_rounds() {
+        if round > 0 {
+            rap_transcript.extend_rap_challenges(air, round, &mut rap_challenges);
+        }
 
-    if let Some(root) = proof.lde_trace_merkle_roots.get(1) {
-        transcript.append(root);
+        let num_round_columns = air.number_auxiliary_rap_columns_in_round(round);
+        rap_transcript.append_aux_trace_roots(round, &take_roots(num_round_columns));
     }
 
     // ===================================
@@ -115,7 +167,7 @@ where
         .collect();
 
     // <<<< Receive commitments: [H₁], [H₂]
-    transcript.append(&proof.composition_poly_root);
+    transcript.append(&roots.composition_poly);
 
     // ===================================
     // ==========|   Round 3   |==========
@@ -132,6 +184,15 @@ where
     transcript.append(&proof.composition_poly_even_ood_evaluation.to_bytes_be());
     // <<<< Receive value: H₂(z²)
     transcript.append(&proof.composition_poly_odd_ood_evaluation.to_bytes_be());
+    // <<<< Receive value: M(z²)
+    if let Some(evaluation) = &proof.composition_poly_mask_ood_evaluation {
+        transcript.append(&evaluation.to_bytes_be());
+    }
+    // <<<< Receive values: H₁(w) and H₂(w)
+    if let Some((even, odd)) = &proof.composition_poly_extra_ood_evaluations {
+        transcript.append(&even.to_bytes_be());
+        transcript.append(&odd.to_bytes_be());
+    }
     // <<<< Receive values: tⱼ(zgᵏ)
     for i in 0..proof.trace_ood_frame_evaluations.num_rows() {
         for element in proof.trace_ood_frame_evaluations.get_row(i).iter() {
@@ -146,23 +207,34 @@ where
     // >>>> Send challenges: 𝛾, 𝛾'
     let gamma_even = transcript_to_field(transcript);
     let gamma_odd = transcript_to_field(transcript);
+    // >>>> Send challenge: 𝛾ₘ, if there is a mask
+    let gamma_mask = proof
+        .composition_poly_mask_ood_evaluation
+        .as_ref()
+        .map(|_| transcript_to_field(transcript));
+    // >>>> Send challenges: 𝛾ₑ, 𝛾ₑ', if there is an extra opening
+    let gamma_extra = proof
+        .composition_poly_extra_ood_evaluations
+        .as_ref()
+        .map(|_| {
+            (
+                transcript_to_field(transcript),
+                transcript_to_field(transcript),
+            )
+        });
 
     // >>>> Send challenges: 𝛾ⱼ, 𝛾ⱼ'
-    // Get the number of trace terms the DEEP composition poly will have.
-    // One coefficient will be sampled for each of them.
-    // TODO: try remove this, call transcript inside for and move gamma declarations
-    let trace_term_coeffs = (0..total_columns)
-        .map(|_| {
-            (0..air.context().transition_offsets.len())
-                .map(|_| transcript_to_field(transcript))
-                .collect()
-        })
-        .collect::<Vec<Vec<FieldElement<F>>>>();
+    // One coefficient is sampled for each trace term of the DEEP composition poly.
+    let trace_term_coeffs = air
+        .deep_trace_terms()
+        .into_iter()
+        .map(|cell| (cell, transcript_to_field(transcript)))
+        .collect();
 
     // FRI commit phase
 
-    let merkle_roots = &proof.fri_layers_merkle_roots;
-    let zetas = merkle_roots
+    let zetas = roots
+        .fri_layers
         .iter()
         .map(|root| {
             // <<<< Receive commitment: [pₖ] (the first one is [p₀])
@@ -174,22 +246,26 @@ where
         .collect::<Vec<FieldElement<F>>>();
 
     // <<<< Receive value: pₙ
-    transcript.append(&proof.fri_last_value.to_bytes_be());
+    for coefficient in &proof.fri_last_poly {
+        transcript.append(&coefficient.to_bytes_be());
+    }
 
     // Receive grinding value
     // 1) Receive challenge from the transcript
     let transcript_challenge = transcript.challenge();
     let nonce = proof.nonce;
-    let leading_zeros_count =
-        hash_transcript_with_int_and_get_leading_zeros(&transcript_challenge, nonce);
+    let valid_nonce = is_valid_nonce(&transcript_challenge, nonce, air.options().grinding);
     transcript.append(&nonce.to_be_bytes());
 
     // FRI query phase
     // <<<< Send challenges 𝜄ₛ (iota_s)
-    let iota_max = 2_usize.pow(domain.lde_root_order);
-    let iotas = (0..air.options().fri_number_of_queries)
-        .map(|_| transcript_to_usize(transcript) % iota_max)
-        .collect();
+    let iota_max = 1_usize << domain.lde_root_order;
+    let iotas = sample_query_indexes(
+        transcript,
+        air.options().fri_number_of_queries,
+        iota_max,
+        air.options().deduplicate_queries,
+    );
 
     Challenges {
         z,
@@ -198,10 +274,39 @@ where
         trace_term_coeffs,
         gamma_even,
         gamma_odd,
+        gamma_mask,
+        gamma_extra,
         zetas,
         iotas,
         rap_challenges,
-        leading_zeros_count,
+        valid_nonce,
+    }
+}
+
+/// Buffers reused by the verifications of many proofs, so checking the composition
+/// polynomial at the out-of-domain point doesn't allocate them for each proof. See
+/// `verify_with_context`.
+pub struct VerifierContext<F: IsField> {
+    boundary_numerators: Vec<FieldElement<F>>,
+    boundary_denominators: Vec<FieldElement<F>>,
+    exemption_evaluations: Vec<FieldElement<F>>,
+    degree_adjustments: Vec<FieldElement<F>>,
+}
+
+impl<F: IsField> VerifierContext<F> {
+    pub fn new() -> Self {
+        Self {
+            boundary_numerators: Vec::new(),
+            boundary_denominators: Vec::new(),
+            exemption_evaluations: Vec::new(),
+            degree_adjustments: Vec::new(),
+        }
+    }
+}
+
+impl<F: IsField> Default for VerifierContext<F> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -210,7 +315,15 @@ fn step_2_verify_claimed_composition_polynomial<F: IsFFTField, A: AIR<Field = F>
     proof: &StarkProof<F>,
     domain: &Domain<F>,
     challenges: &Challenges<F, A>,
+    context: &mut VerifierContext<F>,
 ) -> bool {
+    let VerifierContext {
+        boundary_numerators,
+        boundary_denominators,
+        exemption_evaluations,
+        degree_adjustments,
+    } = context;
+
     // BEGIN TRACE <-> Composition poly consistency evaluation check
     // These are H_1(z^2) and H_2(z^2)
     let composition_poly_even_ood_evaluation = &proof.composition_poly_even_ood_evaluation;
@@ -221,89 +334,110 @@ fn step_2_verify_claimed_composition_polynomial<F: IsFFTField, A: AIR<Field = F>
     //let n_trace_cols = air.context().trace_columns;
     // special cases.
     let trace_length = air.trace_length();
-    let composition_poly_degree_bound = air.composition_poly_degree_bound();
-    let boundary_term_degree_adjustment = composition_poly_degree_bound - trace_length;
-    let number_of_b_constraints = boundary_constraints.constraints.len();
+    // Checked with the shape of the proof
+    let boundary_term_degree_adjustment = air.degree_adjustment(1).unwrap();
+    // The boundary constraints are checked at the row of the frame of offset zero.
+    // Its presence is checked with the shape of the proof.
+    // The frame of the proof has the committed columns only, see `AIR::virtual_columns`
+    let trace_ood_frame_evaluations = air
+        .committed_columns()
+        .expand_frame(&proof.trace_ood_frame_evaluations);
+    let current_row = air.context().frame_row_of_offset(0).unwrap();
+    let current_row_evaluations = trace_ood_frame_evaluations.get_row(current_row);
 
     // Following naming conventions from https://www.notamonadtutorial.com/diving-deep-fri/
-    let (boundary_c_i_evaluations_num, mut boundary_c_i_evaluations_den): (
-        Vec<FieldElement<F>>,
-        Vec<FieldElement<F>>,
-    ) = (0..number_of_b_constraints)
-        .map(|index| {
-            let step = boundary_constraints.constraints[index].step;
-            let point = &domain.trace_primitive_root.pow(step as u64);
-            let trace_idx = boundary_constraints.constraints[index].col;
-            let trace_evaluation = &proof.trace_ood_frame_evaluations.get_row(0)[trace_idx];
-            let boundary_zerofier_challenges_z_den = &challenges.z - point;
-
-            let boundary_quotient_ood_evaluation_num =
-                trace_evaluation - &boundary_constraints.constraints[index].value;
-
-            (
-                boundary_quotient_ood_evaluation_num,
-                boundary_zerofier_challenges_z_den,
-            )
-        })
-        .collect::<Vec<_>>()
-        .into_iter()
-        .unzip();
+    boundary_numerators.clear();
+    boundary_denominators.clear();
+    for constraint in &boundary_constraints.constraints {
+        // A step past the end of the trace would constrain the row it wraps around to
+        if constraint.step >= trace_length {
+            error!(
+                step = constraint.step,
+                trace_length, "Boundary constraint outside of the trace"
+            );
+            return false;
+        }
+        let point = domain.trace_primitive_root.pow(constraint.step as u64);
+        let trace_evaluation = &current_row_evaluations[constraint.col];
+        boundary_numerators.push(trace_evaluation - &constraint.value);
+        boundary_denominators.push(&challenges.z - point);
+    }
 
-    FieldElement::inplace_batch_inverse(&mut boundary_c_i_evaluations_den);
+    FieldElement::inplace_batch_inverse(boundary_denominators);
 
     let boundary_degree_z = challenges.z.pow(boundary_term_degree_adjustment);
-    let boundary_quotient_ood_evaluation: FieldElement<F> = boundary_c_i_evaluations_num
+    let boundary_quotient_ood_evaluation: FieldElement<F> = boundary_numerators
         .iter()
-        .zip(&boundary_c_i_evaluations_den)
+        .zip(boundary_denominators.iter())
         .zip(&challenges.boundary_coeffs)
         .map(|((num, den), (alpha, beta))| num * den * (alpha * &boundary_degree_z + beta))
         .fold(FieldElement::<F>::zero(), |acc, x| acc + x);
 
-    let transition_ood_frame_evaluations = air.compute_transition(
-        &proof.trace_ood_frame_evaluations,
-        &challenges.rap_challenges,
-    );
-
-    let denominator = (&challenges.z.pow(trace_length) - FieldElement::<F>::one()).inv();
+    let transition_ood_frame_evaluations =
+        air.compute_transition(&trace_ood_frame_evaluations, &challenges.rap_challenges);
 
-    let exemption = air
-        .transition_exemptions_verifier(
-            domain.trace_roots_of_unity.iter().last().expect("has last"),
-        )
-        .iter()
-        .map(|poly| poly.evaluate(&challenges.z))
-        .collect::<Vec<FieldElement<F>>>();
+    let denominator = (&challenges.z.pow(trace_length as u64) - FieldElement::<F>::one()).inv();
 
-    let max_degree = air
+    // Evaluations at z of the polynomials of `transition_exemptions_verifier`, the
+    // i-th one being the product of (x - g⁻ᵏ) for k from 1 to i + 1, computed one
+    // from the previous one
+    let last_trace_root = domain.trace_roots_of_unity.iter().last().expect("has last");
+    let max_exemption = air
         .context()
-        .transition_degrees()
-        .iter()
+        .effective_transition_exemptions_iter()
         .max()
-        .expect("has maximum degree");
-    let degree_adjustments = (1..=*max_degree)
-        .map(|transition_degree| {
-            let degree_adjustment =
-                composition_poly_degree_bound - (trace_length * (transition_degree - 1));
-            challenges.z.pow(degree_adjustment)
-        })
-        .collect::<Vec<FieldElement<F>>>();
+        .unwrap_or(0);
+    exemption_evaluations.clear();
+    let mut root_power = FieldElement::<F>::one();
+    let mut exemption_evaluation = FieldElement::<F>::one();
+    for _ in 0..max_exemption {
+        root_power = root_power * last_trace_root;
+        exemption_evaluation = exemption_evaluation * (&challenges.z - &root_power);
+        exemption_evaluations.push(exemption_evaluation.clone());
+    }
+
+    // One for each constraint, see `AIR::transition_degree_adjustment`. Constraints
+    // with the same exponent share the power of z.
+    degree_adjustments.clear();
+    let mut adjustment_exponents: Vec<u64> = Vec::new();
+    for constraint in 0..air.context().num_transition_constraints() {
+        // Checked with the shape of the proof
+        let exponent = air.transition_degree_adjustment(constraint).unwrap();
+        let adjustment = match adjustment_exponents
+            .iter()
+            .position(|other| *other == exponent)
+        {
+            Some(previous) => degree_adjustments[previous].clone(),
+            None => challenges.z.pow(exponent),
+        };
+        adjustment_exponents.push(exponent);
+        degree_adjustments.push(adjustment);
+    }
     let unity = &FieldElement::one();
+    // Evaluation at z of the polynomial of `transition_exemption_zones`
+    let zone_exemptions_evaluation = |constraint| {
+        air.context()
+            .zone_exempted_steps(constraint, trace_length)
+            .iter()
+            .fold(FieldElement::<F>::one(), |acc, step| {
+                acc * (&challenges.z - &domain.trace_roots_of_unity[*step])
+            })
+    };
     let transition_c_i_evaluations_sum = transition_ood_frame_evaluations
         .iter()
-        .zip(&air.context().transition_degrees)
-        .zip(&air.context().transition_exemptions)
+        .enumerate()
+        .map(|(constraint, eval)| eval * zone_exemptions_evaluation(constraint))
+        .zip(degree_adjustments.iter())
+        .zip(air.context().effective_transition_exemptions_iter())
         .zip(&challenges.transition_coeffs)
         .fold(
             FieldElement::zero(),
-            |acc, (((eval, degree), except), (alpha, beta))| {
+            |acc, (((eval, degree_adjustment), except), (alpha, beta))| {
                 let except = except
                     .checked_sub(1)
-                    .map(|i| &exemption[i])
+                    .map(|i| &exemption_evaluations[i])
                     .unwrap_or(unity);
-                acc + &denominator
-                    * eval
-                    * (alpha * &degree_adjustments[degree - 1] + beta)
-                    * except
+                acc + &denominator * eval * (alpha * degree_adjustment + beta) * except
             },
         );
 
@@ -316,10 +450,12 @@ fn step_2_verify_claimed_composition_polynomial<F: IsFFTField, A: AIR<Field = F>
     composition_poly_claimed_ood_evaluation == composition_poly_ood_evaluation
 }
 
-fn step_3_verify_fri<F, A>(
+fn step_4_verify_fri<F, A>(
     proof: &StarkProof<F>,
     domain: &Domain<F>,
     challenges: &Challenges<F, A>,
+    deep_poly_evaluations: &[FieldElement<F>],
+    options: &ProofOptions,
 ) -> bool
 where
     F: IsFFTField,
@@ -327,6 +463,13 @@ where
     A: AIR<Field = F>,
 {
     // verify FRI
+    let commit_phase = FriCommitPhase {
+        layers_caps: &proof.fri_layers_merkle_caps,
+        zetas: &challenges.zetas,
+        last_poly: &proof.fri_last_poly,
+        lde_root_order: domain.lde_root_order,
+        options,
+    };
     let two_inv = &FieldElement::from(2).inv();
     let mut evaluation_point_inverse = challenges
         .iotas
@@ -336,35 +479,124 @@ where
         .collect::<Vec<FieldElement<F>>>();
     FieldElement::inplace_batch_inverse(&mut evaluation_point_inverse);
     proof
-        .query_list
-        .iter()
-        .zip(&challenges.iotas)
+        .query_openings(&challenges.iotas)
         .zip(evaluation_point_inverse)
-        .fold(true, |mut result, ((proof_s, iota_s), eval)| {
+        .zip(deep_poly_evaluations)
+        .fold(true, |mut result, ((query, eval), deep_poly_evaluation)| {
             // this is done in constant time
-            result &= verify_query_and_sym_openings(
-                proof,
-                &challenges.zetas,
-                *iota_s,
-                proof_s,
-                domain,
+            result &= verify_fri_query(
+                &commit_phase,
+                query.iota,
+                query.fri_decommitment,
+                &domain.lde_roots_of_unity_coset[query.iota],
                 eval,
                 two_inv,
+                deep_poly_evaluation,
             );
             result
         })
 }
 
-fn step_4_verify_deep_composition_polynomial<F: IsFFTField, A: AIR<Field = F>>(
+/// Verifies the openings of the trace and composition polynomials at the queried
+/// points and returns the evaluations of the DEEP composition polynomial
+/// reconstructed from them. FRI checks these evaluations as the ones of its first
+/// layer, so they are not opened again in the FRI decommitments.
+fn step_3_verify_deep_composition_polynomial<F: IsFFTField, A: AIR<Field = F>>(
     air: &A,
     proof: &StarkProof<F>,
     domain: &Domain<F>,
     challenges: &Challenges<F, A>,
+) -> Option<Vec<FieldElement<F>>>
+where
+    FieldElement<F>: ByteConversion,
+{
+    verify_trace_and_composition_openings(air, proof, challenges)
+        .then(|| reconstruct_deep_composition_poly_evaluations(air, proof, domain, challenges))
+}
+
+/// Verifies the openings of the trace and composition polynomials at the queried
+/// points against their commitments.
+fn verify_trace_and_composition_openings<F: IsFFTField, A: AIR<Field = F>>(
+    air: &A,
+    proof: &StarkProof<F>,
+    challenges: &Challenges<F, A>,
 ) -> bool
 where
     FieldElement<F>: ByteConversion,
 {
-    let primitive_root = &F::get_primitive_root_of_unity(domain.root_order as u64).unwrap();
+    let column_groups = trace_column_groups(air);
+    let options = air.options();
+    let salted = options.salt_commitments;
+    let num_trace_salts = if salted { column_groups.len() } else { 0 };
+    let masked = options.randomizes_trace();
+
+    proof
+        .query_openings(&challenges.iotas)
+        .fold(true, |mut result, query| {
+            let iota_n = query.iota;
+            let deep_poly_opening = query.deep_poly_openings;
+            // There are salts exactly when the commitments are salted, and openings
+            // of the mask exactly when the trace is randomized
+            result &= deep_poly_opening.lde_composition_poly_salt.is_some() == salted
+                && deep_poly_opening.lde_trace_salts.len() == num_trace_salts
+                && deep_poly_opening.composition_poly_openings().mask.is_some() == masked;
+
+            let evaluations = leaf_with_salt(
+                deep_poly_opening.composition_poly_leaf(),
+                deep_poly_opening.lde_composition_poly_salt.as_ref(),
+            );
+
+            // Verify opening Open(H₁(D_LDE, 𝜐₀), Open(H₂(D_LDE, 𝜐₀) and Open(M(D_LDE, 𝜐₀)),
+            result &= BatchedVectorCommitment::<F>::verify_opening(
+                options,
+                &proof.composition_poly_cap,
+                &deep_poly_opening.lde_composition_poly_proof,
+                iota_n,
+                &evaluations,
+            );
+
+            let lde_trace_evaluations = column_groups.iter().enumerate().map(|(j, group)| {
+                leaf_with_salt(
+                    deep_poly_opening.lde_trace_evaluations[group.clone()].to_vec(),
+                    deep_poly_opening.lde_trace_salts.get(j),
+                )
+            });
+
+            // Verify openings Open(tⱼ(D_LDE), 𝜐₀)
+            proof
+                .lde_trace_merkle_caps
+                .iter()
+                .zip(&deep_poly_opening.lde_trace_merkle_proofs)
+                .zip(lde_trace_evaluations)
+                .fold(result, |acc, ((merkle_cap, merkle_proof), evaluation)| {
+                    acc & BatchedVectorCommitment::<F>::verify_opening(
+                        options,
+                        merkle_cap,
+                        merkle_proof,
+                        iota_n,
+                        &evaluation,
+                    )
+                })
+        })
+}
+
+/// Reconstructs Deep(𝜐₀) at each of the queried points from the opened values,
+/// without checking them against their commitments.
+fn reconstruct_deep_composition_poly_evaluations<F: IsFFTField, A: AIR<Field = F>>(
+    air: &A,
+    proof: &StarkProof<F>,
+    domain: &Domain<F>,
+    challenges: &Challenges<F, A>,
+) -> Vec<FieldElement<F>> {
+    let primitive_root = &F::get_primitive_root_of_unity(domain.root_order).unwrap();
+    // The rows of the out-of-domain frame are the evaluations at z·gᵏ, for each
+    // offset k of the frame of the AIR.
+    let frame_points: Vec<_> = air
+        .context()
+        .transition_offsets
+        .iter()
+        .map(|offset| &challenges.z * primitive_root.pow(*offset))
+        .collect();
     let z_squared = &challenges.z.square();
     let mut denom_inv = challenges
         .iotas
@@ -372,188 +604,129 @@ where
         .map(|iota_n| &domain.lde_roots_of_unity_coset[*iota_n] - z_squared)
         .collect::<Vec<FieldElement<F>>>();
     FieldElement::inplace_batch_inverse(&mut denom_inv);
+    // 1 / (𝜐₀ − w) for each query, if the AIR opens H₁ and H₂ at an extra point w
+    let extra_denom_inv = air.composition_poly_extra_opening().map(|opening| {
+        let w = opening.point(&challenges.z, primitive_root);
+        let mut extra_denom_inv = challenges
+            .iotas
+            .iter()
+            .map(|iota_n| &domain.lde_roots_of_unity_coset[*iota_n] - &w)
+            .collect::<Vec<FieldElement<F>>>();
+        FieldElement::inplace_batch_inverse(&mut extra_denom_inv);
+        extra_denom_inv
+    });
 
-    challenges
-        .iotas
-        .iter()
-        .zip(&proof.deep_poly_openings)
+    proof
+        .query_openings(&challenges.iotas)
         .zip(&denom_inv)
         .enumerate()
-        .fold(
-            true,
-            |mut result, (i, ((iota_n, deep_poly_opening), denom_inv))| {
-                let evaluations = vec![
-                    deep_poly_opening
-                        .lde_composition_poly_even_evaluation
-                        .clone(),
-                    deep_poly_opening
-                        .lde_composition_poly_odd_evaluation
-                        .clone(),
-                ];
-
-                // Verify opening Open(H₁(D_LDE, 𝜐₀) and Open(H₂(D_LDE, 𝜐₀),
-                result &= deep_poly_opening
-                    .lde_composition_poly_proof
-                    .verify::<BatchedMerkleTreeBackend<F>>(
-                        &proof.composition_poly_root,
-                        *iota_n,
-                        &evaluations,
-                    );
-
-                let num_main_columns =
-                    air.context().trace_columns - air.number_auxiliary_rap_columns();
-                let lde_trace_evaluations = vec![
-                    deep_poly_opening.lde_trace_evaluations[..num_main_columns].to_vec(),
-                    deep_poly_opening.lde_trace_evaluations[num_main_columns..].to_vec(),
-                ];
-
-                // Verify openings Open(tⱼ(D_LDE), 𝜐₀)
-                proof
-                    .lde_trace_merkle_roots
-                    .iter()
-                    .zip(&deep_poly_opening.lde_trace_merkle_proofs)
-                    .zip(lde_trace_evaluations)
-                    .fold(result, |acc, ((merkle_root, merkle_proof), evaluation)| {
-                        acc & merkle_proof.verify::<BatchedMerkleTreeBackend<F>>(
-                            merkle_root,
-                            *iota_n,
-                            &evaluation,
-                        )
-                    });
-
-                // DEEP consistency check
-                // Verify that Deep(x) is constructed correctly
-                let mut divisors = (0..proof.trace_ood_frame_evaluations.num_rows())
-                    .map(|row_idx| {
-                        &domain.lde_roots_of_unity_coset[*iota_n]
-                            - &challenges.z * primitive_root.pow(row_idx as u64)
-                    })
-                    .collect::<Vec<FieldElement<F>>>();
-                FieldElement::inplace_batch_inverse(&mut divisors);
-                let deep_poly_evaluation = reconstruct_deep_composition_poly_evaluation(
-                    proof, challenges, denom_inv, &divisors, i,
-                );
-
-                let deep_poly_claimed_evaluation = &proof.query_list[i].layers_evaluations[0];
-                result & (deep_poly_claimed_evaluation == &deep_poly_evaluation)
-            },
-        )
-}
-
-fn verify_query_and_sym_openings<F: IsField + IsFFTField>(
-    proof: &StarkProof<F>,
-    zetas: &[FieldElement<F>],
-    iota: usize,
-    fri_decommitment: &FriDecommitment<F>,
-    domain: &Domain<F>,
-    evaluation_point: FieldElement<F>,
-    two_inv: &FieldElement<F>,
-) -> bool
-where
-    FieldElement<F>: ByteConversion,
-{
-    let fri_layers_merkle_roots = &proof.fri_layers_merkle_roots;
-    let evaluation_point_vec: Vec<FieldElement<F>> =
-        core::iter::successors(Some(evaluation_point), |evaluation_point| {
-            Some(evaluation_point.square())
+        .map(|(i, (query, denom_inv))| {
+            let mut divisors = frame_points
+                .iter()
+                .map(|frame_point| &domain.lde_roots_of_unity_coset[query.iota] - frame_point)
+                .collect::<Vec<FieldElement<F>>>();
+            FieldElement::inplace_batch_inverse(&mut divisors);
+            reconstruct_deep_composition_poly_evaluation(
+                proof,
+                query.deep_poly_openings,
+                challenges,
+                denom_inv,
+                extra_denom_inv.as_ref().map(|denoms| &denoms[i]),
+                &divisors,
+            )
         })
-        .take(fri_layers_merkle_roots.len())
-        .collect();
-
-    let mut v = fri_decommitment.layers_evaluations[0].clone();
-    // For each fri layer merkle proof check:
-    // That each merkle path verifies
+        .collect()
+}
 
-    // Sample beta with fiat shamir
-    // Compute v = [P_i(z_i) + P_i(-z_i)] / 2 + beta * [P_i(z_i) - P_i(-z_i)] / (2 * z_i)
-    // Where P_i is the folded polynomial of the i-th fiat shamir round
-    // z_i is obtained from the first z (that was derived through Fiat-Shamir) through a known calculation
-    // The calculation is, given the index, index % length_of_evaluation_domain
+fn leaf_with_salt<F: IsField>(
+    leaf: Vec<FieldElement<F>>,
+    salt: Option<&Salt>,
+) -> Vec<FieldElement<F>> {
+    match salt {
+        Some(salt) => salted_leaf(&leaf, salt),
+        None => leaf,
+    }
+}
 
-    // Check that v = P_{i+1}(z_i)
+/// Returns the ranges of trace columns committed by each of the trace Merkle roots,
+/// in the same order as they appear in the proof: first the groups of the main trace
+/// and then the groups of the auxiliary segment of each interaction round.
+fn trace_column_groups<F: IsFFTField, A: AIR<Field = F>>(air: &A) -> Vec<Range<usize>> {
+    let layout = &air.options().commitment_layout;
+    let num_main_columns = air.committed_columns().len() - air.number_auxiliary_rap_columns();
+    let mut groups = layout.column_groups(num_main_columns);
+
+    let mut offset = num_main_columns;
+    for round in 0..air.number_of_rap_rounds() {
+        let num_round_columns = air.number_auxiliary_rap_columns_in_round(round);
+        groups.extend(
+            layout
+                .column_groups(num_round_columns)
+                .into_iter()
+                .map(|group| (group.start + offset)..(group.end + offset)),
+        );
+        offset += num_round_columns;
+    }
 
-    // For each (merkle_root, merkle_auth_path) / fold
-    // With the auth path containining the element that the path proves it's existence
-    fri_layers_merkle_roots
-        .iter()
-        .enumerate()
-        .zip(&fri_decommitment.layers_auth_paths)
-        .zip(&fri_decommitment.layers_evaluations)
-        .zip(&fri_decommitment.layers_auth_paths_sym)
-        .zip(&fri_decommitment.layers_evaluations_sym)
-        .zip(evaluation_point_vec)
-        .fold(
-            true,
-            |result,
-             (
-                (((((k, merkle_root), auth_path), evaluation), auth_path_sym), evaluation_sym),
-                evaluation_point_inv,
-            )| {
-                let domain_length = 1 << (domain.lde_root_order - k as u32);
-                let layer_evaluation_index_sym = (iota + domain_length / 2) % domain_length;
-                // Since we always derive the current layer from the previous layer
-                // We start with the second one, skipping the first, so previous is layer is the first one
-                // This is the current layer's evaluation domain length.
-                // We need it to know what the decommitment index for the current
-                // layer is, so we can check the merkle paths at the right index.
-
-                // Verify opening Open(pₖ(Dₖ), −𝜐ₛ^(2ᵏ))
-                let auth_sym = &auth_path_sym.verify::<FriMerkleTreeBackend<F>>(
-                    merkle_root,
-                    layer_evaluation_index_sym,
-                    evaluation_sym,
-                );
-                // Verify opening Open(pₖ(Dₖ), 𝜐ₛ)
-                let auth_point =
-                    auth_path.verify::<FriMerkleTreeBackend<F>>(merkle_root, iota, evaluation);
-                let beta = &zetas[k];
-                // v is the calculated element for the co linearity check
-                v = (&v + evaluation_sym) * two_inv
-                    + beta * (&v - evaluation_sym) * two_inv * evaluation_point_inv;
-
-                // Check that next value is the given by the prover
-                if k < fri_decommitment.layers_evaluations.len() - 1 {
-                    let next_layer_evaluation = &fri_decommitment.layers_evaluations[k + 1];
-                    result & (v == *next_layer_evaluation) & auth_point & auth_sym
-                } else {
-                    result & (v == proof.fri_last_value) & auth_point & auth_sym
-                }
-            },
-        )
+    groups
 }
 
 // Reconstruct Deep(\upsilon_0) off the values in the proof
 fn reconstruct_deep_composition_poly_evaluation<F: IsFFTField, A: AIR<Field = F>>(
     proof: &StarkProof<F>,
+    deep_poly_openings: &DeepPolynomialOpenings<F>,
     challenges: &Challenges<F, A>,
     denom_inv: &FieldElement<F>,
+    extra_denom_inv: Option<&FieldElement<F>>,
     divisors: &[FieldElement<F>],
-    i: usize,
 ) -> FieldElement<F> {
-    let trace_term = (0..proof.trace_ood_frame_evaluations.num_columns())
-        .zip(&challenges.trace_term_coeffs)
-        .fold(FieldElement::zero(), |trace_terms, (col_idx, coeff_row)| {
-            let trace_i = (0..proof.trace_ood_frame_evaluations.num_rows())
-                .zip(coeff_row)
-                .fold(FieldElement::zero(), |trace_t, (row_idx, coeff)| {
-                    let poly_evaluation =
-                        (proof.deep_poly_openings[i].lde_trace_evaluations[col_idx].clone()
-                            - proof.trace_ood_frame_evaluations.get_row(row_idx)[col_idx].clone())
-                            * &divisors[row_idx];
-                    trace_t + &poly_evaluation * coeff
-                });
-            trace_terms + trace_i
-        });
+    let trace_term = challenges.trace_term_coeffs.iter().fold(
+        FieldElement::zero(),
+        |trace_terms, ((row_idx, col_idx), coeff)| {
+            let poly_evaluation = (&deep_poly_openings.lde_trace_evaluations[*col_idx]
+                - &proof.trace_ood_frame_evaluations.get_row(*row_idx)[*col_idx])
+                * &divisors[*row_idx];
+            trace_terms + &poly_evaluation * coeff
+        },
+    );
 
-    let h_1_upsilon_0 = &proof.deep_poly_openings[i].lde_composition_poly_even_evaluation;
+    let composition_poly_openings = deep_poly_openings.composition_poly_openings();
+    let h_1_upsilon_0 = composition_poly_openings.even;
     let h_1_zsquared = &proof.composition_poly_even_ood_evaluation;
-    let h_2_upsilon_0 = &proof.deep_poly_openings[i].lde_composition_poly_odd_evaluation;
+    let h_2_upsilon_0 = composition_poly_openings.odd;
     let h_2_zsquared = &proof.composition_poly_odd_ood_evaluation;
 
     let h_1_term = (h_1_upsilon_0 - h_1_zsquared) * denom_inv;
     let h_2_term = (h_2_upsilon_0 - h_2_zsquared) * denom_inv;
 
-    trace_term + h_1_term * &challenges.gamma_even + h_2_term * &challenges.gamma_odd
+    let mask_term = match (
+        composition_poly_openings.mask,
+        &proof.composition_poly_mask_ood_evaluation,
+        &challenges.gamma_mask,
+    ) {
+        (Some(m_upsilon_0), Some(m_zsquared), Some(gamma_mask)) => {
+            (m_upsilon_0 - m_zsquared) * denom_inv * gamma_mask
+        }
+        _ => FieldElement::zero(),
+    };
+
+    let extra_opening_term = match (
+        &proof.composition_poly_extra_ood_evaluations,
+        &challenges.gamma_extra,
+        extra_denom_inv,
+    ) {
+        (Some((h_1_w, h_2_w)), Some((gamma_e, gamma_e_p)), Some(extra_denom_inv)) => {
+            ((h_1_upsilon_0 - h_1_w) * gamma_e + (h_2_upsilon_0 - h_2_w) * gamma_e_p)
+                * extra_denom_inv
+        }
+        _ => FieldElement::zero(),
+    };
+
+    trace_term
+        + h_1_term * &challenges.gamma_even
+        + h_2_term * &challenges.gamma_odd
+        + mask_term
+        + extra_opening_term
 }
 
 pub fn verify<F, A>(
@@ -562,95 +735,615 @@ pub fn verify<F, A>(
     proof_options: &ProofOptions,
 ) -> bool
 where
-    F: IsFFTField,
+    F: IsFFTField + IdentifiedField,
     A: AIR<Field = F>,
-    FieldElement<F>: ByteConversion,
+    FieldElement<F>: ByteConversion + Send + Sync,
 {
-    // Verify there are enough queries
-    if proof.query_list.len() < proof_options.fri_number_of_queries {
+    verify_with_transcript::<F, A, _>(
+        proof,
+        pub_input,
+        proof_options,
+        &mut step_1_transcript_initialization(),
+    )
+}
+
+/// Verifies a proof with the options it was generated with, which can be any that
+/// the policy accepts, instead of options fixed in advance.
+pub fn verify_with_policy<F, A>(
+    proof: &StarkProof<F>,
+    pub_input: &A::PublicInputs,
+    policy: &ProofOptionsPolicy,
+) -> bool
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    if let Err(error) = policy.check(&proof.options, proof.trace_length) {
+        error!(%error, "The options of the proof are not accepted");
         return false;
     }
 
-    #[cfg(feature = "instruments")]
-    println!("- Started step 1: Recover challenges");
-    #[cfg(feature = "instruments")]
-    let timer1 = Instant::now();
+    verify::<F, A>(proof, pub_input, &proof.options)
+}
+
+/// Verifies a proof generated by `prove_interactive`. The challenge provider must
+/// give the same challenges as the one used by the prover.
+pub fn verify_interactive<F, A, P>(
+    proof: &StarkProof<F>,
+    pub_input: &A::PublicInputs,
+    proof_options: &ProofOptions,
+    challenge_provider: P,
+) -> bool
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion + Send + Sync,
+    P: ChallengeProvider,
+{
+    verify_with_transcript::<F, A, _>(
+        proof,
+        pub_input,
+        proof_options,
+        &mut PublicCoinTranscript::new(challenge_provider),
+    )
+}
+
+/// Partial verification of a proof, for light clients and protocols that defer
+/// some of the checks, like optimistic verification with a FRI challenge game.
+/// A proof passes `verify` exactly when it passes `verify_commitments`,
+/// `verify_deep_only` and `verify_fri_only`.
+///
+/// Checks the shape of the proof, the grinding and the openings of the trace and
+/// composition polynomials against their commitments. Neither the constraints of
+/// the AIR nor the degree of the DEEP composition polynomial are checked.
+pub fn verify_commitments<F, A>(
+    proof: &StarkProof<F>,
+    pub_input: &A::PublicInputs,
+    proof_options: &ProofOptions,
+) -> bool
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    let mut transcript = step_1_transcript_initialization();
+    let Some((air, _, challenges)) = check_proof_and_recover_challenges::<F, A, _>(
+        proof,
+        pub_input,
+        proof_options,
+        &mut transcript,
+    ) else {
+        return false;
+    };
+
+    verify_trace_and_composition_openings(&air, proof, &challenges)
+}
 
+/// Checks the shape of the proof, the grinding and that the out-of-domain
+/// evaluations of the trace and the composition polynomial satisfy the
+/// constraints of the AIR. The evaluations are not checked against the
+/// commitments, see `verify_commitments`.
+pub fn verify_deep_only<F, A>(
+    proof: &StarkProof<F>,
+    pub_input: &A::PublicInputs,
+    proof_options: &ProofOptions,
+) -> bool
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
     let mut transcript = step_1_transcript_initialization();
+    let Some((air, domain, challenges)) = check_proof_and_recover_challenges::<F, A, _>(
+        proof,
+        pub_input,
+        proof_options,
+        &mut transcript,
+    ) else {
+        return false;
+    };
+
+    step_2_verify_claimed_composition_polynomial(
+        &air,
+        proof,
+        &domain,
+        &challenges,
+        &mut VerifierContext::new(),
+    )
+}
+
+/// Checks the shape of the proof, the grinding and that the evaluations of the
+/// DEEP composition polynomial, reconstructed from the opened values, are close to
+/// a low degree polynomial. The opened values are not checked against the
+/// commitments, see `verify_commitments`.
+pub fn verify_fri_only<F, A>(
+    proof: &StarkProof<F>,
+    pub_input: &A::PublicInputs,
+    proof_options: &ProofOptions,
+) -> bool
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    let mut transcript = step_1_transcript_initialization();
+    let Some((air, domain, challenges)) = check_proof_and_recover_challenges::<F, A, _>(
+        proof,
+        pub_input,
+        proof_options,
+        &mut transcript,
+    ) else {
+        return false;
+    };
+
+    let deep_poly_evaluations =
+        reconstruct_deep_composition_poly_evaluations(&air, proof, &domain, &challenges);
+    step_4_verify_fri(
+        proof,
+        &domain,
+        &challenges,
+        &deep_poly_evaluations,
+        proof_options,
+    )
+}
+
+/// Checks the shape of the proof against the AIR and the proof options, replays
+/// the rounds of the prover to recover the challenges and verifies the grinding.
+/// Every other check of the verifier relies on it.
+fn check_proof_and_recover_challenges<F, A, T>(
+    proof: &StarkProof<F>,
+    pub_input: &A::PublicInputs,
+    proof_options: &ProofOptions,
+    transcript: &mut T,
+) -> Option<(A, Domain<F>, Challenges<F, A>)>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion + Send + Sync,
+    T: Transcript,
+{
+    let (air, domain, challenges) =
+        check_preamble_and_recover_challenges(proof, pub_input, proof_options, transcript)?;
+
+    // Verify there is one opening for each query
+    if proof.query_list.len() != challenges.iotas.len()
+        || proof.deep_poly_openings.len() != challenges.iotas.len()
+    {
+        error!("Number of openings does not match the number of queries");
+        return None;
+    }
+    if !proof
+        .deep_poly_openings
+        .iter()
+        .all(|opening| opening_has_shape_of_air(&air, opening))
+    {
+        return None;
+    }
+
+    Some((air, domain, challenges))
+}
+
+/// Checks everything `check_proof_and_recover_challenges` does except for the
+/// openings of the queries, which the proof may not have.
+fn check_preamble_and_recover_challenges<F, A, T>(
+    proof: &StarkProof<F>,
+    pub_input: &A::PublicInputs,
+    proof_options: &ProofOptions,
+    transcript: &mut T,
+) -> Option<(A, Domain<F>, Challenges<F, A>)>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion + Send + Sync,
+    T: Transcript,
+{
+    if let Err(error) = proof_options.validate() {
+        error!(%error, "Invalid proof options");
+        return None;
+    }
+
+    if !proof.options.same_proofs_as(proof_options) {
+        error!("The proof was generated with other options");
+        return None;
+    }
+
+    if proof.field_id != F::FIELD_ID {
+        error!(field_id = ?proof.field_id, "The proof is over another field");
+        return None;
+    }
+
+    let Some(lde_domain_size) =
+        lde_domain_size::<F>(proof.trace_length, proof_options.blowup_factor)
+    else {
+        error!(
+            trace_length = proof.trace_length,
+            "The field has no LDE domain for the trace length and the blowup factor"
+        );
+        return None;
+    };
+    if lde_domain_size > ProofLimits::MAX_LDE_DOMAIN_SIZE {
+        error!(
+            trace_length = proof.trace_length,
+            "The LDE domain is larger than the verifier accepts"
+        );
+        return None;
+    }
+    if let Err(error) = proof_options.validate_coset_offset::<F>(lde_domain_size) {
+        error!(%error, "Invalid coset offset");
+        return None;
+    }
+
+    if let Err(error) = A::check_configuration(pub_input) {
+        error!(%error, "The AIR doesn't support the configuration of the proof");
+        return None;
+    }
+
     let air = A::new(proof.trace_length, pub_input, proof_options);
     let domain = Domain::new(&air);
 
+    // Verify the number of FRI layers and the degree of the last one
+    if proof.fri_layers_merkle_caps.len() != proof_options.fri_number_of_layers(proof.trace_length)
+        || proof.fri_last_poly.len() != proof_options.fri_last_poly_length(proof.trace_length)
+    {
+        error!("FRI layers do not match the proof options");
+        return None;
+    }
+
+    // Verify the DEEP composition polynomial is masked exactly when the trace is randomized
+    if proof.composition_poly_mask_ood_evaluation.is_some() != proof_options.randomizes_trace() {
+        error!("Masking of the DEEP composition polynomial does not match the proof options");
+        return None;
+    }
+
+    // Verify H₁ and H₂ are opened at an extra point exactly when the AIR asks for it
+    if proof.composition_poly_extra_ood_evaluations.is_some()
+        != air.composition_poly_extra_opening().is_some()
+    {
+        error!("Extra openings of the composition polynomial do not match the AIR");
+        return None;
+    }
+
+    // Verify there is one trace commitment for each group of columns of the layout
+    if proof.lde_trace_merkle_caps.len() != trace_column_groups(&air).len() {
+        error!("Number of trace commitments does not match the commitment layout");
+        return None;
+    }
+
+    // Verify each cap has the nodes at the height of the options. The trees of the
+    // trace and the composition polynomial are over the LDE domain, and the one of
+    // each FRI layer over half the domain of the previous one.
+    let lde_tree_depth = domain.lde_root_order as usize;
+    let cap_len = |depth: usize| 1 << proof_options.merkle_cap_height.min(depth);
+    if proof
+        .lde_trace_merkle_caps
+        .iter()
+        .chain([&proof.composition_poly_cap])
+        .any(|cap| cap.len() != cap_len(lde_tree_depth))
+        || proof
+            .fri_layers_merkle_caps
+            .iter()
+            .enumerate()
+            .any(|(k, cap)| cap.len() != cap_len(lde_tree_depth.saturating_sub(k)))
+    {
+        error!("Merkle caps do not match the proof options");
+        return None;
+    }
+    let Some(roots) = proof.commitment_roots() else {
+        error!("Merkle caps do not hash to a root");
+        return None;
+    };
+
+    // Verify the out-of-domain frame has the shape of the AIR
+    if proof.trace_ood_frame_evaluations.num_columns() != air.committed_columns().len()
+        || proof.trace_ood_frame_evaluations.num_rows() != air.context().transition_offsets.len()
+    {
+        error!("Trace evaluations do not match the number of columns of the AIR");
+        return None;
+    }
+    if air.context().frame_row_of_offset(0).is_none() {
+        error!("The frame of the AIR has no row of offset zero to check the boundary constraints");
+        return None;
+    }
+    if !air.degree_bound_covers_constraints() {
+        error!("The degree bound of the composition polynomial is smaller than the constraints");
+        return None;
+    }
+    if (proof_options.blowup_factor as usize) < air.min_blowup_factor() {
+        error!(
+            min_blowup_factor = air.min_blowup_factor(),
+            "The blowup factor is too small for the degrees of the constraints"
+        );
+        return None;
+    }
+
     let challenges =
-        step_1_replay_rounds_and_recover_challenges(&air, proof, &domain, &mut transcript);
+        step_1_replay_rounds_and_recover_challenges(&air, proof, &roots, &domain, transcript);
+
+    // If the queries are deduplicated there can be less than `fri_number_of_queries`
+    // of them
+    if !proof_options.deduplicate_queries
+        && challenges.iotas.len() != proof_options.fri_number_of_queries
+    {
+        error!("Number of queries does not match the proof options");
+        return None;
+    }
 
     // verify grinding
-    let grinding_factor = air.context().proof_options.grinding_factor;
-    if challenges.leading_zeros_count < grinding_factor {
+    if !challenges.valid_nonce {
         error!("Grinding factor not satisfied");
-        return false;
+        return None;
     }
 
-    #[cfg(feature = "instruments")]
-    let elapsed1 = timer1.elapsed();
-    #[cfg(feature = "instruments")]
-    println!("  Time spent: {:?}", elapsed1);
-
-    #[cfg(feature = "instruments")]
-    println!("- Started step 2: Verify claimed polynomial");
-    #[cfg(feature = "instruments")]
-    let timer2 = Instant::now();
+    Some((air, domain, challenges))
+}
 
-    if !step_2_verify_claimed_composition_polynomial(&air, proof, &domain, &challenges) {
-        error!("Composition Polynomial verification failed");
+/// Checks the opening of a query has one Merkle proof for each trace commitment
+/// and one evaluation for each committed column of the AIR.
+fn opening_has_shape_of_air<F, A>(air: &A, opening: &DeepPolynomialOpenings<F>) -> bool
+where
+    F: IsFFTField,
+    A: AIR<Field = F>,
+{
+    if opening.lde_trace_merkle_proofs.len() != trace_column_groups(air).len() {
+        error!("Number of trace commitments does not match the commitment layout");
         return false;
     }
+    if opening.lde_trace_evaluations.len() != air.committed_columns().len() {
+        error!("Trace evaluations do not match the number of columns of the AIR");
+        return false;
+    }
+    true
+}
 
-    #[cfg(feature = "instruments")]
-    let elapsed2 = timer2.elapsed();
-    #[cfg(feature = "instruments")]
-    println!("  Time spent: {:?}", elapsed2);
-    #[cfg(feature = "instruments")]
+/// Same as `verify`, but continuing `transcript` instead of starting a new one. It
+/// must be in the state of the one given to `prove_with_transcript`, and it's left
+/// with the messages of the proof for the next steps of the larger protocol.
+pub fn verify_with_transcript<F, A, T>(
+    proof: &StarkProof<F>,
+    pub_input: &A::PublicInputs,
+    proof_options: &ProofOptions,
+    transcript: &mut T,
+) -> bool
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion + Send + Sync,
+    T: Transcript,
+{
+    verify_proof::<F, A, T>(
+        proof,
+        pub_input,
+        proof_options,
+        transcript,
+        &mut VerifierContext::new(),
+    )
+    .is_ok()
+}
 
-    println!("- Started step 3: Verify FRI");
-    #[cfg(feature = "instruments")]
-    let timer3 = Instant::now();
+/// Same as `verify`, but telling the step of the verification the proof failed.
+pub fn try_verify<F, A>(
+    proof: &StarkProof<F>,
+    pub_input: &A::PublicInputs,
+    proof_options: &ProofOptions,
+) -> Result<(), VerificationError>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    verify_proof::<F, A, _>(
+        proof,
+        pub_input,
+        proof_options,
+        &mut step_1_transcript_initialization(),
+        &mut VerifierContext::new(),
+    )
+}
+
+/// Verifies a proof generated by `prove_recursive_friendly` with the same options.
+pub fn verify_recursive_friendly<A>(
+    proof: &StarkProof<Stark252PrimeField>,
+    pub_input: &A::PublicInputs,
+    proof_options: &ProofOptions,
+) -> bool
+where
+    A: AIR<Field = Stark252PrimeField>,
+{
+    verify_with_transcript::<Stark252PrimeField, A, _>(
+        proof,
+        pub_input,
+        &proof_options.clone().with_poseidon_commitments(),
+        &mut PoseidonTranscript::new(),
+    )
+}
 
-    if !step_3_verify_fri(proof, &domain, &challenges) {
+/// Same as `verify`, but reusing the buffers of `context`. Verifiers of many proofs
+/// should keep a context for each thread, so they're allocated once.
+pub fn verify_with_context<F, A>(
+    proof: &StarkProof<F>,
+    pub_input: &A::PublicInputs,
+    proof_options: &ProofOptions,
+    context: &mut VerifierContext<F>,
+) -> bool
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion + Send + Sync,
+{
+    verify_proof::<F, A, _>(
+        proof,
+        pub_input,
+        proof_options,
+        &mut step_1_transcript_initialization(),
+        context,
+    )
+    .is_ok()
+}
+
+fn verify_proof<F, A, T>(
+    proof: &StarkProof<F>,
+    pub_input: &A::PublicInputs,
+    proof_options: &ProofOptions,
+    transcript: &mut T,
+    context: &mut VerifierContext<F>,
+) -> Result<(), VerificationError>
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion + Send + Sync,
+    T: Transcript,
+{
+    let _span = info_span!(
+        "verify",
+        trace_length = proof.trace_length,
+        blowup_factor = proof_options.blowup_factor,
+        num_queries = proof_options.fri_number_of_queries,
+    )
+    .entered();
+
+    let Some((air, domain, challenges)) =
+        in_timed_span(info_span!("step_1_recover_challenges"), || {
+            check_proof_and_recover_challenges::<F, A, T>(
+                proof,
+                pub_input,
+                proof_options,
+                transcript,
+            )
+        })
+    else {
+        return Err(VerificationError::InvalidShape);
+    };
+
+    let composition_poly_verified =
+        in_timed_span(info_span!("step_2_verify_composition_polynomial"), || {
+            step_2_verify_claimed_composition_polynomial(&air, proof, &domain, &challenges, context)
+        });
+    if !composition_poly_verified {
+        error!("Composition Polynomial verification failed");
+        return Err(VerificationError::CompositionPolynomial);
+    }
+
+    let deep_poly_evaluations = in_timed_span(
+        info_span!("step_3_verify_deep_composition_polynomial"),
+        || step_3_verify_deep_composition_polynomial(&air, proof, &domain, &challenges),
+    );
+    let Some(deep_poly_evaluations) = deep_poly_evaluations else {
+        error!("DEEP Composition Polynomial verification failed");
+        return Err(VerificationError::DeepCompositionPolynomial);
+    };
+
+    let fri_verified = in_timed_span(info_span!("step_4_verify_fri"), || {
+        step_4_verify_fri(
+            proof,
+            &domain,
+            &challenges,
+            &deep_poly_evaluations,
+            proof_options,
+        )
+    });
+    if !fri_verified {
         error!("FRI verification failed");
-        return false;
+        return Err(VerificationError::Fri);
     }
 
-    #[cfg(feature = "instruments")]
-    let elapsed3 = timer3.elapsed();
-    #[cfg(feature = "instruments")]
-    println!("  Time spent: {:?}", elapsed3);
+    Ok(())
+}
 
-    #[cfg(feature = "instruments")]
-    println!("- Started step 4: Verify deep composition polynomial");
-    #[cfg(feature = "instruments")]
-    let timer4 = Instant::now();
+/// Verifies a proof in the streamed format of `write_proof_stream`, reading the
+/// openings of one query at a time. Only the proof without its openings and the
+/// openings of a single query are held in memory, so this can verify proofs with
+/// many queries or large FRI decommitments in a bounded amount of memory.
+pub fn verify_stream<F, A, R>(
+    reader: R,
+    pub_input: &A::PublicInputs,
+    proof_options: &ProofOptions,
+) -> bool
+where
+    F: IsFFTField + IdentifiedField,
+    A: AIR<Field = F>,
+    FieldElement<F>: ByteConversion + Send + Sync,
+    R: Read,
+{
+    let mut reader = ProofStreamReader::new(reader);
+    let (mut proof, num_queries) = match reader.read_preamble::<F>() {
+        Ok(preamble) => preamble,
+        Err(error) => {
+            error!(%error, "Could not read the proof");
+            return false;
+        }
+    };
+
+    let _span = info_span!(
+        "verify_stream",
+        trace_length = proof.trace_length,
+        blowup_factor = proof_options.blowup_factor,
+        num_queries = proof_options.fri_number_of_queries,
+    )
+    .entered();
+
+    let Some((air, domain, mut challenges)) = check_preamble_and_recover_challenges::<F, A, _>(
+        &proof,
+        pub_input,
+        proof_options,
+        &mut step_1_transcript_initialization(),
+    ) else {
+        return false;
+    };
+
+    if !step_2_verify_claimed_composition_polynomial(
+        &air,
+        &proof,
+        &domain,
+        &challenges,
+        &mut VerifierContext::new(),
+    ) {
+        error!("Composition Polynomial verification failed");
+        return false;
+    }
 
-    #[allow(clippy::let_and_return)]
-    if !step_4_verify_deep_composition_polynomial(&air, proof, &domain, &challenges) {
-        error!("DEEP Composition Polynomial verification failed");
+    if num_queries != challenges.iotas.len() {
+        error!("Number of openings does not match the number of queries");
         return false;
     }
 
-    #[cfg(feature = "instruments")]
-    let elapsed4 = timer4.elapsed();
-    #[cfg(feature = "instruments")]
-    println!("  Time spent: {:?}", elapsed4);
+    // Verify each query as if it was the only one of the proof
+    for iota in std::mem::take(&mut challenges.iotas) {
+        let (fri_decommitment, deep_poly_openings) = match reader.read_query::<F>() {
+            Ok(query) => query,
+            Err(error) => {
+                error!(%error, "Could not read the openings of a query");
+                return false;
+            }
+        };
+        if !opening_has_shape_of_air(&air, &deep_poly_openings) {
+            return false;
+        }
+        proof.query_list = vec![fri_decommitment];
+        proof.deep_poly_openings = vec![deep_poly_openings];
+        challenges.iotas = vec![iota];
+
+        let Some(deep_poly_evaluations) =
+            step_3_verify_deep_composition_polynomial(&air, &proof, &domain, &challenges)
+        else {
+            error!("DEEP Composition Polynomial verification failed");
+            return false;
+        };
+        if !step_4_verify_fri(
+            &proof,
+            &domain,
+            &challenges,
+            &deep_poly_evaluations,
+            proof_options,
+        ) {
+            error!("FRI verification failed");
+            return false;
+        }
+    }
 
-    #[cfg(feature = "instruments")]
-    {
-        let total_time = elapsed1 + elapsed2 + elapsed3 + elapsed4;
-        println!(
-            " Fraction of verifying time per step: {:.4} {:.4} {:.4} {:.4}",
-            elapsed1.as_nanos() as f64 / total_time.as_nanos() as f64,
-            elapsed2.as_nanos() as f64 / total_time.as_nanos() as f64,
-            elapsed3.as_nanos() as f64 / total_time.as_nanos() as f64,
-            elapsed4.as_nanos() as f64 / total_time.as_nanos() as f64
-        );
+    if let Err(error) = reader.finish() {
+        error!(%error, "The proof stream continues after the last query");
+        return false;
     }
 
     true
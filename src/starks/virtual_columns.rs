@@ -0,0 +1,356 @@
+//! Virtual columns: columns of the main trace that are a linear combination of
+//! other columns of the same row, so they don't need to be committed.
+//!
+//! The prover leaves them out of the trace commitments, the out-of-domain frame and
+//! the openings of the queries, which all have the committed columns only, in the
+//! order of the AIR. As the combination is linear, it holds for the polynomials of
+//! the columns too, so both the prover, over the LDE of the trace, and the verifier,
+//! at the points of the out-of-domain frame, reconstruct the virtual columns from
+//! the committed ones before evaluating the constraints of the AIR.
+
+use std::{borrow::Cow, collections::BTreeSet};
+
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    polynomial::Polynomial,
+};
+
+use super::{frame::Frame, trace::TraceTable};
+
+/// A column of the main trace whose values are `constant + Σ cᵢ tᵢ`, with `tᵢ` the
+/// values of committed columns in the same row.
+#[derive(Clone, Debug)]
+pub struct VirtualColumn<F: IsFFTField> {
+    /// Column of the main trace
+    pub column: usize,
+    /// Committed columns with their coefficients cᵢ
+    pub terms: Vec<(usize, FieldElement<F>)>,
+    pub constant: FieldElement<F>,
+}
+
+impl<F: IsFFTField> VirtualColumn<F> {
+    /// The value of the column in `row`, which has all the columns of the AIR.
+    pub fn evaluate(&self, row: &[FieldElement<F>]) -> FieldElement<F> {
+        self.terms
+            .iter()
+            .fold(self.constant.clone(), |acc, (column, coefficient)| {
+                acc + coefficient * &row[*column]
+            })
+    }
+}
+
+/// The columns of an AIR the prover commits to: all of them but the virtual ones,
+/// in the same order. See `AIR::committed_columns`.
+#[derive(Clone, Debug)]
+pub struct CommittedColumns<F: IsFFTField> {
+    virtual_columns: Vec<VirtualColumn<F>>,
+    // Column of the AIR of each committed column
+    columns: Vec<usize>,
+    // Position among the committed columns of each column of the AIR, if committed
+    positions: Vec<Option<usize>>,
+}
+
+impl<F: IsFFTField> CommittedColumns<F> {
+    /// Panics if a virtual column isn't one of the `num_columns` columns of the AIR,
+    /// is defined twice, or has terms that aren't committed columns.
+    pub fn new(num_columns: usize, virtual_columns: Vec<VirtualColumn<F>>) -> Self {
+        let mut is_virtual = vec![false; num_columns];
+        for virtual_column in &virtual_columns {
+            assert!(
+                virtual_column.column < num_columns,
+                "virtual column {} outside of the trace",
+                virtual_column.column
+            );
+            assert!(
+                !is_virtual[virtual_column.column],
+                "virtual column {} defined twice",
+                virtual_column.column
+            );
+            is_virtual[virtual_column.column] = true;
+        }
+        for virtual_column in &virtual_columns {
+            assert!(
+                virtual_column
+                    .terms
+                    .iter()
+                    .all(|(column, _)| *column < num_columns && !is_virtual[*column]),
+                "virtual column {} must be a combination of committed columns",
+                virtual_column.column
+            );
+        }
+
+        let columns: Vec<usize> = (0..num_columns)
+            .filter(|column| !is_virtual[*column])
+            .collect();
+        let mut positions = vec![None; num_columns];
+        for (position, column) in columns.iter().enumerate() {
+            positions[*column] = Some(position);
+        }
+
+        Self {
+            virtual_columns,
+            columns,
+            positions,
+        }
+    }
+
+    /// Number of committed columns.
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Number of columns of the AIR, counting the virtual ones.
+    pub fn num_air_columns(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn has_virtual_columns(&self) -> bool {
+        !self.virtual_columns.is_empty()
+    }
+
+    /// Position among the committed columns of a column of the AIR, or `None` if
+    /// it's virtual.
+    pub fn position(&self, column: usize) -> Option<usize> {
+        self.positions.get(column).copied().flatten()
+    }
+
+    /// The committed columns of `row`, a row with all the columns of the AIR.
+    pub fn select_row(&self, row: &[FieldElement<F>]) -> Vec<FieldElement<F>> {
+        self.columns
+            .iter()
+            .map(|column| row[*column].clone())
+            .collect()
+    }
+
+    /// The committed columns of the main trace, which has the main columns of the
+    /// AIR. Borrowed if there are no virtual columns.
+    pub fn select_main_trace<'t>(&self, main_trace: &'t TraceTable<F>) -> Cow<'t, TraceTable<F>> {
+        if !self.has_virtual_columns() {
+            return Cow::Borrowed(main_trace);
+        }
+        let main_columns: Vec<usize> = self
+            .columns
+            .iter()
+            .copied()
+            .filter(|column| *column < main_trace.n_cols)
+            .collect();
+        Cow::Owned(main_trace.get_cols(&main_columns))
+    }
+
+    /// Sets the virtual columns of the main trace to the combination of the committed
+    /// ones, in every row, like the random ones appended to randomize the trace.
+    pub(crate) fn fill_virtual_columns(&self, main_trace: &mut TraceTable<F>) {
+        if !self.has_virtual_columns() {
+            return;
+        }
+        let n_cols = main_trace.n_cols;
+        for row in main_trace.table.chunks_mut(n_cols) {
+            for virtual_column in &self.virtual_columns {
+                row[virtual_column.column] = virtual_column.evaluate(row);
+            }
+        }
+    }
+
+    /// All the columns of the AIR from the committed ones, each one a vector of
+    /// values over the same points.
+    pub fn expand_columns(&self, columns: Vec<Vec<FieldElement<F>>>) -> Vec<Vec<FieldElement<F>>> {
+        if !self.has_virtual_columns() {
+            return columns;
+        }
+        let num_points = columns.first().map_or(0, Vec::len);
+        let mut committed_columns = columns.into_iter();
+        let mut expanded: Vec<_> = self
+            .positions
+            .iter()
+            .map(|position| match position {
+                Some(_) => committed_columns
+                    .next()
+                    .expect("a column for each position"),
+                None => Vec::new(),
+            })
+            .collect();
+
+        for virtual_column in &self.virtual_columns {
+            expanded[virtual_column.column] = (0..num_points)
+                .map(|point| {
+                    virtual_column.terms.iter().fold(
+                        virtual_column.constant.clone(),
+                        |acc, (column, coefficient)| acc + coefficient * &expanded[*column][point],
+                    )
+                })
+                .collect();
+        }
+        expanded
+    }
+
+    /// All the columns of the AIR from a row of the committed ones.
+    pub fn expand_row(&self, committed_row: &[FieldElement<F>]) -> Vec<FieldElement<F>> {
+        let mut row: Vec<_> = self
+            .positions
+            .iter()
+            .map(|position| match position {
+                Some(position) => committed_row[*position].clone(),
+                None => FieldElement::zero(),
+            })
+            .collect();
+        for virtual_column in &self.virtual_columns {
+            row[virtual_column.column] = virtual_column.evaluate(&row);
+        }
+        row
+    }
+
+    /// The frame of the AIR from a frame of the committed columns, like the
+    /// out-of-domain frame of a proof.
+    pub fn expand_frame<'a, 'f>(&self, frame: &'a Frame<'f, F>) -> Cow<'a, Frame<'f, F>> {
+        if !self.has_virtual_columns() {
+            return Cow::Borrowed(frame);
+        }
+        let data = (0..frame.num_rows())
+            .flat_map(|row| self.expand_row(frame.get_row(row)))
+            .collect();
+        Cow::Owned(Frame::new(data, self.num_air_columns()))
+    }
+
+    /// The polynomials of all the columns of the AIR from the ones of the committed
+    /// columns.
+    pub fn expand_polys(
+        &self,
+        polys: &[Polynomial<FieldElement<F>>],
+    ) -> Vec<Polynomial<FieldElement<F>>> {
+        let mut expanded: Vec<_> = self
+            .positions
+            .iter()
+            .map(|position| match position {
+                Some(position) => polys[*position].clone(),
+                None => Polynomial::zero(),
+            })
+            .collect();
+        for virtual_column in &self.virtual_columns {
+            expanded[virtual_column.column] = virtual_column.terms.iter().fold(
+                Polynomial::new_monomial(virtual_column.constant.clone(), 0),
+                |acc, (column, coefficient)| acc + &expanded[*column] * coefficient,
+            );
+        }
+        expanded
+    }
+
+    /// The cells of the committed columns holding `cells`, cells of the frame of
+    /// the AIR as `(row, column)` pairs. A cell of a virtual column is held by the
+    /// cells of its terms in the same row.
+    pub fn committed_cells(&self, cells: &BTreeSet<(usize, usize)>) -> BTreeSet<(usize, usize)> {
+        let mut committed_cells = BTreeSet::new();
+        for (row, column) in cells {
+            match self.position(*column) {
+                Some(position) => {
+                    committed_cells.insert((*row, position));
+                }
+                None => {
+                    let virtual_column = self
+                        .virtual_columns
+                        .iter()
+                        .find(|virtual_column| virtual_column.column == *column)
+                        .expect("columns are either committed or virtual");
+                    committed_cells.extend(virtual_column.terms.iter().map(|(term, _)| {
+                        (*row, self.position(*term).expect("terms are committed"))
+                    }));
+                }
+            }
+        }
+        committed_cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    // Column 1 is 3 t₀ + 2 t₂ + 1
+    fn committed_columns() -> CommittedColumns<F> {
+        CommittedColumns::new(
+            4,
+            vec![VirtualColumn {
+                column: 1,
+                terms: vec![(0, FE::from(3)), (2, FE::from(2))],
+                constant: FE::one(),
+            }],
+        )
+    }
+
+    #[test]
+    fn virtual_columns_are_reconstructed_from_the_committed_ones() {
+        let columns = committed_columns();
+        assert_eq!(columns.len(), 3);
+        assert_eq!(columns.position(1), None);
+        assert_eq!(columns.position(3), Some(2));
+
+        let row = columns.expand_row(&[FE::from(5), FE::from(7), FE::from(11)]);
+        assert_eq!(row, [5, 30, 7, 11].map(FE::from));
+        assert_eq!(columns.select_row(&row), [5, 7, 11].map(FE::from));
+
+        let expanded = columns.expand_columns(vec![
+            vec![FE::from(5), FE::one()],
+            vec![FE::from(7), FE::zero()],
+            vec![FE::from(11), FE::from(2)],
+        ]);
+        assert_eq!(expanded[1], vec![FE::from(30), FE::from(4)]);
+    }
+
+    #[test]
+    fn expanded_polynomials_interpolate_the_expanded_columns() {
+        let columns = committed_columns();
+        let trace = TraceTable::new_from_cols(&[
+            vec![FE::from(5), FE::one(), FE::from(4), FE::from(8)],
+            vec![FE::from(7), FE::zero(), FE::from(9), FE::from(3)],
+            vec![FE::from(11), FE::from(2), FE::from(6), FE::zero()],
+        ]);
+
+        let polys = columns.expand_polys(&trace.compute_trace_polys());
+        let expanded_trace = TraceTable::new_from_cols(&columns.expand_columns(trace.cols()));
+        let expected_polys = expanded_trace.compute_trace_polys();
+
+        let x = FE::from(13);
+        assert_eq!(polys.len(), expected_polys.len());
+        for (poly, expected_poly) in polys.iter().zip(&expected_polys) {
+            assert_eq!(poly.evaluate(&x), expected_poly.evaluate(&x));
+        }
+    }
+
+    #[test]
+    fn cells_of_virtual_columns_are_held_by_the_cells_of_their_terms() {
+        let cells = BTreeSet::from([(0, 1), (1, 3)]);
+
+        assert_eq!(
+            committed_columns().committed_cells(&cells),
+            BTreeSet::from([(0, 0), (0, 1), (1, 2)])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "combination of committed columns")]
+    fn virtual_columns_cant_depend_on_other_virtual_columns() {
+        CommittedColumns::<F>::new(
+            3,
+            vec![
+                VirtualColumn {
+                    column: 1,
+                    terms: vec![(0, FE::one())],
+                    constant: FE::zero(),
+                },
+                VirtualColumn {
+                    column: 2,
+                    terms: vec![(1, FE::one())],
+                    constant: FE::zero(),
+                },
+            ],
+        );
+    }
+}
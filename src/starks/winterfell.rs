@@ -0,0 +1,240 @@
+//! Adapter of the AIRs of this crate to the `Air` trait of Winterfell, so the same
+//! constraint system can be proven by both provers. Proving a trace that satisfies
+//! the constraints with one prover and not with the other points to a bug in one of
+//! them, and proving the same trace with both compares their performance.
+//!
+//! Only AIRs without auxiliary RAP columns are supported, with frames of two
+//! consecutive rows as Winterfell's, and the same number of exempted rows for all
+//! the transition constraints, see `WinterfellAir::try_new`.
+//!
+//! A field is adapted by implementing `WinterfellField`, which needs a field of
+//! lambdaworks with the modulus of a base field of Winterfell. The revision of
+//! lambdaworks the crate depends on has none, so no field implements it yet and the
+//! adapter isn't tested against Winterfell.
+
+use ::winterfell::{
+    math::{ExtensibleField, FieldElement as WinterfellFieldElement, StarkField, ToElements},
+    Air, AirContext as WinterfellAirContext, Assertion, EvaluationFrame, FieldExtension,
+    ProofOptions as WinterfellProofOptions, TraceInfo, TraceTable as WinterfellTraceTable,
+    TransitionConstraintDegree,
+};
+use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
+use thiserror::Error;
+
+use super::{
+    frame::Frame,
+    proof::{
+        errors::InsecureOptionError,
+        options::{GrindingConfig, ProofOptions},
+    },
+    trace::TraceTable,
+    traits::AIR,
+};
+
+/// Coset offset of the options given to the adapted AIRs. Winterfell builds its
+/// own LDE domain, so it doesn't change the constraints.
+const COSET_OFFSET: u64 = 3;
+
+/// A field of this crate that is also a base field of Winterfell. Both libraries
+/// must agree on the modulus, so the conversions are bijections.
+pub trait WinterfellField: IsFFTField {
+    type BaseField: StarkField + ExtensibleField<2> + ExtensibleField<3>;
+
+    fn to_winterfell(value: &FieldElement<Self>) -> Self::BaseField;
+
+    fn from_winterfell(value: Self::BaseField) -> FieldElement<Self>;
+}
+
+/// Public inputs of an adapted AIR. Winterfell seeds its transcript with
+/// `elements`, so they should determine `inputs`.
+pub struct WinterfellPublicInputs<A: AIR> {
+    pub inputs: A::PublicInputs,
+    pub elements: Vec<FieldElement<A::Field>>,
+}
+
+impl<A> ToElements<<A::Field as WinterfellField>::BaseField> for WinterfellPublicInputs<A>
+where
+    A: AIR,
+    A::Field: WinterfellField,
+{
+    fn to_elements(&self) -> Vec<<A::Field as WinterfellField>::BaseField> {
+        self.elements.iter().map(A::Field::to_winterfell).collect()
+    }
+}
+
+/// Why an AIR can't be adapted to Winterfell.
+#[derive(Debug, Error)]
+pub enum UnsupportedAirError {
+    #[error("AIRs with auxiliary RAP columns can't be adapted to Winterfell")]
+    AuxiliaryColumns,
+    #[error("Winterfell frames are two consecutive rows")]
+    TransitionOffsets,
+    #[error("Winterfell exempts the same rows of all the transition constraints")]
+    TransitionExemptions,
+    #[error("Winterfell only exempts the last rows of the trace")]
+    ExemptionZones,
+    #[error("The Winterfell options have no equivalent: {0}")]
+    ProofOptions(#[from] InsecureOptionError),
+}
+
+/// An AIR of this crate seen as a Winterfell AIR.
+pub struct WinterfellAir<A>
+where
+    A: AIR,
+    A::Field: WinterfellField,
+{
+    air: A,
+    context: WinterfellAirContext<<A::Field as WinterfellField>::BaseField>,
+}
+
+impl<A> WinterfellAir<A>
+where
+    A: AIR<RAPChallenges = ()>,
+    A::Field: WinterfellField,
+{
+    pub fn inner(&self) -> &A {
+        &self.air
+    }
+
+    /// Adapts the AIR of a trace, or tells why it can't be. Winterfell builds the
+    /// AIRs with `Air::new`, which panics on these errors, so provers and verifiers
+    /// should check the AIR with this first.
+    pub fn try_new(
+        trace_info: TraceInfo,
+        pub_inputs: WinterfellPublicInputs<A>,
+        options: WinterfellProofOptions,
+    ) -> Result<Self, UnsupportedAirError> {
+        let proof_options = ProofOptions::new(
+            options.blowup_factor() as u8,
+            options.num_queries(),
+            COSET_OFFSET,
+            match options.grinding_factor() {
+                0 => GrindingConfig::Disabled,
+                bits => GrindingConfig::Bits(bits as u8),
+            },
+        )?;
+        let air = A::new(trace_info.length(), &pub_inputs.inputs, &proof_options);
+
+        let air_context = air.context();
+        if air.number_auxiliary_rap_columns() != 0 {
+            return Err(UnsupportedAirError::AuxiliaryColumns);
+        }
+        if air_context.transition_offsets != [0, 1] {
+            return Err(UnsupportedAirError::TransitionOffsets);
+        }
+        let num_exemptions = air_context.transition_exemptions.first().copied();
+        if !air_context
+            .transition_exemptions
+            .iter()
+            .all(|exemptions| Some(*exemptions) == num_exemptions)
+        {
+            return Err(UnsupportedAirError::TransitionExemptions);
+        }
+        if !air_context.transition_exemption_zones.is_empty() {
+            return Err(UnsupportedAirError::ExemptionZones);
+        }
+
+        let degrees = air_context
+            .transition_degrees()
+            .iter()
+            .map(|degree| TransitionConstraintDegree::new(*degree))
+            .collect();
+        let num_assertions = air.boundary_constraints(&()).constraints.len();
+        let mut context = WinterfellAirContext::new(trace_info, degrees, num_assertions, options);
+        if let Some(num_exemptions) = num_exemptions {
+            context = context.set_num_transition_exemptions(num_exemptions);
+        }
+
+        Ok(Self { air, context })
+    }
+}
+
+impl<A> Air for WinterfellAir<A>
+where
+    A: AIR<RAPChallenges = ()> + Send + Sync,
+    A::Field: WinterfellField,
+{
+    type BaseField = <A::Field as WinterfellField>::BaseField;
+    type PublicInputs = WinterfellPublicInputs<A>;
+
+    /// Panics if the AIR can't be adapted, see `try_new`.
+    fn new(
+        trace_info: TraceInfo,
+        pub_inputs: Self::PublicInputs,
+        options: WinterfellProofOptions,
+    ) -> Self {
+        Self::try_new(trace_info, pub_inputs, options).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    fn context(&self) -> &WinterfellAirContext<Self::BaseField> {
+        &self.context
+    }
+
+    fn evaluate_transition<E: WinterfellFieldElement<BaseField = Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        _periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let row_width = frame.current().len();
+        let data: Vec<_> = [frame.current(), frame.next()]
+            .into_iter()
+            .flat_map(|row| {
+                let row = E::as_base_elements(row);
+                assert_eq!(
+                    row.len(),
+                    row_width,
+                    "Only Winterfell proofs without field extension are supported"
+                );
+                row.iter().map(|value| A::Field::from_winterfell(*value))
+            })
+            .collect();
+
+        let evaluations = self
+            .air
+            .compute_transition(&Frame::new(data, row_width), &());
+        for (result, evaluation) in result.iter_mut().zip(&evaluations) {
+            *result = E::from(A::Field::to_winterfell(evaluation));
+        }
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        self.air
+            .boundary_constraints(&())
+            .constraints
+            .iter()
+            .map(|constraint| {
+                Assertion::single(
+                    constraint.col,
+                    constraint.step,
+                    A::Field::to_winterfell(&constraint.value),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Winterfell options equivalent to `proof_options`, without field extension and
+/// folding FRI layers by two until a constant as the prover of this crate does.
+pub fn winterfell_proof_options(proof_options: &ProofOptions) -> WinterfellProofOptions {
+    WinterfellProofOptions::new(
+        proof_options.fri_number_of_queries,
+        proof_options.blowup_factor as usize,
+        proof_options.grinding.bits() as u32,
+        FieldExtension::None,
+        2,
+        0,
+    )
+}
+
+/// Copies a trace of this crate to a Winterfell trace.
+pub fn to_winterfell_trace<F: WinterfellField>(
+    trace: &TraceTable<F>,
+) -> WinterfellTraceTable<F::BaseField> {
+    let columns = trace
+        .cols()
+        .iter()
+        .map(|column| column.iter().map(F::to_winterfell).collect())
+        .collect();
+    WinterfellTraceTable::init(columns)
+}
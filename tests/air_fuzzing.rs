@@ -0,0 +1,344 @@
+//! Property tests run on every AIR of the crate: honest proofs of random traces
+//! must verify, and changing a single cell of a trace must make either the prover
+//! return an error or the verifier fail. A panic fails the tests. The number of
+//! cases is kept small so they can run in CI.
+//! Run more of them with:
+//!
+//!     AIR_FUZZ_CASES=64 cargo test --release --test air_fuzzing
+
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField as F;
+use lambdaworks_stark::{
+    cairo::{
+        air::{
+            generate_cairo_proof, verify_cairo_proof, CairoAIR, FRAME_AP, FRAME_DST_ADDR,
+            FRAME_OP0_ADDR, FRAME_OP1_ADDR, FRAME_PC,
+        },
+        runner::run::{cairo0_program_path, generate_prover_args, CairoVersion},
+    },
+    starks::{
+        example::{
+            dummy_air::{self, DummyAIR},
+            fibonacci_2_columns::{self, Fibonacci2ColsAIR},
+            fibonacci_multi_rap::FibonacciMultiRAP,
+            fibonacci_rap::{fibonacci_rap_trace, FibonacciRAP, FibonacciRAPPublicInputs},
+            fibonacci_rap_extension::FibonacciRAPExtension,
+            hash_chain_vdf::{hash_chain_vdf_trace, HashChainVdfAIR, HashChainVdfPublicInputs},
+            poseidon_merkle_path::{
+                poseidon_merkle_path_trace, PoseidonMerklePathAIR, PoseidonMerklePathPublicInputs,
+            },
+            quadratic_air::{self, QuadraticAIR, QuadraticPublicInputs},
+            simple_fibonacci::{self, FibonacciAIR, FibonacciPublicInputs},
+            stitched_fibonacci::{
+                stitched_fibonacci_trace, StitchedFibonacciAIR, StitchedFibonacciPublicInputs,
+            },
+            strided_counter::{self, StridedCounterAIR, StridedCounterPublicInputs},
+        },
+        gadgets::poseidon::PoseidonParameters,
+        proof::{options::ProofOptions, stark::StarkProof},
+        prover::{prove, ProvingError},
+        trace::TraceTable,
+        traits::AIR,
+        verifier::verify,
+    },
+    FE,
+};
+use proptest::{
+    prelude::*,
+    sample::{select, Index},
+    test_runner::{Config, TestCaseError, TestRunner},
+};
+
+type ProveFn<A> = fn(
+    &TraceTable<F>,
+    &<A as AIR>::PublicInputs,
+    &ProofOptions,
+) -> Result<StarkProof<F>, ProvingError>;
+type VerifyFn<A> = fn(&StarkProof<F>, &<A as AIR>::PublicInputs, &ProofOptions) -> bool;
+
+/// An AIR under test, with a generator of honest traces and the cells of the
+/// trace that every execution constrains.
+struct AirFuzzer<A: AIR<Field = F>> {
+    /// Builds an honest trace and its public inputs from a seed, which also picks
+    /// the length of the trace.
+    honest_trace: fn(u64) -> (TraceTable<F>, A::PublicInputs),
+    /// Columns whose cells are all constrained.
+    perturbable_columns: Vec<usize>,
+    /// Number of leading rows of the trace whose cells are all constrained.
+    perturbable_rows: fn(&TraceTable<F>, &A::PublicInputs) -> usize,
+    prove: ProveFn<A>,
+    verify: VerifyFn<A>,
+}
+
+impl<A> AirFuzzer<A>
+where
+    A: AIR<Field = F> + Send + Sync,
+    A::RAPChallenges: Send + Sync,
+{
+    fn new(honest_trace: fn(u64) -> (TraceTable<F>, A::PublicInputs)) -> Self {
+        Self {
+            honest_trace,
+            perturbable_columns: (0..honest_trace(0).0.n_cols).collect(),
+            perturbable_rows: |trace, _| trace.n_rows(),
+            prove: prove::<F, A>,
+            verify: verify::<F, A>,
+        }
+    }
+
+    fn run(&self, cases: u32) {
+        self.honest_proofs_verify(cases);
+        self.perturbed_traces_are_rejected(cases);
+    }
+
+    fn honest_proofs_verify(&self, cases: u32) {
+        runner(cases)
+            .run(
+                &(any::<u64>(), some_proof_options()),
+                |(seed, proof_options)| {
+                    let (trace, pub_inputs) = (self.honest_trace)(seed);
+                    let proof = (self.prove)(&trace, &pub_inputs, &proof_options)
+                        .map_err(|error| TestCaseError::fail(format!("{error:?}")))?;
+                    prop_assert!((self.verify)(&proof, &pub_inputs, &proof_options));
+                    Ok(())
+                },
+            )
+            .unwrap();
+    }
+
+    fn perturbed_traces_are_rejected(&self, cases: u32) {
+        let cells = (any::<Index>(), select(self.perturbable_columns.clone()));
+        runner(cases)
+            .run(
+                &(
+                    any::<u64>(),
+                    some_proof_options(),
+                    cells,
+                    1..u64::from(u16::MAX),
+                ),
+                |(seed, proof_options, (row, col), delta)| {
+                    let (trace, pub_inputs) = (self.honest_trace)(seed);
+                    let row = row.index((self.perturbable_rows)(&trace, &pub_inputs));
+
+                    let mut columns = trace.cols();
+                    columns[col][row] = columns[col][row] + FE::from(delta);
+                    let perturbed = TraceTable::new_from_cols(&columns)
+                        .with_rows_per_step(trace.rows_per_step());
+
+                    let rejected = match (self.prove)(&perturbed, &pub_inputs, &proof_options) {
+                        Ok(proof) => !(self.verify)(&proof, &pub_inputs, &proof_options),
+                        Err(_) => true,
+                    };
+                    prop_assert!(rejected, "changing the cell ({row}, {col}) was not caught");
+                    Ok(())
+                },
+            )
+            .unwrap();
+    }
+}
+
+fn runner(default_cases: u32) -> TestRunner {
+    let cases = std::env::var("AIR_FUZZ_CASES")
+        .ok()
+        .and_then(|cases| cases.parse().ok())
+        .unwrap_or(default_cases);
+    TestRunner::new(Config {
+        cases,
+        ..Config::default()
+    })
+}
+
+prop_compose! {
+    fn some_proof_options()(
+        blowup_factor in select(vec![4_u8, 8]),
+        fri_number_of_queries in 1_usize..5,
+        deduplicate_queries in any::<bool>(),
+        salt_commitments in any::<bool>(),
+        merkle_cap_height in 0_usize..4,
+    ) -> ProofOptions {
+        ProofOptions {
+            blowup_factor,
+            fri_number_of_queries,
+            deduplicate_queries,
+            salt_commitments,
+            merkle_cap_height,
+            ..ProofOptions::default_test_options()
+        }
+    }
+}
+
+/// Length of the trace of a seed, from 8 to 32 rows.
+fn trace_length(seed: u64) -> usize {
+    8 << (seed % 3)
+}
+
+fn initial_values(seed: u64) -> [FE; 2] {
+    [FE::from(seed), FE::from(seed.rotate_left(32))]
+}
+
+fn fibonacci_trace(seed: u64) -> (TraceTable<F>, FibonacciPublicInputs<F>) {
+    let [a0, a1] = initial_values(seed);
+    let trace = simple_fibonacci::fibonacci_trace([a0, a1], trace_length(seed));
+    (trace, FibonacciPublicInputs { a0, a1 })
+}
+
+fn fibonacci_2_columns_trace(seed: u64) -> (TraceTable<F>, FibonacciPublicInputs<F>) {
+    let [a0, a1] = initial_values(seed);
+    let trace = fibonacci_2_columns::fibonacci_trace_2_columns([a0, a1], trace_length(seed));
+    (trace, FibonacciPublicInputs { a0, a1 })
+}
+
+fn fibonacci_rap_trace_of_seed(seed: u64) -> (TraceTable<F>, FibonacciRAPPublicInputs<F>) {
+    // The boundary constraints of the RAPs fix the initial values to one, and their
+    // traces are padded to a power of two
+    let [a0, a1] = [FE::one(), FE::one()];
+    let steps = 8 + (seed % 17) as usize;
+    let trace = fibonacci_rap_trace([a0, a1], steps);
+    (trace, FibonacciRAPPublicInputs { steps, a0, a1 })
+}
+
+fn quadratic_trace(seed: u64) -> (TraceTable<F>, QuadraticPublicInputs<F>) {
+    let a0 = FE::from(seed);
+    let trace = quadratic_air::quadratic_trace(a0, trace_length(seed));
+    (trace, QuadraticPublicInputs { a0 })
+}
+
+fn strided_counter_trace(seed: u64) -> (TraceTable<F>, StridedCounterPublicInputs<F>) {
+    let [a0, a1] = initial_values(seed);
+    let trace = strided_counter::strided_counter_trace([a0, a1], trace_length(seed));
+    (
+        trace,
+        StridedCounterPublicInputs {
+            a0,
+            a1,
+            extra_opening: None,
+        },
+    )
+}
+
+fn poseidon_merkle_path_trace_of_seed(
+    seed: u64,
+) -> (TraceTable<F>, PoseidonMerklePathPublicInputs) {
+    let depth = 1 + (seed % 3) as usize;
+    let siblings: Vec<_> = (0..depth as u32)
+        .map(|level| FE::from(seed.rotate_left(8 * level)))
+        .collect();
+    poseidon_merkle_path_trace(FE::from(seed), seed as usize, &siblings)
+}
+
+fn stitched_fibonacci_trace_of_seed(
+    seed: u64,
+) -> (TraceTable<F>, StitchedFibonacciPublicInputs<F>) {
+    let [a0, a1] = initial_values(seed);
+    let trace = stitched_fibonacci_trace([a0, a1], trace_length(seed));
+    (trace, StitchedFibonacciPublicInputs { a0, a1 })
+}
+
+fn hash_chain_vdf_trace_of_seed(seed: u64) -> (TraceTable<F>, HashChainVdfPublicInputs) {
+    hash_chain_vdf_trace(FE::from(seed), 1 + (seed % 2) as usize)
+}
+
+fn dummy_trace(seed: u64) -> (TraceTable<F>, ()) {
+    (dummy_air::dummy_trace(trace_length(seed)), ())
+}
+
+const CAIRO_PROGRAMS: [&str; 3] = [
+    "fibonacci_5.json",
+    "simple_program.json",
+    "output_program.json",
+];
+
+fn cairo_trace(seed: u64) -> (TraceTable<F>, <CairoAIR as AIR>::PublicInputs) {
+    let program = CAIRO_PROGRAMS[(seed % CAIRO_PROGRAMS.len() as u64) as usize];
+    let program_content = std::fs::read(cairo0_program_path(program)).unwrap();
+    generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap()
+}
+
+#[test]
+fn fuzz_fibonacci_air() {
+    AirFuzzer::<FibonacciAIR<F>>::new(fibonacci_trace).run(4);
+}
+
+#[test]
+fn fuzz_fibonacci_2_columns_air() {
+    AirFuzzer::<Fibonacci2ColsAIR<F>>::new(fibonacci_2_columns_trace).run(4);
+}
+
+#[test]
+fn fuzz_fibonacci_rap() {
+    AirFuzzer::<FibonacciRAP<F>>::new(fibonacci_rap_trace_of_seed).run(4);
+}
+
+#[test]
+fn fuzz_fibonacci_multi_rap() {
+    AirFuzzer::<FibonacciMultiRAP<F>>::new(fibonacci_rap_trace_of_seed).run(4);
+}
+
+#[test]
+fn fuzz_fibonacci_rap_extension() {
+    AirFuzzer::<FibonacciRAPExtension<F>>::new(fibonacci_rap_trace_of_seed).run(4);
+}
+
+#[test]
+fn fuzz_quadratic_air() {
+    AirFuzzer::<QuadraticAIR<F>>::new(quadratic_trace).run(4);
+}
+
+#[test]
+fn fuzz_strided_counter_air() {
+    AirFuzzer::<StridedCounterAIR<F>>::new(strided_counter_trace).run(4);
+}
+
+#[test]
+fn fuzz_poseidon_merkle_path_air() {
+    AirFuzzer {
+        // The levels of the path. The ones after it, which fill the trace, aren't
+        // checked.
+        perturbable_rows: |_, pub_inputs| {
+            pub_inputs.depth * (PoseidonParameters::starknet().n_rounds() + 1)
+        },
+        ..AirFuzzer::<PoseidonMerklePathAIR>::new(poseidon_merkle_path_trace_of_seed)
+    }
+    .run(2);
+}
+
+#[test]
+fn fuzz_stitched_fibonacci_air() {
+    AirFuzzer::<StitchedFibonacciAIR<F>>::new(stitched_fibonacci_trace_of_seed).run(4);
+}
+
+#[test]
+fn fuzz_hash_chain_vdf_air() {
+    AirFuzzer {
+        // The iterations of the chain. The rows after it, which fill the trace,
+        // aren't checked.
+        perturbable_rows: |_, pub_inputs| {
+            pub_inputs.iterations * (PoseidonParameters::starknet().n_rounds() + 1)
+        },
+        ..AirFuzzer::<HashChainVdfAIR>::new(hash_chain_vdf_trace_of_seed)
+    }
+    .run(2);
+}
+
+#[test]
+fn fuzz_dummy_air() {
+    AirFuzzer::<DummyAIR>::new(dummy_trace).run(4);
+}
+
+#[test]
+fn fuzz_cairo_air() {
+    AirFuzzer {
+        // The registers and addresses of the steps of the execution. The padding
+        // rows after them repeat the last step.
+        perturbable_columns: vec![
+            FRAME_PC,
+            FRAME_AP,
+            FRAME_DST_ADDR,
+            FRAME_OP0_ADDR,
+            FRAME_OP1_ADDR,
+        ],
+        perturbable_rows: |_, pub_inputs| pub_inputs.num_steps,
+        prove: generate_cairo_proof,
+        verify: verify_cairo_proof,
+        ..AirFuzzer::<CairoAIR>::new(cairo_trace)
+    }
+    .run(2);
+}
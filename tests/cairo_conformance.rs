@@ -0,0 +1,440 @@
+//! Conformance vectors of the CPU constraints of the Cairo AIR against the semantics
+//! of the Cairo whitepaper, section 4.5. Each vector is a single hand-encoded
+//! instruction with the memory it reads and the registers before and after running
+//! it, worked out by hand from the whitepaper instead of by the runner. The trace of
+//! the step must satisfy the CPU constraints of `compute_transition`, and changing any
+//! of the cells the instruction constrains must violate one of them.
+//!
+//! Unlike the bundled programs, the vectors cover every opcode and flag group on their
+//! own, so a regression in a single constraint points to the instruction that breaks.
+
+use std::collections::HashMap;
+
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField as F;
+use lambdaworks_stark::{
+    cairo::{
+        air::{
+            CairoAIR, CairoRAPChallenges, MemoryPadding, MemorySegmentMap, PublicInputs, FRAME_AP,
+            FRAME_DST, FRAME_DST_ADDR, FRAME_FP, FRAME_INST, FRAME_MUL, FRAME_OP0, FRAME_OP0_ADDR,
+            FRAME_OP1, FRAME_OP1_ADDR, FRAME_PC, FRAME_RES, FRAME_T0, FRAME_T1, OFF_DST, OFF_OP0,
+            OFF_OP1,
+        },
+        cairo_mem::CairoMemory,
+        constraints::ConstraintId::{self, *},
+        execution_trace::build_cairo_execution_trace,
+        register_states::{RegisterStates, RegistersState},
+    },
+    starks::{frame::Frame, proof::options::ProofOptions, trace::TraceTable, traits::AIR},
+    FE,
+};
+
+// Bits of the flags of an instruction, see section 4.5 of the Cairo whitepaper.
+const DST_FP: u64 = 1 << 0;
+const OP0_FP: u64 = 1 << 1;
+const OP1_IMM: u64 = 1 << 2;
+const OP1_FP: u64 = 1 << 3;
+const RES_ADD: u64 = 1 << 5;
+const RES_MUL: u64 = 1 << 6;
+const PC_JUMP_ABS: u64 = 1 << 7;
+const PC_JUMP_REL: u64 = 1 << 8;
+const PC_JNZ: u64 = 1 << 9;
+const AP_ADD: u64 = 1 << 10;
+const AP_ADD1: u64 = 1 << 11;
+const OPC_CALL: u64 = 1 << 12;
+const OPC_RET: u64 = 1 << 13;
+const OPC_AEQ: u64 = 1 << 14;
+
+/// Address of the instruction of every vector.
+const PC: u64 = 1;
+
+fn encode_instruction(off_dst: i16, off_op0: i16, off_op1: i16, flags: u64) -> u64 {
+    let biased = |offset: i16| (i32::from(offset) + (1 << 15)) as u64;
+    biased(off_dst) | biased(off_op0) << 16 | biased(off_op1) << 32 | flags << 48
+}
+
+/// `jmp rel 0`, the instruction run after the one of the vector.
+fn halt() -> [u64; 2] {
+    [
+        encode_instruction(-1, -1, 1, DST_FP | OP0_FP | OP1_IMM | PC_JUMP_REL),
+        0,
+    ]
+}
+
+/// A single step of a Cairo execution.
+struct Vector {
+    name: &'static str,
+    /// The instruction at `PC`, followed by its immediate if it has one.
+    instruction: Vec<u64>,
+    /// Memory read or written by the instruction.
+    memory: Vec<(u64, u64)>,
+    /// `(pc, ap, fp)` before and after the instruction.
+    registers: [(u64, u64, u64); 2],
+    /// Cells of the step, besides the ones every instruction constrains, and the
+    /// constraints one of which must be violated when they change.
+    bound_cells: Vec<(usize, usize, Vec<ConstraintId>)>,
+}
+
+impl Vector {
+    /// The main trace of the step, followed by the one of the halting instruction.
+    fn trace(&self) -> TraceTable<F> {
+        let [(pc, ap, fp), (next_pc, next_ap, next_fp)] = self.registers;
+        let mut memory: HashMap<u64, u64> = self.memory.iter().copied().collect();
+        for (i, word) in self.instruction.iter().enumerate() {
+            memory.insert(PC + i as u64, *word);
+        }
+        for (i, word) in halt().into_iter().enumerate() {
+            memory.insert(next_pc + i as u64, word);
+        }
+        // The halting instruction reads [fp - 1]
+        memory.entry(next_fp - 1).or_insert(0);
+
+        let register_states = RegisterStates {
+            rows: vec![
+                RegistersState { pc, ap, fp },
+                RegistersState {
+                    pc: next_pc,
+                    ap: next_ap,
+                    fp: next_fp,
+                },
+            ],
+        };
+        let memory = CairoMemory::new(
+            memory
+                .into_iter()
+                .map(|(address, value)| (address, FE::from(value)))
+                .collect(),
+        );
+        build_cairo_execution_trace(&register_states, &memory, &self.public_inputs())
+    }
+
+    fn public_inputs(&self) -> PublicInputs {
+        let [(pc, ap, fp), (next_pc, next_ap, _)] = self.registers;
+        PublicInputs {
+            pc_init: FE::from(pc),
+            ap_init: FE::from(ap),
+            fp_init: FE::from(fp),
+            pc_final: FE::from(next_pc),
+            ap_final: FE::from(next_ap),
+            range_check_min: Some(0),
+            range_check_max: Some(u16::MAX),
+            memory_address_max: Some(u64::MAX),
+            memory_segments: MemorySegmentMap::new(),
+            public_memory: HashMap::new(),
+            num_steps: 2,
+            memory_padding: MemoryPadding::default(),
+            num_memory_holes: 0,
+            builtins: Vec::new(),
+            memory_commitment: None,
+        }
+    }
+
+    /// Evaluations of the CPU constraints on the transition from the step of the
+    /// vector to the next one, with the cell at `(row, column)` of the main trace
+    /// increased by one if given.
+    fn cpu_constraints(&self, perturbed_cell: Option<(usize, usize)>) -> Vec<FE> {
+        let pub_inputs = self.public_inputs();
+        let air = CairoAIR::new(2, &pub_inputs, &ProofOptions::default_test_options());
+        let trace = self.trace();
+
+        // The auxiliary columns are left as zeros, as only the CPU constraints are read.
+        let mut rows: Vec<Vec<FE>> = (0..2)
+            .map(|row| {
+                let mut row = trace.get_row(row).to_vec();
+                row.resize(air.context().trace_columns, FE::zero());
+                row
+            })
+            .collect();
+        if let Some((row, column)) = perturbed_cell {
+            rows[row][column] = rows[row][column] + FE::one();
+        }
+        let frame = Frame::new(rows.concat(), air.context().trace_columns);
+        let challenges = CairoRAPChallenges {
+            alpha_memory: FE::from(3),
+            z_memory: FE::from(5),
+            z_range_check: FE::from(7),
+        };
+
+        let mut evaluations = air.compute_transition(&frame, &challenges);
+        evaluations.truncate(AssertEq.index() + 1);
+        evaluations
+    }
+
+    fn check(&self) {
+        let violated = |evaluations: &[FE]| -> Vec<&'static str> {
+            ConstraintId::ALL
+                .iter()
+                .filter(|id| {
+                    evaluations
+                        .get(id.index())
+                        .map_or(false, |e| e != &FE::zero())
+                })
+                .map(|id| id.name())
+                .collect()
+        };
+
+        let honest = self.cpu_constraints(None);
+        assert!(
+            violated(&honest).is_empty(),
+            "{}: the honest step violates {:?}",
+            self.name,
+            violated(&honest)
+        );
+
+        for (row, column, constraints) in always_bound_cells().into_iter().chain(
+            self.bound_cells
+                .iter()
+                .map(|(row, column, constraints)| (*row, *column, constraints.clone())),
+        ) {
+            let evaluations = self.cpu_constraints(Some((row, column)));
+            assert!(
+                constraints
+                    .iter()
+                    .any(|id| evaluations[id.index()] != FE::zero()),
+                "{}: changing the cell ({row}, {column}) violates {:?} instead of one of {:?}",
+                self.name,
+                violated(&evaluations),
+                constraints.iter().map(|id| id.name()).collect::<Vec<_>>()
+            );
+        }
+    }
+}
+
+/// Cells every instruction constrains, whatever its flags, with the constraints
+/// one of which must be violated when they change.
+fn always_bound_cells() -> Vec<(usize, usize, Vec<ConstraintId>)> {
+    let mut cells: Vec<_> = (0..16)
+        .chain([OFF_DST, OFF_OP0, OFF_OP1, FRAME_INST])
+        .map(|column| (0, column, vec![Inst]))
+        .collect();
+    cells.extend([
+        (0, FRAME_DST_ADDR, vec![DstAddr]),
+        (0, FRAME_OP0_ADDR, vec![Op0Addr]),
+        (0, FRAME_OP1_ADDR, vec![Op1Addr]),
+        (0, FRAME_OP0, vec![Mul1]),
+        (0, FRAME_OP1, vec![Mul1]),
+        (0, FRAME_MUL, vec![Mul1]),
+        (0, FRAME_T0, vec![T0]),
+        (0, FRAME_T1, vec![T1]),
+        (0, FRAME_AP, vec![NextAp]),
+        (1, FRAME_AP, vec![NextAp]),
+        (1, FRAME_FP, vec![NextFp]),
+        (1, FRAME_PC, vec![NextPc1, NextPc2]),
+    ]);
+    cells
+}
+
+#[test]
+fn call_rel_with_immediate() {
+    // call rel 5
+    Vector {
+        name: "call",
+        instruction: vec![
+            encode_instruction(0, 1, 1, OP1_IMM | PC_JUMP_REL | OPC_CALL),
+            5,
+        ],
+        // The frame pointer and the return address are pushed
+        memory: vec![(20, 20), (21, PC + 2)],
+        registers: [(PC, 20, 20), (PC + 5, 22, 22)],
+        bound_cells: vec![
+            (0, FRAME_DST, vec![Call1]),
+            (0, FRAME_FP, vec![Call1]),
+            (0, FRAME_PC, vec![Op1Addr, Call2, NextPc2]),
+            (0, FRAME_RES, vec![Mul2, NextPc2]),
+        ],
+    }
+    .check();
+}
+
+#[test]
+fn ret() {
+    // ret
+    Vector {
+        name: "ret",
+        instruction: vec![encode_instruction(
+            -2,
+            -1,
+            -1,
+            DST_FP | OP0_FP | OP1_FP | PC_JUMP_ABS | OPC_RET,
+        )],
+        // The frame pointer and the return address pushed by the call
+        memory: vec![(20, 30), (21, 8)],
+        registers: [(PC, 22, 22), (8, 22, 30)],
+        bound_cells: vec![
+            (0, FRAME_DST, vec![NextFp]),
+            (0, FRAME_FP, vec![DstAddr, Op0Addr, Op1Addr]),
+            (0, FRAME_RES, vec![Mul2, NextPc2]),
+        ],
+    }
+    .check();
+}
+
+#[test]
+fn jnz_taken() {
+    // jmp rel 4 if [ap - 1] != 0
+    Vector {
+        name: "jnz taken",
+        instruction: vec![encode_instruction(-1, -1, 1, OP0_FP | OP1_IMM | PC_JNZ), 4],
+        memory: vec![(19, 7)],
+        registers: [(PC, 20, 20), (PC + 4, 20, 20)],
+        bound_cells: vec![
+            (0, FRAME_DST, vec![T0]),
+            (0, FRAME_RES, vec![T1]),
+            (0, FRAME_PC, vec![Op1Addr, NextPc2]),
+        ],
+    }
+    .check();
+}
+
+#[test]
+fn jnz_not_taken() {
+    // jmp rel 4 if [ap - 1] != 0
+    Vector {
+        name: "jnz not taken",
+        instruction: vec![encode_instruction(-1, -1, 1, OP0_FP | OP1_IMM | PC_JNZ), 4],
+        memory: vec![(19, 0)],
+        registers: [(PC, 20, 20), (PC + 2, 20, 20)],
+        bound_cells: vec![
+            (0, FRAME_DST, vec![T0]),
+            (0, FRAME_PC, vec![Op1Addr, NextPc1]),
+        ],
+    }
+    .check();
+}
+
+#[test]
+fn jmp_abs_with_immediate() {
+    // jmp abs 11
+    Vector {
+        name: "jmp abs",
+        instruction: vec![
+            encode_instruction(-1, -1, 1, DST_FP | OP0_FP | OP1_IMM | PC_JUMP_ABS),
+            11,
+        ],
+        memory: vec![(19, 0)],
+        registers: [(PC, 20, 20), (11, 20, 20)],
+        bound_cells: vec![
+            (0, FRAME_PC, vec![Op1Addr]),
+            (0, FRAME_RES, vec![Mul2, NextPc2]),
+        ],
+    }
+    .check();
+}
+
+#[test]
+fn assert_eq_with_immediate_and_ap_increment() {
+    // [ap] = 5, ap++
+    Vector {
+        name: "assert_eq imm, ap++",
+        instruction: vec![
+            encode_instruction(0, -1, 1, OP0_FP | OP1_IMM | AP_ADD1 | OPC_AEQ),
+            5,
+        ],
+        memory: vec![(19, 0), (20, 5)],
+        registers: [(PC, 20, 20), (PC + 2, 21, 20)],
+        bound_cells: vec![
+            (0, FRAME_DST, vec![AssertEq]),
+            (0, FRAME_RES, vec![AssertEq, Mul2]),
+            (0, FRAME_PC, vec![Op1Addr, NextPc2]),
+            (0, FRAME_FP, vec![Op0Addr, NextFp]),
+        ],
+    }
+    .check();
+}
+
+#[test]
+fn assert_eq_with_immediate_relative_to_fp() {
+    // [fp + 1] = 7
+    Vector {
+        name: "assert_eq imm",
+        instruction: vec![
+            encode_instruction(1, -1, 1, DST_FP | OP0_FP | OP1_IMM | OPC_AEQ),
+            7,
+        ],
+        memory: vec![(19, 0), (21, 7)],
+        registers: [(PC, 20, 20), (PC + 2, 20, 20)],
+        bound_cells: vec![
+            (0, FRAME_DST, vec![AssertEq]),
+            (0, FRAME_RES, vec![AssertEq, Mul2]),
+            (0, FRAME_FP, vec![DstAddr, Op0Addr, NextFp]),
+        ],
+    }
+    .check();
+}
+
+#[test]
+fn ap_add_with_immediate() {
+    // ap += 3
+    Vector {
+        name: "ap +=",
+        instruction: vec![
+            encode_instruction(-1, -1, 1, DST_FP | OP0_FP | OP1_IMM | AP_ADD),
+            3,
+        ],
+        memory: vec![(19, 0)],
+        registers: [(PC, 20, 20), (PC + 2, 23, 20)],
+        bound_cells: vec![
+            (0, FRAME_RES, vec![Mul2, NextAp]),
+            (0, FRAME_PC, vec![Op1Addr, NextPc2]),
+        ],
+    }
+    .check();
+}
+
+#[test]
+fn double_dereference() {
+    // [ap] = [[fp - 3] + 1], ap++
+    Vector {
+        name: "double deref",
+        instruction: vec![encode_instruction(0, -3, 1, OP0_FP | AP_ADD1 | OPC_AEQ)],
+        memory: vec![(17, 40), (41, 9), (20, 9)],
+        registers: [(PC, 20, 20), (PC + 1, 21, 20)],
+        bound_cells: vec![
+            (0, FRAME_DST, vec![AssertEq]),
+            (0, FRAME_RES, vec![AssertEq, Mul2]),
+            (0, FRAME_PC, vec![NextPc2]),
+        ],
+    }
+    .check();
+}
+
+#[test]
+fn addition_of_operands() {
+    // [ap] = [ap - 1] + [fp - 4], ap++
+    Vector {
+        name: "add",
+        instruction: vec![encode_instruction(
+            0,
+            -1,
+            -4,
+            OP1_FP | RES_ADD | AP_ADD1 | OPC_AEQ,
+        )],
+        memory: vec![(19, 3), (16, 4), (20, 7)],
+        registers: [(PC, 20, 20), (PC + 1, 21, 20)],
+        bound_cells: vec![
+            (0, FRAME_DST, vec![AssertEq]),
+            (0, FRAME_RES, vec![AssertEq, Mul2]),
+        ],
+    }
+    .check();
+}
+
+#[test]
+fn multiplication_of_operands() {
+    // [ap] = [ap - 1] * [fp - 4], ap++
+    Vector {
+        name: "mul",
+        instruction: vec![encode_instruction(
+            0,
+            -1,
+            -4,
+            OP1_FP | RES_MUL | AP_ADD1 | OPC_AEQ,
+        )],
+        memory: vec![(19, 3), (16, 4), (20, 12)],
+        registers: [(PC, 20, 20), (PC + 1, 21, 20)],
+        bound_cells: vec![
+            (0, FRAME_DST, vec![AssertEq]),
+            (0, FRAME_RES, vec![AssertEq, Mul2]),
+        ],
+    }
+    .check();
+}
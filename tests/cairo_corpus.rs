@@ -0,0 +1,185 @@
+//! Proves and verifies every compiled program under `cairo_programs`, the JSON
+//! outputs of `cairo-compile` and the CASM contract classes, without a test per
+//! program. The Cairo version, the builtins and the layout of each program are read
+//! from the program itself, and the memory segments of its builtins are the ones
+//! found by running it, so a program is covered as soon as it's compiled.
+//!
+//! The programs declaring builtins the Cairo AIR can't prove yet, and the ones too
+//! long to prove in a test, are skipped. The coverage matrix of the corpus, with the
+//! builtins and the result of each program, is written to
+//! `cairo_corpus_coverage.md` in the temporary directory of the tests.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use lambdaworks_stark::{
+    cairo::{
+        air::{generate_cairo_proof, verify_cairo_proof},
+        cairo_layout::{CairoBuiltin, CairoLayout},
+        runner::{
+            program::CairoProgram,
+            run::{estimate_trace_length, Error},
+        },
+    },
+    starks::proof::options::ProofOptions,
+};
+
+/// Longest estimated trace of the programs proven by the harness.
+const MAX_TRACE_LENGTH: usize = 1 << 14;
+
+enum Outcome {
+    Verified,
+    Unprovable(CairoBuiltin),
+    TooLong(usize),
+    Failed(String),
+}
+
+struct CorpusEntry {
+    name: String,
+    builtins: Vec<CairoBuiltin>,
+    layout: Option<CairoLayout>,
+    outcome: Outcome,
+}
+
+/// The compiled programs in `directory` and its subdirectories, sorted by path.
+fn compiled_programs(directory: &Path) -> Vec<PathBuf> {
+    let mut programs = vec![];
+    for entry in std::fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            programs.extend(compiled_programs(&path));
+        } else if matches!(
+            path.extension().and_then(|extension| extension.to_str()),
+            Some("json" | "casm")
+        ) {
+            programs.push(path);
+        }
+    }
+    programs.sort();
+    programs
+}
+
+fn prove_and_verify(program: &CairoProgram, proof_options: &ProofOptions) -> Outcome {
+    match estimate_trace_length(program.content(), program.version()) {
+        Ok(trace_length) if trace_length > MAX_TRACE_LENGTH => {
+            return Outcome::TooLong(trace_length)
+        }
+        Ok(_) => {}
+        Err(error) => return Outcome::Failed(format!("could not be run: {error}")),
+    }
+
+    let (main_trace, pub_inputs) = match program.generate_prover_args(&None) {
+        Ok(prover_args) => prover_args,
+        Err(error) => return Outcome::Failed(format!("could not be run: {error}")),
+    };
+    match generate_cairo_proof(&main_trace, &pub_inputs, proof_options) {
+        Ok(proof) if verify_cairo_proof(&proof, &pub_inputs, proof_options) => Outcome::Verified,
+        Ok(_) => Outcome::Failed("the proof does not verify".to_string()),
+        Err(error) => Outcome::Failed(format!("could not be proven: {error:?}")),
+    }
+}
+
+fn corpus_entry(path: &Path, proof_options: &ProofOptions) -> CorpusEntry {
+    let name = path
+        .strip_prefix(env!("CARGO_MANIFEST_DIR"))
+        .unwrap_or(path)
+        .display()
+        .to_string();
+    let failed = |error: Error| CorpusEntry {
+        name: name.clone(),
+        builtins: vec![],
+        layout: None,
+        outcome: Outcome::Failed(error.to_string()),
+    };
+
+    let program = match CairoProgram::from_file(path) {
+        Ok(program) => program,
+        Err(error) => return failed(error),
+    };
+    let builtins = match program.builtins() {
+        Ok(builtins) => builtins,
+        Err(error) => return failed(error),
+    };
+    let (layout, outcome) = match program.layout() {
+        Ok(layout) => (Some(layout), prove_and_verify(&program, proof_options)),
+        Err(Error::UnprovableBuiltin(builtin)) => (None, Outcome::Unprovable(builtin)),
+        Err(error) => (None, Outcome::Failed(error.to_string())),
+    };
+
+    CorpusEntry {
+        name,
+        builtins,
+        layout,
+        outcome,
+    }
+}
+
+/// Markdown table with a row per program, marking the builtins it declares.
+fn coverage_matrix(entries: &[CorpusEntry]) -> String {
+    let mut matrix = "| program | layout |".to_string();
+    for builtin in CairoBuiltin::ALL {
+        write!(matrix, " {} |", builtin.as_str()).unwrap();
+    }
+    matrix.push_str(" result |\n|---|---|");
+    matrix.push_str(&"---|".repeat(CairoBuiltin::ALL.len() + 1));
+    matrix.push('\n');
+
+    for entry in entries {
+        let layout = entry.layout.as_ref().map_or("-", CairoLayout::as_str);
+        write!(matrix, "| {} | {layout} |", entry.name).unwrap();
+        for builtin in CairoBuiltin::ALL {
+            let mark = if entry.builtins.contains(&builtin) {
+                "x"
+            } else {
+                ""
+            };
+            write!(matrix, " {mark} |").unwrap();
+        }
+        let result = match &entry.outcome {
+            Outcome::Verified => "verified".to_string(),
+            Outcome::Unprovable(builtin) => format!("skipped, {} is unprovable", builtin.as_str()),
+            Outcome::TooLong(trace_length) => {
+                format!("skipped, trace of {trace_length} rows")
+            }
+            Outcome::Failed(error) => format!("FAILED: {error}"),
+        };
+        writeln!(matrix, " {result} |").unwrap();
+    }
+    matrix
+}
+
+#[test_log::test]
+fn test_every_compiled_program_is_proven_and_verified() {
+    let corpus_directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("cairo_programs");
+    let programs = compiled_programs(&corpus_directory);
+    assert!(
+        !programs.is_empty(),
+        "There are no compiled programs in cairo_programs, compile them with `make test`"
+    );
+
+    let proof_options = ProofOptions::default_test_options();
+    let entries: Vec<_> = programs
+        .iter()
+        .map(|path| corpus_entry(path, &proof_options))
+        .collect();
+
+    let matrix = coverage_matrix(&entries);
+    let matrix_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cairo_corpus_coverage.md");
+    std::fs::write(&matrix_path, &matrix).unwrap();
+    tracing::info!(path = %matrix_path.display(), "coverage matrix written");
+
+    assert!(
+        entries
+            .iter()
+            .any(|entry| matches!(entry.outcome, Outcome::Verified)),
+        "No program of the corpus was proven"
+    );
+    let failures: Vec<_> = entries
+        .iter()
+        .filter_map(|entry| match &entry.outcome {
+            Outcome::Failed(error) => Some(format!("{}: {error}", entry.name)),
+            _ => None,
+        })
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
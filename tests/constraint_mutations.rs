@@ -0,0 +1,392 @@
+//! Mutation tests of the Cairo AIR. For each transition constraint, a cell of the
+//! trace it references is changed so the constraint is violated, together with as
+//! few other constraints as possible, and the proof of the changed trace must be
+//! rejected. A constraint that no change of its own cells violates is vacuous, like
+//! one disabled by a selector that is always zero, and fails the tests.
+//!
+//! The cells of the auxiliary trace are changed after the prover builds it, with
+//! `PerturbedCairoAIR`, as the prover would otherwise build it again from the main
+//! trace.
+
+use std::collections::BTreeSet;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use lambdaworks_crypto::fiat_shamir::{
+    default_transcript::DefaultTranscript, transcript::Transcript,
+};
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField as F;
+use lambdaworks_stark::{
+    cairo::{
+        air::{
+            generate_cairo_proof, verify_cairo_proof, CairoAIR, CairoRAPChallenges, PublicInputs,
+        },
+        constraints::ConstraintId,
+        runner::run::{cairo0_program_path, generate_prover_args, CairoVersion},
+    },
+    starks::{
+        constraints::{boundary::BoundaryConstraints, symbolic::Expr},
+        context::AirContext,
+        frame::Frame,
+        proof::options::ProofOptions,
+        prover::prove,
+        trace::TraceTable,
+        traits::AIR,
+        virtual_columns::VirtualColumn,
+    },
+    FE,
+};
+
+/// The program has the range-check builtin, so its constraints are mutated too.
+const PROGRAM: &str = "rc_program.json";
+
+/// Steps whose cells are changed. They are away from the first and last rows of
+/// the trace, where the boundary constraints would reject the changes too.
+const MUTATED_STEPS: [usize; 3] = [1, 2, 3];
+
+/// The instruction is a virtual column, the packing of its offsets and flags, so
+/// the prover reconstructs it from the changed cells and the unpacking constraint
+/// holds by construction. See `CairoAIR::virtual_columns`.
+const VACUOUS_CONSTRAINTS: [ConstraintId; 1] = [ConstraintId::Inst];
+
+/// A change of a cell of the trace, and the constraints it violates.
+#[derive(Clone, Debug)]
+struct Mutation {
+    row: usize,
+    col: usize,
+    delta: FE,
+    violated: BTreeSet<usize>,
+}
+
+struct MutationHarness {
+    main_trace: TraceTable<F>,
+    pub_inputs: PublicInputs,
+    air: CairoAIR,
+    rap_challenges: CairoRAPChallenges,
+}
+
+impl MutationHarness {
+    fn new() -> Self {
+        let program_content = std::fs::read(cairo0_program_path(PROGRAM)).unwrap();
+        let (main_trace, pub_inputs) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let air = CairoAIR::new(
+            main_trace.n_rows(),
+            &pub_inputs,
+            &ProofOptions::default_test_options(),
+        );
+        let rap_challenges = air.build_rap_challenges(&mut DefaultTranscript::new());
+        assert!(
+            main_trace.n_rows() > MUTATED_STEPS[MUTATED_STEPS.len() - 1] + 2,
+            "the trace of {PROGRAM} is too short"
+        );
+
+        Self {
+            main_trace,
+            pub_inputs,
+            air,
+            rap_challenges,
+        }
+    }
+
+    /// The main and auxiliary traces with `mutation` applied, as the prover would
+    /// build them.
+    fn full_trace(&self, mutation: Option<(usize, usize, &FE)>) -> Option<TraceTable<F>> {
+        let num_main_columns = self.main_trace.n_cols;
+        let mut main_columns = self.main_trace.cols();
+        if let Some((row, col, delta)) = mutation.filter(|(_, col, _)| *col < num_main_columns) {
+            main_columns[col][row] = main_columns[col][row] + delta;
+            for virtual_column in self.air.virtual_columns() {
+                let values: Vec<FE> = main_columns.iter().map(|column| column[row]).collect();
+                main_columns[virtual_column.column][row] = virtual_column.evaluate(&values);
+            }
+        }
+        let main_trace = TraceTable::new_from_cols(&main_columns)
+            .with_rows_per_step(self.main_trace.rows_per_step());
+
+        // The auxiliary trace of some changes can't be built, like the one of an
+        // offset out of range
+        let aux_trace = catch_unwind(AssertUnwindSafe(|| {
+            self.air
+                .build_auxiliary_trace(0, &main_trace, &self.rap_challenges)
+        }))
+        .ok()?;
+        let mut columns = TraceTable::concatenate_tables(&[&main_trace, &aux_trace]).cols();
+        if let Some((row, col, delta)) = mutation.filter(|(_, col, _)| *col >= num_main_columns) {
+            columns[col][row] = columns[col][row] + delta;
+        }
+        Some(TraceTable::new_from_cols(&columns))
+    }
+
+    /// Constraints that don't hold in some step of `trace` where they apply.
+    fn violated_constraints(&self, trace: &TraceTable<F>) -> BTreeSet<usize> {
+        let context = self.air.context();
+        let n_rows = trace.n_rows();
+        let mut violated = BTreeSet::new();
+        for step in 0..n_rows {
+            let frame = Frame::read_from_trace(trace, step, 1, &context.transition_offsets);
+            let evaluations = self.air.compute_transition(&frame, &self.rap_challenges);
+            for (constraint, evaluation) in evaluations.iter().enumerate() {
+                if step < n_rows - context.transition_exemptions[constraint]
+                    && evaluation != &FE::zero()
+                {
+                    violated.insert(constraint);
+                }
+            }
+        }
+        violated
+    }
+
+    /// A change of one of the cells referenced by `constraint` that violates it, with
+    /// as few other violated constraints as possible. `None` if the constraint is
+    /// vacuous.
+    fn find_mutation(&self, constraint: usize, expression: &Expr<F>) -> Option<Mutation> {
+        let mut cells = BTreeSet::new();
+        expression.collect_cells(&mut cells);
+        let committed_columns = self.air.committed_columns();
+
+        let mut best: Option<Mutation> = None;
+        for (offset, col) in cells {
+            // The cells of virtual columns are rebuilt by the prover
+            if committed_columns.position(col).is_none() {
+                continue;
+            }
+            for step in MUTATED_STEPS {
+                for delta in [FE::one(), FE::from(2)] {
+                    let row = step + offset;
+                    let Some(trace) = self.full_trace(Some((row, col, &delta))) else {
+                        continue;
+                    };
+                    let violated = self.violated_constraints(&trace);
+                    if !violated.contains(&constraint) {
+                        continue;
+                    }
+                    if best
+                        .as_ref()
+                        .map_or(true, |best| violated.len() < best.violated.len())
+                    {
+                        best = Some(Mutation {
+                            row,
+                            col,
+                            delta,
+                            violated,
+                        });
+                    }
+                    if best.as_ref().unwrap().violated.len() == 1 {
+                        return best;
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// The mutation of each constraint that isn't vacuous by construction.
+    fn mutations(&self) -> Vec<(usize, Option<Mutation>)> {
+        let expressions = self.air.symbolic_transition().unwrap();
+        assert_eq!(expressions.len(), self.air.num_transition_constraints());
+
+        expressions
+            .iter()
+            .enumerate()
+            .filter(|(constraint, _)| !is_vacuous_by_construction(*constraint))
+            .map(|(constraint, expression)| {
+                (constraint, self.find_mutation(constraint, expression))
+            })
+            .collect()
+    }
+
+    /// Whether the verifier rejects the proof of the trace changed by `mutation`. The
+    /// prover must generate it, as a prover refusing the trace wouldn't stop a
+    /// malicious one.
+    fn proof_is_rejected(&self, mutation: &Mutation) -> bool {
+        let proof_options = ProofOptions::default_test_options();
+        let proof = if mutation.col < self.main_trace.n_cols {
+            let mut columns = self.main_trace.cols();
+            columns[mutation.col][mutation.row] =
+                columns[mutation.col][mutation.row] + mutation.delta;
+            let trace = TraceTable::new_from_cols(&columns)
+                .with_rows_per_step(self.main_trace.rows_per_step());
+            generate_cairo_proof(&trace, &self.pub_inputs, &proof_options)
+        } else {
+            let pub_inputs = PerturbedPublicInputs {
+                pub_inputs: self.pub_inputs.clone(),
+                aux_cell: (mutation.row, mutation.col - self.main_trace.n_cols),
+                delta: mutation.delta,
+            };
+            prove::<F, PerturbedCairoAIR>(&self.main_trace, &pub_inputs, &proof_options)
+        };
+        let proof = proof.unwrap_or_else(|error| {
+            panic!("the trace changed by {mutation:?} wasn't proven: {error}")
+        });
+
+        !verify_cairo_proof(&proof, &self.pub_inputs, &proof_options)
+    }
+}
+
+fn is_vacuous_by_construction(constraint: usize) -> bool {
+    VACUOUS_CONSTRAINTS
+        .iter()
+        .any(|id| id.index() == constraint)
+}
+
+fn constraint_name(air: &CairoAIR, constraint: usize) -> String {
+    air.transition_constraint_name(constraint)
+        .map_or_else(|| constraint.to_string(), str::to_string)
+}
+
+#[test]
+fn no_constraint_is_vacuous() {
+    let harness = MutationHarness::new();
+
+    let vacuous: Vec<String> = harness
+        .mutations()
+        .into_iter()
+        .filter(|(_, mutation)| mutation.is_none())
+        .map(|(constraint, _)| constraint_name(&harness.air, constraint))
+        .collect();
+    assert!(
+        vacuous.is_empty(),
+        "no change of their cells violates the constraints {vacuous:?}"
+    );
+}
+
+#[test]
+fn proofs_violating_each_constraint_are_rejected() {
+    let harness = MutationHarness::new();
+
+    let accepted: Vec<String> = harness
+        .mutations()
+        .into_iter()
+        .filter_map(|(constraint, mutation)| Some((constraint, mutation?)))
+        .filter(|(_, mutation)| !harness.proof_is_rejected(mutation))
+        .map(|(constraint, mutation)| {
+            format!(
+                "{} (cell ({}, {}) violating {:?})",
+                constraint_name(&harness.air, constraint),
+                mutation.row,
+                mutation.col,
+                mutation.violated
+            )
+        })
+        .collect();
+    assert!(
+        accepted.is_empty(),
+        "proofs of traces violating the constraints {accepted:?} were accepted"
+    );
+}
+
+/// Public inputs of `PerturbedCairoAIR`: the ones of the program, and the change of
+/// the auxiliary trace.
+#[derive(Clone, Debug)]
+struct PerturbedPublicInputs {
+    pub_inputs: PublicInputs,
+    /// `(row, column)` of the cell in the auxiliary trace
+    aux_cell: (usize, usize),
+    delta: FE,
+}
+
+/// The Cairo AIR, changing a cell of the auxiliary trace after building it. The
+/// rest is the same, so its proofs are verified as the ones of `CairoAIR`.
+struct PerturbedCairoAIR {
+    air: CairoAIR,
+    pub_inputs: PerturbedPublicInputs,
+}
+
+impl AIR for PerturbedCairoAIR {
+    type Field = F;
+    type RAPChallenges = CairoRAPChallenges;
+    type PublicInputs = PerturbedPublicInputs;
+
+    fn new(
+        trace_length: usize,
+        pub_inputs: &Self::PublicInputs,
+        proof_options: &ProofOptions,
+    ) -> Self {
+        Self {
+            air: CairoAIR::new(trace_length, &pub_inputs.pub_inputs, proof_options),
+            pub_inputs: pub_inputs.clone(),
+        }
+    }
+
+    fn check_configuration(pub_inputs: &Self::PublicInputs) -> Result<(), String> {
+        CairoAIR::check_configuration(&pub_inputs.pub_inputs)
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        round: usize,
+        main_trace: &TraceTable<Self::Field>,
+        rap_challenges: &Self::RAPChallenges,
+    ) -> TraceTable<Self::Field> {
+        let aux_trace = self
+            .air
+            .build_auxiliary_trace(round, main_trace, rap_challenges);
+        let (row, col) = self.pub_inputs.aux_cell;
+        let mut columns = aux_trace.cols();
+        columns[col][row] = columns[col][row] + self.pub_inputs.delta;
+        TraceTable::new_from_cols(&columns).with_rows_per_step(aux_trace.rows_per_step())
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges {
+        self.air.build_rap_challenges(transcript)
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        self.air.number_auxiliary_rap_columns()
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.air.composition_poly_degree_bound()
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::Field>,
+        rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FE> {
+        self.air.compute_transition(frame, rap_challenges)
+    }
+
+    fn boundary_constraints(
+        &self,
+        rap_challenges: &Self::RAPChallenges,
+    ) -> BoundaryConstraints<Self::Field> {
+        self.air.boundary_constraints(rap_challenges)
+    }
+
+    fn context(&self) -> &AirContext {
+        self.air.context()
+    }
+
+    fn trace_length(&self) -> usize {
+        self.air.trace_length()
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.pub_inputs
+    }
+
+    fn public_parameters(&self) -> Vec<u8> {
+        self.air.public_parameters()
+    }
+
+    fn transition_constraint_name(&self, index: usize) -> Option<&'static str> {
+        self.air.transition_constraint_name(index)
+    }
+
+    fn symbolic_transition(&self) -> Option<Vec<Expr<Self::Field>>> {
+        self.air.symbolic_transition()
+    }
+
+    fn rap_challenge_names(&self) -> Vec<&'static str> {
+        self.air.rap_challenge_names()
+    }
+
+    fn referenced_frame_cells(&self) -> Option<BTreeSet<(usize, usize)>> {
+        self.air.referenced_frame_cells()
+    }
+
+    fn virtual_columns(&self) -> Vec<VirtualColumn<Self::Field>> {
+        self.air.virtual_columns()
+    }
+}
@@ -1,34 +1,84 @@
 use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use lambdaworks_math::field::fields::{
-    fft_friendly::stark_252_prime_field::Stark252PrimeField as F,
-    u64_prime_field::{F17, FE17},
+use lambdaworks_crypto::fiat_shamir::{
+    default_transcript::DefaultTranscript, transcript::Transcript,
 };
+use lambdaworks_math::field::{
+    element::FieldElement,
+    fields::{
+        fft_friendly::stark_252_prime_field::Stark252PrimeField as F,
+        u64_prime_field::{F17, FE17},
+    },
+    traits::IsFFTField,
+};
+use lambdaworks_math::traits::{ByteConversion, Deserializable, Serializable};
 use lambdaworks_stark::{
     cairo::{
         air::{
-            generate_cairo_proof, verify_cairo_proof, MemorySegment, MemorySegmentMap,
-            PublicInputs, FRAME_DST_ADDR, FRAME_OP0_ADDR, FRAME_OP1_ADDR, FRAME_PC,
+            generate_cairo_proof, generate_cairo_proof_recursive_friendly,
+            generate_cairo_proof_with_prover, try_verify_cairo_proof, verify_cairo_proof,
+            verify_cairo_proof_recursive_friendly, verify_cairo_proof_with_address_space, CairoAIR,
+            CairoProvingPlan, MemorySegment, MemorySegmentMap, PublicInputs, FRAME_DST_ADDR,
+            FRAME_OP0_ADDR, FRAME_OP1_ADDR, FRAME_PC,
         },
-        cairo_layout::CairoLayout,
-        execution_trace::build_main_trace,
-        runner::run::{
-            cairo0_program_path, cairo1_program_path, generate_prover_args, run_program,
-            CairoVersion,
+        cairo_layout::{CairoBuiltin, CairoLayout},
+        container::CairoProofContainer,
+        execution_trace::{build_main_trace, MIN_TRACE_LENGTH},
+        runner::{
+            hints::{HintConfig, HintPolicy},
+            limits::{ExecutionLimits, LimitExceeded},
+            program::CairoProgram,
+            run::{
+                cairo0_program_path, cairo1_program_path, generate_prover_args,
+                generate_prover_args_sandboxed, generate_prover_args_streaming_outputs,
+                generate_prover_args_with_hint_config, run_program, run_program_with_hint_config,
+                run_program_without_relocation, CairoVersion, Error,
+            },
         },
+        statement::verify_cairo_proof_with_statement,
     },
     starks::{
         example::{
             dummy_air::{self, DummyAIR},
             fibonacci_2_columns::{self, Fibonacci2ColsAIR},
+            fibonacci_multi_rap::FibonacciMultiRAP,
             fibonacci_rap::{fibonacci_rap_trace, FibonacciRAP, FibonacciRAPPublicInputs},
+            fibonacci_rap_extension::FibonacciRAPExtension,
+            hash_chain_vdf::{prove_vdf, vdf_output, verify_vdf, HashChainVdfPublicInputs},
+            poseidon_merkle_path::{
+                poseidon_merkle_path_trace, PoseidonMerklePathAIR, PoseidonMerklePathPublicInputs,
+            },
             quadratic_air::{self, QuadraticAIR, QuadraticPublicInputs},
             simple_fibonacci::{self, FibonacciAIR, FibonacciPublicInputs},
+            stitched_fibonacci::{
+                stitched_fibonacci_trace, StitchedFibonacciAIR, StitchedFibonacciPublicInputs,
+            },
+            strided_counter::{self, StridedCounterAIR, StridedCounterPublicInputs},
+        },
+        frame::Frame,
+        plan::ProvingPlan,
+        proof::{
+            field_id::IdentifiedField,
+            options::{
+                CommitmentHash, CommitmentLayout, GrindingConfig, MerkleTreeStorage, ProofOptions,
+                ProofOptionsPolicy, SecurityLevel,
+            },
+            stark::StarkProof,
+            stream::write_proof_stream,
+        },
+        prover::{
+            prove, prove_interactive, prove_with_transcript, CpuProver, IsStarkProver,
+            ProvingError, RecursionFriendlyProof,
         },
-        proof::options::{ProofOptions, SecurityLevel},
-        prover::prove,
+        public_coin::SeededChallengeProvider,
         trace::TraceTable,
-        verifier::verify,
+        traits::{ExtraOpeningPoint, AIR},
+        verifier::{
+            verify, verify_commitments, verify_deep_only, verify_fri_only, verify_interactive,
+            verify_stream, verify_with_context, verify_with_policy, verify_with_transcript,
+            VerifierContext,
+        },
     },
     FE,
 };
@@ -52,6 +102,306 @@ fn test_prove_fib() {
     ));
 }
 
+#[test_log::test]
+fn test_prove_stitched_fib_with_exemption_zones() {
+    let trace = stitched_fibonacci_trace([FE::from(1), FE::from(1)], 32);
+    let proof_options = ProofOptions::default_test_options();
+    let pub_inputs = StitchedFibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let proof = prove::<F, StitchedFibonacciAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    assert!(verify::<F, StitchedFibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+
+    // A value in the zone is still constrained by the steps before it, which aren't
+    // exempted
+    let mut column = trace.cols().remove(0);
+    column[15] = &column[15] + FE::one();
+    let malicious_trace = TraceTable::new_from_cols(&[column]);
+    let proof =
+        prove::<F, StitchedFibonacciAIR<F>>(&malicious_trace, &pub_inputs, &proof_options).unwrap();
+    assert!(!verify::<F, StitchedFibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_verify_fib_with_the_options_of_the_proof() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 16);
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+    let policy = ProofOptionsPolicy {
+        min_security_bits: 8,
+        max_fri_number_of_queries: 8,
+        allow_disabled_grinding: false,
+        max_trace_length: 1 << 10,
+    };
+
+    let expected_options = ProofOptions::default_test_options();
+    let proof_options = ProofOptions {
+        blowup_factor: 8,
+        ..ProofOptions::default_test_options()
+    };
+    let proof = prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    let proof = StarkProof::<F>::deserialize(&proof.serialize()).unwrap();
+
+    assert!(verify_with_policy::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &policy
+    ));
+    assert!(!verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &expected_options
+    ));
+
+    // With a blowup factor of 4, the queries give less security than required
+    let weaker_proof = prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &expected_options).unwrap();
+    assert!(!verify_with_policy::<F, FibonacciAIR<F>>(
+        &weaker_proof,
+        &pub_inputs,
+        &policy
+    ));
+}
+
+#[test_log::test]
+fn test_verify_fib_proof_stream() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 16);
+    let proof_options = ProofOptions::default_test_options();
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let proof = prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    let mut stream = Vec::new();
+    write_proof_stream(&proof, &mut stream).unwrap();
+    assert!(verify_stream::<F, FibonacciAIR<F>, _>(
+        stream.as_slice(),
+        &pub_inputs,
+        &proof_options
+    ));
+
+    // Change a byte of the openings of the last query
+    let mut tampered = stream.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    assert!(!verify_stream::<F, FibonacciAIR<F>, _>(
+        tampered.as_slice(),
+        &pub_inputs,
+        &proof_options
+    ));
+
+    // Leave out the openings of the last query
+    let truncated = &stream[..stream.len() - 1];
+    assert!(!verify_stream::<F, FibonacciAIR<F>, _>(
+        truncated,
+        &pub_inputs,
+        &proof_options
+    ));
+
+    // Append a byte after the openings of the last query
+    let mut extended = stream.clone();
+    extended.push(0);
+    assert!(!verify_stream::<F, FibonacciAIR<F>, _>(
+        extended.as_slice(),
+        &pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_verify_proofs_of_several_airs_with_the_same_context() {
+    let proof_options = ProofOptions::default_test_options();
+    let mut context = VerifierContext::new();
+
+    // The buffers of the context are resized for each AIR
+    for trace_length in [8, 32, 16] {
+        let trace = simple_fibonacci::fibonacci_trace([FE::one(), FE::one()], trace_length);
+        let pub_inputs = FibonacciPublicInputs {
+            a0: FE::one(),
+            a1: FE::one(),
+        };
+        let proof = prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+        assert!(verify_with_context::<F, FibonacciAIR<F>>(
+            &proof,
+            &pub_inputs,
+            &proof_options,
+            &mut context
+        ));
+
+        let trace = strided_counter::strided_counter_trace([FE::one(), FE::from(2)], trace_length);
+        let pub_inputs = StridedCounterPublicInputs {
+            a0: FE::one(),
+            a1: FE::from(2),
+            extra_opening: None,
+        };
+        let proof = prove::<F, StridedCounterAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+        assert!(verify_with_context::<F, StridedCounterAIR<F>>(
+            &proof,
+            &pub_inputs,
+            &proof_options,
+            &mut context
+        ));
+    }
+}
+
+/// Backend delegating to the CPU prover, counting the proofs it generates.
+#[derive(Default)]
+struct CountingProver {
+    num_proofs: AtomicUsize,
+}
+
+impl IsStarkProver for CountingProver {
+    fn prove_with_transcript<Field, A, T>(
+        &self,
+        main_trace: &TraceTable<Field>,
+        pub_inputs: &A::PublicInputs,
+        proof_options: &ProofOptions,
+        transcript: T,
+    ) -> Result<StarkProof<Field>, ProvingError>
+    where
+        Field: IsFFTField + IdentifiedField,
+        A: AIR<Field = Field> + Send + Sync,
+        A::RAPChallenges: Send + Sync,
+        FieldElement<Field>: ByteConversion + Send + Sync,
+        T: Transcript,
+    {
+        self.num_proofs.fetch_add(1, Ordering::Relaxed);
+        CpuProver.prove_with_transcript::<Field, A, T>(
+            main_trace,
+            pub_inputs,
+            proof_options,
+            transcript,
+        )
+    }
+}
+
+#[test_log::test]
+fn test_proofs_of_other_backends_are_verified() {
+    let prover = CountingProver::default();
+    let proof_options = ProofOptions::default_test_options();
+
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+    let proof = prover
+        .prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options)
+        .unwrap();
+    assert!(verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+
+    let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+    let proof = generate_cairo_proof_with_prover(&prover, &main_trace, &pub_inputs, &proof_options)
+        .unwrap();
+    assert!(verify_cairo_proof(&proof, &pub_inputs, &proof_options));
+
+    assert_eq!(prover.num_proofs.load(Ordering::Relaxed), 2);
+}
+
+#[test_log::test]
+fn test_proofs_continue_the_transcript_of_a_larger_protocol() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+    let proof_options = ProofOptions::default_test_options();
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+    let protocol_transcript = |first_message: &[u8]| {
+        let mut transcript = DefaultTranscript::new();
+        transcript.append(first_message);
+        transcript
+    };
+
+    let mut prover_transcript = protocol_transcript(b"statement");
+    let proof = prove_with_transcript::<F, FibonacciAIR<F>, _>(
+        &trace,
+        &pub_inputs,
+        &proof_options,
+        &mut prover_transcript,
+    )
+    .unwrap();
+
+    let mut verifier_transcript = protocol_transcript(b"statement");
+    assert!(verify_with_transcript::<F, FibonacciAIR<F>, _>(
+        &proof,
+        &pub_inputs,
+        &proof_options,
+        &mut verifier_transcript,
+    ));
+    // Both parties continue the protocol from the same state
+    assert_eq!(
+        prover_transcript.challenge(),
+        verifier_transcript.challenge()
+    );
+
+    assert!(!verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+    assert!(!verify_with_transcript::<F, FibonacciAIR<F>, _>(
+        &proof,
+        &pub_inputs,
+        &proof_options,
+        &mut protocol_transcript(b"another statement"),
+    ));
+}
+
+#[test_log::test]
+fn test_prover_rejects_blowup_factor_not_power_of_two() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+
+    let mut proof_options = ProofOptions::default_test_options();
+    proof_options.blowup_factor = 6;
+
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    assert!(matches!(
+        prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options),
+        Err(ProvingError::WrongParameter(_))
+    ));
+}
+
+#[test_log::test]
+fn test_prover_rejects_blowup_factor_below_the_degrees_of_the_constraints() {
+    let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+    let air = CairoAIR::new(main_trace.n_rows(), &pub_inputs, &proof_options);
+    assert_eq!(air.min_blowup_factor(), 4);
+
+    let proof_options = ProofOptions {
+        blowup_factor: 2,
+        ..proof_options
+    };
+    assert!(matches!(
+        generate_cairo_proof(&main_trace, &pub_inputs, &proof_options),
+        Err(ProvingError::WrongParameter(_))
+    ));
+}
+
 #[test_log::test]
 fn test_prove_fib17() {
     let trace = simple_fibonacci::fibonacci_trace([FE17::from(1), FE17::from(1)], 4);
@@ -59,8 +409,16 @@ fn test_prove_fib17() {
     let proof_options = ProofOptions {
         blowup_factor: 2,
         fri_number_of_queries: 7,
-        coset_offset: 3,
-        grinding_factor: 1,
+        coset_offset: 3.into(),
+        grinding: GrindingConfig::Bits(1),
+        commitment_layout: CommitmentLayout::RowBatched,
+        commitment_hash: CommitmentHash::Keccak256,
+        deduplicate_queries: false,
+        fri_max_layers: None,
+        salt_commitments: false,
+        trace_randomization_rows: 0,
+        merkle_cap_height: 0,
+        merkle_tree_storage: MerkleTreeStorage::InMemory,
     };
 
     let pub_inputs = FibonacciPublicInputs {
@@ -95,6 +453,29 @@ fn test_prove_fib_2_cols() {
     ));
 }
 
+#[test_log::test]
+fn test_degree_adjustments_of_degree_bounds_past_u32() {
+    // The degree bound of the composition polynomial is 2^32, past `u32::MAX`
+    let air = QuadraticAIR::<F>::new(
+        1 << 31,
+        &QuadraticPublicInputs { a0: FE::from(3) },
+        &ProofOptions::default_test_options(),
+    );
+
+    assert_eq!(air.degree_adjustment(0), Some(1 << 32));
+    assert_eq!(air.degree_adjustment(1), Some(1 << 31));
+    assert_eq!(air.degree_adjustment(2), Some(0));
+    assert_eq!(air.degree_adjustment(3), None);
+    assert_eq!(air.degree_adjustment(usize::MAX), None);
+    assert!(air.degree_bound_covers_constraints());
+
+    let z = FE::from(5);
+    assert_eq!(
+        z.pow(air.degree_adjustment(0).unwrap()),
+        z.pow(1_u64 << 31).square()
+    );
+}
+
 #[test_log::test]
 fn test_prove_quadratic() {
     let trace = quadratic_air::quadratic_trace(FE::from(3), 4);
@@ -111,6 +492,134 @@ fn test_prove_quadratic() {
     ));
 }
 
+#[test_log::test]
+fn test_prove_strided_counter() {
+    let trace = strided_counter::strided_counter_trace([FE::from(3), FE::from(10)], 16);
+
+    let proof_options = ProofOptions::default_test_options();
+
+    let pub_inputs = StridedCounterPublicInputs {
+        a0: FE::from(3),
+        a1: FE::from(10),
+        extra_opening: None,
+    };
+
+    let proof = prove::<F, StridedCounterAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    // The frame has the rows of offsets 0 and 2, and nothing else
+    assert_eq!(proof.trace_ood_frame_evaluations.num_rows(), 2);
+    assert!(verify::<F, StridedCounterAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_prove_with_extra_openings_of_the_composition_poly() {
+    let trace = strided_counter::strided_counter_trace([FE::from(3), FE::from(10)], 16);
+    let proof_options = ProofOptions::default_test_options();
+
+    for extra_opening in [ExtraOpeningPoint::NegatedZ, ExtraOpeningPoint::ShiftedZ] {
+        let pub_inputs = StridedCounterPublicInputs {
+            a0: FE::from(3),
+            a1: FE::from(10),
+            extra_opening: Some(extra_opening),
+        };
+
+        let mut proof =
+            prove::<F, StridedCounterAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+        assert!(proof.composition_poly_extra_ood_evaluations.is_some());
+        assert!(verify::<F, StridedCounterAIR<F>>(
+            &proof,
+            &pub_inputs,
+            &proof_options
+        ));
+
+        // A verifier not expecting the extra openings rejects them
+        let pub_inputs_without_opening = StridedCounterPublicInputs {
+            extra_opening: None,
+            ..pub_inputs.clone()
+        };
+        assert!(!verify::<F, StridedCounterAIR<F>>(
+            &proof,
+            &pub_inputs_without_opening,
+            &proof_options
+        ));
+
+        // The extra evaluations are tied to the committed parts by the DEEP composition
+        if let Some((even, _)) = &mut proof.composition_poly_extra_ood_evaluations {
+            *even = &*even + FE::one();
+        }
+        assert!(!verify::<F, StridedCounterAIR<F>>(
+            &proof,
+            &pub_inputs,
+            &proof_options
+        ));
+    }
+}
+
+#[test_log::test]
+fn test_prove_hash_chain_vdf() {
+    let proof_options = ProofOptions::default_test_options();
+    let (proof, pub_inputs) = prove_vdf(FE::from(7), 10, &proof_options).unwrap();
+
+    assert_eq!(pub_inputs.output, vdf_output(&FE::from(7), 10));
+    assert!(verify_vdf(&proof, &pub_inputs, &proof_options));
+
+    let other_output = HashChainVdfPublicInputs {
+        output: vdf_output(&FE::from(7), 9),
+        ..pub_inputs.clone()
+    };
+    assert!(!verify_vdf(&proof, &other_output, &proof_options));
+
+    let fewer_iterations = HashChainVdfPublicInputs {
+        iterations: 9,
+        ..pub_inputs
+    };
+    assert!(!verify_vdf(&proof, &fewer_iterations, &proof_options));
+
+    assert!(matches!(
+        prove_vdf(FE::from(7), 0, &proof_options),
+        Err(ProvingError::WrongParameter(_))
+    ));
+}
+
+#[test_log::test]
+fn test_prove_poseidon_merkle_path() {
+    let siblings = [FE::from(11), FE::from(13), FE::from(17)];
+    let (trace, pub_inputs) = poseidon_merkle_path_trace(FE::from(7), 0b110, &siblings);
+
+    let proof_options = ProofOptions::default_test_options();
+
+    let proof = prove::<F, PoseidonMerklePathAIR>(&trace, &pub_inputs, &proof_options).unwrap();
+    assert!(verify::<F, PoseidonMerklePathAIR>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+
+    let other_root = PoseidonMerklePathPublicInputs {
+        root: &pub_inputs.root + FE::one(),
+        ..pub_inputs.clone()
+    };
+    assert!(!verify::<F, PoseidonMerklePathAIR>(
+        &proof,
+        &other_root,
+        &proof_options
+    ));
+
+    // The root of a longer path would be past the end of the trace
+    let longer_path = PoseidonMerklePathPublicInputs {
+        depth: 8,
+        ..pub_inputs
+    };
+    assert!(!verify::<F, PoseidonMerklePathAIR>(
+        &proof,
+        &longer_path,
+        &proof_options
+    ));
+}
+
 /// Loads the program in path, runs it with the Cairo VM, and makes a proof of it
 fn test_prove_cairo_program(file_path: &str, output_range: &Option<Range<u64>>) {
     let proof_options = ProofOptions::default_test_options();
@@ -139,11 +648,128 @@ fn test_prove_cairo_simple_program() {
     test_prove_cairo_program(&cairo0_program_path("simple_program.json"), &None);
 }
 
+#[test_log::test]
+fn test_prove_cairo_programs_of_one_and_two_steps() {
+    for (file_name, num_steps) in [("empty_main.json", 1), ("two_steps.json", 2)] {
+        let program_content = std::fs::read(cairo0_program_path(file_name)).unwrap();
+        let (main_trace, pub_inputs) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof_options = ProofOptions::default_test_options();
+
+        assert_eq!(pub_inputs.num_steps, num_steps);
+        assert_eq!(main_trace.n_rows(), MIN_TRACE_LENGTH);
+
+        let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+        assert!(verify_cairo_proof(&proof, &pub_inputs, &proof_options));
+
+        let mut wrong_pub_inputs = pub_inputs.clone();
+        wrong_pub_inputs.pc_final += FE::one();
+        assert!(!verify_cairo_proof(
+            &proof,
+            &wrong_pub_inputs,
+            &proof_options
+        ));
+
+        let mut wrong_pub_inputs = pub_inputs;
+        wrong_pub_inputs.num_steps = 0;
+        assert!(!verify_cairo_proof(
+            &proof,
+            &wrong_pub_inputs,
+            &proof_options
+        ));
+    }
+}
+
 #[test_log::test]
 fn test_prove_cairo_fibonacci_5() {
     test_prove_cairo_program(&cairo0_program_path("fibonacci_5.json"), &None);
 }
 
+#[test_log::test]
+fn test_prove_cairo_fibonacci_5_recursive_friendly() {
+    let proof_options = ProofOptions::default_test_options();
+    let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+
+    let RecursionFriendlyProof { proof, felts } =
+        generate_cairo_proof_recursive_friendly(&main_trace, &pub_inputs, &proof_options).unwrap();
+
+    assert!(verify_cairo_proof_recursive_friendly(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+    assert_eq!(proof.options.commitment_hash, CommitmentHash::Poseidon);
+    assert_eq!(felts, proof.to_felts().unwrap());
+    assert_eq!(felts[0], FE::from(proof.trace_length as u64));
+
+    // The challenges of the default transcript are different
+    assert!(!verify_cairo_proof(
+        &proof,
+        &pub_inputs,
+        &proof_options.with_poseidon_commitments()
+    ));
+
+    // Proofs committed with other hashes can't be written as field elements
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+    assert!(proof.to_felts().is_err());
+}
+
+#[test_log::test]
+fn test_estimated_trace_length_sizes_the_proof_options() {
+    let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+    let estimated_trace_length =
+        CairoAIR::estimate_trace_length(&program_content, &CairoVersion::V0).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+
+    // Only the rows filling the range-check holes are left out of the estimate
+    assert!(estimated_trace_length <= main_trace.n_rows());
+    assert!(main_trace.n_rows() <= 2 * estimated_trace_length);
+
+    let proof_options = ProofOptions::default_test_options()
+        .sized_for(estimated_trace_length, 60)
+        .unwrap();
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+    assert!(verify_cairo_proof(&proof, &pub_inputs, &proof_options));
+}
+
+#[test_log::test]
+fn test_proving_plan_matches_the_proof() {
+    let program = CairoProgram::from_file(cairo0_program_path("rc_program.json")).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+    let CairoProvingPlan { num_steps, plan } = program.proving_plan(&proof_options).unwrap();
+
+    let (main_trace, pub_inputs) = program.generate_prover_args(&None).unwrap();
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+    assert_eq!(num_steps, pub_inputs.num_steps);
+
+    // The plan of the trace of the estimated length, which may be one power of two
+    // short, is the one of the proven trace
+    let air = CairoAIR::new(main_trace.n_rows(), &pub_inputs, &proof_options);
+    let exact_plan = ProvingPlan::for_air(&air).unwrap();
+    if plan.trace_length == main_trace.n_rows() {
+        assert_eq!(plan, exact_plan);
+    } else {
+        assert_eq!(2 * plan.trace_length, main_trace.n_rows());
+    }
+
+    assert_eq!(exact_plan.trace_length, proof.trace_length);
+    assert_eq!(
+        exact_plan.lde_domain_size,
+        proof.trace_length * usize::from(proof_options.blowup_factor)
+    );
+    assert_eq!(
+        exact_plan.fri_number_of_layers,
+        proof.fri_layers_merkle_caps.len()
+    );
+    assert_eq!(
+        exact_plan.num_commitments,
+        proof.lde_trace_merkle_caps.len() + 1 + proof.fri_layers_merkle_caps.len()
+    );
+}
+
 #[cfg_attr(feature = "metal", ignore)]
 #[test_log::test]
 fn test_prove_cairo_fibonacci_casm() {
@@ -151,28 +777,847 @@ fn test_prove_cairo_fibonacci_casm() {
 }
 
 #[test_log::test]
-fn test_prove_cairo_rc_program() {
-    test_prove_cairo_program(&cairo0_program_path("rc_program.json"), &None);
-}
+fn test_prove_cairo_rc_program() {
+    test_prove_cairo_program(&cairo0_program_path("rc_program.json"), &None);
+}
+
+#[test_log::test]
+fn test_prove_cairo_lt_comparison() {
+    test_prove_cairo_program(&cairo0_program_path("lt_comparison.json"), &None);
+}
+
+#[cfg_attr(feature = "metal", ignore)]
+#[test_log::test]
+fn test_prove_cairo_compare_lesser_array() {
+    test_prove_cairo_program(&cairo0_program_path("compare_lesser_array.json"), &None);
+}
+
+#[test_log::test]
+fn test_prove_cairo_output_and_rc_program() {
+    test_prove_cairo_program(&cairo0_program_path("signed_div_rem.json"), &Some(289..293));
+}
+
+#[test_log::test]
+fn test_proofs_of_programs_with_several_builtins_are_deterministic() {
+    let program_content = std::fs::read(cairo0_program_path("signed_div_rem.json")).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+    assert!(!proof_options.randomizes_trace());
+
+    let [(first_pub_inputs, first_proof), (second_pub_inputs, second_proof)] = [(); 2].map(|_| {
+        let (main_trace, pub_inputs) =
+            generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+        let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+        (pub_inputs, proof)
+    });
+
+    assert_eq!(first_pub_inputs.memory_segments.len(), 2);
+    assert!(first_pub_inputs
+        .memory_segments
+        .iter()
+        .eq(second_pub_inputs.memory_segments.iter()));
+    assert_eq!(first_proof.serialize(), second_proof.serialize());
+}
+
+#[test_log::test]
+fn test_execution_result_contains_program_outputs() {
+    let program_content = std::fs::read(cairo0_program_path("signed_div_rem.json")).unwrap();
+    let output_range = 289..293;
+    let execution_result = generate_prover_args_with_hint_config(
+        &program_content,
+        &CairoVersion::V0,
+        &Some(output_range.clone()),
+        &HintConfig::default(),
+    )
+    .unwrap();
+
+    let expected_outputs: Vec<FE> = output_range
+        .map(|addr| execution_result.pub_inputs.public_memory[&FE::from(addr)])
+        .collect();
+    assert_eq!(execution_result.outputs, expected_outputs);
+    assert_eq!(execution_result.outputs.len(), 4);
+}
+
+#[test_log::test]
+fn test_program_outputs_are_streamed_while_running() {
+    let program_content = std::fs::read(cairo0_program_path("output_then_loop.json")).unwrap();
+    let mut streamed_outputs = vec![];
+    let execution_result = generate_prover_args_streaming_outputs(
+        &program_content,
+        &CairoVersion::V0,
+        &None,
+        &HintConfig::default(),
+        &ExecutionLimits::unlimited(),
+        &mut |output| streamed_outputs.push(output),
+    )
+    .unwrap();
+
+    assert_eq!(streamed_outputs, vec![FE::from(1234), FE::from(5678)]);
+    assert_eq!(streamed_outputs, execution_result.outputs);
+
+    // The run is stopped after the first output was written, which is only sent if
+    // it's streamed between the chunks of steps
+    let mut streamed_outputs = vec![];
+    let result = generate_prover_args_streaming_outputs(
+        &program_content,
+        &CairoVersion::V0,
+        &None,
+        &HintConfig::default(),
+        &ExecutionLimits::unlimited().with_max_steps(70_000),
+        &mut |output| streamed_outputs.push(output),
+    );
+
+    assert!(matches!(
+        result,
+        Err(Error::LimitExceeded(LimitExceeded::Steps(70_000)))
+    ));
+    assert_eq!(streamed_outputs, vec![FE::from(1234)]);
+}
+
+#[test_log::test]
+fn test_program_outputs_are_opened_against_the_output_commitment() {
+    let program_content = std::fs::read(cairo0_program_path("signed_div_rem.json")).unwrap();
+    let execution_result = generate_prover_args_with_hint_config(
+        &program_content,
+        &CairoVersion::V0,
+        &Some(289..293),
+        &HintConfig::default(),
+    )
+    .unwrap();
+    let pub_inputs = &execution_result.pub_inputs;
+    let output_commitment = pub_inputs.output_commitment().unwrap();
+
+    for (index, output) in execution_result.outputs.iter().enumerate() {
+        let opening = pub_inputs.open_output(index).unwrap();
+        assert_eq!(&opening.value, output);
+        assert!(opening.verify(&output_commitment));
+    }
+    assert!(pub_inputs
+        .open_output(execution_result.outputs.len())
+        .is_none());
+}
+
+#[test_log::test]
+fn test_cairo_proofs_are_verified_against_the_program_and_output_hashes() {
+    let program_content = std::fs::read(cairo0_program_path("signed_div_rem.json")).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &Some(289..293)).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+    let statement = pub_inputs.statement();
+    let container = CairoProofContainer::new(proof, pub_inputs);
+
+    assert!(verify_cairo_proof_with_statement(
+        &container,
+        &statement,
+        &proof_options
+    ));
+
+    let mut other_statement = statement;
+    other_statement.output_hash[0] ^= 1;
+    assert!(!verify_cairo_proof_with_statement(
+        &container,
+        &other_statement,
+        &proof_options
+    ));
+
+    // Public inputs with another output don't hash to the statement anymore
+    let mut other_container = CairoProofContainer::new(container.proof, container.pub_inputs);
+    let output_address = FE::from(289);
+    let output = other_container.pub_inputs.public_memory[&output_address];
+    other_container
+        .pub_inputs
+        .public_memory
+        .insert(output_address, output + FE::one());
+    assert!(!verify_cairo_proof_with_statement(
+        &other_container,
+        &statement,
+        &proof_options
+    ));
+
+    // An output segment spanning the whole address space is rejected without walking it
+    let mut other_container =
+        CairoProofContainer::new(other_container.proof, other_container.pub_inputs);
+    other_container
+        .pub_inputs
+        .memory_segments
+        .insert(MemorySegment::Output, 0..u64::MAX);
+    assert!(!verify_cairo_proof_with_statement(
+        &other_container,
+        &statement,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_memory_left_in_segments_is_relocated_as_cairo_vm_does() {
+    let program_content = std::fs::read(cairo0_program_path("signed_div_rem.json")).unwrap();
+    let (register_states, memory, program_size, memory_segments, _) = run_program_with_hint_config(
+        None,
+        CairoLayout::Small,
+        &program_content,
+        &CairoVersion::V0,
+        &HintConfig::default(),
+    )
+    .unwrap();
+
+    let (
+        segmented_register_states,
+        segmented_memory,
+        segmented_program_size,
+        segmented_memory_segments,
+        _,
+    ) = run_program_without_relocation(
+        None,
+        CairoLayout::Small,
+        &program_content,
+        &HintConfig::default(),
+    )
+    .unwrap();
+
+    assert_eq!(segmented_register_states, register_states);
+    assert_eq!(segmented_memory, memory);
+    assert_eq!(segmented_program_size, program_size);
+    assert_eq!(segmented_memory_segments, memory_segments);
+}
+
+/// Runs, proves and verifies a Cairo 0 program, failing with the crate error.
+fn run_prove_and_verify(
+    program_content: &[u8],
+    proof_options: &ProofOptions,
+) -> Result<(), lambdaworks_stark::Error> {
+    let (main_trace, pub_inputs) = generate_prover_args(program_content, &CairoVersion::V0, &None)?;
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, proof_options)?;
+    try_verify_cairo_proof(&proof, &pub_inputs, proof_options)?;
+    Ok(())
+}
+
+#[test_log::test]
+fn test_errors_of_every_step_convert_to_the_crate_error() {
+    let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+    run_prove_and_verify(&program_content, &proof_options).unwrap();
+
+    assert!(matches!(
+        run_prove_and_verify(b"not a program", &proof_options),
+        Err(lambdaworks_stark::Error::Runner(_))
+    ));
+
+    let mut wrong_options = proof_options.clone();
+    wrong_options.blowup_factor = 6;
+    assert!(matches!(
+        run_prove_and_verify(&program_content, &wrong_options),
+        Err(lambdaworks_stark::Error::Proving(
+            ProvingError::WrongParameter(_)
+        ))
+    ));
+
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+    let mut other_pub_inputs = pub_inputs.clone();
+    other_pub_inputs.ap_final = other_pub_inputs.ap_final + FE::one();
+    let error = try_verify_cairo_proof(&proof, &other_pub_inputs, &proof_options).unwrap_err();
+    assert!(matches!(
+        lambdaworks_stark::Error::from(error),
+        lambdaworks_stark::Error::Verification(_)
+    ));
+}
+
+#[test_log::test]
+fn test_builtin_segments_are_detected_from_the_program() {
+    let program_content = std::fs::read(cairo0_program_path("signed_div_rem.json")).unwrap();
+    let (_, pub_inputs) = generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+
+    assert_eq!(
+        pub_inputs.memory_segments.get(&MemorySegment::Output),
+        Some(&(289..293))
+    );
+    assert!(pub_inputs
+        .memory_segments
+        .contains_key(&MemorySegment::RangeCheck));
+}
+
+#[test_log::test]
+fn test_prove_cairo_output_program_without_output_range() {
+    test_prove_cairo_program(&cairo0_program_path("output_program.json"), &None);
+}
+
+#[test_log::test]
+fn test_layout_without_the_program_builtins_is_rejected() {
+    let program_content = std::fs::read(cairo0_program_path("rc_program.json")).unwrap();
+    let result = run_program(
+        None,
+        CairoLayout::Plain,
+        &program_content,
+        &CairoVersion::V0,
+    );
+
+    assert!(matches!(
+        result,
+        Err(Error::UnsupportedBuiltin(
+            CairoBuiltin::RangeCheck,
+            CairoLayout::Plain
+        ))
+    ));
+}
+
+#[test_log::test]
+fn test_prove_rap_fib() {
+    let steps = 16;
+    let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
+
+    let proof_options = ProofOptions::default_test_options();
+
+    let pub_inputs = FibonacciRAPPublicInputs {
+        steps,
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let proof = prove::<F, FibonacciRAP<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    assert!(verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_prove_rap_fib_with_challenge_in_extension() {
+    let steps = 16;
+    let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
+
+    let proof_options = ProofOptions::default_test_options();
+
+    let pub_inputs = FibonacciRAPPublicInputs {
+        steps,
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let proof = prove::<F, FibonacciRAPExtension<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    assert!(verify::<F, FibonacciRAPExtension<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_prove_rap_fib_with_column_grouped_commitments() {
+    let steps = 16;
+    let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
+
+    let proof_options = ProofOptions {
+        commitment_layout: CommitmentLayout::ColumnGrouped { group_size: 1 },
+        ..ProofOptions::default_test_options()
+    };
+
+    let pub_inputs = FibonacciRAPPublicInputs {
+        steps,
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let proof = prove::<F, FibonacciRAP<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    assert_eq!(proof.lde_trace_merkle_caps.len(), 3);
+    assert!(verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+    assert!(!verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &ProofOptions::default_test_options()
+    ));
+}
+
+#[test_log::test]
+fn test_proof_batches_every_deep_term_into_a_single_fri_instance() {
+    let steps = 16;
+    let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
+
+    let proof_options = ProofOptions::default_test_options();
+
+    let pub_inputs = FibonacciRAPPublicInputs {
+        steps,
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let proof = prove::<F, FibonacciRAP<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+
+    // A root for the main trace, one for the auxiliary trace, a single one for
+    // both parts of the composition polynomial and one for each FRI layer
+    let fri_layers = proof_options.fri_number_of_layers(proof.trace_length);
+    assert_eq!(proof.lde_trace_merkle_caps.len(), 2);
+    assert_eq!(proof.fri_layers_merkle_caps.len(), fri_layers);
+
+    // p₀ is only opened at the symmetric element of each query
+    for decommitment in &proof.query_list {
+        assert_eq!(decommitment.layers_evaluations_sym.len(), fri_layers);
+        assert_eq!(decommitment.layers_auth_paths_sym.len(), fri_layers);
+        assert_eq!(decommitment.layers_evaluations.len(), fri_layers - 1);
+        assert_eq!(decommitment.layers_auth_paths.len(), fri_layers - 1);
+    }
+    for openings in &proof.deep_poly_openings {
+        assert_eq!(openings.lde_trace_merkle_proofs.len(), 2);
+    }
+
+    assert!(verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_merkle_caps_shorten_the_paths_of_the_openings() {
+    let steps = 16;
+    let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
+    let pub_inputs = FibonacciRAPPublicInputs {
+        steps,
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let root_options = ProofOptions::default_test_options();
+    let cap_options = ProofOptions {
+        merkle_cap_height: 2,
+        ..root_options.clone()
+    };
+    let root_proof = prove::<F, FibonacciRAP<F>>(&trace, &pub_inputs, &root_options).unwrap();
+    let proof = prove::<F, FibonacciRAP<F>>(&trace, &pub_inputs, &cap_options).unwrap();
+
+    assert_eq!(proof.composition_poly_cap.len(), 4);
+    assert!(proof.lde_trace_merkle_caps.iter().all(|cap| cap.len() == 4));
+    let path_len = |proof: &StarkProof<F>| {
+        proof.deep_poly_openings[0]
+            .lde_composition_poly_proof
+            .merkle_path
+            .len()
+    };
+    assert_eq!(path_len(&proof) + 2, path_len(&root_proof));
+
+    assert!(verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &cap_options
+    ));
+    // The shape of the caps is given by the options
+    assert!(!verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &root_options
+    ));
+
+    let mut tampered_proof = proof;
+    tampered_proof.composition_poly_cap[3][0] ^= 1;
+    assert!(!verify::<F, FibonacciRAP<F>>(
+        &tampered_proof,
+        &pub_inputs,
+        &cap_options
+    ));
+}
+
+#[cfg(feature = "mmap")]
+#[test_log::test]
+fn test_prove_rap_fib_with_merkle_trees_on_disk() {
+    let steps = 16;
+    let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
+    let pub_inputs = FibonacciRAPPublicInputs {
+        steps,
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let in_memory_options = ProofOptions::default_test_options();
+    let on_disk_options = ProofOptions {
+        merkle_tree_storage: MerkleTreeStorage::OnDisk {
+            directory: std::env::temp_dir(),
+            cached_levels: 2,
+        },
+        ..ProofOptions::default_test_options()
+    };
+
+    // The storage of the trees doesn't change the proof
+    let proof = prove::<F, FibonacciRAP<F>>(&trace, &pub_inputs, &on_disk_options).unwrap();
+    let in_memory_proof =
+        prove::<F, FibonacciRAP<F>>(&trace, &pub_inputs, &in_memory_options).unwrap();
+    assert_eq!(proof.serialize(), in_memory_proof.serialize());
+    assert!(verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &on_disk_options
+    ));
+}
+
+#[cfg(feature = "blake3")]
+#[test_log::test]
+fn test_prove_rap_fib_with_blake3_commitments() {
+    let steps = 16;
+    let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
+
+    let proof_options = ProofOptions {
+        commitment_hash: CommitmentHash::Blake3,
+        ..ProofOptions::default_test_options()
+    };
+
+    let pub_inputs = FibonacciRAPPublicInputs {
+        steps,
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let proof = prove::<F, FibonacciRAP<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    assert!(verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+    assert!(!verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &ProofOptions::default_test_options()
+    ));
+}
+
+#[test_log::test]
+fn test_prove_fib_with_deduplicated_queries() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+
+    // 40 queries in an LDE domain of 32 elements are bound to repeat some index
+    let proof_options = ProofOptions {
+        fri_number_of_queries: 40,
+        deduplicate_queries: true,
+        ..ProofOptions::default_test_options()
+    };
+
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let proof = prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    assert!(proof.query_list.len() < proof_options.fri_number_of_queries);
+    assert_eq!(proof.deep_poly_openings.len(), proof.query_list.len());
+    assert!(verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+
+    let options_without_deduplication = ProofOptions {
+        deduplicate_queries: false,
+        ..proof_options
+    };
+    assert!(!verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &options_without_deduplication
+    ));
+}
+
+#[test_log::test]
+fn test_verifier_rejects_proofs_with_missing_openings() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+    let proof_options = ProofOptions::default_test_options();
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+    let prove_fib = || prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+
+    let proof = prove_fib();
+    assert_eq!(proof.query_list.len(), proof_options.fri_number_of_queries);
+    assert!(verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+
+    let mut proof = prove_fib();
+    proof.query_list.pop();
+    proof.deep_poly_openings.pop();
+    assert!(!verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+
+    let mut proof = prove_fib();
+    proof.query_list[0].layers_evaluations_sym.pop();
+    proof.query_list[0].layers_auth_paths_sym.pop();
+    assert!(!verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+
+    let mut proof = prove_fib();
+    proof.deep_poly_openings[0].lde_trace_evaluations.pop();
+    assert!(!verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+
+    let mut proof = prove_fib();
+    let first_row = proof.trace_ood_frame_evaluations.get_row(0).to_vec();
+    proof.trace_ood_frame_evaluations = Frame::new(first_row, 1);
+    assert!(!verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_partial_verification_checks_each_part_of_the_proof() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+    let proof_options = ProofOptions::default_test_options();
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+    let prove_fib = || prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    let partial_results = |proof: &StarkProof<F>| {
+        (
+            verify_commitments::<F, FibonacciAIR<F>>(proof, &pub_inputs, &proof_options),
+            verify_deep_only::<F, FibonacciAIR<F>>(proof, &pub_inputs, &proof_options),
+            verify_fri_only::<F, FibonacciAIR<F>>(proof, &pub_inputs, &proof_options),
+        )
+    };
+
+    let proof = prove_fib();
+    assert_eq!(partial_results(&proof), (true, true, true));
+
+    // The opened values are not part of the transcript, so changing one of them
+    // only breaks the openings and the DEEP composition polynomial built from it
+    let mut proof = prove_fib();
+    proof.deep_poly_openings[0].lde_trace_evaluations[0] += FE::one();
+    assert_eq!(partial_results(&proof), (false, true, false));
+
+    let mut proof = prove_fib();
+    proof.query_list[0].layers_evaluations_sym[0] += FE::one();
+    assert_eq!(partial_results(&proof), (true, true, false));
+    assert!(!verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_prove_fib_with_fewer_fri_layers() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+
+    let proof_options = ProofOptions {
+        fri_max_layers: Some(1),
+        ..ProofOptions::default_test_options()
+    };
+
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let proof = prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    assert_eq!(proof.fri_layers_merkle_caps.len(), 1);
+    assert_eq!(proof.fri_last_poly.len(), 4);
+    assert!(verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+    assert!(!verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &ProofOptions::default_test_options()
+    ));
+}
+
+#[test_log::test]
+fn test_prove_rap_fib_with_salted_commitments() {
+    let steps = 16;
+    let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
+
+    let proof_options = ProofOptions {
+        salt_commitments: true,
+        ..ProofOptions::default_test_options()
+    };
+
+    let pub_inputs = FibonacciRAPPublicInputs {
+        steps,
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let proof = prove::<F, FibonacciRAP<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    let opening = &proof.deep_poly_openings[0];
+    assert!(opening.lde_composition_poly_salt.is_some());
+    assert_eq!(
+        opening.lde_trace_salts.len(),
+        proof.lde_trace_merkle_caps.len()
+    );
+    assert!(verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+
+    // Leaves can't be opened without their salts
+    assert!(!verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &ProofOptions::default_test_options()
+    ));
+
+    let mut proof = proof;
+    proof.deep_poly_openings[0].lde_trace_salts[0][0] ^= 1;
+    assert!(!verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_prove_fib_with_zero_knowledge() {
+    let randomization_rows = 4;
+    let trace =
+        simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 32 - randomization_rows);
+
+    let proof_options =
+        ProofOptions::default_test_options().with_zero_knowledge(randomization_rows);
+
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let proof = prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    assert_eq!(proof.trace_length, 32);
+    assert!(proof.composition_poly_mask_ood_evaluation.is_some());
+    assert!(proof.deep_poly_openings[0]
+        .lde_composition_poly_mask_evaluation
+        .is_some());
+    assert!(verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+
+    // Verifiers not expecting the random rows and the mask reject the proof
+    let salted_options = ProofOptions {
+        salt_commitments: true,
+        ..ProofOptions::default_test_options()
+    };
+    assert!(!verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &salted_options
+    ));
+
+    let mut proof = proof;
+    proof.composition_poly_mask_ood_evaluation = Some(FE::from(7));
+    assert!(!verify::<F, FibonacciAIR<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_randomized_trace_length_must_be_a_power_of_two() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 32);
+    let proof_options = ProofOptions::default_test_options().with_zero_knowledge(4);
+
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    assert!(matches!(
+        prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options),
+        Err(ProvingError::WrongParameter(_))
+    ));
+}
+
+#[test_log::test]
+fn test_prove_rap_fib_with_seeded_challenges() {
+    let steps = 16;
+    let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
+
+    let proof_options = ProofOptions::default_test_options();
+
+    let pub_inputs = FibonacciRAPPublicInputs {
+        steps,
+        a0: FE::one(),
+        a1: FE::one(),
+    };
+
+    let seed = [7; 32];
+    let proof = prove_interactive::<F, FibonacciRAP<F>, _>(
+        &trace,
+        &pub_inputs,
+        &proof_options,
+        SeededChallengeProvider::new(seed),
+    )
+    .unwrap();
+
+    assert!(verify_interactive::<F, FibonacciRAP<F>, _>(
+        &proof,
+        &pub_inputs,
+        &proof_options,
+        SeededChallengeProvider::new(seed)
+    ));
+    assert!(!verify_interactive::<F, FibonacciRAP<F>, _>(
+        &proof,
+        &pub_inputs,
+        &proof_options,
+        SeededChallengeProvider::new([8; 32])
+    ));
+    assert!(!verify::<F, FibonacciRAP<F>>(
+        &proof,
+        &pub_inputs,
+        &proof_options
+    ));
+}
+
+#[cfg(not(feature = "test_fiat_shamir"))]
+#[test_log::test]
+fn test_interactive_proof_with_fiat_shamir_challenges_matches_the_non_interactive_one() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
 
-#[test_log::test]
-fn test_prove_cairo_lt_comparison() {
-    test_prove_cairo_program(&cairo0_program_path("lt_comparison.json"), &None);
-}
+    let proof_options = ProofOptions::default_test_options();
 
-#[cfg_attr(feature = "metal", ignore)]
-#[test_log::test]
-fn test_prove_cairo_compare_lesser_array() {
-    test_prove_cairo_program(&cairo0_program_path("compare_lesser_array.json"), &None);
-}
+    let pub_inputs = FibonacciPublicInputs {
+        a0: FE::one(),
+        a1: FE::one(),
+    };
 
-#[test_log::test]
-fn test_prove_cairo_output_and_rc_program() {
-    test_prove_cairo_program(&cairo0_program_path("signed_div_rem.json"), &Some(289..293));
+    let proof = prove::<F, FibonacciAIR<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    let interactive_proof = prove_interactive::<F, FibonacciAIR<F>, _>(
+        &trace,
+        &pub_inputs,
+        &proof_options,
+        DefaultTranscript::new(),
+    )
+    .unwrap();
+
+    assert_eq!(proof.serialize(), interactive_proof.serialize());
+    assert!(verify_interactive::<F, FibonacciAIR<F>, _>(
+        &proof,
+        &pub_inputs,
+        &proof_options,
+        DefaultTranscript::new()
+    ));
 }
 
 #[test_log::test]
-fn test_prove_rap_fib() {
+fn test_prove_multi_round_rap_fib() {
     let steps = 16;
     let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], steps);
 
@@ -184,8 +1629,9 @@ fn test_prove_rap_fib() {
         a1: FE::one(),
     };
 
-    let proof = prove::<F, FibonacciRAP<F>>(&trace, &pub_inputs, &proof_options).unwrap();
-    assert!(verify::<F, FibonacciRAP<F>>(
+    let proof = prove::<F, FibonacciMultiRAP<F>>(&trace, &pub_inputs, &proof_options).unwrap();
+    assert_eq!(proof.lde_trace_merkle_caps.len(), 3);
+    assert!(verify::<F, FibonacciMultiRAP<F>>(
         &proof,
         &pub_inputs,
         &proof_options
@@ -240,6 +1686,47 @@ fn test_verifier_rejects_proof_with_different_range_bounds() {
     assert!(!verify_cairo_proof(&proof, &pub_inputs, &proof_options));
 }
 
+#[test_log::test]
+fn test_verifier_rejects_invalid_range_bounds() {
+    let program_content = std::fs::read(cairo0_program_path("simple_program.json")).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+
+    let proof_options = ProofOptions::default_test_options();
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+    assert!(verify_cairo_proof(&proof, &pub_inputs, &proof_options));
+
+    // The generic verifier rejects them too, without the checks of the Cairo wrapper
+    let mut wrong_pub_inputs = pub_inputs.clone();
+    wrong_pub_inputs.range_check_max = None;
+    assert!(!verify_cairo_proof(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+    assert!(!verify::<F, CairoAIR>(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+
+    let mut wrong_pub_inputs = pub_inputs;
+    std::mem::swap(
+        &mut wrong_pub_inputs.range_check_min,
+        &mut wrong_pub_inputs.range_check_max,
+    );
+    assert!(!verify_cairo_proof(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+    assert!(!verify::<F, CairoAIR>(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+}
+
 #[test_log::test]
 fn test_verifier_rejects_proof_with_changed_range_check_value() {
     // In this test we change the range-check value in the trace, so the constraint
@@ -265,6 +1752,121 @@ fn test_verifier_rejects_proof_with_changed_range_check_value() {
     assert!(!verify_cairo_proof(&proof, &pub_inputs, &proof_options));
 }
 
+#[test_log::test]
+fn test_execution_summary_records_executed_hints() {
+    let program_content = std::fs::read(cairo0_program_path("rc_program.json")).unwrap();
+    let execution_result = generate_prover_args_with_hint_config(
+        &program_content,
+        &CairoVersion::V0,
+        &None,
+        &HintConfig::default(),
+    )
+    .unwrap();
+
+    assert!(execution_result.execution_summary.has_hints());
+
+    let proof_options = ProofOptions::default_test_options();
+    let proof = generate_cairo_proof(
+        &execution_result.main_trace,
+        &execution_result.pub_inputs,
+        &proof_options,
+    )
+    .unwrap();
+    assert!(verify_cairo_proof(
+        &proof,
+        &execution_result.pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_deny_hint_policy_rejects_programs_with_hints() {
+    let program_content = std::fs::read(cairo0_program_path("rc_program.json")).unwrap();
+    let result = generate_prover_args_with_hint_config(
+        &program_content,
+        &CairoVersion::V0,
+        &None,
+        &HintConfig::new(HintPolicy::Deny),
+    );
+
+    assert!(matches!(result, Err(Error::DisallowedHint(_))));
+}
+
+#[test_log::test]
+fn test_sandboxed_execution_stops_at_the_step_limit() {
+    let program_content = std::fs::read(cairo0_program_path("fibonacci_1000.json")).unwrap();
+    let result = generate_prover_args_sandboxed(
+        &program_content,
+        &CairoVersion::V0,
+        &None,
+        &HintConfig::default(),
+        &ExecutionLimits::unlimited().with_max_steps(100),
+    );
+
+    assert!(matches!(
+        result,
+        Err(Error::LimitExceeded(LimitExceeded::Steps(100)))
+    ));
+}
+
+#[test_log::test]
+fn test_sandboxed_cairo1_execution_stops_at_the_step_limit() {
+    let program_content = std::fs::read(cairo1_program_path("fibonacci_cairo1.casm")).unwrap();
+    let result = generate_prover_args_sandboxed(
+        &program_content,
+        &CairoVersion::V1,
+        &None,
+        &HintConfig::default(),
+        &ExecutionLimits::unlimited().with_max_steps(10),
+    );
+
+    assert!(matches!(
+        result,
+        Err(Error::LimitExceeded(LimitExceeded::Steps(10)))
+    ));
+}
+
+#[test_log::test]
+fn test_malformed_cairo1_program_is_an_error() {
+    let result = generate_prover_args_sandboxed(
+        b"{ \"bytecode\": 1 }",
+        &CairoVersion::V1,
+        &None,
+        &HintConfig::default(),
+        &ExecutionLimits::unlimited(),
+    );
+
+    assert!(matches!(result, Err(Error::ProgramParsing(_))));
+}
+
+#[test_log::test]
+fn test_prove_cairo_program_within_execution_limits() {
+    let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+    let execution_result = generate_prover_args_sandboxed(
+        &program_content,
+        &CairoVersion::V0,
+        &None,
+        &HintConfig::default(),
+        &ExecutionLimits::unlimited()
+            .with_max_steps(1000)
+            .with_max_memory_cells(1000),
+    )
+    .unwrap();
+
+    let proof_options = ProofOptions::default_test_options();
+    let proof = generate_cairo_proof(
+        &execution_result.main_trace,
+        &execution_result.pub_inputs,
+        &proof_options,
+    )
+    .unwrap();
+    assert!(verify_cairo_proof(
+        &proof,
+        &execution_result.pub_inputs,
+        &proof_options
+    ));
+}
+
 #[test_log::test]
 fn test_verifier_rejects_proof_with_overflowing_range_check_value() {
     // In this test we manually insert a value greater than 2^128 in the range-check builtin segment.
@@ -301,6 +1903,64 @@ fn test_verifier_rejects_proof_with_overflowing_range_check_value() {
     assert!(!verify_cairo_proof(&proof, &pub_inputs, &proof_options));
 }
 
+#[test_log::test]
+fn test_prove_cairo_program_with_range_check96_segment() {
+    // The second value checked by the range-check builtin of the program is checked by
+    // the range-check96 builtin instead, so the trace has the columns of both builtins.
+    let program_content = std::fs::read(cairo0_program_path("rc_program.json")).unwrap();
+    let (register_states, mut memory, program_size, _) = run_program(
+        None,
+        CairoLayout::Small,
+        &program_content,
+        &CairoVersion::V0,
+    )
+    .unwrap();
+    let memory_segments = MemorySegmentMap::from([
+        (MemorySegment::RangeCheck, 27..28),
+        (MemorySegment::RangeCheck96, 28..29),
+    ]);
+    let proof_options = ProofOptions::default_test_options();
+
+    let mut pub_inputs =
+        PublicInputs::from_regs_and_mem(&register_states, &memory, program_size, &memory_segments);
+    assert_eq!(pub_inputs.builtins, ["range_check", "range_check96"]);
+    let main_trace = build_main_trace(&register_states, &memory, &mut pub_inputs);
+
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+    assert!(verify_cairo_proof(&proof, &pub_inputs, &proof_options));
+
+    // A value of 128 bits passes the range-check builtin, but not the range-check96 one
+    let overflowing_rc96_value = FE::from_hex("0x1000000000000000000000001").unwrap();
+    memory.data.insert(28, overflowing_rc96_value);
+
+    let mut pub_inputs =
+        PublicInputs::from_regs_and_mem(&register_states, &memory, program_size, &memory_segments);
+    let malicious_trace = build_main_trace(&register_states, &memory, &mut pub_inputs);
+
+    let proof = generate_cairo_proof(&malicious_trace, &pub_inputs, &proof_options).unwrap();
+    assert!(!verify_cairo_proof(&proof, &pub_inputs, &proof_options));
+}
+
+#[test_log::test]
+fn test_verifier_rejects_proof_with_other_builtins() {
+    let program_content = std::fs::read(cairo0_program_path("output_program.json")).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+    assert!(verify_cairo_proof(&proof, &pub_inputs, &proof_options));
+
+    // The builtins are only bound to the proof by the public parameters of the AIR
+    let mut other_pub_inputs = pub_inputs.clone();
+    other_pub_inputs.builtins.clear();
+    assert!(!verify_cairo_proof(
+        &proof,
+        &other_pub_inputs,
+        &proof_options
+    ));
+}
+
 #[test_log::test]
 fn test_verifier_rejects_proof_with_changed_output() {
     let program_content = std::fs::read(cairo0_program_path("output_program.json")).unwrap();
@@ -333,10 +1993,206 @@ fn test_verifier_rejects_proof_with_changed_output() {
     malicious_trace_columns[output_col_idx] = output_column;
 
     let malicious_trace = TraceTable::new_from_cols(&malicious_trace_columns);
-    let proof = generate_cairo_proof(&malicious_trace, &pub_inputs, &proof_options).unwrap();
+    // `generate_cairo_proof` rejects traces not matching the public inputs, so the
+    // prover is called directly to check that the verifier rejects it too.
+    let proof = prove::<F, CairoAIR>(&malicious_trace, &pub_inputs, &proof_options).unwrap();
     assert!(!verify_cairo_proof(&proof, &pub_inputs, &proof_options));
 }
 
+#[test_log::test]
+fn test_prover_rejects_public_inputs_not_matching_the_trace() {
+    let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+
+    let mut wrong_pub_inputs = pub_inputs.clone();
+    wrong_pub_inputs.ap_final += FE::one();
+    assert!(generate_cairo_proof(&main_trace, &wrong_pub_inputs, &proof_options).is_err());
+
+    let mut wrong_pub_inputs = pub_inputs.clone();
+    wrong_pub_inputs.num_steps -= 1;
+    assert!(generate_cairo_proof(&main_trace, &wrong_pub_inputs, &proof_options).is_err());
+
+    let mut wrong_pub_inputs = pub_inputs.clone();
+    wrong_pub_inputs.range_check_max = Some(wrong_pub_inputs.range_check_max.unwrap() + 1);
+    assert!(generate_cairo_proof(&main_trace, &wrong_pub_inputs, &proof_options).is_err());
+
+    let mut wrong_pub_inputs = pub_inputs;
+    let first_instruction = wrong_pub_inputs
+        .public_memory
+        .get_mut(&FE::from(1))
+        .unwrap();
+    *first_instruction += FE::one();
+    assert!(generate_cairo_proof(&main_trace, &wrong_pub_inputs, &proof_options).is_err());
+}
+
+#[test_log::test]
+fn test_verifier_rejects_addresses_out_of_the_address_space() {
+    let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+
+    assert!(verify_cairo_proof_with_address_space(
+        &proof,
+        &pub_inputs,
+        &proof_options,
+        16
+    ));
+    assert!(!verify_cairo_proof_with_address_space(
+        &proof,
+        &pub_inputs,
+        &proof_options,
+        4
+    ));
+
+    let mut wrong_pub_inputs = pub_inputs;
+    wrong_pub_inputs.memory_address_max = Some(u64::MAX);
+    assert!(!verify_cairo_proof(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_verifier_rejects_builtin_segments_out_of_the_memory_addresses() {
+    let program_content = std::fs::read(cairo0_program_path("signed_div_rem.json")).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+    assert!(verify::<F, CairoAIR>(&proof, &pub_inputs, &proof_options));
+
+    let mut wrong_pub_inputs = pub_inputs.clone();
+    wrong_pub_inputs.memory_address_max = None;
+    assert!(!verify::<F, CairoAIR>(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+
+    let memory_address_max = pub_inputs.memory_address_max.unwrap();
+    let mut wrong_pub_inputs = pub_inputs;
+    wrong_pub_inputs.memory_segments.insert(
+        MemorySegment::Output,
+        memory_address_max..memory_address_max + 2,
+    );
+    assert!(!verify::<F, CairoAIR>(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_prove_cairo_program_with_memory_holes() {
+    let program_content = std::fs::read(cairo0_program_path("memory_holes.json")).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+
+    // At least the three cells skipped by the program
+    assert!(pub_inputs.num_memory_holes >= 3);
+    assert!(
+        pub_inputs.num_steps + pub_inputs.memory_hole_rows() + pub_inputs.memory_padding.num_rows()
+            <= main_trace.n_rows()
+    );
+
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+    assert!(verify_cairo_proof(&proof, &pub_inputs, &proof_options));
+
+    // The number of holes is absorbed in the transcript
+    let mut wrong_pub_inputs = pub_inputs.clone();
+    wrong_pub_inputs.num_memory_holes -= 1;
+    assert!(!verify_cairo_proof(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+
+    // and the rows filling them must fit in the trace
+    let mut wrong_pub_inputs = pub_inputs;
+    wrong_pub_inputs.num_memory_holes = main_trace.n_rows() * 4;
+    assert!(!verify_cairo_proof(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_memory_padding_is_bound_to_the_proof() {
+    let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+
+    let padding = &pub_inputs.memory_padding;
+    assert!(padding.dummy_accesses > pub_inputs.public_memory.len());
+    assert_eq!(padding.dummy_accesses % 4, 0);
+    assert!(pub_inputs.num_steps + padding.num_rows() <= main_trace.n_rows());
+
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+    assert!(verify_cairo_proof(&proof, &pub_inputs, &proof_options));
+
+    // Counts fitting in the trace are absorbed in the transcript, so the challenges differ
+    let mut wrong_pub_inputs = pub_inputs.clone();
+    wrong_pub_inputs.memory_padding.padding_rows /= 2;
+    wrong_pub_inputs.memory_padding.dummy_accesses += 4;
+    assert!(!verify_cairo_proof(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+
+    let mut wrong_pub_inputs = pub_inputs.clone();
+    wrong_pub_inputs.memory_padding.dummy_accesses = pub_inputs.public_memory.len() - 1;
+    assert!(!verify_cairo_proof(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+    assert!(generate_cairo_proof(&main_trace, &wrong_pub_inputs, &proof_options).is_err());
+
+    let mut wrong_pub_inputs = pub_inputs;
+    wrong_pub_inputs.memory_padding.padding_rows = main_trace.n_rows();
+    assert!(!verify_cairo_proof(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+}
+
+#[test_log::test]
+fn test_proofs_of_unsupported_builtins_are_rejected() {
+    let program_content = std::fs::read(cairo0_program_path("fibonacci_5.json")).unwrap();
+    let (main_trace, pub_inputs) =
+        generate_prover_args(&program_content, &CairoVersion::V0, &None).unwrap();
+    let proof_options = ProofOptions::default_test_options();
+    let proof = generate_cairo_proof(&main_trace, &pub_inputs, &proof_options).unwrap();
+    assert!(pub_inputs.unsupported_builtins().is_empty());
+
+    let mut wrong_pub_inputs = pub_inputs;
+    wrong_pub_inputs.builtins.push("bitwise".to_string());
+    wrong_pub_inputs.builtins.push("range_check96".to_string());
+    assert_eq!(
+        wrong_pub_inputs.unsupported_builtins(),
+        ["bitwise", "range_check96"]
+    );
+    assert!(!verify_cairo_proof(
+        &proof,
+        &wrong_pub_inputs,
+        &proof_options
+    ));
+    assert!(matches!(
+        generate_cairo_proof(&main_trace, &wrong_pub_inputs, &proof_options),
+        Err(ProvingError::WrongParameter(error)) if error.contains("bitwise, range_check96")
+    ));
+}
+
 #[test_log::test]
 fn test_verifier_rejects_proof_with_different_security_params() {
     let program_content = std::fs::read(cairo0_program_path("output_program.json")).unwrap();
@@ -0,0 +1,342 @@
+//! Differential tests against the Stone prover. The same Cairo 0 programs are
+//! proven with both provers, each proof is checked by its own verifier, and the
+//! public inputs of the executions are compared to catch divergences between the
+//! Cairo AIR of this crate and Stone's.
+//!
+//! They need the `cairo-lang` toolchain (see `requirements.txt`) and the Stone
+//! binaries. The paths of the binaries are read from the `STONE_CPU_AIR_PROVER` and
+//! `STONE_CPU_AIR_VERIFIER` environment variables, falling back to the binaries in
+//! the `PATH`. Run them with:
+//!
+//!     cargo test --release -F stone --test stone_differential -- --nocapture
+#![cfg(feature = "stone")]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use lambdaworks_math::traits::Serializable;
+use lambdaworks_stark::{
+    cairo::{
+        air::{generate_cairo_proof, verify_cairo_proof, MemorySegment, PublicInputs},
+        runner::hints::HintConfig,
+        runner::run::{generate_prover_args_with_hint_config, CairoVersion},
+    },
+    starks::proof::options::ProofOptions,
+    FE,
+};
+use serde_json::{json, Value};
+
+const CAIRO0_PROGRAMS_DIR: &str = "cairo_programs/cairo0";
+
+/// Stone proves the programs with the layout that has the output and range-check
+/// builtins.
+const STONE_LAYOUT: &str = "small";
+
+/// Log2 of the number of rows of the Stone trace per Cairo step.
+const STONE_LOG_ROWS_PER_STEP: u32 = 4;
+
+const STONE_MAX_LOG_LAST_LAYER_DEGREE_BOUND: u32 = 6;
+
+/// Time and size of the proof of a program by one of the provers.
+struct ProverReport {
+    proving_time: Duration,
+    verification_time: Duration,
+    proof_size: usize,
+}
+
+/// Execution of a program by the Cairo runner of `cairo-lang` and its proof with Stone.
+struct StoneExecution {
+    public_input: Value,
+    report: ProverReport,
+}
+
+fn binary(env_var: &str, default: &str) -> String {
+    env::var(env_var).unwrap_or_else(|_| default.to_string())
+}
+
+fn run(command: &mut Command) {
+    let output = command
+        .output()
+        .unwrap_or_else(|error| panic!("failed to run {command:?}: {error}"));
+    assert!(
+        output.status.success(),
+        "{command:?} failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// FRI parameters of Stone for an execution of `n_steps` steps. The degree of the
+/// trace polynomials must be the product of the FRI steps and the degree bound of
+/// the last layer.
+fn stone_parameters(n_steps: u64) -> Value {
+    let log_degree_bound = n_steps.trailing_zeros() + STONE_LOG_ROWS_PER_STEP;
+    let log_last_layer_degree_bound = log_degree_bound.min(STONE_MAX_LOG_LAST_LAYER_DEGREE_BOUND);
+    let mut fri_steps = vec![0];
+    let mut remaining_log_degree = log_degree_bound - log_last_layer_degree_bound;
+    while remaining_log_degree > 0 {
+        let step = remaining_log_degree.min(4);
+        fri_steps.push(step);
+        remaining_log_degree -= step;
+    }
+
+    json!({
+        "field": "PrimeField0",
+        "channel_hash": "poseidon3",
+        "commitment_hash": "keccak256_masked160_lsb",
+        "n_verifier_friendly_commitment_layers": 0,
+        "pow_hash": "keccak256",
+        "statement": { "page_hash": "pedersen" },
+        "stark": {
+            "fri": {
+                "fri_step_list": fri_steps,
+                "last_layer_degree_bound": 1_u64 << log_last_layer_degree_bound,
+                "n_queries": 18,
+                "proof_of_work_bits": 24
+            },
+            "log_n_cosets": 4
+        },
+        "use_extension_field": false,
+        "verifier_friendly_channel_updates": true,
+        "verifier_friendly_commitment_hashes": false
+    })
+}
+
+fn stone_prover_config() -> Value {
+    json!({
+        "cached_lde_config": { "store_full_lde": false, "use_fft_for_eval": false },
+        "constraint_polynomial_task_size": 256,
+        "n_out_of_memory_merkle_layers": 1,
+        "table_prover_n_tasks_per_segment": 32
+    })
+}
+
+/// Compiles the program in proof mode, runs it with `cairo-run` and proves and
+/// verifies its execution with Stone.
+fn prove_with_stone(program_name: &str, work_dir: &Path) -> StoneExecution {
+    let source = Path::new(CAIRO0_PROGRAMS_DIR).join(format!("{program_name}.cairo"));
+    let file = |name: &str| work_dir.join(name);
+
+    run(Command::new("cairo-compile")
+        .arg("--proof_mode")
+        .arg(format!("--cairo_path={CAIRO0_PROGRAMS_DIR}"))
+        .arg(&source)
+        .arg("--output")
+        .arg(file("program.json")));
+    run(Command::new("cairo-run")
+        .arg("--proof_mode")
+        .arg(format!("--layout={STONE_LAYOUT}"))
+        .arg("--program")
+        .arg(file("program.json"))
+        .arg("--air_public_input")
+        .arg(file("public_input.json"))
+        .arg("--air_private_input")
+        .arg(file("private_input.json"))
+        .arg("--trace_file")
+        .arg(file("trace.bin"))
+        .arg("--memory_file")
+        .arg(file("memory.bin")));
+
+    let public_input: Value =
+        serde_json::from_slice(&fs::read(file("public_input.json")).unwrap()).unwrap();
+    let n_steps = public_input["n_steps"].as_u64().unwrap();
+    fs::write(
+        file("parameters.json"),
+        stone_parameters(n_steps).to_string(),
+    )
+    .unwrap();
+    fs::write(
+        file("prover_config.json"),
+        stone_prover_config().to_string(),
+    )
+    .unwrap();
+
+    let timer = Instant::now();
+    run(
+        Command::new(binary("STONE_CPU_AIR_PROVER", "cpu_air_prover"))
+            .arg("--out_file")
+            .arg(file("proof.json"))
+            .arg("--public_input_file")
+            .arg(file("public_input.json"))
+            .arg("--private_input_file")
+            .arg(file("private_input.json"))
+            .arg("--prover_config_file")
+            .arg(file("prover_config.json"))
+            .arg("--parameter_file")
+            .arg(file("parameters.json")),
+    );
+    let proving_time = timer.elapsed();
+
+    let timer = Instant::now();
+    run(
+        Command::new(binary("STONE_CPU_AIR_VERIFIER", "cpu_air_verifier"))
+            .arg("--in_file")
+            .arg(file("proof.json")),
+    );
+    let verification_time = timer.elapsed();
+
+    let proof: Value = serde_json::from_slice(&fs::read(file("proof.json")).unwrap()).unwrap();
+    let proof_size = proof["proof_hex"]
+        .as_str()
+        .map_or(0, |proof_hex| proof_hex.trim_start_matches("0x").len() / 2);
+
+    StoneExecution {
+        public_input,
+        report: ProverReport {
+            proving_time,
+            verification_time,
+            proof_size,
+        },
+    }
+}
+
+/// Runs the program with the Cairo VM and proves and verifies its execution with
+/// this crate.
+fn prove_with_lambdaworks(program_name: &str) -> (PublicInputs, Vec<FE>, ProverReport) {
+    let proof_options = ProofOptions::default_test_options();
+    let program_path = Path::new(CAIRO0_PROGRAMS_DIR).join(format!("{program_name}.json"));
+    let program_content = fs::read(program_path).unwrap();
+    let execution = generate_prover_args_with_hint_config(
+        &program_content,
+        &CairoVersion::V0,
+        &None,
+        &HintConfig::default(),
+    )
+    .unwrap();
+
+    let timer = Instant::now();
+    let proof =
+        generate_cairo_proof(&execution.main_trace, &execution.pub_inputs, &proof_options).unwrap();
+    let proving_time = timer.elapsed();
+
+    let timer = Instant::now();
+    assert!(verify_cairo_proof(
+        &proof,
+        &execution.pub_inputs,
+        &proof_options
+    ));
+    let verification_time = timer.elapsed();
+
+    let report = ProverReport {
+        proving_time,
+        verification_time,
+        proof_size: proof.serialize().len(),
+    };
+    (execution.pub_inputs, execution.outputs, report)
+}
+
+fn parse_felt(value: &Value) -> FE {
+    let hex = value.as_str().expect("Stone values are hex strings");
+    FE::from_hex_unchecked(hex.trim_start_matches("0x"))
+}
+
+/// Values of the public memory of Stone in the segment `segment_name`, sorted by
+/// address.
+fn stone_segment_values(public_input: &Value, segment_name: &str) -> Vec<FE> {
+    let segment = &public_input["memory_segments"][segment_name];
+    let (Some(begin), Some(stop)) = (segment["begin_addr"].as_u64(), segment["stop_ptr"].as_u64())
+    else {
+        return Vec::new();
+    };
+
+    let mut values: Vec<(u64, FE)> = public_input["public_memory"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|cell| {
+            let address = cell["address"].as_u64().unwrap();
+            (begin..stop)
+                .contains(&address)
+                .then(|| (address, parse_felt(&cell["value"])))
+        })
+        .collect();
+    values.sort_by_key(|(address, _)| *address);
+    values.into_iter().map(|(_, value)| value).collect()
+}
+
+fn stone_segment_length(public_input: &Value, segment_name: &str) -> u64 {
+    let segment = &public_input["memory_segments"][segment_name];
+    match (segment["begin_addr"].as_u64(), segment["stop_ptr"].as_u64()) {
+        (Some(begin), Some(stop)) => stop - begin,
+        _ => 0,
+    }
+}
+
+fn differential_test(program_name: &str) {
+    let work_dir: PathBuf = env::temp_dir().join(format!(
+        "stone_differential_{}_{program_name}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&work_dir).unwrap();
+
+    let stone = prove_with_stone(program_name, &work_dir);
+    let (pub_inputs, outputs, report) = prove_with_lambdaworks(program_name);
+    fs::remove_dir_all(&work_dir).unwrap();
+
+    // The programs proven by Stone are compiled in proof mode, which only adds
+    // instructions around `main`, so both executions write the same outputs and
+    // use the range-check builtin the same number of times.
+    assert_eq!(
+        outputs,
+        stone_segment_values(&stone.public_input, "output"),
+        "{program_name}: the outputs differ"
+    );
+    let range_check_length = pub_inputs
+        .memory_segments
+        .get(&MemorySegment::RangeCheck)
+        .map_or(0, |range| range.end - range.start);
+    assert_eq!(
+        range_check_length,
+        stone_segment_length(&stone.public_input, "range_check"),
+        "{program_name}: the range-check builtin segments differ"
+    );
+
+    // The instructions added by the proof mode can only widen the range of the
+    // offsets, so Stone's range must contain the one of this crate.
+    let stone_rc_min = stone.public_input["rc_min"].as_u64().unwrap();
+    let stone_rc_max = stone.public_input["rc_max"].as_u64().unwrap();
+    let rc_min = u64::from(pub_inputs.range_check_min.unwrap());
+    let rc_max = u64::from(pub_inputs.range_check_max.unwrap());
+    assert!(
+        stone_rc_min <= rc_min && rc_max <= stone_rc_max,
+        "{program_name}: offsets range [{rc_min}, {rc_max}] outside Stone's [{stone_rc_min}, {stone_rc_max}]"
+    );
+
+    println!(
+        "{program_name:<24} | lambdaworks: prove {:>10.2?} verify {:>10.2?} size {:>8} B \
+         | stone: prove {:>10.2?} verify {:>10.2?} size {:>8} B",
+        report.proving_time,
+        report.verification_time,
+        report.proof_size,
+        stone.report.proving_time,
+        stone.report.verification_time,
+        stone.report.proof_size,
+    );
+}
+
+#[test]
+fn stone_differential_fibonacci_5() {
+    differential_test("fibonacci_5");
+}
+
+#[test]
+fn stone_differential_fibonacci_100() {
+    differential_test("fibonacci_100");
+}
+
+#[test]
+fn stone_differential_output_program() {
+    differential_test("output_program");
+}
+
+#[test]
+fn stone_differential_rc_program() {
+    differential_test("rc_program");
+}
+
+#[test]
+fn stone_differential_compare_lesser_array() {
+    differential_test("compare_lesser_array");
+}